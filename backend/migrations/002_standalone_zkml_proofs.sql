@@ -0,0 +1,16 @@
+-- Proofs generated directly through the ZK-ML API belong to a user rather
+-- than to an agent prediction
+
+ALTER TYPE proof_type ADD VALUE 'hash_proof';
+
+ALTER TABLE zkml_proofs ADD COLUMN user_id UUID REFERENCES users(id) ON DELETE CASCADE;
+
+UPDATE zkml_proofs
+SET user_id = agent_predictions.user_id
+FROM agent_predictions
+WHERE zkml_proofs.prediction_id = agent_predictions.id;
+
+ALTER TABLE zkml_proofs ALTER COLUMN user_id SET NOT NULL;
+ALTER TABLE zkml_proofs ALTER COLUMN prediction_id DROP NOT NULL;
+
+CREATE INDEX idx_zkml_proofs_user_id ON zkml_proofs(user_id);
@@ -0,0 +1,6 @@
+-- Administrators can reach the operator endpoints under /api/v1/admin
+
+ALTER TABLE users ADD COLUMN is_admin BOOLEAN NOT NULL DEFAULT false;
+
+-- The stats dashboard counts the proofs generated since midnight
+CREATE INDEX idx_zkml_proofs_created_at ON zkml_proofs(created_at);
@@ -0,0 +1,9 @@
+-- Solana wallets users sign in with instead of a password
+
+CREATE TABLE wallet_logins (
+    public_key VARCHAR(64) PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_wallet_logins_user_id ON wallet_logins(user_id);
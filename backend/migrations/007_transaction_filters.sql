@@ -0,0 +1,5 @@
+-- Filtering a wallet's transactions by status, type or creation time
+
+CREATE INDEX idx_transactions_wallet_created_at ON transactions(wallet_id, created_at DESC);
+CREATE INDEX idx_transactions_wallet_status ON transactions(wallet_id, status, created_at DESC);
+CREATE INDEX idx_transactions_wallet_type ON transactions(wallet_id, transaction_type, created_at DESC);
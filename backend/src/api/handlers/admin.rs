@@ -0,0 +1,80 @@
+//! Operator endpoints
+
+use crate::{
+    api::{middleware::auth::UserContext, AppState},
+    config::MaintenanceConfig,
+    db::models::Role,
+    error::Error,
+    services::{AdminService, TokenRegistryRefresher},
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+pub struct SetRoleRequest {
+    pub role: Role,
+}
+
+/// System-wide stats for the operator dashboard
+pub async fn get_stats(
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, Error> {
+    let admin_service = AdminService::new(state);
+    let stats = admin_service.stats().await?;
+
+    Ok(Json(stats))
+}
+
+/// Current maintenance mode settings
+pub async fn get_maintenance(
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, Error> {
+    Ok(Json(state.maintenance.current()))
+}
+
+/// Replace the maintenance mode settings on this instance until the next
+/// restart or configuration reload
+pub async fn set_maintenance(
+    State(state): State<Arc<AppState>>,
+    Extension(user_context): Extension<UserContext>,
+    Json(req): Json<MaintenanceConfig>,
+) -> Result<impl IntoResponse, Error> {
+    tracing::warn!(
+        "🚧 Maintenance mode turned {} by {}",
+        if req.enabled { "on" } else { "off" },
+        user_context.email
+    );
+    state.maintenance.set(req.clone());
+
+    Ok(Json(req))
+}
+
+/// Reload the supported token list from its configured source now, rather
+/// than at the next scheduled refresh
+pub async fn refresh_token_registry(
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, Error> {
+    let refresh = TokenRegistryRefresher::new(state).refresh().await?;
+
+    Ok(Json(refresh))
+}
+
+/// Change a user's role, signing them out everywhere if it changed
+pub async fn set_user_role(
+    State(state): State<Arc<AppState>>,
+    Extension(user_context): Extension<UserContext>,
+    Path(user_id): Path<Uuid>,
+    Json(req): Json<SetRoleRequest>,
+) -> Result<impl IntoResponse, Error> {
+    tracing::warn!("👤 User {} given role {:?} by {}", user_id, req.role, user_context.email);
+    AdminService::new(state).set_role(user_id, req.role).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
\ No newline at end of file
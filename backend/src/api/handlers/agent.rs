@@ -1,7 +1,7 @@
 //! Agent handlers
 
 use crate::{
-    api::{AppState, middleware::auth::UserContext},
+    api::{AppState, middleware::auth::UserContext, pagination::Page},
     error::Error,
     services::{AgentService, agent::{CreatePredictionRequest, MarketAnalysisRequest}},
     db::models::AgentType,
@@ -28,6 +28,18 @@ pub struct PredictionQuery {
     pub asset_symbol: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct VerifyExplanationRequest {
+    pub explanation_text: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AnalysisQuery {
+    /// Preview the analysis without saving its recommendation
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
 /// Get all active agents
 pub async fn get_agents(
     State(state): State<Arc<AppState>>,
@@ -77,14 +89,14 @@ pub async fn get_predictions(
 ) -> Result<impl IntoResponse, Error> {
     let user_id = user_context.user_id;
 
+    let page = Page::new(query.limit, query.offset);
+
     let agent_service = AgentService::new(state);
     
     let predictions = if let Some(asset_symbol) = query.asset_symbol {
-        agent_service.get_asset_predictions(user_id, &asset_symbol).await?
+        agent_service.get_asset_predictions(user_id, &asset_symbol, page).await?
     } else {
-        let limit = query.limit.unwrap_or(50);
-        let offset = query.offset.unwrap_or(0);
-        agent_service.get_user_predictions(user_id, limit, offset).await?
+        agent_service.get_user_predictions(user_id, page).await?
     };
 
     Ok(Json(predictions))
@@ -104,21 +116,68 @@ pub async fn get_prediction(
     Ok(Json(prediction))
 }
 
-/// Generate market analysis using ensemble of agents
+/// Check whether a prediction's stored proof and explanation are still valid
+pub async fn verify_prediction_proof(
+    State(state): State<Arc<AppState>>,
+    Extension(user_context): Extension<UserContext>,
+    Path(prediction_id): Path<Uuid>,
+) -> Result<impl IntoResponse, Error> {
+    let user_id = user_context.user_id;
+
+    let agent_service = AgentService::new(state);
+    let check = agent_service.verify_prediction_proof(prediction_id, user_id).await?;
+
+    Ok(Json(check))
+}
+
+/// Check whether a claimed explanation is the one committed to by the
+/// prediction's explanation hash
+pub async fn verify_explanation(
+    State(state): State<Arc<AppState>>,
+    Extension(user_context): Extension<UserContext>,
+    Path(prediction_id): Path<Uuid>,
+    Json(req): Json<VerifyExplanationRequest>,
+) -> Result<impl IntoResponse, Error> {
+    let user_id = user_context.user_id;
+
+    let agent_service = AgentService::new(state);
+    let check = agent_service.verify_explanation(prediction_id, user_id, &req.explanation_text).await?;
+
+    Ok(Json(check))
+}
+
+/// Generate market analysis using ensemble of agents, or with
+/// `?dry_run=true` preview it without saving anything
 pub async fn generate_market_analysis(
     State(state): State<Arc<AppState>>,
     Extension(user_context): Extension<UserContext>,
+    Query(query): Query<AnalysisQuery>,
     Json(req): Json<MarketAnalysisRequest>,
 ) -> Result<impl IntoResponse, Error> {
     let user_id = user_context.user_id;
 
     let asset_symbol = req.asset_symbol.clone();
     let agent_service = AgentService::new(state);
-    let analysis = agent_service.generate_market_analysis(user_id, &asset_symbol, req).await?;
+    let analysis = if query.dry_run {
+        agent_service.simulate_market_analysis(user_id, &asset_symbol, req).await?
+    } else {
+        agent_service.generate_market_analysis(user_id, &asset_symbol, req).await?
+    };
 
     Ok(Json(analysis))
 }
 
+/// Reload an agent's model without restarting (admin endpoint)
+pub async fn reload_agent_model(
+    State(state): State<Arc<AppState>>,
+    Path(agent_id): Path<Uuid>,
+) -> Result<impl IntoResponse, Error> {
+    let agent_service = AgentService::new(state);
+    let model = agent_service.reload_agent_model(agent_id).await?;
+
+    Ok(Json(serde_json::to_value(&*model)?))
+}
+
 /// Clean up expired predictions (admin endpoint)
 pub async fn cleanup_expired_predictions(
     State(state): State<Arc<AppState>>,
@@ -0,0 +1,59 @@
+//! API key management handlers
+
+use crate::{
+    api::{middleware::auth::UserContext, AppState},
+    error::Error,
+    services::ApiKeyService,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// A newly created API key. `key` is shown only once.
+#[derive(Debug, Serialize)]
+pub struct CreatedApiKey {
+    pub id: Uuid,
+    pub name: String,
+    pub key: String,
+    pub permissions: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Create an API key for the logged-in user
+pub async fn create_api_key(
+    State(state): State<Arc<AppState>>,
+    Extension(user_context): Extension<UserContext>,
+    Json(req): Json<CreateApiKeyRequest>,
+) -> Result<impl IntoResponse, Error> {
+    let api_key_service = ApiKeyService::new(state);
+    let created = api_key_service.create_api_key(user_context.user_id, req).await?;
+
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+/// Revoke one of the logged-in user's API keys
+pub async fn revoke_api_key(
+    State(state): State<Arc<AppState>>,
+    Extension(user_context): Extension<UserContext>,
+    Path(key_id): Path<Uuid>,
+) -> Result<impl IntoResponse, Error> {
+    let api_key_service = ApiKeyService::new(state);
+    api_key_service.revoke_api_key(user_context.user_id, key_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
\ No newline at end of file
@@ -1,9 +1,26 @@
 //! Authentication handlers
 
-use crate::{api::AppState, error::Error, services::auth::AuthService};
-use axum::{extract::State, response::IntoResponse, Json};
+use crate::{
+    api::{
+        middleware::auth::{bearer_token, UserContext},
+        AppState,
+    },
+    db::models::UserSession,
+    error::Error,
+    services::auth::AuthService,
+};
+use axum::{
+    extract::{Extension, Path, State},
+    http::{header::USER_AGENT, HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sqlx::types::ipnetwork::IpNetwork;
+use std::net::IpAddr;
 use std::sync::Arc;
+use uuid::Uuid;
 
 #[derive(Debug, Deserialize)]
 pub struct RegisterRequest {
@@ -18,11 +35,29 @@ pub struct LoginRequest {
     pub password: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct WalletChallengeRequest {
+    pub public_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifySignatureRequest {
+    pub public_key: String,
+    pub nonce: String,
+    /// Base58 ed25519 signature of the challenge message
+    pub signature: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct RefreshTokenRequest {
     pub refresh_token: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct LogoutRequest {
+    pub refresh_token: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct VerifyEmailRequest {
     pub token: String,
@@ -39,6 +74,45 @@ pub struct ResetPasswordRequest {
     pub new_password: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+    /// Refresh token of the session making the change, which stays signed in
+    pub refresh_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevokeSessionsRequest {
+    /// Refresh token of the session making the request, which stays signed in
+    pub refresh_token: String,
+}
+
+/// A signed-in session, as shown to its user. The refresh token hash is
+/// never exposed.
+#[derive(Debug, Serialize)]
+pub struct SessionInfo {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<IpAddr>,
+}
+
+impl From<UserSession> for SessionInfo {
+    fn from(session: UserSession) -> Self {
+        Self {
+            id: session.id,
+            created_at: session.created_at,
+            last_used_at: session.last_used_at,
+            expires_at: session.expires_at,
+            user_agent: session.user_agent,
+            ip_address: session.ip_address.map(|network| network.ip()),
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct AuthResponse {
     pub access_token: String,
@@ -52,12 +126,39 @@ pub struct MessageResponse {
     pub message: String,
 }
 
+/// Client details recorded on the sessions a request creates
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<IpNetwork>,
+}
+
+impl ClientInfo {
+    /// Read the user agent and client IP from request headers. The IP comes
+    /// from the first `X-Forwarded-For` entry, falling back to `X-Real-IP`.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+
+        let ip_address = header("x-forwarded-for")
+            .and_then(|value| value.split(',').next())
+            .or_else(|| header("x-real-ip"))
+            .and_then(|value| value.trim().parse::<IpAddr>().ok())
+            .map(IpNetwork::from);
+
+        Self {
+            user_agent: header(USER_AGENT.as_str()).map(str::to_string),
+            ip_address,
+        }
+    }
+}
+
 /// Register a new user
 pub async fn register(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(req): Json<RegisterRequest>,
 ) -> Result<impl IntoResponse, Error> {
-    let auth_service = AuthService::new(state);
+    let auth_service = AuthService::new(state).with_client_info(ClientInfo::from_headers(&headers));
     let response = auth_service.register(req).await?;
     Ok(Json(response))
 }
@@ -65,28 +166,58 @@ pub async fn register(
 /// User login
 pub async fn login(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(req): Json<LoginRequest>,
 ) -> Result<impl IntoResponse, Error> {
-    let auth_service = AuthService::new(state);
+    let auth_service = AuthService::new(state).with_client_info(ClientInfo::from_headers(&headers));
     let response = auth_service.login(req).await?;
     Ok(Json(response))
 }
 
+/// Issue a challenge for a Solana wallet to sign in with
+pub async fn wallet_challenge(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<WalletChallengeRequest>,
+) -> Result<impl IntoResponse, Error> {
+    let auth_service = AuthService::new(state);
+    let challenge = auth_service.wallet_challenge(req).await?;
+    Ok(Json(challenge))
+}
+
+/// Sign in with a wallet's signature of its challenge
+pub async fn verify_signature(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<VerifySignatureRequest>,
+) -> Result<impl IntoResponse, Error> {
+    let auth_service = AuthService::new(state).with_client_info(ClientInfo::from_headers(&headers));
+    let response = auth_service.verify_signature(req).await?;
+    Ok(Json(response))
+}
+
 /// Refresh access token
 pub async fn refresh_token(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(req): Json<RefreshTokenRequest>,
 ) -> Result<impl IntoResponse, Error> {
-    let auth_service = AuthService::new(state);
+    let auth_service = AuthService::new(state).with_client_info(ClientInfo::from_headers(&headers));
     let response = auth_service.refresh_token(req).await?;
     Ok(Json(response))
 }
 
 /// User logout
 pub async fn logout(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<LogoutRequest>,
 ) -> Result<impl IntoResponse, Error> {
-    // TODO: Implement token blacklisting
+    let auth_service = AuthService::new(state);
+    auth_service.logout(req).await?;
+    // The access token would otherwise keep working until it expires
+    if let Some(access_token) = bearer_token(&headers) {
+        auth_service.revoke_access_token(access_token).await?;
+    }
     Ok(Json(MessageResponse {
         message: "Successfully logged out".to_string(),
     }))
@@ -112,7 +243,7 @@ pub async fn forgot_password(
     let auth_service = AuthService::new(state);
     auth_service.forgot_password(req).await?;
     Ok(Json(MessageResponse {
-        message: "Password reset email sent".to_string(),
+        message: "If an account exists for this email, a password reset link has been sent".to_string(),
     }))
 }
 
@@ -126,4 +257,63 @@ pub async fn reset_password(
     Ok(Json(MessageResponse {
         message: "Password reset successfully".to_string(),
     }))
-} 
\ No newline at end of file
+}
+
+/// Change the password of the logged-in user
+pub async fn change_password(
+    State(state): State<Arc<AppState>>,
+    Extension(user_context): Extension<UserContext>,
+    headers: HeaderMap,
+    Json(req): Json<ChangePasswordRequest>,
+) -> Result<impl IntoResponse, Error> {
+    let auth_service = AuthService::new(state);
+    // The token making the change keeps working; every other one is revoked
+    auth_service.change_password(user_context.user_id, bearer_token(&headers), req).await?;
+    Ok(Json(MessageResponse {
+        message: "Password changed successfully".to_string(),
+    }))
+} 
+
+/// Deactivate the logged-in user's account, signing it out everywhere
+pub async fn deactivate_account(
+    State(state): State<Arc<AppState>>,
+    Extension(user_context): Extension<UserContext>,
+) -> Result<impl IntoResponse, Error> {
+    let auth_service = AuthService::new(state);
+    auth_service.deactivate_account(user_context.user_id).await?;
+    Ok(Json(MessageResponse {
+        message: "Account deactivated".to_string(),
+    }))
+}
+
+/// List the logged-in user's active sessions
+pub async fn list_sessions(
+    State(state): State<Arc<AppState>>,
+    Extension(user_context): Extension<UserContext>,
+) -> Result<impl IntoResponse, Error> {
+    let auth_service = AuthService::new(state);
+    let sessions = auth_service.list_sessions(user_context.user_id).await?;
+    Ok(Json(sessions))
+}
+
+/// Revoke one of the logged-in user's sessions
+pub async fn revoke_session(
+    State(state): State<Arc<AppState>>,
+    Extension(user_context): Extension<UserContext>,
+    Path(session_id): Path<Uuid>,
+) -> Result<impl IntoResponse, Error> {
+    let auth_service = AuthService::new(state);
+    auth_service.revoke_session(user_context.user_id, session_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Revoke every session of the logged-in user except the current one
+pub async fn revoke_other_sessions(
+    State(state): State<Arc<AppState>>,
+    Extension(user_context): Extension<UserContext>,
+    Json(req): Json<RevokeSessionsRequest>,
+) -> Result<impl IntoResponse, Error> {
+    let auth_service = AuthService::new(state);
+    let revoked = auth_service.revoke_other_sessions(user_context.user_id, req).await?;
+    Ok(Json(serde_json::json!({ "revoked": revoked })))
+}
\ No newline at end of file
@@ -2,118 +2,103 @@
 
 use crate::api::AppState;
 use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
-use serde_json::json;
+use serde_json::{json, Value};
+use std::future::Future;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 /// Basic health check endpoint
-pub async fn health_check() -> impl IntoResponse {
+pub async fn health_check(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let maintenance = state.maintenance.current();
+
     Json(json!({
-        "status": "healthy",
+        "status": if maintenance.enabled { "maintenance" } else { "healthy" },
+        "maintenance": maintenance,
         "service": "guardian-aa-backend",
         "version": env!("CARGO_PKG_VERSION"),
         "timestamp": chrono::Utc::now().to_rfc3339()
     }))
 }
 
-/// Readiness check endpoint - verifies all dependencies are available
-pub async fn readiness_check(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    let mut checks = vec![];
-    let mut all_ready = true;
+/// A failed check is retried after at most this long, whatever the cache
+/// interval, so recovery shows up quickly without a dependency that is down
+/// being probed on every poll
+const FAILED_CHECK_MAX_AGE: Duration = Duration::from_secs(1);
 
-    // Check database connection
-    match state.db.health_check().await {
-        Ok(_) => {
-            checks.push(json!({
-                "name": "database",
-                "status": "ready"
-            }));
-        }
-        Err(e) => {
-            all_ready = false;
-            checks.push(json!({
-                "name": "database",
-                "status": "not_ready",
-                "error": e.to_string()
-            }));
-        }
-    }
+/// Recent readiness check results. Monitoring may poll `/ready` every
+/// second; between refreshes each dependency's last result is served
+/// instead of reaching the database, Redis, the Solana RPC and the prover
+/// again.
+#[derive(Clone, Default)]
+pub struct ReadinessCache(Arc<ReadinessChecks>);
 
-    // Check Redis connection
-    match state.redis.get_connection() {
-        Ok(_) => {
-            checks.push(json!({
-                "name": "redis",
-                "status": "ready"
-            }));
-        }
-        Err(e) => {
-            all_ready = false;
-            checks.push(json!({
-                "name": "redis",
-                "status": "not_ready",
-                "error": e.to_string()
-            }));
-        }
-    }
+#[derive(Default)]
+struct ReadinessChecks {
+    database: CachedCheck,
+    redis: CachedCheck,
+    solana_rpc: CachedCheck,
+    zkml_system: CachedCheck,
+}
 
-    // Check Solana RPC connection
-    match state.solana_client.health_check().await {
-        Ok(true) => {
-            checks.push(json!({
-                "name": "solana_rpc",
-                "status": "ready",
-                "rpc_url": state.config.blockchain.solana_rpc_url
-            }));
-        }
-        Ok(false) => {
-            all_ready = false;
-            checks.push(json!({
-                "name": "solana_rpc",
-                "status": "not_ready",
-                "error": "RPC health check failed",
-                "rpc_url": state.config.blockchain.solana_rpc_url
-            }));
-        }
-        Err(e) => {
-            all_ready = false;
-            checks.push(json!({
-                "name": "solana_rpc",
-                "status": "not_ready",
-                "error": e.to_string(),
-                "rpc_url": state.config.blockchain.solana_rpc_url
-            }));
+/// The last result of one check. The lock is held while the check runs, so
+/// concurrent polls share one refresh.
+#[derive(Default)]
+struct CachedCheck(tokio::sync::Mutex<Option<CheckResult>>);
+
+#[derive(Clone)]
+struct CheckResult {
+    ready: bool,
+    report: Value,
+    checked_at: Instant,
+}
+
+impl CachedCheck {
+    /// The cached result if it's fresh enough, otherwise the result of `check`
+    async fn get(&self, max_age: Duration, check: impl Future<Output = (bool, Value)>) -> (bool, Value) {
+        let mut last = self.0.lock().await;
+        if let Some(result) = last.as_ref() {
+            let max_age = if result.ready { max_age } else { max_age.min(FAILED_CHECK_MAX_AGE) };
+            if result.checked_at.elapsed() < max_age {
+                return (result.ready, result.report.clone());
+            }
         }
+
+        let (ready, report) = check.await;
+        *last = Some(CheckResult { ready, report: report.clone(), checked_at: Instant::now() });
+        (ready, report)
     }
+}
 
-    // Check ZKML system
-    match state.zkml_service.health_check() {
-        Ok(true) => {
-            let status = state.zkml_service.get_status();
-            checks.push(json!({
-                "name": "zkml_system",
-                "status": "ready",
-                "circuit_size": status.circuit_size,
-                "estimated_setup_time_ms": status.estimated_setup_time_ms
-            }));
-        }
-        Ok(false) => {
-            all_ready = false;
-            checks.push(json!({
-                "name": "zkml_system",
-                "status": "not_ready",
-                "error": "ZKML health check failed"
-            }));
-        }
-        Err(e) => {
-            all_ready = false;
-            checks.push(json!({
-                "name": "zkml_system",
-                "status": "not_ready",
-                "error": e.to_string()
-            }));
-        }
+/// Readiness check endpoint - verifies all dependencies are available
+pub async fn readiness_check(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    // Stop taking traffic as soon as shutdown begins, without waiting on dependencies
+    if state.draining.load(Ordering::SeqCst) {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({
+                "ready": false,
+                "draining": true,
+                "maintenance": state.maintenance.current(),
+                "service": "guardian-aa-backend",
+                "version": env!("CARGO_PKG_VERSION"),
+                "timestamp": chrono::Utc::now().to_rfc3339()
+            })),
+        );
     }
 
+    let max_age = Duration::from_secs(state.config.server.readiness_cache_secs);
+    let cache = &state.readiness.0;
+    let results = [
+        cache.database.get(max_age, check_database(&state)).await,
+        cache.redis.get(max_age, check_redis(&state)).await,
+        cache.solana_rpc.get(max_age, check_solana_rpc(&state)).await,
+        cache.zkml_system.get(max_age, check_zkml_system(&state)).await,
+    ];
+
+    let all_ready = results.iter().all(|(ready, _)| *ready);
+    let checks: Vec<Value> = results.into_iter().map(|(_, report)| report).collect();
+
     let status_code = if all_ready {
         StatusCode::OK
     } else {
@@ -124,10 +109,93 @@ pub async fn readiness_check(State(state): State<Arc<AppState>>) -> impl IntoRes
         status_code,
         Json(json!({
             "ready": all_ready,
+            "draining": false,
+            "maintenance": state.maintenance.current(),
             "checks": checks,
             "service": "guardian-aa-backend",
             "version": env!("CARGO_PKG_VERSION"),
             "timestamp": chrono::Utc::now().to_rfc3339()
         })),
     )
-} 
\ No newline at end of file
+}
+
+/// Check database connection
+async fn check_database(state: &AppState) -> (bool, Value) {
+    match state.db.health_check().await {
+        Ok(_) => (true, json!({
+            "name": "database",
+            "status": "ready"
+        })),
+        Err(e) => (false, json!({
+            "name": "database",
+            "status": "not_ready",
+            "error": e.to_string()
+        })),
+    }
+}
+
+/// Check Redis connection
+async fn check_redis(state: &AppState) -> (bool, Value) {
+    match state.redis.get_connection() {
+        Ok(_) => (true, json!({
+            "name": "redis",
+            "status": "ready"
+        })),
+        Err(e) => (false, json!({
+            "name": "redis",
+            "status": "not_ready",
+            "error": e.to_string()
+        })),
+    }
+}
+
+/// Check Solana RPC connection
+async fn check_solana_rpc(state: &AppState) -> (bool, Value) {
+    match state.solana_client.health_check().await {
+        Ok(true) => (true, json!({
+            "name": "solana_rpc",
+            "status": "ready",
+            "rpc_url": state.solana_client.current_endpoint(),
+            "endpoints": state.solana_client.endpoint_health()
+        })),
+        Ok(false) => (false, json!({
+            "name": "solana_rpc",
+            "status": "not_ready",
+            "error": "RPC health check failed",
+            "rpc_url": state.solana_client.current_endpoint(),
+            "endpoints": state.solana_client.endpoint_health()
+        })),
+        Err(e) => (false, json!({
+            "name": "solana_rpc",
+            "status": "not_ready",
+            "error": e.to_string(),
+            "rpc_url": state.solana_client.current_endpoint(),
+            "endpoints": state.solana_client.endpoint_health()
+        })),
+    }
+}
+
+/// Check ZKML system
+async fn check_zkml_system(state: &AppState) -> (bool, Value) {
+    match state.zkml_service.health_check() {
+        Ok(true) => {
+            let status = state.zkml_service.get_status();
+            (true, json!({
+                "name": "zkml_system",
+                "status": "ready",
+                "circuit_size": status.circuit_size,
+                "estimated_setup_time_ms": status.estimated_setup_time_ms
+            }))
+        }
+        Ok(false) => (false, json!({
+            "name": "zkml_system",
+            "status": "not_ready",
+            "error": "ZKML health check failed"
+        })),
+        Err(e) => (false, json!({
+            "name": "zkml_system",
+            "status": "not_ready",
+            "error": e.to_string()
+        })),
+    }
+}
\ No newline at end of file
@@ -1,10 +1,13 @@
 //! API request handlers
 
+pub mod admin;
 pub mod agent;
+pub mod api_key;
 pub mod auth;
 pub mod health;
 pub mod transaction;
 pub mod wallet;
+pub mod webhook;
 pub mod zkml;
 
 use axum::{http::StatusCode, response::IntoResponse, Json};
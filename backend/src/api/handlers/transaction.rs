@@ -1,10 +1,11 @@
 //! Transaction handlers
 
 use crate::{
-    api::{AppState, middleware::auth::UserContext},
+    api::{AppState, middleware::auth::UserContext, pagination::Page},
+    blockchain::PriorityLevel,
     error::Error,
     services::TransactionService,
-    db::models::{CreateTransaction, TransactionType},
+    db::models::{CreateTransaction, TransactionFilter, TransactionStatus, TransactionType},
 };
 use axum::{
     extract::{Path, Query, State},
@@ -12,6 +13,7 @@ use axum::{
     Extension,
     Json,
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use uuid::Uuid;
@@ -27,11 +29,23 @@ pub struct CreateTransactionRequest {
     pub raw_transaction: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct DecodeTransactionRequest {
+    /// Base64 or hex encoded transaction
+    pub raw_transaction: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct TransactionQuery {
     pub limit: Option<i64>,
     pub offset: Option<i64>,
     pub wallet_id: Option<Uuid>,
+    pub status: Option<TransactionStatus>,
+    pub transaction_type: Option<TransactionType>,
+    /// Only transactions created at or after this time (RFC 3339)
+    pub from: Option<DateTime<Utc>>,
+    /// Only transactions created before this time (RFC 3339)
+    pub to: Option<DateTime<Utc>>,
 }
 
 /// Create a new transaction
@@ -67,11 +81,16 @@ pub async fn get_transactions(
     let user_id = user_context.user_id;
 
     let wallet_id = query.wallet_id.ok_or_else(|| Error::BadRequest("wallet_id is required".to_string()))?;
-    let limit = query.limit.unwrap_or(50);
-    let offset = query.offset.unwrap_or(0);
+    let page = Page::new(query.limit, query.offset);
+    let filter = TransactionFilter {
+        status: query.status,
+        transaction_type: query.transaction_type,
+        from: query.from,
+        to: query.to,
+    };
 
     let transaction_service = TransactionService::new(state);
-    let transactions = transaction_service.get_wallet_transactions(wallet_id, user_id, limit, offset).await?;
+    let transactions = transaction_service.get_wallet_transactions(wallet_id, user_id, filter, page).await?;
 
     Ok(Json(transactions))
 }
@@ -90,13 +109,30 @@ pub async fn get_transaction(
     Ok(Json(transaction))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct SubmitTransactionBatchRequest {
+    pub transaction_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubmitTransactionParams {
+    /// Simulate before broadcasting and reject transactions that would fail
+    #[serde(default = "default_simulate")]
+    pub simulate: bool,
+}
+
+fn default_simulate() -> bool {
+    true
+}
+
 /// Submit a transaction to the blockchain
 pub async fn submit_transaction(
     State(state): State<Arc<AppState>>,
     Path(transaction_id): Path<Uuid>,
+    Query(params): Query<SubmitTransactionParams>,
 ) -> Result<impl IntoResponse, Error> {
     let transaction_service = TransactionService::new(state);
-    let tx_hash = transaction_service.submit_transaction(transaction_id).await?;
+    let tx_hash = transaction_service.submit_transaction(transaction_id, params.simulate).await?;
 
     Ok(Json(serde_json::json!({
         "transaction_hash": tx_hash,
@@ -104,9 +140,55 @@ pub async fn submit_transaction(
     })))
 }
 
+#[derive(Debug, Default, Deserialize)]
+pub struct FeeEstimateParams {
+    /// Percentile of recent priority fees to recommend
+    #[serde(default)]
+    pub priority_level: PriorityLevel,
+}
+
+/// Submit several transactions, reporting each one's signature or error
+pub async fn submit_transaction_batch(
+    State(state): State<Arc<AppState>>,
+    Extension(user_context): Extension<UserContext>,
+    Query(params): Query<SubmitTransactionParams>,
+    Json(req): Json<SubmitTransactionBatchRequest>,
+) -> Result<impl IntoResponse, Error> {
+    let transaction_service = TransactionService::new(state);
+    let results = transaction_service
+        .submit_transactions_batch(user_context.user_id, &req.transaction_ids, params.simulate)
+        .await?;
+
+    Ok(Json(serde_json::json!({ "results": results })))
+}
+
+/// Explain what a raw transaction would do; it is never submitted
+pub async fn decode_transaction(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<DecodeTransactionRequest>,
+) -> Result<impl IntoResponse, Error> {
+    let decoded = state.solana_client.decode_transaction(&req.raw_transaction).await?;
+
+    Ok(Json(decoded))
+}
+
+/// Recommend the compute budget a raw transaction should set
+pub async fn recommend_compute_budget(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<FeeEstimateParams>,
+    Json(req): Json<DecodeTransactionRequest>,
+) -> Result<impl IntoResponse, Error> {
+    let recommendation = state.solana_client
+        .recommend_compute_budget(&req.raw_transaction, params.priority_level)
+        .await?;
+
+    Ok(Json(recommendation))
+}
+
 /// Estimate transaction fee
 pub async fn estimate_fee(
     State(state): State<Arc<AppState>>,
+    Query(params): Query<FeeEstimateParams>,
     Json(req): Json<CreateTransactionRequest>,
 ) -> Result<impl IntoResponse, Error> {
     let transaction_data = CreateTransaction {
@@ -120,7 +202,7 @@ pub async fn estimate_fee(
     };
 
     let transaction_service = TransactionService::new(state);
-    let fee_estimate = transaction_service.estimate_fee(&transaction_data).await?;
+    let fee_estimate = transaction_service.estimate_fee(&transaction_data, params.priority_level).await?;
 
     Ok(Json(fee_estimate))
 }
@@ -1,7 +1,8 @@
 //! Wallet management handlers
 
 use crate::{
-    api::{AppState, middleware::auth::UserContext},
+    api::{AppState, middleware::auth::UserContext, pagination::Page},
+    auth::PrivateKey,
     error::Error,
     services::WalletService,
     db::models::{CreateWallet, WalletType},
@@ -13,6 +14,7 @@ use axum::{
     Json,
 };
 use serde::{Deserialize, Serialize};
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -21,7 +23,8 @@ pub struct CreateWalletRequest {
     pub name: String,
     pub wallet_type: WalletType,
     pub public_key: String,
-    pub encrypted_private_key: Option<String>,
+    /// Plaintext private key; only its encryption is stored
+    pub private_key: Option<PrivateKey>,
     pub derivation_path: Option<String>,
 }
 
@@ -43,7 +46,7 @@ pub async fn create_wallet(
         name: req.name,
         wallet_type: req.wallet_type,
         public_key: req.public_key,
-        encrypted_private_key: req.encrypted_private_key,
+        private_key: req.private_key,
         derivation_path: req.derivation_path,
     };
 
@@ -57,12 +60,13 @@ pub async fn create_wallet(
 pub async fn get_wallets(
     State(state): State<Arc<AppState>>,
     Extension(user_context): Extension<UserContext>,
-    Query(_query): Query<WalletQuery>,
+    Query(query): Query<WalletQuery>,
 ) -> Result<impl IntoResponse, Error> {
     let user_id = user_context.user_id;
+    let page = Page::new(query.limit, query.offset);
 
     let wallet_service = WalletService::new(state);
-    let wallets = wallet_service.get_user_wallets(user_id).await?;
+    let wallets = wallet_service.get_user_wallets(user_id, page).await?;
 
     Ok(Json(wallets))
 }
@@ -81,20 +85,65 @@ pub async fn get_wallet(
     Ok(Json(wallet))
 }
 
+/// Get the balances of all of the user's wallets
+pub async fn get_all_balances(
+    State(state): State<Arc<AppState>>,
+    Extension(user_context): Extension<UserContext>,
+) -> Result<impl IntoResponse, Error> {
+    let user_id = user_context.user_id;
+
+    let wallet_service = WalletService::new(state);
+    let balances = wallet_service.get_all_balances(user_id).await?;
+
+    Ok(Json(balances))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct BalanceParams {
+    /// Skip the balance caches and query the RPC; also accepted as `refresh`
+    #[serde(default, alias = "refresh")]
+    pub fresh: bool,
+}
+
 /// Get wallet balance
 pub async fn get_wallet_balance(
     State(state): State<Arc<AppState>>,
     Extension(user_context): Extension<UserContext>,
     Path(wallet_id): Path<Uuid>,
+    Query(params): Query<BalanceParams>,
 ) -> Result<impl IntoResponse, Error> {
     let user_id = user_context.user_id;
 
     let wallet_service = WalletService::new(state);
-    let balance = wallet_service.get_wallet_balance(wallet_id, user_id).await?;
+    let balance = wallet_service.get_wallet_balance(wallet_id, user_id, params.fresh).await?;
 
     Ok(Json(balance))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct AirdropRequest {
+    /// Lamports to request; 1 SOL if not given
+    #[serde(default = "default_airdrop_lamports")]
+    pub lamports: u64,
+}
+
+fn default_airdrop_lamports() -> u64 {
+    LAMPORTS_PER_SOL
+}
+
+/// Fund a wallet from the devnet or testnet faucet (administrators only,
+/// outside production)
+pub async fn request_airdrop(
+    State(state): State<Arc<AppState>>,
+    Path(wallet_id): Path<Uuid>,
+    Json(req): Json<AirdropRequest>,
+) -> Result<impl IntoResponse, Error> {
+    let wallet_service = WalletService::new(state);
+    let result = wallet_service.request_airdrop(wallet_id, req.lamports).await?;
+
+    Ok(Json(result))
+}
+
 /// Deactivate a wallet
 pub async fn deactivate_wallet(
     State(state): State<Arc<AppState>>,
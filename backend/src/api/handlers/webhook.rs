@@ -0,0 +1,68 @@
+//! Webhook registration handlers
+
+use crate::{
+    api::{middleware::auth::UserContext, AppState},
+    error::Error,
+    services::WebhookService,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    pub event_types: Vec<String>,
+}
+
+/// A newly registered webhook. `secret` is shown only once.
+#[derive(Debug, Serialize)]
+pub struct CreatedWebhook {
+    pub id: Uuid,
+    pub url: String,
+    pub secret: String,
+    pub event_types: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Register a webhook for the logged-in user
+pub async fn create_webhook(
+    State(state): State<Arc<AppState>>,
+    Extension(user_context): Extension<UserContext>,
+    Json(req): Json<CreateWebhookRequest>,
+) -> Result<impl IntoResponse, Error> {
+    let webhook_service = WebhookService::new(state);
+    let created = webhook_service.create_webhook(user_context.user_id, req).await?;
+
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+/// List the logged-in user's webhooks
+pub async fn list_webhooks(
+    State(state): State<Arc<AppState>>,
+    Extension(user_context): Extension<UserContext>,
+) -> Result<impl IntoResponse, Error> {
+    let webhook_service = WebhookService::new(state);
+    let webhooks = webhook_service.list_webhooks(user_context.user_id).await?;
+
+    Ok(Json(webhooks))
+}
+
+/// Delete one of the logged-in user's webhooks
+pub async fn delete_webhook(
+    State(state): State<Arc<AppState>>,
+    Extension(user_context): Extension<UserContext>,
+    Path(webhook_id): Path<Uuid>,
+) -> Result<impl IntoResponse, Error> {
+    let webhook_service = WebhookService::new(state);
+    webhook_service.delete_webhook(user_context.user_id, webhook_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
\ No newline at end of file
@@ -3,101 +3,363 @@
 use crate::{
     api::{AppState, middleware::auth::UserContext},
     error::Error,
-    zkml::ZkProof,
+    services::{zkml::StoredProof, JobStatus, ZkmlProofService},
+    zkml::{ProofRequest, ZkProof, ZkmlService, COMMITMENT_CIRCUIT},
 };
 use axum::{
-    extract::{Path, State},
-    response::IntoResponse,
+    body::{Body, Bytes},
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderName, StatusCode},
+    response::{IntoResponse, Response},
     Extension,
     Json,
 };
 use base64::{Engine as _, engine::general_purpose};
 use serde::Deserialize;
+use sha2::{Digest, Sha256};
 use std::sync::Arc;
+use uuid::Uuid;
+
+/// Header carrying the hex-encoded public hash of a streamed proof
+pub const PROOF_HASH_HEADER: &str = "x-proof-hash";
+
+/// Header carrying the hex-encoded SHA256 checksum of streamed proof bytes
+pub const PROOF_CHECKSUM_HEADER: &str = "x-proof-checksum";
+
+/// Room in a generate request for everything but the encoded data: field
+/// names, circuit type, commitment, blinding and content type
+const GENERATE_REQUEST_OVERHEAD_BYTES: usize = 4 * 1024;
+
+/// Largest generate request body worth reading: the biggest input any
+/// circuit takes, base64-encoded, plus the other fields. Anything larger
+/// would only be rejected after decoding.
+pub fn generate_body_limit(zkml: &ZkmlService) -> usize {
+    zkml.max_input_len().div_ceil(3) * 4 + GENERATE_REQUEST_OVERHEAD_BYTES
+}
+
+/// Largest batch generate request body worth reading: as many of the
+/// largest generate bodies as a batch may hold, which leaves each entry
+/// room for its quotes and separator, plus the request's own fields
+pub fn generate_batch_body_limit(zkml: &ZkmlService) -> usize {
+    zkml.max_batch_size() * generate_body_limit(zkml) + GENERATE_REQUEST_OVERHEAD_BYTES
+}
 
 #[derive(Debug, Deserialize)]
 pub struct GenerateProofRequest {
     pub data: String, // Base64 encoded data
     pub circuit_type: Option<String>,
+    /// Hex-encoded SHA256(blinding || data); when set, the proof is made over
+    /// the commitment and the data is not kept
+    pub commitment: Option<String>,
+    /// Hex-encoded 32-byte blinding factor, required with `commitment`
+    pub blinding: Option<String>,
+    /// MIME type of the decoded data, checked against the proof input policy
+    pub content_type: Option<String>,
+    /// Prove again even if this input was proved before
+    #[serde(default)]
+    pub bypass_cache: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GenerateProofBatchRequest {
+    pub inputs: Vec<String>, // Base64 encoded data, one entry per proof
 }
 
 #[derive(Debug, Deserialize)]
 pub struct VerifyProofRequest {
     pub proof: ZkProof,
-    pub original_data: String, // Base64 encoded original data
+    pub original_data: Option<String>, // Base64 encoded original data, not needed for commitment proofs
+    /// Id of the stored proof to mark as verified when verification succeeds
+    pub proof_id: Option<Uuid>,
+}
+
+/// Verification request from an external party. Unknown fields are
+/// rejected, so a verifying key can't be smuggled in alongside the proof.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VerifyExternalProofRequest {
+    /// Hex fingerprint of the pinned verifying key the proof was made with
+    pub vk_hash: String,
+    pub proof: ZkProof,
+    pub original_data: Option<String>, // Base64 encoded original data, not needed for commitment proofs
+}
+
+/// Decode a hex-encoded 32-byte value from a request field
+fn decode_hex_32(field: &str, value: &str) -> Result<[u8; 32], Error> {
+    hex::decode(value)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| Error::BadRequest(format!("{} must be 32 hex-encoded bytes", field)))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct GenerateProofParams {
+    /// Queue the proof and return a job id instead of waiting for it
+    #[serde(default, rename = "async")]
+    pub run_async: bool,
 }
 
 /// Generate a zero-knowledge proof
 pub async fn generate_proof(
     State(state): State<Arc<AppState>>,
-    Extension(_user_context): Extension<UserContext>,
+    Extension(user_context): Extension<UserContext>,
+    Query(params): Query<GenerateProofParams>,
     Json(req): Json<GenerateProofRequest>,
+) -> Result<Response, Error> {
+    // Decode the input data
+    let data = general_purpose::STANDARD.decode(&req.data)
+        .map_err(|_| Error::BadRequest("Invalid base64 data".to_string()))?;
+    state.zkml_service.validate_input(&data, req.content_type.as_deref())?;
+
+    // Prove over a commitment when one is supplied, so the data is dropped after proving
+    let commitment = match &req.commitment {
+        Some(commitment) => {
+            let commitment = decode_hex_32("commitment", commitment)?;
+            let blinding = req.blinding.as_deref()
+                .ok_or_else(|| Error::BadRequest("blinding is required with commitment".to_string()))
+                .and_then(|blinding| decode_hex_32("blinding", blinding))?;
+            Some((commitment, blinding))
+        }
+        None => None,
+    };
+
+    let request = ProofRequest {
+        input_data: data,
+        circuit_type: req.circuit_type.unwrap_or_else(|| "sha256".to_string()),
+        bypass_cache: req.bypass_cache,
+    };
+    // An input too big for the circuit would otherwise only fail once a
+    // queued job got to it
+    let circuit = request.circuit_type.parse::<guardian_zkml::CircuitType>()
+        .map_err(Error::BadRequest)?;
+    state.zkml_service.check_input_size(circuit, &request.input_data)?;
+    let user_id = user_context.user_id;
+
+    if params.run_async {
+        let job_state = state.clone();
+        let job_id = state.proof_jobs.submit(user_id, async move {
+            prove_and_store(&job_state, user_id, request, commitment).await
+        });
+
+        let body = Json(serde_json::json!({
+            "job_id": job_id,
+            "status": JobStatus::Queued,
+        }));
+        return Ok((StatusCode::ACCEPTED, body).into_response());
+    }
+
+    let stored = prove_and_store(&state, user_id, request, commitment).await?;
+    Ok(Json(stored).into_response())
+}
+
+/// Generate a proof, over the commitment if one is given, and store it for
+/// the audit trail
+async fn prove_and_store(
+    state: &Arc<AppState>,
+    user_id: Uuid,
+    request: ProofRequest,
+    commitment: Option<([u8; 32], [u8; 32])>,
+) -> Result<StoredProof, Error> {
+    let proof_service = ZkmlProofService::new(state.clone());
+
+    match commitment {
+        Some((commitment, blinding)) => {
+            let proof = state.zkml_service
+                .generate_committed_proof(&request.input_data, &blinding, &commitment)
+                .await?;
+            proof_service.store_proof(user_id, proof).await
+        }
+        None => proof_service.generate_proof(user_id, &request).await,
+    }
+}
+
+/// Generate SHA256 proofs for several inputs, reporting each input's result
+pub async fn generate_proof_batch(
+    State(state): State<Arc<AppState>>,
+    Extension(user_context): Extension<UserContext>,
+    Json(req): Json<GenerateProofBatchRequest>,
 ) -> Result<impl IntoResponse, Error> {
+    let inputs = req.inputs.iter()
+        .enumerate()
+        .map(|(index, data)| general_purpose::STANDARD.decode(data)
+            .map_err(|_| Error::BadRequest(format!("Invalid base64 data at index {}", index))))
+        .collect::<Result<Vec<_>, _>>()?;
+    for (index, data) in inputs.iter().enumerate() {
+        state.zkml_service.validate_input(data, None).map_err(|e| match e {
+            Error::Validation(msg) => Error::Validation(format!("Input at index {}: {}", index, msg)),
+            e => e,
+        })?;
+    }
+
+    let proof_service = ZkmlProofService::new(state);
+    let results = proof_service.generate_proofs_batch(user_context.user_id, &inputs).await?;
+
+    Ok(Json(serde_json::json!({ "results": results })))
+}
+
+/// Generate a zero-knowledge proof and stream the raw proof bytes back
+pub async fn generate_proof_stream(
+    State(state): State<Arc<AppState>>,
+    Extension(_user_context): Extension<UserContext>,
+    Json(req): Json<GenerateProofRequest>,
+) -> Result<Response, Error> {
     // Decode the input data
     let data = general_purpose::STANDARD.decode(&req.data)
         .map_err(|_| Error::BadRequest("Invalid base64 data".to_string()))?;
+    state.zkml_service.validate_input(&data, req.content_type.as_deref())?;
 
-    // For now, we only support SHA256 circuit
     let circuit_type = req.circuit_type.unwrap_or_else(|| "sha256".to_string());
     if circuit_type != "sha256" {
         return Err(Error::BadRequest("Only SHA256 circuit is currently supported".to_string()));
     }
 
-    // Generate the proof
     let proof = state.zkml_service.generate_sha256_proof(&data).await?;
 
-    // Store proof in database (optional - for audit trail)
-    // TODO: Add proof storage to database
+    let headers = [
+        (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+        (header::CONTENT_LENGTH, proof.proof_data.len().to_string()),
+        (HeaderName::from_static(PROOF_HASH_HEADER), hex::encode(proof.hash)),
+        (HeaderName::from_static(PROOF_CHECKSUM_HEADER), proof_checksum(&proof.proof_data)),
+    ];
 
-    Ok(Json(proof))
+    Ok((headers, Body::from(proof.proof_data)).into_response())
+}
+
+/// Verify a zero-knowledge proof sent as a raw `application/octet-stream` body
+pub async fn verify_proof_stream(
+    State(state): State<Arc<AppState>>,
+    Extension(_user_context): Extension<UserContext>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoResponse, Error> {
+    let hash_hex = headers.get(PROOF_HASH_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| Error::BadRequest(format!("Missing {} header", PROOF_HASH_HEADER)))?;
+
+    let hash: [u8; 32] = hex::decode(hash_hex)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| Error::BadRequest("Proof hash must be 32 hex-encoded bytes".to_string()))?;
+
+    // Reject truncated or corrupted uploads before running the verifier
+    if let Some(checksum) = headers.get(PROOF_CHECKSUM_HEADER).and_then(|value| value.to_str().ok()) {
+        if !checksum.eq_ignore_ascii_case(&proof_checksum(&body)) {
+            return Err(Error::BadRequest("Proof checksum mismatch".to_string()));
+        }
+    }
+
+    let is_valid = state.zkml_service.verify_proof_bytes(&hash, &body).await?;
+
+    Ok(Json(serde_json::json!({
+        "valid": is_valid,
+        "circuit_type": "sha256",
+        "verified_at": chrono::Utc::now()
+    })))
+}
+
+/// Hex-encoded SHA256 checksum of proof bytes, sent in [`PROOF_CHECKSUM_HEADER`]
+pub fn proof_checksum(proof_bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(proof_bytes);
+    hex::encode(hasher.finalize())
 }
 
 /// Verify a zero-knowledge proof
 pub async fn verify_proof(
     State(state): State<Arc<AppState>>,
-    Extension(_user_context): Extension<UserContext>,
+    Extension(user_context): Extension<UserContext>,
     Json(req): Json<VerifyProofRequest>,
 ) -> Result<impl IntoResponse, Error> {
-    // Decode the original data
-    let original_data = general_purpose::STANDARD.decode(&req.original_data)
-        .map_err(|_| Error::BadRequest("Invalid base64 original data".to_string()))?;
+    let is_valid = if req.proof.circuit_type == COMMITMENT_CIRCUIT {
+        // Commitment proofs are checked against their public inputs alone
+        state.zkml_service.verify_committed_proof(&req.proof).await?
+    } else {
+        // Decode the original data
+        let original_data = req.original_data.as_deref()
+            .ok_or_else(|| Error::BadRequest("original_data is required".to_string()))?;
+        let original_data = general_purpose::STANDARD.decode(original_data)
+            .map_err(|_| Error::BadRequest("Invalid base64 original data".to_string()))?;
+
+        // Verify the proof with the circuit it was made for
+        state.zkml_service.verify_proof(&req.proof, &original_data).await?
+    };
 
-    // Verify the proof
-    let is_valid = state.zkml_service.verify_sha256_proof(&req.proof, &original_data).await?;
+    // Record the result against the stored proof, if one was named
+    let mut verification_gas_cost = None;
+    if let (true, Some(proof_id)) = (is_valid, req.proof_id) {
+        let status = ZkmlProofService::new(state).mark_verified(proof_id, user_context.user_id).await?;
+        verification_gas_cost = status.verification_gas_cost;
+    }
 
     Ok(Json(serde_json::json!({
         "valid": is_valid,
         "circuit_type": req.proof.circuit_type,
+        "proof_id": req.proof_id,
+        "verification_gas_cost": verification_gas_cost,
         "verified_at": chrono::Utc::now()
     })))
 }
 
-/// Get proof status (for async proof generation)
-pub async fn get_proof_status(
-    State(_state): State<Arc<AppState>>,
-    Path(proof_id): Path<String>,
+/// Verify a proof made by an external party against a pinned verifying key
+pub async fn verify_external_proof(
+    State(state): State<Arc<AppState>>,
+    Extension(_user_context): Extension<UserContext>,
+    Json(req): Json<VerifyExternalProofRequest>,
 ) -> Result<impl IntoResponse, Error> {
-    // TODO: Implement proof status tracking for async operations
-    // For now, return a placeholder response
+    let original_data = req.original_data.as_deref()
+        .map(|data| general_purpose::STANDARD.decode(data))
+        .transpose()
+        .map_err(|_| Error::BadRequest("Invalid base64 original data".to_string()))?;
+
+    let is_valid = state.zkml_service
+        .verify_external_proof(&req.vk_hash, &req.proof, original_data.as_deref())
+        .await?;
+
     Ok(Json(serde_json::json!({
-        "proof_id": proof_id,
-        "status": "completed",
-        "message": "Proof status tracking not yet implemented"
+        "valid": is_valid,
+        "circuit_type": req.proof.circuit_type,
+        "vk_hash": req.vk_hash.to_ascii_lowercase(),
+        "verified_at": chrono::Utc::now()
     })))
 }
 
+/// Get the status of a proof job or the verification status of a stored proof
+pub async fn get_proof_status(
+    State(state): State<Arc<AppState>>,
+    Extension(user_context): Extension<UserContext>,
+    Path(id): Path<Uuid>,
+) -> Result<Response, Error> {
+    if let Some(job) = state.proof_jobs.get(id, user_context.user_id) {
+        return Ok(Json(job).into_response());
+    }
+
+    let proof_service = ZkmlProofService::new(state);
+    let status = proof_service.get_proof_status(id, user_context.user_id).await?;
+
+    Ok(Json(status).into_response())
+}
+
+/// Label a stored proof's public inputs with the circuit ABI's fields
+pub async fn explain_proof(
+    State(state): State<Arc<AppState>>,
+    Extension(user_context): Extension<UserContext>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, Error> {
+    let proof_service = ZkmlProofService::new(state);
+    let explanation = proof_service.explain_proof(id, user_context.user_id).await?;
+
+    Ok(Json(explanation))
+}
+
 /// Get circuit information
 pub async fn get_circuit_info(
     State(state): State<Arc<AppState>>,
     Path(circuit_name): Path<String>,
 ) -> Result<impl IntoResponse, Error> {
-    match circuit_name.as_str() {
-        "sha256" => {
-            let info = state.zkml_service.get_sha256_circuit_info();
-            Ok(Json(info))
-        }
-        _ => Err(Error::NotFound),
-    }
+    let circuit = circuit_name.parse::<guardian_zkml::CircuitType>()
+        .map_err(|_| Error::NotFound)?;
+
+    Ok(Json(state.zkml_service.get_circuit_info(circuit)))
 }
 
 /// Get ZKML system status
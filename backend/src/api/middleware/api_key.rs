@@ -0,0 +1,116 @@
+//! API key authentication for external integrations
+
+use crate::{
+    api::{middleware::auth::{AuthState, UserContext}, AppState},
+    db::models::Role,
+    db::queries::{ApiKeyQueries, UserQueries},
+    error::{Error, Result},
+    services::api_key::hash_api_key,
+};
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use chrono::Utc;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Header carrying the API key
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// The API key a request authenticated with
+#[derive(Debug, Clone)]
+pub struct ApiKeyContext {
+    pub key_id: Uuid,
+    pub user_id: Uuid,
+    pub permissions: Vec<String>,
+}
+
+impl ApiKeyContext {
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.permissions.iter().any(|granted| granted == permission)
+    }
+}
+
+/// Authenticate a request by its `X-API-Key` header. Inserts both an
+/// [`ApiKeyContext`] and the key owner's [`UserContext`], so handlers behind
+/// it work the same as with a JWT. The owner's revocations apply to keys
+/// created before them, as they do to tokens issued before them.
+pub async fn api_key_middleware(
+    State(state): State<Arc<AppState>>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response> {
+    let key = request
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|header| header.to_str().ok())
+        .filter(|key| !key.is_empty())
+        .ok_or_else(|| {
+            tracing::warn!("❌ No API key header found");
+            Error::Unauthorized
+        })?;
+
+    let api_key = ApiKeyQueries::find_by_hash(state.db.pool(), &hash_api_key(key)).await?
+        .ok_or(Error::Unauthorized)?;
+
+    if !api_key.is_active {
+        tracing::warn!("❌ API key {} has been revoked", api_key.id);
+        return Err(Error::Unauthorized);
+    }
+    if api_key.expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) {
+        tracing::warn!("❌ API key {} has expired", api_key.id);
+        return Err(Error::Unauthorized);
+    }
+
+    // Same denylist and account checks as a JWT, failing closed alike
+    AuthState::from_app_state(&state)
+        .check_credential(&api_key.id.to_string(), api_key.user_id, api_key.created_at.timestamp())
+        .await?;
+
+    let user = UserQueries::find_by_id(state.db.pool(), api_key.user_id).await?
+        .filter(|user| user.is_active)
+        .ok_or(Error::Unauthorized)?;
+
+    ApiKeyQueries::update_last_used(state.db.pool(), api_key.id).await?;
+
+    // Permissions that aren't strings are ignored rather than granted
+    let permissions = api_key.permissions.as_array()
+        .map(|permissions| permissions.iter().filter_map(|p| p.as_str().map(str::to_string)).collect())
+        .unwrap_or_default();
+
+    request.extensions_mut().insert(ApiKeyContext {
+        key_id: api_key.id,
+        user_id: user.id,
+        permissions,
+    });
+    // Keys carry their own permissions and never the owner's role
+    request.extensions_mut().insert(UserContext {
+        user_id: user.id,
+        email: user.email,
+        role: Role::User,
+    });
+
+    Ok(next.run(request).await)
+}
+
+/// Require the authenticating API key to hold `permission`. Layer it inside
+/// [`api_key_middleware`].
+pub async fn require_permission(
+    State(permission): State<&'static str>,
+    request: Request,
+    next: Next,
+) -> Result<Response> {
+    let context = request
+        .extensions()
+        .get::<ApiKeyContext>()
+        .ok_or(Error::Unauthorized)?;
+
+    if !context.has_permission(permission) {
+        tracing::warn!("❌ API key {} lacks the {} permission", context.key_id, permission);
+        return Err(Error::Forbidden);
+    }
+
+    Ok(next.run(request).await)
+}
\ No newline at end of file
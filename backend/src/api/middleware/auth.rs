@@ -1,13 +1,16 @@
 //! Authentication middleware for Guardian-AA Backend
 
 use crate::{
-    config::Config,
+    api::AppState,
+    auth::{AccountStatuses, TokenDenylist},
+    config::{AuthConfig, Config},
+    db::models::Role,
     error::{Error, Result},
-    services::auth::Claims,
+    services::auth::{Claims, TokenType},
 };
 use axum::{
     extract::{Request, State},
-    http::header::AUTHORIZATION,
+    http::{header::AUTHORIZATION, HeaderMap},
     middleware::Next,
     response::Response,
 };
@@ -20,11 +23,97 @@ use uuid::Uuid;
 pub struct UserContext {
     pub user_id: Uuid,
     pub email: String,
+    pub role: Role,
+}
+
+/// State for the authentication middlewares
+#[derive(Clone)]
+pub struct AuthState {
+    pub config: Arc<Config>,
+    /// Revoked tokens to reject; without one, revocation isn't checked
+    pub denylist: Option<TokenDenylist>,
+    /// Where to check that the token's account is still active and look up
+    /// its current role; without one, the account isn't checked and the
+    /// role in the token is trusted
+    pub accounts: Option<AccountStatuses>,
+}
+
+impl AuthState {
+    pub fn new(config: Arc<Config>) -> Self {
+        Self { config, denylist: None, accounts: None }
+    }
+
+    /// Reject tokens revoked in `denylist`
+    pub fn with_denylist(mut self, denylist: TokenDenylist) -> Self {
+        self.denylist = Some(denylist);
+        self
+    }
+
+    /// Reject tokens whose account is missing from or inactive in
+    /// `accounts`, and take the user's role from there
+    pub fn with_accounts(mut self, accounts: AccountStatuses) -> Self {
+        self.accounts = Some(accounts);
+        self
+    }
+
+    /// Authentication state for the application's protected routes
+    pub fn from_app_state(state: &AppState) -> Self {
+        Self::new(Arc::new(state.config.clone()))
+            .with_denylist(TokenDenylist::new(state.redis.clone()))
+            .with_accounts(state.account_statuses.clone())
+    }
+
+    /// Validate a token and check that it hasn't been revoked and its
+    /// account is still active, with the role the account holds now
+    async fn authenticate(&self, token: &str) -> Result<UserContext> {
+        let claims = decode_claims(token, &self.config.auth)?;
+        let jti = claims.jti.clone();
+        let issued_at = claims.iat;
+        let mut context = user_context(claims)?;
+
+        if let Some(role) = self.check_credential(&jti, context.user_id, issued_at).await? {
+            context.role = role;
+        }
+
+        Ok(context)
+    }
+
+    /// Check that the credential `jti`, issued to `user_id` at `issued_at`
+    /// (a Unix timestamp), hasn't been revoked and that its account is still
+    /// active. Returns the role the account holds now, if `accounts` is set.
+    pub async fn check_credential(&self, jti: &str, user_id: Uuid, issued_at: i64) -> Result<Option<Role>> {
+        if let Some(denylist) = &self.denylist {
+            match denylist.is_token_revoked(jti, &user_id.to_string(), issued_at).await {
+                Ok(true) => {
+                    tracing::warn!("❌ Token {} has been revoked", jti);
+                    return Err(Error::Unauthorized);
+                }
+                Ok(false) => {}
+                // A revoked token must not get through while Redis is down
+                Err(e) => {
+                    tracing::error!("❌ Failed to check token denylist: {}", e);
+                    return Err(Error::ServiceUnavailable);
+                }
+            }
+        }
+
+        let Some(accounts) = &self.accounts else {
+            return Ok(None);
+        };
+        let status = accounts.get(user_id).await?
+            .filter(|status| status.is_active)
+            .ok_or_else(|| {
+                tracing::warn!("❌ Account {} is missing or inactive", user_id);
+                Error::Unauthorized
+            })?;
+
+        Ok(Some(status.role()))
+    }
 }
 
 /// Authentication middleware that validates JWT tokens
 pub async fn auth_middleware(
-    State(config): State<Arc<Config>>,
+    State(auth): State<AuthState>,
     mut request: Request,
     next: Next,
 ) -> Result<Response> {
@@ -61,7 +150,7 @@ pub async fn auth_middleware(
     tracing::debug!("🔐 Token extracted, length: {}, preview: {}...", token.len(), &token[..20.min(token.len())]);
 
     // Validate the JWT token
-    let user_context = validate_jwt_token(token, &config.auth.jwt_secret)?;
+    let user_context = auth.authenticate(token).await?;
 
     tracing::debug!("✅ Token validated successfully for user: {}", user_context.email);
 
@@ -74,7 +163,7 @@ pub async fn auth_middleware(
 
 /// Optional authentication middleware that doesn't fail if no token is provided
 pub async fn optional_auth_middleware(
-    State(config): State<Arc<Config>>,
+    State(auth): State<AuthState>,
     mut request: Request,
     next: Next,
 ) -> Response {
@@ -88,7 +177,7 @@ pub async fn optional_auth_middleware(
             let token = auth_header.trim_start_matches("Bearer ");
             if !token.is_empty() {
                 // Try to validate token and add user info to request extensions
-                if let Ok(user_context) = validate_jwt_token(token, &config.auth.jwt_secret) {
+                if let Ok(user_context) = auth.authenticate(token).await {
                     request.extensions_mut().insert(user_context);
                 }
             }
@@ -98,12 +187,43 @@ pub async fn optional_auth_middleware(
     next.run(request).await
 }
 
-/// Validate JWT token and extract user claims
-fn validate_jwt_token(token: &str, secret: &str) -> Result<UserContext> {
-    tracing::debug!("🔍 Validating JWT token with secret length: {}", secret.len());
+/// Restrict a route to users holding at least the `required` role. Layer
+/// it inside [`auth_middleware`], which looks the role up, so a changed
+/// role applies within `auth.account_status_cache_secs`.
+pub async fn require_role(
+    State(required): State<Role>,
+    request: Request,
+    next: Next,
+) -> Result<Response> {
+    let user = request.user_context()?;
+
+    if user.role < required {
+        tracing::warn!("❌ User {} has role {:?}, {:?} required", user.user_id, user.role, required);
+        return Err(Error::Forbidden);
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// The token from a `Bearer` authorization header, if there is one
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .filter(|token| !token.is_empty())
+}
+
+/// Validate an access token and extract its claims. Refresh tokens are
+/// signed with the same key but are rejected here.
+pub fn decode_claims(token: &str, auth_config: &AuthConfig) -> Result<Claims> {
+    tracing::debug!("🔍 Validating JWT token with secret length: {}", auth_config.jwt_secret.len());
     
-    let decoding_key = DecodingKey::from_secret(secret.as_bytes());
-    let validation = Validation::default();
+    let decoding_key = DecodingKey::from_secret(auth_config.jwt_secret.as_bytes());
+    let mut validation = Validation::default();
+    validation.set_issuer(&[&auth_config.jwt_issuer]);
+    validation.set_audience(&auth_config.jwt_audiences);
+    validation.set_required_spec_claims(&["exp", "iss", "aud"]);
 
     let token_data = decode::<Claims>(token, &decoding_key, &validation)
         .map_err(|e| {
@@ -111,7 +231,16 @@ fn validate_jwt_token(token: &str, secret: &str) -> Result<UserContext> {
             Error::Unauthorized
         })?;
 
-    let claims = token_data.claims;
+    if token_data.claims.typ != TokenType::Access {
+        tracing::warn!("❌ Token {} is not an access token", token_data.claims.jti);
+        return Err(Error::Unauthorized);
+    }
+
+    Ok(token_data.claims)
+}
+
+/// Build the user context from validated claims
+fn user_context(claims: Claims) -> Result<UserContext> {
     tracing::debug!("🔍 JWT claims extracted: sub={}, email={}", claims.sub, claims.email);
 
     // Parse user ID from claims
@@ -126,6 +255,7 @@ fn validate_jwt_token(token: &str, secret: &str) -> Result<UserContext> {
     Ok(UserContext {
         user_id,
         email: claims.email,
+        role: claims.role,
     })
 }
 
@@ -0,0 +1,27 @@
+//! Request body limits per route group
+
+use crate::error::Error;
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+/// Marks a 413 already explained by the innermost, tightest limit, so the
+/// limits of enclosing route groups leave it alone
+#[derive(Clone, Copy)]
+struct Explained;
+
+/// Turn the bare 413 of a body over `limit` bytes into an error response
+/// that says what the limit is. Must sit outside the layer enforcing it.
+pub async fn body_limit_middleware(State(limit): State<usize>, request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    if response.status() != StatusCode::PAYLOAD_TOO_LARGE || response.extensions().get::<Explained>().is_some() {
+        return response;
+    }
+
+    let mut response = Error::PayloadTooLarge { limit }.into_response();
+    response.extensions_mut().insert(Explained);
+    response
+}
\ No newline at end of file
@@ -0,0 +1,22 @@
+//! Locale negotiation for error messages
+
+use crate::i18n::{self, Locale};
+use axum::{
+    extract::Request,
+    http::header::ACCEPT_LANGUAGE,
+    middleware::Next,
+    response::Response,
+};
+
+/// Pick the request's locale from `Accept-Language` so error responses
+/// produced while handling it are localized
+pub async fn locale_middleware(request: Request, next: Next) -> Response {
+    let locale = request
+        .headers()
+        .get(ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .map(Locale::from_accept_language)
+        .unwrap_or_default();
+
+    i18n::with_locale(locale, next.run(request)).await
+}
@@ -0,0 +1,100 @@
+//! Sampled request logging middleware for Guardian-AA Backend
+
+use crate::config::LoggingConfig;
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Request counters, updated for every request whether or not it is logged
+#[derive(Debug, Default)]
+pub struct RequestMetrics {
+    total: AtomicU64,
+    errors: AtomicU64,
+    slow: AtomicU64,
+}
+
+impl RequestMetrics {
+    pub fn total(&self) -> u64 {
+        self.total.load(Ordering::Relaxed)
+    }
+
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    pub fn slow(&self) -> u64 {
+        self.slow.load(Ordering::Relaxed)
+    }
+}
+
+/// Decides which requests get logged
+#[derive(Debug, Clone)]
+pub struct RequestLogSampler {
+    sample_rate: f64,
+    slow_threshold: Duration,
+    metrics: Arc<RequestMetrics>,
+}
+
+impl RequestLogSampler {
+    pub fn new(config: &LoggingConfig, metrics: Arc<RequestMetrics>) -> Self {
+        Self {
+            sample_rate: config.sample_rate.clamp(0.0, 1.0),
+            slow_threshold: Duration::from_millis(config.slow_request_threshold_ms),
+            metrics,
+        }
+    }
+
+    pub fn metrics(&self) -> &Arc<RequestMetrics> {
+        &self.metrics
+    }
+
+    fn sampled(&self) -> bool {
+        self.sample_rate >= 1.0 || (self.sample_rate > 0.0 && rand::random::<f64>() < self.sample_rate)
+    }
+}
+
+/// Logging middleware that always logs failed and slow requests but only a
+/// sampled fraction of successful ones
+pub async fn request_logging_middleware(
+    State(sampler): State<RequestLogSampler>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().clone();
+    let uri = request.uri().clone();
+    let start = Instant::now();
+
+    let response = next.run(request).await;
+
+    let latency = start.elapsed();
+    let status = response.status();
+    let latency_ms = latency.as_millis() as u64;
+    let is_error = status.is_client_error() || status.is_server_error();
+    let is_slow = latency >= sampler.slow_threshold;
+
+    let metrics = sampler.metrics();
+    metrics.total.fetch_add(1, Ordering::Relaxed);
+    if is_error {
+        metrics.errors.fetch_add(1, Ordering::Relaxed);
+    }
+    if is_slow {
+        metrics.slow.fetch_add(1, Ordering::Relaxed);
+    }
+
+    if status.is_server_error() {
+        tracing::error!(%method, %uri, status = status.as_u16(), latency_ms, "Request failed");
+    } else if is_error {
+        tracing::warn!(%method, %uri, status = status.as_u16(), latency_ms, "Request rejected");
+    } else if is_slow {
+        tracing::warn!(%method, %uri, status = status.as_u16(), latency_ms, "Slow request");
+    } else if sampler.sampled() {
+        tracing::info!(%method, %uri, status = status.as_u16(), latency_ms, "Request completed");
+    }
+
+    response
+}
@@ -0,0 +1,72 @@
+//! Maintenance mode
+
+use crate::{
+    config::{MaintenanceConfig, MaintenanceScope},
+    error::Error,
+};
+use axum::{
+    extract::{Request, State},
+    http::Method,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::{Arc, RwLock};
+
+/// Maintenance settings that can be changed while the server runs, from the
+/// admin API or by reloading the configuration. Changes only apply to this
+/// instance.
+#[derive(Clone, Default)]
+pub struct MaintenanceMode {
+    current: Arc<RwLock<MaintenanceConfig>>,
+}
+
+impl MaintenanceMode {
+    pub fn new(config: MaintenanceConfig) -> Self {
+        Self { current: Arc::new(RwLock::new(config)) }
+    }
+
+    /// The settings in effect
+    pub fn current(&self) -> MaintenanceConfig {
+        self.current.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Replace the settings in effect
+    pub fn set(&self, config: MaintenanceConfig) {
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = config;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.current.read().unwrap_or_else(|e| e.into_inner()).enabled
+    }
+
+    /// The error to answer `method` with, if maintenance turns it away
+    fn rejection(&self, method: &Method) -> Option<Error> {
+        let current = self.current.read().unwrap_or_else(|e| e.into_inner());
+        if !current.enabled {
+            return None;
+        }
+
+        let read_only = matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS);
+        if current.scope == MaintenanceScope::Writes && read_only {
+            return None;
+        }
+
+        Some(Error::Maintenance {
+            message: current.message.clone(),
+            retry_after_secs: current.retry_after_secs,
+        })
+    }
+}
+
+/// Answer requests with 503 and `Retry-After` while maintenance mode covers
+/// them
+pub async fn maintenance_middleware(
+    State(maintenance): State<MaintenanceMode>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match maintenance.rejection(request.method()) {
+        Some(error) => error.into_response(),
+        None => next.run(request).await,
+    }
+}
\ No newline at end of file
@@ -1,3 +1,10 @@
 //! API middleware
 
-pub mod auth; 
\ No newline at end of file
+pub mod api_key;
+pub mod auth;
+pub mod body_limit;
+pub mod locale;
+pub mod logging;
+pub mod maintenance;
+pub mod rate_limit;
+pub mod request_id; 
\ No newline at end of file
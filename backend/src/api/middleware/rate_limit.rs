@@ -0,0 +1,159 @@
+//! Redis-backed rate limiting
+
+use crate::{
+    api::{handlers::auth::ClientInfo, middleware::auth::UserContext},
+    config::RateLimitRule,
+    error::Error,
+};
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header::RETRY_AFTER, HeaderName, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::net::SocketAddr;
+use uuid::Uuid;
+
+/// Requests the client may still make in the current window
+pub const RATE_LIMIT_REMAINING: HeaderName = HeaderName::from_static("x-ratelimit-remaining");
+/// Requests allowed per window
+pub const RATE_LIMIT_LIMIT: HeaderName = HeaderName::from_static("x-ratelimit-limit");
+/// Seconds until the oldest request in the window ages out and frees a slot
+pub const RATE_LIMIT_RESET: HeaderName = HeaderName::from_static("x-ratelimit-reset");
+
+/// Sliding-window limiter for one group of routes. Requests are counted per
+/// user when authenticated and per client IP otherwise.
+#[derive(Clone)]
+pub struct RateLimiter {
+    redis: redis::Client,
+    scope: &'static str,
+    rule: RateLimitRule,
+    enabled: bool,
+}
+
+/// Outcome of counting one request
+struct Decision {
+    allowed: bool,
+    remaining: u64,
+    /// Seconds until the next slot frees up
+    reset_secs: u64,
+}
+
+impl RateLimiter {
+    /// Limit the routes in `scope` to `rule`. Scopes are counted separately,
+    /// so a route under two limiters must pass both.
+    pub fn new(redis: redis::Client, scope: &'static str, rule: RateLimitRule) -> Self {
+        Self { redis, scope, rule, enabled: true }
+    }
+
+    /// Let every request through without counting it
+    pub fn disabled(mut self) -> Self {
+        self.enabled = false;
+        self
+    }
+
+    /// Record a request from `subject` and decide whether it is allowed.
+    /// Rejected requests aren't recorded, so clients that back off for
+    /// `Retry-After` get through.
+    async fn check(&self, subject: &str) -> redis::RedisResult<Decision> {
+        let key = format!("rate_limit:{}:{}", self.scope, subject);
+        let window_ms = self.rule.window_secs.max(1) * 1000;
+        let now_ms = chrono::Utc::now().timestamp_millis() as u64;
+        let member = Uuid::new_v4().to_string();
+
+        let mut conn = self.redis.get_multiplexed_async_connection().await?;
+        let (count, oldest): (u64, Vec<(String, f64)>) = redis::pipe()
+            .atomic()
+            .zrembyscore(&key, 0, now_ms.saturating_sub(window_ms))
+            .ignore()
+            .zadd(&key, &member, now_ms)
+            .ignore()
+            .zcard(&key)
+            .zrange_withscores(&key, 0, 0)
+            .cmd("PEXPIRE").arg(&key).arg(window_ms)
+            .ignore()
+            .query_async(&mut conn)
+            .await?;
+
+        // A slot frees up once the oldest request in the window ages out
+        let oldest_ms = oldest.first().map_or(now_ms, |(_, score)| *score as u64);
+        let reset_secs = (oldest_ms + window_ms).saturating_sub(now_ms).div_ceil(1000).max(1);
+
+        if count <= self.rule.requests {
+            return Ok(Decision {
+                allowed: true,
+                remaining: self.rule.requests - count,
+                reset_secs,
+            });
+        }
+
+        redis::cmd("ZREM").arg(&key).arg(&member).query_async::<_, ()>(&mut conn).await?;
+
+        Ok(Decision {
+            allowed: false,
+            remaining: 0,
+            reset_secs,
+        })
+    }
+}
+
+/// Who a request is counted against
+fn subject(request: &Request) -> String {
+    if let Some(user) = request.extensions().get::<UserContext>() {
+        return format!("user:{}", user.user_id);
+    }
+
+    let ip = ClientInfo::from_headers(request.headers())
+        .ip_address
+        .map(|network| network.ip())
+        .or_else(|| {
+            request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip())
+        });
+
+    match ip {
+        Some(ip) => format!("ip:{}", ip),
+        None => "unknown".to_string(),
+    }
+}
+
+/// Reject requests over the limiter's rule with 429 and `Retry-After`, and
+/// report the limit, remaining allowance and time until a slot frees up in
+/// the `X-RateLimit-*` headers. Redis errors let the request through.
+pub async fn rate_limit_middleware(
+    State(limiter): State<RateLimiter>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !limiter.enabled {
+        return next.run(request).await;
+    }
+
+    let decision = match limiter.check(&subject(&request)).await {
+        Ok(decision) => decision,
+        Err(e) => {
+            // An outage of the limiter shouldn't take the API down with it
+            tracing::warn!(scope = limiter.scope, error = %e, "Rate limit check failed, allowing request");
+            return next.run(request).await;
+        }
+    };
+
+    let mut response = if decision.allowed {
+        next.run(request).await
+    } else {
+        let mut response = Error::RateLimitExceeded.into_response();
+        response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(decision.reset_secs));
+        response
+    };
+
+    // Under nested limiters the inner, route-specific one reports its numbers
+    let headers = response.headers_mut();
+    if !headers.contains_key(RATE_LIMIT_REMAINING) {
+        headers.insert(RATE_LIMIT_LIMIT, HeaderValue::from(limiter.rule.requests));
+        headers.insert(RATE_LIMIT_REMAINING, HeaderValue::from(decision.remaining));
+        headers.insert(RATE_LIMIT_RESET, HeaderValue::from(decision.reset_secs));
+    }
+    response
+}
\ No newline at end of file
@@ -1,9 +1,15 @@
 //! API layer for Guardian-AA Backend
 
-use crate::{config::Config, db::Database, blockchain::SolanaClient, zkml::ZkmlService};
+use crate::{auth::AccountStatuses, config::Config, db::Database, blockchain::{EthereumClient, SolanaClient}, inference::ModelRegistry, services::{ProofJobQueue, TokenRegistry, TransactionEvents}, zkml::ZkmlService};
+use self::middleware::{logging::RequestMetrics, maintenance::MaintenanceMode};
+use self::handlers::health::ReadinessCache;
+use self::websocket::WebSocketShutdown;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
 
 pub mod handlers;
 pub mod middleware;
+pub mod pagination;
 pub mod routes;
 pub mod websocket;
 
@@ -14,7 +20,25 @@ pub struct AppState {
     pub db: Database,
     pub redis: redis::Client,
     pub solana_client: SolanaClient,
+    pub ethereum_client: EthereumClient,
     pub zkml_service: ZkmlService,
+    pub proof_jobs: ProofJobQueue,
+    pub model_registry: ModelRegistry,
+    pub request_metrics: Arc<RequestMetrics>,
+    /// Set once shutdown begins; readiness then fails so traffic drains away
+    pub draining: Arc<AtomicBool>,
+    /// Maintenance mode, which can be toggled while the server runs
+    pub maintenance: MaintenanceMode,
+    /// Transaction status changes, for anyone who wants to follow them
+    pub transaction_events: TransactionEvents,
+    /// Supported tokens, kept current by the registry refresher
+    pub token_registry: TokenRegistry,
+    /// Closes open WebSockets when the server shuts down
+    pub websocket_shutdown: WebSocketShutdown,
+    /// Recent readiness check results
+    pub readiness: ReadinessCache,
+    /// Recently checked account statuses, shared by every authenticated route
+    pub account_statuses: AccountStatuses,
 }
 
 pub use routes::create_router; 
\ No newline at end of file
@@ -0,0 +1,53 @@
+//! Pagination for list endpoints
+
+use serde::Serialize;
+
+/// Page size used when the client does not ask for one
+pub const DEFAULT_PAGE_LIMIT: i64 = 50;
+
+/// Largest page size the server will return, whatever the client asks for
+pub const MAX_PAGE_LIMIT: i64 = 200;
+
+/// A validated window into a list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Page {
+    pub limit: i64,
+    pub offset: i64,
+}
+
+impl Page {
+    /// Build a page from query parameters, clamping `limit` to
+    /// `1..=MAX_PAGE_LIMIT` and negative offsets to zero
+    pub fn new(limit: Option<i64>, offset: Option<i64>) -> Self {
+        Self {
+            limit: limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT),
+            offset: offset.unwrap_or(0).max(0),
+        }
+    }
+}
+
+impl Default for Page {
+    fn default() -> Self {
+        Self::new(None, None)
+    }
+}
+
+/// One page of a list, with the total row count so clients can page through it
+#[derive(Debug, Serialize)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+impl<T> Paginated<T> {
+    pub fn new(items: Vec<T>, total: i64, page: Page) -> Self {
+        Self {
+            items,
+            total,
+            limit: page.limit,
+            offset: page.offset,
+        }
+    }
+}
\ No newline at end of file
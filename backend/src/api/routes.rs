@@ -1,8 +1,14 @@
 //! API routes configuration
 
-use crate::api::{handlers, middleware, websocket, AppState};
+use crate::{
+    api::{handlers, middleware, websocket, AppState},
+    config::RateLimitRule,
+    db::models::Role,
+    telemetry,
+};
 use axum::{
-    routing::{get, post, delete},
+    extract::DefaultBodyLimit,
+    routing::{get, post, put, delete},
     Router,
 };
 use std::sync::Arc;
@@ -10,11 +16,38 @@ use tower_http::limit::RequestBodyLimitLayer;
 
 /// Create the main application router
 pub fn create_router(state: Arc<AppState>) -> Router {
-    Router::new()
+    let metrics = state.config.metrics.clone();
+    let mut router = Router::new()
         .merge(health_routes(state.clone()))
-        .nest("/api/v1", api_v1_routes(state.clone()))
+        .nest("/api/v1", api_v1_routes(state.clone()));
+
+    // With a dedicated port the endpoint is served by `metrics_router` instead
+    if metrics.enabled && metrics.port.is_none() {
+        router = router.merge(metrics_router(state.clone()));
+    }
+
+    let router = router
         .nest("/ws", websocket_routes(state))
         .fallback(handlers::fallback)
+        .layer(axum::middleware::from_fn(middleware::locale::locale_middleware));
+
+    let router = if metrics.enabled {
+        // Install the recorder before the first request is counted
+        telemetry::handle();
+        router.layer(axum::middleware::from_fn(telemetry::http_metrics_middleware))
+    } else {
+        router
+    };
+
+    // Outermost, so every response and error body carries the id
+    router.layer(axum::middleware::from_fn(middleware::request_id::request_id_middleware))
+}
+
+/// Prometheus metrics, left unauthenticated for scrapers
+pub fn metrics_router(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/metrics", get(telemetry::metrics_handler))
+        .with_state(state)
 }
 
 /// Health check routes
@@ -27,39 +60,162 @@ fn health_routes(state: Arc<AppState>) -> Router {
 
 /// API v1 routes
 fn api_v1_routes(state: Arc<AppState>) -> Router {
-    Router::new()
+    // Admin routes stay reachable during maintenance so it can be turned off
+    let routes = Router::new()
         // Public routes (no auth required)
-        .nest("/auth", auth_routes())
+        .nest("/auth", auth_routes(&state).merge(protected_auth_routes(state.clone())))
         // Protected routes (auth required)
         .nest("/wallet", protected_wallet_routes(state.clone()))
         .nest("/transaction", protected_transaction_routes(state.clone()))
         .nest("/agent", protected_agent_routes(state.clone()))
         .nest("/zkml", protected_zkml_routes(state.clone()))
+        .nest("/api-keys", protected_api_key_routes(state.clone()))
+        .nest("/webhooks", protected_webhook_routes(state.clone()))
+        // Routes for external integrations (API key required)
+        .nest("/integrations", integration_routes(state.clone()))
+        .layer(axum::middleware::from_fn_with_state(
+            state.maintenance.clone(),
+            middleware::maintenance::maintenance_middleware
+        ));
+
+    routes
+        .nest("/admin", admin_routes(state.clone()))
         .with_state(state)
 }
 
 /// Authentication routes (public)
-fn auth_routes() -> Router<Arc<AppState>> {
-    Router::new()
+fn auth_routes(state: &Arc<AppState>) -> Router<Arc<AppState>> {
+    let routes = Router::new()
         .route("/register", post(handlers::auth::register))
         .route("/login", post(handlers::auth::login))
+        .route("/challenge", post(handlers::auth::wallet_challenge))
+        .route("/verify-signature", post(handlers::auth::verify_signature))
         .route("/refresh", post(handlers::auth::refresh_token))
         .route("/logout", post(handlers::auth::logout))
         .route("/verify-email", post(handlers::auth::verify_email))
         .route("/forgot-password", post(handlers::auth::forgot_password))
-        .route("/reset-password", post(handlers::auth::reset_password))
-        // Apply request body limit to auth routes
-        .layer(RequestBodyLimitLayer::new(5 * 1024 * 1024)) // 5MB limit
+        .route("/reset-password", post(handlers::auth::reset_password));
+
+    // Nobody is logged in yet, so these are limited per client IP
+    let routes = rate_limited(routes, state, "auth", state.config.rate_limit.auth);
+    body_limited(routes, state.config.server.body_limits.auth_bytes)
+}
+
+/// Authentication routes for logged-in users
+fn protected_auth_routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    let routes = Router::new()
+        .route("/change-password", post(handlers::auth::change_password))
+        .route("/account", delete(handlers::auth::deactivate_account))
+        .route("/sessions", get(handlers::auth::list_sessions).delete(handlers::auth::revoke_other_sessions))
+        .route("/sessions/{session_id}", delete(handlers::auth::revoke_session));
+
+    // Limited inside authentication so requests are counted per user
+    let routes = rate_limited(routes, &state, "api", state.config.rate_limit.default)
+        .route_layer(axum::middleware::from_fn_with_state(
+            middleware::auth::AuthState::from_app_state(&state),
+            middleware::auth::auth_middleware
+        ));
+    body_limited(routes, state.config.server.body_limits.auth_bytes)
+}
+
+/// API key management routes (JWT required)
+fn protected_api_key_routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    let routes = Router::new()
+        .route("/", post(handlers::api_key::create_api_key))
+        .route("/{key_id}", delete(handlers::api_key::revoke_api_key));
+
+    // Limited inside authentication so requests are counted per user
+    let routes = rate_limited(routes, &state, "api", state.config.rate_limit.default)
+        .route_layer(axum::middleware::from_fn_with_state(
+            middleware::auth::AuthState::from_app_state(&state),
+            middleware::auth::auth_middleware
+        ));
+    body_limited(routes, state.config.server.body_limits.default_bytes)
+}
+
+/// Webhook registration routes (JWT required)
+fn protected_webhook_routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    let routes = Router::new()
+        .route("/", get(handlers::webhook::list_webhooks).post(handlers::webhook::create_webhook))
+        .route("/{webhook_id}", delete(handlers::webhook::delete_webhook));
+
+    // Limited inside authentication so requests are counted per user
+    let routes = rate_limited(routes, &state, "api", state.config.rate_limit.default)
+        .route_layer(axum::middleware::from_fn_with_state(
+            middleware::auth::AuthState::from_app_state(&state),
+            middleware::auth::auth_middleware
+        ));
+    body_limited(routes, state.config.server.body_limits.default_bytes)
+}
+
+/// Operator routes (JWT of an administrator required)
+fn admin_routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    let routes = Router::new()
+        .route("/stats", get(handlers::admin::get_stats))
+        .route("/maintenance", get(handlers::admin::get_maintenance))
+        .route("/maintenance", put(handlers::admin::set_maintenance))
+        .route("/token-registry/refresh", post(handlers::admin::refresh_token_registry))
+        .route("/users/{user_id}/role", put(handlers::admin::set_user_role));
+
+    // Layers run outermost first, so the user is authenticated before the
+    // admin check
+    rate_limited(routes, &state, "api", state.config.rate_limit.default)
+        .route_layer(axum::middleware::from_fn_with_state(
+            Role::Admin,
+            middleware::auth::require_role
+        ))
+        .route_layer(axum::middleware::from_fn_with_state(
+            middleware::auth::AuthState::from_app_state(&state),
+            middleware::auth::auth_middleware
+        ))
+}
+
+/// Wallet, transaction and ZK-ML routes for API keys, each group gated on
+/// the matching key permission
+fn integration_routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
+    let routes = Router::new()
+        .nest("/wallet", api_key_protected(wallet_routes(), "wallet", &state))
+        .nest("/transaction", api_key_protected(transaction_routes(), "transaction", &state))
+        .nest("/zkml", api_key_protected(zkml_routes(&state), "zkml", &state));
+    body_limited(routes, state.config.server.body_limits.default_bytes)
+}
+
+/// Require an API key holding `permission` for every route in `routes`
+fn api_key_protected(
+    routes: Router<Arc<AppState>>,
+    permission: &'static str,
+    state: &Arc<AppState>,
+) -> Router<Arc<AppState>> {
+    // Layers run outermost first, so the key is authenticated before its
+    // permissions are checked and requests are counted against its user
+    rate_limited(routes, state, "api", state.config.rate_limit.default)
+        .route_layer(axum::middleware::from_fn_with_state(
+            permission,
+            middleware::api_key::require_permission
+        ))
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            middleware::api_key::api_key_middleware
+        ))
 }
 
 /// Protected wallet management routes
 fn protected_wallet_routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
-    wallet_routes()
+    // Faucet funding is for administrators, and not offered to integrations
+    let routes = wallet_routes()
+        .route("/{wallet_id}/airdrop", post(handlers::wallet::request_airdrop)
+            .route_layer(axum::middleware::from_fn_with_state(
+                Role::Admin,
+                middleware::auth::require_role
+            )));
+
+    // Limited inside authentication so requests are counted per user
+    let routes = rate_limited(routes, &state, "api", state.config.rate_limit.default)
         .route_layer(axum::middleware::from_fn_with_state(
-            Arc::new(state.config.clone()),
+            middleware::auth::AuthState::from_app_state(&state),
             middleware::auth::auth_middleware
-        ))
-        .layer(RequestBodyLimitLayer::new(5 * 1024 * 1024)) // 5MB limit
+        ));
+    body_limited(routes, state.config.server.body_limits.default_bytes)
 }
 
 /// Wallet management routes
@@ -67,6 +223,7 @@ fn wallet_routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/", post(handlers::wallet::create_wallet))
         .route("/", get(handlers::wallet::get_wallets))
+        .route("/balances", get(handlers::wallet::get_all_balances))
         .route("/{wallet_id}", get(handlers::wallet::get_wallet))
         .route("/{wallet_id}", delete(handlers::wallet::deactivate_wallet))
         .route("/{wallet_id}/balance", get(handlers::wallet::get_wallet_balance))
@@ -74,12 +231,13 @@ fn wallet_routes() -> Router<Arc<AppState>> {
 
 /// Protected transaction routes
 fn protected_transaction_routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
-    transaction_routes()
+    // Limited inside authentication so requests are counted per user
+    let routes = rate_limited(transaction_routes(), &state, "api", state.config.rate_limit.default)
         .route_layer(axum::middleware::from_fn_with_state(
-            Arc::new(state.config.clone()),
+            middleware::auth::AuthState::from_app_state(&state),
             middleware::auth::auth_middleware
-        ))
-        .layer(RequestBodyLimitLayer::new(5 * 1024 * 1024)) // 5MB limit
+        ));
+    body_limited(routes, state.config.server.body_limits.default_bytes)
 }
 
 /// Transaction routes
@@ -88,18 +246,22 @@ fn transaction_routes() -> Router<Arc<AppState>> {
         .route("/", post(handlers::transaction::create_transaction))
         .route("/", get(handlers::transaction::get_transactions))
         .route("/estimate-fee", post(handlers::transaction::estimate_fee))
+        .route("/compute-budget", post(handlers::transaction::recommend_compute_budget))
+        .route("/decode", post(handlers::transaction::decode_transaction))
+        .route("/submit-batch", post(handlers::transaction::submit_transaction_batch))
         .route("/{transaction_id}", get(handlers::transaction::get_transaction))
         .route("/{transaction_id}/submit", post(handlers::transaction::submit_transaction))
 }
 
 /// Protected AI agent routes
 fn protected_agent_routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
-    agent_routes()
+    // Limited inside authentication so requests are counted per user
+    let routes = rate_limited(agent_routes(), &state, "api", state.config.rate_limit.default)
         .route_layer(axum::middleware::from_fn_with_state(
-            Arc::new(state.config.clone()),
+            middleware::auth::AuthState::from_app_state(&state),
             middleware::auth::auth_middleware
-        ))
-        .layer(RequestBodyLimitLayer::new(5 * 1024 * 1024)) // 5MB limit
+        ));
+    body_limited(routes, state.config.server.body_limits.default_bytes)
 }
 
 /// AI agent routes
@@ -107,37 +269,100 @@ fn agent_routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/", get(handlers::agent::get_agents))
         .route("/{agent_id}", get(handlers::agent::get_agent))
+        .route("/{agent_id}/reload-model", post(handlers::agent::reload_agent_model)
+            .route_layer(axum::middleware::from_fn_with_state(
+                Role::Admin,
+                middleware::auth::require_role
+            )))
         .route("/predictions", post(handlers::agent::create_prediction))
         .route("/predictions", get(handlers::agent::get_predictions))
         .route("/predictions/{prediction_id}", get(handlers::agent::get_prediction))
+        .route("/predictions/{prediction_id}/proof/verify", get(handlers::agent::verify_prediction_proof))
+        .route("/predictions/{prediction_id}/verify-explanation", post(handlers::agent::verify_explanation))
         .route("/analyze", post(handlers::agent::generate_market_analysis))
-        .route("/cleanup", post(handlers::agent::cleanup_expired_predictions))
+        .route("/cleanup", post(handlers::agent::cleanup_expired_predictions)
+            .route_layer(axum::middleware::from_fn_with_state(
+                Role::Admin,
+                middleware::auth::require_role
+            )))
 }
 
 /// Protected ZK-ML routes
 fn protected_zkml_routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
-    zkml_routes()
+    // Limited inside authentication so requests are counted per user
+    let routes = rate_limited(zkml_routes(&state), &state, "api", state.config.rate_limit.default)
         .route_layer(axum::middleware::from_fn_with_state(
-            Arc::new(state.config.clone()),
+            middleware::auth::AuthState::from_app_state(&state),
             middleware::auth::auth_middleware
-        ))
-        .layer(RequestBodyLimitLayer::new(5 * 1024 * 1024)) // 5MB limit
+        ));
+    body_limited(routes, state.config.server.body_limits.default_bytes)
 }
 
 /// ZK-ML routes
-fn zkml_routes() -> Router<Arc<AppState>> {
-    Router::new()
+fn zkml_routes(state: &Arc<AppState>) -> Router<Arc<AppState>> {
+    // A single proof's input can't be bigger than the circuit takes, so
+    // neither can the request carrying it, nor a batch of them
+    let single = Router::new()
         .route("/generate", post(handlers::zkml::generate_proof))
+        .route("/generate/stream", post(handlers::zkml::generate_proof_stream));
+    let batch = Router::new()
+        .route("/generate-batch", post(handlers::zkml::generate_proof_batch));
+    let generation = body_limited(single, handlers::zkml::generate_body_limit(&state.zkml_service))
+        .merge(body_limited(batch, handlers::zkml::generate_batch_body_limit(&state.zkml_service)));
+
+    Router::new()
+        .merge(rate_limited(generation, state, "proof_generation", state.config.rate_limit.proof_generation))
         .route("/verify", post(handlers::zkml::verify_proof))
+        .route("/verify/stream", post(handlers::zkml::verify_proof_stream))
+        .route("/verify-external", post(handlers::zkml::verify_external_proof))
         .route("/status/{id}", get(handlers::zkml::get_proof_status))
+        .route("/proofs/{id}/explain", get(handlers::zkml::explain_proof))
         .route("/circuit/{name}", get(handlers::zkml::get_circuit_info))
         .route("/system/status", get(handlers::zkml::get_system_status))
         .route("/health", get(handlers::zkml::health_check))
 }
 
+/// Accept bodies of at most `limit` bytes on every route in `routes`,
+/// answering larger ones with a 413 that names the limit
+fn body_limited(routes: Router<Arc<AppState>>, limit: usize) -> Router<Arc<AppState>> {
+    routes
+        // Extractors have a default limit of their own that would otherwise
+        // cut in first
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(limit))
+        .layer(axum::middleware::from_fn_with_state(limit, middleware::body_limit::body_limit_middleware))
+}
+
+/// Limit every route in `routes` to `rule`, counting requests under `scope`
+fn rate_limited(
+    routes: Router<Arc<AppState>>,
+    state: &Arc<AppState>,
+    scope: &'static str,
+    rule: RateLimitRule,
+) -> Router<Arc<AppState>> {
+    let mut limiter = middleware::rate_limit::RateLimiter::new(state.redis.clone(), scope, rule);
+    if !state.config.rate_limit.enabled {
+        limiter = limiter.disabled();
+    }
+
+    routes.route_layer(axum::middleware::from_fn_with_state(
+        limiter,
+        middleware::rate_limit::rate_limit_middleware
+    ))
+}
+
 /// WebSocket routes
 fn websocket_routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/", get(websocket::websocket_handler))
+        // Anyone may connect, but only authenticated clients can subscribe
+        .layer(axum::middleware::from_fn_with_state(
+            middleware::auth::AuthState::from_app_state(&state),
+            middleware::auth::optional_auth_middleware
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.maintenance.clone(),
+            middleware::maintenance::maintenance_middleware
+        ))
         .with_state(state)
 } 
\ No newline at end of file
@@ -1,24 +1,114 @@
 //! WebSocket handlers for Guardian-AA Backend
+//!
+//! Authenticated clients can follow the status of their wallets'
+//! transactions by subscribing to the `tx` channel:
+//!
+//! ```json
+//! {"type": "subscribe", "channel": "tx", "wallet_id": "..."}
+//! ```
+//!
+//! Every status change published on [`AppState::transaction_events`] for a
+//! subscribed wallet is then pushed as a `transaction_update` message.
+//!
+//! When the server shuts down, [`WebSocketShutdown::close_all`] sends every
+//! open connection a going-away close frame.
 
+use crate::{
+    api::{middleware::auth::UserContext, AppState},
+    db::queries::WalletQueries,
+    services::TransactionUpdate,
+    telemetry::ActiveConnection,
+};
 use axum::{
     extract::{
-        ws::{Message, WebSocket},
-        WebSocketUpgrade,
+        ws::{close_code, CloseFrame, Message, WebSocket},
+        State, WebSocketUpgrade,
     },
     response::Response,
+    Extension,
 };
 use serde_json::json;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::watch;
 use tokio::time::{interval, Duration};
-use tracing::{error, info};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+/// Channel carrying transaction status updates
+const TRANSACTION_CHANNEL: &str = "tx";
+
+/// Outgoing bytes buffered before a write is flushed to the socket
+const WRITE_BUFFER_SIZE: usize = 128 * 1024;
+
+/// Tells open connections the server is shutting down. Each connection
+/// holds a receiver, so the receivers also count the open connections.
+#[derive(Clone)]
+pub struct WebSocketShutdown(Arc<watch::Sender<bool>>);
+
+impl Default for WebSocketShutdown {
+    fn default() -> Self {
+        Self(Arc::new(watch::channel(false).0))
+    }
+}
+
+impl WebSocketShutdown {
+    /// Connections currently open
+    pub fn active(&self) -> usize {
+        self.0.receiver_count()
+    }
 
-/// WebSocket upgrade handler
-pub async fn websocket_handler(ws: WebSocketUpgrade) -> Response {
-    ws.on_upgrade(handle_socket)
+    /// Ask every open connection to close, and any opened later to close
+    /// straight away, then wait up to `timeout` for them to finish. Returns
+    /// whether they all closed in time.
+    pub async fn close_all(&self, timeout: Duration) -> bool {
+        self.0.send_replace(true);
+        tokio::time::timeout(timeout, self.0.closed()).await.is_ok()
+    }
+}
+
+/// WebSocket upgrade handler.
+///
+/// Messages are sent uncompressed: the underlying WebSocket implementation
+/// does not negotiate permessage-deflate.
+pub async fn websocket_handler(
+    State(state): State<Arc<AppState>>,
+    user: Option<Extension<UserContext>>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let max_message_size = state.config.websocket.max_message_size;
+
+    // Oversized messages are rejected in `handle_socket` with a close frame;
+    // the transport only drops far larger ones so they are never buffered
+    let transport_limit = max_message_size.saturating_mul(2);
+    // The backlog must exceed the write buffer or every send would fail
+    let max_send_backlog = state.config.websocket.max_send_backlog.max(WRITE_BUFFER_SIZE + 1);
+
+    ws.max_message_size(transport_limit)
+        .max_frame_size(transport_limit)
+        .write_buffer_size(WRITE_BUFFER_SIZE)
+        .max_write_buffer_size(max_send_backlog)
+        .on_upgrade(move |socket| handle_socket(socket, state, user.map(|Extension(user)| user), max_message_size))
+}
+
+/// What one connection has asked to be sent
+struct Connection {
+    state: Arc<AppState>,
+    user: Option<UserContext>,
+    /// Wallets whose transaction updates are pushed to the client
+    wallets: HashSet<Uuid>,
 }
 
 /// Handle individual WebSocket connections
-async fn handle_socket(mut socket: WebSocket) {
+async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>, user: Option<UserContext>, max_message_size: usize) {
     info!("New WebSocket connection established");
+    let _active = ActiveConnection::new();
+    let mut shutdown = state.websocket_shutdown.0.subscribe();
+
+    // Subscribe before greeting the client so no update after it is missed
+    let mut updates = state.transaction_events.subscribe();
+    let mut connection = Connection { state, user, wallets: HashSet::new() };
 
     // Send welcome message
     if let Err(e) = socket.send(Message::Text(
@@ -39,9 +129,17 @@ async fn handle_socket(mut socket: WebSocket) {
             // Handle incoming messages
             msg = socket.recv() => {
                 match msg {
+                    Some(Ok(Message::Text(text))) if text.len() > max_message_size => {
+                        close_too_large(&mut socket, text.len(), max_message_size).await;
+                        break;
+                    }
+                    Some(Ok(Message::Binary(data))) if data.len() > max_message_size => {
+                        close_too_large(&mut socket, data.len(), max_message_size).await;
+                        break;
+                    }
                     Some(Ok(Message::Text(text))) => {
                         info!("Received message: {}", text);
-                        handle_message(&mut socket, text.to_string()).await;
+                        handle_message(&mut socket, &mut connection, text.to_string()).await;
                     }
                     Some(Ok(Message::Close(_))) => {
                         info!("WebSocket connection closed");
@@ -55,6 +153,31 @@ async fn handle_socket(mut socket: WebSocket) {
                     _ => {}
                 }
             }
+            // Push updates for the wallets the client subscribed to
+            update = updates.recv() => {
+                match update {
+                    Ok(update) if connection.wallets.contains(&update.wallet_id) => {
+                        if let Err(e) = socket.send(transaction_update_message(&update)).await {
+                            error!("Failed to send transaction update: {}", e);
+                            break;
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(missed)) => {
+                        if !connection.wallets.is_empty() {
+                            warn!(missed, "WebSocket client fell behind on transaction updates");
+                            let response = json!({"type": "error", "message": format!("Missed {} transaction updates", missed)});
+                            let _ = socket.send(Message::Text(response.to_string().into())).await;
+                        }
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+            // Say goodbye properly when the server shuts down
+            _ = shutdown.wait_for(|closing| *closing) => {
+                close_going_away(&mut socket).await;
+                break;
+            }
             // Send heartbeat
             _ = heartbeat.tick() => {
                 if let Err(e) = socket.send(Message::Text(
@@ -70,8 +193,39 @@ async fn handle_socket(mut socket: WebSocket) {
     info!("WebSocket connection terminated");
 }
 
+/// Close a connection that sent a message over the size limit
+async fn close_too_large(socket: &mut WebSocket, size: usize, max_message_size: usize) {
+    warn!(size, max_message_size, "Closing WebSocket after oversized message");
+    let frame = CloseFrame {
+        code: close_code::SIZE,
+        reason: format!("Message exceeds {} bytes", max_message_size).into(),
+    };
+    let _ = socket.send(Message::Close(Some(frame))).await;
+}
+
+/// Close a connection because the server is shutting down
+async fn close_going_away(socket: &mut WebSocket) {
+    info!("Closing WebSocket for server shutdown");
+    let frame = CloseFrame {
+        code: close_code::AWAY,
+        reason: "Server shutting down".into(),
+    };
+    // Sending flushes, so the frame is on the wire before the socket drops
+    let _ = socket.send(Message::Close(Some(frame))).await;
+}
+
+/// A transaction status change, as pushed to subscribers
+fn transaction_update_message(update: &TransactionUpdate) -> Message {
+    let message = json!({
+        "type": "transaction_update",
+        "channel": TRANSACTION_CHANNEL,
+        "data": update,
+    });
+    Message::Text(message.to_string().into())
+}
+
 /// Handle incoming WebSocket messages
-async fn handle_message(socket: &mut WebSocket, message: String) {
+async fn handle_message(socket: &mut WebSocket, connection: &mut Connection, message: String) {
     // Parse the incoming message
     match serde_json::from_str::<serde_json::Value>(&message) {
         Ok(json_msg) => {
@@ -83,13 +237,17 @@ async fn handle_message(socket: &mut WebSocket, message: String) {
                     let _ = socket.send(Message::Text(response.to_string().into())).await;
                 }
                 "subscribe" => {
-                    // Handle subscription requests (e.g., to transaction updates, market data)
-                    let response = json!({"type": "subscribed", "message": "Subscription successful"});
+                    let response = match subscribe(connection, &json_msg).await {
+                        Ok(wallet_id) => json!({"type": "subscribed", "channel": TRANSACTION_CHANNEL, "wallet_id": wallet_id}),
+                        Err(message) => json!({"type": "error", "message": message}),
+                    };
                     let _ = socket.send(Message::Text(response.to_string().into())).await;
                 }
                 "unsubscribe" => {
-                    // Handle unsubscription requests
-                    let response = json!({"type": "unsubscribed", "message": "Unsubscription successful"});
+                    let response = match unsubscribe(connection, &json_msg) {
+                        Ok(wallet_id) => json!({"type": "unsubscribed", "channel": TRANSACTION_CHANNEL, "wallet_id": wallet_id}),
+                        Err(message) => json!({"type": "error", "message": message}),
+                    };
                     let _ = socket.send(Message::Text(response.to_string().into())).await;
                 }
                 _ => {
@@ -104,4 +262,55 @@ async fn handle_message(socket: &mut WebSocket, message: String) {
             let _ = socket.send(Message::Text(response.to_string().into())).await;
         }
     }
-} 
\ No newline at end of file
+} 
+
+/// The channel and wallet named in a subscribe or unsubscribe message
+fn subscription_target(message: &serde_json::Value) -> Result<Option<Uuid>, String> {
+    match message.get("channel").and_then(|v| v.as_str()) {
+        Some(TRANSACTION_CHANNEL) => {}
+        Some(channel) => return Err(format!("Unknown channel: {}", channel)),
+        None => return Err("channel is required".to_string()),
+    }
+
+    match message.get("wallet_id") {
+        None => Ok(None),
+        Some(wallet_id) => wallet_id.as_str()
+            .and_then(|wallet_id| Uuid::parse_str(wallet_id).ok())
+            .map(Some)
+            .ok_or_else(|| "wallet_id must be a UUID".to_string()),
+    }
+}
+
+/// Start pushing updates for the requested wallet, which must belong to the
+/// authenticated user
+async fn subscribe(connection: &mut Connection, message: &serde_json::Value) -> Result<Uuid, String> {
+    let wallet_id = subscription_target(message)?.ok_or_else(|| "wallet_id is required".to_string())?;
+    let user = connection.user.as_ref().ok_or_else(|| "Authentication required".to_string())?;
+
+    let wallet = WalletQueries::find_by_id(connection.state.db.read_pool(), wallet_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to look up wallet for subscription: {}", e);
+            "Subscription failed".to_string()
+        })?;
+    // Someone else's wallet is reported the same as a missing one
+    if !wallet.is_some_and(|wallet| wallet.user_id == user.user_id) {
+        return Err("Wallet not found".to_string());
+    }
+
+    connection.wallets.insert(wallet_id);
+    Ok(wallet_id)
+}
+
+/// Stop pushing updates for the requested wallet, or for every wallet when
+/// none is named
+fn unsubscribe(connection: &mut Connection, message: &serde_json::Value) -> Result<Option<Uuid>, String> {
+    let wallet_id = subscription_target(message)?;
+    match wallet_id {
+        Some(wallet_id) => {
+            connection.wallets.remove(&wallet_id);
+        }
+        None => connection.wallets.clear(),
+    }
+    Ok(wallet_id)
+}
\ No newline at end of file
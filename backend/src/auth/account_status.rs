@@ -0,0 +1,69 @@
+//! Short-lived cache of the account statuses request authentication checks
+
+use crate::{
+    db::{models::AccountStatus, queries::UserQueries, Database},
+    error::Result,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Most accounts remembered at once; past it, stale entries are dropped
+/// and, if that isn't enough, the whole cache
+const MAX_CACHED_ACCOUNTS: usize = 10_000;
+
+/// The statuses of accounts that authenticated recently. Tokens outlive
+/// changes to their account, so every authenticated request looks its
+/// account up; within `max_age` of the last lookup the answer is reused
+/// instead of asking the database again.
+#[derive(Clone)]
+pub struct AccountStatuses {
+    db: Database,
+    max_age: Duration,
+    cache: Arc<Mutex<HashMap<Uuid, CachedStatus>>>,
+}
+
+#[derive(Clone, Copy)]
+struct CachedStatus {
+    status: Option<AccountStatus>,
+    checked_at: Instant,
+}
+
+impl AccountStatuses {
+    pub fn new(db: Database, max_age_secs: u64) -> Self {
+        Self {
+            db,
+            max_age: Duration::from_secs(max_age_secs),
+            cache: Default::default(),
+        }
+    }
+
+    /// The account's current status, or `None` when there is no such account
+    pub async fn get(&self, user_id: Uuid) -> Result<Option<AccountStatus>> {
+        if let Some(cached) = self.cache.lock().unwrap().get(&user_id) {
+            if cached.checked_at.elapsed() < self.max_age {
+                return Ok(cached.status);
+            }
+        }
+
+        let status = UserQueries::find_status(self.db.pool(), user_id).await?;
+
+        let mut cache = self.cache.lock().unwrap();
+        if cache.len() >= MAX_CACHED_ACCOUNTS {
+            cache.retain(|_, cached| cached.checked_at.elapsed() < self.max_age);
+            if cache.len() >= MAX_CACHED_ACCOUNTS {
+                cache.clear();
+            }
+        }
+        cache.insert(user_id, CachedStatus { status, checked_at: Instant::now() });
+
+        Ok(status)
+    }
+
+    /// Drop the account's cached status, so the next request looks it up.
+    /// Other instances keep theirs until it ages out.
+    pub fn forget(&self, user_id: Uuid) {
+        self.cache.lock().unwrap().remove(&user_id);
+    }
+}
\ No newline at end of file
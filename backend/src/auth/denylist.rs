@@ -0,0 +1,111 @@
+//! Redis-backed denylist of revoked access tokens
+
+use crate::error::{Error, Result};
+use redis::AsyncCommands;
+
+/// Access tokens revoked before their expiry, keyed by their `jti` claim.
+/// Entries expire together with the token, so the list only holds tokens
+/// that would otherwise still be accepted.
+#[derive(Clone)]
+pub struct TokenDenylist {
+    redis: redis::Client,
+}
+
+impl TokenDenylist {
+    pub fn new(redis: redis::Client) -> Self {
+        Self { redis }
+    }
+
+    /// Reject the token with `jti` until `expires_at` (a Unix timestamp)
+    pub async fn revoke(&self, jti: &str, expires_at: i64) -> Result<()> {
+        let remaining = expires_at - chrono::Utc::now().timestamp();
+        if remaining <= 0 {
+            // Expired tokens are already rejected
+            return Ok(());
+        }
+
+        let mut conn = self.redis.get_multiplexed_async_connection().await
+            .map_err(|e| Error::Other(e.into()))?;
+        conn.set_ex::<_, _, ()>(Self::key(jti), 1, remaining as u64).await
+            .map_err(|e| Error::Other(e.into()))?;
+
+        Ok(())
+    }
+
+    /// Whether the token with `jti` has been revoked
+    pub async fn is_revoked(&self, jti: &str) -> Result<bool> {
+        let mut conn = self.redis.get_multiplexed_async_connection().await
+            .map_err(|e| Error::Other(e.into()))?;
+        let revoked: bool = conn.exists(Self::key(jti)).await
+            .map_err(|e| Error::Other(e.into()))?;
+
+        Ok(revoked)
+    }
+
+    /// Reject every token issued to `user_id` so far. Remembered for
+    /// `ttl_secs`, which should be the longest any token lives, after which
+    /// all of them have expired anyway.
+    pub async fn revoke_user(&self, user_id: &str, ttl_secs: u64) -> Result<()> {
+        let mut conn = self.redis.get_multiplexed_async_connection().await
+            .map_err(|e| Error::Other(e.into()))?;
+        redis::pipe()
+            .atomic()
+            .set_ex(Self::user_key(user_id), chrono::Utc::now().timestamp(), ttl_secs.max(1))
+            .ignore()
+            .del(Self::kept_key(user_id))
+            .ignore()
+            .query_async::<_, ()>(&mut conn)
+            .await
+            .map_err(|e| Error::Other(e.into()))?;
+
+        Ok(())
+    }
+
+    /// As `revoke_user`, except for the token with `keep_jti`, which keeps
+    /// working until it expires or is revoked on its own
+    pub async fn revoke_user_except(&self, user_id: &str, keep_jti: &str, ttl_secs: u64) -> Result<()> {
+        let mut conn = self.redis.get_multiplexed_async_connection().await
+            .map_err(|e| Error::Other(e.into()))?;
+        redis::pipe()
+            .atomic()
+            .set_ex(Self::user_key(user_id), chrono::Utc::now().timestamp(), ttl_secs.max(1))
+            .ignore()
+            .set_ex(Self::kept_key(user_id), keep_jti, ttl_secs.max(1))
+            .ignore()
+            .query_async::<_, ()>(&mut conn)
+            .await
+            .map_err(|e| Error::Other(e.into()))?;
+
+        Ok(())
+    }
+
+    /// Whether the token with `jti`, issued to `user_id` at `issued_at`, has
+    /// been revoked on its own or along with the rest of the user's tokens
+    pub async fn is_token_revoked(&self, jti: &str, user_id: &str, issued_at: i64) -> Result<bool> {
+        let mut conn = self.redis.get_multiplexed_async_connection().await
+            .map_err(|e| Error::Other(e.into()))?;
+        let (revoked, user_revoked_at, kept_jti): (bool, Option<i64>, Option<String>) = redis::pipe()
+            .exists(Self::key(jti))
+            .get(Self::user_key(user_id))
+            .get(Self::kept_key(user_id))
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| Error::Other(e.into()))?;
+
+        let revoked_with_user = user_revoked_at.is_some_and(|revoked_at| issued_at <= revoked_at)
+            && kept_jti.as_deref() != Some(jti);
+        Ok(revoked || revoked_with_user)
+    }
+
+    fn key(jti: &str) -> String {
+        format!("revoked_jti:{}", jti)
+    }
+
+    fn user_key(user_id: &str) -> String {
+        format!("revoked_user:{}", user_id)
+    }
+
+    fn kept_key(user_id: &str) -> String {
+        format!("revoked_user_kept:{}", user_id)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,209 @@
+//! Encryption of secrets at rest: wallet private keys and webhook signing
+//! secrets
+//!
+//! Secrets are sealed with AES-256-GCM under a key derived from the
+//! configured master secret (the "pepper"), which lives only in the server
+//! configuration, never in the database. Each seal uses a fresh random
+//! nonce, and a context naming what the secret belongs to (a wallet's public
+//! key, or a webhook's ID) is authenticated alongside the ciphertext, so a
+//! sealed value moved onto another row won't open.
+
+use crate::config::KeyVaultConfig;
+use crate::error::{Error, Result};
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use base64::{engine::general_purpose, Engine as _};
+use hkdf::Hkdf;
+use serde::Deserialize;
+use sha2::Sha256;
+use std::fmt;
+use zeroize::{Zeroize, Zeroizing};
+
+/// Names the sealing scheme, so stored keys can be migrated if it changes
+const SEALED_PREFIX: &str = "v1:";
+
+/// HKDF context separating the vault key from other uses of the secret.
+/// Named for the first thing sealed with it; everything sealed since is
+/// told apart by its context instead.
+const KEY_INFO: &[u8] = b"guardian-aa wallet private keys v1";
+
+const NONCE_LEN: usize = 12;
+
+/// A plaintext private key on its way into the vault. It never shows up in
+/// `Debug` output and is wiped when dropped.
+#[derive(Deserialize)]
+#[serde(transparent)]
+pub struct PrivateKey(String);
+
+impl PrivateKey {
+    pub fn new(key: impl Into<String>) -> Self {
+        Self(key.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PrivateKey(<redacted>)")
+    }
+}
+
+impl Drop for PrivateKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// Seals and opens secrets stored in the database
+#[derive(Clone)]
+pub struct KeyVault {
+    cipher: Aes256Gcm,
+}
+
+impl KeyVault {
+    /// A vault keyed by `master_secret`
+    pub fn new(master_secret: &str) -> Result<Self> {
+        if master_secret.is_empty() {
+            return Err(Error::Config("key_vault.master_secret is not set".to_string()));
+        }
+
+        let mut key = Zeroizing::new([0u8; 32]);
+        Hkdf::<Sha256>::new(None, master_secret.as_bytes())
+            .expand(KEY_INFO, key.as_mut())
+            .map_err(|_| Error::Internal)?;
+
+        Ok(Self { cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key.as_ref())) })
+    }
+
+    pub fn from_config(config: &KeyVaultConfig) -> Result<Self> {
+        Self::new(&config.master_secret)
+    }
+
+    /// Seal `secret` for `context`: a wallet's public key for its
+    /// `encrypted_private_key`, or what [`webhook_context`] gives for a
+    /// webhook's signing secret
+    pub fn encrypt(&self, context: &str, secret: &[u8]) -> Result<String> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self.cipher
+            .encrypt(&nonce, Payload { msg: secret, aad: context.as_bytes() })
+            .map_err(|_| Error::Internal)?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(format!("{}{}", SEALED_PREFIX, general_purpose::STANDARD.encode(sealed)))
+    }
+
+    /// Open a value sealed by [`KeyVault::encrypt`] for the same context.
+    /// The plaintext is wiped when dropped. A wrong master secret, another
+    /// context or a damaged value all fail the same way.
+    pub fn decrypt(&self, context: &str, sealed: &str) -> Result<Zeroizing<Vec<u8>>> {
+        let opened = sealed
+            .strip_prefix(SEALED_PREFIX)
+            .and_then(|encoded| general_purpose::STANDARD.decode(encoded).ok())
+            .filter(|bytes| bytes.len() > NONCE_LEN)
+            .and_then(|bytes| {
+                let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+                self.cipher
+                    .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: context.as_bytes() })
+                    .ok()
+            });
+
+        opened.map(Zeroizing::new).ok_or_else(|| {
+            tracing::warn!(context, "Sealed secret could not be decrypted");
+            Error::Internal
+        })
+    }
+}
+
+/// The context a webhook's signing secret is sealed for. Prefixed so it
+/// can never equal a wallet's public key.
+pub fn webhook_context(webhook_id: &uuid::Uuid) -> String {
+    format!("webhook:{}", webhook_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUBLIC_KEY: &str = "9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin";
+
+    #[test]
+    fn test_round_trip() {
+        let vault = KeyVault::new("pepper").unwrap();
+        let sealed = vault.encrypt(PUBLIC_KEY, b"secret key bytes").unwrap();
+
+        assert!(sealed.starts_with(SEALED_PREFIX));
+        assert!(!sealed.contains("secret"));
+        assert_eq!(vault.decrypt(PUBLIC_KEY, &sealed).unwrap().as_slice(), b"secret key bytes");
+    }
+
+    #[test]
+    fn test_each_seal_uses_a_fresh_nonce() {
+        let vault = KeyVault::new("pepper").unwrap();
+        let first = vault.encrypt(PUBLIC_KEY, b"secret key bytes").unwrap();
+        let second = vault.encrypt(PUBLIC_KEY, b"secret key bytes").unwrap();
+
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_wrong_master_secret_fails_cleanly() {
+        let sealed = KeyVault::new("pepper").unwrap().encrypt(PUBLIC_KEY, b"secret key bytes").unwrap();
+
+        let result = KeyVault::new("another pepper").unwrap().decrypt(PUBLIC_KEY, &sealed);
+        assert!(matches!(result, Err(Error::Internal)));
+    }
+
+    #[test]
+    fn test_sealed_key_is_bound_to_its_wallet() {
+        let vault = KeyVault::new("pepper").unwrap();
+        let sealed = vault.encrypt(PUBLIC_KEY, b"secret key bytes").unwrap();
+
+        assert!(vault.decrypt("0x742d35Cc6634C0532925a3b844Bc454e4438f44e", &sealed).is_err());
+    }
+
+    #[test]
+    fn test_damaged_values_fail_cleanly() {
+        let vault = KeyVault::new("pepper").unwrap();
+        let sealed = vault.encrypt(PUBLIC_KEY, b"secret key bytes").unwrap();
+        let mut tampered = sealed.clone().into_bytes();
+        let last = tampered.len() - 3;
+        tampered[last] = if tampered[last] == b'A' { b'B' } else { b'A' };
+
+        for damaged in [
+            String::from_utf8(tampered).unwrap(),
+            sealed.trim_start_matches(SEALED_PREFIX).to_string(),
+            "v1:not base64!".to_string(),
+            "v1:AAAA".to_string(),
+            String::new(),
+        ] {
+            assert!(vault.decrypt(PUBLIC_KEY, &damaged).is_err(), "{:?} opened", damaged);
+        }
+    }
+
+    #[test]
+    fn test_webhook_secret_is_bound_to_its_webhook() {
+        let vault = KeyVault::new("pepper").unwrap();
+        let webhook_id = uuid::Uuid::new_v4();
+        let sealed = vault.encrypt(&webhook_context(&webhook_id), b"whsec_abc").unwrap();
+
+        assert_eq!(vault.decrypt(&webhook_context(&webhook_id), &sealed).unwrap().as_slice(), b"whsec_abc");
+        assert!(vault.decrypt(&webhook_context(&uuid::Uuid::new_v4()), &sealed).is_err());
+    }
+
+    #[test]
+    fn test_private_key_is_redacted_from_debug() {
+        let key = PrivateKey::new("secret key bytes");
+        assert!(!format!("{:?}", Some(&key)).contains("secret"));
+    }
+
+    #[test]
+    fn test_empty_master_secret_is_rejected() {
+        assert!(matches!(KeyVault::new(""), Err(Error::Config(_))));
+    }
+}
\ No newline at end of file
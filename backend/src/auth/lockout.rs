@@ -0,0 +1,116 @@
+//! Redis-backed lockout of accounts after repeated failed logins
+
+use crate::{
+    config::AuthConfig,
+    error::{Error, Result},
+};
+use redis::AsyncCommands;
+use sha2::{Digest, Sha256};
+
+/// Failed login attempts per email. Once `max_attempts` failures land
+/// within the window, logins for that email are refused until the lockout
+/// expires. Emails are tracked whether or not an account exists, so a
+/// lockout says nothing about which addresses are registered.
+#[derive(Clone)]
+pub struct LoginLockout {
+    redis: redis::Client,
+    max_attempts: u32,
+    window_secs: u64,
+    lockout_secs: u64,
+}
+
+impl LoginLockout {
+    pub fn new(redis: redis::Client, config: &AuthConfig) -> Self {
+        Self {
+            redis,
+            max_attempts: config.login_max_failed_attempts,
+            window_secs: config.login_failure_window_secs,
+            lockout_secs: config.login_lockout_secs,
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        self.max_attempts > 0
+    }
+
+    /// Seconds until logins for `email` are accepted again, if locked
+    pub async fn locked_for(&self, email: &str) -> Result<Option<u64>> {
+        if !self.enabled() {
+            return Ok(None);
+        }
+
+        let mut conn = self.connection().await?;
+        let ttl: i64 = conn.ttl(Self::lockout_key(email)).await
+            .map_err(|e| Error::Other(e.into()))?;
+
+        // -2 means no lockout; -1 (no expiry) shouldn't happen but is still a lockout
+        Ok(match ttl {
+            -2 => None,
+            ttl => Some(ttl.max(1) as u64),
+        })
+    }
+
+    /// Count a failed login, locking the email out once it reaches the limit
+    pub async fn record_failure(&self, email: &str) -> Result<()> {
+        if !self.enabled() {
+            return Ok(());
+        }
+
+        let failures_key = Self::failures_key(email);
+        let mut conn = self.connection().await?;
+        let (failures,): (u32,) = redis::pipe()
+            .atomic()
+            .incr(&failures_key, 1)
+            .expire(&failures_key, self.window_secs as i64)
+            .ignore()
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| Error::Other(e.into()))?;
+
+        if failures >= self.max_attempts {
+            redis::pipe()
+                .atomic()
+                .set_ex(Self::lockout_key(email), 1, self.lockout_secs)
+                .ignore()
+                .del(&failures_key)
+                .ignore()
+                .query_async::<_, ()>(&mut conn)
+                .await
+                .map_err(|e| Error::Other(e.into()))?;
+            tracing::warn!(failures, lockout_secs = self.lockout_secs, "Locking out login after repeated failures");
+        }
+
+        Ok(())
+    }
+
+    /// Forget failed attempts after a successful login
+    pub async fn clear(&self, email: &str) -> Result<()> {
+        if !self.enabled() {
+            return Ok(());
+        }
+
+        let mut conn = self.connection().await?;
+        conn.del::<_, ()>(Self::failures_key(email)).await
+            .map_err(|e| Error::Other(e.into()))?;
+
+        Ok(())
+    }
+
+    async fn connection(&self) -> Result<redis::aio::MultiplexedConnection> {
+        self.redis.get_multiplexed_async_connection().await
+            .map_err(|e| Error::Other(e.into()))
+    }
+
+    fn failures_key(email: &str) -> String {
+        format!("login_failures:{}", Self::email_id(email))
+    }
+
+    fn lockout_key(email: &str) -> String {
+        format!("login_lockout:{}", Self::email_id(email))
+    }
+
+    /// Emails are hashed so the keys don't put addresses in Redis
+    fn email_id(email: &str) -> String {
+        hex::encode(Sha256::digest(email.trim().to_lowercase().as_bytes()))
+    }
+}
\ No newline at end of file
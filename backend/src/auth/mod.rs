@@ -1,17 +1,44 @@
 //! Authentication and authorization module
 
-use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
+pub mod account_status;
+pub mod denylist;
+pub mod key_vault;
+pub mod lockout;
+pub mod wallet_challenge;
+
+pub use account_status::AccountStatuses;
+pub use denylist::TokenDenylist;
+pub use key_vault::{webhook_context, KeyVault, PrivateKey};
+pub use lockout::LoginLockout;
+pub use wallet_challenge::{WalletChallenge, WalletChallenges};
+
+use argon2::{Algorithm, Argon2, Params, PasswordHash, PasswordHasher, PasswordVerifier, Version};
 use argon2::password_hash::{rand_core::OsRng, SaltString};
+use crate::config::AuthConfig;
 use crate::error::{Error, Result};
 
+/// Argon2id with the cost parameters from `config`
+pub fn argon2(config: &AuthConfig) -> Result<Argon2<'static>> {
+    let params = Params::new(config.argon2_memory_kib, config.argon2_iterations, config.argon2_parallelism, None)
+        .map_err(|e| Error::Config(format!("Invalid Argon2 parameters: {}", e)))?;
+
+    Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+}
+
 /// Hash a password using Argon2
 pub fn hash_password(password: &str) -> Result<String> {
+    hash_password_with(&Argon2::default(), password)
+}
+
+/// Hash a password with the given Argon2 instance. The parameters are
+/// encoded in the result, so verifying it never depends on the current
+/// configuration.
+pub fn hash_password_with(argon2: &Argon2, password: &str) -> Result<String> {
     let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
     
     let password_hash = argon2
         .hash_password(password.as_bytes(), &salt)
-        .map_err(|e| Error::AuthenticationFailed)?;
+        .map_err(|_| Error::Internal)?;
     
     Ok(password_hash.to_string())
 }
@@ -58,6 +85,32 @@ mod tests {
         assert!(!verify_password("wrong_password", &hash).unwrap());
     }
 
+    #[test]
+    fn test_custom_argon2_params_are_encoded() {
+        let mut config = crate::config::Config::default().auth;
+        config.argon2_memory_kib = 8192;
+        config.argon2_iterations = 3;
+        config.argon2_parallelism = 2;
+
+        let hash = hash_password_with(&argon2(&config).unwrap(), "test_password_123").unwrap();
+        assert!(hash.starts_with("$argon2id$v=19$m=8192,t=3,p=2$"));
+
+        // Verification reads the parameters from the hash, whatever the
+        // current configuration
+        assert!(verify_password("test_password_123", &hash).unwrap());
+        let default_hash = hash_password("test_password_123").unwrap();
+        let parsed = PasswordHash::new(&default_hash).unwrap();
+        assert!(argon2(&config).unwrap().verify_password(b"test_password_123", &parsed).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_argon2_params_are_rejected() {
+        let mut config = crate::config::Config::default().auth;
+        config.argon2_parallelism = 0;
+
+        assert!(matches!(argon2(&config), Err(Error::Config(_))));
+    }
+
     #[test]
     fn test_secure_token_generation() {
         let token1 = generate_secure_token(32);
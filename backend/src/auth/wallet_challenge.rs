@@ -0,0 +1,137 @@
+//! Redis-backed challenges for signing in with a Solana wallet
+
+use crate::config::AuthConfig;
+use crate::error::{Error, Result};
+use chrono::{DateTime, Duration, SecondsFormat, SubsecRound, Utc};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+
+/// A message for a wallet to sign to prove it holds its key
+#[derive(Debug, Clone, Serialize)]
+pub struct WalletChallenge {
+    pub public_key: String,
+    pub nonce: String,
+    /// Exact text the wallet must sign
+    pub message: String,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl WalletChallenge {
+    /// Whether the challenge can be answered at `now`
+    pub fn is_valid_at(&self, now: DateTime<Utc>) -> bool {
+        self.issued_at <= now && now < self.expires_at
+    }
+
+    /// Whether `signature` is the wallet's ed25519 signature of the message
+    pub fn is_signed_by(&self, public_key: &Pubkey, signature: &Signature) -> bool {
+        signature.verify(public_key.as_ref(), self.message.as_bytes())
+    }
+}
+
+/// When an outstanding challenge was issued and stops being answerable,
+/// as stored with its nonce
+#[derive(Serialize, Deserialize)]
+struct Validity {
+    issued_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+}
+
+/// Nonces handed out to wallets, each good for one sign-in until it
+/// expires
+#[derive(Clone)]
+pub struct WalletChallenges {
+    redis: redis::Client,
+    ttl_secs: u64,
+    domain: String,
+    uri: String,
+}
+
+impl WalletChallenges {
+    pub fn new(redis: redis::Client, config: &AuthConfig) -> Self {
+        Self {
+            redis,
+            ttl_secs: config.wallet_challenge_ttl.max(1) as u64,
+            domain: config.wallet_sign_in_domain.clone(),
+            uri: config.wallet_sign_in_uri.clone(),
+        }
+    }
+
+    /// Hand out a fresh nonce for the wallet at `public_key`
+    pub async fn issue(&self, public_key: &Pubkey) -> Result<WalletChallenge> {
+        let nonce = hex::encode(rand::random::<[u8; 32]>());
+        // Whole seconds, as the message shows them
+        let issued_at = Utc::now().trunc_subsecs(0);
+        let validity = Validity { issued_at, expires_at: issued_at + Duration::seconds(self.ttl_secs as i64) };
+
+        let mut conn = self.connection().await?;
+        let value = serde_json::to_string(&validity).map_err(|e| Error::Other(e.into()))?;
+        conn.set_ex::<_, _, ()>(Self::key(public_key, &nonce), value, self.ttl_secs).await
+            .map_err(|e| Error::Other(e.into()))?;
+
+        Ok(self.challenge(public_key, nonce, validity))
+    }
+
+    /// The challenge issued to the wallet with `nonce`, while it is still
+    /// outstanding. Its message names this service's domain and URI, so a
+    /// signature of a message another site asked for won't match it.
+    pub async fn outstanding(&self, public_key: &Pubkey, nonce: &str) -> Result<Option<WalletChallenge>> {
+        let mut conn = self.connection().await?;
+        let value: Option<String> = conn.get(Self::key(public_key, nonce)).await
+            .map_err(|e| Error::Other(e.into()))?;
+
+        Ok(value
+            .and_then(|value| serde_json::from_str::<Validity>(&value).ok())
+            .map(|validity| self.challenge(public_key, nonce.to_string(), validity)))
+    }
+
+    /// Use up the nonce, returning whether it was outstanding. Nonces that
+    /// expired, were never issued or were already used return `false`.
+    pub async fn consume(&self, public_key: &Pubkey, nonce: &str) -> Result<bool> {
+        let mut conn = self.connection().await?;
+        let removed: u32 = conn.del(Self::key(public_key, nonce)).await
+            .map_err(|e| Error::Other(e.into()))?;
+
+        Ok(removed == 1)
+    }
+
+    /// Text a wallet signs to answer the challenge with `nonce`, in the
+    /// Sign-In With Solana layout
+    pub fn message(
+        &self,
+        public_key: &Pubkey,
+        nonce: &str,
+        issued_at: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+    ) -> String {
+        format!(
+            "{} wants you to sign in with your Solana account:\n{}\n\nSign in to Guardian-AA\n\nURI: {}\nVersion: 1\nNonce: {}\nIssued At: {}\nExpiration Time: {}",
+            self.domain,
+            public_key,
+            self.uri,
+            nonce,
+            issued_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+            expires_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+        )
+    }
+
+    fn challenge(&self, public_key: &Pubkey, nonce: String, validity: Validity) -> WalletChallenge {
+        WalletChallenge {
+            public_key: public_key.to_string(),
+            message: self.message(public_key, &nonce, validity.issued_at, validity.expires_at),
+            nonce,
+            issued_at: validity.issued_at,
+            expires_at: validity.expires_at,
+        }
+    }
+
+    async fn connection(&self) -> Result<redis::aio::MultiplexedConnection> {
+        self.redis.get_multiplexed_async_connection().await
+            .map_err(|e| Error::Other(e.into()))
+    }
+
+    fn key(public_key: &Pubkey, nonce: &str) -> String {
+        format!("wallet_challenge:{}:{}", public_key, nonce)
+    }
+}
\ No newline at end of file
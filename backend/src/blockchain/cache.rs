@@ -0,0 +1,111 @@
+//! Short-lived cache for wallet balance lookups
+
+use super::solana::Balance;
+use crate::{config::BalanceCacheConfig, error::Result};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// Entries kept before expired ones are swept out
+const SWEEP_THRESHOLD: usize = 10_000;
+
+/// A balance together with when it was fetched from the RPC
+#[derive(Debug, Clone)]
+pub struct CachedBalance {
+    pub balance: Balance,
+    pub fetched_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    cached: CachedBalance,
+    expires_at: Instant,
+}
+
+/// Caches balances per address. Empty balances (new or unfunded wallets)
+/// get their own, usually shorter, TTL so a wallet that has just been
+/// funded shows up quickly.
+#[derive(Debug, Clone)]
+pub struct BalanceCache {
+    entries: Arc<RwLock<HashMap<String, Entry>>>,
+    positive_ttl: Duration,
+    negative_ttl: Duration,
+}
+
+impl BalanceCache {
+    pub fn new(config: &BalanceCacheConfig) -> Self {
+        Self {
+            entries: Arc::new(RwLock::new(HashMap::new())),
+            positive_ttl: Duration::from_secs(config.positive_ttl_secs),
+            negative_ttl: Duration::from_secs(config.negative_ttl_secs),
+        }
+    }
+
+    /// Return the cached balance for `address`, or fetch and cache it.
+    ///
+    /// With `fresh` set the cache is skipped, but the fetched balance still
+    /// replaces the cached one. Errors are never cached.
+    pub async fn get_or_fetch<F, Fut>(&self, address: &str, fresh: bool, fetch: F) -> Result<CachedBalance>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Balance>>,
+    {
+        if !fresh {
+            if let Some(cached) = self.get(address) {
+                return Ok(cached);
+            }
+        }
+
+        let cached = CachedBalance {
+            balance: fetch().await?,
+            fetched_at: Utc::now(),
+        };
+        self.insert(address, cached.clone());
+
+        Ok(cached)
+    }
+
+    /// Forget the balance cached for `address`, after something changed it
+    pub fn invalidate(&self, address: &str) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.remove(address);
+        }
+    }
+
+    fn get(&self, address: &str) -> Option<CachedBalance> {
+        let entries = self.entries.read().ok()?;
+        entries.get(address)
+            .filter(|entry| entry.expires_at > Instant::now())
+            .map(|entry| entry.cached.clone())
+    }
+
+    fn insert(&self, address: &str, cached: CachedBalance) {
+        let ttl = if cached.balance.is_empty() {
+            self.negative_ttl
+        } else {
+            self.positive_ttl
+        };
+        if ttl.is_zero() {
+            return;
+        }
+
+        if let Ok(mut entries) = self.entries.write() {
+            let now = Instant::now();
+            if entries.len() >= SWEEP_THRESHOLD {
+                entries.retain(|_, entry| entry.expires_at > now);
+            }
+            entries.insert(address.to_string(), Entry {
+                cached,
+                expires_at: now + ttl,
+            });
+        }
+    }
+}
+
+impl Default for BalanceCache {
+    fn default() -> Self {
+        Self::new(&BalanceCacheConfig::default())
+    }
+}
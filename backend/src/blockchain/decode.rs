@@ -0,0 +1,293 @@
+//! Human-readable breakdown of a raw transaction
+//!
+//! Decoding only reads the transaction; nothing is simulated or sent. Each
+//! instruction is listed with its program and accounts, and those of well
+//! known programs are also described as actions. Programs the decoder
+//! doesn't recognise are flagged so clients can warn before signing.
+
+use super::token_accounts::{EncodedAccountMeta, ASSOCIATED_TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID};
+use base64::{Engine as _, engine::general_purpose};
+use serde::Serialize;
+use solana_sdk::{
+    compute_budget,
+    message::{v0::LoadedAddresses, VersionedMessage},
+    pubkey,
+    pubkey::Pubkey,
+    system_instruction::SystemInstruction,
+    system_program,
+    transaction::{Transaction, TransactionVersion, VersionedTransaction},
+};
+use spl_token::instruction::TokenInstruction;
+
+/// SPL Token-2022 program, which shares the original program's base
+/// instructions
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// SPL Memo program
+pub const MEMO_PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+/// `SetComputeUnitLimit` and `SetComputeUnitPrice` in the Compute Budget
+/// program
+const SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+const SET_COMPUTE_UNIT_PRICE: u8 = 3;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedTransaction {
+    /// `"legacy"` or the version number, e.g. `0`
+    pub version: TransactionVersion,
+    pub signatures: Vec<String>,
+    /// Whether every required signature is present and valid
+    pub fully_signed: bool,
+    pub fee_payer: Option<String>,
+    pub recent_blockhash: String,
+    pub instructions: Vec<DecodedInstruction>,
+    /// Programs invoked that the decoder doesn't recognise, in order of
+    /// first use
+    pub unknown_programs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DecodedInstruction {
+    pub index: usize,
+    pub program_id: String,
+    /// Name of the program, if the decoder recognises it
+    pub program: Option<&'static str>,
+    pub accounts: Vec<EncodedAccountMeta>,
+    /// Base64 instruction data
+    pub data: String,
+    /// What the instruction does, if the decoder understands it
+    pub action: Option<InstructionAction>,
+}
+
+/// An instruction of a well known program, described by its effect
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InstructionAction {
+    SolTransfer {
+        from: String,
+        to: String,
+        lamports: u64,
+    },
+    CreateAccount {
+        from: String,
+        new_account: String,
+        lamports: u64,
+        space: u64,
+        owner: String,
+    },
+    /// Amounts are in the token's base units
+    TokenTransfer {
+        source: String,
+        destination: String,
+        authority: String,
+        amount: u64,
+        /// Only known for `TransferChecked`
+        mint: Option<String>,
+        decimals: Option<u8>,
+    },
+    CreateAssociatedTokenAccount {
+        payer: String,
+        account: String,
+        owner: String,
+        mint: String,
+    },
+    SetComputeUnitLimit {
+        units: u32,
+    },
+    SetComputeUnitPrice {
+        micro_lamports: u64,
+    },
+    Memo {
+        text: String,
+    },
+}
+
+/// Name of a program the decoder recognises
+fn program_name(program_id: &Pubkey) -> Option<&'static str> {
+    if *program_id == system_program::id() {
+        Some("system")
+    } else if *program_id == TOKEN_PROGRAM_ID {
+        Some("spl_token")
+    } else if *program_id == TOKEN_2022_PROGRAM_ID {
+        Some("spl_token_2022")
+    } else if *program_id == ASSOCIATED_TOKEN_PROGRAM_ID {
+        Some("spl_associated_token_account")
+    } else if *program_id == compute_budget::id() {
+        Some("compute_budget")
+    } else if *program_id == MEMO_PROGRAM_ID {
+        Some("spl_memo")
+    } else {
+        None
+    }
+}
+
+/// Every account a message references, in the order its instructions
+/// index them: the static keys, then those loaded from address lookup
+/// tables, writable before readonly
+pub fn account_keys(message: &VersionedMessage, loaded: &LoadedAddresses) -> Vec<Pubkey> {
+    message.static_account_keys().iter()
+        .chain(&loaded.writable)
+        .chain(&loaded.readonly)
+        .copied()
+        .collect()
+}
+
+/// Whether account `index` of `message` may be written, going by the
+/// message header alone for static keys and by the lookup they came
+/// through for loaded ones
+fn is_writable(message: &VersionedMessage, loaded: &LoadedAddresses, index: usize) -> bool {
+    let header = message.header();
+    let static_keys = message.static_account_keys().len();
+    let signers = header.num_required_signatures as usize;
+    if index >= static_keys {
+        index - static_keys < loaded.writable.len()
+    } else if index < signers {
+        index < signers.saturating_sub(header.num_readonly_signed_accounts as usize)
+    } else {
+        index < static_keys.saturating_sub(header.num_readonly_unsigned_accounts as usize)
+    }
+}
+
+impl DecodedTransaction {
+    /// Decode a legacy or v0 transaction. `loaded` holds the addresses a v0
+    /// transaction's lookup tables resolve to; accounts missing from it are
+    /// left out of the instructions they appear in.
+    pub fn new(transaction: &VersionedTransaction, loaded: &LoadedAddresses) -> Self {
+        let message = &transaction.message;
+        let header = message.header();
+        let account_keys = account_keys(message, loaded);
+        let mut unknown_programs = Vec::new();
+
+        let instructions = message.instructions().iter()
+            .enumerate()
+            .map(|(index, instruction)| {
+                let program_id = account_keys
+                    .get(instruction.program_id_index as usize)
+                    .copied()
+                    .unwrap_or_default();
+                let accounts: Vec<Pubkey> = instruction.accounts.iter()
+                    .filter_map(|&account| account_keys.get(account as usize).copied())
+                    .collect();
+
+                let program = program_name(&program_id);
+                if program.is_none() && !unknown_programs.contains(&program_id.to_string()) {
+                    unknown_programs.push(program_id.to_string());
+                }
+
+                DecodedInstruction {
+                    index,
+                    program_id: program_id.to_string(),
+                    program,
+                    accounts: instruction.accounts.iter()
+                        .filter_map(|&account| {
+                            let pubkey = account_keys.get(account as usize)?;
+                            Some(EncodedAccountMeta {
+                                pubkey: pubkey.to_string(),
+                                is_signer: (account as usize) < header.num_required_signatures as usize,
+                                is_writable: is_writable(message, loaded, account as usize),
+                            })
+                        })
+                        .collect(),
+                    data: general_purpose::STANDARD.encode(&instruction.data),
+                    action: decode_action(&program_id, &accounts, &instruction.data),
+                }
+            })
+            .collect();
+
+        Self {
+            version: transaction.version(),
+            signatures: transaction.signatures.iter().map(ToString::to_string).collect(),
+            fully_signed: transaction.signatures.len() == header.num_required_signatures as usize
+                && transaction.verify_with_results().into_iter().all(|verified| verified),
+            fee_payer: message.static_account_keys().first().map(ToString::to_string),
+            recent_blockhash: message.recent_blockhash().to_string(),
+            instructions,
+            unknown_programs,
+        }
+    }
+}
+
+impl From<&VersionedTransaction> for DecodedTransaction {
+    /// Decode without resolving lookup tables, which is enough for anything
+    /// that only looks at program ids and instruction data
+    fn from(transaction: &VersionedTransaction) -> Self {
+        Self::new(transaction, &LoadedAddresses::default())
+    }
+}
+
+impl From<&Transaction> for DecodedTransaction {
+    fn from(transaction: &Transaction) -> Self {
+        Self::new(&VersionedTransaction::from(transaction.clone()), &LoadedAddresses::default())
+    }
+}
+
+/// Describe an instruction of a recognised program, given the accounts it
+/// names in order
+fn decode_action(program_id: &Pubkey, accounts: &[Pubkey], data: &[u8]) -> Option<InstructionAction> {
+    let account = |position: usize| accounts.get(position).map(ToString::to_string);
+
+    if *program_id == system_program::id() {
+        match bincode::deserialize::<SystemInstruction>(data).ok()? {
+            SystemInstruction::Transfer { lamports } => Some(InstructionAction::SolTransfer {
+                from: account(0)?,
+                to: account(1)?,
+                lamports,
+            }),
+            SystemInstruction::CreateAccount { lamports, space, owner } => Some(InstructionAction::CreateAccount {
+                from: account(0)?,
+                new_account: account(1)?,
+                lamports,
+                space,
+                owner: owner.to_string(),
+            }),
+            _ => None,
+        }
+    } else if *program_id == TOKEN_PROGRAM_ID || *program_id == TOKEN_2022_PROGRAM_ID {
+        match TokenInstruction::unpack(data).ok()? {
+            TokenInstruction::Transfer { amount } => Some(InstructionAction::TokenTransfer {
+                source: account(0)?,
+                destination: account(1)?,
+                authority: account(2)?,
+                amount,
+                mint: None,
+                decimals: None,
+            }),
+            TokenInstruction::TransferChecked { amount, decimals } => Some(InstructionAction::TokenTransfer {
+                source: account(0)?,
+                destination: account(2)?,
+                authority: account(3)?,
+                amount,
+                mint: account(1),
+                decimals: Some(decimals),
+            }),
+            _ => None,
+        }
+    } else if *program_id == ASSOCIATED_TOKEN_PROGRAM_ID {
+        // `Create` (empty data or 0) and `CreateIdempotent` (1) share accounts
+        match data.first() {
+            None | Some(0) | Some(1) => Some(InstructionAction::CreateAssociatedTokenAccount {
+                payer: account(0)?,
+                account: account(1)?,
+                owner: account(2)?,
+                mint: account(3)?,
+            }),
+            _ => None,
+        }
+    } else if *program_id == compute_budget::id() {
+        match data.split_first()? {
+            (&SET_COMPUTE_UNIT_LIMIT, units) => Some(InstructionAction::SetComputeUnitLimit {
+                units: u32::from_le_bytes(units.try_into().ok()?),
+            }),
+            (&SET_COMPUTE_UNIT_PRICE, price) => Some(InstructionAction::SetComputeUnitPrice {
+                micro_lamports: u64::from_le_bytes(price.try_into().ok()?),
+            }),
+            _ => None,
+        }
+    } else if *program_id == MEMO_PROGRAM_ID {
+        Some(InstructionAction::Memo {
+            text: std::str::from_utf8(data).ok()?.to_string(),
+        })
+    } else {
+        None
+    }
+}
\ No newline at end of file
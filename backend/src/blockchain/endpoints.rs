@@ -0,0 +1,171 @@
+//! Ordered RPC endpoints with failover
+
+use super::retry::{is_retryable, retry_with_backoff, RetryPolicy, NODE_UNHEALTHY};
+use crate::{
+    error::{Error, Result},
+    telemetry,
+};
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    rpc_client::RpcClient,
+    rpc_request::RpcError,
+};
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::Instrument;
+
+struct Endpoint {
+    url: String,
+    client: RpcClient,
+    healthy: AtomicBool,
+}
+
+/// Health of one configured RPC endpoint
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct EndpointHealth {
+    pub url: String,
+    pub healthy: bool,
+    pub current: bool,
+}
+
+/// RPC endpoints in priority order. Calls go to the current endpoint and
+/// move on to the next one when it can't be reached or returns a server
+/// error; the endpoint that answers becomes current.
+#[derive(Clone)]
+pub struct RpcEndpoints {
+    endpoints: Arc<Vec<Endpoint>>,
+    current: Arc<AtomicUsize>,
+}
+
+impl RpcEndpoints {
+    pub fn new(urls: &[String], commitment: CommitmentConfig) -> Result<Self> {
+        if urls.is_empty() {
+            return Err(Error::Config("At least one Solana RPC URL is required".to_string()));
+        }
+
+        let endpoints = urls.iter()
+            .map(|url| Endpoint {
+                url: url.clone(),
+                client: RpcClient::new_with_commitment(url.clone(), commitment),
+                healthy: AtomicBool::new(true),
+            })
+            .collect();
+
+        Ok(Self {
+            endpoints: Arc::new(endpoints),
+            current: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    /// URL of the endpoint calls currently go to
+    pub fn current(&self) -> &str {
+        &self.endpoints[self.current.load(Ordering::Relaxed)].url
+    }
+
+    pub fn health(&self) -> Vec<EndpointHealth> {
+        let current = self.current.load(Ordering::Relaxed);
+        self.endpoints.iter()
+            .enumerate()
+            .map(|(index, endpoint)| EndpointHealth {
+                url: endpoint.url.clone(),
+                healthy: endpoint.healthy.load(Ordering::Relaxed),
+                current: index == current,
+            })
+            .collect()
+    }
+
+    /// Run `call`, the RPC `method`, against the current endpoint, retrying
+    /// it per `policy` and failing over to the following endpoints in turn.
+    /// Each call is traced in a `solana_rpc` span with the endpoint that
+    /// last handled it, the total latency and the outcome.
+    pub async fn call<T>(
+        &self,
+        method: &'static str,
+        policy: &RetryPolicy,
+        call: impl Fn(&RpcClient) -> std::result::Result<T, ClientError>,
+    ) -> std::result::Result<T, ClientError> {
+        let span = tracing::info_span!(
+            "solana_rpc",
+            method,
+            endpoint = tracing::field::Empty,
+            latency_ms = tracing::field::Empty,
+            outcome = tracing::field::Empty,
+        );
+        let started = Instant::now();
+
+        let result = self.call_with_failover(policy, call).instrument(span.clone()).await;
+
+        span.record("latency_ms", started.elapsed().as_millis() as u64);
+        match &result {
+            Ok(_) => {
+                span.record("outcome", "ok");
+            }
+            Err(e) => {
+                span.record("outcome", "error");
+                span.in_scope(|| tracing::warn!(method, error = %e, "Solana RPC call failed"));
+            }
+        }
+        telemetry::record_rpc_call(method, result.is_ok());
+        result
+    }
+
+    async fn call_with_failover<T>(
+        &self,
+        policy: &RetryPolicy,
+        call: impl Fn(&RpcClient) -> std::result::Result<T, ClientError>,
+    ) -> std::result::Result<T, ClientError> {
+        let count = self.endpoints.len();
+        let start = self.current.load(Ordering::Relaxed);
+        let mut last_error = None;
+
+        for offset in 0..count {
+            let index = (start + offset) % count;
+            let endpoint = &self.endpoints[index];
+            let is_last = offset + 1 == count;
+            tracing::Span::current().record("endpoint", endpoint.url.as_str());
+
+            // Don't spend retries on an endpoint that is down while others remain
+            let result = retry_with_backoff(
+                policy,
+                || call(&endpoint.client),
+                |e| is_retryable(e) && (is_last || !should_fail_over(e)),
+            ).await;
+
+            match result {
+                Ok(value) => {
+                    endpoint.healthy.store(true, Ordering::Relaxed);
+                    if index != start {
+                        self.current.store(index, Ordering::Relaxed);
+                        tracing::info!(endpoint = %endpoint.url, "Switched Solana RPC endpoint");
+                    }
+                    return Ok(value);
+                }
+                Err(e) if should_fail_over(&e) => {
+                    endpoint.healthy.store(false, Ordering::Relaxed);
+                    tracing::warn!(endpoint = %endpoint.url, error = %e, "Solana RPC endpoint failed");
+                    last_error = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(last_error.expect("at least one endpoint was tried"))
+    }
+}
+
+/// Whether an error means the endpoint itself is unavailable, rather than
+/// the request being bad
+fn should_fail_over(error: &ClientError) -> bool {
+    match error.kind() {
+        ClientErrorKind::Io(_) => true,
+        ClientErrorKind::Reqwest(e) => {
+            e.is_connect()
+                || e.is_timeout()
+                || e.status().is_some_and(|status| status.is_server_error())
+        }
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => *code == NODE_UNHEALTHY,
+        _ => false,
+    }
+}
@@ -0,0 +1,281 @@
+//! Ethereum blockchain client implementation
+//!
+//! Covers the same ground as [`SolanaClient`](super::SolanaClient) for EVM
+//! wallets: balances, ERC-20 balances, EIP-1559 fee estimates, submission
+//! and status checks.
+
+use super::fees::{self, PriorityLevel};
+use crate::error::{Error, Result};
+use ethers::{
+    abi::{self, ParamType, Token},
+    providers::{Http, Middleware, Provider},
+    types::{transaction::eip2718::TypedTransaction, Address, BlockNumber, Bytes, TransactionRequest, H256, U256},
+    utils::format_units,
+};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Decimal places of ether
+const ETHER_DECIMALS: u32 = 18;
+
+/// Recent blocks whose priority fees the recommendation is based on
+const FEE_HISTORY_BLOCKS: u64 = 10;
+
+/// Priority fee offered when recent blocks paid none (1 gwei)
+const MIN_PRIORITY_FEE_WEI: u64 = 1_000_000_000;
+
+/// ERC-20 `balanceOf(address)` and `decimals()` selectors
+const BALANCE_OF_SELECTOR: [u8; 4] = [0x70, 0xa0, 0x82, 0x31];
+const DECIMALS_SELECTOR: [u8; 4] = [0x31, 0x3c, 0xe5, 0x67];
+
+/// Native balance of an address. Amounts are decimal strings because wei
+/// values don't fit in a u64.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EthBalance {
+    pub wei: String,
+    pub ether: String,
+}
+
+/// Balance of an ERC-20 token held by an address
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Erc20Balance {
+    pub contract: String,
+    /// Amount in the token's smallest unit
+    pub amount: String,
+    pub decimals: u8,
+    pub amount_formatted: String,
+}
+
+/// EIP-1559 fee estimate; per-gas values are in wei
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EthFeeEstimate {
+    pub gas_limit: u64,
+    /// Base fee expected for the next block
+    pub base_fee_per_gas: String,
+    pub max_priority_fee_per_gas: String,
+    /// Twice the base fee plus the tip, so the transaction still lands if
+    /// the base fee keeps rising for a few blocks
+    pub max_fee_per_gas: String,
+    /// Most the transaction can cost, `gas_limit * max_fee_per_gas`
+    pub max_fee_wei: String,
+    pub max_fee_ether: String,
+}
+
+/// Where a transaction stands on chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EthTransactionState {
+    /// Known to the node but not yet in a block
+    Pending,
+    Succeeded,
+    /// Included in a block but reverted
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EthTransactionStatus {
+    pub hash: String,
+    pub state: EthTransactionState,
+    pub block_number: Option<u64>,
+    /// Blocks on top of and including the one holding the transaction
+    pub confirmations: u64,
+}
+
+/// Ethereum blockchain client
+#[derive(Clone)]
+pub struct EthereumClient {
+    provider: Provider<Http>,
+}
+
+impl EthereumClient {
+    /// Create a new Ethereum client. No connection is made until the first call.
+    pub fn new(rpc_url: &str) -> Result<Self> {
+        let provider = Provider::<Http>::try_from(rpc_url)
+            .map_err(|e| Error::Config(format!("Invalid Ethereum RPC URL: {}", e)))?;
+
+        Ok(Self { provider })
+    }
+
+    /// Get the ether balance of an address
+    pub async fn get_balance(&self, address: &str) -> Result<EthBalance> {
+        let address = parse_address(address)?;
+        let wei = self.provider.get_balance(address, None)
+            .await
+            .map_err(|e| Error::Blockchain(format!("Failed to get balance: {}", e)))?;
+
+        Ok(EthBalance {
+            wei: wei.to_string(),
+            ether: format_wei(wei),
+        })
+    }
+
+    /// Get the balance `owner` holds of the ERC-20 token at `token`
+    pub async fn get_token_balance(&self, token: &str, owner: &str) -> Result<Erc20Balance> {
+        let token = parse_address(token)?;
+        let owner = parse_address(owner)?;
+
+        let balance_of = [&BALANCE_OF_SELECTOR[..], &abi::encode(&[Token::Address(owner)])].concat();
+        let amount = self.call_uint(token, balance_of).await?;
+        let decimals = self.call_uint(token, DECIMALS_SELECTOR.to_vec()).await?;
+        if decimals > U256::from(u8::MAX) {
+            return Err(Error::Blockchain(format!("Token reports {} decimals", decimals)));
+        }
+        let decimals = decimals.as_u32() as u8;
+
+        Ok(Erc20Balance {
+            contract: format!("{:?}", token),
+            amount: amount.to_string(),
+            decimals,
+            amount_formatted: format_amount(amount, decimals as u32),
+        })
+    }
+
+    /// Estimate the gas and EIP-1559 fees for `transaction`, tipping at
+    /// `priority_level`'s percentile of what recent blocks paid
+    pub async fn estimate_fee(&self, transaction: &TypedTransaction, priority_level: PriorityLevel) -> Result<EthFeeEstimate> {
+        let gas_limit = self.provider.estimate_gas(transaction, None)
+            .await
+            .map_err(|e| Error::Blockchain(format!("Failed to estimate gas: {}", e)))?;
+
+        let history = self.provider
+            .fee_history(FEE_HISTORY_BLOCKS, BlockNumber::Latest, &[priority_level.percentile() as f64])
+            .await
+            .map_err(|e| Error::Blockchain(format!("Failed to get fee history: {}", e)))?;
+
+        // The last entry is the base fee of the block after the newest one
+        let base_fee = history.base_fee_per_gas.last().copied().unwrap_or_default();
+        let recent_tips: Vec<u64> = history.reward.iter()
+            .filter_map(|rewards| rewards.first())
+            .map(|tip| (*tip).min(U256::from(u64::MAX)).as_u64())
+            .collect();
+        let priority_fee = U256::from(fees::percentile(&recent_tips, 50).max(MIN_PRIORITY_FEE_WEI));
+        let max_fee_per_gas = base_fee * 2 + priority_fee;
+        let max_fee = gas_limit * max_fee_per_gas;
+
+        Ok(EthFeeEstimate {
+            gas_limit: gas_limit.min(U256::from(u64::MAX)).as_u64(),
+            base_fee_per_gas: base_fee.to_string(),
+            max_priority_fee_per_gas: priority_fee.to_string(),
+            max_fee_per_gas: max_fee_per_gas.to_string(),
+            max_fee_wei: max_fee.to_string(),
+            max_fee_ether: format_wei(max_fee),
+        })
+    }
+
+    /// Broadcast a signed, hex-encoded transaction without waiting for it
+    /// to be mined
+    pub async fn submit_transaction(&self, transaction_data: &str) -> Result<EthTransactionStatus> {
+        let bytes = hex::decode(transaction_data.trim_start_matches("0x"))
+            .map_err(|_| Error::BadRequest("Invalid transaction data format".to_string()))?;
+
+        let pending = self.provider.send_raw_transaction(Bytes::from(bytes))
+            .await
+            .map_err(|e| Error::TransactionFailed(format!("Failed to send transaction: {}", e)))?;
+
+        Ok(EthTransactionStatus {
+            hash: format!("{:?}", pending.tx_hash()),
+            state: EthTransactionState::Pending,
+            block_number: None,
+            confirmations: 0,
+        })
+    }
+
+    /// Get transaction status, or `None` if the node doesn't know the transaction
+    pub async fn get_transaction_status(&self, hash: &str) -> Result<Option<EthTransactionStatus>> {
+        let hash = H256::from_str(hash)
+            .map_err(|e| Error::Blockchain(format!("Invalid transaction hash: {}", e)))?;
+
+        let receipt = self.provider.get_transaction_receipt(hash)
+            .await
+            .map_err(|e| Error::Blockchain(format!("Failed to get transaction receipt: {}", e)))?;
+
+        let Some(receipt) = receipt else {
+            // No receipt yet; the transaction may still be waiting in the mempool
+            let transaction = self.provider.get_transaction(hash)
+                .await
+                .map_err(|e| Error::Blockchain(format!("Failed to get transaction: {}", e)))?;
+            return Ok(transaction.map(|_| EthTransactionStatus {
+                hash: format!("{:?}", hash),
+                state: EthTransactionState::Pending,
+                block_number: None,
+                confirmations: 0,
+            }));
+        };
+
+        let block_number = receipt.block_number.map(|number| number.as_u64());
+        let confirmations = match block_number {
+            Some(block_number) => self.get_current_block().await?.saturating_sub(block_number) + 1,
+            None => 0,
+        };
+        // Receipts from before Byzantium carry no status
+        let state = match receipt.status {
+            Some(status) if status.is_zero() => EthTransactionState::Failed,
+            _ => EthTransactionState::Succeeded,
+        };
+
+        Ok(Some(EthTransactionStatus {
+            hash: format!("{:?}", hash),
+            state,
+            block_number,
+            confirmations,
+        }))
+    }
+
+    /// Validate an Ethereum address
+    pub fn validate_address(&self, address: &str) -> Result<bool> {
+        Ok(parse_address(address).is_ok())
+    }
+
+    /// Get the current block number
+    pub async fn get_current_block(&self) -> Result<u64> {
+        let block = self.provider.get_block_number()
+            .await
+            .map_err(|e| Error::Blockchain(format!("Failed to get current block: {}", e)))?;
+
+        Ok(block.as_u64())
+    }
+
+    /// Health check - verify connection to the Ethereum network
+    pub async fn health_check(&self) -> Result<bool> {
+        Ok(self.provider.get_block_number().await.is_ok())
+    }
+
+    /// Call a contract function that returns a single uint
+    async fn call_uint(&self, contract: Address, data: Vec<u8>) -> Result<U256> {
+        let call: TypedTransaction = TransactionRequest::new().to(contract).data(data).into();
+        let output = self.provider.call(&call, None)
+            .await
+            .map_err(|e| Error::Blockchain(format!("Contract call failed: {}", e)))?;
+
+        abi::decode(&[ParamType::Uint(256)], &output)
+            .ok()
+            .and_then(|tokens| tokens.into_iter().next())
+            .and_then(Token::into_uint)
+            .ok_or_else(|| Error::Blockchain("Unexpected response from token contract".to_string()))
+    }
+}
+
+fn parse_address(address: &str) -> Result<Address> {
+    let hex = address.strip_prefix("0x")
+        .ok_or_else(|| Error::Validation("Invalid Ethereum address".to_string()))?;
+    if hex.len() != 40 {
+        return Err(Error::Validation("Invalid Ethereum address".to_string()));
+    }
+    Address::from_str(hex).map_err(|_| Error::Validation("Invalid Ethereum address".to_string()))
+}
+
+/// Format a wei amount in ether
+pub fn format_wei(wei: U256) -> String {
+    format_amount(wei, ETHER_DECIMALS)
+}
+
+/// Format `amount` of a currency with `decimals` places, dropping trailing
+/// zeros (`1500000000000000000` wei is `"1.5"`)
+pub fn format_amount(amount: U256, decimals: u32) -> String {
+    let formatted = format_units(amount, decimals).unwrap_or_else(|_| amount.to_string());
+    if formatted.contains('.') {
+        formatted.trim_end_matches('0').trim_end_matches('.').to_string()
+    } else {
+        formatted
+    }
+}
\ No newline at end of file
@@ -0,0 +1,149 @@
+//! Priority fee recommendations from recent network activity
+
+use super::decode::{DecodedTransaction, InstructionAction};
+use super::token_accounts::EncodedInstruction;
+use serde::{Deserialize, Serialize};
+use solana_sdk::{compute_budget::ComputeBudgetInstruction, transaction::VersionedTransaction};
+
+/// Compute units the runtime budgets per instruction when a transaction
+/// doesn't request a limit itself
+pub const DEFAULT_COMPUTE_UNITS_PER_INSTRUCTION: u32 = 200_000;
+
+/// Most compute units a transaction can use
+pub const MAX_COMPUTE_UNITS: u32 = 1_400_000;
+
+/// How eagerly a transaction should compete for block space
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriorityLevel {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl PriorityLevel {
+    /// Percentile of recent prioritization fees to pay at this level
+    pub fn percentile(&self) -> u8 {
+        match self {
+            PriorityLevel::Low => 50,
+            PriorityLevel::Medium => 75,
+            PriorityLevel::High => 95,
+        }
+    }
+}
+
+/// Nearest-rank percentile of `fees`, or 0 when there are none
+pub fn percentile(fees: &[u64], percentile: u8) -> u64 {
+    if fees.is_empty() {
+        return 0;
+    }
+
+    let mut sorted = fees.to_vec();
+    sorted.sort_unstable();
+
+    let percentile = percentile.min(100) as usize;
+    let rank = (percentile * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// Recommended compute unit price in micro-lamports for `level`, given the
+/// prioritization fees paid in recent slots
+pub fn recommended_compute_unit_price(recent_fees: &[u64], level: PriorityLevel) -> u64 {
+    percentile(recent_fees, level.percentile())
+}
+
+/// Lamports paid for `compute_units` at `micro_lamports_per_cu`, rounded up
+/// as the runtime does
+pub fn priority_fee_lamports(micro_lamports_per_cu: u64, compute_units: u32) -> u64 {
+    let micro_lamports = micro_lamports_per_cu as u128 * compute_units as u128;
+    micro_lamports.div_ceil(1_000_000).min(u64::MAX as u128) as u64
+}
+
+/// Compute unit limit the runtime applies to a transaction with
+/// `instructions` instructions and no explicit limit
+pub fn default_compute_unit_limit(instructions: usize) -> u32 {
+    (instructions as u32)
+        .saturating_mul(DEFAULT_COMPUTE_UNITS_PER_INSTRUCTION)
+        .min(MAX_COMPUTE_UNITS)
+}
+
+/// Compute budget a transaction sets for itself through the Compute Budget
+/// program
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestedComputeBudget {
+    pub unit_limit: Option<u32>,
+    /// Micro-lamports per compute unit
+    pub unit_price: Option<u64>,
+    /// Instructions other than compute budget ones
+    #[serde(skip)]
+    pub other_instructions: usize,
+}
+
+impl RequestedComputeBudget {
+    /// Read from a legacy or v0 transaction. Compute budget instructions
+    /// always name their program among the static keys, so lookup tables
+    /// don't need resolving.
+    pub fn of(transaction: &VersionedTransaction) -> Self {
+        let mut budget = Self::default();
+        for instruction in DecodedTransaction::from(transaction).instructions {
+            match instruction.action {
+                Some(InstructionAction::SetComputeUnitLimit { units }) => budget.unit_limit = Some(units),
+                Some(InstructionAction::SetComputeUnitPrice { micro_lamports }) => budget.unit_price = Some(micro_lamports),
+                _ if instruction.program == Some("compute_budget") => {}
+                _ => budget.other_instructions += 1,
+            }
+        }
+        budget
+    }
+
+    /// Compute units the transaction is charged priority fees for
+    pub fn effective_unit_limit(&self) -> u32 {
+        self.unit_limit
+            .unwrap_or_else(|| default_compute_unit_limit(self.other_instructions))
+            .min(MAX_COMPUTE_UNITS)
+    }
+}
+
+/// Compute budget a transaction should set to land at a priority level
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComputeBudgetRecommendation {
+    pub priority_level: PriorityLevel,
+    /// Recommended compute unit price in micro-lamports
+    pub compute_unit_price: u64,
+    pub compute_unit_limit: u32,
+    /// Priority fee at the recommended price and limit
+    pub priority_fee_lamports: u64,
+    /// What the transaction sets now
+    pub current: RequestedComputeBudget,
+    /// Whether the transaction pays less than the recommended price, so it
+    /// should be rebuilt and re-signed before it's submitted
+    pub needs_update: bool,
+    /// Compute budget instructions to put at the front of the transaction,
+    /// replacing any it already has
+    pub instructions: Vec<EncodedInstruction>,
+}
+
+/// Recommend a compute budget for a transaction that currently sets
+/// `current`, given the prioritization fees paid in recent slots
+pub fn recommend_compute_budget(
+    current: RequestedComputeBudget,
+    recent_fees: &[u64],
+    level: PriorityLevel,
+) -> ComputeBudgetRecommendation {
+    let compute_unit_price = recommended_compute_unit_price(recent_fees, level);
+    let compute_unit_limit = current.effective_unit_limit();
+
+    ComputeBudgetRecommendation {
+        priority_level: level,
+        compute_unit_price,
+        compute_unit_limit,
+        priority_fee_lamports: priority_fee_lamports(compute_unit_price, compute_unit_limit),
+        current,
+        needs_update: current.unit_price.unwrap_or(0) < compute_unit_price,
+        instructions: vec![
+            EncodedInstruction::from(&ComputeBudgetInstruction::set_compute_unit_limit(compute_unit_limit)),
+            EncodedInstruction::from(&ComputeBudgetInstruction::set_compute_unit_price(compute_unit_price)),
+        ],
+    }
+}
\ No newline at end of file
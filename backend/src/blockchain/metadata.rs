@@ -0,0 +1,48 @@
+//! Metaplex token metadata accounts
+
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+/// Metaplex Token Metadata program
+pub const TOKEN_METADATA_PROGRAM_ID: &str = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s";
+
+/// Name, symbol and off-chain JSON URI stored in a metadata account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnChainMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+/// Address of the metadata account for `mint`
+pub fn metadata_address(mint: &Pubkey) -> Pubkey {
+    let program_id = Pubkey::from_str(TOKEN_METADATA_PROGRAM_ID).expect("valid program id");
+    let seeds: [&[u8]; 3] = [b"metadata", program_id.as_ref(), mint.as_ref()];
+    Pubkey::find_program_address(&seeds, &program_id).0
+}
+
+/// Parse the name, symbol and URI out of a metadata account's data.
+///
+/// The account is Borsh encoded as a key byte, the update authority and
+/// mint, then the three strings. Metaplex pads the strings with NULs to
+/// fixed lengths, which are stripped here.
+pub fn parse_metadata_account(data: &[u8]) -> Option<OnChainMetadata> {
+    let mut offset = 1 + 32 + 32;
+    let name = read_string(data, &mut offset)?;
+    let symbol = read_string(data, &mut offset)?;
+    let uri = read_string(data, &mut offset)?;
+
+    Some(OnChainMetadata { name, symbol, uri })
+}
+
+fn read_string(data: &[u8], offset: &mut usize) -> Option<String> {
+    let len_bytes: [u8; 4] = data.get(*offset..*offset + 4)?.try_into().ok()?;
+    let len = u32::from_le_bytes(len_bytes) as usize;
+    *offset += 4;
+
+    let bytes = data.get(*offset..offset.checked_add(len)?)?;
+    *offset += len;
+
+    let value = String::from_utf8_lossy(bytes);
+    Some(value.trim_end_matches('\0').trim().to_string())
+}
@@ -1,5 +1,21 @@
 //! Blockchain integration module
 
+pub mod cache;
+pub mod decode;
+pub mod endpoints;
+pub mod ethereum;
+pub mod fees;
+pub mod metadata;
+pub mod retry;
 pub mod solana;
+pub mod token_accounts;
+pub mod verifier;
 
-pub use solana::SolanaClient;
+pub use cache::{BalanceCache, CachedBalance};
+pub use decode::{DecodedTransaction, InstructionAction};
+pub use endpoints::EndpointHealth;
+pub use ethereum::{EthBalance, EthFeeEstimate, EthTransactionState, EthTransactionStatus, EthereumClient};
+pub use fees::{ComputeBudgetRecommendation, PriorityLevel, RequestedComputeBudget};
+pub use retry::{retry_with_backoff, RetryPolicy};
+pub use solana::{ConfirmationPolicy, ConfirmationStatus, SolanaClient, TransactionResult, TransactionSimulation};
+pub use verifier::{GuardianVerifier, OnChainVerification};
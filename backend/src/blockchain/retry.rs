@@ -0,0 +1,92 @@
+//! Retry with exponential backoff for RPC calls
+
+use crate::config::RetryConfig;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    rpc_request::RpcError,
+};
+use std::time::Duration;
+
+/// JSON-RPC error code returned by a node that is behind or unhealthy
+pub(crate) const NODE_UNHEALTHY: i64 = -32005;
+
+/// Longest wait between two attempts
+const MAX_DELAY: Duration = Duration::from_secs(10);
+
+/// How often and how patiently a failed call is retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first
+    pub max_attempts: u32,
+    /// Wait before the first retry; doubled for each one after
+    pub base_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// A single attempt with no retries
+    pub const NONE: RetryPolicy = RetryPolicy {
+        max_attempts: 1,
+        base_delay: Duration::ZERO,
+    };
+
+    /// Wait before retry number `retry` (starting at 1)
+    fn delay(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(MAX_DELAY)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::from(&RetryConfig::default())
+    }
+}
+
+impl From<&RetryConfig> for RetryPolicy {
+    fn from(config: &RetryConfig) -> Self {
+        Self {
+            max_attempts: config.max_attempts.max(1),
+            base_delay: Duration::from_millis(config.base_delay_ms),
+        }
+    }
+}
+
+/// Run `operation` until it succeeds, fails with an error `is_retryable`
+/// rejects, or the policy's attempts run out. The last error is returned.
+pub async fn retry_with_backoff<T, E>(
+    policy: &RetryPolicy,
+    mut operation: impl FnMut() -> Result<T, E>,
+    is_retryable: impl Fn(&E) -> bool,
+) -> Result<T, E>
+where
+    E: std::fmt::Display,
+{
+    let mut attempt = 1;
+    loop {
+        match operation() {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < policy.max_attempts && is_retryable(&e) => {
+                let delay = policy.delay(attempt);
+                tracing::debug!(attempt, delay_ms = delay.as_millis() as u64, error = %e, "Retrying RPC call");
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Whether an RPC error is transient: rate limiting, timeouts, connection
+/// failures, server errors and unhealthy nodes
+pub fn is_retryable(error: &ClientError) -> bool {
+    match error.kind() {
+        ClientErrorKind::Io(_) => true,
+        ClientErrorKind::Reqwest(e) => {
+            e.is_timeout()
+                || e.is_connect()
+                || e.status().is_some_and(|status| status.as_u16() == 429 || status.is_server_error())
+        }
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => *code == NODE_UNHEALTHY,
+        _ => false,
+    }
+}
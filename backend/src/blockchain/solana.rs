@@ -1,16 +1,28 @@
 //! Solana blockchain client implementation
 
+use super::cache::{BalanceCache, CachedBalance};
+use super::decode::{self, DecodedTransaction};
+use super::metadata::{self, OnChainMetadata};
+use super::endpoints::{EndpointHealth, RpcEndpoints};
+use super::fees::{self, ComputeBudgetRecommendation, PriorityLevel, RequestedComputeBudget};
+use super::retry::RetryPolicy;
+use super::token_accounts;
+use super::verifier::{GuardianVerifier, OnChainVerification};
+use crate::config::ConfirmationConfig;
 use crate::error::{Error, Result};
-use solana_client::rpc_client::RpcClient;
+use solana_client::{client_error::ClientError, rpc_client::RpcClient, rpc_config::RpcSimulateTransactionConfig};
 use solana_sdk::{
+    address_lookup_table::state::AddressLookupTable,
     commitment_config::CommitmentConfig,
+    message::{v0::LoadedAddresses, VersionedMessage, MESSAGE_VERSION_PREFIX},
     pubkey::Pubkey,
+    short_vec,
     signature::Signature,
-    transaction::Transaction,
+    transaction::VersionedTransaction,
     native_token::LAMPORTS_PER_SOL,
 };
 use std::str::FromStr;
-use std::sync::Arc;
+use std::time::{Duration, Instant};
 use serde::{Deserialize, Serialize};
 use base64::{Engine as _, engine::general_purpose};
 
@@ -21,6 +33,13 @@ pub struct Balance {
     pub token_balances: Vec<TokenBalance>,
 }
 
+impl Balance {
+    /// Whether the wallet holds nothing, as for a new or unfunded account
+    pub fn is_empty(&self) -> bool {
+        self.sol_balance == 0 && self.token_balances.iter().all(|tb| tb.amount == 0)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenBalance {
     pub mint: String,
@@ -31,27 +50,103 @@ pub struct TokenBalance {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransactionFeeEstimate {
+    /// Signature fee for the transaction
     pub fee_lamports: u64,
     pub fee_sol: f64,
+    /// Recommended compute unit price in micro-lamports
+    pub compute_unit_price: u64,
+    /// Compute units the priority fee is charged for
+    pub compute_unit_limit: u32,
+    /// Priority fee at the recommended price
+    pub priority_fee_lamports: u64,
+    pub priority_fee_sol: f64,
+    /// The transaction pays less than the recommended compute unit price;
+    /// see [`SolanaClient::recommend_compute_budget`]
+    pub needs_compute_budget_update: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransactionResult {
     pub signature: String,
     pub slot: u64,
-    pub confirmation_status: String,
+    pub confirmation_status: ConfirmationStatus,
+}
+
+/// Outcome of simulating a transaction against current chain state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionSimulation {
+    /// Program error the transaction would fail with, if any
+    pub error: Option<String>,
+    pub logs: Vec<String>,
+    pub units_consumed: Option<u64>,
+}
+
+impl TransactionSimulation {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// How far a transaction has progressed on chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfirmationStatus {
+    Processed,
+    Confirmed,
+    Finalized,
+    /// Not seen at the configured commitment before the confirmation timeout
+    TimedOut,
+}
+
+impl ConfirmationStatus {
+    /// Whether the transaction has reached at least `confirmed`
+    pub fn is_confirmed(&self) -> bool {
+        matches!(self, ConfirmationStatus::Confirmed | ConfirmationStatus::Finalized)
+    }
+}
+
+/// How long to wait for a submitted transaction to confirm
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfirmationPolicy {
+    pub timeout: Duration,
+    pub poll_interval: Duration,
+}
+
+impl Default for ConfirmationPolicy {
+    fn default() -> Self {
+        Self::from(&ConfirmationConfig::default())
+    }
+}
+
+impl From<&ConfirmationConfig> for ConfirmationPolicy {
+    fn from(config: &ConfirmationConfig) -> Self {
+        Self {
+            timeout: Duration::from_secs(config.timeout_secs),
+            poll_interval: Duration::from_millis(config.poll_interval_ms),
+        }
+    }
 }
 
 /// Solana blockchain client
 #[derive(Clone)]
 pub struct SolanaClient {
-    rpc_client: Arc<RpcClient>,
+    endpoints: RpcEndpoints,
     commitment: CommitmentConfig,
+    balance_cache: BalanceCache,
+    retry_policy: RetryPolicy,
+    confirmation_policy: ConfirmationPolicy,
+    verifier: Option<GuardianVerifier>,
 }
 
 impl SolanaClient {
     /// Create a new Solana client
     pub fn new(rpc_url: &str, commitment: &str) -> Result<Self> {
+        Self::with_endpoints(&[rpc_url.to_string()], commitment)
+    }
+
+    /// Create a client over several RPC endpoints in priority order, failing
+    /// over to the next one when the current endpoint is unavailable
+    pub fn with_endpoints(rpc_urls: &[String], commitment: &str) -> Result<Self> {
         let commitment_config = match commitment {
             "processed" => CommitmentConfig::processed(),
             "confirmed" => CommitmentConfig::confirmed(),
@@ -59,22 +154,74 @@ impl SolanaClient {
             _ => CommitmentConfig::confirmed(),
         };
 
-        let rpc_client = RpcClient::new_with_commitment(rpc_url.to_string(), commitment_config);
-
         Ok(Self {
-            rpc_client: Arc::new(rpc_client),
+            endpoints: RpcEndpoints::new(rpc_urls, commitment_config)?,
             commitment: commitment_config,
+            balance_cache: BalanceCache::default(),
+            retry_policy: RetryPolicy::default(),
+            confirmation_policy: ConfirmationPolicy::default(),
+            verifier: None,
         })
     }
 
+    /// URL of the RPC endpoint currently in use
+    pub fn current_endpoint(&self) -> &str {
+        self.endpoints.current()
+    }
+
+    /// Health of every configured RPC endpoint
+    pub fn endpoint_health(&self) -> Vec<EndpointHealth> {
+        self.endpoints.health()
+    }
+
+    /// Make a call to the RPC `method` with retries and endpoint failover
+    async fn rpc<T>(
+        &self,
+        method: &'static str,
+        call: impl Fn(&RpcClient) -> std::result::Result<T, ClientError>,
+    ) -> std::result::Result<T, ClientError> {
+        self.endpoints.call(method, &self.retry_policy, call).await
+    }
+
+    /// Retry transient RPC failures according to `policy`
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Wait for submitted transactions according to `policy`
+    pub fn with_confirmation_policy(mut self, policy: ConfirmationPolicy) -> Self {
+        self.confirmation_policy = policy;
+        self
+    }
+
+    /// Verify proofs on chain with `verifier`
+    pub fn with_verifier(mut self, verifier: GuardianVerifier) -> Self {
+        self.verifier = Some(verifier);
+        self
+    }
+
+    /// Use `cache` for [`SolanaClient::get_balance_cached`] lookups
+    pub fn with_balance_cache(mut self, cache: BalanceCache) -> Self {
+        self.balance_cache = cache;
+        self
+    }
+
+    /// Get a wallet's balance, served from the balance cache unless `fresh` is set
+    pub async fn get_balance_cached(&self, wallet_address: &str, fresh: bool) -> Result<CachedBalance> {
+        self.balance_cache
+            .get_or_fetch(wallet_address, fresh, || self.get_balance(wallet_address))
+            .await
+    }
+
     /// Get SOL balance for a wallet (simplified version)
     pub async fn get_balance(&self, wallet_address: &str) -> Result<Balance> {
         let pubkey = Pubkey::from_str(wallet_address)
             .map_err(|e| Error::Blockchain(format!("Invalid wallet address: {}", e)))?;
 
         // Get SOL balance
-        let sol_balance = self.rpc_client
-            .get_balance_with_commitment(&pubkey, self.commitment)
+        let sol_balance = self.rpc("getBalance", |client| client.get_balance_with_commitment(&pubkey, self.commitment))
+            .await
             .map_err(|e| Error::Blockchain(format!("Failed to get SOL balance: {}", e)))?
             .value;
 
@@ -90,69 +237,280 @@ impl SolanaClient {
         })
     }
 
-    /// Submit a transaction to the Solana network
+    /// Get a token's Metaplex metadata, or `None` if the mint has none
+    pub async fn get_token_metadata(&self, mint: &str) -> Result<Option<OnChainMetadata>> {
+        let mint = Pubkey::from_str(mint)
+            .map_err(|e| Error::Blockchain(format!("Invalid mint address: {}", e)))?;
+
+        let address = metadata::metadata_address(&mint);
+        let account = self.rpc("getAccountInfo", |client| client.get_account_with_commitment(&address, self.commitment))
+            .await
+            .map_err(|e| Error::Blockchain(format!("Failed to get token metadata: {}", e)))?
+            .value;
+
+        Ok(account.and_then(|account| metadata::parse_metadata_account(&account.data)))
+    }
+
+    /// Submit a transaction to the Solana network and wait for it to reach
+    /// the configured commitment. If it hasn't by the confirmation timeout,
+    /// the result carries [`ConfirmationStatus::TimedOut`] so the caller can
+    /// keep tracking the signature.
     pub async fn submit_transaction(&self, transaction_data: &str) -> Result<TransactionResult> {
         // Deserialize the transaction from base64 or hex
         let transaction = self.deserialize_transaction(transaction_data)?;
 
-        // Submit the transaction
-        let signature = self.rpc_client
-            .send_and_confirm_transaction_with_spinner_and_commitment(
-                &transaction,
-                self.commitment,
-            )
+        // It's already signed so nothing can be added, but without a price
+        // it's likely to be dropped while the network is congested
+        if RequestedComputeBudget::of(&transaction).unit_price.is_none() {
+            tracing::warn!("Submitting a transaction that sets no compute unit price");
+        }
+
+        // Resending the same signed transaction is safe: the network
+        // deduplicates it by signature
+        let signature = self.rpc("sendTransaction", |client| client.send_transaction(&transaction))
+            .await
             .map_err(|e| Error::TransactionFailed(format!("Failed to submit transaction: {}", e)))?;
 
-        // Get current slot as we can't get transaction details immediately
-        let slot = self.get_current_slot().await.unwrap_or(0);
+        self.await_confirmation(signature).await
+    }
 
-        Ok(TransactionResult {
-            signature: signature.to_string(),
-            slot,
-            confirmation_status: format!("{:?}", self.commitment.commitment),
+    /// Ask the cluster's faucet for `lamports` to be sent to `address` and
+    /// wait for the airdrop to confirm. Mainnet has no faucet, so this is
+    /// refused when any configured endpoint looks like mainnet.
+    pub async fn request_airdrop(&self, address: &Pubkey, lamports: u64) -> Result<TransactionResult> {
+        if self.is_mainnet() {
+            return Err(Error::BadRequest("Airdrops are only available on devnet and testnet".to_string()));
+        }
+
+        // Not retried: a request that timed out may still have been granted
+        let signature = self.endpoints.call("requestAirdrop", &RetryPolicy::NONE, |client| client.request_airdrop(address, lamports))
+            .await
+            .map_err(|e| Error::Blockchain(format!("Failed to request airdrop: {}", e)))?;
+
+        let result = self.await_confirmation(signature).await?;
+        self.balance_cache.invalidate(&address.to_string());
+        Ok(result)
+    }
+
+    /// Whether the client is configured for mainnet, going by its endpoint
+    /// URLs
+    pub fn is_mainnet(&self) -> bool {
+        self.endpoints.health().iter().any(|endpoint| endpoint.url.to_ascii_lowercase().contains("mainnet"))
+    }
+
+    /// Poll `signature` until it reaches the configured commitment, or the
+    /// confirmation timeout passes and it's reported as timed out
+    async fn await_confirmation(&self, signature: Signature) -> Result<TransactionResult> {
+        let deadline = Instant::now() + self.confirmation_policy.timeout;
+        let mut slot = 0;
+        loop {
+            if let Some(result) = self.signature_status(&signature).await? {
+                slot = result.slot;
+                if self.meets_commitment(result.confirmation_status) {
+                    return Ok(result);
+                }
+            }
+
+            if Instant::now() + self.confirmation_policy.poll_interval > deadline {
+                tracing::warn!(%signature, "Timed out waiting for transaction confirmation");
+                return Ok(TransactionResult {
+                    signature: signature.to_string(),
+                    slot,
+                    confirmation_status: ConfirmationStatus::TimedOut,
+                });
+            }
+            tokio::time::sleep(self.confirmation_policy.poll_interval).await;
+        }
+    }
+
+    /// Simulate a transaction without broadcasting it, to find out whether
+    /// it would fail and how much compute it uses
+    pub async fn simulate_transaction(&self, transaction_data: &str) -> Result<TransactionSimulation> {
+        let transaction = self.deserialize_transaction(transaction_data)?;
+
+        let simulation = self.rpc("simulateTransaction", |client| client.simulate_transaction(&transaction))
+            .await
+            .map_err(|e| Error::Blockchain(format!("Failed to simulate transaction: {}", e)))?
+            .value;
+
+        Ok(TransactionSimulation {
+            error: simulation.err.map(|err| err.to_string()),
+            logs: simulation.logs.unwrap_or_default(),
+            units_consumed: simulation.units_consumed,
+        })
+    }
+
+    /// Have the Guardian program verify a proof, by simulating its
+    /// `VerifyProof` instruction. Nothing is signed or sent, so nothing is
+    /// paid; a rejected proof is reported in the result, not as an error.
+    pub async fn verify_proof_on_chain(&self, proof_bytes: &[u8], public_inputs: &[u8]) -> Result<OnChainVerification> {
+        let verifier = self.verifier
+            .ok_or_else(|| Error::BadRequest("On-chain proof verification is not enabled".to_string()))?;
+        let transaction = VersionedTransaction::from(verifier.verify_transaction(proof_bytes, public_inputs)?);
+
+        // Unsigned, with a placeholder blockhash the node swaps for its own
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            commitment: Some(self.commitment),
+            ..Default::default()
+        };
+        let simulation = self.rpc("simulateTransaction", |client| client.simulate_transaction_with_config(&transaction, config.clone()))
+            .await
+            .map_err(|e| Error::Blockchain(format!("Failed to simulate proof verification: {}", e)))?
+            .value;
+
+        Ok(OnChainVerification {
+            verified: simulation.err.is_none(),
+            error: simulation.err.map(|err| err.to_string()),
+            compute_units: simulation.units_consumed,
+            logs: simulation.logs.unwrap_or_default(),
         })
     }
 
-    /// Estimate transaction fee
-    pub async fn estimate_fee(&self, transaction_data: &str) -> Result<TransactionFeeEstimate> {
-        // For Solana, we can estimate based on the transaction size and current fee rates
+    /// Token accounts the transaction's SPL transfers send to that don't
+    /// exist, which makes those transfers fail
+    pub async fn missing_transfer_destinations(&self, transaction_data: &str) -> Result<Vec<Pubkey>> {
+        let transaction = self.deserialize_transaction(transaction_data)?;
+        let loaded = self.load_addresses(&transaction.message).await?;
+        let account_keys = decode::account_keys(&transaction.message, &loaded);
+        let destinations = token_accounts::transfer_destinations(transaction.message.instructions(), &account_keys);
+        if destinations.is_empty() {
+            return Ok(destinations);
+        }
+
+        let accounts = self.rpc("getMultipleAccounts", |client| client.get_multiple_accounts_with_commitment(&destinations, self.commitment))
+            .await
+            .map_err(|e| Error::Blockchain(format!("Failed to get token accounts: {}", e)))?
+            .value;
+
+        Ok(destinations.into_iter()
+            .zip(accounts)
+            .filter(|(_, account)| account.is_none())
+            .map(|(destination, _)| destination)
+            .collect())
+    }
+
+    /// Whether an account exists at `address`
+    pub async fn account_exists(&self, address: &Pubkey) -> Result<bool> {
+        let account = self.rpc("getAccountInfo", |client| client.get_account_with_commitment(address, self.commitment))
+            .await
+            .map_err(|e| Error::Blockchain(format!("Failed to get account: {}", e)))?
+            .value;
+
+        Ok(account.is_some())
+    }
+
+    /// Lamports an account of `data_len` bytes must hold to be rent exempt
+    pub async fn minimum_balance_for_rent_exemption(&self, data_len: usize) -> Result<u64> {
+        self.rpc("getMinimumBalanceForRentExemption", |client| client.get_minimum_balance_for_rent_exemption(data_len))
+            .await
+            .map_err(|e| Error::Blockchain(format!("Failed to get rent exemption minimum: {}", e)))
+    }
+
+    /// Whether `status` satisfies the client's commitment level
+    pub fn meets_commitment(&self, status: ConfirmationStatus) -> bool {
+        if self.commitment.is_finalized() {
+            status == ConfirmationStatus::Finalized
+        } else if self.commitment.is_confirmed() {
+            status.is_confirmed()
+        } else {
+            status != ConfirmationStatus::TimedOut
+        }
+    }
+
+    /// Look up a signature's status, or `None` if the network hasn't seen it.
+    /// A transaction that executed with an error is reported as failed.
+    async fn signature_status(&self, signature: &Signature) -> Result<Option<TransactionResult>> {
+        let statuses = self.rpc("getSignatureStatuses", |client| client.get_signature_statuses(std::slice::from_ref(signature)))
+            .await
+            .map_err(|e| Error::Blockchain(format!("Failed to get transaction status: {}", e)))?
+            .value;
+
+        let Some(status) = statuses.into_iter().next().flatten() else {
+            return Ok(None);
+        };
+
+        if let Some(err) = &status.err {
+            return Err(Error::TransactionFailed(format!("Transaction {} failed: {}", signature, err)));
+        }
+
+        let confirmation_status = if status.satisfies_commitment(CommitmentConfig::finalized()) {
+            ConfirmationStatus::Finalized
+        } else if status.satisfies_commitment(CommitmentConfig::confirmed()) {
+            ConfirmationStatus::Confirmed
+        } else {
+            ConfirmationStatus::Processed
+        };
+
+        Ok(Some(TransactionResult {
+            signature: signature.to_string(),
+            slot: status.slot,
+            confirmation_status,
+        }))
+    }
+
+    /// Estimate transaction fee, including the priority fee needed to land
+    /// at `priority_level` given recent fees paid for the same accounts
+    pub async fn estimate_fee(&self, transaction_data: &str, priority_level: PriorityLevel) -> Result<TransactionFeeEstimate> {
         let transaction = self.deserialize_transaction(transaction_data)?;
 
-        // Calculate fee based on transaction signatures - simplified approach
-        let fee_lamports = self.rpc_client
-            .get_fee_for_message(&transaction.message)
+        let fee_lamports = self.endpoints.call("getFeeForMessage", &RetryPolicy::NONE, |client| match &transaction.message {
+            VersionedMessage::Legacy(message) => client.get_fee_for_message(message),
+            VersionedMessage::V0(message) => client.get_fee_for_message(message),
+        })
+            .await
             .map_err(|e| Error::Blockchain(format!("Failed to calculate fee: {}", e)))?;
 
-        let fee_sol = fee_lamports as f64 / LAMPORTS_PER_SOL as f64;
+        let recent_fees = self.recent_prioritization_fees(&transaction).await?;
+        let budget = fees::recommend_compute_budget(RequestedComputeBudget::of(&transaction), &recent_fees, priority_level);
 
         Ok(TransactionFeeEstimate {
             fee_lamports,
-            fee_sol,
+            fee_sol: fee_lamports as f64 / LAMPORTS_PER_SOL as f64,
+            compute_unit_price: budget.compute_unit_price,
+            compute_unit_limit: budget.compute_unit_limit,
+            priority_fee_lamports: budget.priority_fee_lamports,
+            priority_fee_sol: budget.priority_fee_lamports as f64 / LAMPORTS_PER_SOL as f64,
+            needs_compute_budget_update: budget.needs_update,
         })
     }
 
+    /// Recommend the compute unit price and limit a transaction should set
+    /// to land at `priority_level`, with the instructions setting them.
+    ///
+    /// Submitted transactions are already signed, so the backend can't add
+    /// these itself; clients rebuild and re-sign when `needs_update` is set.
+    pub async fn recommend_compute_budget(&self, transaction_data: &str, priority_level: PriorityLevel) -> Result<ComputeBudgetRecommendation> {
+        let transaction = self.deserialize_transaction(transaction_data)?;
+        let recent_fees = self.recent_prioritization_fees(&transaction).await?;
+
+        Ok(fees::recommend_compute_budget(RequestedComputeBudget::of(&transaction), &recent_fees, priority_level))
+    }
+
+    /// Fees paid in recent slots for transactions locking the same accounts,
+    /// including any a v0 transaction loads from lookup tables
+    async fn recent_prioritization_fees(&self, transaction: &VersionedTransaction) -> Result<Vec<u64>> {
+        let loaded = self.load_addresses(&transaction.message).await?;
+        let account_keys = decode::account_keys(&transaction.message, &loaded);
+        let recent_fees = self.endpoints.call("getRecentPrioritizationFees", &RetryPolicy::NONE, |client| {
+            client.get_recent_prioritization_fees(&account_keys)
+        })
+            .await
+            .map_err(|e| Error::Blockchain(format!("Failed to get recent prioritization fees: {}", e)))?
+            .into_iter()
+            .map(|fee| fee.prioritization_fee)
+            .collect();
+
+        Ok(recent_fees)
+    }
+
     /// Get transaction status
     pub async fn get_transaction_status(&self, signature: &str) -> Result<Option<TransactionResult>> {
         let signature = Signature::from_str(signature)
             .map_err(|e| Error::Blockchain(format!("Invalid signature: {}", e)))?;
 
-        // Use get_signature_status to check if transaction exists
-        let status = self.rpc_client
-            .get_signature_status(&signature)
-            .map_err(|e| Error::Blockchain(format!("Failed to get transaction status: {}", e)))?;
-
-        if let Some(_) = status {
-            // Transaction exists, get current slot
-            let slot = self.get_current_slot().await.unwrap_or(0);
-            
-            Ok(Some(TransactionResult {
-                signature: signature.to_string(),
-                slot,
-                confirmation_status: format!("{:?}", self.commitment.commitment),
-            }))
-        } else {
-            Ok(None)
-        }
+        self.signature_status(&signature).await
     }
 
     /// Validate a Solana address
@@ -165,35 +523,86 @@ impl SolanaClient {
 
     /// Get current slot
     pub async fn get_current_slot(&self) -> Result<u64> {
-        let slot = self.rpc_client
-            .get_slot_with_commitment(self.commitment)
+        let slot = self.rpc("getSlot", |client| client.get_slot_with_commitment(self.commitment))
+            .await
             .map_err(|e| Error::Blockchain(format!("Failed to get current slot: {}", e)))?;
 
         Ok(slot)
     }
 
-    /// Helper function to deserialize transaction data
-    fn deserialize_transaction(&self, transaction_data: &str) -> Result<Transaction> {
-        // Try to deserialize from base64 first
-        if let Ok(bytes) = general_purpose::STANDARD.decode(transaction_data) {
-            if let Ok(transaction) = bincode::deserialize::<Transaction>(&bytes) {
-                return Ok(transaction);
-            }
+    /// Break a base64 or hex encoded transaction down into its instructions
+    /// and what they do, without simulating or submitting it. Accounts a v0
+    /// transaction loads from lookup tables are resolved against the
+    /// tables' current contents.
+    pub async fn decode_transaction(&self, transaction_data: &str) -> Result<DecodedTransaction> {
+        let transaction = self.deserialize_transaction(transaction_data)?;
+        let loaded = self.load_addresses(&transaction.message).await?;
+        Ok(DecodedTransaction::new(&transaction, &loaded))
+    }
+
+    /// Addresses a v0 message loads from address lookup tables, in the order
+    /// the runtime appends them to its account keys. Legacy messages and v0
+    /// ones without lookups load nothing and need no RPC call.
+    async fn load_addresses(&self, message: &VersionedMessage) -> Result<LoadedAddresses> {
+        let lookups = match message.address_table_lookups() {
+            Some(lookups) if !lookups.is_empty() => lookups,
+            _ => return Ok(LoadedAddresses::default()),
+        };
+
+        let table_keys: Vec<Pubkey> = lookups.iter().map(|lookup| lookup.account_key).collect();
+        let tables = self.rpc("getMultipleAccounts", |client| client.get_multiple_accounts_with_commitment(&table_keys, self.commitment))
+            .await
+            .map_err(|e| Error::Blockchain(format!("Failed to get address lookup tables: {}", e)))?
+            .value;
+
+        let mut loaded = LoadedAddresses::default();
+        for (lookup, table) in lookups.iter().zip(tables) {
+            let table = table
+                .ok_or_else(|| Error::BadRequest(format!("Address lookup table {} does not exist", lookup.account_key)))?;
+            let table = AddressLookupTable::deserialize(&table.data)
+                .map_err(|_| Error::BadRequest(format!("Account {} is not an address lookup table", lookup.account_key)))?;
+
+            let resolve = |indexes: &[u8]| -> Result<Vec<Pubkey>> {
+                indexes.iter()
+                    .map(|&index| table.addresses.get(index as usize).copied().ok_or_else(|| {
+                        Error::BadRequest(format!("Address lookup table {} has no entry {}", lookup.account_key, index))
+                    }))
+                    .collect()
+            };
+            loaded.writable.extend(resolve(&lookup.writable_indexes)?);
+            loaded.readonly.extend(resolve(&lookup.readonly_indexes)?);
         }
 
-        // Try to deserialize from hex
-        if let Ok(bytes) = hex::decode(transaction_data) {
-            if let Ok(transaction) = bincode::deserialize::<Transaction>(&bytes) {
+        Ok(loaded)
+    }
+
+    /// Deserialize a base64 or hex encoded legacy or v0 transaction
+    fn deserialize_transaction(&self, transaction_data: &str) -> Result<VersionedTransaction> {
+        let encodings = [
+            general_purpose::STANDARD.decode(transaction_data).ok(),
+            hex::decode(transaction_data).ok(),
+        ];
+        let candidates: Vec<Vec<u8>> = encodings.into_iter().flatten().collect();
+
+        for bytes in &candidates {
+            if let Ok(transaction) = bincode::deserialize::<VersionedTransaction>(bytes) {
                 return Ok(transaction);
             }
         }
 
-        Err(Error::Blockchain("Invalid transaction data format".to_string()))
+        // Tell a newer format apart from bytes that aren't a transaction at
+        // all, so clients know whether to re-encode or rebuild
+        if let Some(version) = candidates.iter().find_map(|bytes| message_version(bytes)) {
+            return Err(Error::UnsupportedTransactionVersion(version));
+        }
+
+        Err(Error::BadRequest("Invalid transaction data format".to_string()))
     }
 
-    /// Health check - verify connection to Solana network
+    /// Health check - verify connection to Solana network, moving to a
+    /// healthy endpoint if the current one is down
     pub async fn health_check(&self) -> Result<bool> {
-        match self.rpc_client.get_health() {
+        match self.endpoints.call("getHealth", &RetryPolicy::NONE, |client| client.get_health()).await {
             Ok(_) => Ok(true),
             Err(_) => Ok(false),
         }
@@ -201,10 +610,20 @@ impl SolanaClient {
 
     /// Get network version info
     pub async fn get_version(&self) -> Result<String> {
-        let version = self.rpc_client
-            .get_version()
+        let version = self.endpoints.call("getVersion", &RetryPolicy::NONE, |client| client.get_version())
+            .await
             .map_err(|e| Error::Blockchain(format!("Failed to get version: {}", e)))?;
 
         Ok(format!("{}", version.solana_core))
     }
-} 
\ No newline at end of file
+} 
+
+/// Version a serialized transaction's message declares, if it declares one
+/// other than v0. Versioned messages start with a byte whose high bit is
+/// set, right after the signatures.
+fn message_version(bytes: &[u8]) -> Option<u8> {
+    let (signatures, length_bytes) = short_vec::decode_shortu16_len(bytes).ok()?;
+    let prefix = *bytes.get(length_bytes + signatures * 64)?;
+    let version = prefix & !MESSAGE_VERSION_PREFIX;
+    (prefix & MESSAGE_VERSION_PREFIX != 0 && version > 0).then_some(version)
+}
\ No newline at end of file
@@ -0,0 +1,120 @@
+//! SPL token accounts: spotting transfers to token accounts that don't
+//! exist and building the instruction that creates a recipient's
+//! associated token account
+
+use base64::{Engine as _, engine::general_purpose};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    instruction::{AccountMeta, CompiledInstruction, Instruction},
+    pubkey,
+    pubkey::Pubkey,
+    system_program,
+};
+use spl_token::instruction::TokenInstruction;
+
+/// SPL Token program
+pub const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+/// Associated Token Account program
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey = pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+/// Size of an SPL token account, which sets the rent its creator pays
+pub const TOKEN_ACCOUNT_LEN: usize = 165;
+
+/// `CreateIdempotent` in the Associated Token Account program, which
+/// succeeds even if the account was created in the meantime
+const CREATE_IDEMPOTENT: u8 = 1;
+
+/// Address of `owner`'s associated token account for `mint`
+pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), TOKEN_PROGRAM_ID.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    ).0
+}
+
+/// Instruction creating `owner`'s associated token account for `mint`,
+/// paid for by `payer`
+pub fn create_associated_token_account(payer: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: ASSOCIATED_TOKEN_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(associated_token_address(owner, mint), false),
+            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        ],
+        data: vec![CREATE_IDEMPOTENT],
+    }
+}
+
+/// Token accounts a transaction's SPL transfers send to, leaving out any
+/// the transaction creates itself before the transfer. `account_keys` are
+/// all the keys `instructions` index into, including any loaded from
+/// address lookup tables.
+pub fn transfer_destinations(instructions: &[CompiledInstruction], account_keys: &[Pubkey]) -> Vec<Pubkey> {
+    let mut created = Vec::new();
+    let mut destinations = Vec::new();
+
+    for instruction in instructions {
+        let account = |position: usize| {
+            instruction.accounts.get(position)
+                .and_then(|&index| account_keys.get(index as usize))
+                .copied()
+        };
+        let Some(program_id) = account_keys.get(instruction.program_id_index as usize) else {
+            continue;
+        };
+
+        if *program_id == ASSOCIATED_TOKEN_PROGRAM_ID {
+            created.extend(account(1));
+        } else if *program_id == TOKEN_PROGRAM_ID {
+            let destination = match TokenInstruction::unpack(&instruction.data) {
+                Ok(TokenInstruction::Transfer { .. }) => account(1),
+                Ok(TokenInstruction::TransferChecked { .. }) => account(2),
+                _ => None,
+            };
+            if let Some(destination) = destination {
+                if !created.contains(&destination) && !destinations.contains(&destination) {
+                    destinations.push(destination);
+                }
+            }
+        }
+    }
+
+    destinations
+}
+
+/// An instruction in a form JSON clients can rebuild, with base58 keys and
+/// base64 data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncodedInstruction {
+    pub program_id: String,
+    pub accounts: Vec<EncodedAccountMeta>,
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncodedAccountMeta {
+    pub pubkey: String,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl From<&Instruction> for EncodedInstruction {
+    fn from(instruction: &Instruction) -> Self {
+        Self {
+            program_id: instruction.program_id.to_string(),
+            accounts: instruction.accounts.iter()
+                .map(|meta| EncodedAccountMeta {
+                    pubkey: meta.pubkey.to_string(),
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: general_purpose::STANDARD.encode(&instruction.data),
+        }
+    }
+}
\ No newline at end of file
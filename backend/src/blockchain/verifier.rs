@@ -0,0 +1,96 @@
+//! Proof verification by the Guardian on-chain program
+//!
+//! The program verifies a proof in a single `VerifyProof` instruction. Its
+//! data is the instruction tag followed by the proof and the public inputs,
+//! each prefixed with its length as a little-endian `u32`. The fee payer is
+//! the only account it reads.
+
+use crate::config::OnChainVerificationConfig;
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    message::Message,
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    transaction::Transaction,
+};
+use std::str::FromStr;
+
+/// Tag of the program's `VerifyProof` instruction
+pub const VERIFY_PROOF_TAG: u8 = 0;
+
+/// The deployed Guardian program and the account paying to run it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GuardianVerifier {
+    pub program_id: Pubkey,
+    pub fee_payer: Pubkey,
+}
+
+impl GuardianVerifier {
+    /// The verifier configured for `program_id`, or none if on-chain
+    /// verification is disabled
+    pub fn from_config(program_id: &str, config: &OnChainVerificationConfig) -> Result<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+
+        let parse = |name: &str, value: &str| {
+            Pubkey::from_str(value).map_err(|e| Error::Config(format!("{} is not a valid public key: {}", name, e)))
+        };
+        Ok(Some(Self {
+            program_id: parse("blockchain.guardian_program_id", program_id)?,
+            fee_payer: parse("blockchain.on_chain_verification.fee_payer", &config.fee_payer)?,
+        }))
+    }
+
+    /// `VerifyProof` instruction for `proof_bytes` and `public_inputs`
+    pub fn verify_instruction(&self, proof_bytes: &[u8], public_inputs: &[u8]) -> Result<Instruction> {
+        let mut data = Vec::with_capacity(1 + 4 + proof_bytes.len() + 4 + public_inputs.len());
+        data.push(VERIFY_PROOF_TAG);
+        for field in [proof_bytes, public_inputs] {
+            let len = u32::try_from(field.len())
+                .map_err(|_| Error::Validation("Proof is too large to verify on chain".to_string()))?;
+            data.extend_from_slice(&len.to_le_bytes());
+            data.extend_from_slice(field);
+        }
+
+        Ok(Instruction::new_with_bytes(
+            self.program_id,
+            &data,
+            vec![AccountMeta::new(self.fee_payer, true)],
+        ))
+    }
+
+    /// Unsigned transaction carrying only the `VerifyProof` instruction, for
+    /// simulation. It must fit in one packet; proofs that don't can't be
+    /// verified this way.
+    pub fn verify_transaction(&self, proof_bytes: &[u8], public_inputs: &[u8]) -> Result<Transaction> {
+        let instruction = self.verify_instruction(proof_bytes, public_inputs)?;
+        let message = Message::new_with_blockhash(&[instruction], Some(&self.fee_payer), &Hash::default());
+        let transaction = Transaction::new_unsigned(message);
+
+        let size = bincode::serialized_size(&transaction)
+            .map_err(|e| Error::Blockchain(format!("Failed to size verification transaction: {}", e)))?;
+        if size > PACKET_DATA_SIZE as u64 {
+            return Err(Error::Validation(format!(
+                "Proof needs a {}-byte transaction to verify on chain, over the {}-byte limit",
+                size, PACKET_DATA_SIZE
+            )));
+        }
+        Ok(transaction)
+    }
+}
+
+/// What the Guardian program made of a proof
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnChainVerification {
+    /// The program accepted the proof
+    pub verified: bool,
+    /// Program error the proof was rejected with, if any
+    pub error: Option<String>,
+    /// Compute units verification took, recorded as its gas cost
+    pub compute_units: Option<u64>,
+    pub logs: Vec<String>,
+}
\ No newline at end of file
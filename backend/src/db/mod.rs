@@ -1,42 +1,121 @@
 //! Database layer for Guardian-AA Backend
 
 use crate::{config::DatabaseConfig, error::Result};
-use sqlx::{postgres::PgPoolOptions, PgPool};
+use sqlx::{migrate::Migrate, postgres::PgPoolOptions, PgConnection, PgPool};
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
 use std::time::Duration;
 
 pub mod models;
 pub mod queries;
 
+/// Future returned by closures passed to [`Database::transaction`]
+pub type TxFuture<'c, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'c>>;
+
 /// Database connection wrapper
+///
+/// Writes always go to the primary pool. Read-only queries can use
+/// [`Database::read_pool`], which points at the read replica when one is
+/// configured and at the primary otherwise.
 #[derive(Clone)]
 pub struct Database {
     pool: PgPool,
+    read_pool: Option<PgPool>,
 }
 
 impl Database {
-    /// Create a new database connection pool
+    /// Create the primary connection pool, plus a replica pool if configured
     pub async fn new(config: &DatabaseConfig) -> Result<Self> {
-        let pool = PgPoolOptions::new()
-            .max_connections(config.max_connections)
-            .min_connections(config.min_connections)
-            .acquire_timeout(Duration::from_secs(config.connect_timeout))
+        let pool = Self::pool_options(config)
             .connect(&config.url)
             .await?;
 
-        Ok(Self { pool })
+        let read_pool = match &config.read_replica_url {
+            Some(url) => Some(Self::pool_options(config).connect(url).await?),
+            None => None,
+        };
+
+        Ok(Self::from_pools(pool, read_pool))
+    }
+
+    /// Wrap existing pools; `read_pool` of `None` routes reads to the primary
+    pub fn from_pools(pool: PgPool, read_pool: Option<PgPool>) -> Self {
+        Self { pool, read_pool }
     }
 
-    /// Get a reference to the connection pool
+    fn pool_options(config: &DatabaseConfig) -> PgPoolOptions {
+        PgPoolOptions::new()
+            .max_connections(config.max_connections)
+            .min_connections(config.min_connections)
+            .acquire_timeout(Duration::from_secs(config.connect_timeout))
+    }
+
+    /// Get a reference to the primary (read-write) connection pool
     pub fn pool(&self) -> &PgPool {
         &self.pool
     }
 
-    /// Run database migrations
-    pub async fn run_migrations(&self) -> Result<()> {
-        sqlx::migrate!("./migrations")
-            .run(&self.pool)
-            .await?;
-        Ok(())
+    /// Get a reference to the pool for read-only queries
+    pub fn read_pool(&self) -> &PgPool {
+        self.read_pool.as_ref().unwrap_or(&self.pool)
+    }
+
+    /// Whether read-only queries are served by a separate replica
+    pub fn has_read_replica(&self) -> bool {
+        self.read_pool.is_some()
+    }
+
+    /// Run `f` inside a database transaction.
+    ///
+    /// The transaction is committed if `f` returns `Ok` and rolled back
+    /// otherwise, so multi-step writes are never partially persisted.
+    pub async fn transaction<F, T>(&self, f: F) -> Result<T>
+    where
+        F: for<'c> FnOnce(&'c mut PgConnection) -> TxFuture<'c, T>,
+        T: Send,
+    {
+        let mut tx = self.pool.begin().await?;
+
+        match f(&mut *tx).await {
+            Ok(value) => {
+                tx.commit().await?;
+                Ok(value)
+            }
+            Err(e) => {
+                if let Err(rollback_err) = tx.rollback().await {
+                    tracing::error!("Failed to roll back transaction: {}", rollback_err);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Run database migrations, returning the versions this run applied.
+    /// Migrations that were already applied are skipped.
+    pub async fn run_migrations(&self) -> Result<Vec<i64>> {
+        let migrator = sqlx::migrate!("./migrations");
+
+        let applied: HashSet<i64> = {
+            let mut conn = self.pool.acquire().await?;
+            conn.ensure_migrations_table().await?;
+            conn.list_applied_migrations().await?
+                .into_iter()
+                .map(|migration| migration.version)
+                .collect()
+        };
+
+        migrator.run(&self.pool).await?;
+
+        let newly_applied: Vec<i64> = migrator
+            .iter()
+            .map(|migration| migration.version)
+            .filter(|version| !applied.contains(version))
+            .collect();
+        for version in &newly_applied {
+            tracing::info!("Applied database migration {}", version);
+        }
+        Ok(newly_applied)
     }
 
     /// Health check for database connection
@@ -44,6 +123,12 @@ impl Database {
         sqlx::query("SELECT 1")
             .execute(&self.pool)
             .await?;
+
+        if let Some(read_pool) = &self.read_pool {
+            sqlx::query("SELECT 1")
+                .execute(read_pool)
+                .await?;
+        }
         Ok(())
     }
 } 
\ No newline at end of file
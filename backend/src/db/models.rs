@@ -1,5 +1,6 @@
 //! Database models for Guardian-AA Backend
 
+use crate::auth::PrivateKey;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
@@ -14,11 +15,43 @@ pub struct User {
     pub email: String,
     pub password_hash: String,
     pub is_active: bool,
+    pub email_verified: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub last_login: Option<DateTime<Utc>>,
 }
 
+/// What request authentication needs to know about an account
+#[derive(Debug, Clone, Copy, FromRow)]
+pub struct AccountStatus {
+    pub is_active: bool,
+    pub is_admin: bool,
+}
+
+impl AccountStatus {
+    /// The role the account holds now, whatever its tokens say
+    pub fn role(&self) -> Role {
+        Role::from_admin_flag(self.is_admin)
+    }
+}
+
+/// What a user may do. Roles are ordered, so a higher role can do
+/// everything a lower one can.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    #[default]
+    User,
+    Admin,
+}
+
+impl Role {
+    /// The role of a user with the given `is_admin` flag
+    pub fn from_admin_flag(is_admin: bool) -> Self {
+        if is_admin { Role::Admin } else { Role::User }
+    }
+}
+
 /// User creation request
 #[derive(Debug, Deserialize)]
 pub struct CreateUser {
@@ -41,7 +74,8 @@ pub struct Wallet {
     pub name: String,
     pub wallet_type: WalletType,
     pub public_key: String,
-    pub encrypted_private_key: Option<String>, // None for watch-only wallets
+    /// Sealed by the key vault; None for watch-only wallets
+    pub encrypted_private_key: Option<String>,
     pub derivation_path: Option<String>,       // For HD wallets
     pub is_active: bool,
     pub created_at: DateTime<Utc>,
@@ -64,7 +98,8 @@ pub struct CreateWallet {
     pub name: String,
     pub wallet_type: WalletType,
     pub public_key: String,
-    pub encrypted_private_key: Option<String>,
+    /// Plaintext key, encrypted by the key vault before it's stored
+    pub private_key: Option<PrivateKey>,
     pub derivation_path: Option<String>,
 }
 
@@ -90,6 +125,17 @@ pub struct Transaction {
     pub confirmed_at: Option<DateTime<Utc>>,
 }
 
+/// Narrows a wallet's transaction list; unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct TransactionFilter {
+    pub status: Option<TransactionStatus>,
+    pub transaction_type: Option<TransactionType>,
+    /// Created at or after this time
+    pub from: Option<DateTime<Utc>>,
+    /// Created before this time
+    pub to: Option<DateTime<Utc>>,
+}
+
 /// Transaction types
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "transaction_type", rename_all = "snake_case")]
@@ -123,6 +169,28 @@ impl std::fmt::Display for TransactionStatus {
     }
 }
 
+/// Aggregates over a set of transactions. Sums are exact decimal strings
+/// that skip amounts and fees which aren't plain decimal numbers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionAggregates {
+    pub total_transactions: i64,
+    pub total_volume: String,
+    pub total_fees: String,
+    pub confirmed: i64,
+    pub failed: i64,
+    /// Mean seconds from creation to confirmation of confirmed transactions
+    pub average_confirmation_secs: Option<f64>,
+    pub by_type: Vec<TransactionTypeAggregate>,
+}
+
+/// Count and volume of one type of transaction
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TransactionTypeAggregate {
+    pub transaction_type: TransactionType,
+    pub count: i64,
+    pub volume: String,
+}
+
 /// Transaction creation request
 #[derive(Debug, Deserialize)]
 pub struct CreateTransaction {
@@ -151,7 +219,7 @@ pub struct Agent {
 }
 
 /// Agent types as defined in the research
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, sqlx::Type)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, sqlx::Type)]
 #[sqlx(type_name = "agent_type", rename_all = "snake_case")]
 pub enum AgentType {
     NewsSentiment,
@@ -190,7 +258,8 @@ pub enum PredictionType {
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct ZkmlProof {
     pub id: Uuid,
-    pub prediction_id: Uuid,
+    pub prediction_id: Option<Uuid>, // Unset for proofs generated directly through the API
+    pub user_id: Uuid,
     pub proof_type: ProofType,
     pub proof_data: String, // Base64 encoded proof
     pub public_inputs: serde_json::Value, // JSON of public inputs
@@ -209,6 +278,7 @@ pub enum ProofType {
     AgentProof,      // Individual agent proof
     RecursiveProof,  // Aggregated proof
     FinalProof,      // Final proof for on-chain verification
+    HashProof,       // Hash preimage proof generated through the API
 }
 
 /// Portfolio rebalancing recommendation
@@ -217,7 +287,7 @@ pub struct PortfolioRecommendation {
     pub id: Uuid,
     pub user_id: Uuid,
     pub recommendation_type: RecommendationType,
-    pub asset_allocations: serde_json::Value, // JSON of asset -> percentage mappings
+    pub asset_allocations: serde_json::Value, // `AssetAllocations`: asset -> units of 1/scale
     pub cash_ratio: f64,
     pub crypto_ratio: f64,
     pub confidence_score: f64,
@@ -264,3 +334,74 @@ pub struct ApiKey {
     pub expires_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
 }
+
+/// URL a user registered to be sent events
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub url: String,
+    /// Signing secret sealed with the key vault
+    #[serde(skip_serializing)]
+    pub encrypted_secret: String,
+    pub event_types: Vec<String>,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Webhook delivery status
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "webhook_delivery_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookDeliveryStatus {
+    Pending,
+    Delivered,
+    /// Given up on after the last attempt failed
+    Failed,
+}
+
+/// One event queued for one webhook
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub event_type: String,
+    pub body: String,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// A delivery claimed for sending, with where to send it
+#[derive(Debug, Clone, FromRow)]
+pub struct DueWebhookDelivery {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub event_type: String,
+    pub body: String,
+    pub attempts: i32,
+    pub url: String,
+    pub encrypted_secret: String,
+}
+
+/// System-wide counts for the admin dashboard
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DashboardCounts {
+    pub active_users: i64,
+    pub total_wallets: i64,
+    pub pending_transactions: i64,
+    pub proofs_generated_today: i64,
+    pub predictions_today: i64,
+}
+
+/// Number of predictions one agent made
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AgentPredictionCount {
+    pub agent_id: Uuid,
+    pub agent_name: String,
+    pub predictions: i64,
+}
@@ -5,7 +5,7 @@ use crate::{
     error::Result,
 };
 use chrono::{DateTime, Utc};
-use sqlx::PgPool;
+use sqlx::{PgExecutor, PgPool};
 use uuid::Uuid;
 use sqlx::types::ipnetwork;
 
@@ -14,18 +14,18 @@ pub struct UserQueries;
 
 impl UserQueries {
     /// Create a new user
-    pub async fn create(pool: &PgPool, user: &CreateUser, password_hash: &str) -> Result<User> {
+    pub async fn create(executor: impl PgExecutor<'_>, user: &CreateUser, password_hash: &str) -> Result<User> {
         let user = sqlx::query_as!(
             User,
             r#"
             INSERT INTO users (email, password_hash)
             VALUES ($1, $2)
-            RETURNING id, email, password_hash, is_active, created_at, updated_at, last_login
+            RETURNING id, email, password_hash, is_active, email_verified, created_at, updated_at, last_login
             "#,
             user.email,
             password_hash
         )
-        .fetch_one(pool)
+        .fetch_one(executor)
         .await?;
 
         Ok(user)
@@ -36,7 +36,7 @@ impl UserQueries {
         let user = sqlx::query_as!(
             User,
             r#"
-            SELECT id, email, password_hash, is_active, created_at, updated_at, last_login
+            SELECT id, email, password_hash, is_active, email_verified, created_at, updated_at, last_login
             FROM users
             WHERE email = $1
             "#,
@@ -53,7 +53,7 @@ impl UserQueries {
         let user = sqlx::query_as!(
             User,
             r#"
-            SELECT id, email, password_hash, is_active, created_at, updated_at, last_login
+            SELECT id, email, password_hash, is_active, email_verified, created_at, updated_at, last_login
             FROM users
             WHERE id = $1
             "#,
@@ -65,6 +65,56 @@ impl UserQueries {
         Ok(user)
     }
 
+    /// The account's status, checked on every authenticated request
+    pub async fn find_status(pool: &PgPool, user_id: Uuid) -> Result<Option<AccountStatus>> {
+        let status = sqlx::query_as!(
+            AccountStatus,
+            r#"
+            SELECT is_active, is_admin
+            FROM users
+            WHERE id = $1
+            "#,
+            user_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(status)
+    }
+
+    /// Whether the user may use the admin endpoints
+    pub async fn is_admin(pool: &PgPool, user_id: Uuid) -> Result<bool> {
+        let is_admin = sqlx::query_scalar!(
+            r#"
+            SELECT is_admin
+            FROM users
+            WHERE id = $1 AND is_active = true
+            "#,
+            user_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(is_admin.unwrap_or(false))
+    }
+
+    /// Grant or withdraw the user's admin role
+    pub async fn set_admin(pool: &PgPool, user_id: Uuid, is_admin: bool) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE users
+            SET is_admin = $2, updated_at = NOW()
+            WHERE id = $1
+            "#,
+            user_id,
+            is_admin
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
     /// Update user last login
     pub async fn update_last_login(pool: &PgPool, user_id: Uuid) -> Result<()> {
         sqlx::query!(
@@ -81,6 +131,55 @@ impl UserQueries {
         Ok(())
     }
 
+    /// Mark the user's email address as verified
+    pub async fn mark_email_verified(executor: impl PgExecutor<'_>, user_id: Uuid) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE users
+            SET email_verified = true, updated_at = NOW()
+            WHERE id = $1
+            "#,
+            user_id
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Mark the user inactive, so they can no longer log in
+    pub async fn deactivate(executor: impl PgExecutor<'_>, user_id: Uuid) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE users
+            SET is_active = false, updated_at = NOW()
+            WHERE id = $1
+            "#,
+            user_id
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Replace the user's password hash
+    pub async fn update_password(executor: impl PgExecutor<'_>, user_id: Uuid, password_hash: &str) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE users
+            SET password_hash = $2, updated_at = NOW()
+            WHERE id = $1
+            "#,
+            user_id,
+            password_hash
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
     /// Update user
     pub async fn update(pool: &PgPool, user_id: Uuid, update: &UpdateUser) -> Result<User> {
         let user = sqlx::query_as!(
@@ -92,7 +191,7 @@ impl UserQueries {
                 is_active = COALESCE($3, is_active),
                 updated_at = NOW()
             WHERE id = $1
-            RETURNING id, email, password_hash, is_active, created_at, updated_at, last_login
+            RETURNING id, email, password_hash, is_active, email_verified, created_at, updated_at, last_login
             "#,
             user_id,
             update.email.as_ref(),
@@ -109,8 +208,14 @@ impl UserQueries {
 pub struct WalletQueries;
 
 impl WalletQueries {
-    /// Create a new wallet
-    pub async fn create(pool: &PgPool, user_id: Uuid, wallet: &CreateWallet) -> Result<Wallet> {
+    /// Create a new wallet, storing `encrypted_private_key` in place of the
+    /// request's plaintext key
+    pub async fn create(
+        pool: &PgPool,
+        user_id: Uuid,
+        wallet: &CreateWallet,
+        encrypted_private_key: Option<&str>,
+    ) -> Result<Wallet> {
         let wallet = sqlx::query_as!(
             Wallet,
             r#"
@@ -123,7 +228,7 @@ impl WalletQueries {
             wallet.name,
             wallet.wallet_type.clone() as WalletType,
             wallet.public_key,
-            wallet.encrypted_private_key,
+            encrypted_private_key,
             wallet.derivation_path
         )
         .fetch_one(pool)
@@ -133,7 +238,7 @@ impl WalletQueries {
     }
 
     /// Get all wallets for a user
-    pub async fn find_by_user_id(pool: &PgPool, user_id: Uuid) -> Result<Vec<Wallet>> {
+    pub async fn find_by_user_id(pool: &PgPool, user_id: Uuid, limit: i64, offset: i64) -> Result<Vec<Wallet>> {
         let wallets = sqlx::query_as!(
             Wallet,
             r#"
@@ -142,6 +247,28 @@ impl WalletQueries {
             FROM wallets
             WHERE user_id = $1 AND is_active = true
             ORDER BY created_at DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            user_id,
+            limit,
+            offset
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(wallets)
+    }
+
+    /// Get every one of a user's active wallets, oldest first
+    pub async fn find_all_by_user_id(pool: &PgPool, user_id: Uuid) -> Result<Vec<Wallet>> {
+        let wallets = sqlx::query_as!(
+            Wallet,
+            r#"
+            SELECT id, user_id, name, wallet_type as "wallet_type: WalletType", public_key, 
+                   encrypted_private_key, derivation_path, is_active, created_at, updated_at
+            FROM wallets
+            WHERE user_id = $1 AND is_active = true
+            ORDER BY created_at ASC
             "#,
             user_id
         )
@@ -151,6 +278,18 @@ impl WalletQueries {
         Ok(wallets)
     }
 
+    /// Count a user's active wallets
+    pub async fn count_by_user_id(pool: &PgPool, user_id: Uuid) -> Result<i64> {
+        let count = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM wallets WHERE user_id = $1 AND is_active = true"#,
+            user_id
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(count)
+    }
+
     /// Find wallet by ID
     pub async fn find_by_id(pool: &PgPool, wallet_id: Uuid) -> Result<Option<Wallet>> {
         let wallet = sqlx::query_as!(
@@ -203,6 +342,22 @@ impl WalletQueries {
 
         Ok(())
     }
+
+    /// Deactivate every wallet the user has, returning how many were active
+    pub async fn deactivate_for_user(executor: impl PgExecutor<'_>, user_id: Uuid) -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE wallets
+            SET is_active = false, updated_at = NOW()
+            WHERE user_id = $1 AND is_active = true
+            "#,
+            user_id
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
 }
 
 /// Transaction queries
@@ -237,8 +392,15 @@ impl TransactionQueries {
         Ok(transaction)
     }
 
-    /// Get transactions for a wallet
-    pub async fn find_by_wallet_id(pool: &PgPool, wallet_id: Uuid, limit: i64, offset: i64) -> Result<Vec<Transaction>> {
+    /// Get a wallet's transactions matching `filter`, newest first
+    pub async fn find_by_wallet_id(
+        pool: &PgPool,
+        wallet_id: Uuid,
+        filter: &TransactionFilter,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Transaction>> {
+        // Unset filters are passed as NULL and match everything
         let transactions = sqlx::query_as!(
             Transaction,
             r#"
@@ -250,10 +412,18 @@ impl TransactionQueries {
                    created_at, updated_at, confirmed_at
             FROM transactions
             WHERE wallet_id = $1
+              AND ($2::transaction_status IS NULL OR status = $2)
+              AND ($3::transaction_type IS NULL OR transaction_type = $3)
+              AND ($4::TIMESTAMPTZ IS NULL OR created_at >= $4)
+              AND ($5::TIMESTAMPTZ IS NULL OR created_at < $5)
             ORDER BY created_at DESC
-            LIMIT $2 OFFSET $3
+            LIMIT $6 OFFSET $7
             "#,
             wallet_id,
+            filter.status.clone() as Option<TransactionStatus>,
+            filter.transaction_type.clone() as Option<TransactionType>,
+            filter.from,
+            filter.to,
             limit,
             offset
         )
@@ -263,6 +433,30 @@ impl TransactionQueries {
         Ok(transactions)
     }
 
+    /// Count a wallet's transactions matching `filter`
+    pub async fn count_by_wallet_id(pool: &PgPool, wallet_id: Uuid, filter: &TransactionFilter) -> Result<i64> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM transactions
+            WHERE wallet_id = $1
+              AND ($2::transaction_status IS NULL OR status = $2)
+              AND ($3::transaction_type IS NULL OR transaction_type = $3)
+              AND ($4::TIMESTAMPTZ IS NULL OR created_at >= $4)
+              AND ($5::TIMESTAMPTZ IS NULL OR created_at < $5)
+            "#,
+            wallet_id,
+            filter.status.clone() as Option<TransactionStatus>,
+            filter.transaction_type.clone() as Option<TransactionType>,
+            filter.from,
+            filter.to
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(count)
+    }
+
     /// Find transaction by ID
     pub async fn find_by_id(pool: &PgPool, transaction_id: Uuid) -> Result<Option<Transaction>> {
         let transaction = sqlx::query_as!(
@@ -354,6 +548,75 @@ impl TransactionQueries {
         Ok(transaction)
     }
 
+    /// Aggregate the user's transactions created since `since`, optionally
+    /// only those of one wallet
+    pub async fn analytics(
+        pool: &PgPool,
+        user_id: Uuid,
+        wallet_id: Option<Uuid>,
+        since: DateTime<Utc>,
+    ) -> Result<TransactionAggregates> {
+        // Amounts and fees are free-form strings, so only plain decimals are
+        // cast to NUMERIC and summed; anything else would fail the whole query
+        let totals = sqlx::query!(
+            r#"
+            SELECT
+                COUNT(*) AS "total_transactions!",
+                trim_scale(COALESCE(SUM(CASE WHEN t.amount ~ '^[0-9]+(\.[0-9]+)?$' THEN t.amount::NUMERIC END), 0))::TEXT
+                    AS "total_volume!",
+                trim_scale(COALESCE(SUM(CASE WHEN t.fee ~ '^[0-9]+(\.[0-9]+)?$' THEN t.fee::NUMERIC END), 0))::TEXT
+                    AS "total_fees!",
+                COUNT(*) FILTER (WHERE t.status = 'confirmed') AS "confirmed!",
+                COUNT(*) FILTER (WHERE t.status = 'failed') AS "failed!",
+                (AVG(EXTRACT(EPOCH FROM t.confirmed_at - t.created_at)) FILTER (WHERE t.status = 'confirmed'))::FLOAT8
+                    AS average_confirmation_secs
+            FROM transactions t
+            JOIN wallets w ON w.id = t.wallet_id
+            WHERE w.user_id = $1
+              AND ($2::UUID IS NULL OR t.wallet_id = $2)
+              AND t.created_at >= $3
+            "#,
+            user_id,
+            wallet_id,
+            since
+        )
+        .fetch_one(pool)
+        .await?;
+
+        let by_type = sqlx::query_as!(
+            TransactionTypeAggregate,
+            r#"
+            SELECT
+                t.transaction_type AS "transaction_type: TransactionType",
+                COUNT(*) AS "count!",
+                trim_scale(COALESCE(SUM(CASE WHEN t.amount ~ '^[0-9]+(\.[0-9]+)?$' THEN t.amount::NUMERIC END), 0))::TEXT
+                    AS "volume!"
+            FROM transactions t
+            JOIN wallets w ON w.id = t.wallet_id
+            WHERE w.user_id = $1
+              AND ($2::UUID IS NULL OR t.wallet_id = $2)
+              AND t.created_at >= $3
+            GROUP BY t.transaction_type
+            ORDER BY COUNT(*) DESC, t.transaction_type
+            "#,
+            user_id,
+            wallet_id,
+            since
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(TransactionAggregates {
+            total_transactions: totals.total_transactions,
+            total_volume: totals.total_volume,
+            total_fees: totals.total_fees,
+            confirmed: totals.confirmed,
+            failed: totals.failed,
+            average_confirmation_secs: totals.average_confirmation_secs,
+            by_type,
+        })
+    }
+
     /// Get pending transactions
     pub async fn find_pending(pool: &PgPool) -> Result<Vec<Transaction>> {
         let transactions = sqlx::query_as!(
@@ -463,7 +726,7 @@ pub struct AgentPredictionQueries;
 impl AgentPredictionQueries {
     /// Create a new prediction
     pub async fn create(
-        pool: &PgPool,
+        executor: impl PgExecutor<'_>,
         agent_id: Uuid,
         user_id: Uuid,
         asset_symbol: &str,
@@ -497,7 +760,7 @@ impl AgentPredictionQueries {
             data_sources,
             expires_at
         )
-        .fetch_one(pool)
+        .fetch_one(executor)
         .await?;
 
         Ok(prediction)
@@ -527,8 +790,24 @@ impl AgentPredictionQueries {
         Ok(predictions)
     }
 
+    /// Count a user's unexpired predictions
+    pub async fn count_by_user_id(pool: &PgPool, user_id: Uuid) -> Result<i64> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM agent_predictions
+            WHERE user_id = $1 AND expires_at > NOW()
+            "#,
+            user_id
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(count)
+    }
+
     /// Get predictions for an asset
-    pub async fn find_by_asset(pool: &PgPool, user_id: Uuid, asset_symbol: &str) -> Result<Vec<AgentPrediction>> {
+    pub async fn find_by_asset(pool: &PgPool, user_id: Uuid, asset_symbol: &str, limit: i64, offset: i64) -> Result<Vec<AgentPrediction>> {
         let predictions = sqlx::query_as!(
             AgentPrediction,
             r#"
@@ -539,9 +818,12 @@ impl AgentPredictionQueries {
             FROM agent_predictions
             WHERE user_id = $1 AND asset_symbol = $2 AND expires_at > NOW()
             ORDER BY created_at DESC
+            LIMIT $3 OFFSET $4
             "#,
             user_id,
-            asset_symbol
+            asset_symbol,
+            limit,
+            offset
         )
         .fetch_all(pool)
         .await?;
@@ -549,6 +831,23 @@ impl AgentPredictionQueries {
         Ok(predictions)
     }
 
+    /// Count a user's unexpired predictions for an asset
+    pub async fn count_by_asset(pool: &PgPool, user_id: Uuid, asset_symbol: &str) -> Result<i64> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM agent_predictions
+            WHERE user_id = $1 AND asset_symbol = $2 AND expires_at > NOW()
+            "#,
+            user_id,
+            asset_symbol
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(count)
+    }
+
     /// Find prediction by ID
     pub async fn find_by_id(pool: &PgPool, prediction_id: Uuid) -> Result<Option<AgentPrediction>> {
         let prediction = sqlx::query_as!(
@@ -590,8 +889,9 @@ pub struct ZkmlProofQueries;
 impl ZkmlProofQueries {
     /// Create a new proof
     pub async fn create(
-        pool: &PgPool,
-        prediction_id: Uuid,
+        executor: impl PgExecutor<'_>,
+        prediction_id: Option<Uuid>,
+        user_id: Uuid,
         proof_type: ProofType,
         proof_data: &str,
         public_inputs: &serde_json::Value,
@@ -602,23 +902,24 @@ impl ZkmlProofQueries {
             ZkmlProof,
             r#"
             INSERT INTO zkml_proofs (
-                prediction_id, proof_type, proof_data, public_inputs,
+                prediction_id, user_id, proof_type, proof_data, public_inputs,
                 verification_key_hash, circuit_hash
             )
-            VALUES ($1, $2, $3, $4, $5, $6)
-            RETURNING id, prediction_id, proof_type as "proof_type: ProofType",
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id, prediction_id, user_id, proof_type as "proof_type: ProofType",
                       proof_data, public_inputs, verification_key_hash,
                       circuit_hash, is_verified, verification_gas_cost,
                       created_at, verified_at
             "#,
             prediction_id,
+            user_id,
             proof_type as ProofType,
             proof_data,
             public_inputs,
             verification_key_hash,
             circuit_hash
         )
-        .fetch_one(pool)
+        .fetch_one(executor)
         .await?;
 
         Ok(proof)
@@ -629,7 +930,7 @@ impl ZkmlProofQueries {
         let proof = sqlx::query_as!(
             ZkmlProof,
             r#"
-            SELECT id, prediction_id, proof_type as "proof_type: ProofType",
+            SELECT id, prediction_id, user_id, proof_type as "proof_type: ProofType",
                    proof_data, public_inputs, verification_key_hash,
                    circuit_hash, is_verified, verification_gas_cost,
                    created_at, verified_at
@@ -649,7 +950,7 @@ impl ZkmlProofQueries {
         let proofs = sqlx::query_as!(
             ZkmlProof,
             r#"
-            SELECT id, prediction_id, proof_type as "proof_type: ProofType",
+            SELECT id, prediction_id, user_id, proof_type as "proof_type: ProofType",
                    proof_data, public_inputs, verification_key_hash,
                    circuit_hash, is_verified, verification_gas_cost,
                    created_at, verified_at
@@ -689,7 +990,7 @@ impl ZkmlProofQueries {
         let proofs = sqlx::query_as!(
             ZkmlProof,
             r#"
-            SELECT id, prediction_id, proof_type as "proof_type: ProofType",
+            SELECT id, prediction_id, user_id, proof_type as "proof_type: ProofType",
                    proof_data, public_inputs, verification_key_hash,
                    circuit_hash, is_verified, verification_gas_cost,
                    created_at, verified_at
@@ -794,7 +1095,7 @@ pub struct UserSessionQueries;
 impl UserSessionQueries {
     /// Create a new session
     pub async fn create(
-        pool: &PgPool,
+        executor: impl PgExecutor<'_>,
         user_id: Uuid,
         refresh_token_hash: &str,
         expires_at: DateTime<Utc>,
@@ -817,7 +1118,7 @@ impl UserSessionQueries {
             user_agent,
             ip_address
         )
-        .fetch_one(pool)
+        .fetch_one(executor)
         .await?;
 
         Ok(session)
@@ -841,6 +1142,25 @@ impl UserSessionQueries {
         Ok(session)
     }
 
+    /// A user's unexpired sessions, most recently used first
+    pub async fn find_by_user_id(pool: &PgPool, user_id: Uuid) -> Result<Vec<UserSession>> {
+        let sessions = sqlx::query_as!(
+            UserSession,
+            r#"
+            SELECT id, user_id, refresh_token_hash, expires_at,
+                   created_at, last_used_at, user_agent, ip_address
+            FROM user_sessions
+            WHERE user_id = $1 AND expires_at > NOW()
+            ORDER BY last_used_at DESC
+            "#,
+            user_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(sessions)
+    }
+
     /// Update session last used
     pub async fn update_last_used(pool: &PgPool, session_id: Uuid) -> Result<()> {
         sqlx::query!(
@@ -857,19 +1177,86 @@ impl UserSessionQueries {
         Ok(())
     }
 
-    /// Delete session
-    pub async fn delete(pool: &PgPool, session_id: Uuid) -> Result<()> {
-        sqlx::query!(
+    /// Delete session, returning whether it still existed
+    pub async fn delete(executor: impl PgExecutor<'_>, session_id: Uuid) -> Result<bool> {
+        let result = sqlx::query!(
             r#"
             DELETE FROM user_sessions
             WHERE id = $1
             "#,
             session_id
         )
+        .execute(executor)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Delete one of a user's sessions, returning whether the user had it
+    pub async fn revoke(pool: &PgPool, session_id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM user_sessions
+            WHERE id = $1 AND user_id = $2
+            "#,
+            session_id,
+            user_id
+        )
         .execute(pool)
         .await?;
 
-        Ok(())
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Delete the session holding a refresh token, returning whether there was one
+    pub async fn delete_by_token_hash(pool: &PgPool, token_hash: &str) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM user_sessions
+            WHERE refresh_token_hash = $1
+            "#,
+            token_hash
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Delete all of a user's sessions, returning how many there were
+    pub async fn delete_for_user(executor: impl PgExecutor<'_>, user_id: Uuid) -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM user_sessions
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Delete all of a user's sessions except the one holding
+    /// `keep_token_hash`, returning how many were deleted
+    pub async fn delete_for_user_except(
+        executor: impl PgExecutor<'_>,
+        user_id: Uuid,
+        keep_token_hash: &str,
+    ) -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM user_sessions
+            WHERE user_id = $1 AND refresh_token_hash <> $2
+            "#,
+            user_id,
+            keep_token_hash
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(result.rows_affected())
     }
 
     /// Clean up expired sessions
@@ -886,3 +1273,514 @@ impl UserSessionQueries {
         Ok(result.rows_affected())
     }
 }
+
+
+/// API key queries
+pub struct ApiKeyQueries;
+
+impl ApiKeyQueries {
+    /// Store a new API key by its hash
+    pub async fn create(
+        executor: impl PgExecutor<'_>,
+        user_id: Uuid,
+        name: &str,
+        key_hash: &str,
+        permissions: &serde_json::Value,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<ApiKey> {
+        let api_key = sqlx::query_as!(
+            ApiKey,
+            r#"
+            INSERT INTO api_keys (user_id, name, key_hash, permissions, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, user_id, name, key_hash, permissions, is_active,
+                      last_used_at, expires_at, created_at
+            "#,
+            user_id,
+            name,
+            key_hash,
+            permissions,
+            expires_at
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(api_key)
+    }
+
+    /// Count the user's API keys that are active and unexpired. Locks the
+    /// user's row, so concurrent key creation waits for this transaction.
+    pub async fn count_usable_for_user_locked(executor: impl PgExecutor<'_>, user_id: Uuid) -> Result<i64> {
+        let count = sqlx::query_scalar!(
+            r#"
+            WITH locked AS (
+                SELECT id FROM users WHERE id = $1 FOR UPDATE
+            )
+            SELECT COUNT(*) AS "count!"
+            FROM api_keys
+            WHERE user_id = (SELECT id FROM locked)
+              AND is_active = true
+              AND (expires_at IS NULL OR expires_at > NOW())
+            "#,
+            user_id
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Find an API key by its hash, whether or not it is still usable
+    pub async fn find_by_hash(pool: &PgPool, key_hash: &str) -> Result<Option<ApiKey>> {
+        let api_key = sqlx::query_as!(
+            ApiKey,
+            r#"
+            SELECT id, user_id, name, key_hash, permissions, is_active,
+                   last_used_at, expires_at, created_at
+            FROM api_keys
+            WHERE key_hash = $1
+            "#,
+            key_hash
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(api_key)
+    }
+
+    /// Deactivate one of the user's API keys, returning whether it was active
+    pub async fn revoke(pool: &PgPool, key_id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE api_keys
+            SET is_active = false
+            WHERE id = $1 AND user_id = $2 AND is_active = true
+            "#,
+            key_id,
+            user_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Record that an API key was just used
+    pub async fn update_last_used(pool: &PgPool, key_id: Uuid) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE api_keys
+            SET last_used_at = NOW()
+            WHERE id = $1
+            "#,
+            key_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Email verification token queries
+pub struct EmailVerificationTokenQueries;
+
+impl EmailVerificationTokenQueries {
+    /// Store a verification token's hash for the user
+    pub async fn create(
+        executor: impl PgExecutor<'_>,
+        user_id: Uuid,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO email_verification_tokens (user_id, token_hash, expires_at)
+            VALUES ($1, $2, $3)
+            "#,
+            user_id,
+            token_hash,
+            expires_at
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Mark an unused, unexpired token as used, returning its user. Returns
+    /// `None` for unknown, expired or already used tokens.
+    pub async fn consume(executor: impl PgExecutor<'_>, token_hash: &str) -> Result<Option<Uuid>> {
+        let user_id = sqlx::query_scalar!(
+            r#"
+            UPDATE email_verification_tokens
+            SET used_at = NOW()
+            WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW()
+            RETURNING user_id
+            "#,
+            token_hash
+        )
+        .fetch_optional(executor)
+        .await?;
+
+        Ok(user_id)
+    }
+}
+
+/// Password reset token queries
+pub struct PasswordResetTokenQueries;
+
+impl PasswordResetTokenQueries {
+    /// Store a reset token's hash for the user
+    pub async fn create(
+        executor: impl PgExecutor<'_>,
+        user_id: Uuid,
+        token_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO password_reset_tokens (user_id, token_hash, expires_at)
+            VALUES ($1, $2, $3)
+            "#,
+            user_id,
+            token_hash,
+            expires_at
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Mark an unused, unexpired token as used, returning its user. Returns
+    /// `None` for unknown, expired or already used tokens.
+    pub async fn consume(executor: impl PgExecutor<'_>, token_hash: &str) -> Result<Option<Uuid>> {
+        let user_id = sqlx::query_scalar!(
+            r#"
+            UPDATE password_reset_tokens
+            SET used_at = NOW()
+            WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW()
+            RETURNING user_id
+            "#,
+            token_hash
+        )
+        .fetch_optional(executor)
+        .await?;
+
+        Ok(user_id)
+    }
+
+    /// Invalidate every outstanding reset token for the user
+    pub async fn invalidate_for_user(executor: impl PgExecutor<'_>, user_id: Uuid) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE password_reset_tokens
+            SET used_at = NOW()
+            WHERE user_id = $1 AND used_at IS NULL
+            "#,
+            user_id
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Wallets users sign in with
+pub struct WalletLoginQueries;
+
+impl WalletLoginQueries {
+    /// The user who signs in with the wallet at `public_key`
+    pub async fn find_user(pool: &PgPool, public_key: &str) -> Result<Option<User>> {
+        let user = sqlx::query_as!(
+            User,
+            r#"
+            SELECT u.id, u.email, u.password_hash, u.is_active, u.email_verified, u.created_at, u.updated_at, u.last_login
+            FROM users u
+            JOIN wallet_logins w ON w.user_id = u.id
+            WHERE w.public_key = $1
+            "#,
+            public_key
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(user)
+    }
+
+    /// Let the user sign in with the wallet at `public_key`. Returns `false`
+    /// if the wallet already belongs to a user.
+    pub async fn link(executor: impl PgExecutor<'_>, public_key: &str, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO wallet_logins (public_key, user_id)
+            VALUES ($1, $2)
+            ON CONFLICT (public_key) DO NOTHING
+            "#,
+            public_key,
+            user_id
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+}
+
+/// Aggregate queries for the admin dashboard
+pub struct StatsQueries;
+
+impl StatsQueries {
+    /// System-wide counts, with "today" starting at `day_start`. Each count
+    /// is filtered on an indexed column.
+    pub async fn dashboard_counts(pool: &PgPool, day_start: DateTime<Utc>) -> Result<DashboardCounts> {
+        let counts = sqlx::query_as!(
+            DashboardCounts,
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM users WHERE is_active = true) AS "active_users!",
+                (SELECT COUNT(*) FROM wallets) AS "total_wallets!",
+                (SELECT COUNT(*) FROM transactions WHERE status = 'pending') AS "pending_transactions!",
+                (SELECT COUNT(*) FROM zkml_proofs WHERE created_at >= $1) AS "proofs_generated_today!",
+                (SELECT COUNT(*) FROM agent_predictions WHERE created_at >= $1) AS "predictions_today!"
+            "#,
+            day_start
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(counts)
+    }
+
+    /// Predictions each agent made since `since`, including agents that
+    /// made none
+    pub async fn predictions_by_agent(pool: &PgPool, since: DateTime<Utc>) -> Result<Vec<AgentPredictionCount>> {
+        let counts = sqlx::query_as!(
+            AgentPredictionCount,
+            r#"
+            SELECT a.id AS agent_id, a.name AS agent_name, COUNT(p.id) AS "predictions!"
+            FROM agents a
+            LEFT JOIN agent_predictions p ON p.agent_id = a.id AND p.created_at >= $1
+            GROUP BY a.id, a.name
+            ORDER BY a.name
+            "#,
+            since
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(counts)
+    }
+}
+
+/// Webhook queries
+pub struct WebhookQueries;
+
+impl WebhookQueries {
+    /// Register a webhook under `webhook_id`, which its secret is sealed for
+    pub async fn create(
+        executor: impl PgExecutor<'_>,
+        webhook_id: Uuid,
+        user_id: Uuid,
+        url: &str,
+        encrypted_secret: &str,
+        event_types: &[String],
+    ) -> Result<Webhook> {
+        let webhook = sqlx::query_as!(
+            Webhook,
+            r#"
+            INSERT INTO webhooks (id, user_id, url, encrypted_secret, event_types)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, user_id, url, encrypted_secret, event_types, is_active, created_at
+            "#,
+            webhook_id,
+            user_id,
+            url,
+            encrypted_secret,
+            event_types
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(webhook)
+    }
+
+    /// Count the user's webhooks. Locks the user's row, so concurrent
+    /// registrations wait for this transaction.
+    pub async fn count_for_user_locked(executor: impl PgExecutor<'_>, user_id: Uuid) -> Result<i64> {
+        let count = sqlx::query_scalar!(
+            r#"
+            WITH locked AS (
+                SELECT id FROM users WHERE id = $1 FOR UPDATE
+            )
+            SELECT COUNT(*) AS "count!"
+            FROM webhooks
+            WHERE user_id = (SELECT id FROM locked)
+            "#,
+            user_id
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// The user's webhooks, oldest first
+    pub async fn find_by_user_id(pool: &PgPool, user_id: Uuid) -> Result<Vec<Webhook>> {
+        let webhooks = sqlx::query_as!(
+            Webhook,
+            r#"
+            SELECT id, user_id, url, encrypted_secret, event_types, is_active, created_at
+            FROM webhooks
+            WHERE user_id = $1
+            ORDER BY created_at
+            "#,
+            user_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(webhooks)
+    }
+
+    /// Delete one of the user's webhooks and its deliveries, returning
+    /// whether it existed
+    pub async fn delete(pool: &PgPool, webhook_id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query!(
+            "DELETE FROM webhooks WHERE id = $1 AND user_id = $2",
+            webhook_id,
+            user_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// Webhook delivery queue queries
+pub struct WebhookDeliveryQueries;
+
+impl WebhookDeliveryQueries {
+    /// Queue `body` for each of the user's active webhooks subscribed to
+    /// `event_type`, returning how many deliveries were queued
+    pub async fn enqueue(executor: impl PgExecutor<'_>, user_id: Uuid, event_type: &str, body: &str) -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO webhook_deliveries (webhook_id, event_type, body)
+            SELECT id, $2, $3
+            FROM webhooks
+            WHERE user_id = $1 AND is_active = true AND $2 = ANY(event_types)
+            "#,
+            user_id,
+            event_type,
+            body
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Claim up to `limit` pending deliveries that are due. Claimed
+    /// deliveries aren't due again for `lease_secs`, so other instances
+    /// skip them while this one sends them.
+    pub async fn claim_due(pool: &PgPool, limit: i64, lease_secs: i64) -> Result<Vec<DueWebhookDelivery>> {
+        let deliveries = sqlx::query_as!(
+            DueWebhookDelivery,
+            r#"
+            UPDATE webhook_deliveries d
+            SET next_attempt_at = NOW() + make_interval(secs => $2)
+            FROM webhooks w
+            WHERE w.id = d.webhook_id
+              AND d.id IN (
+                  SELECT id FROM webhook_deliveries
+                  WHERE status = 'pending' AND next_attempt_at <= NOW()
+                  ORDER BY next_attempt_at
+                  LIMIT $1
+                  FOR UPDATE SKIP LOCKED
+              )
+            RETURNING d.id, d.webhook_id, d.event_type, d.body, d.attempts, w.url, w.encrypted_secret
+            "#,
+            limit,
+            lease_secs as f64
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(deliveries)
+    }
+
+    /// Record a successful attempt
+    pub async fn mark_delivered(pool: &PgPool, delivery_id: Uuid, response_status: i32) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = 'delivered', attempts = attempts + 1, last_response_status = $2,
+                last_error = NULL, delivered_at = NOW()
+            WHERE id = $1
+            "#,
+            delivery_id,
+            response_status
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record a failed attempt, to be retried at `retry_at` or, without
+    /// one, given up on
+    pub async fn mark_attempt_failed(
+        pool: &PgPool,
+        delivery_id: Uuid,
+        response_status: Option<i32>,
+        error: &str,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET attempts = attempts + 1,
+                last_response_status = $2,
+                last_error = $3,
+                status = CASE WHEN $4::TIMESTAMPTZ IS NULL
+                              THEN 'failed'::webhook_delivery_status
+                              ELSE status END,
+                next_attempt_at = COALESCE($4, next_attempt_at)
+            WHERE id = $1
+            "#,
+            delivery_id,
+            response_status,
+            error,
+            retry_at
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Deliveries queued for a webhook, oldest first
+    pub async fn find_by_webhook_id(pool: &PgPool, webhook_id: Uuid) -> Result<Vec<WebhookDelivery>> {
+        let deliveries = sqlx::query_as!(
+            WebhookDelivery,
+            r#"
+            SELECT id, webhook_id, event_type, body,
+                   status as "status: WebhookDeliveryStatus",
+                   attempts, next_attempt_at, last_response_status, last_error,
+                   created_at, delivered_at
+            FROM webhook_deliveries
+            WHERE webhook_id = $1
+            ORDER BY created_at
+            "#,
+            webhook_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(deliveries)
+    }
+}
\ No newline at end of file
@@ -1,10 +1,14 @@
 //! Error types and handling for Guardian-AA Backend
 
 use axum::{
-    http::StatusCode,
+    http::{header::RETRY_AFTER, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
+use crate::{
+    api::middleware::request_id::current_request_id,
+    i18n::{self, Locale},
+};
 use serde_json::json;
 use thiserror::Error;
 
@@ -47,6 +51,14 @@ pub enum Error {
     #[error("Transaction failed: {0}")]
     TransactionFailed(String),
 
+    /// An SPL transfer's recipient has no token account to receive it
+    #[error("Recipient token account {0} does not exist; create the recipient's associated token account for this mint first")]
+    TokenAccountMissing(String),
+
+    /// A well-formed transaction in a format newer than the backend handles
+    #[error("Unsupported transaction version {0}; only legacy and v0 transactions are supported")]
+    UnsupportedTransactionVersion(u8),
+
     // ZK proof errors
     #[error("Proof generation failed: {0}")]
     ProofGenerationFailed(String),
@@ -78,17 +90,103 @@ pub enum Error {
     #[error("Bad request: {0}")]
     BadRequest(String),
 
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
     #[error("Service unavailable")]
     ServiceUnavailable,
 
     #[error("Rate limit exceeded")]
     RateLimitExceeded,
 
+    /// The same work was requested again before its cool-down ran out
+    #[error("Requested again too soon, retry in {retry_after_secs}s")]
+    CoolingDown { retry_after_secs: u64 },
+
+    /// Too many failed logins for the account; logins resume after the lockout
+    #[error("Too many failed login attempts, retry in {retry_after_secs}s")]
+    AccountLocked { retry_after_secs: u64 },
+
+    /// The request body is larger than the route accepts
+    #[error("Request body exceeds the {limit}-byte limit for this endpoint")]
+    PayloadTooLarge { limit: usize },
+
+    /// The service is in maintenance mode and not taking this request
+    #[error("Under maintenance: {message}")]
+    Maintenance { message: String, retry_after_secs: u64 },
+
     // Other errors
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
 
+impl Error {
+    /// Stable machine-readable code identifying the kind of error
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::Config(_) | Error::ConfigError(_) => "config_error",
+            Error::Database(_) => "database_error",
+            Error::Migration(_) => "migration_error",
+            Error::JsonSerialization(_) => "serialization_error",
+            Error::AuthenticationFailed => "authentication_failed",
+            Error::Unauthorized => "unauthorized",
+            Error::InvalidToken => "invalid_token",
+            Error::Blockchain(_) => "blockchain_error",
+            Error::TransactionFailed(_) => "transaction_failed",
+            Error::TokenAccountMissing(_) => "token_account_missing",
+            Error::UnsupportedTransactionVersion(_) => "unsupported_transaction_version",
+            Error::ProofGenerationFailed(_) => "proof_generation_failed",
+            Error::ProofVerificationFailed => "proof_verification_failed",
+            Error::Validation(_) => "validation_error",
+            Error::InvalidRequest(_) | Error::BadRequest(_) => "bad_request",
+            Error::Conflict(_) => "conflict",
+            Error::ExternalService(_) => "external_service_error",
+            Error::Internal | Error::Other(_) => "internal_error",
+            Error::NotFound => "not_found",
+            Error::Forbidden => "forbidden",
+            Error::ServiceUnavailable => "service_unavailable",
+            Error::RateLimitExceeded => "rate_limit_exceeded",
+            Error::CoolingDown { .. } => "cooling_down",
+            Error::AccountLocked { .. } => "account_locked",
+            Error::PayloadTooLarge { .. } => "payload_too_large",
+            Error::Maintenance { .. } => "maintenance",
+        }
+    }
+
+    /// Caller-supplied detail, shown after the localized description
+    fn detail(&self) -> Option<&str> {
+        match self {
+            Error::Config(detail)
+            | Error::Blockchain(detail)
+            | Error::TransactionFailed(detail)
+            | Error::TokenAccountMissing(detail)
+            | Error::ProofGenerationFailed(detail)
+            | Error::Validation(detail)
+            | Error::InvalidRequest(detail)
+            | Error::ExternalService(detail)
+            | Error::BadRequest(detail)
+            | Error::Conflict(detail)
+            | Error::Maintenance { message: detail, .. } => Some(detail),
+            _ => None,
+        }
+    }
+
+    /// The `message` field in the request's locale. English keeps the full
+    /// error text; other locales get the catalog entry plus any detail.
+    fn localized_message(&self, english: String) -> String {
+        let locale = i18n::current_locale();
+        if locale == Locale::En {
+            return english;
+        }
+
+        let message = i18n::message(self.code(), locale);
+        match self.detail() {
+            Some(detail) => format!("{}: {}", message, detail),
+            None => message.to_string(),
+        }
+    }
+}
+
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
         let (status, error_message) = match self {
@@ -102,34 +200,53 @@ impl IntoResponse for Error {
             Error::InvalidToken => (StatusCode::UNAUTHORIZED, "Invalid token"),
             Error::Blockchain(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Blockchain error"),
             Error::TransactionFailed(_) => (StatusCode::BAD_REQUEST, "Transaction failed"),
+            Error::TokenAccountMissing(_) => (StatusCode::UNPROCESSABLE_ENTITY, "Recipient token account missing"),
+            Error::UnsupportedTransactionVersion(_) => (StatusCode::BAD_REQUEST, "Unsupported transaction version"),
             Error::ProofGenerationFailed(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Proof generation failed"),
             Error::ProofVerificationFailed => (StatusCode::BAD_REQUEST, "Proof verification failed"),
-            Error::Validation(ref msg) => return validation_error_response(msg),
-            Error::InvalidRequest(ref msg) => return bad_request_response(msg),
+            Error::Validation(ref msg) => return validation_error_response(&self.localized_message(msg.clone())),
+            Error::InvalidRequest(ref msg) => return bad_request_response(&self.localized_message(msg.clone())),
             Error::ExternalService(_) => (StatusCode::BAD_GATEWAY, "External service error"),
             Error::Internal => (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error"),
             Error::NotFound => (StatusCode::NOT_FOUND, "Resource not found"),
             Error::Forbidden => (StatusCode::FORBIDDEN, "Forbidden"),
-            Error::BadRequest(ref msg) => return bad_request_response(msg),
+            Error::BadRequest(ref msg) => return bad_request_response(&self.localized_message(msg.clone())),
+            Error::Conflict(_) => (StatusCode::CONFLICT, "Conflict"),
             Error::ServiceUnavailable => (StatusCode::SERVICE_UNAVAILABLE, "Service unavailable"),
             Error::RateLimitExceeded => (StatusCode::TOO_MANY_REQUESTS, "Rate limit exceeded"),
+            Error::CoolingDown { .. } => (StatusCode::TOO_MANY_REQUESTS, "Cooling down"),
+            Error::AccountLocked { .. } => (StatusCode::TOO_MANY_REQUESTS, "Account locked"),
+            Error::PayloadTooLarge { .. } => (StatusCode::PAYLOAD_TOO_LARGE, "Payload too large"),
+            Error::Maintenance { .. } => (StatusCode::SERVICE_UNAVAILABLE, "Under maintenance"),
             Error::Other(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error"),
         };
 
         let body = Json(json!({
             "error": error_message,
-            "message": self.to_string(),
+            "code": self.code(),
+            "message": self.localized_message(self.to_string()),
+            "request_id": current_request_id(),
         }));
 
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+        if let Error::CoolingDown { retry_after_secs }
+        | Error::AccountLocked { retry_after_secs }
+        | Error::Maintenance { retry_after_secs, .. } = self
+        {
+            response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(retry_after_secs));
+        }
+
+        response
     }
 }
 
 fn validation_error_response(message: &str) -> Response {
     let body = Json(json!({
         "error": "Validation failed",
+        "code": "validation_error",
         "message": message,
-        "type": "validation_error"
+        "type": "validation_error",
+        "request_id": current_request_id(),
     }));
 
     (StatusCode::UNPROCESSABLE_ENTITY, body).into_response()
@@ -138,9 +255,11 @@ fn validation_error_response(message: &str) -> Response {
 fn bad_request_response(message: &str) -> Response {
     let body = Json(json!({
         "error": "Bad request",
+        "code": "bad_request",
         "message": message,
-        "type": "bad_request"
+        "type": "bad_request",
+        "request_id": current_request_id(),
     }));
 
     (StatusCode::BAD_REQUEST, body).into_response()
-} 
\ No newline at end of file
+}
\ No newline at end of file
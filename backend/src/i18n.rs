@@ -0,0 +1,146 @@
+//! Localized error messages
+//!
+//! Messages are looked up by the stable error code sent in every error
+//! response. The request's locale comes from its `Accept-Language` header
+//! and is made available to error responses for the rest of the request.
+
+use std::future::Future;
+
+/// A locale error messages are available in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    En,
+    Es,
+}
+
+impl Locale {
+    fn from_tag(tag: &str) -> Option<Self> {
+        let primary = tag.split(['-', '_']).next()?.trim();
+        if primary.eq_ignore_ascii_case("en") {
+            Some(Locale::En)
+        } else if primary.eq_ignore_ascii_case("es") {
+            Some(Locale::Es)
+        } else {
+            None
+        }
+    }
+
+    /// The supported locale the client prefers most, by `q` weight, falling
+    /// back to English
+    pub fn from_accept_language(header: &str) -> Self {
+        let mut best: Option<(Locale, f32)> = None;
+
+        for entry in header.split(',') {
+            let mut parts = entry.split(';');
+            let Some(locale) = parts.next().and_then(Locale::from_tag) else {
+                continue;
+            };
+            let weight = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+
+            // Earlier entries win ties
+            if weight > 0.0 && best.is_none_or(|(_, best_weight)| weight > best_weight) {
+                best = Some((locale, weight));
+            }
+        }
+
+        best.map(|(locale, _)| locale).unwrap_or_default()
+    }
+}
+
+const EN: &[(&str, &str)] = &[
+    ("config_error", "Configuration error"),
+    ("database_error", "Database error"),
+    ("migration_error", "Database migration error"),
+    ("serialization_error", "Serialization error"),
+    ("authentication_failed", "Authentication failed"),
+    ("unauthorized", "Unauthorized"),
+    ("invalid_token", "Invalid token"),
+    ("blockchain_error", "Blockchain error"),
+    ("transaction_failed", "Transaction failed"),
+    ("token_account_missing", "Recipient token account missing"),
+    ("unsupported_transaction_version", "Unsupported transaction version"),
+    ("proof_generation_failed", "Proof generation failed"),
+    ("proof_verification_failed", "Proof verification failed"),
+    ("validation_error", "Validation failed"),
+    ("bad_request", "Bad request"),
+    ("conflict", "Conflict"),
+    ("external_service_error", "External service error"),
+    ("internal_error", "Internal server error"),
+    ("not_found", "Resource not found"),
+    ("forbidden", "Forbidden"),
+    ("service_unavailable", "Service unavailable"),
+    ("rate_limit_exceeded", "Rate limit exceeded"),
+    ("cooling_down", "Requested again too soon"),
+    ("account_locked", "Too many failed login attempts"),
+    ("payload_too_large", "Request body too large"),
+    ("maintenance", "Under maintenance"),
+];
+
+const ES: &[(&str, &str)] = &[
+    ("config_error", "Error de configuración"),
+    ("database_error", "Error de base de datos"),
+    ("migration_error", "Error de migración de la base de datos"),
+    ("serialization_error", "Error de serialización"),
+    ("authentication_failed", "Error de autenticación"),
+    ("unauthorized", "No autorizado"),
+    ("invalid_token", "Token no válido"),
+    ("blockchain_error", "Error de blockchain"),
+    ("transaction_failed", "La transacción falló"),
+    ("token_account_missing", "El destinatario no tiene cuenta de token"),
+    ("unsupported_transaction_version", "Versión de transacción no admitida"),
+    ("proof_generation_failed", "No se pudo generar la prueba"),
+    ("proof_verification_failed", "La verificación de la prueba falló"),
+    ("validation_error", "Error de validación"),
+    ("bad_request", "Solicitud incorrecta"),
+    ("conflict", "Conflicto"),
+    ("external_service_error", "Error de un servicio externo"),
+    ("internal_error", "Error interno del servidor"),
+    ("not_found", "Recurso no encontrado"),
+    ("forbidden", "Prohibido"),
+    ("service_unavailable", "Servicio no disponible"),
+    ("rate_limit_exceeded", "Límite de solicitudes excedido"),
+    ("cooling_down", "Solicitado de nuevo demasiado pronto"),
+    ("account_locked", "Demasiados intentos de inicio de sesión fallidos"),
+    ("payload_too_large", "El cuerpo de la solicitud es demasiado grande"),
+    ("maintenance", "En mantenimiento"),
+];
+
+fn catalog(locale: Locale) -> &'static [(&'static str, &'static str)] {
+    match locale {
+        Locale::En => EN,
+        Locale::Es => ES,
+    }
+}
+
+fn lookup(locale: Locale, code: &str) -> Option<&'static str> {
+    catalog(locale)
+        .iter()
+        .find(|(entry, _)| *entry == code)
+        .map(|(_, message)| *message)
+}
+
+/// Message for `code` in `locale`, falling back to English and then to the
+/// code itself
+pub fn message(code: &str, locale: Locale) -> &str {
+    lookup(locale, code)
+        .or_else(|| lookup(Locale::En, code))
+        .unwrap_or(code)
+}
+
+tokio::task_local! {
+    static REQUEST_LOCALE: Locale;
+}
+
+/// Run `future` with `locale` as the locale for error messages
+pub async fn with_locale<F: Future>(locale: Locale, future: F) -> F::Output {
+    REQUEST_LOCALE.scope(locale, future).await
+}
+
+/// Locale of the request being handled, or English outside of one
+pub fn current_locale() -> Locale {
+    REQUEST_LOCALE.try_with(|locale| *locale).unwrap_or_default()
+}
@@ -0,0 +1,200 @@
+//! Agent model loading
+//!
+//! Agent models are ONNX files keyed by `Agent.model_version`. Each model
+//! ships with a small JSON manifest describing its tensor shapes, so a
+//! model can be validated before it replaces the one currently serving.
+//!
+//! Models are looked up as `<source>/<model_version>.onnx` and
+//! `<source>/<model_version>.json`, where the source is either a local
+//! directory or an HTTP(S) object store prefix.
+
+use crate::{
+    config::ModelConfig,
+    error::{Error, Result},
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use uuid::Uuid;
+
+/// Tensor shapes declared by a model manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelManifest {
+    pub input_shape: Vec<usize>,
+    pub output_shape: Vec<usize>,
+}
+
+/// A model that passed shape validation and is ready to serve
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadedModel {
+    pub model_version: String,
+    pub manifest: ModelManifest,
+    /// Hex-encoded SHA-256 of the model file
+    pub checksum: String,
+    pub loaded_at: chrono::DateTime<chrono::Utc>,
+    #[serde(skip)]
+    pub bytes: Vec<u8>,
+}
+
+/// Where model files are fetched from
+#[derive(Debug, Clone)]
+pub enum ModelSource {
+    Directory(PathBuf),
+    ObjectStore(String),
+}
+
+impl ModelSource {
+    /// Treat HTTP(S) URLs as object store prefixes and anything else as a path
+    pub fn parse(source: &str) -> Self {
+        if source.starts_with("http://") || source.starts_with("https://") {
+            Self::ObjectStore(source.trim_end_matches('/').to_string())
+        } else {
+            Self::Directory(PathBuf::from(source))
+        }
+    }
+
+    async fn fetch(&self, file_name: &str) -> Result<Vec<u8>> {
+        match self {
+            Self::Directory(dir) => {
+                let path = dir.join(file_name);
+                tokio::fs::read(&path).await.map_err(|e| {
+                    Error::ExternalService(format!("Failed to read model file {}: {}", path.display(), e))
+                })
+            }
+            Self::ObjectStore(base_url) => {
+                let url = format!("{}/{}", base_url, file_name);
+                let response = reqwest::get(&url)
+                    .await
+                    .and_then(|response| response.error_for_status())
+                    .map_err(|e| Error::ExternalService(format!("Failed to fetch {}: {}", url, e)))?;
+                let bytes = response
+                    .bytes()
+                    .await
+                    .map_err(|e| Error::ExternalService(format!("Failed to fetch {}: {}", url, e)))?;
+                Ok(bytes.to_vec())
+            }
+        }
+    }
+}
+
+/// Loads and validates agent models
+#[derive(Debug, Clone)]
+pub struct ModelLoader {
+    source: ModelSource,
+    expected: ModelManifest,
+}
+
+impl ModelLoader {
+    pub fn new(config: &ModelConfig) -> Self {
+        Self {
+            source: ModelSource::parse(&config.source),
+            expected: ModelManifest {
+                input_shape: config.input_shape.clone(),
+                output_shape: config.output_shape.clone(),
+            },
+        }
+    }
+
+    /// Load a model version and check its shapes against the configured ones
+    pub async fn load(&self, model_version: &str) -> Result<LoadedModel> {
+        validate_model_version(model_version)?;
+
+        let manifest_bytes = self.source.fetch(&format!("{}.json", model_version)).await?;
+        let manifest: ModelManifest = serde_json::from_slice(&manifest_bytes)
+            .map_err(|e| Error::Validation(format!("Invalid manifest for model {}: {}", model_version, e)))?;
+
+        if manifest.input_shape != self.expected.input_shape {
+            return Err(Error::Validation(format!(
+                "Model {} input shape {:?} does not match expected {:?}",
+                model_version, manifest.input_shape, self.expected.input_shape
+            )));
+        }
+
+        if manifest.output_shape != self.expected.output_shape {
+            return Err(Error::Validation(format!(
+                "Model {} output shape {:?} does not match expected {:?}",
+                model_version, manifest.output_shape, self.expected.output_shape
+            )));
+        }
+
+        let bytes = self.source.fetch(&format!("{}.onnx", model_version)).await?;
+        if bytes.is_empty() {
+            return Err(Error::Validation(format!("Model {} file is empty", model_version)));
+        }
+
+        Ok(LoadedModel {
+            model_version: model_version.to_string(),
+            manifest,
+            checksum: hex::encode(Sha256::digest(&bytes)),
+            loaded_at: chrono::Utc::now(),
+            bytes,
+        })
+    }
+}
+
+/// Model versions become file names, so keep them to a safe character set
+fn validate_model_version(model_version: &str) -> Result<()> {
+    let is_safe = !model_version.is_empty()
+        && !model_version.starts_with('.')
+        && model_version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+
+    if !is_safe {
+        return Err(Error::Validation(format!("Invalid model version: {:?}", model_version)));
+    }
+
+    Ok(())
+}
+
+/// Models currently serving, keyed by agent ID
+#[derive(Clone)]
+pub struct ModelRegistry {
+    loader: ModelLoader,
+    models: Arc<RwLock<HashMap<Uuid, Arc<LoadedModel>>>>,
+}
+
+impl ModelRegistry {
+    pub fn new(loader: ModelLoader) -> Self {
+        Self {
+            loader,
+            models: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Model currently serving an agent, if one has been loaded
+    pub fn get(&self, agent_id: Uuid) -> Option<Arc<LoadedModel>> {
+        self.models
+            .read()
+            .expect("model registry lock poisoned")
+            .get(&agent_id)
+            .cloned()
+    }
+
+    /// Load `model_version` and swap it in for the agent.
+    ///
+    /// If loading or validation fails the previous model keeps serving and
+    /// the error is returned.
+    pub async fn reload(&self, agent_id: Uuid, model_version: &str) -> Result<Arc<LoadedModel>> {
+        let model = match self.loader.load(model_version).await {
+            Ok(model) => Arc::new(model),
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to load model {} for agent {}, keeping previous model: {}",
+                    model_version, agent_id, e
+                );
+                return Err(e);
+            }
+        };
+
+        self.models
+            .write()
+            .expect("model registry lock poisoned")
+            .insert(agent_id, model.clone());
+
+        tracing::info!("Loaded model {} for agent {}", model_version, agent_id);
+        Ok(model)
+    }
+}
@@ -8,8 +8,11 @@ pub mod blockchain;
 pub mod config;
 pub mod db;
 pub mod error;
+pub mod i18n;
+pub mod inference;
 pub mod server;
 pub mod services;
+pub mod telemetry;
 pub mod utils;
 pub mod zkml;
 
@@ -1,21 +1,35 @@
 //! Server initialization and startup
 
 use crate::{
-    api::{create_router, AppState},
-    blockchain::SolanaClient,
-    config::Config,
+    api::{
+        create_router,
+        routes::metrics_router,
+        middleware::{
+            logging::{request_logging_middleware, RequestLogSampler, RequestMetrics},
+            maintenance::MaintenanceMode,
+            request_id::REQUEST_ID_HEADER,
+        },
+        websocket::WebSocketShutdown,
+        AppState,
+    },
+    auth::AccountStatuses,
+    blockchain::{BalanceCache, ConfirmationPolicy, EthereumClient, GuardianVerifier, RetryPolicy, SolanaClient},
+    config::{Config, ServerConfig},
     db::Database,
     error::Result,
+    inference::{ModelLoader, ModelRegistry},
+    services::{PredictionCleanup, ProofJobQueue, TokenRegistry, TokenRegistryRefresher, TransactionEvents, TransactionMonitor, WebhookDispatcher},
 };
 use axum::Router;
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::signal;
 use tower::ServiceBuilder;
 use tower_http::{
     compression::CompressionLayer,
-    cors::{Any, CorsLayer},
-    trace::TraceLayer,
+    cors::{AllowOrigin, CorsLayer},
 };
 use tracing::info;
 
@@ -24,18 +38,33 @@ pub async fn run(config: Config, addr: SocketAddr) -> Result<()> {
     // Initialize database connection
     let db = Database::new(&config.database).await?;
     
-    // Run migrations - TODO: Re-enable when migrate feature is added
-    // db.run_migrations().await?;
+    // Bring the schema up to date before serving anything that queries it
+    if config.database.auto_migrate {
+        let applied = db.run_migrations().await.map_err(|e| {
+            crate::error::Error::Config(format!("Failed to apply database migrations: {}", e))
+        })?;
+        info!("Database schema is up to date ({} migrations applied)", applied.len());
+    }
     
     // Initialize Redis connection
     let redis_client = redis::Client::open(config.redis.url.clone())
         .map_err(|e| crate::error::Error::Config(format!("Failed to connect to Redis: {}", e)))?;
     
     // Initialize Solana client
-    let solana_client = SolanaClient::new(
-        &config.blockchain.solana_rpc_url,
+    let solana_client = SolanaClient::with_endpoints(
+        &config.blockchain.rpc_urls(),
         &config.blockchain.commitment,
-    )?;
+    )?
+    .with_balance_cache(BalanceCache::new(&config.blockchain.balance_cache))
+    .with_retry_policy(RetryPolicy::from(&config.blockchain.retry))
+    .with_confirmation_policy(ConfirmationPolicy::from(&config.blockchain.confirmation));
+    let solana_client = match GuardianVerifier::from_config(
+        &config.blockchain.guardian_program_id,
+        &config.blockchain.on_chain_verification,
+    )? {
+        Some(verifier) => solana_client.with_verifier(verifier),
+        None => solana_client,
+    };
     
     // Test Solana connection
     match solana_client.health_check().await {
@@ -44,8 +73,13 @@ pub async fn run(config: Config, addr: SocketAddr) -> Result<()> {
         Err(e) => info!("❌ Solana RPC connection failed: {}", e),
     }
     
+    let ethereum_client = EthereumClient::new(&config.blockchain.ethereum.rpc_url)?;
+    
     // Initialize ZKML service
-    let zkml_service = crate::zkml::ZkmlService::new()?;
+    let mut zkml_service = crate::zkml::ZkmlService::with_config(&config.zkml)?;
+    if config.zkml.proof_cache_ttl_secs > 0 {
+        zkml_service = zkml_service.with_proof_cache(redis_client.clone(), config.zkml.proof_cache_ttl_secs);
+    }
     
     // Test ZKML system
     match zkml_service.health_check() {
@@ -54,14 +88,72 @@ pub async fn run(config: Config, addr: SocketAddr) -> Result<()> {
         Err(e) => info!("❌ ZKML proof system failed: {}", e),
     }
     
+    // Agent models are loaded on demand through the reload endpoint
+    let model_registry = ModelRegistry::new(ModelLoader::new(&config.models));
+    
+    let account_statuses = AccountStatuses::new(db.clone(), config.auth.account_status_cache_secs);
+
     // Create application state
     let state = Arc::new(AppState {
         config: config.clone(),
         db,
         redis: redis_client,
         solana_client,
+        ethereum_client,
         zkml_service,
+        proof_jobs: ProofJobQueue::from_config(&config.zkml),
+        model_registry,
+        request_metrics: Arc::new(RequestMetrics::default()),
+        draining: Arc::new(AtomicBool::new(false)),
+        maintenance: MaintenanceMode::new(config.maintenance.clone()),
+        transaction_events: TransactionEvents::default(),
+        token_registry: TokenRegistry::default(),
+        websocket_shutdown: WebSocketShutdown::default(),
+        readiness: Default::default(),
+        account_statuses,
     });
+    let draining = state.draining.clone();
+    let websockets = state.websocket_shutdown.clone();
+    if state.maintenance.is_enabled() {
+        info!("🚧 Starting in maintenance mode");
+    }
+
+    #[cfg(unix)]
+    tokio::spawn(reload_maintenance_on_sighup(state.maintenance.clone()));
+
+    // Settle transactions whose submission timed out before confirming
+    if config.blockchain.monitor.enabled {
+        tokio::spawn(TransactionMonitor::new(state.clone()).run());
+    }
+
+    if config.blockchain.token_registry.source.is_some() {
+        tokio::spawn(TokenRegistryRefresher::new(state.clone()).run());
+    }
+
+    if config.webhooks.enabled {
+        tokio::spawn(WebhookDispatcher::new(state.clone()).run());
+    }
+
+    if config.prediction_cleanup.enabled {
+        tokio::spawn(PredictionCleanup::new(state.clone()).run());
+    }
+    let drain_delay = Duration::from_secs(config.server.shutdown_drain_secs);
+    let websocket_close_timeout = Duration::from_secs(config.websocket.close_timeout_secs);
+
+    // Metrics get their own listener when a port is configured for them
+    if let (true, Some(port)) = (config.metrics.enabled, config.metrics.port) {
+        let metrics_addr = SocketAddr::new(addr.ip(), port);
+        let listener = tokio::net::TcpListener::bind(metrics_addr).await
+            .map_err(|e| crate::error::Error::Config(format!("Failed to bind metrics to {}: {}", metrics_addr, e)))?;
+        info!("Metrics listening on {}", metrics_addr);
+
+        let metrics_app = metrics_router(state.clone());
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, metrics_app).await {
+                tracing::error!("Metrics server failed: {}", e);
+            }
+        });
+    }
     
     // Create the application router
     let app = create_app(state, &config)?;
@@ -72,26 +164,31 @@ pub async fn run(config: Config, addr: SocketAddr) -> Result<()> {
     
     info!("Server listening on {}", addr);
     
-    // Run the server with graceful shutdown
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
+    // Run the server with graceful shutdown; the peer address is kept for
+    // rate limiting clients that don't come through a proxy
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown_signal(draining, drain_delay, websockets, websocket_close_timeout))
         .await
         .map_err(|e| crate::error::Error::Other(e.into()))?;
     
     Ok(())
 }
 
-/// Create the application with all middleware
-fn create_app(state: Arc<AppState>, config: &Config) -> Result<Router> {
-    // Configure CORS
-    let cors = CorsLayer::new()
-        .allow_origin(
-            config
-                .server
-                .cors_origin
+/// CORS for the configured origins. Credentials are allowed, so only the
+/// listed origins are echoed back; any other origin gets no CORS headers.
+pub fn cors_layer(config: &ServerConfig) -> Result<CorsLayer> {
+    let origins = config
+        .cors_origins
+        .iter()
+        .map(|origin| {
+            origin
                 .parse::<axum::http::HeaderValue>()
-                .map_err(|e| crate::error::Error::Config(format!("Invalid CORS origin: {}", e)))?,
-        )
+                .map_err(|e| crate::error::Error::Config(format!("Invalid CORS origin {:?}: {}", origin, e)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(CorsLayer::new()
+        .allow_origin(AllowOrigin::predicate(move |origin, _| origins.contains(origin)))
         .allow_methods([
             axum::http::Method::GET,
             axum::http::Method::POST,
@@ -104,14 +201,24 @@ fn create_app(state: Arc<AppState>, config: &Config) -> Result<Router> {
             axum::http::header::CONTENT_TYPE,
             axum::http::header::ACCEPT,
             axum::http::header::ORIGIN,
+            REQUEST_ID_HEADER,
         ])
-        .allow_credentials(true);
+        .expose_headers([REQUEST_ID_HEADER])
+        .allow_credentials(true))
+}
+
+/// Create the application with all middleware
+fn create_app(state: Arc<AppState>, config: &Config) -> Result<Router> {
+    let cors = cors_layer(&config.server)?;
+    
+    // Log failed and slow requests, and a sample of the rest
+    let sampler = RequestLogSampler::new(&config.logging, state.request_metrics.clone());
     
     // Create the main router
     let app = create_router(state)
         .layer(
             ServiceBuilder::new()
-                .layer(TraceLayer::new_for_http())
+                .layer(axum::middleware::from_fn_with_state(sampler, request_logging_middleware))
                 .layer(cors)
                 .layer(CompressionLayer::new()),
         );
@@ -119,8 +226,44 @@ fn create_app(state: Arc<AppState>, config: &Config) -> Result<Router> {
     Ok(app)
 }
 
-/// Graceful shutdown signal handler
-async fn shutdown_signal() {
+/// Re-read the maintenance settings from the configuration on each SIGHUP,
+/// so maintenance mode can be toggled without a restart
+#[cfg(unix)]
+async fn reload_maintenance_on_sighup(maintenance: MaintenanceMode) {
+    let mut hangup = match signal::unix::signal(signal::unix::SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(e) => {
+            tracing::warn!("Failed to install SIGHUP handler: {}", e);
+            return;
+        }
+    };
+
+    while hangup.recv().await.is_some() {
+        match Config::load() {
+            Ok(config) => {
+                info!(
+                    "Configuration reloaded, maintenance mode {}",
+                    if config.maintenance.enabled { "on" } else { "off" }
+                );
+                maintenance.set(config.maintenance);
+            }
+            Err(e) => tracing::error!("Failed to reload configuration: {}", e),
+        }
+    }
+}
+
+/// Graceful shutdown signal handler.
+///
+/// Once a signal arrives the server is marked as draining and keeps serving
+/// for `drain_delay`, giving load balancers time to notice the failing
+/// readiness check before connections are closed. Open WebSockets are then
+/// sent a close frame, and given up to `websocket_close_timeout` to go.
+async fn shutdown_signal(
+    draining: Arc<AtomicBool>,
+    drain_delay: Duration,
+    websockets: WebSocketShutdown,
+    websocket_close_timeout: Duration,
+) {
     let ctrl_c = async {
         signal::ctrl_c()
             .await
@@ -143,5 +286,12 @@ async fn shutdown_signal() {
         _ = terminate => {},
     }
 
-    info!("Shutdown signal received");
+    info!("Shutdown signal received, draining for {}s", drain_delay.as_secs());
+    draining.store(true, Ordering::SeqCst);
+    tokio::time::sleep(drain_delay).await;
+
+    info!("Closing {} WebSocket connections", websockets.active());
+    if !websockets.close_all(websocket_close_timeout).await {
+        tracing::warn!("{} WebSocket connections still open after {}s", websockets.active(), websocket_close_timeout.as_secs());
+    }
 } 
\ No newline at end of file
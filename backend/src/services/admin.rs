@@ -0,0 +1,146 @@
+//! Operator dashboard service
+
+use crate::{
+    api::AppState,
+    auth::TokenDenylist,
+    db::{
+        models::{AgentPredictionCount, DashboardCounts, Role},
+        queries::{StatsQueries, UserQueries},
+    },
+    error::{Error, Result},
+};
+use chrono::{DateTime, Utc};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Redis key the latest stats are cached under
+const STATS_CACHE_KEY: &str = "admin_stats";
+
+pub struct AdminService {
+    state: Arc<AppState>,
+}
+
+impl AdminService {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+
+    /// System stats, served from cache for `admin.stats_cache_ttl_secs`
+    pub async fn stats(&self) -> Result<AdminStats> {
+        if let Some(stats) = self.cached_stats().await {
+            return Ok(stats);
+        }
+
+        let stats = self.compute_stats().await?;
+        self.cache_stats(&stats).await;
+        Ok(stats)
+    }
+
+    /// Compute the stats afresh, counting from midnight UTC
+    pub async fn compute_stats(&self) -> Result<AdminStats> {
+        let generated_at = Utc::now();
+        let day_start = generated_at
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .expect("midnight is a valid time")
+            .and_utc();
+
+        let pool = self.state.db.read_pool();
+        let counts = StatsQueries::dashboard_counts(pool, day_start).await?;
+        let predictions_by_agent = StatsQueries::predictions_by_agent(pool, day_start).await?;
+        let dependencies = self.dependency_health().await;
+
+        Ok(AdminStats {
+            counts,
+            predictions_by_agent,
+            dependencies,
+            since: day_start,
+            generated_at,
+        })
+    }
+
+    /// Give the user `role`. When it changes, every token the user holds is
+    /// revoked, so they sign in again under the new role.
+    pub async fn set_role(&self, user_id: Uuid, role: Role) -> Result<()> {
+        let status = UserQueries::find_status(self.state.db.pool(), user_id).await?
+            .ok_or(Error::NotFound)?;
+        if status.role() == role {
+            return Ok(());
+        }
+
+        UserQueries::set_admin(self.state.db.pool(), user_id, role == Role::Admin).await?;
+        self.state.account_statuses.forget(user_id);
+
+        let auth = &self.state.config.auth;
+        let token_lifetime = auth.jwt_expiration.max(auth.refresh_token_expiration).max(0) as u64;
+        TokenDenylist::new(self.state.redis.clone())
+            .revoke_user(&user_id.to_string(), token_lifetime)
+            .await
+    }
+
+    /// Whether each external dependency is reachable
+    pub async fn dependency_health(&self) -> DependencyHealth {
+        let database = self.state.db.health_check().await.is_ok();
+        let redis = match self.state.redis.get_multiplexed_async_connection().await {
+            Ok(mut conn) => redis::cmd("PING").query_async::<_, String>(&mut conn).await.is_ok(),
+            Err(_) => false,
+        };
+        let solana_rpc = matches!(self.state.solana_client.health_check().await, Ok(true));
+        let zkml = matches!(self.state.zkml_service.health_check(), Ok(true));
+
+        DependencyHealth { database, redis, solana_rpc, zkml }
+    }
+
+    async fn cached_stats(&self) -> Option<AdminStats> {
+        if self.state.config.admin.stats_cache_ttl_secs == 0 {
+            return None;
+        }
+
+        let mut conn = self.state.redis.get_multiplexed_async_connection().await.ok()?;
+        let value: Option<String> = conn.get(STATS_CACHE_KEY).await.ok()?;
+        serde_json::from_str(&value?).ok()
+    }
+
+    async fn cache_stats(&self, stats: &AdminStats) {
+        let ttl = self.state.config.admin.stats_cache_ttl_secs;
+        if ttl == 0 {
+            return;
+        }
+        let Ok(value) = serde_json::to_string(stats) else {
+            return;
+        };
+
+        match self.state.redis.get_multiplexed_async_connection().await {
+            Ok(mut conn) => {
+                if let Err(e) = conn.set_ex::<_, _, ()>(STATS_CACHE_KEY, value, ttl).await {
+                    tracing::debug!(error = %e, "Failed to cache admin stats");
+                }
+            }
+            Err(e) => tracing::debug!(error = %e, "Failed to cache admin stats"),
+        }
+    }
+}
+
+/// Summary of the system's state for operators
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminStats {
+    #[serde(flatten)]
+    pub counts: DashboardCounts,
+    /// Predictions made today by each agent
+    pub predictions_by_agent: Vec<AgentPredictionCount>,
+    pub dependencies: DependencyHealth,
+    /// Start of the day the "today" counts cover
+    pub since: DateTime<Utc>,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Reachability of the services the backend depends on
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DependencyHealth {
+    pub database: bool,
+    pub redis: bool,
+    pub solana_rpc: bool,
+    pub zkml: bool,
+}
\ No newline at end of file
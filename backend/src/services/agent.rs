@@ -1,27 +1,49 @@
 //! Agent service
 
 use crate::{
-    api::AppState,
+    api::{pagination::{Page, Paginated}, AppState},
+    config::CooldownResponse,
     db::{models::*, queries::*},
     error::{Error, Result},
+    inference::LoadedModel,
+    services::{
+        agent_inference::AgentInferenceRegistry,
+        allocation::{self, AssetAllocations, RebalanceTrade},
+        ensemble::{self, EnsembleResult},
+        wallet::WalletBalance,
+        webhook::{self, WebhookEvent},
+    },
 };
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use uuid::Uuid;
 use chrono::{DateTime, Utc, Duration};
 use serde_json;
+use base64::{Engine as _, engine::general_purpose};
+use redis::AsyncCommands;
 
 pub struct AgentService {
     state: Arc<AppState>,
+    inference: AgentInferenceRegistry,
 }
 
 impl AgentService {
     pub fn new(state: Arc<AppState>) -> Self {
-        Self { state }
+        Self {
+            state,
+            inference: AgentInferenceRegistry::default(),
+        }
+    }
+
+    /// Run agents' models through `inference` instead of the mock backends
+    pub fn with_inference(mut self, inference: AgentInferenceRegistry) -> Self {
+        self.inference = inference;
+        self
     }
 
     /// Get all active agents
     pub async fn get_active_agents(&self) -> Result<Vec<Agent>> {
-        let agents = AgentQueries::find_active(self.state.db.pool()).await?;
+        let agents = AgentQueries::find_active(self.state.db.read_pool()).await?;
         Ok(agents)
     }
 
@@ -34,7 +56,7 @@ impl AgentService {
 
     /// Get agents by type
     pub async fn get_agents_by_type(&self, agent_type: AgentType) -> Result<Vec<Agent>> {
-        let agents = AgentQueries::find_by_type(self.state.db.pool(), agent_type).await?;
+        let agents = AgentQueries::find_by_type(self.state.db.read_pool(), agent_type).await?;
         Ok(agents)
     }
 
@@ -59,19 +81,50 @@ impl AgentService {
         // Set expiration time (24 hours from now)
         let expires_at = Utc::now() + Duration::hours(24);
 
-        // Create the prediction
-        let prediction = AgentPredictionQueries::create(
-            self.state.db.pool(),
-            prediction_request.agent_id,
-            user_id,
-            &prediction_request.asset_symbol,
-            prediction_request.prediction,
-            prediction_request.confidence,
-            &explanation_hash,
-            &prediction_request.explanation_text,
-            &prediction_request.data_sources,
-            expires_at,
-        ).await?;
+        // Prove the explanation up front so the transaction below stays short
+        let proof = if prediction_request.generate_proof {
+            let proof = self.state.zkml_service
+                .generate_sha256_proof(prediction_request.explanation_text.as_bytes())
+                .await?;
+            let verification_key_hash = self.state.zkml_service.verification_key_hash()?;
+            let circuit_hash = agent.circuit_hash.clone().unwrap_or_else(|| verification_key_hash.clone());
+            Some((proof, verification_key_hash, circuit_hash))
+        } else {
+            None
+        };
+
+        // Create the prediction and its proof atomically
+        let prediction = self.state.db.transaction(move |conn| Box::pin(async move {
+            let prediction = AgentPredictionQueries::create(
+                &mut *conn,
+                prediction_request.agent_id,
+                user_id,
+                &prediction_request.asset_symbol,
+                prediction_request.prediction,
+                prediction_request.confidence,
+                &explanation_hash,
+                &prediction_request.explanation_text,
+                &prediction_request.data_sources,
+                expires_at,
+            ).await?;
+
+            if let Some((proof, verification_key_hash, circuit_hash)) = proof {
+                ZkmlProofQueries::create(
+                    &mut *conn,
+                    Some(prediction.id),
+                    user_id,
+                    ProofType::AgentProof,
+                    &general_purpose::STANDARD.encode(&proof.proof_data),
+                    &serde_json::json!(hex::encode(&proof.public_inputs)),
+                    &verification_key_hash,
+                    &circuit_hash,
+                ).await?;
+            }
+
+            webhook::enqueue_event(&mut *conn, user_id, WebhookEvent::PredictionCreated, &prediction).await?;
+
+            Ok(prediction)
+        })).await?;
 
         Ok(prediction)
     }
@@ -80,11 +133,12 @@ impl AgentService {
     pub async fn get_user_predictions(
         &self,
         user_id: Uuid,
-        limit: i64,
-        offset: i64,
-    ) -> Result<Vec<AgentPrediction>> {
-        let predictions = AgentPredictionQueries::find_by_user_id(self.state.db.pool(), user_id, limit, offset).await?;
-        Ok(predictions)
+        page: Page,
+    ) -> Result<Paginated<AgentPrediction>> {
+        let pool = self.state.db.read_pool();
+        let predictions = AgentPredictionQueries::find_by_user_id(pool, user_id, page.limit, page.offset).await?;
+        let total = AgentPredictionQueries::count_by_user_id(pool, user_id).await?;
+        Ok(Paginated::new(predictions, total, page))
     }
 
     /// Get predictions for an asset
@@ -92,14 +146,17 @@ impl AgentService {
         &self,
         user_id: Uuid,
         asset_symbol: &str,
-    ) -> Result<Vec<AgentPrediction>> {
-        let predictions = AgentPredictionQueries::find_by_asset(self.state.db.pool(), user_id, asset_symbol).await?;
-        Ok(predictions)
+        page: Page,
+    ) -> Result<Paginated<AgentPrediction>> {
+        let pool = self.state.db.read_pool();
+        let predictions = AgentPredictionQueries::find_by_asset(pool, user_id, asset_symbol, page.limit, page.offset).await?;
+        let total = AgentPredictionQueries::count_by_asset(pool, user_id, asset_symbol).await?;
+        Ok(Paginated::new(predictions, total, page))
     }
 
     /// Get prediction by ID
     pub async fn get_prediction(&self, prediction_id: Uuid, user_id: Uuid) -> Result<AgentPrediction> {
-        let prediction = AgentPredictionQueries::find_by_id(self.state.db.pool(), prediction_id).await?
+        let prediction = AgentPredictionQueries::find_by_id(self.state.db.read_pool(), prediction_id).await?
             .ok_or(Error::NotFound)?;
 
         // Ensure the prediction belongs to the user
@@ -110,54 +167,203 @@ impl AgentService {
         Ok(prediction)
     }
 
-    /// Generate market analysis using ensemble of agents
+    /// Check whether a prediction's stored proof is currently valid.
+    ///
+    /// The latest proof for the prediction is run through the verifier with
+    /// the current verifying key, and the stored explanation text is checked
+    /// against both the explanation hash and the digest the proof attests to.
+    pub async fn verify_prediction_proof(&self, prediction_id: Uuid, user_id: Uuid) -> Result<PredictionProofCheck> {
+        let prediction = self.get_prediction(prediction_id, user_id).await?;
+
+        let proof = ZkmlProofQueries::find_by_prediction_id(self.state.db.pool(), prediction_id).await?
+            .into_iter()
+            .next()
+            .ok_or(Error::NotFound)?;
+
+        let explanation_digest: [u8; 32] = {
+            use sha2::{Sha256, Digest};
+            Sha256::digest(prediction.explanation_text.as_bytes()).into()
+        };
+        let proven_hash = proof.public_inputs.as_str()
+            .and_then(|inputs| hex::decode(inputs).ok())
+            .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok());
+
+        let explanation_matches = self.generate_explanation_hash(&prediction.explanation_text) == prediction.explanation_hash
+            && proven_hash == Some(explanation_digest);
+
+        // Only the current verifying key is loaded, so a proof made under an
+        // older key can't be checked and is reported as invalid
+        let vk_hash = self.state.zkml_service.verification_key_hash()?;
+        let proof_valid = match (proven_hash, general_purpose::STANDARD.decode(&proof.proof_data)) {
+            (Some(hash), Ok(proof_bytes)) if proof.verification_key_hash == vk_hash => {
+                self.state.zkml_service.verify_proof_bytes(&hash, &proof_bytes).await?
+            }
+            _ => false,
+        };
+
+        Ok(PredictionProofCheck {
+            prediction_id,
+            proof_id: proof.id,
+            proof_valid,
+            explanation_matches,
+            vk_hash,
+        })
+    }
+
+    /// Check a claimed explanation against the hash committed when the
+    /// prediction was made, so a client holding the text off-chain can tell
+    /// whether it is the explanation the prediction was recorded with
+    pub async fn verify_explanation(
+        &self,
+        prediction_id: Uuid,
+        user_id: Uuid,
+        explanation_text: &str,
+    ) -> Result<ExplanationCheck> {
+        let prediction = self.get_prediction(prediction_id, user_id).await?;
+        let computed_hash = self.generate_explanation_hash(explanation_text);
+
+        Ok(ExplanationCheck {
+            prediction_id,
+            matches: computed_hash == prediction.explanation_hash,
+            explanation_hash: prediction.explanation_hash,
+            computed_hash,
+        })
+    }
+
+    /// Generate market analysis using ensemble of agents. A user asking for
+    /// the same asset again within `analysis.min_interval_secs` gets the
+    /// previous analysis, or a cool-down error, instead of a new run.
     pub async fn generate_market_analysis(
         &self,
         user_id: Uuid,
         asset_symbol: &str,
         market_data: MarketAnalysisRequest,
     ) -> Result<MarketAnalysis> {
+        let cooldown_key = format!("analysis_cooldown:{}:{}", user_id, asset_symbol.to_uppercase());
+        if let Some((previous, retry_after_secs)) = self.previous_analysis(&cooldown_key).await {
+            return match self.state.config.analysis.on_cooldown {
+                CooldownResponse::Cached => Ok(previous),
+                CooldownResponse::Reject => Err(Error::CoolingDown { retry_after_secs }),
+            };
+        }
+
+        let mut analysis = self.analyze_market(asset_symbol, &market_data).await?;
+        let recommendation = self.generate_portfolio_recommendation(user_id, &analysis.ensemble_result)?;
+        let recommendation = self.save_portfolio_recommendation(recommendation).await?;
+        analysis.portfolio_recommendation = Some(recommendation);
+
+        self.remember_analysis(&cooldown_key, &analysis).await;
+        Ok(analysis)
+    }
+
+    /// Run the same analysis as [`AgentService::generate_market_analysis`]
+    /// without saving anything, to preview what it would recommend. The
+    /// recommendation comes back with a nil id. Previews neither start nor
+    /// wait out the cool-down, so committing right after one still works.
+    pub async fn simulate_market_analysis(
+        &self,
+        user_id: Uuid,
+        asset_symbol: &str,
+        market_data: MarketAnalysisRequest,
+    ) -> Result<MarketAnalysis> {
+        let mut analysis = self.analyze_market(asset_symbol, &market_data).await?;
+        analysis.portfolio_recommendation = Some(self.generate_portfolio_recommendation(user_id, &analysis.ensemble_result)?);
+        analysis.dry_run = true;
+        Ok(analysis)
+    }
+
+    /// Run every agent on `market_data` and combine their predictions,
+    /// without a recommendation yet
+    async fn analyze_market(&self, asset_symbol: &str, market_data: &MarketAnalysisRequest) -> Result<MarketAnalysis> {
         // Get all active agents
         let agents = self.get_active_agents().await?;
 
-        // TODO: Run each agent's model on the market data
-        // For now, simulate agent predictions
+        // Run each agent's model on the market data; the ensemble agent only
+        // aggregates the others
         let mut agent_predictions = Vec::new();
 
-        for agent in &agents {
-            if agent.agent_type != AgentType::Ensemble {
-                let prediction = self.simulate_agent_prediction(agent, &market_data).await?;
-                agent_predictions.push(prediction);
-            }
+        for agent in agents.iter().filter(|agent| agent.agent_type != AgentType::Ensemble) {
+            let Some(inference) = self.inference.get(&agent.agent_type) else {
+                tracing::warn!(agent_id = %agent.id, agent_type = ?agent.agent_type, "No inference backend for agent, skipping it");
+                continue;
+            };
+            agent_predictions.push(inference.predict(agent, market_data).await?);
         }
 
         // Aggregate predictions using ensemble logic
-        let ensemble_result = self.aggregate_predictions(&agent_predictions)?;
-
-        // Generate portfolio recommendation
-        let recommendation = self.generate_portfolio_recommendation(
-            user_id,
-            &ensemble_result,
-            &market_data,
-        ).await?;
+        let ensemble_result = ensemble::aggregate_predictions(
+            &agents,
+            &agent_predictions,
+            &self.state.config.analysis.agent_weights,
+        )?;
+        if !ensemble_result.excluded.is_empty() {
+            tracing::debug!(excluded = ?ensemble_result.excluded, "Agents left out of the ensemble vote");
+        }
 
         Ok(MarketAnalysis {
             asset_symbol: asset_symbol.to_string(),
             analysis_timestamp: Utc::now(),
             agent_predictions,
             ensemble_result: ensemble_result.clone(),
-            portfolio_recommendation: Some(recommendation),
+            portfolio_recommendation: None,
             confidence_score: ensemble_result.confidence,
             risk_assessment: self.assess_risk(&ensemble_result),
+            dry_run: false,
         })
     }
 
+    /// The analysis still cooling down under `key`, with the seconds left.
+    /// Without Redis there is no cool-down.
+    async fn previous_analysis(&self, key: &str) -> Option<(MarketAnalysis, u64)> {
+        if self.state.config.analysis.min_interval_secs == 0 {
+            return None;
+        }
+
+        let mut conn = self.state.redis.get_multiplexed_async_connection().await.ok()?;
+        let (value, ttl): (Option<String>, i64) = redis::pipe()
+            .get(key)
+            .ttl(key)
+            .query_async(&mut conn)
+            .await
+            .ok()?;
+
+        let analysis = serde_json::from_str(&value?).ok()?;
+        Some((analysis, ttl.max(1) as u64))
+    }
+
+    /// Start the cool-down for `key`
+    async fn remember_analysis(&self, key: &str, analysis: &MarketAnalysis) {
+        let interval = self.state.config.analysis.min_interval_secs;
+        if interval == 0 {
+            return;
+        }
+        let Ok(value) = serde_json::to_string(analysis) else {
+            return;
+        };
+
+        match self.state.redis.get_multiplexed_async_connection().await {
+            Ok(mut conn) => {
+                if let Err(e) = conn.set_ex::<_, _, ()>(key, value, interval).await {
+                    tracing::debug!(key, error = %e, "Failed to start analysis cool-down");
+                }
+            }
+            Err(e) => tracing::debug!(key, error = %e, "Failed to start analysis cool-down"),
+        }
+    }
+
     /// Update agent circuit hash (for ZKML integration)
     pub async fn update_agent_circuit(&self, agent_id: Uuid, circuit_hash: &str) -> Result<()> {
         AgentQueries::update_circuit_hash(self.state.db.pool(), agent_id, circuit_hash).await?;
         Ok(())
     }
 
+    /// Load the agent's current model version and swap it in, keeping the
+    /// previous model if the new one fails to load
+    pub async fn reload_agent_model(&self, agent_id: Uuid) -> Result<Arc<LoadedModel>> {
+        let agent = self.get_agent(agent_id).await?;
+        self.state.model_registry.reload(agent.id, &agent.model_version).await
+    }
+
     /// Clean up expired predictions
     pub async fn cleanup_expired_predictions(&self) -> Result<u64> {
         let count = AgentPredictionQueries::cleanup_expired(self.state.db.pool()).await?;
@@ -189,132 +395,71 @@ impl AgentService {
         format!("{:x}", hasher.finalize())
     }
 
-    /// Simulate agent prediction (placeholder for actual ML inference)
-    async fn simulate_agent_prediction(
-        &self,
-        agent: &Agent,
-        _market_data: &MarketAnalysisRequest,
-    ) -> Result<AgentPredictionResult> {
-        // TODO: Integrate with actual ML models
-        // For now, simulate based on agent type
-        let (prediction, confidence) = match agent.agent_type {
-            AgentType::NewsSentiment => (PredictionType::Bullish, 0.7),
-            AgentType::MarketFactor => (PredictionType::Neutral, 0.6),
-            AgentType::TechnicalAnalysis => (PredictionType::Bearish, 0.8),
-            AgentType::CryptoFactor => (PredictionType::Bullish, 0.65),
-            AgentType::Ensemble => return Err(Error::Internal), // Should not be called for ensemble
-        };
-
-        Ok(AgentPredictionResult {
-            agent_id: agent.id,
-            agent_name: agent.name.clone(),
-            agent_type: agent.agent_type.clone(),
-            prediction,
-            confidence,
-            reasoning: format!("Simulated prediction from {} agent", agent.name),
-        })
-    }
-
-    /// Aggregate predictions using ensemble logic
-    fn aggregate_predictions(&self, predictions: &[AgentPredictionResult]) -> Result<EnsembleResult> {
-        if predictions.is_empty() {
-            return Err(Error::BadRequest("No predictions to aggregate".to_string()));
-        }
-
-        // Calculate weighted average confidence
-        let total_confidence: f64 = predictions.iter().map(|p| p.confidence).sum();
-        let avg_confidence = total_confidence / predictions.len() as f64;
-
-        // Determine overall prediction based on majority vote weighted by confidence
-        let mut bullish_weight = 0.0;
-        let mut bearish_weight = 0.0;
-        let mut neutral_weight = 0.0;
-
-        for pred in predictions {
-            match pred.prediction {
-                PredictionType::Bullish => bullish_weight += pred.confidence,
-                PredictionType::Bearish => bearish_weight += pred.confidence,
-                PredictionType::Neutral => neutral_weight += pred.confidence,
-            }
-        }
-
-        let overall_prediction = if bullish_weight > bearish_weight && bullish_weight > neutral_weight {
-            PredictionType::Bullish
-        } else if bearish_weight > neutral_weight {
-            PredictionType::Bearish
-        } else {
-            PredictionType::Neutral
-        };
-
-        Ok(EnsembleResult {
-            prediction: overall_prediction,
-            confidence: avg_confidence,
-            agent_count: predictions.len(),
-            consensus_strength: self.calculate_consensus_strength(predictions),
+    /// Portfolio recommendation for `user_id` following `ensemble_result`,
+    /// not yet saved: its id is nil
+    fn generate_portfolio_recommendation(&self, user_id: Uuid, ensemble_result: &EnsembleResult) -> Result<PortfolioRecommendation> {
+        // Determine recommendation type and allocations based on prediction
+        let config = &self.state.config.analysis;
+        let (recommendation_type, allocations) = allocation::portfolio_allocation(
+            ensemble_result,
+            config.allocation_scale.max(1),
+            config.allocation_rounding,
+        );
+
+        Ok(PortfolioRecommendation {
+            id: Uuid::nil(),
+            user_id,
+            recommendation_type,
+            asset_allocations: serde_json::to_value(&allocations)?,
+            cash_ratio: allocations.cash_ratio(),
+            crypto_ratio: allocations.crypto_ratio(),
+            confidence_score: ensemble_result.confidence,
+            reasoning: format!("Recommendation based on ensemble prediction: {:?}", ensemble_result.prediction),
+            zkml_proof_id: None, // No ZKML proof yet
+            is_executed: false,
+            created_at: Utc::now(),
+            executed_at: None,
         })
     }
 
-    /// Calculate consensus strength among agents
-    fn calculate_consensus_strength(&self, predictions: &[AgentPredictionResult]) -> f64 {
-        if predictions.len() <= 1 {
-            return 1.0;
-        }
-
-        // Count predictions by type
-        let mut counts = std::collections::HashMap::new();
-        for pred in predictions {
-            *counts.entry(pred.prediction.clone()).or_insert(0) += 1;
-        }
-
-        // Find the maximum count
-        let max_count = counts.values().max().unwrap_or(&0);
-        *max_count as f64 / predictions.len() as f64
-    }
-
-    /// Generate portfolio recommendation
-    async fn generate_portfolio_recommendation(
-        &self,
-        user_id: Uuid,
-        ensemble_result: &EnsembleResult,
-        _market_data: &MarketAnalysisRequest,
-    ) -> Result<PortfolioRecommendation> {
-        // Determine recommendation type and allocations based on prediction
-        let (recommendation_type, crypto_ratio, cash_ratio) = match ensemble_result.prediction {
-            PredictionType::Bullish => {
-                let ratio = 0.7 + (ensemble_result.confidence - 0.5) * 0.4;
-                (RecommendationType::Buy, ratio.min(0.9), 1.0 - ratio.min(0.9))
-            }
-            PredictionType::Bearish => {
-                let ratio = 0.3 - (ensemble_result.confidence - 0.5) * 0.4;
-                (RecommendationType::Sell, ratio.max(0.1), 1.0 - ratio.max(0.1))
-            }
-            PredictionType::Neutral => (RecommendationType::Hold, 0.5, 0.5),
-        };
-
-        // Create asset allocations
-        let asset_allocations = serde_json::json!({
-            "SOL": crypto_ratio * 0.6,
-            "BTC": crypto_ratio * 0.3,
-            "ETH": crypto_ratio * 0.1,
-            "CASH": cash_ratio
-        });
-
-        // Create recommendation
+    /// Save `recommendation`, returning it as stored
+    async fn save_portfolio_recommendation(&self, recommendation: PortfolioRecommendation) -> Result<PortfolioRecommendation> {
         let recommendation = PortfolioRecommendationQueries::create(
             self.state.db.pool(),
-            user_id,
-            recommendation_type,
-            &asset_allocations,
-            cash_ratio,
-            crypto_ratio,
-            ensemble_result.confidence,
-            &format!("Recommendation based on ensemble prediction: {:?}", ensemble_result.prediction),
-            None, // No ZKML proof yet
+            recommendation.user_id,
+            recommendation.recommendation_type,
+            &recommendation.asset_allocations,
+            recommendation.cash_ratio,
+            recommendation.crypto_ratio,
+            recommendation.confidence_score,
+            &recommendation.reasoning,
+            recommendation.zkml_proof_id,
         ).await?;
 
         Ok(recommendation)
     }
 
+    /// Trades that take the holdings in `balance` (as reported by
+    /// [`WalletService::get_wallet_balance`](crate::services::WalletService::get_wallet_balance))
+    /// to `recommendation`'s allocation. `prices` gives the cash value of one
+    /// unit of each asset held or recommended.
+    pub fn rebalance_trades(
+        &self,
+        balance: &WalletBalance,
+        recommendation: &PortfolioRecommendation,
+        prices: &BTreeMap<String, f64>,
+    ) -> Result<Vec<RebalanceTrade>> {
+        let target: AssetAllocations = serde_json::from_value(recommendation.asset_allocations.clone())
+            .map_err(|_| Error::Validation("Recommendation has no fixed-point asset allocations".to_string()))?;
+
+        allocation::rebalance_trades(
+            &allocation::wallet_holdings(balance),
+            prices,
+            &target,
+            self.state.config.analysis.min_trade_value,
+        )
+    }
+
     /// Assess risk based on ensemble result
     fn assess_risk(&self, ensemble_result: &EnsembleResult) -> RiskAssessment {
         let risk_level = if ensemble_result.confidence > 0.8 && ensemble_result.consensus_strength > 0.7 {
@@ -343,6 +488,35 @@ pub struct CreatePredictionRequest {
     pub confidence: f64,
     pub explanation_text: String,
     pub data_sources: serde_json::Value,
+    /// Also generate and store a ZK proof of the explanation
+    #[serde(default)]
+    pub generate_proof: bool,
+}
+
+/// Result of re-checking a prediction's stored proof
+#[derive(Debug, serde::Serialize)]
+pub struct PredictionProofCheck {
+    pub prediction_id: Uuid,
+    pub proof_id: Uuid,
+    /// The proof verifies under the current verifying key
+    pub proof_valid: bool,
+    /// The stored explanation still hashes to the recorded and proven digest
+    pub explanation_matches: bool,
+    /// Fingerprint of the verifying key the proof was checked against
+    pub vk_hash: String,
+}
+
+/// Result of checking a claimed explanation against a prediction's
+/// committed hash
+#[derive(Debug, serde::Serialize)]
+pub struct ExplanationCheck {
+    pub prediction_id: Uuid,
+    /// The claimed text hashes to the committed hash
+    pub matches: bool,
+    /// SHA256 recorded when the prediction was made, in hex
+    pub explanation_hash: String,
+    /// SHA256 of the claimed text, in hex
+    pub computed_hash: String,
 }
 
 /// Market analysis request
@@ -356,7 +530,7 @@ pub struct MarketAnalysisRequest {
 }
 
 /// Market analysis response
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct MarketAnalysis {
     pub asset_symbol: String,
     pub analysis_timestamp: DateTime<Utc>,
@@ -365,10 +539,13 @@ pub struct MarketAnalysis {
     pub portfolio_recommendation: Option<PortfolioRecommendation>,
     pub confidence_score: f64,
     pub risk_assessment: RiskAssessment,
+    /// Whether this is a preview whose recommendation was never saved
+    #[serde(default)]
+    pub dry_run: bool,
 }
 
 /// Individual agent prediction result
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct AgentPredictionResult {
     pub agent_id: Uuid,
     pub agent_name: String,
@@ -378,17 +555,8 @@ pub struct AgentPredictionResult {
     pub reasoning: String,
 }
 
-/// Ensemble aggregation result
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct EnsembleResult {
-    pub prediction: PredictionType,
-    pub confidence: f64,
-    pub agent_count: usize,
-    pub consensus_strength: f64,
-}
-
 /// Risk assessment
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct RiskAssessment {
     pub risk_level: RiskLevel,
     pub confidence_factor: f64,
@@ -397,7 +565,7 @@ pub struct RiskAssessment {
 }
 
 /// Risk levels
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub enum RiskLevel {
     Low,
     Medium,
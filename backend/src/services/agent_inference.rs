@@ -0,0 +1,85 @@
+//! Agent inference backends
+
+use crate::{
+    db::models::{Agent, AgentType, PredictionType},
+    error::Result,
+    services::agent::{AgentPredictionResult, MarketAnalysisRequest},
+};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Future returned by [`AgentInference::predict`]
+pub type PredictFuture<'a> = Pin<Box<dyn Future<Output = Result<AgentPredictionResult>> + Send + 'a>>;
+
+/// Runs an agent's model on market data. Implement this to plug in a model
+/// backend for an agent type.
+pub trait AgentInference: Send + Sync {
+    /// `agent`'s prediction for the market described by `market`
+    fn predict<'a>(&'a self, agent: &'a Agent, market: &'a MarketAnalysisRequest) -> PredictFuture<'a>;
+}
+
+/// Inference that gives the same prediction every time, used until a
+/// model backend is registered and in tests
+#[derive(Debug, Clone, Copy)]
+pub struct MockAgentInference {
+    pub prediction: PredictionType,
+    pub confidence: f64,
+}
+
+impl MockAgentInference {
+    pub fn new(prediction: PredictionType, confidence: f64) -> Self {
+        Self { prediction, confidence }
+    }
+}
+
+impl AgentInference for MockAgentInference {
+    fn predict<'a>(&'a self, agent: &'a Agent, _market: &'a MarketAnalysisRequest) -> PredictFuture<'a> {
+        Box::pin(async move {
+            Ok(AgentPredictionResult {
+                agent_id: agent.id,
+                agent_name: agent.name.clone(),
+                agent_type: agent.agent_type.clone(),
+                prediction: self.prediction.clone(),
+                confidence: self.confidence,
+                reasoning: format!("Simulated prediction from {} agent", agent.name),
+            })
+        })
+    }
+}
+
+/// Inference backends by agent type
+#[derive(Clone)]
+pub struct AgentInferenceRegistry {
+    backends: HashMap<AgentType, Arc<dyn AgentInference>>,
+}
+
+impl AgentInferenceRegistry {
+    /// A registry with no backends
+    pub fn empty() -> Self {
+        Self { backends: HashMap::new() }
+    }
+
+    /// Use `backend` for agents of `agent_type`, replacing any registered before
+    pub fn register(mut self, agent_type: AgentType, backend: Arc<dyn AgentInference>) -> Self {
+        self.backends.insert(agent_type, backend);
+        self
+    }
+
+    /// The backend for agents of `agent_type`, if one is registered
+    pub fn get(&self, agent_type: &AgentType) -> Option<&Arc<dyn AgentInference>> {
+        self.backends.get(agent_type)
+    }
+}
+
+impl Default for AgentInferenceRegistry {
+    /// Mock backends for every agent type the ensemble draws on
+    fn default() -> Self {
+        Self::empty()
+            .register(AgentType::NewsSentiment, Arc::new(MockAgentInference::new(PredictionType::Bullish, 0.7)))
+            .register(AgentType::MarketFactor, Arc::new(MockAgentInference::new(PredictionType::Neutral, 0.6)))
+            .register(AgentType::TechnicalAnalysis, Arc::new(MockAgentInference::new(PredictionType::Bearish, 0.8)))
+            .register(AgentType::CryptoFactor, Arc::new(MockAgentInference::new(PredictionType::Bullish, 0.65)))
+    }
+}
\ No newline at end of file
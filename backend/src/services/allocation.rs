@@ -0,0 +1,231 @@
+//! Fixed-point portfolio allocations
+//!
+//! Allocations are whole units of `1 / scale` of the portfolio (basis
+//! points at the default scale of 10,000), so the parts of a
+//! recommendation always add up to exactly 100%.
+//!
+//! Rebalancing trades are priced in cash, which is also what they settle
+//! against: sells raise cash and buys spend it, so cash itself is never
+//! traded.
+
+use crate::{
+    config::AllocationRounding,
+    db::models::{PredictionType, RecommendationType},
+    error::{Error, Result},
+    services::{ensemble::EnsembleResult, wallet::WalletBalance},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// How the crypto share of a portfolio is split between assets
+const CRYPTO_WEIGHTS: [(&str, f64); 3] = [("SOL", 0.6), ("BTC", 0.3), ("ETH", 0.1)];
+
+const CASH: &str = "CASH";
+
+/// Tokens held as cash
+const STABLECOINS: [&str; 2] = ["USDC", "USDT"];
+
+/// A portfolio split into whole units that sum to `scale`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssetAllocations {
+    pub scale: u32,
+    pub units: BTreeMap<String, u32>,
+}
+
+impl AssetAllocations {
+    /// Share of the portfolio held in cash
+    pub fn cash_ratio(&self) -> f64 {
+        self.units.get(CASH).copied().unwrap_or(0) as f64 / self.scale as f64
+    }
+
+    /// Share of the portfolio held in crypto assets
+    pub fn crypto_ratio(&self) -> f64 {
+        let crypto: u32 = self.units.iter()
+            .filter(|(asset, _)| asset.as_str() != CASH)
+            .map(|(_, units)| units)
+            .sum();
+        crypto as f64 / self.scale as f64
+    }
+}
+
+/// Recommendation and allocations for an ensemble prediction
+pub fn portfolio_allocation(
+    ensemble_result: &EnsembleResult,
+    scale: u32,
+    rounding: AllocationRounding,
+) -> (RecommendationType, AssetAllocations) {
+    let (recommendation_type, crypto_ratio) = match ensemble_result.prediction {
+        PredictionType::Bullish => {
+            let ratio = 0.7 + (ensemble_result.confidence - 0.5) * 0.4;
+            (RecommendationType::Buy, ratio.min(0.9))
+        }
+        PredictionType::Bearish => {
+            let ratio = 0.3 - (ensemble_result.confidence - 0.5) * 0.4;
+            (RecommendationType::Sell, ratio.max(0.1))
+        }
+        PredictionType::Neutral => (RecommendationType::Hold, 0.5),
+    };
+    let crypto_ratio = crypto_ratio.clamp(0.0, 1.0);
+
+    // Cash comes last so it absorbs the remainder under `AllocationRounding::Last`
+    let mut weights: Vec<(&str, f64)> = CRYPTO_WEIGHTS.iter()
+        .map(|&(asset, weight)| (asset, crypto_ratio * weight))
+        .collect();
+    weights.push((CASH, 1.0 - crypto_ratio));
+
+    let shares: Vec<f64> = weights.iter().map(|&(_, weight)| weight).collect();
+    let units = split_units(&shares, scale, rounding);
+
+    let allocations = AssetAllocations {
+        scale,
+        units: weights.iter().map(|&(asset, _)| asset.to_string()).zip(units).collect(),
+    };
+    (recommendation_type, allocations)
+}
+
+/// Split `scale` units in proportion to `weights`. The result always sums
+/// to `scale`; `rounding` decides which parts get the units lost to
+/// rounding down.
+pub fn split_units(weights: &[f64], scale: u32, rounding: AllocationRounding) -> Vec<u32> {
+    if weights.is_empty() {
+        return Vec::new();
+    }
+
+    let weights: Vec<f64> = weights.iter().map(|weight| weight.max(0.0)).collect();
+    let total: f64 = weights.iter().sum();
+    let exact: Vec<f64> = if total > 0.0 {
+        weights.iter().map(|weight| weight / total * scale as f64).collect()
+    } else {
+        // Nothing to go on, so everything goes to the last part
+        let mut exact = vec![0.0; weights.len()];
+        exact[weights.len() - 1] = scale as f64;
+        exact
+    };
+
+    // Round every part down, never handing out more than `scale`
+    let mut budget = scale;
+    let mut units: Vec<u32> = exact.iter()
+        .map(|&share| {
+            let part = (share.floor() as u32).min(budget);
+            budget -= part;
+            part
+        })
+        .collect();
+
+    match rounding {
+        AllocationRounding::LargestRemainder => {
+            // Biggest fractional parts first; ties go to the earlier part
+            let mut order: Vec<usize> = (0..units.len()).collect();
+            order.sort_by(|&a, &b| {
+                let (fraction_a, fraction_b) = (exact[a] - exact[a].floor(), exact[b] - exact[b].floor());
+                fraction_b.total_cmp(&fraction_a).then(a.cmp(&b))
+            });
+            for index in order.into_iter().cycle().take(budget as usize) {
+                units[index] += 1;
+            }
+        }
+        AllocationRounding::Last => {
+            let last = units.len() - 1;
+            units[last] += budget;
+        }
+    }
+
+    units
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+/// One trade against cash towards a target allocation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RebalanceTrade {
+    pub asset: String,
+    pub side: TradeSide,
+    /// Amount of `asset` to buy or sell, in its own units
+    pub amount: f64,
+}
+
+/// Amounts a Solana wallet holds per allocation asset: SOL from the native
+/// balance, stablecoins as cash and other tokens by symbol. Tokens without
+/// a symbol can't be matched to an allocation and are left out.
+pub fn wallet_holdings(balance: &WalletBalance) -> BTreeMap<String, f64> {
+    let mut holdings = BTreeMap::new();
+    if let Ok(sol) = balance.sol_balance.parse::<f64>() {
+        *holdings.entry("SOL".to_string()).or_insert(0.0) += sol;
+    }
+
+    for token in &balance.token_balances {
+        let (Some(symbol), Ok(amount)) = (&token.symbol, token.balance.parse::<f64>()) else {
+            continue;
+        };
+        let symbol = symbol.to_uppercase();
+        let asset = if STABLECOINS.contains(&symbol.as_str()) { CASH.to_string() } else { symbol };
+        *holdings.entry(asset).or_insert(0.0) += amount;
+    }
+
+    holdings
+}
+
+/// Trades that bring `holdings` to the `target` allocation, with `prices`
+/// giving the cash value of one unit of each asset.
+///
+/// Every asset off target is bought or sold once against cash, so there is
+/// at most one trade per asset. Differences worth less than
+/// `min_trade_value` are left alone rather than traded as dust. Sells come
+/// first so they fund the buys.
+pub fn rebalance_trades(
+    holdings: &BTreeMap<String, f64>,
+    prices: &BTreeMap<String, f64>,
+    target: &AssetAllocations,
+    min_trade_value: f64,
+) -> Result<Vec<RebalanceTrade>> {
+    let price = |asset: &str| -> Result<f64> {
+        if asset == CASH {
+            return Ok(1.0);
+        }
+        prices.get(asset)
+            .copied()
+            .filter(|price| *price > 0.0)
+            .ok_or_else(|| Error::Validation(format!("No price for {}", asset)))
+    };
+
+    let mut total = 0.0;
+    for (asset, &amount) in holdings {
+        if amount > 0.0 {
+            total += amount * price(asset)?;
+        }
+    }
+
+    let assets: BTreeSet<&String> = holdings.keys().chain(target.units.keys()).collect();
+    let (mut sells, mut buys) = (Vec::new(), Vec::new());
+    for asset in assets.into_iter().filter(|asset| asset.as_str() != CASH) {
+        let held = holdings.get(asset).copied().unwrap_or(0.0).max(0.0);
+        let target_units = target.units.get(asset).copied().unwrap_or(0);
+        if held == 0.0 && target_units == 0 {
+            continue;
+        }
+
+        let unit_price = price(asset)?;
+        let target_value = total * target_units as f64 / target.scale.max(1) as f64;
+        let difference = target_value - held * unit_price;
+        if difference.abs() < min_trade_value {
+            continue;
+        }
+
+        if difference < 0.0 {
+            // Selling out entirely uses the exact holding, not a value
+            // round-trip that could leave dust behind
+            let amount = if target_units == 0 { held } else { -difference / unit_price };
+            sells.push(RebalanceTrade { asset: asset.clone(), side: TradeSide::Sell, amount });
+        } else {
+            buys.push(RebalanceTrade { asset: asset.clone(), side: TradeSide::Buy, amount: difference / unit_price });
+        }
+    }
+
+    sells.extend(buys);
+    Ok(sells)
+}
\ No newline at end of file
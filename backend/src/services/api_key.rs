@@ -0,0 +1,86 @@
+//! API key management
+
+use crate::{
+    api::{
+        handlers::api_key::{CreateApiKeyRequest, CreatedApiKey},
+        AppState,
+    },
+    db::queries::ApiKeyQueries,
+    error::{Error, Result},
+};
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Permissions an API key can be granted, one per group of integration routes
+pub const API_KEY_PERMISSIONS: &[&str] = &["wallet", "transaction", "zkml"];
+
+/// Prefix identifying Guardian API keys in logs and secret scanners
+const API_KEY_PREFIX: &str = "gaa_";
+
+pub struct ApiKeyService {
+    state: Arc<AppState>,
+}
+
+impl ApiKeyService {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+
+    /// Create an API key for the user, up to `auth.max_api_keys_per_user`
+    /// usable keys. The key itself is only returned here; just its hash is
+    /// stored.
+    pub async fn create_api_key(&self, user_id: Uuid, req: CreateApiKeyRequest) -> Result<CreatedApiKey> {
+        if req.name.trim().is_empty() {
+            return Err(Error::Validation("API key name cannot be empty".to_string()));
+        }
+        if let Some(unknown) = req.permissions.iter().find(|p| !API_KEY_PERMISSIONS.contains(&p.as_str())) {
+            return Err(Error::Validation(format!("Unknown API key permission: {}", unknown)));
+        }
+        if req.expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) {
+            return Err(Error::Validation("API key expiry must be in the future".to_string()));
+        }
+
+        let key = format!("{}{}", API_KEY_PREFIX, hex::encode(rand::random::<[u8; 32]>()));
+        let key_hash = hash_api_key(&key);
+        let name = req.name.trim().to_string();
+        let permissions = serde_json::json!(req.permissions);
+        let expires_at = req.expires_at;
+        let max_keys = self.state.config.auth.max_api_keys_per_user;
+
+        let api_key = self.state.db.transaction(move |conn| Box::pin(async move {
+            // Revoked and expired keys don't take up a slot
+            let usable = ApiKeyQueries::count_usable_for_user_locked(&mut *conn, user_id).await?;
+            if usable >= max_keys {
+                return Err(Error::Conflict(format!(
+                    "API key limit of {} reached; revoke a key before creating another",
+                    max_keys
+                )));
+            }
+
+            ApiKeyQueries::create(&mut *conn, user_id, &name, &key_hash, &permissions, expires_at).await
+        })).await?;
+
+        Ok(CreatedApiKey {
+            id: api_key.id,
+            name: api_key.name,
+            key,
+            permissions: req.permissions,
+            expires_at: api_key.expires_at,
+        })
+    }
+
+    /// Revoke one of the user's API keys
+    pub async fn revoke_api_key(&self, user_id: Uuid, key_id: Uuid) -> Result<()> {
+        if !ApiKeyQueries::revoke(self.state.db.pool(), key_id, user_id).await? {
+            return Err(Error::NotFound);
+        }
+        Ok(())
+    }
+}
+
+/// Hash an API key for storage and lookup
+pub fn hash_api_key(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
\ No newline at end of file
@@ -3,21 +3,34 @@
 use crate::{
     api::{
         handlers::auth::{
-            AuthResponse, ForgotPasswordRequest, LoginRequest, RefreshTokenRequest,
-            RegisterRequest, ResetPasswordRequest, VerifyEmailRequest,
+            AuthResponse, ChangePasswordRequest, ClientInfo, ForgotPasswordRequest, LoginRequest, LogoutRequest,
+            RefreshTokenRequest, RegisterRequest, ResetPasswordRequest, RevokeSessionsRequest, SessionInfo,
+            VerifyEmailRequest, VerifySignatureRequest, WalletChallengeRequest,
         },
+        middleware::auth::decode_claims,
         AppState,
     },
+    db::{
+        models::{Role, User},
+        queries::{
+            EmailVerificationTokenQueries, PasswordResetTokenQueries, UserQueries, UserSessionQueries, WalletLoginQueries,
+            WalletQueries,
+        },
+    },
+    auth::{LoginLockout, TokenDenylist, WalletChallenge, WalletChallenges},
     error::{Error, Result},
+    services::email::{EmailSender, NoopEmailSender},
 };
 use argon2::{
-    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    password_hash::{PasswordHash, PasswordVerifier},
     Argon2,
 };
 use chrono::{Duration, Utc};
 use jsonwebtoken::{encode, EncodingKey, Header};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
 use uuid::Uuid;
 use sha2::{Sha256, Digest};
 
@@ -27,15 +40,54 @@ pub struct Claims {
     pub email: String,
     pub exp: i64,
     pub iat: i64,
+    pub iss: String,
+    pub aud: String,
+    /// Unique token id, used to revoke the token and to keep tokens issued
+    /// within the same second distinct
+    pub jti: String,
+    /// The user's role when the token was issued. Tokens from before roles
+    /// existed carry none and count as a regular user's. The application's
+    /// routes use the role the account holds now instead.
+    #[serde(default)]
+    pub role: Role,
+    /// Whether this is an access or a refresh token. Only access tokens
+    /// authenticate requests; tokens without one are rejected.
+    pub typ: TokenType,
+}
+
+/// What a token may be used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenType {
+    Access,
+    Refresh,
 }
 
 pub struct AuthService {
     state: Arc<AppState>,
+    email_sender: Arc<dyn EmailSender>,
+    client_info: ClientInfo,
 }
 
 impl AuthService {
     pub fn new(state: Arc<AppState>) -> Self {
-        Self { state }
+        Self {
+            state,
+            email_sender: Arc::new(NoopEmailSender),
+            client_info: ClientInfo::default(),
+        }
+    }
+
+    /// Deliver account emails through `sender`
+    pub fn with_email_sender(mut self, sender: Arc<dyn EmailSender>) -> Self {
+        self.email_sender = sender;
+        self
+    }
+
+    /// Record `client_info` on the sessions this service creates
+    pub fn with_client_info(mut self, client_info: ClientInfo) -> Self {
+        self.client_info = client_info;
+        self
     }
 
     /// Register a new user
@@ -52,41 +104,142 @@ impl AuthService {
         // Hash password
         let password_hash = self.hash_password(&req.password)?;
 
-        // Create user in database
+        // Create the unverified user together with its verification token
         let create_user = crate::db::models::CreateUser {
             email: req.email.clone(),
             password: req.password,
         };
-        let user = crate::db::queries::UserQueries::create(self.state.db.pool(), &create_user, &password_hash).await?;
+        let verification_token = hex::encode(rand::random::<[u8; 32]>());
+        let token_hash = self.hash_token(&verification_token);
+        let token_expires_at = Utc::now() + Duration::seconds(self.state.config.auth.email_verification_ttl);
+        let user = self.state.db.transaction(move |conn| Box::pin(async move {
+            let user = UserQueries::create(&mut *conn, &create_user, &password_hash).await?;
+            EmailVerificationTokenQueries::create(&mut *conn, user.id, &token_hash, token_expires_at).await?;
+            Ok(user)
+        })).await?;
+
+        // The account exists either way; a failed send only delays verification
+        if let Err(e) = self.email_sender.send_verification_email(&user.email, &verification_token).await {
+            tracing::warn!(user_id = %user.id, error = %e, "Failed to send verification email");
+        }
 
-        // Generate tokens
-        self.generate_auth_response(&user.id.to_string(), &req.email)
+        self.start_session(user.id, &user.email).await
     }
 
     /// User login
     pub async fn login(&self, req: LoginRequest) -> Result<AuthResponse> {
+        let lockout = LoginLockout::new(self.state.redis.clone(), &self.state.config.auth);
+
+        // Checked before the account is looked up, so unknown emails lock
+        // out exactly like real ones. Logins keep working if Redis is down.
+        let locked_for = lockout.locked_for(&req.email).await.unwrap_or_else(|e| {
+            tracing::warn!("Failed to check login lockout: {}", e);
+            None
+        });
+        if let Some(retry_after_secs) = locked_for {
+            return Err(Error::AccountLocked { retry_after_secs });
+        }
+
         // Fetch user from database
-        let user = crate::db::queries::UserQueries::find_by_email(self.state.db.pool(), &req.email).await?
+        let user = crate::db::queries::UserQueries::find_by_email(self.state.db.pool(), &req.email).await?;
+
+        // Always verify a password, against a stand-in hash when there is
+        // no account, so failures take as long whether or not it exists
+        let dummy_hash = dummy_password_hash(&self.argon2()?);
+        let password_hash = user.as_ref().map_or(dummy_hash, |user| user.password_hash.as_str());
+        let password_matches = self.password_matches(&req.password, password_hash)?;
+
+        let user = match user {
+            Some(user) if user.is_active && password_matches => user,
+            _ => {
+                if let Err(e) = lockout.record_failure(&req.email).await {
+                    tracing::warn!("Failed to record failed login: {}", e);
+                }
+                return Err(Error::AuthenticationFailed);
+            }
+        };
+
+        if let Err(e) = lockout.clear(&req.email).await {
+            tracing::warn!("Failed to clear failed logins: {}", e);
+        }
+
+        if self.state.config.auth.require_email_verification && !user.email_verified {
+            return Err(Error::Forbidden);
+        }
+
+        // Update last login
+        crate::db::queries::UserQueries::update_last_login(self.state.db.pool(), user.id).await?;
+
+        self.start_session(user.id, &user.email).await
+    }
+
+    /// Issue a nonce for the wallet at `req.public_key` to sign
+    pub async fn wallet_challenge(&self, req: WalletChallengeRequest) -> Result<WalletChallenge> {
+        let public_key = Pubkey::from_str(&req.public_key)
+            .map_err(|_| Error::Validation("Invalid Solana public key".to_string()))?;
+        self.wallet_challenges().issue(&public_key).await
+    }
+
+    /// Sign in with a wallet's signature of an outstanding challenge. The
+    /// first sign-in with a wallet creates a user for it, with no password
+    /// that could be guessed.
+    pub async fn verify_signature(&self, req: VerifySignatureRequest) -> Result<AuthResponse> {
+        let public_key = Pubkey::from_str(&req.public_key).map_err(|_| Error::AuthenticationFailed)?;
+        let signature = Signature::from_str(&req.signature).map_err(|_| Error::AuthenticationFailed)?;
+
+        // The message is rebuilt from what was issued, so the signature must
+        // cover this service's domain and URI and the challenge's validity
+        // window. It is checked before the nonce is used up, so a bad
+        // signature can't spend someone else's challenge.
+        let challenges = self.wallet_challenges();
+        let challenge = challenges.outstanding(&public_key, &req.nonce).await?
             .ok_or(Error::AuthenticationFailed)?;
+        if !challenge.is_valid_at(Utc::now()) || !challenge.is_signed_by(&public_key, &signature) {
+            return Err(Error::AuthenticationFailed);
+        }
+        if !challenges.consume(&public_key, &req.nonce).await? {
+            return Err(Error::AuthenticationFailed);
+        }
 
-        // Check if user is active
+        let public_key = public_key.to_string();
+        let user = match WalletLoginQueries::find_user(self.state.db.pool(), &public_key).await? {
+            Some(user) => user,
+            // A concurrent first sign-in may have created the user first
+            None => match self.create_wallet_user(&public_key).await {
+                Ok(user) => user,
+                Err(e) => WalletLoginQueries::find_user(self.state.db.pool(), &public_key).await?.ok_or(e)?,
+            },
+        };
         if !user.is_active {
             return Err(Error::AuthenticationFailed);
         }
 
-        // Verify password
-        let parsed_hash = PasswordHash::new(&user.password_hash)
-            .map_err(|_| Error::Internal)?;
-        
-        Argon2::default()
-            .verify_password(req.password.as_bytes(), &parsed_hash)
-            .map_err(|_| Error::AuthenticationFailed)?;
+        UserQueries::update_last_login(self.state.db.pool(), user.id).await?;
+        self.start_session(user.id, &user.email).await
+    }
 
-        // Update last login
-        crate::db::queries::UserQueries::update_last_login(self.state.db.pool(), user.id).await?;
+    /// Create a user signing in with the wallet at `public_key`. It gets a
+    /// placeholder address on the reserved `.invalid` domain and the hash of
+    /// a random password nobody knows.
+    async fn create_wallet_user(&self, public_key: &str) -> Result<User> {
+        let create_user = crate::db::models::CreateUser {
+            email: format!("{}@wallet.invalid", public_key),
+            password: String::new(),
+        };
+        let password_hash = self.hash_password(&hex::encode(rand::random::<[u8; 32]>()))?;
+        let public_key = public_key.to_string();
+
+        self.state.db.transaction(move |conn| Box::pin(async move {
+            let user = UserQueries::create(&mut *conn, &create_user, &password_hash).await?;
+            if !WalletLoginQueries::link(&mut *conn, &public_key, user.id).await? {
+                return Err(Error::Conflict("Wallet is already linked to a user".to_string()));
+            }
+            Ok(user)
+        })).await
+    }
 
-        // Generate tokens
-        self.generate_auth_response(&user.id.to_string(), &req.email)
+    fn wallet_challenges(&self) -> WalletChallenges {
+        WalletChallenges::new(self.state.redis.clone(), &self.state.config.auth)
     }
 
     /// Refresh access token
@@ -96,46 +249,258 @@ impl AuthService {
         let session = crate::db::queries::UserSessionQueries::find_by_token_hash(self.state.db.pool(), &token_hash).await?
             .ok_or(Error::AuthenticationFailed)?;
 
+        // Revoke sessions left idle too long, even if they haven't expired
+        let idle_timeout = Duration::seconds(self.state.config.auth.refresh_idle_timeout);
+        if Utc::now() - session.last_used_at > idle_timeout {
+            crate::db::queries::UserSessionQueries::delete(self.state.db.pool(), session.id).await?;
+            return Err(Error::AuthenticationFailed);
+        }
+
         // Get user
         let user = crate::db::queries::UserQueries::find_by_id(self.state.db.pool(), session.user_id).await?
             .ok_or(Error::AuthenticationFailed)?;
 
-        // Update session last used
-        crate::db::queries::UserSessionQueries::update_last_used(self.state.db.pool(), session.id).await?;
+        // Rotate the refresh token: the old session is replaced by one
+        // whose last use is now, so the presented token stops working.
+        // The role is read again so a changed role applies from here on.
+        let role = self.user_role(user.id).await?;
+        let response = self.generate_auth_response(&user.id.to_string(), &user.email, role)?;
+        let new_token_hash = self.hash_token(&response.refresh_token);
+        let expires_at = self.refresh_token_expiry();
+        let client_info = self.client_info.clone();
+        self.state.db.transaction(move |conn| Box::pin(async move {
+            // Losing this race means a concurrent refresh already used the token
+            if !UserSessionQueries::delete(&mut *conn, session.id).await? {
+                return Err(Error::AuthenticationFailed);
+            }
+            UserSessionQueries::create(
+                &mut *conn,
+                user.id,
+                &new_token_hash,
+                expires_at,
+                client_info.user_agent.as_deref(),
+                client_info.ip_address,
+            ).await?;
+            Ok(())
+        })).await?;
+
+        Ok(response)
+    }
 
-        self.generate_auth_response(&user.id.to_string(), &user.email)
+    /// End the session holding the refresh token. Unknown tokens are
+    /// ignored, so logging out twice is harmless.
+    pub async fn logout(&self, req: LogoutRequest) -> Result<()> {
+        let token_hash = self.hash_token(&req.refresh_token);
+        UserSessionQueries::delete_by_token_hash(self.state.db.pool(), &token_hash).await?;
+        Ok(())
     }
 
-    /// Verify email
-    pub async fn verify_email(&self, _req: VerifyEmailRequest) -> Result<()> {
-        // TODO: Implement email verification
+    /// The user's active sessions, most recently used first
+    pub async fn list_sessions(&self, user_id: Uuid) -> Result<Vec<SessionInfo>> {
+        let sessions = UserSessionQueries::find_by_user_id(self.state.db.pool(), user_id).await?;
+        Ok(sessions.into_iter().map(SessionInfo::from).collect())
+    }
+
+    /// End one of the user's sessions, so its refresh token stops working.
+    /// Other users' sessions are reported as missing.
+    pub async fn revoke_session(&self, user_id: Uuid, session_id: Uuid) -> Result<()> {
+        if !UserSessionQueries::revoke(self.state.db.pool(), session_id, user_id).await? {
+            return Err(Error::NotFound);
+        }
         Ok(())
     }
 
-    /// Forgot password
-    pub async fn forgot_password(&self, _req: ForgotPasswordRequest) -> Result<()> {
-        // TODO: Send password reset email
+    /// End every session of the user except the one holding
+    /// `req.refresh_token`, returning how many were ended
+    pub async fn revoke_other_sessions(&self, user_id: Uuid, req: RevokeSessionsRequest) -> Result<u64> {
+        let keep_token_hash = self.hash_token(&req.refresh_token);
+        UserSessionQueries::delete_for_user_except(self.state.db.pool(), user_id, &keep_token_hash).await
+    }
+
+    /// Reject an access token for the rest of its lifetime. Tokens that are
+    /// already invalid or expired need no revoking and are ignored.
+    pub async fn revoke_access_token(&self, access_token: &str) -> Result<()> {
+        let Ok(claims) = decode_claims(access_token, &self.state.config.auth) else {
+            return Ok(());
+        };
+
+        TokenDenylist::new(self.state.redis.clone())
+            .revoke(&claims.jti, claims.exp)
+            .await
+    }
+
+    /// Deactivate the user's account: it can't log in again, every session
+    /// ends, its wallets are deactivated and the tokens it still holds stop
+    /// working. Nothing is deleted.
+    pub async fn deactivate_account(&self, user_id: Uuid) -> Result<()> {
+        self.state.db.transaction(move |conn| Box::pin(async move {
+            UserQueries::deactivate(&mut *conn, user_id).await?;
+            UserSessionQueries::delete_for_user(&mut *conn, user_id).await?;
+            WalletQueries::deactivate_for_user(&mut *conn, user_id).await?;
+            Ok(())
+        })).await?;
+        self.state.account_statuses.forget(user_id);
+
+        TokenDenylist::new(self.state.redis.clone())
+            .revoke_user(&user_id.to_string(), self.token_lifetime())
+            .await
+    }
+
+    /// Verify a user's email address with the token sent on registration.
+    /// Each token works once and only until it expires.
+    pub async fn verify_email(&self, req: VerifyEmailRequest) -> Result<()> {
+        let token_hash = self.hash_token(&req.token);
+
+        self.state.db.transaction(move |conn| Box::pin(async move {
+            let user_id = EmailVerificationTokenQueries::consume(&mut *conn, &token_hash).await?
+                .ok_or_else(|| Error::BadRequest("Invalid or expired verification token".to_string()))?;
+            UserQueries::mark_email_verified(&mut *conn, user_id).await
+        })).await
+    }
+
+    /// Send a password reset token to the account's email. Succeeds whether
+    /// or not the account exists, so callers can't probe for registered
+    /// addresses.
+    pub async fn forgot_password(&self, req: ForgotPasswordRequest) -> Result<()> {
+        let user = match UserQueries::find_by_email(self.state.db.pool(), &req.email).await? {
+            Some(user) if user.is_active => user,
+            _ => return Ok(()),
+        };
+
+        let reset_token = hex::encode(rand::random::<[u8; 32]>());
+        let expires_at = Utc::now() + Duration::seconds(self.state.config.auth.password_reset_ttl);
+        PasswordResetTokenQueries::create(self.state.db.pool(), user.id, &self.hash_token(&reset_token), expires_at).await?;
+
+        if let Err(e) = self.email_sender.send_password_reset_email(&user.email, &reset_token).await {
+            tracing::warn!(user_id = %user.id, error = %e, "Failed to send password reset email");
+        }
+
         Ok(())
     }
 
-    /// Reset password
+    /// Set a new password with a reset token. The token works once and only
+    /// until it expires; all of the user's sessions are revoked and the
+    /// access tokens it still holds stop working.
     pub async fn reset_password(&self, req: ResetPasswordRequest) -> Result<()> {
         // Validate new password
         self.validate_password(&req.new_password)?;
 
         // Hash new password
-        let _password_hash = self.hash_password(&req.new_password)?;
+        let password_hash = self.hash_password(&req.new_password)?;
+        let token_hash = self.hash_token(&req.token);
+
+        let user_id = self.state.db.transaction(move |conn| Box::pin(async move {
+            let user_id = PasswordResetTokenQueries::consume(&mut *conn, &token_hash).await?
+                .ok_or_else(|| Error::BadRequest("Invalid or expired reset token".to_string()))?;
+
+            UserQueries::update_password(&mut *conn, user_id, &password_hash).await?;
+            PasswordResetTokenQueries::invalidate_for_user(&mut *conn, user_id).await?;
+            UserSessionQueries::delete_for_user(&mut *conn, user_id).await?;
+            Ok(user_id)
+        })).await?;
+
+        TokenDenylist::new(self.state.redis.clone())
+            .revoke_user(&user_id.to_string(), self.token_lifetime())
+            .await
+    }
 
-        // TODO: Update password in database
-        
-        Ok(())
+    /// Issue tokens for the user and store the refresh token's session
+    async fn start_session(&self, user_id: Uuid, email: &str) -> Result<AuthResponse> {
+        let role = self.user_role(user_id).await?;
+        let response = self.generate_auth_response(&user_id.to_string(), email, role)?;
+
+        UserSessionQueries::create(
+            self.state.db.pool(),
+            user_id,
+            &self.hash_token(&response.refresh_token),
+            self.refresh_token_expiry(),
+            self.client_info.user_agent.as_deref(),
+            self.client_info.ip_address,
+        ).await?;
+
+        Ok(response)
+    }
+
+    /// When a refresh token issued now expires
+    fn refresh_token_expiry(&self) -> chrono::DateTime<Utc> {
+        Utc::now() + Duration::seconds(self.state.config.auth.refresh_token_expiration)
+    }
+
+    /// How long a user-wide revocation has to last to outlive every token
+    /// issued so far, refresh tokens included
+    fn token_lifetime(&self) -> u64 {
+        let auth = &self.state.config.auth;
+        auth.jwt_expiration.max(auth.refresh_token_expiration).max(0) as u64
+    }
+
+    /// Change a logged-in user's password. Wrong current passwords count
+    /// towards the login lockout. Every other session is revoked, and every
+    /// access token but `access_token`, the caller's; the session holding
+    /// `req.refresh_token`, if given, stays signed in.
+    pub async fn change_password(&self, user_id: Uuid, access_token: Option<&str>, req: ChangePasswordRequest) -> Result<()> {
+        let user = UserQueries::find_by_id(self.state.db.pool(), user_id).await?
+            .ok_or(Error::Unauthorized)?;
+
+        // The same lockout as logging in, so a stolen access token can't be
+        // used to guess the password either. Keeps working if Redis is down.
+        let lockout = LoginLockout::new(self.state.redis.clone(), &self.state.config.auth);
+        let locked_for = lockout.locked_for(&user.email).await.unwrap_or_else(|e| {
+            tracing::warn!("Failed to check login lockout: {}", e);
+            None
+        });
+        if let Some(retry_after_secs) = locked_for {
+            return Err(Error::AccountLocked { retry_after_secs });
+        }
+
+        if !self.password_matches(&req.current_password, &user.password_hash)? {
+            if let Err(e) = lockout.record_failure(&user.email).await {
+                tracing::warn!("Failed to record failed password change: {}", e);
+            }
+            return Err(Error::BadRequest("Current password is incorrect".to_string()));
+        }
+
+        if let Err(e) = lockout.clear(&user.email).await {
+            tracing::warn!("Failed to clear failed logins: {}", e);
+        }
+
+        self.validate_password(&req.new_password)?;
+        let password_hash = self.hash_password(&req.new_password)?;
+        let keep_token_hash = req.refresh_token.map(|token| self.hash_token(&token));
+
+        self.state.db.transaction(move |conn| Box::pin(async move {
+            UserQueries::update_password(&mut *conn, user_id, &password_hash).await?;
+            match keep_token_hash {
+                Some(token_hash) => UserSessionQueries::delete_for_user_except(&mut *conn, user_id, &token_hash).await?,
+                None => UserSessionQueries::delete_for_user(&mut *conn, user_id).await?,
+            };
+            Ok(())
+        })).await?;
+
+        let denylist = TokenDenylist::new(self.state.redis.clone());
+        let keep_jti = access_token
+            .and_then(|token| decode_claims(token, &self.state.config.auth).ok())
+            .filter(|claims| claims.sub == user_id.to_string())
+            .map(|claims| claims.jti);
+        match keep_jti {
+            Some(jti) => denylist.revoke_user_except(&user_id.to_string(), &jti, self.token_lifetime()).await,
+            None => denylist.revoke_user(&user_id.to_string(), self.token_lifetime()).await,
+        }
+    }
+
+    /// The role to put in the user's tokens
+    async fn user_role(&self, user_id: Uuid) -> Result<Role> {
+        let is_admin = UserQueries::is_admin(self.state.db.pool(), user_id).await?;
+        Ok(Role::from_admin_flag(is_admin))
     }
 
     /// Generate auth response with tokens
-    fn generate_auth_response(&self, user_id: &str, email: &str) -> Result<AuthResponse> {
+    fn generate_auth_response(&self, user_id: &str, email: &str, role: Role) -> Result<AuthResponse> {
         let now = Utc::now();
         let access_token_exp = now + Duration::seconds(self.state.config.auth.jwt_expiration);
-        let refresh_token_exp = now + Duration::seconds(self.state.config.auth.refresh_token_expiration);
+        let refresh_token_exp = self.refresh_token_expiry();
+        let issuer = &self.state.config.auth.jwt_issuer;
+        let audience = self.state.config.auth.jwt_audiences.first()
+            .ok_or_else(|| Error::Config("At least one JWT audience must be configured".to_string()))?;
 
         // Create access token claims
         let access_claims = Claims {
@@ -143,6 +508,11 @@ impl AuthService {
             email: email.to_string(),
             exp: access_token_exp.timestamp(),
             iat: now.timestamp(),
+            iss: issuer.clone(),
+            aud: audience.clone(),
+            jti: Uuid::new_v4().to_string(),
+            role,
+            typ: TokenType::Access,
         };
 
         // Create refresh token claims
@@ -151,6 +521,11 @@ impl AuthService {
             email: email.to_string(),
             exp: refresh_token_exp.timestamp(),
             iat: now.timestamp(),
+            iss: issuer.clone(),
+            aud: audience.clone(),
+            jti: Uuid::new_v4().to_string(),
+            role,
+            typ: TokenType::Refresh,
         };
 
         // Encode tokens
@@ -194,16 +569,25 @@ impl AuthService {
         Ok(())
     }
 
+    /// Argon2 with the configured cost parameters
+    fn argon2(&self) -> Result<Argon2<'static>> {
+        crate::auth::argon2(&self.state.config.auth)
+    }
+
     /// Hash password using Argon2
     fn hash_password(&self, password: &str) -> Result<String> {
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        
-        let password_hash = argon2
-            .hash_password(password.as_bytes(), &salt)
+        crate::auth::hash_password_with(&self.argon2()?, password)
+    }
+
+    /// Check a password against a stored Argon2 hash, using the parameters
+    /// encoded in it
+    fn password_matches(&self, password: &str, password_hash: &str) -> Result<bool> {
+        let parsed_hash = PasswordHash::new(password_hash)
             .map_err(|_| Error::Internal)?;
-        
-        Ok(password_hash.to_string())
+
+        Ok(self.argon2()?
+            .verify_password(password.as_bytes(), &parsed_hash)
+            .is_ok())
     }
 
     /// Check if user exists
@@ -218,4 +602,15 @@ impl AuthService {
         hasher.update(token.as_bytes());
         format!("{:x}", hasher.finalize())
     }
-} 
\ No newline at end of file
+}
+
+/// Hash of a random password, verified against when a login names no
+/// account so it costs the same as a real check
+fn dummy_password_hash(argon2: &Argon2) -> &'static str {
+    static HASH: OnceLock<String> = OnceLock::new();
+    HASH.get_or_init(|| {
+        let password = crate::auth::generate_secure_token(32);
+        crate::auth::hash_password_with(argon2, &password)
+            .expect("hashing a random password succeeds")
+    })
+}
\ No newline at end of file
@@ -0,0 +1,37 @@
+//! Outgoing email
+
+use crate::error::Result;
+use std::future::Future;
+use std::pin::Pin;
+
+/// Future returned by [`EmailSender`] methods
+pub type SendFuture<'a> = Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>>;
+
+/// Delivers account emails. Implement this to plug in a mail provider.
+pub trait EmailSender: Send + Sync {
+    /// Send the token that confirms `email` belongs to the user
+    fn send_verification_email<'a>(&'a self, email: &'a str, token: &'a str) -> SendFuture<'a>;
+
+    /// Send the token that lets the user choose a new password
+    fn send_password_reset_email<'a>(&'a self, email: &'a str, token: &'a str) -> SendFuture<'a>;
+}
+
+/// Sender that delivers nothing, used until a mail provider is configured
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopEmailSender;
+
+impl EmailSender for NoopEmailSender {
+    fn send_verification_email<'a>(&'a self, email: &'a str, _token: &'a str) -> SendFuture<'a> {
+        Box::pin(async move {
+            tracing::debug!(email, "No email sender configured, skipping verification email");
+            Ok(())
+        })
+    }
+
+    fn send_password_reset_email<'a>(&'a self, email: &'a str, _token: &'a str) -> SendFuture<'a> {
+        Box::pin(async move {
+            tracing::debug!(email, "No email sender configured, skipping password reset email");
+            Ok(())
+        })
+    }
+}
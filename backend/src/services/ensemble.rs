@@ -0,0 +1,166 @@
+//! Ensemble aggregation of agent predictions
+//!
+//! Each agent votes for its prediction with its confidence times the weight
+//! configured for its type. Predictions below the agent's own
+//! `confidence_threshold` are left out, and the result lists which agents
+//! counted and which didn't.
+
+use crate::{
+    config::AgentWeights,
+    db::models::{Agent, AgentType, PredictionType},
+    error::{Error, Result},
+    services::agent::AgentPredictionResult,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Ensemble aggregation result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnsembleResult {
+    pub prediction: PredictionType,
+    /// Weighted mean confidence of the included agents
+    pub confidence: f64,
+    /// Number of included agents
+    pub agent_count: usize,
+    /// Share of the included agents in the largest camp
+    pub consensus_strength: f64,
+    #[serde(default)]
+    pub included: Vec<EnsembleMember>,
+    #[serde(default)]
+    pub excluded: Vec<ExcludedAgent>,
+}
+
+/// An agent whose prediction counted in the vote
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnsembleMember {
+    pub agent_id: Uuid,
+    pub agent_name: String,
+    pub agent_type: AgentType,
+    pub weight: f64,
+}
+
+/// An agent whose prediction was left out of the vote
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExcludedAgent {
+    pub agent_id: Uuid,
+    pub agent_name: String,
+    pub agent_type: AgentType,
+    pub reason: ExclusionReason,
+}
+
+/// Why a prediction was left out
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExclusionReason {
+    /// The agent wasn't confident enough by its own standard
+    BelowConfidenceThreshold { confidence: f64, threshold: f64 },
+    /// The agent's type has no weight
+    ZeroWeight,
+}
+
+/// Combine `predictions` by weighted vote. `agents` supplies each
+/// predicting agent's confidence threshold; predictions from agents not in
+/// it have none. If every prediction is left out the result is neutral
+/// with no confidence.
+pub fn aggregate_predictions(
+    agents: &[Agent],
+    predictions: &[AgentPredictionResult],
+    weights: &AgentWeights,
+) -> Result<EnsembleResult> {
+    if predictions.is_empty() {
+        return Err(Error::BadRequest("No predictions to aggregate".to_string()));
+    }
+
+    let thresholds: HashMap<Uuid, f64> = agents.iter()
+        .map(|agent| (agent.id, agent.confidence_threshold))
+        .collect();
+
+    let mut included = Vec::new();
+    let mut excluded = Vec::new();
+    let mut counted = Vec::new();
+    for pred in predictions {
+        let threshold = thresholds.get(&pred.agent_id).copied().unwrap_or(0.0);
+        let weight = weights.weight(&pred.agent_type);
+
+        let reason = if pred.confidence < threshold {
+            Some(ExclusionReason::BelowConfidenceThreshold { confidence: pred.confidence, threshold })
+        } else if weight <= 0.0 {
+            Some(ExclusionReason::ZeroWeight)
+        } else {
+            None
+        };
+
+        match reason {
+            Some(reason) => excluded.push(ExcludedAgent {
+                agent_id: pred.agent_id,
+                agent_name: pred.agent_name.clone(),
+                agent_type: pred.agent_type.clone(),
+                reason,
+            }),
+            None => {
+                included.push(EnsembleMember {
+                    agent_id: pred.agent_id,
+                    agent_name: pred.agent_name.clone(),
+                    agent_type: pred.agent_type.clone(),
+                    weight,
+                });
+                counted.push((pred, weight));
+            }
+        }
+    }
+
+    // Majority vote weighted by confidence and agent weight
+    let mut bullish_weight = 0.0;
+    let mut bearish_weight = 0.0;
+    let mut neutral_weight = 0.0;
+    let mut total_weight = 0.0;
+    let mut weighted_confidence = 0.0;
+
+    for (pred, weight) in &counted {
+        let vote = pred.confidence * weight;
+        match pred.prediction {
+            PredictionType::Bullish => bullish_weight += vote,
+            PredictionType::Bearish => bearish_weight += vote,
+            PredictionType::Neutral => neutral_weight += vote,
+        }
+        total_weight += weight;
+        weighted_confidence += vote;
+    }
+
+    let overall_prediction = if bullish_weight > bearish_weight && bullish_weight > neutral_weight {
+        PredictionType::Bullish
+    } else if bearish_weight > neutral_weight {
+        PredictionType::Bearish
+    } else {
+        PredictionType::Neutral
+    };
+
+    let predictions: Vec<&AgentPredictionResult> = counted.iter().map(|(pred, _)| *pred).collect();
+    Ok(EnsembleResult {
+        prediction: overall_prediction,
+        confidence: if total_weight > 0.0 { weighted_confidence / total_weight } else { 0.0 },
+        agent_count: predictions.len(),
+        consensus_strength: consensus_strength(&predictions),
+        included,
+        excluded,
+    })
+}
+
+/// Share of the agents that agree with the largest camp, whatever their
+/// weights
+fn consensus_strength(predictions: &[&AgentPredictionResult]) -> f64 {
+    match predictions.len() {
+        0 => 0.0,
+        1 => 1.0,
+        len => {
+            let mut counts = HashMap::new();
+            for pred in predictions {
+                *counts.entry(pred.prediction.clone()).or_insert(0) += 1;
+            }
+
+            let max_count = counts.values().max().unwrap_or(&0);
+            *max_count as f64 / len as f64
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,120 @@
+//! Redis lock keeping a background job to one instance at a time
+//!
+//! The lock is a key set with `SET NX PX` to a token unique to its holder.
+//! It expires on its own if the holder dies, is renewed while the job is
+//! running, and is only renewed or released by the holder whose token it
+//! still carries, so an instance whose lock lapsed can't free someone
+//! else's.
+
+use std::future::Future;
+use std::time::Duration;
+
+/// Extend the lock's expiry if it is still ours
+const RENEW_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("PEXPIRE", KEYS[1], ARGV[2])
+end
+return 0
+"#;
+
+/// Delete the lock if it is still ours
+const RELEASE_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+end
+return 0
+"#;
+
+pub struct JobLock {
+    redis: redis::Client,
+    key: String,
+    token: String,
+    ttl: Duration,
+}
+
+impl JobLock {
+    pub fn new(redis: redis::Client, key: impl Into<String>, ttl_secs: u64) -> Self {
+        Self {
+            redis,
+            key: key.into(),
+            token: hex::encode(rand::random::<[u8; 16]>()),
+            ttl: Duration::from_secs(ttl_secs.max(1)),
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Take the lock, or renew it if this holder has it already. `false`
+    /// means someone else holds it.
+    pub async fn acquire(&self) -> redis::RedisResult<bool> {
+        let mut conn = self.redis.get_multiplexed_async_connection().await?;
+        let set: Option<String> = redis::cmd("SET")
+            .arg(&self.key)
+            .arg(&self.token)
+            .arg("NX")
+            .arg("PX")
+            .arg(self.ttl.as_millis() as u64)
+            .query_async(&mut conn)
+            .await?;
+        if set.is_some() {
+            return Ok(true);
+        }
+        self.run_script(&mut conn, RENEW_SCRIPT).await
+    }
+
+    /// Push the lock's expiry back a full TTL. `false` means it lapsed and
+    /// may now be someone else's.
+    pub async fn renew(&self) -> redis::RedisResult<bool> {
+        let mut conn = self.redis.get_multiplexed_async_connection().await?;
+        self.run_script(&mut conn, RENEW_SCRIPT).await
+    }
+
+    /// Give the lock up if this holder still has it
+    pub async fn release(&self) -> redis::RedisResult<bool> {
+        let mut conn = self.redis.get_multiplexed_async_connection().await?;
+        self.run_script(&mut conn, RELEASE_SCRIPT).await
+    }
+
+    /// Run `job` if the lock can be taken, renewing it every third of its
+    /// TTL until the job finishes and then releasing it. `None` means
+    /// another instance holds the lock and the job didn't run.
+    pub async fn run_exclusive<F: Future>(&self, job: F) -> redis::RedisResult<Option<F::Output>> {
+        if !self.acquire().await? {
+            return Ok(None);
+        }
+
+        tokio::pin!(job);
+        let mut renewals = tokio::time::interval(self.ttl / 3);
+        // The first tick is immediate, and the lock was only just taken
+        renewals.tick().await;
+        let output = loop {
+            tokio::select! {
+                output = &mut job => break output,
+                _ = renewals.tick() => match self.renew().await {
+                    Ok(true) => {}
+                    Ok(false) => tracing::warn!(key = %self.key, "Job lock lapsed while the job was running"),
+                    Err(e) => tracing::warn!(key = %self.key, error = %e, "Failed to renew job lock"),
+                },
+            }
+        };
+
+        // Left alone, the lock still expires after its TTL
+        if let Err(e) = self.release().await {
+            tracing::debug!(key = %self.key, error = %e, "Failed to release job lock");
+        }
+
+        Ok(Some(output))
+    }
+
+    async fn run_script(&self, conn: &mut redis::aio::MultiplexedConnection, script: &str) -> redis::RedisResult<bool> {
+        let changed: i64 = redis::Script::new(script)
+            .key(&self.key)
+            .arg(&self.token)
+            .arg(self.ttl.as_millis() as u64)
+            .invoke_async(conn)
+            .await?;
+        Ok(changed == 1)
+    }
+}
\ No newline at end of file
@@ -1,11 +1,37 @@
 //! Business logic services
 
+pub mod admin;
+pub mod api_key;
 pub mod auth;
+pub mod email;
 pub mod wallet;
 pub mod transaction;
+pub mod transaction_monitor;
 pub mod agent;
+pub mod agent_inference;
+pub mod allocation;
+pub mod ensemble;
+pub mod job_lock;
+pub mod zkml;
+pub mod prediction_cleanup;
+pub mod proof_jobs;
+pub mod token_metadata;
+pub mod token_registry;
+pub mod webhook;
 
+pub use admin::AdminService;
+pub use api_key::ApiKeyService;
 pub use auth::AuthService;
+pub use email::{EmailSender, NoopEmailSender};
 pub use wallet::WalletService;
 pub use transaction::TransactionService;
-pub use agent::AgentService; 
\ No newline at end of file
+pub use transaction_monitor::{TransactionEvents, TransactionMonitor, TransactionUpdate};
+pub use agent::AgentService;
+pub use agent_inference::{AgentInference, AgentInferenceRegistry, MockAgentInference};
+pub use job_lock::JobLock;
+pub use prediction_cleanup::PredictionCleanup;
+pub use zkml::ZkmlProofService;
+pub use proof_jobs::{JobStatus, ProofJob, ProofJobQueue};
+pub use token_metadata::{TokenMetadata, TokenMetadataService};
+pub use token_registry::{TokenRegistry, TokenRegistryRefresher}; 
+pub use webhook::{WebhookDispatcher, WebhookEvent, WebhookService};
\ No newline at end of file
@@ -0,0 +1,35 @@
+//! Background deletion of expired agent predictions
+
+use crate::{api::AppState, services::{AgentService, JobLock}};
+use std::sync::Arc;
+
+pub struct PredictionCleanup {
+    state: Arc<AppState>,
+}
+
+impl PredictionCleanup {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+
+    /// Delete expired predictions every `prediction_cleanup.interval_secs`,
+    /// on whichever instance holds the cleanup lock at the time
+    pub async fn run(self) {
+        let config = &self.state.config.prediction_cleanup;
+        let lock = JobLock::new(self.state.redis.clone(), config.lock_key.clone(), config.lock_ttl_secs);
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(config.interval_secs.max(1)));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let agent_service = AgentService::new(self.state.clone());
+
+        loop {
+            interval.tick().await;
+            match lock.run_exclusive(agent_service.cleanup_expired_predictions()).await {
+                Ok(Some(Ok(0))) => {}
+                Ok(Some(Ok(count))) => tracing::info!(count, "Deleted expired predictions"),
+                Ok(Some(Err(e))) => tracing::warn!(error = %e, "Expired prediction cleanup failed"),
+                Ok(None) => tracing::debug!("Another instance is cleaning up expired predictions"),
+                Err(e) => tracing::warn!(error = %e, "Failed to take the prediction cleanup lock; skipping cleanup"),
+            }
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,154 @@
+//! Background proof generation jobs
+
+use crate::{
+    config::ZkmlConfig,
+    error::Result,
+    services::zkml::StoredProof,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use uuid::Uuid;
+
+/// How long finished jobs stay pollable before they are dropped
+const JOB_RETENTION: Duration = Duration::from_secs(60 * 60);
+
+/// Lifecycle of a proof job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+}
+
+impl JobStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(self, JobStatus::Completed | JobStatus::Failed)
+    }
+}
+
+/// A proof job, with the proof attached once it completes
+#[derive(Debug, Clone, Serialize)]
+pub struct ProofJob {
+    pub job_id: Uuid,
+    #[serde(skip)]
+    pub user_id: Uuid,
+    pub status: JobStatus,
+    pub proof: Option<StoredProof>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// In-memory queue running proof jobs on background tasks
+#[derive(Clone)]
+pub struct ProofJobQueue {
+    jobs: Arc<RwLock<HashMap<Uuid, ProofJob>>>,
+    workers: Arc<Semaphore>,
+    timeout: Duration,
+}
+
+impl ProofJobQueue {
+    /// Create a queue running up to `workers` jobs at once, failing any job
+    /// that takes longer than `timeout`
+    pub fn new(workers: usize, timeout: Duration) -> Self {
+        Self {
+            jobs: Arc::new(RwLock::new(HashMap::new())),
+            workers: Arc::new(Semaphore::new(workers.max(1))),
+            timeout,
+        }
+    }
+
+    pub fn from_config(config: &ZkmlConfig) -> Self {
+        Self::new(config.max_concurrent_proofs, Duration::from_secs(config.prover_timeout))
+    }
+
+    /// Queue `work` for the user and return the job id to poll
+    pub fn submit<F>(&self, user_id: Uuid, work: F) -> Uuid
+    where
+        F: Future<Output = Result<StoredProof>> + Send + 'static,
+    {
+        self.prune_finished();
+
+        let now = Utc::now();
+        let job_id = Uuid::new_v4();
+        if let Ok(mut jobs) = self.jobs.write() {
+            jobs.insert(job_id, ProofJob {
+                job_id,
+                user_id,
+                status: JobStatus::Queued,
+                proof: None,
+                error: None,
+                created_at: now,
+                updated_at: now,
+            });
+        }
+
+        let queue = self.clone();
+        tokio::spawn(async move {
+            let Ok(_permit) = queue.workers.acquire().await else {
+                queue.finish(job_id, Err("Proof queue is shut down".to_string()));
+                return;
+            };
+            queue.update(job_id, |job| job.status = JobStatus::Running);
+
+            let result = match tokio::time::timeout(queue.timeout, work).await {
+                Ok(Ok(proof)) => Ok(proof),
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(_) => Err(format!("Proof generation timed out after {}s", queue.timeout.as_secs())),
+            };
+            if let Err(e) = &result {
+                tracing::warn!(%job_id, error = %e, "Proof job failed");
+            }
+            queue.finish(job_id, result);
+        });
+
+        job_id
+    }
+
+    /// Get a job owned by the user
+    pub fn get(&self, job_id: Uuid, user_id: Uuid) -> Option<ProofJob> {
+        let jobs = self.jobs.read().ok()?;
+        jobs.get(&job_id)
+            .filter(|job| job.user_id == user_id)
+            .cloned()
+    }
+
+    fn finish(&self, job_id: Uuid, result: std::result::Result<StoredProof, String>) {
+        self.update(job_id, |job| match result {
+            Ok(proof) => {
+                job.status = JobStatus::Completed;
+                job.proof = Some(proof);
+            }
+            Err(e) => {
+                job.status = JobStatus::Failed;
+                job.error = Some(e);
+            }
+        });
+    }
+
+    fn update(&self, job_id: Uuid, f: impl FnOnce(&mut ProofJob)) {
+        if let Ok(mut jobs) = self.jobs.write() {
+            if let Some(job) = jobs.get_mut(&job_id) {
+                f(job);
+                job.updated_at = Utc::now();
+            }
+        }
+    }
+
+    fn prune_finished(&self) {
+        let Ok(retention) = chrono::Duration::from_std(JOB_RETENTION) else {
+            return;
+        };
+        let cutoff = Utc::now() - retention;
+        if let Ok(mut jobs) = self.jobs.write() {
+            jobs.retain(|_, job| !job.status.is_finished() || job.updated_at > cutoff);
+        }
+    }
+}
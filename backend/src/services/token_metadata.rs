@@ -0,0 +1,140 @@
+//! Token metadata resolution service
+
+use crate::{api::AppState, blockchain::metadata::OnChainMetadata};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Timeout for fetching a token's off-chain metadata JSON
+const OFF_CHAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Display metadata for a token mint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenMetadata {
+    pub symbol: String,
+    pub name: String,
+    pub logo_uri: Option<String>,
+}
+
+/// Well-known mainnet mints, which seed the token registry: (mint, symbol,
+/// name)
+const BUNDLED_TOKENS: &[(&str, &str, &str)] = &[
+    ("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", "USDC", "USD Coin"),
+    ("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB", "USDT", "USDT"),
+    ("So11111111111111111111111111111111111111112", "SOL", "Wrapped SOL"),
+    ("mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So", "mSOL", "Marinade staked SOL"),
+    ("DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263", "Bonk", "Bonk"),
+    ("JUPyiwrYJFskUPiHa7hkeR8VUtAeFoSYbKedZNsDvCN", "JUP", "Jupiter"),
+];
+
+/// Look up a mint in the bundled token list
+pub fn bundled_token_metadata(mint: &str) -> Option<TokenMetadata> {
+    bundled_tokens().find(|(address, _)| address == mint).map(|(_, metadata)| metadata)
+}
+
+/// Every mint in the bundled token list with its metadata
+pub fn bundled_tokens() -> impl Iterator<Item = (String, TokenMetadata)> {
+    BUNDLED_TOKENS.iter().map(|(mint, symbol, name)| {
+        let metadata = TokenMetadata {
+            symbol: symbol.to_string(),
+            name: name.to_string(),
+            logo_uri: None,
+        };
+        (mint.to_string(), metadata)
+    })
+}
+
+/// Redis key a mint's resolved metadata is cached under
+pub(crate) fn cache_key(mint: &str) -> String {
+    format!("token_metadata:{}", mint)
+}
+
+pub struct TokenMetadataService {
+    state: Arc<AppState>,
+}
+
+impl TokenMetadataService {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+
+    /// Resolve a mint to its metadata, or `None` if it is unknown.
+    ///
+    /// Results, including unknown mints, are cached in Redis. Cache and RPC
+    /// failures fall back to the token registry rather than failing the
+    /// caller.
+    pub async fn resolve(&self, mint: &str) -> Option<TokenMetadata> {
+        let key = cache_key(mint);
+        if let Some(cached) = self.cached(&key).await {
+            return cached;
+        }
+
+        let metadata = match self.state.solana_client.get_token_metadata(mint).await {
+            Ok(Some(on_chain)) if !on_chain.symbol.is_empty() => Some(with_logo(on_chain).await),
+            Ok(_) => self.state.token_registry.get(mint),
+            Err(e) => {
+                // Don't cache a lookup that failed, so it is retried next time
+                tracing::debug!(mint, error = %e, "Token metadata lookup failed");
+                return self.state.token_registry.get(mint);
+            }
+        };
+
+        self.cache(&key, &metadata).await;
+        metadata
+    }
+
+    async fn cached(&self, key: &str) -> Option<Option<TokenMetadata>> {
+        let mut conn = self.state.redis.get_multiplexed_async_connection().await.ok()?;
+        let value: Option<String> = conn.get(key).await.ok()?;
+        serde_json::from_str(&value?).ok()
+    }
+
+    async fn cache(&self, key: &str, metadata: &Option<TokenMetadata>) {
+        let Ok(value) = serde_json::to_string(metadata) else {
+            return;
+        };
+        let ttl = self.state.config.blockchain.token_metadata_ttl_secs;
+
+        match self.state.redis.get_multiplexed_async_connection().await {
+            Ok(mut conn) => {
+                if let Err(e) = conn.set_ex::<_, _, ()>(key, value, ttl).await {
+                    tracing::debug!(key, error = %e, "Failed to cache token metadata");
+                }
+            }
+            Err(e) => tracing::debug!(key, error = %e, "Failed to cache token metadata"),
+        }
+    }
+}
+
+/// Metadata from an on-chain account, with the logo from its off-chain JSON
+async fn with_logo(on_chain: OnChainMetadata) -> TokenMetadata {
+    let logo_uri = fetch_logo_uri(&on_chain.uri).await;
+    TokenMetadata {
+        symbol: on_chain.symbol,
+        name: on_chain.name,
+        logo_uri,
+    }
+}
+
+/// Read the `image` field from a token's off-chain metadata JSON
+async fn fetch_logo_uri(uri: &str) -> Option<String> {
+    if !uri.starts_with("https://") && !uri.starts_with("http://") {
+        return None;
+    }
+
+    let client = reqwest::Client::builder().timeout(OFF_CHAIN_TIMEOUT).build().ok()?;
+    let json: serde_json::Value = client.get(uri)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .ok()?
+        .json()
+        .await
+        .ok()?;
+
+    json.get("image")
+        .and_then(|image| image.as_str())
+        .filter(|image| !image.is_empty())
+        .map(str::to_string)
+}
@@ -0,0 +1,207 @@
+//! Registry of supported tokens, reloaded from a token list
+//!
+//! The registry starts out as the bundled token list. With a source
+//! configured, a background task reloads it on a jittered schedule, and
+//! admins can force a reload. Each reload replaces the registry and
+//! overwrites the Redis entries [`TokenMetadataService`] resolves from, so
+//! renamed and newly listed tokens show up in balances without waiting for
+//! cached metadata to expire.
+//!
+//! [`TokenMetadataService`]: super::TokenMetadataService
+
+use crate::{
+    api::AppState,
+    error::{Error, Result},
+    services::token_metadata::{bundled_tokens, cache_key, TokenMetadata},
+};
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Timeout for downloading the token list
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Metadata of every supported mint, shared by all requests
+#[derive(Debug, Clone)]
+pub struct TokenRegistry {
+    tokens: Arc<RwLock<HashMap<String, TokenMetadata>>>,
+}
+
+impl TokenRegistry {
+    pub fn get(&self, mint: &str) -> Option<TokenMetadata> {
+        self.tokens.read().ok()?.get(mint).cloned()
+    }
+
+    /// Number of supported mints
+    pub fn len(&self) -> usize {
+        self.tokens.read().map(|tokens| tokens.len()).unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn replace(&self, tokens: HashMap<String, TokenMetadata>) {
+        if let Ok(mut current) = self.tokens.write() {
+            *current = tokens;
+        }
+    }
+}
+
+impl Default for TokenRegistry {
+    fn default() -> Self {
+        Self {
+            tokens: Arc::new(RwLock::new(bundled_tokens().collect())),
+        }
+    }
+}
+
+/// One token in a Solana token-list document
+#[derive(Debug, Deserialize)]
+struct TokenListEntry {
+    address: String,
+    symbol: String,
+    name: String,
+    #[serde(default, rename = "logoURI", alias = "logo_uri")]
+    logo_uri: Option<String>,
+}
+
+/// A token list, either wrapped the way the Solana token list publishes it
+/// or as a bare array
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum TokenList {
+    Wrapped { tokens: Vec<TokenListEntry> },
+    Bare(Vec<TokenListEntry>),
+}
+
+impl TokenList {
+    fn into_entries(self) -> Vec<TokenListEntry> {
+        match self {
+            TokenList::Wrapped { tokens } | TokenList::Bare(tokens) => tokens,
+        }
+    }
+}
+
+/// Result of reloading the registry
+#[derive(Debug, Clone, Serialize)]
+pub struct RegistryRefresh {
+    /// Supported mints after the reload, bundled ones included
+    pub tokens: usize,
+    pub refreshed_at: DateTime<Utc>,
+}
+
+pub struct TokenRegistryRefresher {
+    state: Arc<AppState>,
+}
+
+impl TokenRegistryRefresher {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+
+    /// Reload the registry now and then every `refresh_interval_secs`, plus
+    /// up to `refresh_jitter_secs`
+    pub async fn run(self) {
+        loop {
+            match self.refresh().await {
+                Ok(refresh) => tracing::info!(tokens = refresh.tokens, "Refreshed token registry"),
+                Err(e) => tracing::warn!(error = %e, "Token registry refresh failed"),
+            }
+            tokio::time::sleep(self.next_delay()).await;
+        }
+    }
+
+    fn next_delay(&self) -> Duration {
+        let config = &self.state.config.blockchain.token_registry;
+        let jitter = rand::thread_rng().gen_range(0..=config.refresh_jitter_secs);
+        Duration::from_secs(config.refresh_interval_secs.max(1).saturating_add(jitter))
+    }
+
+    /// Reload the token list from the configured source. Tokens it lists
+    /// override the bundled ones; a source that can't be read leaves the
+    /// registry as it was.
+    pub async fn refresh(&self) -> Result<RegistryRefresh> {
+        let source = self.state.config.blockchain.token_registry.source.as_deref()
+            .ok_or_else(|| Error::BadRequest("No token registry source is configured".to_string()))?;
+
+        let listed: Vec<(String, TokenMetadata)> = load_token_list(source).await?
+            .into_iter()
+            .filter(|entry| !entry.address.is_empty() && !entry.symbol.is_empty())
+            .map(|entry| {
+                let metadata = TokenMetadata {
+                    symbol: entry.symbol,
+                    name: entry.name,
+                    logo_uri: entry.logo_uri.filter(|uri| !uri.is_empty()),
+                };
+                (entry.address, metadata)
+            })
+            .collect();
+
+        self.cache(&listed).await;
+
+        let mut tokens: HashMap<String, TokenMetadata> = bundled_tokens().collect();
+        tokens.extend(listed);
+        let refresh = RegistryRefresh {
+            tokens: tokens.len(),
+            refreshed_at: Utc::now(),
+        };
+        self.state.token_registry.replace(tokens);
+
+        Ok(refresh)
+    }
+
+    /// Overwrite the cached metadata of every listed token
+    async fn cache(&self, tokens: &[(String, TokenMetadata)]) {
+        if tokens.is_empty() {
+            return;
+        }
+
+        let ttl = self.state.config.blockchain.token_metadata_ttl_secs;
+        let mut pipe = redis::pipe();
+        for (mint, metadata) in tokens {
+            let Ok(value) = serde_json::to_string(&Some(metadata)) else {
+                continue;
+            };
+            pipe.set_ex(cache_key(mint), value, ttl).ignore();
+        }
+
+        let result = match self.state.redis.get_multiplexed_async_connection().await {
+            Ok(mut conn) => pipe.query_async::<_, ()>(&mut conn).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            tracing::warn!(error = %e, "Failed to cache refreshed token metadata");
+        }
+    }
+}
+
+/// Read a token list from an HTTP(S) URL or a local file
+async fn load_token_list(source: &str) -> Result<Vec<TokenListEntry>> {
+    let body = if source.starts_with("https://") || source.starts_with("http://") {
+        let client = reqwest::Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            .build()
+            .map_err(|e| Error::ExternalService(format!("Failed to build HTTP client: {}", e)))?;
+        client.get(source)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| Error::ExternalService(format!("Failed to fetch token list: {}", e)))?
+            .bytes()
+            .await
+            .map_err(|e| Error::ExternalService(format!("Failed to fetch token list: {}", e)))?
+            .to_vec()
+    } else {
+        tokio::fs::read(source)
+            .await
+            .map_err(|e| Error::ExternalService(format!("Failed to read token list {}: {}", source, e)))?
+    };
+
+    serde_json::from_slice::<TokenList>(&body)
+        .map(TokenList::into_entries)
+        .map_err(|e| Error::ExternalService(format!("Invalid token list: {}", e)))
+}
\ No newline at end of file
@@ -1,12 +1,20 @@
 //! Transaction service
 
 use crate::{
-    api::AppState,
+    api::{pagination::{Page, Paginated}, AppState},
+    blockchain::{token_accounts::{self, EncodedInstruction}, PriorityLevel},
+    config::MissingTokenAccountPolicy,
     db::{models::*, queries::*},
     error::{Error, Result},
-    services::wallet::WalletService,
+    services::{webhook::{self, WebhookEvent}, wallet::WalletService, TransactionUpdate},
+    utils::amount,
 };
+use rust_decimal::Decimal;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::str::FromStr;
 use std::sync::Arc;
+use tokio::{sync::Semaphore, task::JoinSet};
 use uuid::Uuid;
 
 pub struct TransactionService {
@@ -28,8 +36,9 @@ impl TransactionService {
         let wallet_service = WalletService::new(self.state.clone());
         let _wallet = wallet_service.get_wallet(transaction_data.wallet_id, user_id).await?;
 
-        // Validate transaction data
-        self.validate_transaction_data(&transaction_data)?;
+        // Validate transaction data, storing the amount in canonical form
+        let amount = self.validate_transaction_data(&transaction_data)?;
+        let transaction_data = CreateTransaction { amount: amount::format_amount(amount), ..transaction_data };
 
         // Create the transaction
         let transaction = TransactionQueries::create(self.state.db.pool(), &transaction_data).await?;
@@ -37,27 +46,35 @@ impl TransactionService {
         Ok(transaction)
     }
 
-    /// Get transactions for a wallet
+    /// Get a page of a wallet's transactions matching `filter`
     pub async fn get_wallet_transactions(
         &self,
         wallet_id: Uuid,
         user_id: Uuid,
-        limit: i64,
-        offset: i64,
-    ) -> Result<Vec<Transaction>> {
+        filter: TransactionFilter,
+        page: Page,
+    ) -> Result<Paginated<Transaction>> {
+        if let (Some(from), Some(to)) = (filter.from, filter.to) {
+            if from >= to {
+                return Err(Error::Validation("from must be before to".to_string()));
+            }
+        }
+
         // Validate the wallet belongs to the user
         let wallet_service = WalletService::new(self.state.clone());
         let _wallet = wallet_service.get_wallet(wallet_id, user_id).await?;
 
         // Get transactions
-        let transactions = TransactionQueries::find_by_wallet_id(self.state.db.pool(), wallet_id, limit, offset).await?;
+        let pool = self.state.db.read_pool();
+        let transactions = TransactionQueries::find_by_wallet_id(pool, wallet_id, &filter, page.limit, page.offset).await?;
+        let total = TransactionQueries::count_by_wallet_id(pool, wallet_id, &filter).await?;
 
-        Ok(transactions)
+        Ok(Paginated::new(transactions, total, page))
     }
 
     /// Get a specific transaction
     pub async fn get_transaction(&self, transaction_id: Uuid, user_id: Uuid) -> Result<Transaction> {
-        let transaction = TransactionQueries::find_by_id(self.state.db.pool(), transaction_id).await?
+        let transaction = TransactionQueries::find_by_id(self.state.db.read_pool(), transaction_id).await?
             .ok_or(Error::NotFound)?;
 
         // Verify the transaction's wallet belongs to the user
@@ -87,17 +104,44 @@ impl TransactionService {
             error_message,
         ).await?;
 
+        if matches!(transaction.status, TransactionStatus::Confirmed) {
+            self.notify_confirmed(&transaction).await;
+        }
+
         Ok(transaction)
     }
 
+    /// Queue a `transaction.confirmed` webhook event for the wallet's owner.
+    /// The confirmation is already recorded, so a failure here is only
+    /// logged.
+    async fn notify_confirmed(&self, transaction: &Transaction) {
+        let pool = self.state.db.pool();
+        let result = async {
+            let wallet = WalletQueries::find_by_id(pool, transaction.wallet_id).await?
+                .ok_or(Error::NotFound)?;
+            webhook::enqueue_event(
+                pool,
+                wallet.user_id,
+                WebhookEvent::TransactionConfirmed,
+                &TransactionUpdate::from(transaction),
+            ).await
+        }.await;
+
+        if let Err(e) = result {
+            tracing::warn!(transaction_id = %transaction.id, error = %e, "Failed to queue confirmation webhooks");
+        }
+    }
+
     /// Get pending transactions (for blockchain monitoring)
     pub async fn get_pending_transactions(&self) -> Result<Vec<Transaction>> {
         let transactions = TransactionQueries::find_pending(self.state.db.pool()).await?;
         Ok(transactions)
     }
 
-    /// Submit transaction to blockchain using Solana client
-    pub async fn submit_transaction(&self, transaction_id: Uuid) -> Result<String> {
+    /// Submit transaction to blockchain using Solana client. With `simulate`
+    /// set, the transaction is simulated first and not broadcast if the
+    /// simulation fails.
+    pub async fn submit_transaction(&self, transaction_id: Uuid, simulate: bool) -> Result<String> {
         let transaction = TransactionQueries::find_by_id(self.state.db.pool(), transaction_id).await?
             .ok_or(Error::NotFound)?;
 
@@ -105,13 +149,36 @@ impl TransactionService {
         let raw_transaction = transaction.raw_transaction
             .ok_or(Error::BadRequest("No raw transaction data available".to_string()))?;
 
-        // Submit to Solana blockchain
+        if simulate {
+            let simulation = self.state.solana_client.simulate_transaction(&raw_transaction).await?;
+            if let Some(error) = simulation.error {
+                tracing::debug!(%transaction_id, logs = ?simulation.logs, "Transaction failed simulation");
+
+                // A transfer to a token account that doesn't exist is the
+                // usual culprit, and the client can fix it
+                let missing = self.state.solana_client.missing_transfer_destinations(&raw_transaction).await
+                    .unwrap_or_default();
+                if let Some(account) = missing.first() {
+                    return Err(Error::TokenAccountMissing(account.to_string()));
+                }
+                return Err(Error::TransactionFailed(format!("Simulation failed: {}", error)));
+            }
+        }
+
+        // Submit to Solana blockchain and wait for confirmation
         let result = self.state.solana_client.submit_transaction(&raw_transaction).await?;
 
+        // Transactions that haven't confirmed yet stay pending for monitoring
+        let status = if result.confirmation_status.is_confirmed() {
+            TransactionStatus::Confirmed
+        } else {
+            TransactionStatus::Pending
+        };
+
         // Update transaction with blockchain result
         self.update_transaction_status(
             transaction_id,
-            TransactionStatus::Pending,
+            status,
             Some(&result.signature),
             Some(result.slot as i64),
             None,
@@ -121,24 +188,136 @@ impl TransactionService {
         Ok(result.signature)
     }
 
-    /// Estimate transaction fee using Solana client
-    pub async fn estimate_fee(&self, transaction_data: &CreateTransaction) -> Result<TransactionFeeEstimate> {
+    /// Submit several of the user's transactions, a few at a time. Each
+    /// transaction's outcome is reported in request order; one failing
+    /// doesn't stop the rest.
+    pub async fn submit_transactions_batch(
+        &self,
+        user_id: Uuid,
+        transaction_ids: &[Uuid],
+        simulate: bool,
+    ) -> Result<Vec<BatchSubmitResult>> {
+        let config = &self.state.config.blockchain;
+        if transaction_ids.is_empty() {
+            return Err(Error::Validation("At least one transaction id is required".to_string()));
+        }
+        if transaction_ids.len() > config.max_submit_batch_size {
+            return Err(Error::Validation(format!(
+                "At most {} transactions can be submitted at once",
+                config.max_submit_batch_size
+            )));
+        }
+        let mut seen = HashSet::new();
+        if let Some(duplicate) = transaction_ids.iter().find(|id| !seen.insert(**id)) {
+            return Err(Error::Validation(format!("Transaction {} is listed more than once", duplicate)));
+        }
+
+        let permits = Arc::new(Semaphore::new(config.max_concurrent_submissions.max(1)));
+        let mut submissions = JoinSet::new();
+        for (index, &transaction_id) in transaction_ids.iter().enumerate() {
+            let service = TransactionService::new(self.state.clone());
+            let permits = permits.clone();
+            submissions.spawn(async move {
+                let _permit = permits.acquire_owned().await;
+                // Ownership is checked per transaction so one foreign id
+                // only fails its own entry
+                let result = match service.get_transaction(transaction_id, user_id).await {
+                    Ok(_) => service.submit_transaction(transaction_id, simulate).await,
+                    Err(e) => Err(e),
+                };
+                (index, result)
+            });
+        }
+
+        let mut results: Vec<BatchSubmitResult> = transaction_ids.iter()
+            .map(|&transaction_id| BatchSubmitResult {
+                transaction_id,
+                signature: None,
+                error: Some("Submission did not complete".to_string()),
+            })
+            .collect();
+
+        while let Some(joined) = submissions.join_next().await {
+            let Ok((index, result)) = joined else { continue };
+            let entry = &mut results[index];
+            match result {
+                Ok(signature) => {
+                    entry.signature = Some(signature);
+                    entry.error = None;
+                }
+                Err(e) => entry.error = Some(e.to_string()),
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Estimate transaction fee using Solana client, with a priority fee
+    /// matching `priority_level`
+    pub async fn estimate_fee(&self, transaction_data: &CreateTransaction, priority_level: PriorityLevel) -> Result<TransactionFeeEstimate> {
         // Ensure we have raw transaction data for fee estimation
         let raw_transaction = transaction_data.raw_transaction
             .as_ref()
             .ok_or(Error::BadRequest("Raw transaction data required for fee estimation".to_string()))?;
 
+        let token_account_creation = self.recipient_token_account(transaction_data).await?;
+
         // Get fee estimate from Solana
-        let fee_estimate = self.state.solana_client.estimate_fee(raw_transaction).await?;
+        let fee_estimate = self.state.solana_client.estimate_fee(raw_transaction, priority_level).await?;
+        let total_lamports = fee_estimate.fee_lamports.saturating_add(fee_estimate.priority_fee_lamports);
 
         Ok(TransactionFeeEstimate {
-            base_fee: fee_estimate.fee_sol.to_string(),
-            priority_fee: "0.0".to_string(), // Solana doesn't have separate priority fees like Ethereum
-            total_fee: fee_estimate.fee_sol.to_string(),
+            base_fee: amount::format_amount(amount::lamports_to_sol(fee_estimate.fee_lamports)),
+            priority_fee: amount::format_amount(amount::lamports_to_sol(fee_estimate.priority_fee_lamports)),
+            total_fee: amount::format_amount(amount::lamports_to_sol(total_lamports)),
             fee_currency: "SOL".to_string(),
+            priority_level,
+            compute_unit_price: fee_estimate.compute_unit_price,
+            compute_unit_limit: fee_estimate.compute_unit_limit,
+            needs_compute_budget_update: fee_estimate.needs_compute_budget_update,
+            token_account_creation,
         })
     }
 
+    /// Check that an SPL transfer's recipient has an associated token
+    /// account for the mint. If not, the configured policy either rejects
+    /// the transfer or returns the instruction creating the account.
+    async fn recipient_token_account(&self, transaction_data: &CreateTransaction) -> Result<Option<TokenAccountCreation>> {
+        let (TransactionType::Send, Some(mint)) = (&transaction_data.transaction_type, &transaction_data.token_mint) else {
+            return Ok(None);
+        };
+
+        let parse = |address: &str, role: &str| {
+            Pubkey::from_str(address.trim()).map_err(|_| Error::Validation(format!("Invalid {} address", role)))
+        };
+        let payer = parse(&transaction_data.from_address, "sender")?;
+        let owner = parse(&transaction_data.to_address, "recipient")?;
+        let mint = parse(mint, "token mint")?;
+
+        let client = &self.state.solana_client;
+        let address = token_accounts::associated_token_address(&owner, &mint);
+        if client.account_exists(&address).await? {
+            return Ok(None);
+        }
+
+        match self.state.config.blockchain.missing_token_account {
+            MissingTokenAccountPolicy::Reject => Err(Error::TokenAccountMissing(address.to_string())),
+            MissingTokenAccountPolicy::CreateBySender => {
+                let rent_lamports = client.minimum_balance_for_rent_exemption(token_accounts::TOKEN_ACCOUNT_LEN).await?;
+                let instruction = token_accounts::create_associated_token_account(&payer, &owner, &mint);
+
+                Ok(Some(TokenAccountCreation {
+                    address: address.to_string(),
+                    owner: owner.to_string(),
+                    mint: mint.to_string(),
+                    payer: payer.to_string(),
+                    rent_lamports,
+                    instruction: EncodedInstruction::from(&instruction),
+                }))
+            }
+        }
+    }
+
     /// Monitor and update transaction status from blockchain
     pub async fn monitor_transaction(&self, transaction_id: Uuid) -> Result<Transaction> {
         let transaction = TransactionQueries::find_by_id(self.state.db.pool(), transaction_id).await?
@@ -147,7 +326,8 @@ impl TransactionService {
         // Only monitor transactions that have been submitted
         if let Some(tx_hash) = &transaction.transaction_hash {
             // Check status on Solana blockchain
-            if let Some(result) = self.state.solana_client.get_transaction_status(tx_hash).await? {
+            let result = self.state.solana_client.get_transaction_status(tx_hash).await?;
+            if let Some(result) = result.filter(|result| result.confirmation_status.is_confirmed()) {
                 // Update transaction status based on blockchain result
                 let updated_transaction = self.update_transaction_status(
                     transaction_id,
@@ -165,8 +345,8 @@ impl TransactionService {
         Ok(transaction)
     }
 
-    /// Validate transaction data
-    fn validate_transaction_data(&self, transaction_data: &CreateTransaction) -> Result<()> {
+    /// Validate transaction data, returning the parsed amount
+    fn validate_transaction_data(&self, transaction_data: &CreateTransaction) -> Result<Decimal> {
         // Validate addresses
         if transaction_data.from_address.trim().is_empty() {
             return Err(Error::Validation("From address cannot be empty".to_string()));
@@ -176,15 +356,7 @@ impl TransactionService {
             return Err(Error::Validation("To address cannot be empty".to_string()));
         }
 
-        // Validate amount
-        if let Err(_) = transaction_data.amount.parse::<f64>() {
-            return Err(Error::Validation("Invalid amount format".to_string()));
-        }
-
-        let amount: f64 = transaction_data.amount.parse().unwrap();
-        if amount <= 0.0 {
-            return Err(Error::Validation("Amount must be greater than zero".to_string()));
-        }
+        let amount = amount::parse_positive_amount(&transaction_data.amount)?;
 
         // Validate transaction type specific requirements
         match transaction_data.transaction_type {
@@ -198,7 +370,7 @@ impl TransactionService {
             }
             TransactionType::Stake | TransactionType::Unstake => {
                 // Additional validation for staking transactions
-                if amount < 0.001 {
+                if amount < Decimal::new(1, 3) {
                     return Err(Error::Validation("Minimum stake amount is 0.001 SOL".to_string()));
                 }
             }
@@ -209,29 +381,57 @@ impl TransactionService {
             }
         }
 
-        Ok(())
+        Ok(amount)
     }
 
-    /// Get transaction history with analytics
+    /// Analytics over the user's transactions from the last `days` days,
+    /// optionally for a single wallet
     pub async fn get_transaction_analytics(
         &self,
         user_id: Uuid,
         wallet_id: Option<Uuid>,
         days: i32,
     ) -> Result<TransactionAnalytics> {
-        // TODO: Implement comprehensive transaction analytics
-        // For now, return mock data
+        if days <= 0 {
+            return Err(Error::Validation("days must be positive".to_string()));
+        }
+
+        let since = chrono::Utc::now() - chrono::Duration::days(days.into());
+        let aggregates = TransactionQueries::analytics(self.state.db.read_pool(), user_id, wallet_id, since).await?;
+
+        // Only settled transactions count towards the success rate
+        let settled = aggregates.confirmed + aggregates.failed;
+        let success_rate = if settled == 0 {
+            100.0
+        } else {
+            aggregates.confirmed as f64 / settled as f64 * 100.0
+        };
+
         Ok(TransactionAnalytics {
-            total_transactions: 0,
-            total_volume: "0.0".to_string(),
-            total_fees: "0.0".to_string(),
-            success_rate: 100.0,
-            average_confirmation_time: 0,
-            transaction_types: vec![],
+            total_transactions: aggregates.total_transactions,
+            total_volume: aggregates.total_volume,
+            total_fees: aggregates.total_fees,
+            success_rate,
+            average_confirmation_time: aggregates.average_confirmation_secs.map_or(0, |secs| secs.round() as i64),
+            transaction_types: aggregates.by_type.into_iter()
+                .map(|by_type| TransactionTypeCount {
+                    transaction_type: by_type.transaction_type,
+                    count: by_type.count,
+                    volume: by_type.volume,
+                })
+                .collect(),
         })
     }
 }
 
+/// Outcome of one transaction in a batch submission
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct BatchSubmitResult {
+    pub transaction_id: Uuid,
+    pub signature: Option<String>,
+    pub error: Option<String>,
+}
+
 /// Transaction fee estimate
 #[derive(Debug, serde::Serialize)]
 pub struct TransactionFeeEstimate {
@@ -239,6 +439,29 @@ pub struct TransactionFeeEstimate {
     pub priority_fee: String,
     pub total_fee: String,
     pub fee_currency: String,
+    pub priority_level: PriorityLevel,
+    /// Compute unit price to set on the transaction, in micro-lamports
+    pub compute_unit_price: u64,
+    pub compute_unit_limit: u32,
+    /// The transaction sets a lower compute unit price than recommended
+    /// and should be rebuilt with the compute budget before signing
+    pub needs_compute_budget_update: bool,
+    /// Token account the sender must create for the recipient, by adding
+    /// this instruction ahead of the transfer
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_account_creation: Option<TokenAccountCreation>,
+}
+
+/// A recipient's associated token account that doesn't exist yet
+#[derive(Debug, serde::Serialize)]
+pub struct TokenAccountCreation {
+    pub address: String,
+    pub owner: String,
+    pub mint: String,
+    pub payer: String,
+    /// Rent the payer deposits into the new account, on top of the fees
+    pub rent_lamports: u64,
+    pub instruction: EncodedInstruction,
 }
 
 /// Transaction analytics
@@ -0,0 +1,242 @@
+//! Background confirmation of pending transactions
+//!
+//! Transactions whose submission timed out before confirming stay pending
+//! with their signature recorded. The monitor periodically looks each one
+//! up on chain, records confirmations and on-chain failures, gives up on
+//! those that never confirm, and publishes every status change it makes.
+
+use crate::{
+    api::AppState,
+    config::TransactionMonitorConfig,
+    db::{models::*, queries::TransactionQueries},
+    error::{Error, Result},
+    services::{JobLock, TransactionService},
+};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Updates buffered for each subscriber; one that falls further behind
+/// misses the oldest
+const EVENT_CAPACITY: usize = 1024;
+
+/// A transaction's new status
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TransactionUpdate {
+    pub transaction_id: Uuid,
+    pub wallet_id: Uuid,
+    pub status: TransactionStatus,
+    pub transaction_hash: Option<String>,
+    pub block_number: Option<i64>,
+    pub error_message: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<&Transaction> for TransactionUpdate {
+    fn from(transaction: &Transaction) -> Self {
+        Self {
+            transaction_id: transaction.id,
+            wallet_id: transaction.wallet_id,
+            status: transaction.status.clone(),
+            transaction_hash: transaction.transaction_hash.clone(),
+            block_number: transaction.block_number,
+            error_message: transaction.error_message.clone(),
+            updated_at: transaction.updated_at,
+        }
+    }
+}
+
+/// Fans transaction status changes out to everyone listening
+#[derive(Debug, Clone)]
+pub struct TransactionEvents {
+    sender: broadcast::Sender<TransactionUpdate>,
+}
+
+impl TransactionEvents {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    /// Send `update` to the current subscribers, if there are any
+    pub fn publish(&self, update: TransactionUpdate) {
+        let _ = self.sender.send(update);
+    }
+
+    /// Receive every update published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<TransactionUpdate> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for TransactionEvents {
+    fn default() -> Self {
+        Self::new(EVENT_CAPACITY)
+    }
+}
+
+/// What one sweep did
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MonitorSweep {
+    /// Transactions looked up on chain
+    pub checked: usize,
+    pub confirmed: usize,
+    pub failed: usize,
+}
+
+pub struct TransactionMonitor {
+    state: Arc<AppState>,
+    /// Last check of each transaction old enough to be backed off
+    last_checked: HashMap<Uuid, DateTime<Utc>>,
+}
+
+impl TransactionMonitor {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self {
+            state,
+            last_checked: HashMap::new(),
+        }
+    }
+
+    /// Sweep the pending transactions every `monitor.interval_secs`, on
+    /// whichever instance holds the monitor's lock at the time
+    pub async fn run(mut self) {
+        let config = &self.state.config.blockchain.monitor;
+        let lock = JobLock::new(self.state.redis.clone(), config.lock_key.clone(), config.lock_ttl_secs);
+        let period = std::time::Duration::from_secs(config.interval_secs.max(1));
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        loop {
+            interval.tick().await;
+            match lock.run_exclusive(self.sweep()).await {
+                Ok(Some(Ok(sweep))) if sweep.confirmed + sweep.failed > 0 => tracing::info!(
+                    checked = sweep.checked,
+                    confirmed = sweep.confirmed,
+                    failed = sweep.failed,
+                    "Updated pending transactions"
+                ),
+                Ok(Some(Ok(_))) => {}
+                Ok(Some(Err(e))) => tracing::warn!(error = %e, "Pending transaction sweep failed"),
+                Ok(None) => tracing::debug!("Another instance is sweeping pending transactions"),
+                // Sweeping without the lock could race another instance
+                Err(e) => tracing::warn!(error = %e, "Failed to take the transaction monitor lock; skipping sweep"),
+            }
+        }
+    }
+
+    /// Check each submitted transaction that is still pending and due a
+    /// check, ageing transactions from when they were created
+    pub async fn sweep(&mut self) -> Result<MonitorSweep> {
+        let config = self.state.config.blockchain.monitor.clone();
+        let now = Utc::now();
+        let pending = TransactionQueries::find_pending(self.state.db.pool()).await?;
+
+        // Forget transactions that have settled since the last sweep
+        let pending_ids: HashSet<Uuid> = pending.iter().map(|transaction| transaction.id).collect();
+        self.last_checked.retain(|id, _| pending_ids.contains(id));
+
+        let mut sweep = MonitorSweep::default();
+        for transaction in &pending {
+            // Not submitted yet, so there is nothing to look up
+            let Some(signature) = transaction.transaction_hash.as_deref() else {
+                continue;
+            };
+            let age = now - transaction.created_at;
+            if !self.is_due(transaction.id, age, now, &config) {
+                continue;
+            }
+
+            sweep.checked += 1;
+            match self.check(transaction, signature, age, &config).await {
+                Ok(Some(update)) => {
+                    match update.status {
+                        TransactionStatus::Confirmed => sweep.confirmed += 1,
+                        TransactionStatus::Failed => sweep.failed += 1,
+                        _ => {}
+                    }
+                    self.last_checked.remove(&transaction.id);
+                    self.state.transaction_events.publish(update);
+                }
+                Ok(None) => {
+                    self.last_checked.insert(transaction.id, now);
+                }
+                Err(e) => {
+                    tracing::debug!(transaction_id = %transaction.id, error = %e, "Failed to check pending transaction");
+                    self.last_checked.insert(transaction.id, now);
+                }
+            }
+        }
+
+        Ok(sweep)
+    }
+
+    /// Young transactions are checked on every sweep, older ones every
+    /// `backoff_interval_secs`, and any past the deadline once more before
+    /// they are failed
+    fn is_due(&self, transaction_id: Uuid, age: Duration, now: DateTime<Utc>, config: &TransactionMonitorConfig) -> bool {
+        if age < seconds(config.backoff_after_secs) || age >= seconds(config.deadline_secs) {
+            return true;
+        }
+
+        self.last_checked
+            .get(&transaction_id)
+            .map_or(true, |last| now - *last >= seconds(config.backoff_interval_secs))
+    }
+
+    /// Look the transaction up on chain and record what became of it, if
+    /// anything did
+    async fn check(
+        &self,
+        transaction: &Transaction,
+        signature: &str,
+        age: Duration,
+        config: &TransactionMonitorConfig,
+    ) -> Result<Option<TransactionUpdate>> {
+        let service = TransactionService::new(self.state.clone());
+
+        let updated = match self.state.solana_client.get_transaction_status(signature).await {
+            Ok(Some(result)) if self.state.solana_client.meets_commitment(result.confirmation_status) => {
+                service.update_transaction_status(
+                    transaction.id,
+                    TransactionStatus::Confirmed,
+                    Some(&result.signature),
+                    Some(result.slot as i64),
+                    None,
+                    None,
+                ).await?
+            }
+            Err(Error::TransactionFailed(reason)) => {
+                service.update_transaction_status(
+                    transaction.id,
+                    TransactionStatus::Failed,
+                    None,
+                    None,
+                    None,
+                    Some(&reason),
+                ).await?
+            }
+            Err(e) => return Err(e),
+            Ok(_) if age >= seconds(config.deadline_secs) => {
+                let reason = format!("Not confirmed within {}s", config.deadline_secs);
+                service.update_transaction_status(
+                    transaction.id,
+                    TransactionStatus::Failed,
+                    None,
+                    None,
+                    None,
+                    Some(&reason),
+                ).await?
+            }
+            Ok(_) => return Ok(None),
+        };
+
+        Ok(Some(TransactionUpdate::from(&updated)))
+    }
+}
+
+fn seconds(secs: u64) -> Duration {
+    Duration::seconds(secs.min(i64::MAX as u64) as i64)
+}
\ No newline at end of file
@@ -1,11 +1,18 @@
 //! Wallet service
 
 use crate::{
-    api::AppState,
+    api::{pagination::{Page, Paginated}, AppState},
+    auth::KeyVault,
+    blockchain::TransactionResult,
     db::{models::*, queries::*},
     error::{Error, Result},
+    services::TokenMetadataService,
 };
+use redis::AsyncCommands;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
 use std::sync::Arc;
+use tokio::{sync::Semaphore, task::JoinSet};
 use uuid::Uuid;
 
 pub struct WalletService {
@@ -31,21 +38,56 @@ impl WalletService {
             return Err(Error::BadRequest("Wallet with this public key already exists".to_string()));
         }
 
+        // Only the sealed private key is stored; the plaintext is wiped
+        // when `wallet_data` is dropped
+        let encrypted_private_key = match &wallet_data.private_key {
+            Some(private_key) => Some(
+                KeyVault::from_config(&self.state.config.key_vault)?
+                    .encrypt(&wallet_data.public_key, private_key.expose().as_bytes())?,
+            ),
+            None => None,
+        };
+
         // Create the wallet
-        let wallet = WalletQueries::create(self.state.db.pool(), user_id, &wallet_data).await?;
+        let wallet = WalletQueries::create(
+            self.state.db.pool(),
+            user_id,
+            &wallet_data,
+            encrypted_private_key.as_deref(),
+        ).await?;
 
         Ok(wallet)
     }
 
-    /// Get all wallets for a user
-    pub async fn get_user_wallets(&self, user_id: Uuid) -> Result<Vec<Wallet>> {
-        let wallets = WalletQueries::find_by_user_id(self.state.db.pool(), user_id).await?;
-        Ok(wallets)
+    /// Run `sign` with the wallet's private key. This is the only way to the
+    /// plaintext: it's decrypted for the call and wiped as soon as `sign`
+    /// returns.
+    pub async fn with_signing_key<T>(
+        &self,
+        wallet_id: Uuid,
+        user_id: Uuid,
+        sign: impl FnOnce(&[u8]) -> Result<T>,
+    ) -> Result<T> {
+        let wallet = self.get_wallet(wallet_id, user_id).await?;
+        let sealed = wallet.encrypted_private_key.as_deref()
+            .ok_or_else(|| Error::BadRequest("Wallet has no private key".to_string()))?;
+
+        let private_key = KeyVault::from_config(&self.state.config.key_vault)?
+            .decrypt(&wallet.public_key, sealed)?;
+        sign(&private_key)
+    }
+
+    /// Get a page of a user's wallets
+    pub async fn get_user_wallets(&self, user_id: Uuid, page: Page) -> Result<Paginated<Wallet>> {
+        let pool = self.state.db.read_pool();
+        let wallets = WalletQueries::find_by_user_id(pool, user_id, page.limit, page.offset).await?;
+        let total = WalletQueries::count_by_user_id(pool, user_id).await?;
+        Ok(Paginated::new(wallets, total, page))
     }
 
     /// Get a specific wallet by ID
     pub async fn get_wallet(&self, wallet_id: Uuid, user_id: Uuid) -> Result<Wallet> {
-        let wallet = WalletQueries::find_by_id(self.state.db.pool(), wallet_id).await?
+        let wallet = WalletQueries::find_by_id(self.state.db.read_pool(), wallet_id).await?
             .ok_or(Error::NotFound)?;
 
         // Ensure the wallet belongs to the user
@@ -73,11 +115,56 @@ impl WalletService {
         Ok(())
     }
 
-    /// Get wallet balance using real Solana blockchain data
-    pub async fn get_wallet_balance(&self, wallet_id: Uuid, user_id: Uuid) -> Result<WalletBalance> {
+    /// Get wallet balance using real Solana blockchain data, served from the
+    /// balance caches unless `fresh` is set
+    pub async fn get_wallet_balance(&self, wallet_id: Uuid, user_id: Uuid, fresh: bool) -> Result<WalletBalance> {
         let wallet = self.get_wallet(wallet_id, user_id).await?;
+        self.wallet_balance(&wallet, fresh).await
+    }
+
+    /// Balances of every one of the user's active wallets, a few looked up
+    /// at a time and served from the balance caches where possible. A wallet
+    /// whose balance can't be fetched gets an error entry instead of failing
+    /// the others.
+    pub async fn get_all_balances(&self, user_id: Uuid) -> Result<Vec<WalletBalanceResult>> {
+        let wallets = WalletQueries::find_all_by_user_id(self.state.db.read_pool(), user_id).await?;
+
+        let permits = Arc::new(Semaphore::new(self.state.config.blockchain.max_concurrent_balance_fetches.max(1)));
+        let mut lookups = JoinSet::new();
+        for (index, wallet) in wallets.iter().cloned().enumerate() {
+            let service = WalletService::new(self.state.clone());
+            let permits = permits.clone();
+            lookups.spawn(async move {
+                let _permit = permits.acquire_owned().await;
+                (index, service.wallet_balance(&wallet, false).await)
+            });
+        }
+
+        let mut results: Vec<WalletBalanceResult> = wallets.iter()
+            .map(|wallet| WalletBalanceResult {
+                wallet_id: wallet.id,
+                balance: None,
+                error: Some("Balance lookup did not complete".to_string()),
+            })
+            .collect();
+
+        while let Some(joined) = lookups.join_next().await {
+            let Ok((index, result)) = joined else { continue };
+            let entry = &mut results[index];
+            match result {
+                Ok(balance) => {
+                    entry.balance = Some(balance);
+                    entry.error = None;
+                }
+                Err(e) => entry.error = Some(e.to_string()),
+            }
+        }
 
-        // Only fetch balance for Solana wallets for now
+        Ok(results)
+    }
+
+    async fn wallet_balance(&self, wallet: &Wallet, fresh: bool) -> Result<WalletBalance> {
+        let wallet_id = wallet.id;
         match wallet.wallet_type {
             WalletType::Solana => {
                 // Validate the Solana address first
@@ -85,30 +172,69 @@ impl WalletService {
                     return Err(Error::Validation("Invalid Solana address".to_string()));
                 }
 
+                let key = format!("wallet_balance:{}", wallet.public_key);
+                if !fresh {
+                    if let Some(cached) = self.cached_balance(&key).await {
+                        return Ok(WalletBalance { wallet_id, ..cached });
+                    }
+                }
+
                 // Get balance from Solana blockchain
-                let balance = self.state.solana_client.get_balance(&wallet.public_key).await?;
+                let cached = self.state.solana_client.get_balance_cached(&wallet.public_key, fresh).await?;
+                let balance = cached.balance;
 
-                // Convert to our response format
-                let token_balances: Vec<TokenBalance> = balance.token_balances
-                    .into_iter()
-                    .map(|tb| TokenBalance {
-                        mint: tb.mint,
+                // Convert to our response format, leaving unknown tokens unlabelled
+                let metadata_service = TokenMetadataService::new(self.state.clone());
+                let mut token_balances = Vec::with_capacity(balance.token_balances.len());
+                for tb in balance.token_balances {
+                    let metadata = metadata_service.resolve(&tb.mint).await;
+                    token_balances.push(TokenBalance {
                         balance: tb.amount_formatted.to_string(),
                         decimals: tb.decimals,
-                        symbol: None, // TODO: Add token metadata lookup
-                        name: None,   // TODO: Add token metadata lookup
-                    })
-                    .collect();
+                        symbol: metadata.as_ref().map(|m| m.symbol.clone()),
+                        name: metadata.as_ref().map(|m| m.name.clone()),
+                        logo_uri: metadata.and_then(|m| m.logo_uri),
+                        mint: tb.mint,
+                    });
+                }
 
-                Ok(WalletBalance {
+                let wallet_balance = WalletBalance {
                     wallet_id,
                     sol_balance: balance.sol_balance_formatted.to_string(),
                     token_balances,
+                    last_updated: cached.fetched_at,
+                };
+                self.cache_balance(&key, &wallet_balance).await;
+
+                Ok(wallet_balance)
+            }
+            WalletType::Ethereum => {
+                if !self.state.ethereum_client.validate_address(&wallet.public_key)? {
+                    return Err(Error::Validation("Invalid Ethereum address".to_string()));
+                }
+
+                let key = format!("wallet_balance:{}", wallet.public_key);
+                if !fresh {
+                    if let Some(cached) = self.cached_balance(&key).await {
+                        return Ok(WalletBalance { wallet_id, ..cached });
+                    }
+                }
+
+                // ERC-20 holdings can't be listed without an indexer, so
+                // only the ether balance is reported
+                let balance = self.state.ethereum_client.get_balance(&wallet.public_key).await?;
+                let wallet_balance = WalletBalance {
+                    wallet_id,
+                    sol_balance: balance.ether,
+                    token_balances: vec![],
                     last_updated: chrono::Utc::now(),
-                })
+                };
+                self.cache_balance(&key, &wallet_balance).await;
+
+                Ok(wallet_balance)
             }
             _ => {
-                // For non-Solana wallets, return empty balance for now
+                // Bitcoin and watch-only wallets have no balance source yet
                 Ok(WalletBalance {
                     wallet_id,
                     sol_balance: "0.0".to_string(),
@@ -119,6 +245,66 @@ impl WalletService {
         }
     }
 
+    /// Fund a Solana wallet from the cluster's faucet for development and
+    /// testing. Any user's wallet can be funded; callers restrict this to
+    /// administrators. Refused outright in production.
+    pub async fn request_airdrop(&self, wallet_id: Uuid, lamports: u64) -> Result<TransactionResult> {
+        if self.state.config.environment == "production" {
+            return Err(Error::Forbidden);
+        }
+        if lamports == 0 {
+            return Err(Error::Validation("Airdrop amount must be greater than zero".to_string()));
+        }
+
+        let wallet = WalletQueries::find_by_id(self.state.db.read_pool(), wallet_id).await?
+            .ok_or(Error::NotFound)?;
+        if !matches!(wallet.wallet_type, WalletType::Solana) {
+            return Err(Error::Validation("Airdrops are only available for Solana wallets".to_string()));
+        }
+        let address = Pubkey::from_str(&wallet.public_key)
+            .map_err(|_| Error::Validation("Invalid Solana address".to_string()))?;
+
+        let result = self.state.solana_client.request_airdrop(&address, lamports).await?;
+
+        // The cached response predates the airdrop
+        if let Ok(mut conn) = self.state.redis.get_multiplexed_async_connection().await {
+            let _: redis::RedisResult<()> = conn.del(format!("wallet_balance:{}", wallet.public_key)).await;
+        }
+
+        Ok(result)
+    }
+
+    /// A balance response another request cached in Redis. Redis being
+    /// unavailable counts as a miss.
+    async fn cached_balance(&self, key: &str) -> Option<WalletBalance> {
+        if self.state.config.blockchain.balance_cache.redis_ttl_secs == 0 {
+            return None;
+        }
+
+        let mut conn = self.state.redis.get_multiplexed_async_connection().await.ok()?;
+        let value: Option<String> = conn.get(key).await.ok()?;
+        serde_json::from_str(&value?).ok()
+    }
+
+    async fn cache_balance(&self, key: &str, balance: &WalletBalance) {
+        let ttl = self.state.config.blockchain.balance_cache.redis_ttl_secs;
+        if ttl == 0 {
+            return;
+        }
+        let Ok(value) = serde_json::to_string(balance) else {
+            return;
+        };
+
+        match self.state.redis.get_multiplexed_async_connection().await {
+            Ok(mut conn) => {
+                if let Err(e) = conn.set_ex::<_, _, ()>(key, value, ttl).await {
+                    tracing::debug!(key, error = %e, "Failed to cache wallet balance");
+                }
+            }
+            Err(e) => tracing::debug!(key, error = %e, "Failed to cache wallet balance"),
+        }
+    }
+
     /// Validate wallet creation data
     fn validate_wallet_data(&self, wallet_data: &CreateWallet) -> Result<()> {
         // Validate wallet name
@@ -150,7 +336,7 @@ impl WalletService {
             }
             WalletType::WatchOnly => {
                 // Watch-only wallets should not have private keys
-                if wallet_data.encrypted_private_key.is_some() {
+                if wallet_data.private_key.is_some() {
                     return Err(Error::Validation("Watch-only wallets cannot have private keys".to_string()));
                 }
             }
@@ -161,20 +347,30 @@ impl WalletService {
 }
 
 /// Wallet balance response
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct WalletBalance {
     pub wallet_id: Uuid,
+    /// Native balance: SOL for Solana wallets, ether for Ethereum ones
     pub sol_balance: String,
     pub token_balances: Vec<TokenBalance>,
     pub last_updated: chrono::DateTime<chrono::Utc>,
 }
 
+/// Outcome of one wallet's lookup in a batch of balances
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct WalletBalanceResult {
+    pub wallet_id: Uuid,
+    pub balance: Option<WalletBalance>,
+    pub error: Option<String>,
+}
+
 /// Token balance information
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct TokenBalance {
     pub mint: String,
     pub balance: String,
     pub decimals: u8,
     pub symbol: Option<String>,
     pub name: Option<String>,
+    pub logo_uri: Option<String>,
 }
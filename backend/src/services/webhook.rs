@@ -0,0 +1,438 @@
+//! Webhook registration and delivery
+//!
+//! Events are queued in the same transaction as the change they describe,
+//! one delivery per subscribed webhook, and a background dispatcher POSTs
+//! them. Each body is signed with HMAC-SHA256 under the webhook's secret so
+//! receivers can check it came from us. Deliveries that fail are retried
+//! with exponential backoff until `webhooks.max_attempts` is reached.
+//!
+//! Webhook URLs must lead to public addresses only, so users can't have the
+//! server make requests inside its own network. Names are resolved and
+//! checked on registration, and again for every delivery, since DNS can
+//! change after a webhook is registered. Secrets are stored sealed with the
+//! key vault.
+
+use crate::{
+    api::{
+        handlers::webhook::{CreateWebhookRequest, CreatedWebhook},
+        AppState,
+    },
+    db::{
+        models::{DueWebhookDelivery, Webhook},
+        queries::{WebhookDeliveryQueries, WebhookQueries},
+    },
+    auth::{webhook_context, KeyVault},
+    error::{Error, Result},
+};
+use chrono::{Duration, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use serde::Serialize;
+use sha2::Sha256;
+use sqlx::PgExecutor;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use tokio::task::JoinSet;
+use uuid::Uuid;
+
+/// Header carrying the event name
+pub const EVENT_HEADER: &str = "X-Guardian-Event";
+/// Header carrying the delivery ID, the same on every retry
+pub const DELIVERY_HEADER: &str = "X-Guardian-Delivery";
+/// Header carrying `sha256=<hex HMAC of the body>`
+pub const SIGNATURE_HEADER: &str = "X-Guardian-Signature";
+
+/// Events a webhook can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookEvent {
+    TransactionConfirmed,
+    PredictionCreated,
+}
+
+impl WebhookEvent {
+    pub const ALL: [WebhookEvent; 2] = [WebhookEvent::TransactionConfirmed, WebhookEvent::PredictionCreated];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WebhookEvent::TransactionConfirmed => "transaction.confirmed",
+            WebhookEvent::PredictionCreated => "prediction.created",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|event| event.name() == name)
+    }
+}
+
+/// Hex HMAC-SHA256 of `body` under `secret`
+pub fn sign_payload(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Whether webhooks may be sent to `ip`: anything but loopback, private,
+/// link-local, unspecified, shared (carrier NAT), benchmarking, reserved,
+/// documentation, broadcast and multicast addresses. IPv6 addresses that
+/// carry an IPv4 one (mapped, compatible, NAT64 and 6to4) are judged by the
+/// IPv4 address they lead to.
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                // "This network" (0.0.0.0/8) and shared address space (100.64.0.0/10)
+                || a == 0
+                || (a == 100 && (64..128).contains(&b))
+                // IETF protocol assignments (192.0.0.0/24), benchmarking
+                // (198.18.0.0/15) and reserved (240.0.0.0/4)
+                || (a == 192 && b == 0 && c == 0)
+                || (a == 198 && b & 0xfe == 18)
+                || a >= 240)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = embedded_ipv4(ip) {
+                return is_public_address(IpAddr::V4(ip));
+            }
+            let segments = ip.segments();
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local (fc00::/7) and link-local (fe80::/10)
+                || segments[0] & 0xfe00 == 0xfc00
+                || segments[0] & 0xffc0 == 0xfe80
+                // Local-use NAT64 (64:ff9b:1::/48), whose prefixes vary
+                // too much to find the IPv4 address in
+                || segments[..3] == [0x64, 0xff9b, 1])
+        }
+    }
+}
+
+/// The IPv4 address an IPv6 one leads to, for those made from one:
+/// IPv4-mapped (::ffff:a.b.c.d), IPv4-compatible (::a.b.c.d), the NAT64
+/// well-known prefix (64:ff9b::/96) and 6to4 (2002::/16)
+fn embedded_ipv4(ip: Ipv6Addr) -> Option<Ipv4Addr> {
+    let segments = ip.segments();
+    let from_segments = |high: u16, low: u16| Ipv4Addr::from((u32::from(high) << 16) | u32::from(low));
+
+    match segments {
+        [0, 0, 0, 0, 0, 0xffff, high, low]
+        | [0, 0, 0, 0, 0, 0, high, low]
+        | [0x64, 0xff9b, 0, 0, 0, 0, high, low] => Some(from_segments(high, low)),
+        [0x2002, high, low, ..] => Some(from_segments(high, low)),
+        _ => None,
+    }
+}
+
+/// The addresses `host` resolves to, refused if any of them isn't public
+async fn resolve_public(host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port)).await?.collect();
+    if let Some(addr) = addrs.iter().find(|addr| !is_public_address(addr.ip())) {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} resolves to non-public address {}", host, addr.ip()),
+        ));
+    }
+    Ok(addrs)
+}
+
+/// DNS resolution for deliveries, refusing names that lead anywhere but
+/// public addresses
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs = resolve_public(name.as_str(), 0).await?;
+            Ok::<Addrs, Box<dyn std::error::Error + Send + Sync>>(Box::new(addrs.into_iter()))
+        })
+    }
+}
+
+/// The IP address a URL names directly, if it does. Such URLs are never
+/// resolved, so they must be checked on their own.
+fn literal_ip(url: &reqwest::Url) -> Option<IpAddr> {
+    url.host_str()?.trim_start_matches('[').trim_end_matches(']').parse().ok()
+}
+
+/// Check that `url` is an http or https URL leading only to public
+/// addresses, unless `allow_private` says otherwise
+pub async fn check_webhook_url(url: &reqwest::Url, allow_private: bool) -> Result<()> {
+    let host = match url.host_str() {
+        Some(host) if matches!(url.scheme(), "http" | "https") => host,
+        _ => return Err(Error::Validation("Webhook URL must be an http or https URL".to_string())),
+    };
+    if allow_private {
+        return Ok(());
+    }
+
+    let public = match literal_ip(url) {
+        Some(ip) => is_public_address(ip),
+        None => {
+            let port = url.port_or_known_default().unwrap_or(443);
+            match resolve_public(host, port).await {
+                Ok(_) => true,
+                Err(e) if e.kind() == io::ErrorKind::PermissionDenied => false,
+                Err(_) => return Err(Error::Validation(format!("Webhook host {} could not be resolved", host))),
+            }
+        }
+    };
+    if !public {
+        return Err(Error::Validation(
+            "Webhook URL must not lead to a loopback, private or link-local address".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Queue `event` for each of the user's webhooks subscribed to it. Pass the
+/// transaction making the change so the event is only sent if it commits.
+pub async fn enqueue_event<T: Serialize>(
+    executor: impl PgExecutor<'_>,
+    user_id: Uuid,
+    event: WebhookEvent,
+    data: &T,
+) -> Result<u64> {
+    let body = serde_json::json!({
+        "id": Uuid::new_v4(),
+        "event": event.name(),
+        "created_at": Utc::now(),
+        "data": data,
+    });
+    WebhookDeliveryQueries::enqueue(executor, user_id, event.name(), &body.to_string()).await
+}
+
+/// Prefix identifying Guardian webhook secrets
+const WEBHOOK_SECRET_PREFIX: &str = "whsec_";
+
+pub struct WebhookService {
+    state: Arc<AppState>,
+}
+
+impl WebhookService {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+
+    /// Register a webhook for the user, up to `webhooks.max_per_user`. The
+    /// signing secret is only returned here.
+    pub async fn create_webhook(&self, user_id: Uuid, req: CreateWebhookRequest) -> Result<CreatedWebhook> {
+        let url = req.url.trim().to_string();
+        let parsed = reqwest::Url::parse(&url)
+            .map_err(|_| Error::Validation("Webhook URL is not a valid URL".to_string()))?;
+        if req.event_types.is_empty() {
+            return Err(Error::Validation("Webhook must subscribe to at least one event".to_string()));
+        }
+        if let Some(unknown) = req.event_types.iter().find(|e| WebhookEvent::from_name(e).is_none()) {
+            return Err(Error::Validation(format!("Unknown webhook event: {}", unknown)));
+        }
+        check_webhook_url(&parsed, self.state.config.webhooks.allow_private_addresses).await?;
+
+        let mut event_types = req.event_types;
+        event_types.sort();
+        event_types.dedup();
+        // Only the sealed secret is stored; the plaintext goes back once, below
+        let webhook_id = Uuid::new_v4();
+        let secret = format!("{}{}", WEBHOOK_SECRET_PREFIX, hex::encode(rand::random::<[u8; 32]>()));
+        let encrypted_secret = KeyVault::from_config(&self.state.config.key_vault)?
+            .encrypt(&webhook_context(&webhook_id), secret.as_bytes())?;
+        let max_webhooks = self.state.config.webhooks.max_per_user;
+
+        let webhook = self.state.db.transaction(move |conn| Box::pin(async move {
+            let registered = WebhookQueries::count_for_user_locked(&mut *conn, user_id).await?;
+            if registered >= max_webhooks {
+                return Err(Error::Conflict(format!(
+                    "Webhook limit of {} reached; delete a webhook before registering another",
+                    max_webhooks
+                )));
+            }
+
+            WebhookQueries::create(&mut *conn, webhook_id, user_id, &url, &encrypted_secret, &event_types).await
+        })).await?;
+
+        Ok(CreatedWebhook {
+            id: webhook.id,
+            url: webhook.url,
+            secret,
+            event_types: webhook.event_types,
+            created_at: webhook.created_at,
+        })
+    }
+
+    /// The user's webhooks, without their secrets
+    pub async fn list_webhooks(&self, user_id: Uuid) -> Result<Vec<Webhook>> {
+        WebhookQueries::find_by_user_id(self.state.db.pool(), user_id).await
+    }
+
+    /// Delete one of the user's webhooks, dropping anything still queued for it
+    pub async fn delete_webhook(&self, user_id: Uuid, webhook_id: Uuid) -> Result<()> {
+        if !WebhookQueries::delete(self.state.db.pool(), webhook_id, user_id).await? {
+            return Err(Error::NotFound);
+        }
+        Ok(())
+    }
+}
+
+/// What one dispatch did
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DeliverySweep {
+    pub delivered: usize,
+    /// Failed attempts that will be tried again
+    pub retried: usize,
+    /// Failed attempts that were the last
+    pub failed: usize,
+}
+
+/// Outcome of sending one delivery
+enum Attempt {
+    Delivered(i32),
+    Failed { status: Option<i32>, error: String },
+}
+
+pub struct WebhookDispatcher {
+    state: Arc<AppState>,
+    client: reqwest::Client,
+}
+
+impl WebhookDispatcher {
+    pub fn new(state: Arc<AppState>) -> Self {
+        let timeout = std::time::Duration::from_secs(state.config.webhooks.timeout_secs.max(1));
+        let mut builder = reqwest::Client::builder()
+            .timeout(timeout)
+            // A receiver redirecting elsewhere shouldn't get the payload
+            // re-sent to a URL nobody registered
+            .redirect(reqwest::redirect::Policy::none());
+        if !state.config.webhooks.allow_private_addresses {
+            builder = builder.dns_resolver(Arc::new(PublicResolver));
+        }
+        let client = builder.build().unwrap_or_default();
+        Self { state, client }
+    }
+
+    /// Send due deliveries every `webhooks.interval_secs`
+    pub async fn run(self) {
+        let period = std::time::Duration::from_secs(self.state.config.webhooks.interval_secs.max(1));
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        loop {
+            interval.tick().await;
+            match self.deliver_due().await {
+                Ok(sweep) if sweep != DeliverySweep::default() => tracing::info!(
+                    delivered = sweep.delivered,
+                    retried = sweep.retried,
+                    failed = sweep.failed,
+                    "Sent webhook deliveries"
+                ),
+                Ok(_) => {}
+                Err(e) => tracing::warn!(error = %e, "Webhook dispatch failed"),
+            }
+        }
+    }
+
+    /// Send one batch of the deliveries that are due, recording each result
+    pub async fn deliver_due(&self) -> Result<DeliverySweep> {
+        let config = &self.state.config.webhooks;
+        let pool = self.state.db.pool();
+        // Long enough that nobody else picks a delivery up while it's sent
+        let lease_secs = (config.timeout_secs.max(1) * 2 + config.interval_secs) as i64;
+        let vault = KeyVault::from_config(&self.state.config.key_vault)?;
+        let due = WebhookDeliveryQueries::claim_due(pool, config.batch_size.max(1), lease_secs).await?;
+
+        let mut sends = JoinSet::new();
+        for delivery in due {
+            let client = self.client.clone();
+            let vault = vault.clone();
+            let allow_private = config.allow_private_addresses;
+            sends.spawn(async move {
+                let attempt = send(&client, &vault, allow_private, &delivery).await;
+                (delivery, attempt)
+            });
+        }
+
+        let mut sweep = DeliverySweep::default();
+        while let Some(joined) = sends.join_next().await {
+            let Ok((delivery, attempt)) = joined else { continue };
+            match attempt {
+                Attempt::Delivered(status) => {
+                    WebhookDeliveryQueries::mark_delivered(pool, delivery.id, status).await?;
+                    sweep.delivered += 1;
+                }
+                Attempt::Failed { status, error } => {
+                    let attempts = delivery.attempts + 1;
+                    let retry_at = (attempts < config.max_attempts)
+                        .then(|| Utc::now() + retry_delay(config.backoff_base_secs, config.max_backoff_secs, attempts));
+                    tracing::debug!(
+                        delivery_id = %delivery.id,
+                        attempts,
+                        error = %error,
+                        "Webhook delivery failed"
+                    );
+                    WebhookDeliveryQueries::mark_attempt_failed(pool, delivery.id, status, &error, retry_at).await?;
+                    if retry_at.is_some() {
+                        sweep.retried += 1;
+                    } else {
+                        sweep.failed += 1;
+                    }
+                }
+            }
+        }
+
+        Ok(sweep)
+    }
+}
+
+/// Wait before the retry following the `attempts`th failure: the base
+/// delay, doubled for each earlier failure, capped at `max_secs`
+fn retry_delay(base_secs: u64, max_secs: u64, attempts: i32) -> Duration {
+    let doublings = (attempts - 1).clamp(0, 30) as u32;
+    let secs = base_secs.saturating_mul(1 << doublings).min(max_secs);
+    Duration::seconds(secs as i64)
+}
+
+async fn send(client: &reqwest::Client, vault: &KeyVault, allow_private: bool, delivery: &DueWebhookDelivery) -> Attempt {
+    let failed = |error: &str| Attempt::Failed { status: None, error: error.to_string() };
+
+    // The client's resolver checks names; addresses in the URL are used as is
+    let Ok(url) = reqwest::Url::parse(&delivery.url) else {
+        return failed("Webhook URL is not a valid URL");
+    };
+    if !allow_private && literal_ip(&url).is_some_and(|ip| !is_public_address(ip)) {
+        return failed("Webhook URL leads to a non-public address");
+    }
+
+    let Ok(secret) = vault.decrypt(&webhook_context(&delivery.webhook_id), &delivery.encrypted_secret) else {
+        return failed("Webhook secret could not be decrypted");
+    };
+    let Ok(secret) = std::str::from_utf8(&secret) else {
+        return failed("Webhook secret could not be decrypted");
+    };
+
+    let signature = format!("sha256={}", sign_payload(secret, &delivery.body));
+    let response = client.post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, &delivery.event_type)
+        .header(DELIVERY_HEADER, delivery.id.to_string())
+        .header(SIGNATURE_HEADER, signature)
+        .body(delivery.body.clone())
+        .send()
+        .await;
+
+    match response {
+        Ok(response) if response.status().is_success() => Attempt::Delivered(response.status().as_u16() as i32),
+        Ok(response) => Attempt::Failed {
+            status: Some(response.status().as_u16() as i32),
+            error: format!("Receiver responded with {}", response.status()),
+        },
+        Err(e) => Attempt::Failed { status: None, error: e.to_string() },
+    }
+}
\ No newline at end of file
@@ -0,0 +1,266 @@
+//! ZK-ML proof service
+
+use crate::{
+    api::AppState,
+    db::{models::*, queries::*},
+    error::{Error, Result},
+    services::JobStatus,
+    zkml::{gas::GasCostTable, ProofRequest, ZkProof},
+};
+use base64::{Engine as _, engine::general_purpose};
+use chrono::{DateTime, Utc};
+use guardian_zkml::abi::{sha256_abi, Abi};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// A generated proof together with the id of its stored record
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredProof {
+    pub proof_id: Uuid,
+    #[serde(flatten)]
+    pub proof: ZkProof,
+}
+
+/// Outcome of one input of a batch proof request
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchProofResult {
+    /// Position of the input in the request
+    pub index: usize,
+    pub proof: Option<StoredProof>,
+    pub error: Option<String>,
+}
+
+/// Verification status of a stored proof
+#[derive(Debug, Clone, Serialize)]
+pub struct ProofStatus {
+    pub proof_id: Uuid,
+    /// Always completed; reported so clients can poll jobs and proofs alike
+    pub status: JobStatus,
+    pub is_verified: bool,
+    pub created_at: DateTime<Utc>,
+    pub verified_at: Option<DateTime<Utc>>,
+    /// Estimated cost of verifying the proof on the configured chain, set
+    /// once the proof is verified
+    pub verification_gas_cost: Option<i64>,
+}
+
+impl From<ZkmlProof> for ProofStatus {
+    fn from(proof: ZkmlProof) -> Self {
+        Self {
+            proof_id: proof.id,
+            status: JobStatus::Completed,
+            is_verified: proof.is_verified,
+            created_at: proof.created_at,
+            verified_at: proof.verified_at,
+            verification_gas_cost: proof.verification_gas_cost,
+        }
+    }
+}
+
+/// A stored proof's public inputs, labelled with the circuit ABI's field
+/// names
+#[derive(Debug, Clone, Serialize)]
+pub struct ProofExplanation {
+    pub proof_id: Uuid,
+    pub circuit_type: String,
+    pub circuit_name: String,
+    pub abi_version: String,
+    /// Hex-encoded public inputs, as stored
+    pub public_inputs_hex: String,
+    pub public_inputs: Vec<LabeledPublicInput>,
+    pub security_properties: Vec<String>,
+    pub is_verified: bool,
+}
+
+/// One public input with its ABI field
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LabeledPublicInput {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_info: String,
+    pub byte_offset: usize,
+    pub value: u8,
+    pub description: String,
+}
+
+pub struct ZkmlProofService {
+    state: Arc<AppState>,
+}
+
+impl ZkmlProofService {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+
+    /// Generate a proof for `request` and record it for the user
+    pub async fn generate_proof(&self, user_id: Uuid, request: &ProofRequest) -> Result<StoredProof> {
+        let proof = self.state.zkml_service.generate_proof(request).await?;
+        self.store_proof(user_id, proof).await
+    }
+
+    /// Generate SHA256 proofs for several inputs and record the successful ones
+    pub async fn generate_proofs_batch(&self, user_id: Uuid, inputs: &[Vec<u8>]) -> Result<Vec<BatchProofResult>> {
+        let proofs = self.state.zkml_service.generate_sha256_proofs_batch(inputs).await?;
+
+        let mut results = Vec::with_capacity(proofs.len());
+        for (index, proof) in proofs.into_iter().enumerate() {
+            let stored = match proof {
+                Ok(proof) => self.store_proof(user_id, proof).await,
+                Err(e) => Err(e),
+            };
+            results.push(match stored {
+                Ok(proof) => BatchProofResult { index, proof: Some(proof), error: None },
+                Err(e) => BatchProofResult { index, proof: None, error: Some(e.to_string()) },
+            });
+        }
+
+        Ok(results)
+    }
+
+    /// Record an already generated proof for the user
+    pub async fn store_proof(&self, user_id: Uuid, proof: ZkProof) -> Result<StoredProof> {
+        let verification_key_hash = self.state.zkml_service.verification_key_hash()?;
+
+        let record = ZkmlProofQueries::create(
+            self.state.db.pool(),
+            None,
+            user_id,
+            ProofType::HashProof,
+            &general_purpose::STANDARD.encode(&proof.proof_data),
+            &serde_json::json!(hex::encode(&proof.public_inputs)),
+            &verification_key_hash,
+            &circuit_hash(&proof.circuit_type),
+        ).await?;
+
+        Ok(StoredProof {
+            proof_id: record.id,
+            proof,
+        })
+    }
+
+    /// Get the status of a proof owned by the user
+    pub async fn get_proof_status(&self, proof_id: Uuid, user_id: Uuid) -> Result<ProofStatus> {
+        let proof = self.find_user_proof(proof_id, user_id).await?;
+        Ok(proof.into())
+    }
+
+    /// Mark a proof owned by the user as verified and return its new status.
+    /// With on-chain verification enabled the Guardian program must accept
+    /// the proof too, and the compute units it took are recorded as the
+    /// gas cost; otherwise an estimate for the target chain is.
+    pub async fn mark_verified(&self, proof_id: Uuid, user_id: Uuid) -> Result<ProofStatus> {
+        let proof = self.find_user_proof(proof_id, user_id).await?;
+        let gas_cost = if self.state.config.blockchain.on_chain_verification.enabled {
+            let proof_bytes = general_purpose::STANDARD.decode(&proof.proof_data)
+                .map_err(|_| Error::Validation("Stored proof data is not valid base64".to_string()))?;
+            let public_inputs = hex::decode(proof.public_inputs.as_str().unwrap_or_default())
+                .map_err(|_| Error::Validation("Stored public inputs are not valid hex".to_string()))?;
+
+            let verification = self.state.solana_client.verify_proof_on_chain(&proof_bytes, &public_inputs).await?;
+            if !verification.verified {
+                return Err(Error::Validation(format!(
+                    "The Guardian program rejected the proof: {}",
+                    verification.error.unwrap_or_default()
+                )));
+            }
+            verification.compute_units
+        } else {
+            Some(GasCostTable::from(&self.state.config.zkml.verification_gas)
+                .estimate(stored_proof_len(&proof), stored_public_input_len(&proof)))
+        };
+        let gas_cost = gas_cost.map(|cost| i64::try_from(cost).unwrap_or(i64::MAX));
+        ZkmlProofQueries::mark_verified(self.state.db.pool(), proof_id, gas_cost).await?;
+
+        let proof = ZkmlProofQueries::find_by_id(self.state.db.pool(), proof_id).await?
+            .ok_or(Error::NotFound)?;
+        Ok(proof.into())
+    }
+
+    /// Label the public inputs of a proof owned by the user with its
+    /// circuit's ABI
+    pub async fn explain_proof(&self, proof_id: Uuid, user_id: Uuid) -> Result<ProofExplanation> {
+        let proof = self.find_user_proof(proof_id, user_id).await?;
+
+        // Only the SHA256 circuit publishes an ABI
+        let circuit_type = "sha256";
+        if proof.circuit_hash != circuit_hash(circuit_type) {
+            return Err(Error::Validation("No ABI is published for the circuit this proof was made with".to_string()));
+        }
+        let abi = sha256_abi();
+
+        let public_inputs_hex = proof.public_inputs.as_str().unwrap_or_default().to_string();
+        let public_inputs = hex::decode(&public_inputs_hex)
+            .ok()
+            .and_then(|bytes| label_public_inputs(&abi, &bytes))
+            .ok_or_else(|| {
+                tracing::error!(%proof_id, "Stored public inputs don't match the circuit ABI");
+                Error::Internal
+            })?;
+
+        Ok(ProofExplanation {
+            proof_id,
+            circuit_type: circuit_type.to_string(),
+            circuit_name: abi.circuit_name,
+            abi_version: abi.version,
+            public_inputs_hex,
+            public_inputs,
+            security_properties: abi.security_properties,
+            is_verified: proof.is_verified,
+        })
+    }
+
+    async fn find_user_proof(&self, proof_id: Uuid, user_id: Uuid) -> Result<ZkmlProof> {
+        // Read from the primary so a status check right after generation
+        // never misses the new row on a lagging replica
+        let proof = ZkmlProofQueries::find_by_id(self.state.db.pool(), proof_id).await?
+            .ok_or(Error::NotFound)?;
+
+        // Don't reveal other users' proofs
+        if proof.user_id != user_id {
+            return Err(Error::NotFound);
+        }
+
+        Ok(proof)
+    }
+}
+
+/// Pair each public input the ABI describes with its byte, or `None` if
+/// the inputs don't have the shape the ABI expects
+pub fn label_public_inputs(abi: &Abi, public_inputs: &[u8]) -> Option<Vec<LabeledPublicInput>> {
+    if public_inputs.len() != abi.public_inputs.len() {
+        return None;
+    }
+
+    abi.public_inputs.iter()
+        .map(|field| Some(LabeledPublicInput {
+            name: field.name.clone(),
+            type_info: field.type_info.clone(),
+            byte_offset: field.byte_offset,
+            value: *public_inputs.get(field.byte_offset)?,
+            description: field.description.clone(),
+        }))
+        .collect()
+}
+
+/// Size in bytes of a stored proof
+fn stored_proof_len(proof: &ZkmlProof) -> usize {
+    general_purpose::STANDARD.decode(&proof.proof_data)
+        .map(|bytes| bytes.len())
+        .unwrap_or(proof.proof_data.len() * 3 / 4)
+}
+
+/// Size in bytes of a stored proof's public inputs, which are kept as a
+/// hex string
+fn stored_public_input_len(proof: &ZkmlProof) -> usize {
+    match &proof.public_inputs {
+        serde_json::Value::String(encoded) => encoded.len() / 2,
+        other => other.to_string().len(),
+    }
+}
+
+/// Identifier of the circuit a proof was made with, stored alongside it
+fn circuit_hash(circuit_type: &str) -> String {
+    hex::encode(Sha256::digest(circuit_type.as_bytes()))
+}
@@ -0,0 +1,130 @@
+//! Prometheus metrics for Guardian-AA Backend
+//!
+//! Metrics are recorded through the `metrics` facade into one process-wide
+//! Prometheus recorder and rendered by the `/metrics` endpoint.
+
+use crate::{api::AppState, db::Database};
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use sqlx::PgPool;
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+
+pub const HTTP_REQUESTS_TOTAL: &str = "http_requests_total";
+pub const HTTP_REQUEST_DURATION_SECONDS: &str = "http_request_duration_seconds";
+pub const PROOF_GENERATION_DURATION_SECONDS: &str = "zkml_proof_generation_duration_seconds";
+pub const SOLANA_RPC_CALLS_TOTAL: &str = "solana_rpc_calls_total";
+pub const SOLANA_RPC_ERRORS_TOTAL: &str = "solana_rpc_errors_total";
+pub const DB_POOL_CONNECTIONS: &str = "db_pool_connections";
+pub const DB_POOL_IDLE_CONNECTIONS: &str = "db_pool_idle_connections";
+pub const DB_POOL_MAX_CONNECTIONS: &str = "db_pool_max_connections";
+pub const WEBSOCKET_CONNECTIONS_ACTIVE: &str = "websocket_connections_active";
+
+/// Buckets for the latency histograms, from fast API calls up to slow proofs
+const DURATION_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Handle to the process-wide recorder, installing it on first use
+pub fn handle() -> &'static PrometheusHandle {
+    HANDLE.get_or_init(|| {
+        let recorder = PrometheusBuilder::new()
+            .set_buckets_for_metric(Matcher::Suffix("_seconds".to_string()), DURATION_BUCKETS)
+            .expect("duration buckets are not empty")
+            .build_recorder();
+        let handle = recorder.handle();
+        if metrics::set_global_recorder(recorder).is_err() {
+            tracing::warn!("A metrics recorder was already installed; Prometheus metrics will be empty");
+        }
+        handle
+    })
+}
+
+/// Count each request and time it, labelled by the route that matched
+/// rather than the raw path so IDs don't become separate series
+pub async fn http_metrics_middleware(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let start = Instant::now();
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16().to_string();
+    metrics::counter!(HTTP_REQUESTS_TOTAL, "method" => method.clone(), "route" => route.clone(), "status" => status)
+        .increment(1);
+    metrics::histogram!(HTTP_REQUEST_DURATION_SECONDS, "method" => method, "route" => route)
+        .record(start.elapsed().as_secs_f64());
+
+    response
+}
+
+/// Record how long generating a proof for `circuit` took
+pub fn record_proof_generation(circuit: &str, started: Instant) {
+    metrics::histogram!(PROOF_GENERATION_DURATION_SECONDS, "circuit" => circuit.to_string())
+        .record(started.elapsed().as_secs_f64());
+}
+
+/// Count a call to the Solana RPC `method` and whether it failed
+pub fn record_rpc_call(method: &'static str, succeeded: bool) {
+    metrics::counter!(SOLANA_RPC_CALLS_TOTAL, "method" => method).increment(1);
+    if !succeeded {
+        metrics::counter!(SOLANA_RPC_ERRORS_TOTAL, "method" => method).increment(1);
+    }
+}
+
+/// Counts a WebSocket connection as active for as long as it is held
+pub struct ActiveConnection(());
+
+impl ActiveConnection {
+    pub fn new() -> Self {
+        metrics::gauge!(WEBSOCKET_CONNECTIONS_ACTIVE).increment(1.0);
+        Self(())
+    }
+}
+
+impl Default for ActiveConnection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for ActiveConnection {
+    fn drop(&mut self) {
+        metrics::gauge!(WEBSOCKET_CONNECTIONS_ACTIVE).decrement(1.0);
+    }
+}
+
+/// Pool usage is sampled at scrape time rather than tracked on every query
+fn record_pool_usage(db: &Database) {
+    record_pool("primary", db.pool());
+    if db.has_read_replica() {
+        record_pool("replica", db.read_pool());
+    }
+}
+
+fn record_pool(name: &'static str, pool: &PgPool) {
+    metrics::gauge!(DB_POOL_CONNECTIONS, "pool" => name).set(pool.size() as f64);
+    metrics::gauge!(DB_POOL_IDLE_CONNECTIONS, "pool" => name).set(pool.num_idle() as f64);
+    metrics::gauge!(DB_POOL_MAX_CONNECTIONS, "pool" => name).set(pool.options().get_max_connections() as f64);
+}
+
+/// Render all metrics in the Prometheus text format
+pub async fn metrics_handler(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    record_pool_usage(&state.db);
+
+    let handle = handle();
+    handle.run_upkeep();
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        handle.render(),
+    )
+}
\ No newline at end of file
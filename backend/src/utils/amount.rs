@@ -0,0 +1,54 @@
+//! Exact parsing and formatting of token amounts
+//!
+//! Amounts are kept as decimal strings so lamport and wei values never pass
+//! through a float. Parsing yields a [`Decimal`], which holds up to 28
+//! significant digits exactly.
+
+use crate::error::{Error, Result};
+use rust_decimal::Decimal;
+
+/// Lamports in one SOL, as a decimal scale
+const SOL_DECIMALS: u32 = 9;
+
+/// Parse a user-supplied amount: plain digits with an optional fractional
+/// part, no sign, exponent or separators. Zero is accepted; callers decide
+/// whether it's meaningful.
+pub fn parse_amount(amount: &str) -> Result<Decimal> {
+    let amount = amount.trim();
+    if amount.starts_with('-') {
+        return Err(Error::Validation("Amount cannot be negative".to_string()));
+    }
+
+    let (whole, fraction) = match amount.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (amount, None),
+    };
+    let is_digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    if !is_digits(whole) || !fraction.map_or(true, is_digits) {
+        return Err(Error::Validation("Invalid amount format".to_string()));
+    }
+
+    // Exact parsing refuses to round away digits that don't fit
+    Decimal::from_str_exact(amount)
+        .map_err(|_| Error::Validation("Amount has too many digits".to_string()))
+}
+
+/// Parse an amount that has to be more than zero
+pub fn parse_positive_amount(amount: &str) -> Result<Decimal> {
+    let amount = parse_amount(amount)?;
+    if amount.is_zero() {
+        return Err(Error::Validation("Amount must be greater than zero".to_string()));
+    }
+    Ok(amount)
+}
+
+/// Canonical string form of an amount: no trailing fractional zeros and no
+/// exponent, so equal amounts always format the same
+pub fn format_amount(amount: Decimal) -> String {
+    amount.normalize().to_string()
+}
+
+/// `lamports` in SOL, exactly
+pub fn lamports_to_sol(lamports: u64) -> Decimal {
+    Decimal::from_i128_with_scale(lamports as i128, SOL_DECIMALS)
+}
\ No newline at end of file
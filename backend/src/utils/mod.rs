@@ -1,7 +1,11 @@
 //! Utility functions and helpers
 
+pub mod amount;
+
 use crate::error::{Error, Result};
 use chrono::{DateTime, Utc};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
 use uuid::Uuid;
 
 /// Validate email format
@@ -59,10 +63,9 @@ pub fn generate_uuid() -> Uuid {
 
 /// Validate Solana address format
 pub fn validate_solana_address(address: &str) -> bool {
-    // Solana addresses are base58 encoded and typically 44 characters
-    address.len() == 44 && address.chars().all(|c| {
-        "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz".contains(c)
-    })
+    // Addresses are base58-encoded 32-byte keys, which can be anywhere from
+    // 32 to 44 characters long, so only a full decode tells them apart
+    Pubkey::from_str(address).is_ok()
 }
 
 /// Validate Ethereum address format
@@ -121,6 +124,13 @@ mod tests {
         assert!(validate_solana_address("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"));
         assert!(!validate_solana_address("invalid_address"));
         assert!(!validate_solana_address("too_short"));
+
+        // 43 characters, still a 32-byte key
+        assert!(validate_solana_address("So11111111111111111111111111111111111111112"));
+        // System program id
+        assert!(validate_solana_address("11111111111111111111111111111111"));
+        // 44 base58 characters that decode to more than 32 bytes
+        assert!(!validate_solana_address("zzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz"));
     }
 
     #[test]
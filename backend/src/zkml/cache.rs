@@ -0,0 +1,69 @@
+//! Redis cache of generated proofs
+//!
+//! Proving the same input with the same circuit and keys always proves the
+//! same statement, so a proof made earlier can be handed out again instead
+//! of recomputed. Entries are keyed by the circuit, the verifying key and
+//! the SHA256 of the input; the raw input is never stored.
+
+use crate::zkml::ZkProof;
+use redis::AsyncCommands;
+use sha2::{Digest, Sha256};
+
+/// Proofs cached in Redis for a fixed time
+#[derive(Clone)]
+pub struct ProofCache {
+    redis: redis::Client,
+    ttl_secs: u64,
+}
+
+impl ProofCache {
+    pub fn new(redis: redis::Client, ttl_secs: u64) -> Self {
+        Self { redis, ttl_secs }
+    }
+
+    /// Key of the proof of `data` with `circuit` under the verifying key
+    /// `vk_hash`, so proofs made under rotated keys are never served
+    pub fn key(circuit: guardian_zkml::CircuitType, vk_hash: &str, data: &[u8]) -> String {
+        format!("zk_proof:{}:{}:{:x}", circuit.name(), vk_hash, Sha256::digest(data))
+    }
+
+    /// The proof cached under `key`. Redis being unreachable, or an entry
+    /// that no longer decodes, counts as a miss.
+    pub async fn get(&self, key: &str) -> Option<ZkProof> {
+        let mut conn = match self.redis.get_multiplexed_async_connection().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                tracing::debug!(key, error = %e, "Proof cache unavailable");
+                return None;
+            }
+        };
+
+        let bytes: Option<Vec<u8>> = conn.get(key).await
+            .map_err(|e| tracing::debug!(key, error = %e, "Failed to read proof cache"))
+            .ok()?;
+        bincode::deserialize(&bytes?)
+            .map_err(|e| tracing::warn!(key, error = %e, "Discarding undecodable cached proof"))
+            .ok()
+    }
+
+    /// Cache `proof` under `key`. Failures are logged and otherwise ignored;
+    /// the proof is still good without the cache.
+    pub async fn put(&self, key: &str, proof: &ZkProof) {
+        let bytes = match bincode::serialize(proof) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                tracing::warn!(key, error = %e, "Failed to encode proof for caching");
+                return;
+            }
+        };
+
+        match self.redis.get_multiplexed_async_connection().await {
+            Ok(mut conn) => {
+                if let Err(e) = conn.set_ex::<_, _, ()>(key, bytes, self.ttl_secs).await {
+                    tracing::debug!(key, error = %e, "Failed to cache proof");
+                }
+            }
+            Err(e) => tracing::debug!(key, error = %e, "Proof cache unavailable"),
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,62 @@
+//! Estimates of what verifying a proof on chain would cost
+//!
+//! Verifier cost is modelled as a fixed overhead plus a charge per byte of
+//! proof and of public input, which tracks how transaction data and the
+//! verifier's multi-scalar multiplications grow. The built-in tables are
+//! rough; deployments with measured numbers should configure their own.
+
+use crate::config::VerificationGasConfig;
+use serde::{Deserialize, Serialize};
+
+/// Chain a proof would be verified on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetChain {
+    /// Costs are in compute units
+    #[default]
+    Solana,
+    /// Costs are in gas
+    Ethereum,
+}
+
+/// Linear verification cost model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GasCostTable {
+    /// Fixed verifier cost, independent of the proof
+    pub base: u64,
+    pub per_proof_byte: u64,
+    pub per_public_input_byte: u64,
+}
+
+impl GasCostTable {
+    /// Built-in estimates for `chain`
+    pub fn for_chain(chain: TargetChain) -> Self {
+        match chain {
+            TargetChain::Solana => Self {
+                base: 100_000,
+                per_proof_byte: 150,
+                per_public_input_byte: 1_200,
+            },
+            TargetChain::Ethereum => Self {
+                base: 210_000,
+                // 16 gas of calldata plus verifier work per byte
+                per_proof_byte: 64,
+                per_public_input_byte: 6_000,
+            },
+        }
+    }
+
+    /// Estimated cost of verifying a proof of `proof_len` bytes with
+    /// `public_input_len` bytes of public input
+    pub fn estimate(&self, proof_len: usize, public_input_len: usize) -> u64 {
+        self.base
+            .saturating_add(self.per_proof_byte.saturating_mul(proof_len as u64))
+            .saturating_add(self.per_public_input_byte.saturating_mul(public_input_len as u64))
+    }
+}
+
+impl From<&VerificationGasConfig> for GasCostTable {
+    fn from(config: &VerificationGasConfig) -> Self {
+        config.cost_table.unwrap_or_else(|| Self::for_chain(config.target_chain))
+    }
+}
@@ -0,0 +1,111 @@
+//! Checks on what may be proved, run before any proving work starts
+//!
+//! Deployments that only prove known kinds of documents can turn everything
+//! else away without spending prover time on it.
+
+use crate::{
+    config::ProofInputPolicy,
+    error::{Error, Result},
+};
+
+/// Data submitted for proving, with the MIME type the client declared
+#[derive(Debug, Clone, Copy)]
+pub struct ProofInput<'a> {
+    pub data: &'a [u8],
+    pub content_type: Option<&'a str>,
+}
+
+impl ProofInput<'_> {
+    /// The declared MIME type without parameters, or the one the data looks
+    /// like when none was declared
+    pub fn effective_content_type(&self) -> String {
+        match self.content_type {
+            Some(declared) => declared.split(';').next().unwrap_or_default().trim().to_ascii_lowercase(),
+            None => sniff_content_type(self.data).to_string(),
+        }
+    }
+}
+
+/// Best guess at what `data` is: JSON, other UTF-8 text, or opaque bytes
+pub fn sniff_content_type(data: &[u8]) -> &'static str {
+    if serde_json::from_slice::<serde::de::IgnoredAny>(data).is_ok() {
+        "application/json"
+    } else if std::str::from_utf8(data).is_ok() {
+        "text/plain"
+    } else {
+        "application/octet-stream"
+    }
+}
+
+fn is_json(content_type: &str) -> bool {
+    content_type == "application/json" || content_type.ends_with("+json")
+}
+
+/// Decides whether an input may be proved
+pub trait ProofInputValidator: Send + Sync {
+    /// Reject `input` with a validation error if it may not be proved
+    fn validate(&self, input: &ProofInput<'_>) -> Result<()>;
+}
+
+/// Lets every input through
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AcceptAll;
+
+impl ProofInputValidator for AcceptAll {
+    fn validate(&self, _input: &ProofInput<'_>) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Enforces a configured [`ProofInputPolicy`]
+#[derive(Debug, Clone)]
+pub struct PolicyValidator {
+    policy: ProofInputPolicy,
+}
+
+impl PolicyValidator {
+    pub fn new(mut policy: ProofInputPolicy) -> Self {
+        for content_type in &mut policy.allowed_content_types {
+            *content_type = content_type.trim().to_ascii_lowercase();
+        }
+        Self { policy }
+    }
+}
+
+impl ProofInputValidator for PolicyValidator {
+    fn validate(&self, input: &ProofInput<'_>) -> Result<()> {
+        if let Some(max_bytes) = self.policy.max_bytes {
+            if input.data.len() > max_bytes {
+                return Err(Error::Validation(format!(
+                    "Input of {} bytes exceeds the maximum of {} bytes",
+                    input.data.len(),
+                    max_bytes
+                )));
+            }
+        }
+
+        let content_type = input.effective_content_type();
+        if !self.policy.allowed_content_types.is_empty() && !self.policy.allowed_content_types.contains(&content_type) {
+            return Err(Error::Validation(format!("Inputs of type {} may not be proved", content_type)));
+        }
+
+        // Under an allow-list, data let in as JSON has to really be JSON
+        let needs_object = !self.policy.required_json_fields.is_empty();
+        let restricted_json = is_json(&content_type) && !self.policy.allowed_content_types.is_empty();
+        if !needs_object && !restricted_json {
+            return Ok(());
+        }
+
+        let document: serde_json::Value = serde_json::from_slice(input.data)
+            .map_err(|_| Error::Validation("Input is not a valid JSON document".to_string()))?;
+        if needs_object {
+            let object = document.as_object()
+                .ok_or_else(|| Error::Validation("Input must be a JSON object".to_string()))?;
+            if let Some(missing) = self.policy.required_json_fields.iter().find(|field| !object.contains_key(field.as_str())) {
+                return Err(Error::Validation(format!("Input is missing the {} field", missing)));
+            }
+        }
+
+        Ok(())
+    }
+}
\ No newline at end of file
@@ -3,10 +3,62 @@
 //! This module integrates with the existing guardian_zkml prover
 //! located in the prover/ directory to provide ZK proof capabilities.
 
-use crate::error::{Error, Result};
+pub mod cache;
+pub mod gas;
+pub mod input;
+
+use crate::{
+    config::ZkmlConfig,
+    error::{Error, Result},
+    telemetry,
+};
+use cache::ProofCache;
+use input::{AcceptAll, PolicyValidator, ProofInput, ProofInputValidator};
 use serde::{Deserialize, Serialize};
 use std::process::Command;
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// Proving time the prover is expected to stay under
+const PROVING_TIME_TARGET_MS: u64 = 500;
+
+/// Circuit type of proofs made over a commitment instead of raw data
+pub const COMMITMENT_CIRCUIT: &str = "sha256_commitment";
+
+/// Expected shape of a circuit's public inputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicInputSchema {
+    /// Number of public input bytes the circuit exposes
+    pub len: usize,
+}
+
+impl PublicInputSchema {
+    /// Schema for a circuit type, or `None` if the circuit is unknown
+    pub fn for_circuit(circuit_type: &str) -> Option<Self> {
+        match circuit_type {
+            // One public input per byte of the digest
+            "sha256" | "keccak256" => Some(Self { len: 32 }),
+            // The digest bytes followed by the commitment bytes
+            COMMITMENT_CIRCUIT => Some(Self { len: 64 }),
+            _ => None,
+        }
+    }
+
+    /// Check that `public_inputs` has the shape this schema expects
+    pub fn validate(&self, public_inputs: &[u8]) -> Result<()> {
+        if public_inputs.len() != self.len {
+            return Err(Error::Validation(format!(
+                "expected {} public input bytes, got {}",
+                self.len,
+                public_inputs.len()
+            )));
+        }
+        Ok(())
+    }
+}
 
 /// ZK proof data structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,6 +75,9 @@ pub struct ZkProof {
 pub struct ProofRequest {
     pub input_data: Vec<u8>,
     pub circuit_type: String,
+    /// Prove from scratch even if a proof of this input is cached
+    #[serde(default)]
+    pub bypass_cache: bool,
 }
 
 /// ZK proof verification request
@@ -36,6 +91,22 @@ pub struct VerificationRequest {
 #[derive(Clone)]
 pub struct ZkmlService {
     prover_path: String,
+    prover_config: guardian_zkml::ProverConfig,
+    last_proof_metrics: Arc<Mutex<Option<guardian_zkml::ProofMetrics>>>,
+    proof_permits: Arc<Semaphore>,
+    max_batch_size: usize,
+    max_batch_bytes: usize,
+    /// Lowercase hex fingerprints of the verifying keys external verify
+    /// requests may name; empty pins the prover's current key
+    pinned_vk_hashes: Vec<String>,
+    /// Decides which inputs may be proved at all
+    input_validator: Arc<dyn ProofInputValidator>,
+    /// Earlier proofs of the same input, reused by [`ZkmlService::generate_proof`]
+    proof_cache: Option<ProofCache>,
+    /// Proofs this service has run the prover for
+    proofs_generated: Arc<AtomicU64>,
+    /// Whether the proving system came up, settled by the first health check
+    healthy: Arc<OnceLock<bool>>,
 }
 
 impl ZkmlService {
@@ -44,86 +115,500 @@ impl ZkmlService {
         // Check if the prover binary exists
         let prover_path = "../prover/target/release/guardian_zkml".to_string();
         
-        Ok(Self { prover_path })
+        Ok(Self {
+            prover_path,
+            prover_config: guardian_zkml::ProverConfig::default(),
+            last_proof_metrics: Arc::new(Mutex::new(None)),
+            proof_permits: Arc::new(Semaphore::new(crate::config::default_max_concurrent_proofs())),
+            max_batch_size: crate::config::default_max_batch_size(),
+            max_batch_bytes: crate::config::default_max_batch_bytes(),
+            pinned_vk_hashes: Vec::new(),
+            input_validator: Arc::new(AcceptAll),
+            proof_cache: None,
+            proofs_generated: Arc::new(AtomicU64::new(0)),
+            healthy: Arc::new(OnceLock::new()),
+        })
+    }
+
+    /// Create a new ZKML service, loading the proving system from the
+    /// configured SRS directory (or generating and caching it there)
+    pub fn with_config(config: &ZkmlConfig) -> Result<Self> {
+        let prover_config = guardian_zkml::ProverConfig {
+            k: config.circuit_k,
+            max_circuit_size: config.max_circuit_size,
+            // Only proofs of inputs this service would prove are verified
+            max_verify_input_len: config.input_policy.max_bytes,
+        };
+
+        guardian_zkml::init_proving_system(prover_config, Some(Path::new(&config.srs_path)))
+            .map_err(|e| Error::Config(format!("Failed to initialize proving system: {}", e)))?;
+
+        // The proving system is shared, so report whatever it was actually initialized with
+        let prover_config = guardian_zkml::prover_config()
+            .map_err(|e| Error::Config(format!("Failed to initialize proving system: {}", e)))?;
+
+        let service = Self {
+            prover_config,
+            proof_permits: Arc::new(Semaphore::new(config.max_concurrent_proofs.max(1))),
+            max_batch_size: config.max_batch_size,
+            max_batch_bytes: config.max_batch_bytes,
+            ..Self::new()?
+        }
+        .with_pinned_vk_hashes(config.pinned_vk_hashes.clone())
+        .with_input_validator(Arc::new(PolicyValidator::new(config.input_policy.clone())));
+
+        // Regenerated keys (e.g. after losing the SRS cache) no longer match the pins
+        if service.pinned_vk_hashes()?.is_empty() {
+            tracing::warn!(
+                "Prover verifying key {} is not pinned; external proof verification will be rejected",
+                service.verification_key_hash()?
+            );
+        }
+
+        Ok(service)
+    }
+
+    /// Only accept external verify requests naming one of `vk_hashes`
+    pub fn with_pinned_vk_hashes(mut self, vk_hashes: Vec<String>) -> Self {
+        self.pinned_vk_hashes = vk_hashes.iter().map(|hash| hash.trim().to_ascii_lowercase()).collect();
+        self
+    }
+
+    /// Reuse proofs of inputs proved before, keeping them in Redis for
+    /// `ttl_secs`
+    pub fn with_proof_cache(mut self, redis: redis::Client, ttl_secs: u64) -> Self {
+        self.proof_cache = Some(ProofCache::new(redis, ttl_secs));
+        self
+    }
+
+    /// Number of proofs this service has run the prover for; proofs served
+    /// from the cache don't count
+    pub fn proofs_generated(&self) -> u64 {
+        self.proofs_generated.load(Ordering::Relaxed)
+    }
+
+    /// Check inputs with `validator` before proving them
+    pub fn with_input_validator(mut self, validator: Arc<dyn ProofInputValidator>) -> Self {
+        self.input_validator = validator;
+        self
+    }
+
+    /// Check that `data`, declared as `content_type` if the client said,
+    /// may be proved. Callers run this before queueing or proving anything.
+    pub fn validate_input(&self, data: &[u8], content_type: Option<&str>) -> Result<()> {
+        self.input_validator.validate(&ProofInput { data, content_type })
+    }
+
+    /// Wait for a free proving slot; proofs are CPU bound, so only a few run at once
+    async fn acquire_proof_permit(&self) -> Result<SemaphorePermit<'_>> {
+        self.proof_permits.acquire().await.map_err(|_| Error::ServiceUnavailable)
+    }
+
+    /// Largest input any circuit takes at the configured size
+    pub fn max_input_len(&self) -> usize {
+        [guardian_zkml::CircuitType::Sha256, guardian_zkml::CircuitType::Keccak256]
+            .into_iter()
+            .map(|circuit| self.prover_config.max_input_len_for(circuit))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Most inputs one batch proof request may hold
+    pub fn max_batch_size(&self) -> usize {
+        self.max_batch_size
+    }
+
+    /// Check that `data` fits in `circuit` at the configured size, before
+    /// anything is queued or proved. The limit is the one
+    /// [`ZkmlService::get_circuit_info`] advertises.
+    pub fn check_input_size(&self, circuit: guardian_zkml::CircuitType, data: &[u8]) -> Result<()> {
+        let max_input_len = self.prover_config.max_input_len_for(circuit);
+        if data.len() > max_input_len {
+            return Err(Error::Validation(format!(
+                "Input of {} bytes exceeds the maximum of {} bytes for a k={} {} circuit",
+                data.len(),
+                max_input_len,
+                self.prover_config.k,
+                circuit.name()
+            )));
+        }
+        Ok(())
     }
 
     /// Generate a SHA256 zero-knowledge proof using the existing guardian_zkml prover
     pub async fn generate_sha256_proof(&self, data: &[u8]) -> Result<ZkProof> {
-        // Use the existing prover library
-        let output = guardian_zkml::generate_proof_slice(data);
-        
-        // Check if proof generation succeeded
-        // The guardian_zkml library returns Output with len=0 on failure
-        if output.len == 0 && !data.is_empty() {
-            return Err(Error::ProofGenerationFailed("Proof generation failed - prover returned empty result".to_string()));
+        self.check_input_size(guardian_zkml::CircuitType::Sha256, data)?;
+        let _permit = self.acquire_proof_permit().await?;
+
+        let started = Instant::now();
+        let result = guardian_zkml::generate_proof_with_metrics(data)
+            .map_err(Error::ProofGenerationFailed)?;
+        telemetry::record_proof_generation("sha256", started);
+        self.proofs_generated.fetch_add(1, Ordering::Relaxed);
+        self.record_metrics(result.metrics);
+
+        Ok(ZkProof {
+            proof_data: result.proof,
+            public_inputs: result.hash.to_vec(),
+            circuit_type: "sha256".to_string(),
+            hash: result.hash,
+            created_at: chrono::Utc::now(),
+        })
+    }
+
+    /// Generate SHA256 proofs for several inputs with one call into the prover.
+    ///
+    /// The batch as a whole must stay within the configured count and
+    /// combined size limits. Results are returned in input order, and an
+    /// input that doesn't fit the circuit fails on its own without failing
+    /// the rest of the batch.
+    pub async fn generate_sha256_proofs_batch(&self, inputs: &[Vec<u8>]) -> Result<Vec<Result<ZkProof>>> {
+        if inputs.is_empty() {
+            return Err(Error::Validation("Batch must contain at least one input".to_string()));
         }
-        
-        // For empty data, len=0 is expected, so we need to check the hash
-        if data.is_empty() {
-            // Verify the hash is correct for empty data
-            use sha2::{Digest, Sha256};
-            let mut hasher = Sha256::new();
-            hasher.update(data);
-            let expected_hash: [u8; 32] = hasher.finalize().into();
-            
-            if output.hash == [0u8; 32] {
-                return Err(Error::ProofGenerationFailed("Proof generation failed for empty data".to_string()));
-            }
-            
-            if output.hash != expected_hash {
-                return Err(Error::ProofGenerationFailed("Hash mismatch for empty data proof".to_string()));
+        if inputs.len() > self.max_batch_size {
+            return Err(Error::Validation(format!(
+                "Batch of {} inputs exceeds the maximum of {}",
+                inputs.len(),
+                self.max_batch_size
+            )));
+        }
+        let total_len: usize = inputs.iter().map(Vec::len).sum();
+        if total_len > self.max_batch_bytes {
+            return Err(Error::Validation(format!(
+                "Batch of {} bytes exceeds the maximum of {} bytes",
+                total_len,
+                self.max_batch_bytes
+            )));
+        }
+
+        let checks: Vec<Result<()>> = inputs.iter()
+            .map(|data| self.check_input_size(guardian_zkml::CircuitType::Sha256, data))
+            .collect();
+        let accepted: Vec<Vec<u8>> = inputs.iter()
+            .zip(&checks)
+            .filter(|(_, check)| check.is_ok())
+            .map(|(data, _)| data.clone())
+            .collect();
+
+        let _permit = self.acquire_proof_permit().await?;
+        let started = Instant::now();
+        // Proving blocks for a while, so keep it off the async workers
+        let mut proofs = tokio::task::spawn_blocking(move || {
+            let inputs: Vec<&[u8]> = accepted.iter().map(Vec::as_slice).collect();
+            guardian_zkml::generate_proofs_batch(&inputs)
+        })
+        .await
+        .map_err(|_| Error::Internal)?
+        .into_iter();
+        telemetry::record_proof_generation("sha256_batch", started);
+        self.proofs_generated.fetch_add(proofs.len() as u64, Ordering::Relaxed);
+
+        let created_at = chrono::Utc::now();
+        Ok(checks.into_iter().map(|check| {
+            check?;
+            let (hash, proof_bytes) = proofs.next()
+                .ok_or(Error::Internal)?
+                .map_err(Error::ProofGenerationFailed)?;
+            Ok(ZkProof {
+                proof_data: proof_bytes,
+                public_inputs: hash.to_vec(),
+                circuit_type: "sha256".to_string(),
+                hash,
+                created_at,
+            })
+        }).collect())
+    }
+
+    /// Log the timings of a finished proof and keep them for status reporting
+    fn record_metrics(&self, metrics: guardian_zkml::ProofMetrics) {
+        if metrics.proving_ms > PROVING_TIME_TARGET_MS {
+            tracing::warn!(
+                proving_ms = metrics.proving_ms,
+                target_ms = PROVING_TIME_TARGET_MS,
+                "Proof generation exceeded target time"
+            );
+        }
+        tracing::info!(
+            setup_ms = metrics.setup_ms,
+            witness_ms = metrics.witness_ms,
+            proving_ms = metrics.proving_ms,
+            total_ms = metrics.total_ms,
+            input_len = metrics.input_len,
+            "Generated SHA256 proof"
+        );
+
+        if let Ok(mut last) = self.last_proof_metrics.lock() {
+            *last = Some(metrics);
+        }
+    }
+
+    /// Generate a Keccak256 zero-knowledge proof
+    pub async fn generate_keccak256_proof(&self, data: &[u8]) -> Result<ZkProof> {
+        let circuit = guardian_zkml::CircuitType::Keccak256;
+        self.check_input_size(circuit, data)?;
+
+        let _permit = self.acquire_proof_permit().await?;
+        let started = Instant::now();
+        let (hash, proof_bytes) = guardian_zkml::generate_proof_for(circuit, data)
+            .map_err(Error::ProofGenerationFailed)?;
+        telemetry::record_proof_generation(circuit.name(), started);
+        self.proofs_generated.fetch_add(1, Ordering::Relaxed);
+
+        Ok(ZkProof {
+            proof_data: proof_bytes,
+            public_inputs: hash.to_vec(),
+            circuit_type: circuit.name().to_string(),
+            hash,
+            created_at: chrono::Utc::now(),
+        })
+    }
+
+    /// Generate a proof for `request`, routed by its circuit type. With a
+    /// proof cache, an input proved before gets the same proof back unless
+    /// the request bypasses the cache; a bypassing request's fresh proof
+    /// still replaces the cached one.
+    pub async fn generate_proof(&self, request: &ProofRequest) -> Result<ZkProof> {
+        let circuit = parse_circuit_type(&request.circuit_type)?;
+
+        let cached = match &self.proof_cache {
+            Some(cache) => {
+                let key = ProofCache::key(circuit, &self.verification_key_hash()?, &request.input_data);
+                if !request.bypass_cache {
+                    if let Some(proof) = cache.get(&key).await {
+                        return Ok(proof);
+                    }
+                }
+                Some((cache, key))
             }
+            None => None,
+        };
+
+        let proof = match circuit {
+            guardian_zkml::CircuitType::Sha256 => self.generate_sha256_proof(&request.input_data).await?,
+            guardian_zkml::CircuitType::Keccak256 => self.generate_keccak256_proof(&request.input_data).await?,
+        };
+
+        if let Some((cache, key)) = cached {
+            cache.put(&key, &proof).await;
+        }
+        Ok(proof)
+    }
+
+    /// Generate a proof that `commitment` = SHA256(blinding || data) commits
+    /// to data with the proven hash.
+    ///
+    /// The data and blinding are only used while proving; the returned proof
+    /// carries just the hash and commitment, so it can be stored safely.
+    pub async fn generate_committed_proof(
+        &self,
+        data: &[u8],
+        blinding: &[u8; 32],
+        commitment: &[u8; 32],
+    ) -> Result<ZkProof> {
+        if guardian_zkml::hash_commitment(blinding, data) != *commitment {
+            return Err(Error::Validation("commitment does not match the supplied data".to_string()));
         }
 
+        let _permit = self.acquire_proof_permit().await?;
+        let started = Instant::now();
+        let (hash, commitment, proof_bytes) = guardian_zkml::generate_committed_proof(data, blinding)
+            .map_err(Error::ProofGenerationFailed)?;
+        telemetry::record_proof_generation(COMMITMENT_CIRCUIT, started);
+        self.proofs_generated.fetch_add(1, Ordering::Relaxed);
+
         Ok(ZkProof {
-            proof_data: vec![], // The current prover doesn't return proof bytes in this interface
-            public_inputs: output.hash.to_vec(),
-            circuit_type: "sha256".to_string(),
-            hash: output.hash,
+            proof_data: proof_bytes,
+            public_inputs: [hash, commitment].concat(),
+            circuit_type: COMMITMENT_CIRCUIT.to_string(),
+            hash,
             created_at: chrono::Utc::now(),
         })
     }
 
+    /// Verify a proof from [`ZkmlService::generate_committed_proof`] without the original data
+    pub async fn verify_committed_proof(&self, proof: &ZkProof) -> Result<bool> {
+        if proof.circuit_type != COMMITMENT_CIRCUIT {
+            return Err(Error::Validation(format!("expected a {} proof, got {}", COMMITMENT_CIRCUIT, proof.circuit_type)));
+        }
+        PublicInputSchema { len: 64 }.validate(&proof.public_inputs)?;
+
+        let (hash, commitment) = proof.public_inputs.split_at(32);
+        if hash != proof.hash {
+            return Err(Error::Validation("public inputs do not match the proof hash".to_string()));
+        }
+        let commitment: [u8; 32] = commitment.try_into().map_err(|_| Error::Internal)?;
+
+        guardian_zkml::verify_committed_proof(&proof.hash, &commitment, &proof.proof_data)
+            .map_err(Error::ProofGenerationFailed)
+    }
+
     /// Verify a SHA256 zero-knowledge proof
     pub async fn verify_sha256_proof(&self, proof: &ZkProof, original_data: &[u8]) -> Result<bool> {
-        // Use the existing prover library for verification
+        let circuit = guardian_zkml::CircuitType::Sha256;
+        if proof.circuit_type != circuit.name() {
+            return Err(Error::Validation(format!("expected a {} proof, got {}", circuit.name(), proof.circuit_type)));
+        }
+        PublicInputSchema { len: 32 }.validate(&proof.public_inputs)?;
+
+        // The public inputs are the digest, so they must agree with the claimed hash
+        if proof.public_inputs != proof.hash {
+            return Err(Error::Validation("public inputs do not match the proof hash".to_string()));
+        }
+
         let output = guardian_zkml::Output {
             len: original_data.len(),
             hash: proof.hash,
         };
-        
-        let is_valid = guardian_zkml::verify_proof_slice(original_data, &output);
+
+        // The proof only attests to the hash, so bind it to the supplied data first
+        if !guardian_zkml::hash_matches(original_data, &output) {
+            return Ok(false);
+        }
+
+        // Run the Halo2 verifier over the proof transcript
+        let is_valid = guardian_zkml::verify_proof_slice(&output, &proof.proof_data);
         Ok(is_valid)
     }
 
+    /// Verify a Keccak256 zero-knowledge proof
+    pub async fn verify_keccak256_proof(&self, proof: &ZkProof, original_data: &[u8]) -> Result<bool> {
+        let circuit = guardian_zkml::CircuitType::Keccak256;
+        if proof.circuit_type != circuit.name() {
+            return Err(Error::Validation(format!("expected a {} proof, got {}", circuit.name(), proof.circuit_type)));
+        }
+        PublicInputSchema { len: 32 }.validate(&proof.public_inputs)?;
+
+        if proof.public_inputs != proof.hash {
+            return Err(Error::Validation("public inputs do not match the proof hash".to_string()));
+        }
+
+        // Bind the proof to the supplied data before running the verifier
+        if guardian_zkml::keccak256(original_data) != proof.hash {
+            return Ok(false);
+        }
+
+        guardian_zkml::verify_proof_for(circuit, &proof.hash, &proof.proof_data)
+            .map_err(Error::ProofGenerationFailed)
+    }
+
+    /// Verify a proof against its original data, routed by the proof's circuit type
+    pub async fn verify_proof(&self, proof: &ZkProof, original_data: &[u8]) -> Result<bool> {
+        match parse_circuit_type(&proof.circuit_type)? {
+            guardian_zkml::CircuitType::Sha256 => self.verify_sha256_proof(proof, original_data).await,
+            guardian_zkml::CircuitType::Keccak256 => self.verify_keccak256_proof(proof, original_data).await,
+        }
+    }
+
+    /// Verify raw proof transcript bytes against a public SHA256 hash
+    pub async fn verify_proof_bytes(&self, hash: &[u8; 32], proof_bytes: &[u8]) -> Result<bool> {
+        let output = guardian_zkml::Output { len: 0, hash: *hash };
+        Ok(guardian_zkml::verify_proof_slice(&output, proof_bytes))
+    }
+
+    /// Hex-encoded fingerprint of the verifying key used by the prover
+    pub fn verification_key_hash(&self) -> Result<String> {
+        guardian_zkml::verifying_key_fingerprint()
+            .map(hex::encode)
+            .map_err(Error::ProofGenerationFailed)
+    }
+
+    /// Fingerprints of the verifying keys external verify requests may name.
+    /// Only keys the prover holds can verify anything, so this is the
+    /// current key when it is pinned (or nothing is) and empty otherwise.
+    pub fn pinned_vk_hashes(&self) -> Result<Vec<String>> {
+        let current = self.verification_key_hash()?;
+        if self.pinned_vk_hashes.is_empty() || self.pinned_vk_hashes.contains(&current) {
+            Ok(vec![current])
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    /// Verify a proof from an external party. The proof must name a pinned
+    /// verifying key by `vk_hash`; keys are never taken from the caller.
+    pub async fn verify_external_proof(
+        &self,
+        vk_hash: &str,
+        proof: &ZkProof,
+        original_data: Option<&[u8]>,
+    ) -> Result<bool> {
+        let vk_hash = vk_hash.trim().to_ascii_lowercase();
+        if !self.pinned_vk_hashes()?.contains(&vk_hash) {
+            return Err(Error::BadRequest(format!("Unknown verifying key {}", vk_hash)));
+        }
+
+        if proof.circuit_type == COMMITMENT_CIRCUIT {
+            return self.verify_committed_proof(proof).await;
+        }
+        let original_data = original_data
+            .ok_or_else(|| Error::BadRequest("original_data is required".to_string()))?;
+        self.verify_proof(proof, original_data).await
+    }
+
     /// Get circuit information for SHA256
     pub fn get_sha256_circuit_info(&self) -> CircuitInfo {
-        CircuitInfo {
-            name: "SHA256".to_string(),
-            description: "Halo2 SHA256 hash function circuit with zero-knowledge proofs".to_string(),
-            max_input_size: 8192, // Based on k=14 circuit size
-            estimated_proof_time_ms: 718, // Based on benchmarks
-            proof_size_bytes: 1024,
-            security_level: 128,
+        self.get_circuit_info(guardian_zkml::CircuitType::Sha256)
+    }
+
+    /// Get circuit information for `circuit`
+    pub fn get_circuit_info(&self, circuit: guardian_zkml::CircuitType) -> CircuitInfo {
+        let public_input_size = PublicInputSchema::for_circuit(circuit.name()).map_or(0, |schema| schema.len);
+        let max_input_size = self.prover_config.max_input_len_for(circuit);
+
+        match circuit {
+            guardian_zkml::CircuitType::Sha256 => CircuitInfo {
+                name: "SHA256".to_string(),
+                description: "Halo2 SHA256 hash function circuit with zero-knowledge proofs".to_string(),
+                max_input_size,
+                public_input_size,
+                estimated_proof_time_ms: 718, // Based on benchmarks
+                proof_size_bytes: 1024,
+                security_level: 128,
+            },
+            guardian_zkml::CircuitType::Keccak256 => CircuitInfo {
+                name: "Keccak256".to_string(),
+                description: "Halo2 Keccak256 hash function circuit for Ethereum-compatible digests".to_string(),
+                max_input_size,
+                public_input_size,
+                // Not benchmarked yet
+                estimated_proof_time_ms: 0,
+                proof_size_bytes: 0,
+                security_level: 128,
+            },
         }
     }
 
-    /// Check if the prover system is available
+    /// Check if the prover system is available. No proof is made: the
+    /// first call initializes the shared proving system if nothing has yet,
+    /// and the outcome is kept, since the keys don't change once generated.
     pub fn health_check(&self) -> Result<bool> {
-        // Try to generate a small proof to verify the system works
-        let test_data = b"health_check";
-        let output = guardian_zkml::generate_proof_slice(test_data);
-        Ok(output.len > 0)
+        Ok(*self.healthy.get_or_init(|| match guardian_zkml::prover_config() {
+            Ok(_) => true,
+            Err(e) => {
+                tracing::warn!("ZKML proving system unavailable: {}", e);
+                false
+            }
+        }))
     }
 
     /// Get prover system status
     pub fn get_status(&self) -> ProverStatus {
+        let last_proof_metrics = self.last_proof_metrics.lock().ok().and_then(|last| *last);
+        let key_persistence = guardian_zkml::key_persistence().ok();
+
         match self.health_check() {
             Ok(true) => ProverStatus {
                 available: true,
-                circuit_size: format!("2^{} = {} rows", 14, 1 << 14),
+                circuit_size: format!(
+                    "2^{} = {} rows",
+                    self.prover_config.k,
+                    self.prover_config.circuit_size()
+                ),
                 estimated_setup_time_ms: 3400, // Based on implementation
                 last_health_check: chrono::Utc::now(),
+                last_proof_metrics,
+                key_persistence,
                 error: None,
             },
             Ok(false) | Err(_) => ProverStatus {
@@ -131,18 +616,26 @@ impl ZkmlService {
                 circuit_size: "Unknown".to_string(),
                 estimated_setup_time_ms: 0,
                 last_health_check: chrono::Utc::now(),
+                last_proof_metrics,
+                key_persistence,
                 error: Some("Prover system not responding".to_string()),
             },
         }
     }
 }
 
+/// Parse a circuit type name from a request or stored proof
+fn parse_circuit_type(circuit_type: &str) -> Result<guardian_zkml::CircuitType> {
+    circuit_type.parse().map_err(Error::BadRequest)
+}
+
 /// Circuit information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CircuitInfo {
     pub name: String,
     pub description: String,
     pub max_input_size: usize,
+    pub public_input_size: usize,
     pub estimated_proof_time_ms: u64,
     pub proof_size_bytes: usize,
     pub security_level: u32,
@@ -155,5 +648,10 @@ pub struct ProverStatus {
     pub circuit_size: String,
     pub estimated_setup_time_ms: u64,
     pub last_health_check: chrono::DateTime<chrono::Utc>,
+    /// Timings of the most recent proof generated by this service
+    pub last_proof_metrics: Option<guardian_zkml::ProofMetrics>,
+    /// Whether the proving parameters survive a restart; keys are derived
+    /// again on every start either way
+    pub key_persistence: Option<guardian_zkml::KeyPersistence>,
     pub error: Option<String>,
 }
@@ -0,0 +1,162 @@
+//! Tests for deactivating an account
+//!
+//! These tests need running Postgres and Redis instances and are ignored
+//! unless run with `--ignored` and both `DATABASE_URL` and `REDIS_URL` set.
+
+use axum::{
+    body::Body,
+    http::{header::AUTHORIZATION, Method, Request, StatusCode},
+    Router,
+};
+use guardian_aa_backend::{
+    api::{
+        create_router,
+        handlers::auth::{LoginRequest, RefreshTokenRequest, RegisterRequest},
+        AppState,
+    },
+    error::Error,
+    services::AuthService,
+};
+use redis::AsyncCommands;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+mod common;
+
+const PASSWORD: &str = "correct horse battery";
+
+async fn test_state() -> Arc<AppState> {
+    let mut config = common::database_and_redis_config();
+    config.rate_limit.enabled = false;
+    common::connect(config).await
+}
+
+async fn send(app: &Router, method: Method, uri: &str, access_token: &str) -> StatusCode {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(AUTHORIZATION, format!("Bearer {}", access_token))
+        .body(Body::empty())
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap().status()
+}
+
+async fn count(state: &AppState, query: &str, user_id: Uuid) -> i64 {
+    sqlx::query_scalar(query).bind(user_id).fetch_one(state.db.pool()).await.unwrap()
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_deactivation_signs_out_everywhere() {
+    let state = test_state().await;
+    let service = AuthService::new(state.clone());
+    let email = format!("deactivate-{}@example.com", Uuid::new_v4());
+
+    let registered = service.register(RegisterRequest {
+        email: email.clone(),
+        password: PASSWORD.to_string(),
+        username: None,
+    }).await.unwrap();
+    // A second session, on another device
+    let other_device = service.login(LoginRequest { email: email.clone(), password: PASSWORD.to_string() }).await.unwrap();
+
+    let user_id: Uuid = sqlx::query_scalar("SELECT id FROM users WHERE email = $1")
+        .bind(&email)
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO wallets (user_id, name, wallet_type, public_key) VALUES ($1, 'Main', 'solana', '11111111111111111111111111111111')")
+        .bind(user_id)
+        .execute(state.db.pool())
+        .await
+        .unwrap();
+
+    let app = create_router(state.clone());
+    assert_eq!(send(&app, Method::GET, "/api/v1/auth/sessions", &registered.access_token).await, StatusCode::OK);
+
+    assert_eq!(send(&app, Method::DELETE, "/api/v1/auth/account", &registered.access_token).await, StatusCode::OK);
+
+    // Every access token the user held stops working at once
+    for access_token in [&registered.access_token, &other_device.access_token] {
+        assert_eq!(send(&app, Method::GET, "/api/v1/auth/sessions", access_token).await, StatusCode::UNAUTHORIZED);
+    }
+
+    assert_eq!(count(&state, "SELECT COUNT(*) FROM user_sessions WHERE user_id = $1", user_id).await, 0);
+    assert_eq!(count(&state, "SELECT COUNT(*) FROM wallets WHERE user_id = $1 AND is_active", user_id).await, 0);
+    // Soft-deleted: the rows are all still there
+    assert_eq!(count(&state, "SELECT COUNT(*) FROM users WHERE id = $1 AND NOT is_active", user_id).await, 1);
+    assert_eq!(count(&state, "SELECT COUNT(*) FROM wallets WHERE user_id = $1", user_id).await, 1);
+
+    let login = service.login(LoginRequest { email, password: PASSWORD.to_string() }).await;
+    assert!(matches!(login, Err(Error::AuthenticationFailed)));
+    let refresh = service.refresh_token(RefreshTokenRequest { refresh_token: other_device.refresh_token }).await;
+    assert!(matches!(refresh, Err(Error::AuthenticationFailed)));
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_deactivation_leaves_other_users_signed_in() {
+    let state = test_state().await;
+    let service = AuthService::new(state.clone());
+
+    let mut tokens = Vec::new();
+    for _ in 0..2 {
+        tokens.push(service.register(RegisterRequest {
+            email: format!("deactivate-{}@example.com", Uuid::new_v4()),
+            password: PASSWORD.to_string(),
+            username: None,
+        }).await.unwrap());
+    }
+
+    let app = create_router(state.clone());
+    assert_eq!(send(&app, Method::DELETE, "/api/v1/auth/account", &tokens[0].access_token).await, StatusCode::OK);
+
+    assert_eq!(send(&app, Method::GET, "/api/v1/auth/sessions", &tokens[1].access_token).await, StatusCode::OK);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_inactive_account_is_rejected_even_without_revocation() {
+    let state = test_state().await;
+    let email = format!("deactivate-{}@example.com", Uuid::new_v4());
+    let registered = AuthService::new(state.clone()).register(RegisterRequest {
+        email: email.clone(),
+        password: PASSWORD.to_string(),
+        username: None,
+    }).await.unwrap();
+
+    // Deactivated behind the service's back, so no token is revoked
+    sqlx::query("UPDATE users SET is_active = false WHERE email = $1")
+        .bind(&email)
+        .execute(state.db.pool())
+        .await
+        .unwrap();
+
+    let app = create_router(state);
+    assert_eq!(send(&app, Method::GET, "/api/v1/auth/sessions", &registered.access_token).await, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_revocation_outlasts_refresh_tokens() {
+    let state = test_state().await;
+    let registered = AuthService::new(state.clone()).register(RegisterRequest {
+        email: format!("deactivate-{}@example.com", Uuid::new_v4()),
+        password: PASSWORD.to_string(),
+        username: None,
+    }).await.unwrap();
+    let user_id: Uuid = sqlx::query_scalar("SELECT user_id FROM user_sessions WHERE refresh_token_hash = $1")
+        .bind(format!("{:x}", Sha256::digest(registered.refresh_token.as_bytes())))
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap();
+
+    AuthService::new(state.clone()).deactivate_account(user_id).await.unwrap();
+
+    let mut conn = state.redis.get_multiplexed_async_connection().await.unwrap();
+    let ttl: i64 = conn.ttl(format!("revoked_user:{}", user_id)).await.unwrap();
+    assert!(ttl > state.config.auth.jwt_expiration);
+    assert!(ttl >= state.config.auth.refresh_token_expiration - 5);
+}
\ No newline at end of file
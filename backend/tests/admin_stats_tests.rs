@@ -0,0 +1,167 @@
+//! Tests for the admin stats dashboard
+//!
+//! These tests need a running Postgres instance and are ignored unless run
+//! with `--ignored` and `DATABASE_URL` set.
+
+use guardian_aa_backend::{
+    api::AppState,
+    db::queries::UserQueries,
+    services::AdminService,
+};
+use chrono::Duration;
+use std::sync::Arc;
+use uuid::Uuid;
+
+mod common;
+
+async fn test_state() -> Arc<AppState> {
+    let mut config = common::database_config();
+    config.admin.stats_cache_ttl_secs = 0;
+    common::connect(config).await
+}
+
+async fn create_user(state: &AppState) -> Uuid {
+    sqlx::query_scalar("INSERT INTO users (email, password_hash) VALUES ($1, 'hash') RETURNING id")
+        .bind(format!("stats-{}@example.com", Uuid::new_v4()))
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap()
+}
+
+async fn create_wallet(state: &AppState, user_id: Uuid) -> Uuid {
+    sqlx::query_scalar(
+        "INSERT INTO wallets (user_id, name, wallet_type, public_key)
+         VALUES ($1, 'Stats', 'solana', $2) RETURNING id",
+    )
+        .bind(user_id)
+        .bind(Uuid::new_v4().to_string())
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap()
+}
+
+async fn create_transaction(state: &AppState, wallet_id: Uuid, status: &str) {
+    sqlx::query(
+        "INSERT INTO transactions (wallet_id, transaction_type, status, from_address, to_address, amount)
+         VALUES ($1, 'send', $2::transaction_status, 'from', 'to', '1')",
+    )
+        .bind(wallet_id)
+        .bind(status)
+        .execute(state.db.pool())
+        .await
+        .unwrap();
+}
+
+async fn create_agent(state: &AppState) -> Uuid {
+    sqlx::query_scalar(
+        "INSERT INTO agents (name, agent_type, description, model_version, is_active)
+         VALUES ($1, 'news_sentiment', 'Stats test agent', 'v1', false) RETURNING id",
+    )
+        .bind(format!("stats-agent-{}", Uuid::new_v4()))
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap()
+}
+
+async fn create_prediction(state: &AppState, agent_id: Uuid, user_id: Uuid, age: Duration) {
+    sqlx::query(
+        "INSERT INTO agent_predictions
+             (agent_id, user_id, asset_symbol, prediction, confidence, explanation_hash, explanation_text, created_at, expires_at)
+         VALUES ($1, $2, 'SOL', 'bullish', 0.7, 'hash', 'explanation', NOW() - $3::interval, NOW() + INTERVAL '1 day')",
+    )
+        .bind(agent_id)
+        .bind(user_id)
+        .bind(format!("{} seconds", age.num_seconds()))
+        .execute(state.db.pool())
+        .await
+        .unwrap();
+}
+
+async fn create_proof(state: &AppState, user_id: Uuid) {
+    sqlx::query(
+        "INSERT INTO zkml_proofs (user_id, proof_type, proof_data, public_inputs, verification_key_hash, circuit_hash)
+         VALUES ($1, 'hash_proof', 'AAAA', '[]', 'vk', 'circuit')",
+    )
+        .bind(user_id)
+        .execute(state.db.pool())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_stats_count_seeded_data() {
+    let state = test_state().await;
+    let admin_service = AdminService::new(state.clone());
+    let before = admin_service.compute_stats().await.unwrap();
+
+    let user_id = create_user(&state).await;
+    let wallet_id = create_wallet(&state, user_id).await;
+    create_wallet(&state, user_id).await;
+    create_transaction(&state, wallet_id, "pending").await;
+    create_transaction(&state, wallet_id, "pending").await;
+    create_transaction(&state, wallet_id, "confirmed").await;
+    create_proof(&state, user_id).await;
+
+    let agent_id = create_agent(&state).await;
+    for _ in 0..3 {
+        create_prediction(&state, agent_id, user_id, Duration::zero()).await;
+    }
+    // Made before today started, so not counted
+    let yesterday = (before.generated_at - before.since) + Duration::hours(1);
+    create_prediction(&state, agent_id, user_id, yesterday).await;
+
+    let after = admin_service.compute_stats().await.unwrap();
+
+    // Other tests may be adding rows at the same time, so only lower bounds
+    // hold for the system-wide counts
+    assert!(after.counts.active_users >= before.counts.active_users + 1);
+    assert!(after.counts.total_wallets >= before.counts.total_wallets + 2);
+    assert!(after.counts.pending_transactions >= before.counts.pending_transactions + 2);
+    assert!(after.counts.proofs_generated_today >= before.counts.proofs_generated_today + 1);
+    assert!(after.counts.predictions_today >= before.counts.predictions_today + 3);
+
+    let agent = after.predictions_by_agent.iter()
+        .find(|count| count.agent_id == agent_id)
+        .expect("seeded agent is missing from the stats");
+    assert_eq!(agent.predictions, 3);
+    assert!(after.dependencies.database);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_agents_without_predictions_are_listed() {
+    let state = test_state().await;
+    let agent_id = create_agent(&state).await;
+
+    let stats = AdminService::new(state).compute_stats().await.unwrap();
+
+    let agent = stats.predictions_by_agent.iter()
+        .find(|count| count.agent_id == agent_id)
+        .expect("seeded agent is missing from the stats");
+    assert_eq!(agent.predictions, 0);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_only_admins_are_admins() {
+    let state = test_state().await;
+    let user_id = create_user(&state).await;
+
+    assert!(!UserQueries::is_admin(state.db.pool(), user_id).await.unwrap());
+
+    sqlx::query("UPDATE users SET is_admin = true WHERE id = $1")
+        .bind(user_id)
+        .execute(state.db.pool())
+        .await
+        .unwrap();
+    assert!(UserQueries::is_admin(state.db.pool(), user_id).await.unwrap());
+
+    // Deactivated administrators lose access
+    sqlx::query("UPDATE users SET is_active = false WHERE id = $1")
+        .bind(user_id)
+        .execute(state.db.pool())
+        .await
+        .unwrap();
+    assert!(!UserQueries::is_admin(state.db.pool(), user_id).await.unwrap());
+}
\ No newline at end of file
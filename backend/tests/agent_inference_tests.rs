@@ -0,0 +1,138 @@
+//! Tests for running market analysis through pluggable agent inference
+//!
+//! The analysis test needs a running Postgres instance and is ignored
+//! unless run with `--ignored` and `DATABASE_URL` set.
+
+use guardian_aa_backend::{
+    api::AppState,
+    db::models::{Agent, AgentType, PredictionType},
+    services::{
+        agent::{AgentPredictionResult, MarketAnalysisRequest},
+        agent_inference::PredictFuture,
+        AgentInference, AgentInferenceRegistry, AgentService, MockAgentInference,
+    },
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use uuid::Uuid;
+
+mod common;
+
+async fn test_state() -> Arc<AppState> {
+    let mut config = common::database_config();
+    // Every run should reach the inference backends
+    config.analysis.min_interval_secs = 0;
+    common::connect(config).await
+}
+
+/// Reads the headlines: strongly bullish whenever news is included
+struct HeadlineReader {
+    calls: AtomicUsize,
+}
+
+impl AgentInference for HeadlineReader {
+    fn predict<'a>(&'a self, agent: &'a Agent, market: &'a MarketAnalysisRequest) -> PredictFuture<'a> {
+        Box::pin(async move {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let prediction = if market.include_news { PredictionType::Bullish } else { PredictionType::Neutral };
+            Ok(AgentPredictionResult {
+                agent_id: agent.id,
+                agent_name: agent.name.clone(),
+                agent_type: agent.agent_type.clone(),
+                prediction,
+                confidence: 0.9,
+                reasoning: format!("Headlines for {}", market.asset_symbol),
+            })
+        })
+    }
+}
+
+/// Reads the chart: mildly bearish on every timeframe
+struct ChartReader {
+    calls: AtomicUsize,
+}
+
+impl AgentInference for ChartReader {
+    fn predict<'a>(&'a self, agent: &'a Agent, market: &'a MarketAnalysisRequest) -> PredictFuture<'a> {
+        Box::pin(async move {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(AgentPredictionResult {
+                agent_id: agent.id,
+                agent_name: agent.name.clone(),
+                agent_type: agent.agent_type.clone(),
+                prediction: PredictionType::Bearish,
+                confidence: 0.6,
+                reasoning: format!("{} chart for {}", market.timeframe, market.asset_symbol),
+            })
+        })
+    }
+}
+
+fn request() -> MarketAnalysisRequest {
+    MarketAnalysisRequest {
+        asset_symbol: "SOL".to_string(),
+        timeframe: "4h".to_string(),
+        include_news: true,
+        include_technical: true,
+        include_fundamentals: false,
+    }
+}
+
+#[test]
+fn test_default_registry_covers_every_aggregated_agent_type() {
+    let registry = AgentInferenceRegistry::default();
+
+    for agent_type in [AgentType::NewsSentiment, AgentType::MarketFactor, AgentType::TechnicalAnalysis, AgentType::CryptoFactor] {
+        assert!(registry.get(&agent_type).is_some(), "no backend for {:?}", agent_type);
+    }
+    assert!(registry.get(&AgentType::Ensemble).is_none());
+}
+
+#[test]
+fn test_registering_replaces_backend() {
+    let registry = AgentInferenceRegistry::empty()
+        .register(AgentType::MarketFactor, Arc::new(MockAgentInference::new(PredictionType::Neutral, 0.5)));
+    assert!(registry.get(&AgentType::MarketFactor).is_some());
+    assert!(registry.get(&AgentType::NewsSentiment).is_none());
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_analysis_aggregates_registered_backends() {
+    let state = test_state().await;
+    let user_id: Uuid = sqlx::query_scalar("INSERT INTO users (email, password_hash) VALUES ($1, 'hash') RETURNING id")
+        .bind(format!("inference-{}@example.com", Uuid::new_v4()))
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap();
+
+    let headlines = Arc::new(HeadlineReader { calls: AtomicUsize::new(0) });
+    let charts = Arc::new(ChartReader { calls: AtomicUsize::new(0) });
+    // Market and crypto factor agents have no backend and are left out
+    let registry = AgentInferenceRegistry::empty()
+        .register(AgentType::NewsSentiment, headlines.clone())
+        .register(AgentType::TechnicalAnalysis, charts.clone());
+
+    let analysis = AgentService::new(state.clone())
+        .with_inference(registry)
+        .generate_market_analysis(user_id, "SOL", request())
+        .await
+        .unwrap();
+
+    // The seeded news sentiment and technical analysis agents
+    assert_eq!(headlines.calls.load(Ordering::SeqCst), 1);
+    assert_eq!(charts.calls.load(Ordering::SeqCst), 1);
+    assert_eq!(analysis.agent_predictions.len(), 2);
+    assert!(analysis.agent_predictions.iter().any(|p| p.reasoning == "4h chart for SOL"));
+
+    // The seeded technical analysis agent wants at least 0.7 confidence, so
+    // its 0.6 bearish call is left out of the vote
+    let ensemble = &analysis.ensemble_result;
+    assert_eq!(ensemble.prediction, PredictionType::Bullish);
+    assert_eq!(ensemble.agent_count, 1);
+    assert!((ensemble.confidence - 0.9).abs() < 1e-9);
+    assert!((ensemble.consensus_strength - 1.0).abs() < 1e-9);
+    assert_eq!(ensemble.included[0].agent_type, AgentType::NewsSentiment);
+    assert_eq!(ensemble.excluded.len(), 1);
+    assert_eq!(ensemble.excluded[0].agent_type, AgentType::TechnicalAnalysis);
+}
\ No newline at end of file
@@ -0,0 +1,136 @@
+//! Tests for exact amount handling on the transaction path
+//!
+//! The transaction tests need a running Postgres instance and are ignored
+//! unless run with `--ignored` and `DATABASE_URL` set.
+
+use guardian_aa_backend::{
+    api::AppState,
+    db::models::{CreateTransaction, TransactionType},
+    error::Error,
+    services::TransactionService,
+    utils::amount::{format_amount, lamports_to_sol, parse_amount, parse_positive_amount},
+};
+use uuid::Uuid;
+
+mod common;
+
+/// One more than a value f64 can hold exactly
+const PAST_F64: &str = "9000000000000000001";
+
+#[test]
+fn test_large_integer_is_exact() {
+    // As a float this collapses onto its neighbour
+    assert_eq!(PAST_F64.parse::<f64>().unwrap(), 9_000_000_000_000_000_000f64);
+
+    let amount = parse_amount(PAST_F64).unwrap();
+    assert_eq!(format_amount(amount), PAST_F64);
+    assert_ne!(amount, parse_amount("9000000000000000000").unwrap());
+}
+
+#[test]
+fn test_fractional_digits_are_kept() {
+    let amount = parse_amount("123456789.123456789123456789").unwrap();
+    assert_eq!(format_amount(amount), "123456789.123456789123456789");
+
+    // 0.1 + 0.2 is exactly 0.3, unlike in floating point
+    let sum = parse_amount("0.1").unwrap() + parse_amount("0.2").unwrap();
+    assert_eq!(sum, parse_amount("0.3").unwrap());
+}
+
+#[test]
+fn test_formatting_is_canonical() {
+    assert_eq!(format_amount(parse_amount("1.500").unwrap()), "1.5");
+    assert_eq!(format_amount(parse_amount(" 007 ").unwrap()), "7");
+    assert_eq!(format_amount(parse_amount("0.000").unwrap()), "0");
+}
+
+#[test]
+fn test_malformed_amounts_are_rejected() {
+    for amount in ["", "abc", "1.", ".5", "1e9", "1_000", "+1", "1.2.3", "NaN", "inf", "0x10"] {
+        assert!(matches!(parse_amount(amount), Err(Error::Validation(_))), "{:?} was accepted", amount);
+    }
+}
+
+#[test]
+fn test_negative_and_zero_amounts_are_rejected() {
+    let Err(Error::Validation(message)) = parse_amount("-1") else { panic!("negative amount accepted") };
+    assert!(message.contains("negative"));
+
+    assert!(parse_amount("0").is_ok());
+    assert!(matches!(parse_positive_amount("0.0"), Err(Error::Validation(_))));
+    assert!(parse_positive_amount("0.000000001").is_ok());
+}
+
+#[test]
+fn test_amount_too_precise_for_decimal_is_rejected() {
+    // Rejected rather than silently rounded
+    let too_many_digits = format!("1.{}", "1".repeat(40));
+    assert!(matches!(parse_amount(&too_many_digits), Err(Error::Validation(_))));
+    assert!(matches!(parse_amount(&"9".repeat(40)), Err(Error::Validation(_))));
+}
+
+#[test]
+fn test_lamports_convert_exactly() {
+    assert_eq!(format_amount(lamports_to_sol(5_000)), "0.000005");
+    assert_eq!(format_amount(lamports_to_sol(1_000_000_000)), "1");
+    assert_eq!(format_amount(lamports_to_sol(u64::MAX)), "18446744073.709551615");
+}
+
+/// A user with one wallet: (user id, wallet id)
+async fn create_wallet(state: &AppState) -> (Uuid, Uuid) {
+    let user_id: Uuid = sqlx::query_scalar("INSERT INTO users (email, password_hash) VALUES ($1, 'hash') RETURNING id")
+        .bind(format!("amount-{}@example.com", Uuid::new_v4()))
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap();
+    let wallet_id = sqlx::query_scalar(
+        "INSERT INTO wallets (user_id, name, wallet_type, public_key) VALUES ($1, 'Amounts', 'solana', $2) RETURNING id",
+    )
+        .bind(user_id)
+        .bind(Uuid::new_v4().to_string())
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap();
+
+    (user_id, wallet_id)
+}
+
+fn send(wallet_id: Uuid, amount: &str) -> CreateTransaction {
+    CreateTransaction {
+        wallet_id,
+        transaction_type: TransactionType::Send,
+        from_address: "sender".to_string(),
+        to_address: "recipient".to_string(),
+        amount: amount.to_string(),
+        token_mint: None,
+        raw_transaction: None,
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_transaction_amount_is_stored_exactly() {
+    let state = common::test_state().await;
+    let (user_id, wallet_id) = create_wallet(&state).await;
+    let service = TransactionService::new(state.clone());
+
+    let transaction = service.create_transaction(user_id, send(wallet_id, PAST_F64)).await.unwrap();
+    assert_eq!(transaction.amount, PAST_F64);
+
+    // Stored in canonical form
+    let transaction = service.create_transaction(user_id, send(wallet_id, "2.50")).await.unwrap();
+    assert_eq!(transaction.amount, "2.5");
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_transaction_with_bad_amount_is_rejected() {
+    let state = common::test_state().await;
+    let (user_id, wallet_id) = create_wallet(&state).await;
+    let service = TransactionService::new(state.clone());
+
+    for amount in ["-5", "1e3", "0"] {
+        let result = service.create_transaction(user_id, send(wallet_id, amount)).await;
+        assert!(matches!(result, Err(Error::Validation(_))), "{:?} was accepted", amount);
+    }
+}
\ No newline at end of file
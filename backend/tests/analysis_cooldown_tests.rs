@@ -0,0 +1,105 @@
+//! Tests for the per-user market analysis cool-down
+//!
+//! The analysis tests need running Postgres and Redis instances and are
+//! ignored unless run with `--ignored` and both `DATABASE_URL` and
+//! `REDIS_URL` set.
+
+use axum::{
+    http::{header::RETRY_AFTER, StatusCode},
+    response::IntoResponse,
+};
+use guardian_aa_backend::{
+    api::AppState,
+    config::CooldownResponse,
+    error::Error,
+    services::{agent::MarketAnalysisRequest, AgentService},
+};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+mod common;
+
+const MIN_INTERVAL_SECS: u64 = 1;
+
+async fn test_state(on_cooldown: CooldownResponse) -> Arc<AppState> {
+    let mut config = common::database_and_redis_config();
+    config.analysis.min_interval_secs = MIN_INTERVAL_SECS;
+    config.analysis.on_cooldown = on_cooldown;
+    common::connect(config).await
+}
+
+async fn create_user(state: &AppState) -> Uuid {
+    sqlx::query_scalar("INSERT INTO users (email, password_hash) VALUES ($1, 'hash') RETURNING id")
+        .bind(format!("analysis-{}@example.com", Uuid::new_v4()))
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap()
+}
+
+fn request() -> MarketAnalysisRequest {
+    MarketAnalysisRequest {
+        asset_symbol: "SOL".to_string(),
+        timeframe: "1d".to_string(),
+        include_news: true,
+        include_technical: true,
+        include_fundamentals: false,
+    }
+}
+
+#[test]
+fn test_cooling_down_response_has_retry_after() {
+    let response = Error::CoolingDown { retry_after_secs: 42 }.into_response();
+
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()[RETRY_AFTER], "42");
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_repeat_analysis_is_cached_until_interval_passes() {
+    let state = test_state(CooldownResponse::Cached).await;
+    let user_id = create_user(&state).await;
+    let service = AgentService::new(state.clone());
+
+    let first = service.generate_market_analysis(user_id, "SOL", request()).await.unwrap();
+    let too_soon = service.generate_market_analysis(user_id, "SOL", request()).await.unwrap();
+    assert_eq!(too_soon.analysis_timestamp, first.analysis_timestamp);
+
+    tokio::time::sleep(Duration::from_millis(MIN_INTERVAL_SECS * 1000 + 200)).await;
+
+    let later = service.generate_market_analysis(user_id, "SOL", request()).await.unwrap();
+    assert!(later.analysis_timestamp > first.analysis_timestamp);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_repeat_analysis_is_rejected_when_configured() {
+    let state = test_state(CooldownResponse::Reject).await;
+    let user_id = create_user(&state).await;
+    let service = AgentService::new(state.clone());
+
+    service.generate_market_analysis(user_id, "SOL", request()).await.unwrap();
+    let too_soon = service.generate_market_analysis(user_id, "SOL", request()).await;
+    assert!(matches!(too_soon, Err(Error::CoolingDown { retry_after_secs }) if retry_after_secs <= MIN_INTERVAL_SECS));
+
+    // The cool-down is per asset
+    service.generate_market_analysis(user_id, "BTC", request()).await.unwrap();
+
+    tokio::time::sleep(Duration::from_millis(MIN_INTERVAL_SECS * 1000 + 200)).await;
+    assert!(service.generate_market_analysis(user_id, "SOL", request()).await.is_ok());
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_preview_does_not_start_cool_down() {
+    let state = test_state(CooldownResponse::Reject).await;
+    let user_id = create_user(&state).await;
+    let service = AgentService::new(state.clone());
+
+    service.simulate_market_analysis(user_id, "SOL", request()).await.unwrap();
+    service.generate_market_analysis(user_id, "SOL", request()).await.unwrap();
+
+    // Nor is a preview held back by one
+    assert!(service.simulate_market_analysis(user_id, "SOL", request()).await.is_ok());
+}
\ No newline at end of file
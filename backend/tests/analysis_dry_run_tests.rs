@@ -0,0 +1,85 @@
+//! Tests for previewing a market analysis without saving it
+//!
+//! These tests need a running Postgres instance and are ignored unless run
+//! with `--ignored` and `DATABASE_URL` set.
+
+use guardian_aa_backend::{
+    api::AppState,
+    services::{agent::MarketAnalysisRequest, AgentService},
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+mod common;
+
+async fn test_state() -> Arc<AppState> {
+    let mut config = common::database_config();
+    config.analysis.min_interval_secs = 0;
+    common::connect(config).await
+}
+
+async fn create_user(state: &AppState) -> Uuid {
+    sqlx::query_scalar("INSERT INTO users (email, password_hash) VALUES ($1, 'hash') RETURNING id")
+        .bind(format!("dry-run-{}@example.com", Uuid::new_v4()))
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap()
+}
+
+/// Rows the user owns in the tables an analysis could write to
+async fn saved_rows(state: &AppState, user_id: Uuid) -> i64 {
+    sqlx::query_scalar(
+        "SELECT (SELECT COUNT(*) FROM portfolio_recommendations WHERE user_id = $1)
+              + (SELECT COUNT(*) FROM agent_predictions WHERE user_id = $1)",
+    )
+    .bind(user_id)
+    .fetch_one(state.db.pool())
+    .await
+    .unwrap()
+}
+
+fn request() -> MarketAnalysisRequest {
+    MarketAnalysisRequest {
+        asset_symbol: "SOL".to_string(),
+        timeframe: "1d".to_string(),
+        include_news: true,
+        include_technical: true,
+        include_fundamentals: false,
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_dry_run_recommends_without_saving() {
+    let state = test_state().await;
+    let user_id = create_user(&state).await;
+    let service = AgentService::new(state.clone());
+
+    let preview = service.simulate_market_analysis(user_id, "SOL", request()).await.unwrap();
+
+    assert!(preview.dry_run);
+    assert!(!preview.agent_predictions.is_empty());
+    let recommendation = preview.portfolio_recommendation.expect("preview has a recommendation");
+    assert!(recommendation.id.is_nil());
+    assert_eq!(recommendation.user_id, user_id);
+    assert_eq!(recommendation.confidence_score, preview.ensemble_result.confidence);
+    assert_eq!(saved_rows(&state, user_id).await, 0);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_committed_analysis_matches_preview() {
+    let state = test_state().await;
+    let user_id = create_user(&state).await;
+    let service = AgentService::new(state.clone());
+
+    let preview = service.simulate_market_analysis(user_id, "SOL", request()).await.unwrap();
+    let analysis = service.generate_market_analysis(user_id, "SOL", request()).await.unwrap();
+
+    assert!(!analysis.dry_run);
+    let previewed = preview.portfolio_recommendation.unwrap();
+    let saved = analysis.portfolio_recommendation.unwrap();
+    assert!(!saved.id.is_nil());
+    assert_eq!(saved.asset_allocations, previewed.asset_allocations);
+    assert_eq!(saved_rows(&state, user_id).await, 1);
+}
\ No newline at end of file
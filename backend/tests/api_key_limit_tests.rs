@@ -0,0 +1,90 @@
+//! Tests for the per-user API key limit
+//!
+//! These tests need a running Postgres instance and are ignored unless run
+//! with `--ignored` and `DATABASE_URL` set.
+
+use guardian_aa_backend::{
+    api::{handlers::api_key::CreateApiKeyRequest, AppState},
+    error::Error,
+    services::ApiKeyService,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+mod common;
+
+const MAX_KEYS: i64 = 2;
+
+async fn test_state() -> Arc<AppState> {
+    let mut config = common::database_config();
+    config.auth.max_api_keys_per_user = MAX_KEYS;
+    common::connect(config).await
+}
+
+async fn create_user(state: &AppState) -> Uuid {
+    sqlx::query_scalar("INSERT INTO users (email, password_hash) VALUES ($1, 'hash') RETURNING id")
+        .bind(format!("api-key-limit-{}@example.com", Uuid::new_v4()))
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap()
+}
+
+async fn create_key(state: &Arc<AppState>, user_id: Uuid) -> Result<Uuid, Error> {
+    ApiKeyService::new(state.clone())
+        .create_api_key(user_id, CreateApiKeyRequest {
+            name: "integration".to_string(),
+            permissions: vec!["zkml".to_string()],
+            expires_at: None,
+        })
+        .await
+        .map(|created| created.id)
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_key_creation_stops_at_limit() {
+    let state = test_state().await;
+    let user_id = create_user(&state).await;
+
+    for _ in 0..MAX_KEYS {
+        create_key(&state, user_id).await.unwrap();
+    }
+
+    assert!(matches!(create_key(&state, user_id).await, Err(Error::Conflict(_))));
+
+    // Other users have their own limit
+    let other_user = create_user(&state).await;
+    assert!(create_key(&state, other_user).await.is_ok());
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_revoking_a_key_frees_a_slot() {
+    let state = test_state().await;
+    let user_id = create_user(&state).await;
+
+    let first = create_key(&state, user_id).await.unwrap();
+    create_key(&state, user_id).await.unwrap();
+    assert!(create_key(&state, user_id).await.is_err());
+
+    ApiKeyService::new(state.clone()).revoke_api_key(user_id, first).await.unwrap();
+    assert!(create_key(&state, user_id).await.is_ok());
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_expired_keys_do_not_count() {
+    let state = test_state().await;
+    let user_id = create_user(&state).await;
+
+    let first = create_key(&state, user_id).await.unwrap();
+    create_key(&state, user_id).await.unwrap();
+
+    sqlx::query("UPDATE api_keys SET expires_at = NOW() - INTERVAL '1 minute' WHERE id = $1")
+        .bind(first)
+        .execute(state.db.pool())
+        .await
+        .unwrap();
+
+    assert!(create_key(&state, user_id).await.is_ok());
+}
\ No newline at end of file
@@ -0,0 +1,176 @@
+//! Tests for API key authentication
+//!
+//! These tests need running Postgres and Redis instances and are ignored
+//! unless run with `--ignored` and both `DATABASE_URL` and `REDIS_URL` set.
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{Method, StatusCode},
+    middleware,
+    routing::get,
+    Extension, Router,
+};
+use guardian_aa_backend::{
+    api::{
+        handlers::api_key::CreateApiKeyRequest,
+        middleware::{
+            api_key::{api_key_middleware, require_permission, ApiKeyContext},
+            auth::UserContext,
+        },
+        AppState,
+    },
+    auth::TokenDenylist,
+    services::ApiKeyService,
+};
+use std::sync::Arc;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+mod common;
+
+async fn test_state() -> Arc<AppState> {
+    common::connect(common::database_and_redis_config()).await
+}
+
+async fn create_user(state: &AppState) -> Uuid {
+    sqlx::query_scalar("INSERT INTO users (email, password_hash) VALUES ($1, 'hash') RETURNING id")
+        .bind(format!("api-keys-{}@example.com", Uuid::new_v4()))
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap()
+}
+
+/// Create a key with `permissions`, returning its id and the key itself
+async fn create_key(state: &Arc<AppState>, user_id: Uuid, permissions: &[&str]) -> (Uuid, String) {
+    let created = ApiKeyService::new(state.clone())
+        .create_api_key(user_id, CreateApiKeyRequest {
+            name: "integration".to_string(),
+            permissions: permissions.iter().map(|p| p.to_string()).collect(),
+            expires_at: None,
+        })
+        .await
+        .unwrap();
+
+    (created.id, created.key)
+}
+
+/// Router with one route that needs a key holding the `zkml` permission
+fn app(state: Arc<AppState>) -> Router {
+    async fn handler(
+        Extension(api_key): Extension<ApiKeyContext>,
+        Extension(user): Extension<UserContext>,
+    ) -> String {
+        assert_eq!(api_key.user_id, user.user_id);
+        user.user_id.to_string()
+    }
+
+    Router::new()
+        .route("/zkml", get(handler))
+        .route_layer(middleware::from_fn_with_state("zkml", require_permission))
+        .route_layer(middleware::from_fn_with_state(state, api_key_middleware))
+}
+
+async fn call(state: &Arc<AppState>, key: Option<&str>) -> StatusCode {
+    let mut request = Request::builder().method(Method::GET).uri("/zkml");
+    if let Some(key) = key {
+        request = request.header("X-API-Key", key);
+    }
+
+    app(state.clone())
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+        .status()
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_valid_key_is_accepted() {
+    let state = test_state().await;
+    let user_id = create_user(&state).await;
+    let (key_id, key) = create_key(&state, user_id, &["zkml"]).await;
+
+    assert_eq!(call(&state, Some(&key)).await, StatusCode::OK);
+
+    let last_used: Option<chrono::DateTime<chrono::Utc>> =
+        sqlx::query_scalar("SELECT last_used_at FROM api_keys WHERE id = $1")
+            .bind(key_id)
+            .fetch_one(state.db.pool())
+            .await
+            .unwrap();
+    assert!(last_used.is_some());
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_missing_or_unknown_key_is_rejected() {
+    let state = test_state().await;
+
+    assert_eq!(call(&state, None).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(call(&state, Some("gaa_not-a-real-key")).await, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_expired_key_is_rejected() {
+    let state = test_state().await;
+    let user_id = create_user(&state).await;
+    let (key_id, key) = create_key(&state, user_id, &["zkml"]).await;
+
+    sqlx::query("UPDATE api_keys SET expires_at = NOW() - INTERVAL '1 minute' WHERE id = $1")
+        .bind(key_id)
+        .execute(state.db.pool())
+        .await
+        .unwrap();
+
+    assert_eq!(call(&state, Some(&key)).await, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_revoked_key_is_rejected() {
+    let state = test_state().await;
+    let user_id = create_user(&state).await;
+    let (key_id, key) = create_key(&state, user_id, &["zkml"]).await;
+
+    ApiKeyService::new(state.clone()).revoke_api_key(user_id, key_id).await.unwrap();
+
+    assert_eq!(call(&state, Some(&key)).await, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_key_without_permission_is_forbidden() {
+    let state = test_state().await;
+    let user_id = create_user(&state).await;
+    let (_, key) = create_key(&state, user_id, &["wallet"]).await;
+
+    assert_eq!(call(&state, Some(&key)).await, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_key_is_rejected_once_its_owner_is_revoked() {
+    let state = test_state().await;
+    let user_id = create_user(&state).await;
+    let (_, key) = create_key(&state, user_id, &["zkml"]).await;
+    assert_eq!(call(&state, Some(&key)).await, StatusCode::OK);
+
+    // What signing a user out everywhere does to their tokens
+    TokenDenylist::new(state.redis.clone()).revoke_user(&user_id.to_string(), 60).await.unwrap();
+
+    assert_eq!(call(&state, Some(&key)).await, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_key_is_refused_while_the_denylist_is_unreachable() {
+    let mut config = common::database_config();
+    config.redis.url = "redis://127.0.0.1:1".to_string();
+    let state = common::connect(config).await;
+    let user_id = create_user(&state).await;
+    let (_, key) = create_key(&state, user_id, &["zkml"]).await;
+
+    assert_eq!(call(&state, Some(&key)).await, StatusCode::SERVICE_UNAVAILABLE);
+}
\ No newline at end of file
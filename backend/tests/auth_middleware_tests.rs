@@ -10,7 +10,8 @@ use axum::{
     Router,
 };
 use guardian_aa_backend::{
-    api::middleware::auth::{auth_middleware, optional_auth_middleware},
+    api::middleware::auth::{auth_middleware, decode_claims, optional_auth_middleware, AuthState},
+    auth::TokenDenylist,
     config::Config,
     error::Error,
 };
@@ -26,15 +27,35 @@ struct TestClaims {
     email: String,
     exp: i64,
     iat: i64,
+    iss: String,
+    aud: String,
+    jti: String,
+    typ: String,
 }
 
 // Helper function to create a valid JWT token
 fn create_test_token(user_id: &str, email: &str, secret: &str, exp: i64) -> String {
+    create_test_token_with_audience(user_id, email, secret, exp, "guardian-aa", "guardian-aa-api")
+}
+
+// Helper function to create a JWT token with a specific issuer and audience
+fn create_test_token_with_audience(
+    user_id: &str,
+    email: &str,
+    secret: &str,
+    exp: i64,
+    iss: &str,
+    aud: &str,
+) -> String {
     let claims = TestClaims {
         sub: user_id.to_string(),
         email: email.to_string(),
         exp,
         iat: chrono::Utc::now().timestamp(),
+        iss: iss.to_string(),
+        aud: aud.to_string(),
+        jti: Uuid::new_v4().to_string(),
+        typ: "access".to_string(),
     };
 
     encode(
@@ -73,7 +94,7 @@ async fn test_auth_middleware_with_valid_token() {
 
     let app = Router::new()
         .route("/protected", get(protected_handler))
-        .layer(middleware::from_fn_with_state(config, auth_middleware));
+        .layer(middleware::from_fn_with_state(AuthState::new(config), auth_middleware));
 
     let request = Request::builder()
         .method(Method::GET)
@@ -97,7 +118,7 @@ async fn test_auth_middleware_with_expired_token() {
 
     let app = Router::new()
         .route("/protected", get(protected_handler))
-        .layer(middleware::from_fn_with_state(config, auth_middleware));
+        .layer(middleware::from_fn_with_state(AuthState::new(config), auth_middleware));
 
     let request = Request::builder()
         .method(Method::GET)
@@ -117,7 +138,7 @@ async fn test_auth_middleware_with_invalid_token() {
 
     let app = Router::new()
         .route("/protected", get(protected_handler))
-        .layer(middleware::from_fn_with_state(config, auth_middleware));
+        .layer(middleware::from_fn_with_state(AuthState::new(config), auth_middleware));
 
     let request = Request::builder()
         .method(Method::GET)
@@ -142,7 +163,101 @@ async fn test_auth_middleware_with_wrong_secret() {
 
     let app = Router::new()
         .route("/protected", get(protected_handler))
-        .layer(middleware::from_fn_with_state(config, auth_middleware));
+        .layer(middleware::from_fn_with_state(AuthState::new(config), auth_middleware));
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/protected")
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_auth_middleware_with_configured_audience() {
+    let mut config = Config::default();
+    config.auth.jwt_secret = "test-secret-key".to_string();
+    config.auth.jwt_audiences = vec!["mobile-app".to_string(), "web-app".to_string()];
+    let config = Arc::new(config);
+
+    let user_id = Uuid::new_v4().to_string();
+    let exp = chrono::Utc::now().timestamp() + 3600;
+    let token = create_test_token_with_audience(
+        &user_id,
+        "test@example.com",
+        &config.auth.jwt_secret,
+        exp,
+        &config.auth.jwt_issuer,
+        "web-app",
+    );
+
+    let app = Router::new()
+        .route("/protected", get(protected_handler))
+        .layer(middleware::from_fn_with_state(AuthState::new(config), auth_middleware));
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/protected")
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_auth_middleware_with_wrong_audience() {
+    let config = create_test_config();
+    let user_id = Uuid::new_v4().to_string();
+    let exp = chrono::Utc::now().timestamp() + 3600;
+
+    // Token minted for a different deployment sharing the same secret
+    let token = create_test_token_with_audience(
+        &user_id,
+        "test@example.com",
+        &config.auth.jwt_secret,
+        exp,
+        &config.auth.jwt_issuer,
+        "other-deployment",
+    );
+
+    let app = Router::new()
+        .route("/protected", get(protected_handler))
+        .layer(middleware::from_fn_with_state(AuthState::new(config), auth_middleware));
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/protected")
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_auth_middleware_with_wrong_issuer() {
+    let config = create_test_config();
+    let user_id = Uuid::new_v4().to_string();
+    let exp = chrono::Utc::now().timestamp() + 3600;
+
+    let token = create_test_token_with_audience(
+        &user_id,
+        "test@example.com",
+        &config.auth.jwt_secret,
+        exp,
+        "someone-else",
+        &config.auth.jwt_audiences[0],
+    );
+
+    let app = Router::new()
+        .route("/protected", get(protected_handler))
+        .layer(middleware::from_fn_with_state(AuthState::new(config), auth_middleware));
 
     let request = Request::builder()
         .method(Method::GET)
@@ -161,7 +276,7 @@ async fn test_auth_middleware_missing_authorization_header() {
 
     let app = Router::new()
         .route("/protected", get(protected_handler))
-        .layer(middleware::from_fn_with_state(config, auth_middleware));
+        .layer(middleware::from_fn_with_state(AuthState::new(config), auth_middleware));
 
     let request = Request::builder()
         .method(Method::GET)
@@ -179,7 +294,7 @@ async fn test_auth_middleware_invalid_bearer_format() {
 
     let app = Router::new()
         .route("/protected", get(protected_handler))
-        .layer(middleware::from_fn_with_state(config, auth_middleware));
+        .layer(middleware::from_fn_with_state(AuthState::new(config), auth_middleware));
 
     let request = Request::builder()
         .method(Method::GET)
@@ -198,7 +313,7 @@ async fn test_auth_middleware_empty_token() {
 
     let app = Router::new()
         .route("/protected", get(protected_handler))
-        .layer(middleware::from_fn_with_state(config, auth_middleware));
+        .layer(middleware::from_fn_with_state(AuthState::new(config), auth_middleware));
 
     let request = Request::builder()
         .method(Method::GET)
@@ -222,7 +337,7 @@ async fn test_optional_auth_middleware_with_valid_token() {
 
     let app = Router::new()
         .route("/optional", get(optional_handler))
-        .layer(middleware::from_fn_with_state(config, optional_auth_middleware));
+        .layer(middleware::from_fn_with_state(AuthState::new(config), optional_auth_middleware));
 
     let request = Request::builder()
         .method(Method::GET)
@@ -241,7 +356,7 @@ async fn test_optional_auth_middleware_without_token() {
 
     let app = Router::new()
         .route("/optional", get(optional_handler))
-        .layer(middleware::from_fn_with_state(config, optional_auth_middleware));
+        .layer(middleware::from_fn_with_state(AuthState::new(config), optional_auth_middleware));
 
     let request = Request::builder()
         .method(Method::GET)
@@ -259,7 +374,7 @@ async fn test_optional_auth_middleware_with_invalid_token() {
 
     let app = Router::new()
         .route("/optional", get(optional_handler))
-        .layer(middleware::from_fn_with_state(config, optional_auth_middleware));
+        .layer(middleware::from_fn_with_state(AuthState::new(config), optional_auth_middleware));
 
     let request = Request::builder()
         .method(Method::GET)
@@ -271,4 +386,139 @@ async fn test_optional_auth_middleware_with_invalid_token() {
     let response = app.oneshot(request).await.unwrap();
     // Should still succeed but without user context
     assert_eq!(response.status(), StatusCode::OK);
-} 
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_auth_middleware_rejects_token_without_jti() {
+    let config = create_test_config();
+    let claims = serde_json::json!({
+        "sub": Uuid::new_v4().to_string(),
+        "email": "test@example.com",
+        "exp": chrono::Utc::now().timestamp() + 3600,
+        "iat": chrono::Utc::now().timestamp(),
+        "iss": "guardian-aa",
+        "aud": "guardian-aa-api",
+    });
+    let token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(config.auth.jwt_secret.as_bytes()),
+    )
+    .unwrap();
+
+    let app = Router::new()
+        .route("/protected", get(protected_handler))
+        .layer(middleware::from_fn_with_state(AuthState::new(config), auth_middleware));
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/protected")
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_auth_middleware_rejects_refresh_token() {
+    let config = create_test_config();
+    let claims = serde_json::json!({
+        "sub": Uuid::new_v4().to_string(),
+        "email": "test@example.com",
+        "exp": chrono::Utc::now().timestamp() + 3600,
+        "iat": chrono::Utc::now().timestamp(),
+        "iss": "guardian-aa",
+        "aud": "guardian-aa-api",
+        "jti": Uuid::new_v4().to_string(),
+        "typ": "refresh",
+    });
+    let token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(config.auth.jwt_secret.as_bytes()),
+    )
+    .unwrap();
+
+    assert!(matches!(decode_claims(&token, &config.auth), Err(Error::Unauthorized)));
+
+    let app = Router::new()
+        .route("/protected", get(protected_handler))
+        .layer(middleware::from_fn_with_state(AuthState::new(config), auth_middleware));
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/protected")
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[ignore = "needs REDIS_URL"]
+async fn test_auth_middleware_rejects_revoked_token() {
+    let url = std::env::var("REDIS_URL").expect("REDIS_URL must be set to run this test");
+
+    let config = create_test_config();
+    let denylist = TokenDenylist::new(redis::Client::open(url).unwrap());
+    let exp = chrono::Utc::now().timestamp() + 3600;
+    let logged_out = create_test_token(&Uuid::new_v4().to_string(), "test@example.com", &config.auth.jwt_secret, exp);
+    let fresh = create_test_token(&Uuid::new_v4().to_string(), "test@example.com", &config.auth.jwt_secret, exp);
+
+    // Revoke the token the way logout does
+    let claims = decode_claims(&logged_out, &config.auth).unwrap();
+    denylist.revoke(&claims.jti, claims.exp).await.unwrap();
+
+    let app = Router::new()
+        .route("/protected", get(protected_handler))
+        .layer(middleware::from_fn_with_state(
+            AuthState::new(config).with_denylist(denylist),
+            auth_middleware,
+        ));
+
+    let status = |token: String| {
+        let app = app.clone();
+        async move {
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri("/protected")
+                .header(AUTHORIZATION, format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap();
+            app.oneshot(request).await.unwrap().status()
+        }
+    };
+
+    assert_eq!(status(logged_out).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(status(fresh).await, StatusCode::OK);
+} 
+
+#[tokio::test]
+async fn test_auth_middleware_fails_closed_when_denylist_is_unreachable() {
+    let config = create_test_config();
+    let exp = chrono::Utc::now().timestamp() + 3600;
+    let token = create_test_token(&Uuid::new_v4().to_string(), "test@example.com", &config.auth.jwt_secret, exp);
+    // Nothing listens on port 1
+    let denylist = TokenDenylist::new(redis::Client::open("redis://127.0.0.1:1").unwrap());
+
+    let app = Router::new()
+        .route("/protected", get(protected_handler))
+        .layer(middleware::from_fn_with_state(
+            AuthState::new(config).with_denylist(denylist),
+            auth_middleware,
+        ));
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/protected")
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+}
\ No newline at end of file
@@ -0,0 +1,101 @@
+//! Tests for the wallet balance cache
+
+use guardian_aa_backend::{
+    blockchain::{solana::Balance, BalanceCache},
+    config::BalanceCacheConfig,
+    error::{Error, Result},
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+const ADDRESS: &str = "11111111111111111111111111111111";
+
+fn balance(lamports: u64) -> Balance {
+    Balance {
+        sol_balance: lamports,
+        sol_balance_formatted: lamports as f64 / 1_000_000_000.0,
+        token_balances: vec![],
+    }
+}
+
+async fn lookup(cache: &BalanceCache, fresh: bool, calls: &AtomicUsize, lamports: u64) -> Result<Balance> {
+    let cached = cache
+        .get_or_fetch(ADDRESS, fresh, || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Ok(balance(lamports))
+        })
+        .await?;
+    Ok(cached.balance)
+}
+
+#[tokio::test]
+async fn test_second_lookup_is_served_from_cache() {
+    let cache = BalanceCache::new(&BalanceCacheConfig { positive_ttl_secs: 60, negative_ttl_secs: 60, ..Default::default() });
+    let calls = AtomicUsize::new(0);
+
+    let first = lookup(&cache, false, &calls, 5_000).await.unwrap();
+    let second = lookup(&cache, false, &calls, 9_000).await.unwrap();
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert_eq!(first.sol_balance, 5_000);
+    assert_eq!(second.sol_balance, 5_000);
+}
+
+#[tokio::test]
+async fn test_empty_balance_is_cached() {
+    let cache = BalanceCache::new(&BalanceCacheConfig { positive_ttl_secs: 60, negative_ttl_secs: 60, ..Default::default() });
+    let calls = AtomicUsize::new(0);
+
+    lookup(&cache, false, &calls, 0).await.unwrap();
+    let second = lookup(&cache, false, &calls, 0).await.unwrap();
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert!(second.is_empty());
+}
+
+#[tokio::test]
+async fn test_fresh_bypasses_cache() {
+    let cache = BalanceCache::new(&BalanceCacheConfig { positive_ttl_secs: 60, negative_ttl_secs: 60, ..Default::default() });
+    let calls = AtomicUsize::new(0);
+
+    lookup(&cache, false, &calls, 5_000).await.unwrap();
+    let fresh = lookup(&cache, true, &calls, 9_000).await.unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert_eq!(fresh.sol_balance, 9_000);
+
+    // The fresh result replaces the cached one
+    let cached = lookup(&cache, false, &calls, 1).await.unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert_eq!(cached.sol_balance, 9_000);
+}
+
+#[tokio::test]
+async fn test_negative_ttl_applies_to_empty_balances() {
+    let cache = BalanceCache::new(&BalanceCacheConfig { positive_ttl_secs: 60, negative_ttl_secs: 1, ..Default::default() });
+    let calls = AtomicUsize::new(0);
+
+    lookup(&cache, false, &calls, 0).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+
+    // The empty result has expired, so the newly funded balance is fetched
+    let funded = lookup(&cache, false, &calls, 5_000).await.unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert_eq!(funded.sol_balance, 5_000);
+}
+
+#[tokio::test]
+async fn test_errors_are_not_cached() {
+    let cache = BalanceCache::default();
+    let calls = AtomicUsize::new(0);
+
+    let result = cache
+        .get_or_fetch(ADDRESS, false, || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(Error::Blockchain("rpc unavailable".to_string()))
+        })
+        .await;
+    assert!(result.is_err());
+
+    lookup(&cache, false, &calls, 5_000).await.unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
\ No newline at end of file
@@ -0,0 +1,152 @@
+//! Tests for the request body limits of each route group
+//!
+//! Routes behind authentication check the caller's account, so the tests
+//! for them need running Postgres and Redis instances and are ignored
+//! unless run with `--ignored` and both `DATABASE_URL` and `REDIS_URL` set.
+
+use axum::{
+    body::{Body, Bytes},
+    http::{header::{AUTHORIZATION, CONTENT_TYPE}, Request, StatusCode},
+    Router,
+};
+use guardian_aa_backend::{
+    api::{create_router, handlers::{auth::RegisterRequest, zkml::{generate_batch_body_limit, generate_body_limit}}, AppState},
+    config::Config,
+    services::AuthService,
+};
+use std::sync::Arc;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+mod common;
+
+/// App state whose connections are only opened on first use; no request
+/// here gets far enough to need them
+fn lazy_state() -> Arc<AppState> {
+    let mut config = Config::default();
+    config.rate_limit.enabled = false;
+    config.redis.url = "redis://127.0.0.1:1".to_string();
+    common::lazy_state(config)
+}
+
+/// Connected app state and the access token of a newly registered user
+async fn signed_in_state() -> (Arc<AppState>, String) {
+    let mut config = common::database_and_redis_config();
+    config.rate_limit.enabled = false;
+    let state = common::connect(config).await;
+
+    let registered = AuthService::new(state.clone())
+        .register(RegisterRequest {
+            email: format!("limits-{}@example.com", Uuid::new_v4()),
+            password: "correct horse battery".to_string(),
+            username: None,
+        })
+        .await
+        .unwrap();
+
+    (state, registered.access_token)
+}
+
+/// A JSON object of exactly `len` bytes whose `field` is filled with `fill`
+fn json_body(field: &str, fill: char, len: usize) -> String {
+    let frame = format!(r#"{{"{}":""}}"#, field);
+    let padding: String = std::iter::repeat(fill).take(len - frame.len()).collect();
+    format!(r#"{{"{}":"{}"}}"#, field, padding)
+}
+
+async fn post(app: &Router, uri: &str, token: Option<&str>, body: Body) -> (StatusCode, serde_json::Value) {
+    let mut request = Request::post(uri).header(CONTENT_TYPE, "application/json");
+    if let Some(token) = token {
+        request = request.header(AUTHORIZATION, format!("Bearer {}", token));
+    }
+
+    let response = app.clone().oneshot(request.body(body).unwrap()).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or_default())
+}
+
+fn assert_too_large(status: StatusCode, body: &serde_json::Value, limit: usize) {
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(body["code"], "payload_too_large");
+    assert!(body["message"].as_str().unwrap().contains(&format!("{}-byte limit", limit)), "{}", body);
+}
+
+#[tokio::test]
+async fn test_auth_routes_take_small_bodies() {
+    let state = lazy_state();
+    let limit = state.config.server.body_limits.auth_bytes;
+    let app = create_router(state);
+
+    // Read in full, then rejected for what it says rather than its size
+    let (status, _) = post(&app, "/api/v1/auth/login", None, Body::from(json_body("padding", 'a', limit))).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let (status, body) = post(&app, "/api/v1/auth/login", None, Body::from(json_body("padding", 'a', limit + 1))).await;
+    assert_too_large(status, &body, limit);
+}
+
+#[tokio::test]
+async fn test_limit_holds_without_content_length() {
+    let state = lazy_state();
+    let limit = state.config.server.body_limits.auth_bytes;
+    let app = create_router(state);
+
+    let chunks: Vec<Result<Bytes, std::io::Error>> = json_body("padding", 'a', limit + 1)
+        .into_bytes()
+        .chunks(1024)
+        .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+        .collect();
+    let body = Body::from_stream(futures_util::stream::iter(chunks));
+
+    let (status, body) = post(&app, "/api/v1/auth/login", None, body).await;
+    assert_too_large(status, &body, limit);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_proof_generation_is_capped_by_the_circuit() {
+    let (state, token) = signed_in_state().await;
+    let limit = generate_body_limit(&state.zkml_service);
+    assert!(limit < state.config.server.body_limits.default_bytes);
+    let app = create_router(state);
+
+    // Not base64, so it fails decoding once read
+    let (status, _) = post(&app, "/api/v1/zkml/generate", Some(&token), Body::from(json_body("data", '!', limit))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // The tighter generation limit is reported, not the route group's
+    let (status, body) = post(&app, "/api/v1/zkml/generate", Some(&token), Body::from(json_body("data", '!', limit + 1))).await;
+    assert_too_large(status, &body, limit);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_batch_generation_is_capped_by_the_batch_size() {
+    let (state, token) = signed_in_state().await;
+    let limit = generate_batch_body_limit(&state.zkml_service);
+    assert!(limit > generate_body_limit(&state.zkml_service));
+    assert!(limit < state.config.server.body_limits.default_bytes);
+    let app = create_router(state);
+
+    // Not a list of inputs, so it fails deserializing once read
+    let (status, _) = post(&app, "/api/v1/zkml/generate-batch", Some(&token), Body::from(json_body("inputs", 'a', limit))).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let (status, body) = post(&app, "/api/v1/zkml/generate-batch", Some(&token), Body::from(json_body("inputs", 'a', limit + 1))).await;
+    assert_too_large(status, &body, limit);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_other_routes_take_the_default_limit() {
+    let (state, token) = signed_in_state().await;
+    let limit = state.config.server.body_limits.default_bytes;
+    let app = create_router(state);
+
+    let (status, _) = post(&app, "/api/v1/transaction/decode", Some(&token), Body::from(json_body("padding", 'a', limit))).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let (status, body) = post(&app, "/api/v1/transaction/decode", Some(&token), Body::from(json_body("padding", 'a', limit + 1))).await;
+    assert_too_large(status, &body, limit);
+}
\ No newline at end of file
@@ -0,0 +1,185 @@
+//! Tests for changing the password of a logged-in user
+//!
+//! These tests need running Postgres and Redis instances and are ignored
+//! unless run with `--ignored` and both `DATABASE_URL` and `REDIS_URL` set.
+
+use axum::{
+    body::Body,
+    http::{header::AUTHORIZATION, Method, Request, StatusCode},
+    Router,
+};
+use guardian_aa_backend::{
+    api::{
+        create_router,
+        handlers::auth::{ChangePasswordRequest, LoginRequest, RefreshTokenRequest, RegisterRequest},
+        AppState,
+    },
+    db::queries::UserQueries,
+    error::Error,
+    services::AuthService,
+};
+use std::sync::Arc;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+mod common;
+
+const OLD_PASSWORD: &str = "correct horse battery";
+const NEW_PASSWORD: &str = "staple battery horse";
+const MAX_ATTEMPTS: u32 = 3;
+
+async fn test_state() -> Arc<AppState> {
+    let mut config = common::database_and_redis_config();
+    config.rate_limit.enabled = false;
+    config.auth.login_max_failed_attempts = MAX_ATTEMPTS;
+    config.auth.login_failure_window_secs = 60;
+    config.auth.login_lockout_secs = 60;
+    common::connect(config).await
+}
+
+/// Register a user, returning their id, email and the registration's
+/// refresh token
+async fn register(state: &Arc<AppState>) -> (Uuid, String, String) {
+    let email = format!("change-password-{}@example.com", Uuid::new_v4());
+    let response = AuthService::new(state.clone())
+        .register(RegisterRequest {
+            email: email.clone(),
+            password: OLD_PASSWORD.to_string(),
+            username: None,
+        })
+        .await
+        .unwrap();
+
+    let user = UserQueries::find_by_email(state.db.pool(), &email).await.unwrap().unwrap();
+    (user.id, email, response.refresh_token)
+}
+
+async fn login(state: &Arc<AppState>, email: &str, password: &str) -> Result<String, Error> {
+    AuthService::new(state.clone())
+        .login(LoginRequest { email: email.to_string(), password: password.to_string() })
+        .await
+        .map(|response| response.refresh_token)
+}
+
+async fn refresh(state: &Arc<AppState>, refresh_token: String) -> Result<String, Error> {
+    AuthService::new(state.clone())
+        .refresh_token(RefreshTokenRequest { refresh_token })
+        .await
+        .map(|response| response.refresh_token)
+}
+
+async fn change_password(app: &Router, access_token: &str, current_password: &str) -> StatusCode {
+    let body = serde_json::json!({ "current_password": current_password, "new_password": NEW_PASSWORD });
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/v1/auth/change-password")
+        .header(AUTHORIZATION, format!("Bearer {}", access_token))
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap().status()
+}
+
+async fn list_sessions(app: &Router, access_token: &str) -> StatusCode {
+    let request = Request::builder()
+        .uri("/api/v1/auth/sessions")
+        .header(AUTHORIZATION, format!("Bearer {}", access_token))
+        .body(Body::empty())
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap().status()
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_wrong_current_password_is_rejected() {
+    let state = test_state().await;
+    let (user_id, email, _) = register(&state).await;
+
+    let result = AuthService::new(state.clone())
+        .change_password(user_id, None, ChangePasswordRequest {
+            current_password: "not my password".to_string(),
+            new_password: NEW_PASSWORD.to_string(),
+            refresh_token: None,
+        })
+        .await;
+
+    assert!(matches!(result, Err(Error::BadRequest(_))));
+    assert!(login(&state, &email, OLD_PASSWORD).await.is_ok());
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_change_password_replaces_password() {
+    let state = test_state().await;
+    let (user_id, email, _) = register(&state).await;
+
+    AuthService::new(state.clone())
+        .change_password(user_id, None, ChangePasswordRequest {
+            current_password: OLD_PASSWORD.to_string(),
+            new_password: NEW_PASSWORD.to_string(),
+            refresh_token: None,
+        })
+        .await
+        .unwrap();
+
+    assert!(matches!(login(&state, &email, OLD_PASSWORD).await, Err(Error::AuthenticationFailed)));
+    assert!(login(&state, &email, NEW_PASSWORD).await.is_ok());
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_change_password_revokes_other_sessions() {
+    let state = test_state().await;
+    let (user_id, email, current_session) = register(&state).await;
+    let other_session = login(&state, &email, OLD_PASSWORD).await.unwrap();
+
+    AuthService::new(state.clone())
+        .change_password(user_id, None, ChangePasswordRequest {
+            current_password: OLD_PASSWORD.to_string(),
+            new_password: NEW_PASSWORD.to_string(),
+            refresh_token: Some(current_session.clone()),
+        })
+        .await
+        .unwrap();
+
+    assert!(matches!(refresh(&state, other_session).await, Err(Error::AuthenticationFailed)));
+    assert!(refresh(&state, current_session).await.is_ok());
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_wrong_current_passwords_lock_out() {
+    let state = test_state().await;
+    let (_, email, _) = register(&state).await;
+    let access_token = AuthService::new(state.clone())
+        .login(LoginRequest { email: email.clone(), password: OLD_PASSWORD.to_string() })
+        .await
+        .unwrap()
+        .access_token;
+
+    let app = create_router(state.clone());
+    for _ in 0..MAX_ATTEMPTS {
+        assert_eq!(change_password(&app, &access_token, "not my password").await, StatusCode::BAD_REQUEST);
+    }
+
+    // Guessing stops, with the right password too, and so does logging in
+    assert_eq!(change_password(&app, &access_token, OLD_PASSWORD).await, StatusCode::TOO_MANY_REQUESTS);
+    assert!(matches!(login(&state, &email, OLD_PASSWORD).await, Err(Error::AccountLocked { .. })));
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_change_password_revokes_other_access_tokens() {
+    let state = test_state().await;
+    let (_, email, _) = register(&state).await;
+    let auth_service = AuthService::new(state.clone());
+    let login_request = || LoginRequest { email: email.clone(), password: OLD_PASSWORD.to_string() };
+    let current = auth_service.login(login_request()).await.unwrap().access_token;
+    let other = auth_service.login(login_request()).await.unwrap().access_token;
+
+    let app = create_router(state.clone());
+    assert_eq!(change_password(&app, &current, OLD_PASSWORD).await, StatusCode::OK);
+
+    assert_eq!(list_sessions(&app, &current).await, StatusCode::OK);
+    assert_eq!(list_sessions(&app, &other).await, StatusCode::UNAUTHORIZED);
+}
\ No newline at end of file
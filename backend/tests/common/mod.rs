@@ -0,0 +1,87 @@
+//! Fixtures shared by the integration tests
+//!
+//! Each test binary pulls this in with `mod common;` and uses only part of
+//! it, hence the `dead_code` allowance.
+#![allow(dead_code)]
+
+use guardian_aa_backend::{
+    api::AppState,
+    auth::AccountStatuses,
+    blockchain::{EthereumClient, SolanaClient},
+    config::Config,
+    db::Database,
+    inference::{ModelLoader, ModelRegistry},
+    services::ProofJobQueue,
+    zkml::ZkmlService,
+};
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
+
+/// App state around `db`, with the clients built from `config` and the
+/// rest at its defaults. Tests that need a different part swap it in with
+/// struct update syntax.
+pub fn app_state(db: Database, config: Config) -> AppState {
+    AppState {
+        account_statuses: AccountStatuses::new(db.clone(), config.auth.account_status_cache_secs),
+        db,
+        redis: redis::Client::open(config.redis.url.clone()).unwrap(),
+        solana_client: SolanaClient::new(&config.blockchain.solana_rpc_url, &config.blockchain.commitment).unwrap(),
+        ethereum_client: EthereumClient::new(&config.blockchain.ethereum.rpc_url).unwrap(),
+        zkml_service: ZkmlService::new().unwrap(),
+        proof_jobs: ProofJobQueue::from_config(&config.zkml),
+        model_registry: ModelRegistry::new(ModelLoader::new(&config.models)),
+        request_metrics: Default::default(),
+        draining: Default::default(),
+        maintenance: Default::default(),
+        transaction_events: Default::default(),
+        token_registry: Default::default(),
+        websocket_shutdown: Default::default(),
+        readiness: Default::default(),
+        config,
+    }
+}
+
+/// A database whose pool only connects when a query first needs it
+pub fn lazy_database(config: &Config) -> Database {
+    let pool = PgPoolOptions::new().connect_lazy(&config.database.url).unwrap();
+    Database::from_pools(pool, None)
+}
+
+/// App state for tests that never reach the database
+pub fn lazy_state(config: Config) -> Arc<AppState> {
+    Arc::new(app_state(lazy_database(&config), config))
+}
+
+/// The value of the environment variable `name`. Tests that need a live
+/// service are `#[ignore]`d, so getting here without one is a setup error
+/// rather than a reason to skip.
+pub fn required_env(name: &str) -> String {
+    std::env::var(name).unwrap_or_else(|_| panic!("{} must be set to run this test", name))
+}
+
+/// The default config pointed at `DATABASE_URL`
+pub fn database_config() -> Config {
+    let mut config = Config::default();
+    config.database.url = required_env("DATABASE_URL");
+    config
+}
+
+/// As `database_config`, also pointed at `REDIS_URL`
+pub fn database_and_redis_config() -> Config {
+    let mut config = database_config();
+    config.redis.url = required_env("REDIS_URL");
+    config
+}
+
+/// Connect to the configured database, apply the migrations and build the
+/// app state around it
+pub async fn connect(config: Config) -> Arc<AppState> {
+    let db = Database::new(&config.database).await.expect("Failed to connect to database");
+    db.run_migrations().await.expect("Failed to run migrations");
+    Arc::new(app_state(db, config))
+}
+
+/// App state on `DATABASE_URL` with the default config
+pub async fn test_state() -> Arc<AppState> {
+    connect(database_config()).await
+}
\ No newline at end of file
@@ -0,0 +1,78 @@
+//! Tests for CORS with several allowed origins
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{
+        header::{ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_REQUEST_METHOD, ORIGIN},
+        Method,
+    },
+    routing::get,
+    Router,
+};
+use guardian_aa_backend::{config::Config, server::cors_layer};
+use tower::ServiceExt;
+
+const WEB_APP: &str = "https://app.guardian.example";
+const MOBILE_DEV: &str = "http://localhost:8081";
+
+fn app() -> Router {
+    let mut config = Config::default();
+    config.server.cors_origins = vec![WEB_APP.to_string(), MOBILE_DEV.to_string()];
+
+    Router::new()
+        .route("/health", get(|| async { "ok" }))
+        .layer(cors_layer(&config.server).unwrap())
+}
+
+async fn allowed_origin(request: Request) -> Option<String> {
+    let response = app().oneshot(request).await.unwrap();
+    response
+        .headers()
+        .get(ACCESS_CONTROL_ALLOW_ORIGIN)
+        .map(|value| value.to_str().unwrap().to_string())
+}
+
+fn request_from(origin: &str) -> Request {
+    Request::builder().uri("/health").header(ORIGIN, origin).body(Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn test_each_configured_origin_is_allowed() {
+    for origin in [WEB_APP, MOBILE_DEV] {
+        assert_eq!(allowed_origin(request_from(origin)).await.as_deref(), Some(origin));
+    }
+}
+
+#[tokio::test]
+async fn test_other_origins_get_no_cors_headers() {
+    assert_eq!(allowed_origin(request_from("https://evil.example")).await, None);
+    // Near misses don't count
+    assert_eq!(allowed_origin(request_from("http://localhost:3000")).await, None);
+    assert_eq!(allowed_origin(request_from("https://app.guardian.example.evil.example")).await, None);
+}
+
+#[tokio::test]
+async fn test_preflight_allows_credentials_for_the_requesting_origin() {
+    let request = Request::builder()
+        .method(Method::OPTIONS)
+        .uri("/health")
+        .header(ORIGIN, MOBILE_DEV)
+        .header(ACCESS_CONTROL_REQUEST_METHOD, "POST")
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app().oneshot(request).await.unwrap();
+
+    // Never a wildcard: browsers refuse one on credentialed requests
+    assert_eq!(response.headers()[ACCESS_CONTROL_ALLOW_ORIGIN], MOBILE_DEV);
+    assert_eq!(response.headers()[ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+}
+
+#[test]
+fn test_unparseable_origin_is_a_config_error() {
+    let mut config = Config::default();
+    config.server.cors_origins = vec!["https://app.example.com\n".to_string()];
+
+    assert!(matches!(cors_layer(&config.server), Err(guardian_aa_backend::Error::Config(_))));
+}
\ No newline at end of file
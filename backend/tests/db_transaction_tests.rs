@@ -0,0 +1,75 @@
+//! Tests for the database transaction helper
+//!
+//! These tests need a running Postgres instance and are ignored unless run
+//! with `--ignored` and `DATABASE_URL` set.
+
+use guardian_aa_backend::{
+    db::{queries::UserQueries, Database},
+    error::Error,
+};
+use uuid::Uuid;
+
+mod common;
+
+async fn connect() -> Database {
+    let config = common::database_config();
+    let db = Database::new(&config.database).await.expect("Failed to connect to database");
+    db.run_migrations().await.expect("Failed to run migrations");
+    db
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_transaction_rolls_back_on_mid_flow_failure() {
+    let db = connect().await;
+    let email = format!("rollback-{}@example.com", Uuid::new_v4());
+
+    let result: Result<(), Error> = db
+        .transaction(|conn| {
+            let email = email.clone();
+            Box::pin(async move {
+                sqlx::query("INSERT INTO users (email, password_hash) VALUES ($1, 'hash')")
+                    .bind(&email)
+                    .execute(&mut *conn)
+                    .await?;
+
+                // Simulate a failure after the first write
+                Err(Error::Internal)
+            })
+        })
+        .await;
+
+    assert!(matches!(result, Err(Error::Internal)));
+
+    let user = UserQueries::find_by_email(db.pool(), &email).await.unwrap();
+    assert!(user.is_none());
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_transaction_commits_on_success() {
+    let db = connect().await;
+    let email = format!("commit-{}@example.com", Uuid::new_v4());
+
+    db.transaction(|conn| {
+        let email = email.clone();
+        Box::pin(async move {
+            sqlx::query("INSERT INTO users (email, password_hash) VALUES ($1, 'hash')")
+                .bind(&email)
+                .execute(&mut *conn)
+                .await?;
+            Ok(())
+        })
+    })
+    .await
+    .unwrap();
+
+    let user = UserQueries::find_by_email(db.pool(), &email).await.unwrap();
+    assert!(user.is_some());
+
+    sqlx::query("DELETE FROM users WHERE email = $1")
+        .bind(&email)
+        .execute(db.pool())
+        .await
+        .unwrap();
+}
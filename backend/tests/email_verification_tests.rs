@@ -0,0 +1,135 @@
+//! Tests for email verification
+//!
+//! These tests need a running Postgres instance and are ignored unless run
+//! with `--ignored` and `DATABASE_URL` set.
+
+use guardian_aa_backend::{
+    api::{
+        handlers::auth::{LoginRequest, RegisterRequest, VerifyEmailRequest},
+        AppState,
+    },
+    db::queries::UserQueries,
+    error::{Error, Result},
+    services::{email::SendFuture, AuthService, EmailSender},
+};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+mod common;
+
+/// Keeps the last verification token instead of sending it
+#[derive(Default)]
+struct CapturingSender {
+    token: Mutex<Option<String>>,
+}
+
+impl CapturingSender {
+    fn token(&self) -> String {
+        self.token.lock().unwrap().clone().expect("no verification email was sent")
+    }
+}
+
+impl EmailSender for CapturingSender {
+    fn send_verification_email<'a>(&'a self, _email: &'a str, token: &'a str) -> SendFuture<'a> {
+        Box::pin(async move {
+            *self.token.lock().unwrap() = Some(token.to_string());
+            Ok(())
+        })
+    }
+
+    fn send_password_reset_email<'a>(&'a self, _email: &'a str, _token: &'a str) -> SendFuture<'a> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+async fn test_state(require_verification: bool) -> Arc<AppState> {
+    let mut config = common::database_config();
+    config.auth.require_email_verification = require_verification;
+    common::connect(config).await
+}
+
+/// Register a fresh user, returning their email and the token they were sent
+async fn register(state: &Arc<AppState>) -> (String, String) {
+    let sender = Arc::new(CapturingSender::default());
+    let email = format!("verify-{}@example.com", Uuid::new_v4());
+
+    AuthService::new(state.clone())
+        .with_email_sender(sender.clone())
+        .register(RegisterRequest {
+            email: email.clone(),
+            password: "correct horse battery".to_string(),
+            username: None,
+        })
+        .await
+        .unwrap();
+
+    (email, sender.token())
+}
+
+async fn verify(state: &Arc<AppState>, token: &str) -> Result<()> {
+    AuthService::new(state.clone())
+        .verify_email(VerifyEmailRequest { token: token.to_string() })
+        .await
+}
+
+async fn is_verified(state: &AppState, email: &str) -> bool {
+    UserQueries::find_by_email(state.db.pool(), email).await.unwrap().unwrap().email_verified
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_valid_token_verifies_account() {
+    let state = test_state(false).await;
+    let (email, token) = register(&state).await;
+    assert!(!is_verified(&state, &email).await);
+
+    verify(&state, &token).await.unwrap();
+
+    assert!(is_verified(&state, &email).await);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_expired_token_is_rejected() {
+    let state = test_state(false).await;
+    let (email, token) = register(&state).await;
+
+    sqlx::query(
+        "UPDATE email_verification_tokens SET expires_at = NOW() - INTERVAL '1 minute'
+         WHERE user_id = (SELECT id FROM users WHERE email = $1)",
+    )
+        .bind(&email)
+        .execute(state.db.pool())
+        .await
+        .unwrap();
+
+    assert!(matches!(verify(&state, &token).await, Err(Error::BadRequest(_))));
+    assert!(!is_verified(&state, &email).await);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_token_cannot_be_reused() {
+    let state = test_state(false).await;
+    let (_, token) = register(&state).await;
+
+    verify(&state, &token).await.unwrap();
+
+    assert!(matches!(verify(&state, &token).await, Err(Error::BadRequest(_))));
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_unverified_login_rejected_when_required() {
+    let state = test_state(true).await;
+    let (email, token) = register(&state).await;
+    let login = || AuthService::new(state.clone()).login(LoginRequest {
+        email: email.clone(),
+        password: "correct horse battery".to_string(),
+    });
+
+    assert!(matches!(login().await, Err(Error::Forbidden)));
+
+    verify(&state, &token).await.unwrap();
+    assert!(login().await.is_ok());
+}
\ No newline at end of file
@@ -0,0 +1,134 @@
+//! Tests for the weighted ensemble vote over agent predictions
+
+use chrono::Utc;
+use guardian_aa_backend::{
+    config::AgentWeights,
+    db::models::{Agent, AgentType, PredictionType},
+    services::{
+        agent::AgentPredictionResult,
+        ensemble::{aggregate_predictions, ExclusionReason},
+    },
+};
+use uuid::Uuid;
+
+fn agent(agent_type: AgentType, confidence_threshold: f64) -> Agent {
+    Agent {
+        id: Uuid::new_v4(),
+        name: format!("{:?} agent", agent_type),
+        agent_type,
+        description: String::new(),
+        model_version: "1.0.0".to_string(),
+        circuit_hash: None,
+        is_active: true,
+        confidence_threshold,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn prediction(agent: &Agent, prediction: PredictionType, confidence: f64) -> AgentPredictionResult {
+    AgentPredictionResult {
+        agent_id: agent.id,
+        agent_name: agent.name.clone(),
+        agent_type: agent.agent_type.clone(),
+        prediction,
+        confidence,
+        reasoning: String::new(),
+    }
+}
+
+#[test]
+fn test_low_confidence_agent_is_excluded() {
+    let news = agent(AgentType::NewsSentiment, 0.6);
+    let technical = agent(AgentType::TechnicalAnalysis, 0.7);
+    let crypto = agent(AgentType::CryptoFactor, 0.5);
+    let agents = [news.clone(), technical.clone(), crypto.clone()];
+
+    // Two bearish votes would win, but the technical agent isn't confident
+    // enough by its own threshold
+    let predictions = [
+        prediction(&news, PredictionType::Bullish, 0.8),
+        prediction(&technical, PredictionType::Bearish, 0.65),
+        prediction(&crypto, PredictionType::Bearish, 0.55),
+    ];
+
+    let result = aggregate_predictions(&agents, &predictions, &AgentWeights::default()).unwrap();
+
+    assert_eq!(result.prediction, PredictionType::Bullish);
+    assert_eq!(result.agent_count, 2);
+    assert!((result.confidence - 0.675).abs() < 1e-9);
+    assert!((result.consensus_strength - 0.5).abs() < 1e-9);
+
+    let included: Vec<Uuid> = result.included.iter().map(|member| member.agent_id).collect();
+    assert_eq!(included, vec![news.id, crypto.id]);
+    assert_eq!(result.excluded.len(), 1);
+    assert_eq!(result.excluded[0].agent_id, technical.id);
+    assert_eq!(
+        result.excluded[0].reason,
+        ExclusionReason::BelowConfidenceThreshold { confidence: 0.65, threshold: 0.7 }
+    );
+}
+
+#[test]
+fn test_weighting_flips_the_majority() {
+    let news = agent(AgentType::NewsSentiment, 0.5);
+    let market = agent(AgentType::MarketFactor, 0.5);
+    let crypto = agent(AgentType::CryptoFactor, 0.5);
+    let agents = [news.clone(), market.clone(), crypto.clone()];
+    let predictions = [
+        prediction(&news, PredictionType::Bullish, 0.8),
+        prediction(&market, PredictionType::Bullish, 0.8),
+        prediction(&crypto, PredictionType::Bearish, 0.9),
+    ];
+
+    // Equal weights: two bullish agents outvote one bearish
+    let equal = aggregate_predictions(&agents, &predictions, &AgentWeights::default()).unwrap();
+    assert_eq!(equal.prediction, PredictionType::Bullish);
+
+    // A trusted crypto factor agent outweighs both
+    let weights = AgentWeights { crypto_factor: 3.0, ..AgentWeights::default() };
+    let weighted = aggregate_predictions(&agents, &predictions, &weights).unwrap();
+    assert_eq!(weighted.prediction, PredictionType::Bearish);
+    assert!((weighted.confidence - (0.8 + 0.8 + 2.7) / 5.0).abs() < 1e-9);
+    assert_eq!(weighted.included.iter().find(|m| m.agent_id == crypto.id).unwrap().weight, 3.0);
+
+    // Consensus still counts agents, not weights
+    assert_eq!(equal.consensus_strength, weighted.consensus_strength);
+    assert!((weighted.consensus_strength - 2.0 / 3.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_zero_weight_leaves_agent_type_out() {
+    let news = agent(AgentType::NewsSentiment, 0.5);
+    let market = agent(AgentType::MarketFactor, 0.5);
+    let agents = [news.clone(), market.clone()];
+    let predictions = [
+        prediction(&news, PredictionType::Bearish, 0.9),
+        prediction(&market, PredictionType::Bullish, 0.6),
+    ];
+
+    let weights = AgentWeights { news_sentiment: 0.0, ..AgentWeights::default() };
+    let result = aggregate_predictions(&agents, &predictions, &weights).unwrap();
+
+    assert_eq!(result.prediction, PredictionType::Bullish);
+    assert_eq!(result.agent_count, 1);
+    assert_eq!(result.excluded[0].reason, ExclusionReason::ZeroWeight);
+}
+
+#[test]
+fn test_every_agent_excluded_is_neutral() {
+    let technical = agent(AgentType::TechnicalAnalysis, 0.7);
+    let predictions = [prediction(&technical, PredictionType::Bullish, 0.4)];
+
+    let result = aggregate_predictions(&[technical], &predictions, &AgentWeights::default()).unwrap();
+
+    assert_eq!(result.prediction, PredictionType::Neutral);
+    assert_eq!(result.confidence, 0.0);
+    assert_eq!(result.agent_count, 0);
+    assert!(result.included.is_empty());
+}
+
+#[test]
+fn test_no_predictions_is_an_error() {
+    assert!(aggregate_predictions(&[], &[], &AgentWeights::default()).is_err());
+}
\ No newline at end of file
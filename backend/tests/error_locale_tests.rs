@@ -0,0 +1,67 @@
+//! Tests for localized error messages
+
+use axum::{
+    body::Body,
+    http::{header::ACCEPT_LANGUAGE, Request, StatusCode},
+    routing::get,
+    Router,
+};
+use guardian_aa_backend::{api::middleware::locale::locale_middleware, i18n::Locale, Error};
+use tower::ServiceExt;
+
+fn app() -> Router {
+    Router::new()
+        .route("/missing", get(|| async { Err::<(), _>(Error::NotFound) }))
+        .route("/invalid", get(|| async { Err::<(), _>(Error::Validation("amount".to_string())) }))
+        .layer(axum::middleware::from_fn(locale_middleware))
+}
+
+async fn error_body(uri: &str, accept_language: Option<&str>) -> (StatusCode, serde_json::Value) {
+    let mut request = Request::builder().uri(uri);
+    if let Some(accept_language) = accept_language {
+        request = request.header(ACCEPT_LANGUAGE, accept_language);
+    }
+
+    let response = app().oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn test_supported_locale_is_localized() {
+    let (status, body) = error_body("/missing", Some("es-ES,es;q=0.9,en;q=0.5")).await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["message"], "Recurso no encontrado");
+    // Machine-readable fields don't change with the locale
+    assert_eq!(body["code"], "not_found");
+    assert_eq!(body["error"], "Resource not found");
+}
+
+#[tokio::test]
+async fn test_localized_message_keeps_detail() {
+    let (status, body) = error_body("/invalid", Some("es")).await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["message"], "Error de validación: amount");
+    assert_eq!(body["type"], "validation_error");
+}
+
+#[tokio::test]
+async fn test_unsupported_locale_falls_back_to_english() {
+    let (_, body) = error_body("/missing", Some("fr-FR,de;q=0.8")).await;
+    assert_eq!(body["message"], "Not found");
+
+    let (_, body) = error_body("/missing", None).await;
+    assert_eq!(body["message"], "Not found");
+    assert_eq!(body["code"], "not_found");
+}
+
+#[test]
+fn test_accept_language_weights() {
+    assert_eq!(Locale::from_accept_language("en;q=0.4, es;q=0.8"), Locale::Es);
+    assert_eq!(Locale::from_accept_language("fr, en-GB;q=0.7, es;q=0.5"), Locale::En);
+    assert_eq!(Locale::from_accept_language("es;q=0"), Locale::En);
+    assert_eq!(Locale::from_accept_language(""), Locale::En);
+}
\ No newline at end of file
@@ -0,0 +1,146 @@
+//! Tests for the Ethereum client against a mock JSON-RPC node
+
+use axum::{routing::post, Json, Router};
+use ethers::types::{transaction::eip2718::TypedTransaction, Address, TransactionRequest, U256};
+use guardian_aa_backend::blockchain::{
+    ethereum::{format_amount, format_wei},
+    EthTransactionState, EthereumClient, PriorityLevel,
+};
+
+const OWNER: &str = "0x742d35cc6634c0532925a3b8d4c9db96c4b4df8a";
+const TOKEN: &str = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+const TX_HASH: &str = "0x88df016429689c079f3b2f6ad39fa052532c56795b733da78a91ebe6a713944b";
+
+fn uint_word(value: u64) -> String {
+    format!("0x{:064x}", value)
+}
+
+/// JSON-RPC node with a fixed chain state
+async fn handle_rpc(Json(request): Json<serde_json::Value>) -> Json<serde_json::Value> {
+    let params = &request["params"];
+    let result = match request["method"].as_str().unwrap_or_default() {
+        // 1.5 ether
+        "eth_getBalance" => serde_json::json!("0x14d1120d7b160000"),
+        "eth_call" => {
+            let data = params[0]["data"].as_str().or_else(|| params[0]["input"].as_str()).unwrap_or_default();
+            match &data[..10] {
+                "0x70a08231" => serde_json::json!(uint_word(1_234_500)),
+                "0x313ce567" => serde_json::json!(uint_word(6)),
+                selector => panic!("unexpected selector {}", selector),
+            }
+        }
+        "eth_estimateGas" => serde_json::json!("0x5208"),
+        "eth_feeHistory" => serde_json::json!({
+            "oldestBlock": "0x10",
+            // The last entry is the next block's base fee, 2 gwei
+            "baseFeePerGas": ["0x3b9aca00", "0x3b9aca00", "0x77359400", "0x77359400"],
+            "gasUsedRatio": [0.5, 0.9, 0.5],
+            // 1.5, 2 and 3 gwei
+            "reward": [["0x59682f00"], ["0x77359400"], ["0xb2d05e00"]]
+        }),
+        "eth_getTransactionReceipt" => serde_json::json!({
+            "transactionHash": TX_HASH,
+            "transactionIndex": "0x0",
+            "blockHash": "0x1d59ff54b1eb26b013ce3cb5fc9dab3705b415a67127a003c3e61eb445bb8df2",
+            "blockNumber": "0x10",
+            "from": OWNER,
+            "to": TOKEN,
+            "cumulativeGasUsed": "0x5208",
+            "gasUsed": "0x5208",
+            "contractAddress": null,
+            "logs": [],
+            "logsBloom": format!("0x{}", "0".repeat(512)),
+            "status": "0x0",
+            "type": "0x2",
+            "effectiveGasPrice": "0x77359400"
+        }),
+        "eth_blockNumber" => serde_json::json!("0x12"),
+        method => panic!("unexpected RPC method {}", method),
+    };
+
+    Json(serde_json::json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+}
+
+async fn start_mock_rpc() -> String {
+    let app = Router::new().route("/", post(handle_rpc));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    url
+}
+
+#[test]
+fn test_wei_formatting() {
+    assert_eq!(format_wei(U256::from(1_500_000_000_000_000_000u64)), "1.5");
+    assert_eq!(format_wei(U256::from(1u64)), "0.000000000000000001");
+    assert_eq!(format_wei(U256::zero()), "0");
+    assert_eq!(format_wei(U256::exp10(18) * 1000), "1000");
+    assert_eq!(format_amount(U256::from(1_234_500u64), 6), "1.2345");
+}
+
+#[tokio::test]
+async fn test_balance_is_formatted_in_ether() {
+    let client = EthereumClient::new(&start_mock_rpc().await).unwrap();
+
+    let balance = client.get_balance(OWNER).await.unwrap();
+
+    assert_eq!(balance.wei, "1500000000000000000");
+    assert_eq!(balance.ether, "1.5");
+}
+
+#[tokio::test]
+async fn test_token_balance_uses_token_decimals() {
+    let client = EthereumClient::new(&start_mock_rpc().await).unwrap();
+
+    let balance = client.get_token_balance(TOKEN, OWNER).await.unwrap();
+
+    assert_eq!(balance.contract, TOKEN);
+    assert_eq!(balance.amount, "1234500");
+    assert_eq!(balance.decimals, 6);
+    assert_eq!(balance.amount_formatted, "1.2345");
+}
+
+#[tokio::test]
+async fn test_fee_estimate_follows_eip1559() {
+    let client = EthereumClient::new(&start_mock_rpc().await).unwrap();
+    let transfer: TypedTransaction = TransactionRequest::new()
+        .from(OWNER.parse::<Address>().unwrap())
+        .to(TOKEN.parse::<Address>().unwrap())
+        .value(1_000u64)
+        .into();
+
+    let estimate = client.estimate_fee(&transfer, PriorityLevel::Medium).await.unwrap();
+
+    assert_eq!(estimate.gas_limit, 21_000);
+    assert_eq!(estimate.base_fee_per_gas, "2000000000");
+    // Median of the recent tips
+    assert_eq!(estimate.max_priority_fee_per_gas, "2000000000");
+    // Twice the base fee plus the tip
+    assert_eq!(estimate.max_fee_per_gas, "6000000000");
+    assert_eq!(estimate.max_fee_wei, "126000000000000");
+    assert_eq!(estimate.max_fee_ether, "0.000126");
+}
+
+#[tokio::test]
+async fn test_reverted_transaction_reports_failure() {
+    let client = EthereumClient::new(&start_mock_rpc().await).unwrap();
+
+    let status = client.get_transaction_status(TX_HASH).await.unwrap().unwrap();
+
+    assert_eq!(status.state, EthTransactionState::Failed);
+    assert_eq!(status.block_number, Some(0x10));
+    assert_eq!(status.confirmations, 3);
+}
+
+#[test]
+fn test_address_validation() {
+    let client = EthereumClient::new("http://127.0.0.1:8545").unwrap();
+
+    assert!(client.validate_address(OWNER).unwrap());
+    assert!(!client.validate_address("742d35cc6634c0532925a3b8d4c9db96c4b4df8a").unwrap());
+    assert!(!client.validate_address("0xinvalid").unwrap());
+}
\ No newline at end of file
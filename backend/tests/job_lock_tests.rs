@@ -0,0 +1,124 @@
+//! Tests for keeping background jobs to one instance with a Redis lock
+//!
+//! These need a running Redis instance and are ignored unless run with
+//! `--ignored` and `REDIS_URL` set.
+
+use guardian_aa_backend::services::JobLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Barrier;
+use uuid::Uuid;
+
+fn redis_client() -> redis::Client {
+    let url = std::env::var("REDIS_URL").expect("REDIS_URL must be set to run this test");
+    redis::Client::open(url).unwrap()
+}
+
+/// A lock key no other test or run uses
+fn unique_key() -> String {
+    format!("guardian:lock:test:{}", Uuid::new_v4())
+}
+
+#[tokio::test]
+#[ignore = "needs REDIS_URL"]
+async fn test_only_one_contending_worker_runs() {
+    let client = redis_client();
+    let key = unique_key();
+    let runs = Arc::new(AtomicUsize::new(0));
+    let start = Arc::new(Barrier::new(2));
+
+    let workers: Vec<_> = (0..2)
+        .map(|_| {
+            let lock = JobLock::new(client.clone(), key.clone(), 30);
+            let runs = runs.clone();
+            let start = start.clone();
+            tokio::spawn(async move {
+                start.wait().await;
+                lock.run_exclusive(async {
+                    runs.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(300)).await;
+                })
+                .await
+                .unwrap()
+            })
+        })
+        .collect();
+
+    let mut ran = 0;
+    for worker in workers {
+        if worker.await.unwrap().is_some() {
+            ran += 1;
+        }
+    }
+    assert_eq!(ran, 1);
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+    // Released once the job finished
+    let later = JobLock::new(client, key, 30);
+    assert!(later.run_exclusive(async {}).await.unwrap().is_some());
+}
+
+#[tokio::test]
+#[ignore = "needs REDIS_URL"]
+async fn test_lock_is_only_released_by_its_holder() {
+    let client = redis_client();
+    let key = unique_key();
+    let holder = JobLock::new(client.clone(), key.clone(), 30);
+    let other = JobLock::new(client, key, 30);
+
+    assert!(holder.acquire().await.unwrap());
+    // Taking it again renews it for the holder, but not for anyone else
+    assert!(holder.acquire().await.unwrap());
+    assert!(!other.acquire().await.unwrap());
+
+    assert!(!other.release().await.unwrap());
+    assert!(!other.renew().await.unwrap());
+    assert!(holder.renew().await.unwrap());
+
+    assert!(holder.release().await.unwrap());
+    assert!(other.acquire().await.unwrap());
+    assert!(other.release().await.unwrap());
+}
+
+#[tokio::test]
+#[ignore = "needs REDIS_URL"]
+async fn test_lock_lapses_without_renewal() {
+    let client = redis_client();
+    let key = unique_key();
+    let holder = JobLock::new(client.clone(), key.clone(), 1);
+    let other = JobLock::new(client, key, 1);
+
+    assert!(holder.acquire().await.unwrap());
+    tokio::time::sleep(Duration::from_millis(1200)).await;
+
+    assert!(other.acquire().await.unwrap());
+    // The old holder can neither extend nor free the new holder's lock
+    assert!(!holder.renew().await.unwrap());
+    assert!(!holder.release().await.unwrap());
+    assert!(other.release().await.unwrap());
+}
+
+#[tokio::test]
+#[ignore = "needs REDIS_URL"]
+async fn test_lock_is_renewed_while_the_job_runs() {
+    let client = redis_client();
+    let key = unique_key();
+    let holder = JobLock::new(client.clone(), key.clone(), 1);
+    let other = JobLock::new(client, key, 1);
+
+    // The job outlasts the TTL twice over, and is still the only one running
+    let output = holder
+        .run_exclusive(async {
+            tokio::time::sleep(Duration::from_millis(1500)).await;
+            let contended = other.acquire().await.unwrap();
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            contended
+        })
+        .await
+        .unwrap();
+    assert_eq!(output, Some(false));
+
+    assert!(other.acquire().await.unwrap());
+    other.release().await.unwrap();
+}
\ No newline at end of file
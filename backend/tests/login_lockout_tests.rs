@@ -0,0 +1,121 @@
+//! Tests for locking accounts out after repeated failed logins
+//!
+//! The login tests need running Postgres and Redis instances and are
+//! ignored unless run with `--ignored` and both `DATABASE_URL` and
+//! `REDIS_URL` set.
+
+use axum::{
+    http::{header::RETRY_AFTER, StatusCode},
+    response::IntoResponse,
+};
+use guardian_aa_backend::{
+    api::{
+        handlers::auth::{LoginRequest, RegisterRequest},
+        AppState,
+    },
+    error::Error,
+    services::AuthService,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+mod common;
+
+const MAX_ATTEMPTS: u32 = 3;
+const LOCKOUT_SECS: u64 = 1;
+const PASSWORD: &str = "Password123";
+
+async fn test_state() -> Arc<AppState> {
+    let mut config = common::database_and_redis_config();
+    config.auth.login_max_failed_attempts = MAX_ATTEMPTS;
+    config.auth.login_failure_window_secs = 60;
+    config.auth.login_lockout_secs = LOCKOUT_SECS;
+    common::connect(config).await
+}
+
+async fn register(auth: &AuthService) -> String {
+    let email = format!("lockout-{}@example.com", Uuid::new_v4());
+    auth.register(RegisterRequest { email: email.clone(), password: PASSWORD.to_string(), username: None })
+        .await
+        .unwrap();
+    email
+}
+
+async fn login(auth: &AuthService, email: &str, password: &str) -> Result<(), Error> {
+    auth.login(LoginRequest { email: email.to_string(), password: password.to_string() })
+        .await
+        .map(|_| ())
+}
+
+#[test]
+fn test_account_locked_response_has_retry_after() {
+    let response = Error::AccountLocked { retry_after_secs: 30 }.into_response();
+
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()[RETRY_AFTER], "30");
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_lockout_after_threshold() {
+    let state = test_state().await;
+    let auth = AuthService::new(state);
+    let email = register(&auth).await;
+
+    for _ in 0..MAX_ATTEMPTS {
+        assert!(matches!(login(&auth, &email, "WrongPassword1").await, Err(Error::AuthenticationFailed)));
+    }
+
+    // Even the right password is refused while locked out
+    let result = login(&auth, &email, PASSWORD).await;
+    assert!(matches!(result, Err(Error::AccountLocked { retry_after_secs }) if retry_after_secs <= LOCKOUT_SECS));
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_lockout_expires_after_cooldown() {
+    let state = test_state().await;
+    let auth = AuthService::new(state);
+    let email = register(&auth).await;
+
+    for _ in 0..MAX_ATTEMPTS {
+        let _ = login(&auth, &email, "WrongPassword1").await;
+    }
+    assert!(matches!(login(&auth, &email, PASSWORD).await, Err(Error::AccountLocked { .. })));
+
+    tokio::time::sleep(Duration::from_millis(LOCKOUT_SECS * 1000 + 200)).await;
+    login(&auth, &email, PASSWORD).await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_successful_login_clears_failures() {
+    let state = test_state().await;
+    let auth = AuthService::new(state);
+    let email = register(&auth).await;
+
+    for _ in 0..MAX_ATTEMPTS - 1 {
+        let _ = login(&auth, &email, "WrongPassword1").await;
+    }
+    login(&auth, &email, PASSWORD).await.unwrap();
+
+    // The earlier failures no longer count towards the limit
+    for _ in 0..MAX_ATTEMPTS - 1 {
+        assert!(matches!(login(&auth, &email, "WrongPassword1").await, Err(Error::AuthenticationFailed)));
+    }
+    login(&auth, &email, PASSWORD).await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_unknown_email_locks_out_like_a_real_one() {
+    let state = test_state().await;
+    let auth = AuthService::new(state);
+    let email = format!("nobody-{}@example.com", Uuid::new_v4());
+
+    for _ in 0..MAX_ATTEMPTS {
+        assert!(matches!(login(&auth, &email, PASSWORD).await, Err(Error::AuthenticationFailed)));
+    }
+    assert!(matches!(login(&auth, &email, PASSWORD).await, Err(Error::AccountLocked { .. })));
+}
\ No newline at end of file
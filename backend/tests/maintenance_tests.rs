@@ -0,0 +1,111 @@
+//! Tests for maintenance mode
+
+use axum::{
+    body::Body,
+    http::{header::RETRY_AFTER, Method, Request, StatusCode},
+    response::Response,
+};
+use guardian_aa_backend::{
+    api::{create_router, AppState},
+    config::{Config, MaintenanceConfig, MaintenanceScope},
+};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+mod common;
+
+/// App state whose connections are only opened on first use, so no
+/// database or Redis is needed
+fn lazy_state() -> Arc<AppState> {
+    let mut config = Config::default();
+    config.rate_limit.enabled = false;
+    common::lazy_state(config)
+}
+
+fn enable(state: &AppState, scope: MaintenanceScope) {
+    state.maintenance.set(MaintenanceConfig {
+        enabled: true,
+        scope,
+        retry_after_secs: 120,
+        message: "Upgrading the database".to_string(),
+    });
+}
+
+async fn send(state: &Arc<AppState>, method: Method, uri: &str) -> Response {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(Body::from("{}"))
+        .unwrap();
+    create_router(state.clone()).oneshot(request).await.unwrap()
+}
+
+async fn json_body(response: Response) -> serde_json::Value {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn test_mutating_routes_are_rejected_during_maintenance() {
+    let state = lazy_state();
+    enable(&state, MaintenanceScope::Writes);
+
+    let response = send(&state, Method::POST, "/api/v1/wallet").await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers().get(RETRY_AFTER).unwrap(), "120");
+    let body = json_body(response).await;
+    assert_eq!(body["code"], "maintenance");
+    assert_eq!(body["message"], "Under maintenance: Upgrading the database");
+
+    // Reads still reach the route, which wants a token
+    let response = send(&state, Method::GET, "/api/v1/wallet").await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_all_scope_rejects_reads() {
+    let state = lazy_state();
+    enable(&state, MaintenanceScope::All);
+
+    let response = send(&state, Method::GET, "/api/v1/wallet").await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[tokio::test]
+async fn test_health_reports_maintenance() {
+    let state = lazy_state();
+
+    let body = json_body(send(&state, Method::GET, "/health").await).await;
+    assert_eq!(body["status"], "healthy");
+    assert_eq!(body["maintenance"]["enabled"], false);
+
+    enable(&state, MaintenanceScope::All);
+
+    let response = send(&state, Method::GET, "/health").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = json_body(response).await;
+    assert_eq!(body["status"], "maintenance");
+    assert_eq!(body["maintenance"]["enabled"], true);
+    assert_eq!(body["maintenance"]["scope"], "all");
+}
+
+#[tokio::test]
+async fn test_admin_routes_stay_reachable_during_maintenance() {
+    let state = lazy_state();
+    enable(&state, MaintenanceScope::All);
+
+    // Turned away by authentication rather than by maintenance
+    let response = send(&state, Method::PUT, "/api/v1/admin/maintenance").await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_mutating_routes_work_after_maintenance() {
+    let state = lazy_state();
+    enable(&state, MaintenanceScope::Writes);
+    state.maintenance.set(MaintenanceConfig::default());
+
+    let response = send(&state, Method::POST, "/api/v1/wallet").await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
\ No newline at end of file
@@ -0,0 +1,61 @@
+//! Tests for the Prometheus metrics endpoint
+
+use axum::{body::Body, http::{Request, StatusCode}, Router};
+use guardian_aa_backend::{
+    api::{create_router, AppState},
+    config::Config,
+};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+mod common;
+
+/// App state whose connections are only opened on first use; requests
+/// made while draining never touch them
+fn lazy_state(config: Config) -> Arc<AppState> {
+    let state = common::lazy_state(config);
+    state.draining.store(true, Ordering::SeqCst);
+    state
+}
+
+async fn get(app: &Router, uri: &str) -> (StatusCode, String) {
+    let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+/// Value of the request counter for draining readiness checks
+fn ready_requests(metrics: &str) -> f64 {
+    metrics.lines()
+        .filter(|line| line.starts_with("http_requests_total{"))
+        .filter(|line| line.contains(r#"route="/ready""#) && line.contains(r#"status="503""#))
+        .filter_map(|line| line.rsplit(' ').next()?.parse::<f64>().ok())
+        .sum()
+}
+
+#[tokio::test]
+async fn test_metrics_counts_requests() {
+    let app = create_router(lazy_state(Config::default()));
+
+    let (status, before) = get(&app, "/metrics").await;
+    assert_eq!(status, StatusCode::OK);
+
+    assert_eq!(get(&app, "/ready").await.0, StatusCode::SERVICE_UNAVAILABLE);
+
+    let (_, after) = get(&app, "/metrics").await;
+    assert_eq!(ready_requests(&after), ready_requests(&before) + 1.0);
+    assert!(after.contains("http_request_duration_seconds_bucket"));
+    assert!(after.contains("db_pool_max_connections"));
+}
+
+#[tokio::test]
+async fn test_metrics_moves_to_its_own_port() {
+    let mut config = Config::default();
+    config.metrics.port = Some(9090);
+    let app = create_router(lazy_state(config));
+
+    assert_eq!(get(&app, "/metrics").await.0, StatusCode::NOT_FOUND);
+}
\ No newline at end of file
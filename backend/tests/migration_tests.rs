@@ -0,0 +1,79 @@
+//! Tests for applying database migrations
+//!
+//! These tests need a running Postgres instance and are ignored unless run
+//! with `--ignored` and `DATABASE_URL` set. Each one migrates a fresh
+//! schema, so they don't depend on the state of the shared database.
+
+use guardian_aa_backend::db::Database;
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    PgPool,
+};
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// A database whose connections see an empty schema of their own
+async fn fresh_schema() -> (Database, PgPool, String) {
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set to run this test");
+
+    let schema = format!("migrations_{}", Uuid::new_v4().simple());
+    let admin_pool = PgPool::connect(&url).await.expect("Failed to connect to database");
+    sqlx::query(&format!("CREATE SCHEMA {}", schema))
+        .execute(&admin_pool)
+        .await
+        .unwrap();
+
+    // Extensions such as uuid-ossp live in public, so keep it on the path
+    let options = PgConnectOptions::from_str(&url)
+        .unwrap()
+        .options([("search_path", format!("{},public", schema))]);
+    let pool = PgPoolOptions::new().connect_with(options).await.unwrap();
+
+    (Database::from_pools(pool, None), admin_pool, schema)
+}
+
+async fn drop_schema(admin_pool: &PgPool, schema: &str) {
+    sqlx::query(&format!("DROP SCHEMA {} CASCADE", schema))
+        .execute(admin_pool)
+        .await
+        .unwrap();
+}
+
+async fn table_exists(db: &Database, schema: &str, table: &str) -> bool {
+    sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM information_schema.tables WHERE table_schema = $1 AND table_name = $2)",
+    )
+        .bind(schema)
+        .bind(table)
+        .fetch_one(db.pool())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_migrations_create_schema() {
+    let (db, admin_pool, schema) = fresh_schema().await;
+
+    let applied = db.run_migrations().await.unwrap();
+
+    assert!(!applied.is_empty());
+    assert!(applied.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(table_exists(&db, &schema, "users").await);
+
+    drop_schema(&admin_pool, &schema).await;
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_migrations_are_idempotent() {
+    let (db, admin_pool, schema) = fresh_schema().await;
+
+    db.run_migrations().await.unwrap();
+    let applied_again = db.run_migrations().await.unwrap();
+
+    assert!(applied_again.is_empty());
+    assert!(table_exists(&db, &schema, "users").await);
+
+    drop_schema(&admin_pool, &schema).await;
+}
\ No newline at end of file
@@ -0,0 +1,89 @@
+//! Tests for agent model loading and hot reloading
+
+use guardian_aa_backend::{
+    config::ModelConfig,
+    error::Error,
+    inference::{ModelLoader, ModelRegistry},
+};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+fn model_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("guardian_models_{}", Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn write_model(dir: &Path, version: &str, input_shape: &[usize], output_shape: &[usize]) {
+    let manifest = serde_json::json!({
+        "input_shape": input_shape,
+        "output_shape": output_shape,
+    });
+    std::fs::write(dir.join(format!("{}.json", version)), manifest.to_string()).unwrap();
+    std::fs::write(dir.join(format!("{}.onnx", version)), format!("onnx-{}", version)).unwrap();
+}
+
+fn registry(dir: &Path) -> ModelRegistry {
+    let config = ModelConfig {
+        source: dir.to_string_lossy().into_owned(),
+        ..ModelConfig::default()
+    };
+    ModelRegistry::new(ModelLoader::new(&config))
+}
+
+#[tokio::test]
+async fn test_reload_swaps_in_new_model() {
+    let dir = model_dir();
+    let config = ModelConfig::default();
+    write_model(&dir, "v1", &config.input_shape, &config.output_shape);
+    write_model(&dir, "v2", &config.input_shape, &config.output_shape);
+
+    let registry = registry(&dir);
+    let agent_id = Uuid::new_v4();
+    assert!(registry.get(agent_id).is_none());
+
+    registry.reload(agent_id, "v1").await.unwrap();
+    assert_eq!(registry.get(agent_id).unwrap().model_version, "v1");
+
+    let model = registry.reload(agent_id, "v2").await.unwrap();
+    assert_eq!(model.model_version, "v2");
+    assert_eq!(model.bytes, b"onnx-v2");
+    assert_eq!(registry.get(agent_id).unwrap().model_version, "v2");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_shape_mismatch_is_rejected_and_previous_model_kept() {
+    let dir = model_dir();
+    let config = ModelConfig::default();
+    write_model(&dir, "v1", &config.input_shape, &config.output_shape);
+    write_model(&dir, "v2-bad", &config.input_shape, &[1, 5]);
+
+    let registry = registry(&dir);
+    let agent_id = Uuid::new_v4();
+    registry.reload(agent_id, "v1").await.unwrap();
+
+    let result = registry.reload(agent_id, "v2-bad").await;
+    assert!(matches!(result, Err(Error::Validation(_))));
+    assert_eq!(registry.get(agent_id).unwrap().model_version, "v1");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_missing_model_keeps_previous_model() {
+    let dir = model_dir();
+    let config = ModelConfig::default();
+    write_model(&dir, "v1", &config.input_shape, &config.output_shape);
+
+    let registry = registry(&dir);
+    let agent_id = Uuid::new_v4();
+    registry.reload(agent_id, "v1").await.unwrap();
+
+    assert!(registry.reload(agent_id, "v3").await.is_err());
+    assert!(registry.reload(agent_id, "../v1").await.is_err());
+    assert_eq!(registry.get(agent_id).unwrap().model_version, "v1");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
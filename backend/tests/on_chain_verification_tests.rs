@@ -0,0 +1,139 @@
+//! Tests for verifying proofs with the Guardian program
+
+use axum::{extract::State, routing::post, Json, Router};
+use base64::{engine::general_purpose, Engine as _};
+use guardian_aa_backend::{
+    blockchain::{verifier::VERIFY_PROOF_TAG, GuardianVerifier, SolanaClient},
+    config::OnChainVerificationConfig,
+    error::Error,
+};
+use solana_sdk::{pubkey::Pubkey, transaction::VersionedTransaction};
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Default)]
+struct MockRpc {
+    /// Simulation requests received
+    simulations: Arc<Mutex<Vec<serde_json::Value>>>,
+    /// Answer simulations as if the program rejected the proof
+    reject: bool,
+}
+
+async fn handle_rpc(State(mock): State<MockRpc>, Json(request): Json<serde_json::Value>) -> Json<serde_json::Value> {
+    let result = match request["method"].as_str().unwrap_or_default() {
+        "getVersion" => serde_json::json!({ "solana-core": "2.1.0", "feature-set": 0 }),
+        "simulateTransaction" => {
+            mock.simulations.lock().unwrap().push(request["params"].clone());
+            let err = if mock.reject {
+                serde_json::json!({ "InstructionError": [0, { "Custom": 6000 }] })
+            } else {
+                serde_json::Value::Null
+            };
+            serde_json::json!({
+                "context": { "slot": 1 },
+                "value": {
+                    "err": err,
+                    "logs": ["Program log: VerifyProof"],
+                    "accounts": null,
+                    "unitsConsumed": 4242,
+                    "returnData": null
+                }
+            })
+        }
+        method => panic!("unexpected RPC call {}", method),
+    };
+
+    Json(serde_json::json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+}
+
+async fn start_mock_rpc(mock: MockRpc) -> String {
+    let app = Router::new().route("/", post(handle_rpc)).with_state(mock);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    url
+}
+
+fn verifier() -> GuardianVerifier {
+    GuardianVerifier {
+        program_id: Pubkey::new_unique(),
+        fee_payer: Pubkey::new_unique(),
+    }
+}
+
+/// The transaction sent in a `simulateTransaction` call
+fn simulated_transaction(params: &serde_json::Value) -> VersionedTransaction {
+    assert_eq!(params[1]["encoding"], "base64");
+    let bytes = general_purpose::STANDARD.decode(params[0].as_str().unwrap()).unwrap();
+    bincode::deserialize(&bytes).unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_instruction_targets_guardian_program() {
+    let mock = MockRpc::default();
+    let url = start_mock_rpc(mock.clone()).await;
+    let verifier = verifier();
+    let client = SolanaClient::new(&url, "confirmed").unwrap().with_verifier(verifier);
+
+    let verification = client.verify_proof_on_chain(&[7; 64], &[1, 2, 3]).await.unwrap();
+
+    assert!(verification.verified);
+    assert_eq!(verification.compute_units, Some(4242));
+
+    let simulations = mock.simulations.lock().unwrap();
+    assert_eq!(simulations.len(), 1);
+    // Nothing is signed, so the node mustn't check signatures
+    assert_eq!(simulations[0][1]["sigVerify"], false);
+    assert_eq!(simulations[0][1]["replaceRecentBlockhash"], true);
+
+    let transaction = simulated_transaction(&simulations[0]);
+    let keys = transaction.message.static_account_keys();
+    assert_eq!(keys[0], verifier.fee_payer);
+    let instructions = transaction.message.instructions();
+    assert_eq!(instructions.len(), 1);
+    assert_eq!(keys[instructions[0].program_id_index as usize], verifier.program_id);
+    let accounts: Vec<Pubkey> = instructions[0].accounts.iter().map(|&index| keys[index as usize]).collect();
+    assert_eq!(accounts, vec![verifier.fee_payer]);
+
+    let mut data = vec![VERIFY_PROOF_TAG];
+    data.extend_from_slice(&64u32.to_le_bytes());
+    data.extend_from_slice(&[7; 64]);
+    data.extend_from_slice(&3u32.to_le_bytes());
+    data.extend_from_slice(&[1, 2, 3]);
+    assert_eq!(instructions[0].data, data);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_rejected_proof_is_reported() {
+    let url = start_mock_rpc(MockRpc { reject: true, ..Default::default() }).await;
+    let client = SolanaClient::new(&url, "confirmed").unwrap().with_verifier(verifier());
+
+    let verification = client.verify_proof_on_chain(&[7; 64], &[1, 2, 3]).await.unwrap();
+
+    assert!(!verification.verified);
+    assert!(verification.error.unwrap().contains("custom program error"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_proof_too_big_for_a_transaction_is_not_simulated() {
+    let mock = MockRpc::default();
+    let url = start_mock_rpc(mock.clone()).await;
+    let client = SolanaClient::new(&url, "confirmed").unwrap().with_verifier(verifier());
+
+    let result = client.verify_proof_on_chain(&[7; 2048], &[1, 2, 3]).await;
+
+    assert!(matches!(result, Err(Error::Validation(_))));
+    assert!(mock.simulations.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_disabled_verification_is_refused() {
+    let config = OnChainVerificationConfig { enabled: false, fee_payer: String::new() };
+    assert!(GuardianVerifier::from_config("11111111111111111111111111111111", &config).unwrap().is_none());
+
+    let client = SolanaClient::new("http://127.0.0.1:1", "confirmed").unwrap();
+    assert!(matches!(client.verify_proof_on_chain(&[7; 64], &[]).await, Err(Error::BadRequest(_))));
+}
\ No newline at end of file
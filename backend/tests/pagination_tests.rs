@@ -0,0 +1,150 @@
+//! Tests for paginated list endpoints
+//!
+//! The database tests need a running Postgres instance and are ignored
+//! unless run with `--ignored` and `DATABASE_URL` set.
+
+use guardian_aa_backend::{
+    api::{pagination::{Page, MAX_PAGE_LIMIT}, AppState},
+    db::models::TransactionFilter,
+    services::{AgentService, TransactionService, WalletService},
+};
+use uuid::Uuid;
+
+mod common;
+
+async fn create_user(state: &AppState) -> Uuid {
+    sqlx::query_scalar("INSERT INTO users (email, password_hash) VALUES ($1, 'hash') RETURNING id")
+        .bind(format!("pagination-{}@example.com", Uuid::new_v4()))
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap()
+}
+
+async fn create_wallet(state: &AppState, user_id: Uuid) -> Uuid {
+    sqlx::query_scalar(
+        "INSERT INTO wallets (user_id, name, wallet_type, public_key)
+         VALUES ($1, 'Pagination', 'solana', $2) RETURNING id",
+    )
+        .bind(user_id)
+        .bind(Uuid::new_v4().to_string())
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap()
+}
+
+async fn create_transaction(state: &AppState, wallet_id: Uuid) {
+    sqlx::query(
+        "INSERT INTO transactions (wallet_id, transaction_type, status, from_address, to_address, amount)
+         VALUES ($1, 'send', 'pending', 'from', 'to', '1')",
+    )
+        .bind(wallet_id)
+        .execute(state.db.pool())
+        .await
+        .unwrap();
+}
+
+async fn create_agent(state: &AppState) -> Uuid {
+    sqlx::query_scalar(
+        "INSERT INTO agents (name, agent_type, description, model_version, is_active)
+         VALUES ($1, 'news_sentiment', 'Pagination test agent', 'v1', false) RETURNING id",
+    )
+        .bind(format!("pagination-agent-{}", Uuid::new_v4()))
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap()
+}
+
+async fn create_prediction(state: &AppState, agent_id: Uuid, user_id: Uuid, asset_symbol: &str) {
+    sqlx::query(
+        "INSERT INTO agent_predictions
+             (agent_id, user_id, asset_symbol, prediction, confidence, explanation_hash, explanation_text, expires_at)
+         VALUES ($1, $2, $3, 'bullish', 0.7, 'hash', 'explanation', NOW() + INTERVAL '1 day')",
+    )
+        .bind(agent_id)
+        .bind(user_id)
+        .bind(asset_symbol)
+        .execute(state.db.pool())
+        .await
+        .unwrap();
+}
+
+#[test]
+fn test_page_clamps_query_parameters() {
+    assert_eq!(Page::new(None, None), Page { limit: 50, offset: 0 });
+    assert_eq!(Page::new(Some(10_000), Some(20)), Page { limit: MAX_PAGE_LIMIT, offset: 20 });
+    assert_eq!(Page::new(Some(0), Some(-5)), Page { limit: 1, offset: 0 });
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_wallet_total_counts_every_page() {
+    let state = common::test_state().await;
+    let user_id = create_user(&state).await;
+    for _ in 0..3 {
+        create_wallet(&state, user_id).await;
+    }
+
+    let wallet_service = WalletService::new(state.clone());
+    let first = wallet_service.get_user_wallets(user_id, Page::new(Some(2), None)).await.unwrap();
+    assert_eq!(first.items.len(), 2);
+    assert_eq!(first.total, 3);
+
+    let second = wallet_service.get_user_wallets(user_id, Page::new(Some(2), Some(2))).await.unwrap();
+    assert_eq!(second.items.len(), 1);
+    assert_eq!(second.total, 3);
+    assert_eq!(second.offset, 2);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_transaction_total_counts_every_page() {
+    let state = common::test_state().await;
+    let user_id = create_user(&state).await;
+    let wallet_id = create_wallet(&state, user_id).await;
+    for _ in 0..3 {
+        create_transaction(&state, wallet_id).await;
+    }
+
+    let transaction_service = TransactionService::new(state.clone());
+    let page = transaction_service
+        .get_wallet_transactions(wallet_id, user_id, TransactionFilter::default(), Page::new(Some(1), None))
+        .await
+        .unwrap();
+
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(page.total, 3);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_prediction_total_counts_every_page() {
+    let state = common::test_state().await;
+    let user_id = create_user(&state).await;
+    let agent_id = create_agent(&state).await;
+    for asset_symbol in ["SOL", "SOL", "ETH"] {
+        create_prediction(&state, agent_id, user_id, asset_symbol).await;
+    }
+
+    let agent_service = AgentService::new(state.clone());
+    let all = agent_service.get_user_predictions(user_id, Page::new(Some(1), None)).await.unwrap();
+    assert_eq!(all.items.len(), 1);
+    assert_eq!(all.total, 3);
+
+    let sol = agent_service.get_asset_predictions(user_id, "SOL", Page::new(Some(1), None)).await.unwrap();
+    assert_eq!(sol.items.len(), 1);
+    assert_eq!(sol.total, 2);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_oversized_limit_is_clamped() {
+    let state = common::test_state().await;
+    let user_id = create_user(&state).await;
+    create_wallet(&state, user_id).await;
+
+    let wallet_service = WalletService::new(state.clone());
+    let page = wallet_service.get_user_wallets(user_id, Page::new(Some(1_000), None)).await.unwrap();
+
+    assert_eq!(page.limit, MAX_PAGE_LIMIT);
+    assert_eq!(page.total, 1);
+}
\ No newline at end of file
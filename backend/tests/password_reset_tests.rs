@@ -0,0 +1,176 @@
+//! Tests for resetting a forgotten password
+//!
+//! These tests need running Postgres and Redis instances and are ignored
+//! unless run with `--ignored` and both `DATABASE_URL` and `REDIS_URL` set.
+
+use axum::{
+    body::Body,
+    http::{header::AUTHORIZATION, Request, StatusCode},
+};
+use chrono::{Duration, Utc};
+use guardian_aa_backend::{
+    api::{
+        create_router,
+        handlers::auth::{ForgotPasswordRequest, LoginRequest, RegisterRequest, ResetPasswordRequest},
+        AppState,
+    },
+    db::queries::{UserQueries, UserSessionQueries},
+    error::Error,
+    services::{email::SendFuture, AuthService, EmailSender},
+};
+use std::sync::{Arc, Mutex};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+mod common;
+
+const OLD_PASSWORD: &str = "correct horse battery";
+const NEW_PASSWORD: &str = "staple battery horse";
+
+/// Keeps the last password reset token instead of sending it
+#[derive(Default)]
+struct CapturingSender {
+    reset_token: Mutex<Option<String>>,
+}
+
+impl EmailSender for CapturingSender {
+    fn send_verification_email<'a>(&'a self, _email: &'a str, _token: &'a str) -> SendFuture<'a> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn send_password_reset_email<'a>(&'a self, _email: &'a str, token: &'a str) -> SendFuture<'a> {
+        Box::pin(async move {
+            *self.reset_token.lock().unwrap() = Some(token.to_string());
+            Ok(())
+        })
+    }
+}
+
+async fn test_state() -> Arc<AppState> {
+    let mut config = common::database_and_redis_config();
+    config.rate_limit.enabled = false;
+    common::connect(config).await
+}
+
+/// Register a user and request a reset for them, returning their email, an
+/// access token from registering and the reset token they were sent
+async fn request_reset(state: &Arc<AppState>) -> (String, String, String) {
+    let sender = Arc::new(CapturingSender::default());
+    let auth_service = AuthService::new(state.clone()).with_email_sender(sender.clone());
+    let email = format!("reset-{}@example.com", Uuid::new_v4());
+
+    let registered = auth_service.register(RegisterRequest {
+        email: email.clone(),
+        password: OLD_PASSWORD.to_string(),
+        username: None,
+    }).await.unwrap();
+    auth_service.forgot_password(ForgotPasswordRequest { email: email.clone() }).await.unwrap();
+
+    let token = sender.reset_token.lock().unwrap().clone().expect("no reset email was sent");
+    (email, registered.access_token, token)
+}
+
+async fn reset(state: &Arc<AppState>, token: &str) -> Result<(), Error> {
+    AuthService::new(state.clone())
+        .reset_password(ResetPasswordRequest {
+            token: token.to_string(),
+            new_password: NEW_PASSWORD.to_string(),
+        })
+        .await
+}
+
+async fn can_login(state: &Arc<AppState>, email: &str, password: &str) -> bool {
+    AuthService::new(state.clone())
+        .login(LoginRequest { email: email.to_string(), password: password.to_string() })
+        .await
+        .is_ok()
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_reset_replaces_password_and_revokes_sessions() {
+    let state = test_state().await;
+    let (email, _, token) = request_reset(&state).await;
+
+    let user = UserQueries::find_by_email(state.db.pool(), &email).await.unwrap().unwrap();
+    let session = UserSessionQueries::create(
+        state.db.pool(),
+        user.id,
+        &format!("{:x}", Uuid::new_v4().as_u128()),
+        Utc::now() + Duration::days(7),
+        None,
+        None,
+    ).await.unwrap();
+
+    reset(&state, &token).await.unwrap();
+
+    assert!(can_login(&state, &email, NEW_PASSWORD).await);
+    assert!(!can_login(&state, &email, OLD_PASSWORD).await);
+
+    let session_exists: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM user_sessions WHERE id = $1)")
+        .bind(session.id)
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap();
+    assert!(!session_exists);
+
+    // The token was single-use
+    assert!(matches!(reset(&state, &token).await, Err(Error::BadRequest(_))));
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_reset_rejects_access_tokens_issued_before_it() {
+    let state = test_state().await;
+    let (_, access_token, token) = request_reset(&state).await;
+
+    let app = create_router(state.clone());
+    let list_sessions = |access_token: &str| Request::builder()
+        .uri("/api/v1/auth/sessions")
+        .header(AUTHORIZATION, format!("Bearer {}", access_token))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(list_sessions(&access_token)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    reset(&state, &token).await.unwrap();
+
+    // Whoever reset the password may be locking out someone holding the old
+    // one, so their token has to stop working too
+    let response = app.oneshot(list_sessions(&access_token)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_expired_reset_token_is_rejected() {
+    let state = test_state().await;
+    let (email, _, token) = request_reset(&state).await;
+
+    sqlx::query(
+        "UPDATE password_reset_tokens SET expires_at = NOW() - INTERVAL '1 minute'
+         WHERE user_id = (SELECT id FROM users WHERE email = $1)",
+    )
+        .bind(&email)
+        .execute(state.db.pool())
+        .await
+        .unwrap();
+
+    assert!(matches!(reset(&state, &token).await, Err(Error::BadRequest(_))));
+    assert!(can_login(&state, &email, OLD_PASSWORD).await);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_unknown_email_is_not_revealed() {
+    let state = test_state().await;
+    let sender = Arc::new(CapturingSender::default());
+
+    let result = AuthService::new(state.clone())
+        .with_email_sender(sender.clone())
+        .forgot_password(ForgotPasswordRequest { email: format!("nobody-{}@example.com", Uuid::new_v4()) })
+        .await;
+
+    assert!(result.is_ok());
+    assert!(sender.reset_token.lock().unwrap().is_none());
+}
\ No newline at end of file
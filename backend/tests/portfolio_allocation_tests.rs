@@ -0,0 +1,198 @@
+//! Tests for fixed-point portfolio allocations
+
+use guardian_aa_backend::{
+    config::AllocationRounding,
+    db::models::{PredictionType, RecommendationType},
+    services::{
+        allocation::{portfolio_allocation, rebalance_trades, split_units, wallet_holdings, AssetAllocations, RebalanceTrade, TradeSide},
+        ensemble::EnsembleResult,
+        wallet::{TokenBalance, WalletBalance},
+    },
+};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+fn ensemble(prediction: PredictionType, confidence: f64) -> EnsembleResult {
+    EnsembleResult {
+        prediction,
+        confidence,
+        agent_count: 4,
+        consensus_strength: 0.75,
+        included: Vec::new(),
+        excluded: Vec::new(),
+    }
+}
+
+#[test]
+fn test_allocations_sum_to_scale_for_any_confidence() {
+    let predictions = [PredictionType::Bullish, PredictionType::Bearish, PredictionType::Neutral];
+    let roundings = [AllocationRounding::LargestRemainder, AllocationRounding::Last];
+
+    for scale in [100, 10_000, 1_000_000, 7] {
+        for rounding in roundings {
+            for prediction in &predictions {
+                for step in 0..=1_000 {
+                    let confidence = step as f64 / 1_000.0;
+                    let (_, allocations) = portfolio_allocation(&ensemble(prediction.clone(), confidence), scale, rounding);
+
+                    let total: u32 = allocations.units.values().sum();
+                    assert_eq!(total, scale, "{:?} at {} with {:?}", prediction, confidence, rounding);
+                    assert_eq!(allocations.scale, scale);
+                    assert!(allocations.cash_ratio() + allocations.crypto_ratio() <= 1.0);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_bullish_allocation_in_basis_points() {
+    // 0.7 + (0.8 - 0.5) * 0.4 = 0.82 in crypto, split 60/30/10
+    let (recommendation, allocations) =
+        portfolio_allocation(&ensemble(PredictionType::Bullish, 0.8), 10_000, AllocationRounding::LargestRemainder);
+
+    assert!(matches!(recommendation, RecommendationType::Buy));
+    assert_eq!(allocations.units["SOL"], 4_920);
+    assert_eq!(allocations.units["BTC"], 2_460);
+    assert_eq!(allocations.units["ETH"], 820);
+    assert_eq!(allocations.units["CASH"], 1_800);
+}
+
+#[test]
+fn test_largest_remainder_goes_to_biggest_fractions() {
+    // Exact shares are 33.3, 33.3 and 33.3; the tie goes to the first part
+    assert_eq!(split_units(&[1.0, 1.0, 1.0], 100, AllocationRounding::LargestRemainder), vec![34, 33, 33]);
+    // Exact shares are 16.67, 33.33 and 50
+    assert_eq!(split_units(&[1.0, 2.0, 3.0], 100, AllocationRounding::LargestRemainder), vec![17, 33, 50]);
+}
+
+#[test]
+fn test_last_takes_the_remainder() {
+    assert_eq!(split_units(&[1.0, 1.0, 1.0], 100, AllocationRounding::Last), vec![33, 33, 34]);
+}
+
+#[test]
+fn test_split_is_deterministic() {
+    let weights = [0.123, 0.456, 0.789, 0.001];
+    let first = split_units(&weights, 10_000, AllocationRounding::LargestRemainder);
+    for _ in 0..10 {
+        assert_eq!(split_units(&weights, 10_000, AllocationRounding::LargestRemainder), first);
+    }
+    assert_eq!(first.iter().sum::<u32>(), 10_000);
+}
+
+fn target(units: &[(&str, u32)]) -> AssetAllocations {
+    AssetAllocations {
+        scale: 10_000,
+        units: units.iter().map(|&(asset, units)| (asset.to_string(), units)).collect(),
+    }
+}
+
+fn amounts(amounts: &[(&str, f64)]) -> BTreeMap<String, f64> {
+    amounts.iter().map(|&(asset, amount)| (asset.to_string(), amount)).collect()
+}
+
+fn assert_trade(trade: &RebalanceTrade, asset: &str, side: TradeSide, amount: f64) {
+    assert_eq!((trade.asset.as_str(), trade.side), (asset, side));
+    assert!((trade.amount - amount).abs() < 1e-9, "{} {:?} {} != {}", asset, side, trade.amount, amount);
+}
+
+#[test]
+fn test_over_weight_asset_is_sold_for_cash() {
+    // 1,000 in SOL and no cash against a 50/50 target
+    let trades = rebalance_trades(
+        &amounts(&[("SOL", 10.0)]),
+        &amounts(&[("SOL", 100.0)]),
+        &target(&[("SOL", 5_000), ("CASH", 5_000)]),
+        1.0,
+    ).unwrap();
+
+    assert_eq!(trades.len(), 1);
+    assert_trade(&trades[0], "SOL", TradeSide::Sell, 5.0);
+}
+
+#[test]
+fn test_under_weight_asset_is_bought_with_cash() {
+    let trades = rebalance_trades(
+        &amounts(&[("SOL", 2.0), ("CASH", 800.0)]),
+        &amounts(&[("SOL", 100.0)]),
+        &target(&[("SOL", 5_000), ("CASH", 5_000)]),
+        1.0,
+    ).unwrap();
+
+    assert_eq!(trades.len(), 1);
+    assert_trade(&trades[0], "SOL", TradeSide::Buy, 3.0);
+}
+
+#[test]
+fn test_sells_come_before_buys_with_one_trade_per_asset() {
+    // 2,000 in total: SOL and BTC hold 1,000 each against 60/30/10
+    let trades = rebalance_trades(
+        &amounts(&[("SOL", 10.0), ("BTC", 0.02)]),
+        &amounts(&[("SOL", 100.0), ("BTC", 50_000.0), ("ETH", 2_000.0)]),
+        &target(&[("SOL", 6_000), ("BTC", 3_000), ("ETH", 1_000), ("CASH", 0)]),
+        1.0,
+    ).unwrap();
+
+    assert_eq!(trades.len(), 3);
+    assert_trade(&trades[0], "BTC", TradeSide::Sell, 0.008);
+    assert_trade(&trades[1], "ETH", TradeSide::Buy, 0.1);
+    assert_trade(&trades[2], "SOL", TradeSide::Buy, 2.0);
+}
+
+#[test]
+fn test_assets_outside_the_target_are_sold_out() {
+    let trades = rebalance_trades(
+        &amounts(&[("BONK", 1_234_567.0), ("CASH", 100.0)]),
+        &amounts(&[("BONK", 0.00002)]),
+        &target(&[("CASH", 10_000)]),
+        1.0,
+    ).unwrap();
+
+    assert_eq!(trades, vec![RebalanceTrade { asset: "BONK".to_string(), side: TradeSide::Sell, amount: 1_234_567.0 }]);
+}
+
+#[test]
+fn test_balanced_portfolio_needs_no_trades() {
+    let prices = amounts(&[("SOL", 100.0)]);
+    let target = target(&[("SOL", 5_000), ("CASH", 5_000)]);
+
+    let balanced = rebalance_trades(&amounts(&[("SOL", 5.0), ("CASH", 500.0)]), &prices, &target, 1.0).unwrap();
+    assert!(balanced.is_empty());
+
+    // 0.40 off target, under the 1.00 minimum
+    let dust = rebalance_trades(&amounts(&[("SOL", 5.004), ("CASH", 499.6)]), &prices, &target, 1.0).unwrap();
+    assert!(dust.is_empty());
+}
+
+#[test]
+fn test_missing_price_is_a_validation_error() {
+    let result = rebalance_trades(&amounts(&[("SOL", 1.0)]), &BTreeMap::new(), &target(&[("SOL", 10_000)]), 1.0);
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_wallet_holdings_count_stablecoins_as_cash() {
+    let token = |symbol: Option<&str>, balance: &str| TokenBalance {
+        mint: Uuid::new_v4().to_string(),
+        balance: balance.to_string(),
+        decimals: 6,
+        symbol: symbol.map(str::to_string),
+        name: None,
+        logo_uri: None,
+    };
+    let balance = WalletBalance {
+        wallet_id: Uuid::new_v4(),
+        sol_balance: "1.5".to_string(),
+        token_balances: vec![
+            token(Some("USDC"), "100"),
+            token(Some("USDT"), "50"),
+            token(Some("BTC"), "0.25"),
+            token(None, "42"),
+        ],
+        last_updated: chrono::Utc::now(),
+    };
+
+    assert_eq!(wallet_holdings(&balance), amounts(&[("SOL", 1.5), ("CASH", 150.0), ("BTC", 0.25)]));
+}
\ No newline at end of file
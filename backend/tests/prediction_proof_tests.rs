@@ -0,0 +1,160 @@
+//! Tests for re-verifying a prediction's stored proof and committed
+//! explanation
+//!
+//! These tests need a running Postgres instance and are ignored unless run
+//! with `--ignored` and `DATABASE_URL` set.
+
+use guardian_aa_backend::{
+    api::AppState,
+    db::models::PredictionType,
+    error::Error,
+    services::{agent::CreatePredictionRequest, AgentService},
+};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use uuid::Uuid;
+
+mod common;
+
+async fn create_user(state: &AppState) -> Uuid {
+    sqlx::query_scalar("INSERT INTO users (email, password_hash) VALUES ($1, 'hash') RETURNING id")
+        .bind(format!("predictions-{}@example.com", Uuid::new_v4()))
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap()
+}
+
+/// Create a proven prediction for the user, returning its id
+async fn create_proven_prediction(state: &Arc<AppState>, user_id: Uuid) -> Uuid {
+    let agent_service = AgentService::new(state.clone());
+    let agent = agent_service.get_active_agents().await.unwrap().remove(0);
+
+    let prediction = agent_service.create_prediction(user_id, CreatePredictionRequest {
+        agent_id: agent.id,
+        asset_symbol: "SOL".to_string(),
+        prediction: PredictionType::Bullish,
+        confidence: 0.8,
+        explanation_text: "Strong inflows and positive funding".to_string(),
+        data_sources: serde_json::json!([]),
+        generate_proof: true,
+    }).await.unwrap();
+
+    prediction.id
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_valid_prediction_proof() {
+    let state = common::test_state().await;
+    let user_id = create_user(&state).await;
+    let prediction_id = create_proven_prediction(&state, user_id).await;
+
+    let check = AgentService::new(state.clone())
+        .verify_prediction_proof(prediction_id, user_id)
+        .await
+        .unwrap();
+
+    assert!(check.proof_valid);
+    assert!(check.explanation_matches);
+    assert_eq!(check.vk_hash, state.zkml_service.verification_key_hash().unwrap());
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_tampered_explanation_is_detected() {
+    let state = common::test_state().await;
+    let user_id = create_user(&state).await;
+    let prediction_id = create_proven_prediction(&state, user_id).await;
+
+    sqlx::query("UPDATE agent_predictions SET explanation_text = 'Rewritten after the fact' WHERE id = $1")
+        .bind(prediction_id)
+        .execute(state.db.pool())
+        .await
+        .unwrap();
+
+    let check = AgentService::new(state)
+        .verify_prediction_proof(prediction_id, user_id)
+        .await
+        .unwrap();
+
+    assert!(check.proof_valid);
+    assert!(!check.explanation_matches);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_invalid_proof_is_detected() {
+    let state = common::test_state().await;
+    let user_id = create_user(&state).await;
+    let prediction_id = create_proven_prediction(&state, user_id).await;
+
+    sqlx::query("UPDATE zkml_proofs SET proof_data = 'AAAAAAAAAAAAAAAA' WHERE prediction_id = $1")
+        .bind(prediction_id)
+        .execute(state.db.pool())
+        .await
+        .unwrap();
+
+    let check = AgentService::new(state)
+        .verify_prediction_proof(prediction_id, user_id)
+        .await
+        .unwrap();
+
+    assert!(!check.proof_valid);
+    assert!(check.explanation_matches);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_prediction_proof_check_requires_ownership() {
+    let state = common::test_state().await;
+    let owner = create_user(&state).await;
+    let other_user = create_user(&state).await;
+    let prediction_id = create_proven_prediction(&state, owner).await;
+
+    let result = AgentService::new(state)
+        .verify_prediction_proof(prediction_id, other_user)
+        .await;
+    assert!(matches!(result, Err(Error::Forbidden)));
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_committed_explanation_verifies() {
+    let state = common::test_state().await;
+    let user_id = create_user(&state).await;
+    let prediction_id = create_proven_prediction(&state, user_id).await;
+
+    let check = AgentService::new(state)
+        .verify_explanation(prediction_id, user_id, "Strong inflows and positive funding")
+        .await
+        .unwrap();
+
+    assert!(check.matches);
+    assert_eq!(check.computed_hash, check.explanation_hash);
+    assert_eq!(
+        check.explanation_hash,
+        format!("{:x}", Sha256::digest(b"Strong inflows and positive funding"))
+    );
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_altered_explanation_does_not_verify() {
+    let state = common::test_state().await;
+    let user_id = create_user(&state).await;
+    let other_user = create_user(&state).await;
+    let prediction_id = create_proven_prediction(&state, user_id).await;
+    let agent_service = AgentService::new(state);
+
+    // Even a trailing space is a different explanation
+    for claimed in ["Weak inflows and negative funding", "Strong inflows and positive funding "] {
+        let check = agent_service.verify_explanation(prediction_id, user_id, claimed).await.unwrap();
+        assert!(!check.matches, "{:?} should not match", claimed);
+        assert_ne!(check.computed_hash, check.explanation_hash);
+    }
+
+    let result = agent_service
+        .verify_explanation(prediction_id, other_user, "Strong inflows and positive funding")
+        .await;
+    assert!(matches!(result, Err(Error::Forbidden)));
+}
\ No newline at end of file
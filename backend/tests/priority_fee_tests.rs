@@ -0,0 +1,173 @@
+//! Tests for priority fee estimation
+
+use axum::{routing::post, Json, Router};
+use base64::{engine::general_purpose, Engine as _};
+use guardian_aa_backend::blockchain::{
+    fees::{percentile, priority_fee_lamports, recommend_compute_budget, recommended_compute_unit_price},
+    PriorityLevel, RequestedComputeBudget, SolanaClient,
+};
+use solana_sdk::{
+    compute_budget::{self, ComputeBudgetInstruction},
+    hash::Hash,
+    instruction::Instruction,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+
+/// Prioritization fees from 20 recent slots, in micro-lamports per CU
+const RECENT_FEES: [u64; 20] = [
+    0, 0, 0, 0, 10, 10, 20, 50, 100, 100,
+    150, 200, 250, 500, 1_000, 1_000, 2_000, 5_000, 10_000, 50_000,
+];
+
+#[test]
+fn test_percentiles_of_fee_distribution() {
+    assert_eq!(percentile(&RECENT_FEES, 0), 0);
+    assert_eq!(percentile(&RECENT_FEES, 50), 100);
+    assert_eq!(percentile(&RECENT_FEES, 75), 1_000);
+    assert_eq!(percentile(&RECENT_FEES, 95), 10_000);
+    assert_eq!(percentile(&RECENT_FEES, 100), 50_000);
+}
+
+#[test]
+fn test_percentile_ignores_input_order() {
+    let mut shuffled = RECENT_FEES;
+    shuffled.reverse();
+    assert_eq!(percentile(&shuffled, 75), 1_000);
+}
+
+#[test]
+fn test_no_recent_fees_recommends_zero() {
+    assert_eq!(recommended_compute_unit_price(&[], PriorityLevel::High), 0);
+}
+
+#[test]
+fn test_priority_levels_map_to_rising_prices() {
+    let low = recommended_compute_unit_price(&RECENT_FEES, PriorityLevel::Low);
+    let medium = recommended_compute_unit_price(&RECENT_FEES, PriorityLevel::Medium);
+    let high = recommended_compute_unit_price(&RECENT_FEES, PriorityLevel::High);
+
+    assert_eq!((low, medium, high), (100, 1_000, 10_000));
+}
+
+#[test]
+fn test_priority_fee_rounds_up_to_whole_lamports() {
+    assert_eq!(priority_fee_lamports(1_000, 200_000), 200);
+    assert_eq!(priority_fee_lamports(1, 200_000), 1);
+    assert_eq!(priority_fee_lamports(0, 200_000), 0);
+}
+
+async fn handle_rpc(Json(request): Json<serde_json::Value>) -> Json<serde_json::Value> {
+    let result = match request["method"].as_str().unwrap_or_default() {
+        "getVersion" => serde_json::json!({ "solana-core": "2.1.0", "feature-set": 0 }),
+        "getFeeForMessage" => serde_json::json!({ "context": { "slot": 1 }, "value": 5_000 }),
+        "getRecentPrioritizationFees" => RECENT_FEES.iter()
+            .enumerate()
+            .map(|(slot, fee)| serde_json::json!({ "slot": slot, "prioritizationFee": fee }))
+            .collect(),
+        method => panic!("unexpected RPC method {}", method),
+    };
+
+    Json(serde_json::json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+}
+
+async fn start_mock_rpc() -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, Router::new().route("/", post(handle_rpc))).await.unwrap();
+    });
+
+    url
+}
+
+/// A signed SOL transfer, preceded by `budget` instructions
+fn transfer(budget: &[Instruction]) -> Transaction {
+    let payer = Keypair::new();
+    let mut instructions = budget.to_vec();
+    instructions.push(system_instruction::transfer(&payer.pubkey(), &Keypair::new().pubkey(), 1_000));
+    Transaction::new_signed_with_payer(&instructions, Some(&payer.pubkey()), &[&payer], Hash::default())
+}
+
+fn encode(transaction: &Transaction) -> String {
+    general_purpose::STANDARD.encode(bincode::serialize(transaction).unwrap())
+}
+
+#[test]
+fn test_requested_compute_budget_is_read_from_instructions() {
+    let budget = RequestedComputeBudget::of(&transfer(&[
+        ComputeBudgetInstruction::set_compute_unit_limit(50_000),
+        ComputeBudgetInstruction::set_compute_unit_price(5),
+    ]).into());
+
+    assert_eq!(budget.unit_limit, Some(50_000));
+    assert_eq!(budget.unit_price, Some(5));
+    assert_eq!(budget.effective_unit_limit(), 50_000);
+
+    // Without a limit, compute budget instructions don't count towards the default
+    let budget = RequestedComputeBudget::of(&transfer(&[ComputeBudgetInstruction::set_compute_unit_price(5)]).into());
+    assert_eq!(budget.effective_unit_limit(), 200_000);
+    assert_eq!(RequestedComputeBudget::of(&transfer(&[]).into()).unit_price, None);
+}
+
+#[test]
+fn test_recommended_budget_tracks_recent_fees() {
+    let current = RequestedComputeBudget::of(&transfer(&[]).into());
+
+    let low = recommend_compute_budget(current, &RECENT_FEES, PriorityLevel::Low);
+    let high = recommend_compute_budget(current, &RECENT_FEES, PriorityLevel::High);
+    assert_eq!(low.compute_unit_price, 100);
+    assert_eq!(high.compute_unit_price, 10_000);
+
+    // Fees rising across the board raise the recommendation with them
+    let congested: Vec<u64> = RECENT_FEES.iter().map(|fee| fee * 10).collect();
+    let congested = recommend_compute_budget(current, &congested, PriorityLevel::High);
+    assert_eq!(congested.compute_unit_price, 100_000);
+    assert_eq!(congested.priority_fee_lamports, 20_000);
+}
+
+#[test]
+fn test_recommendation_flags_underpriced_transactions() {
+    let unpriced = recommend_compute_budget(RequestedComputeBudget::of(&transfer(&[]).into()), &RECENT_FEES, PriorityLevel::Medium);
+    assert!(unpriced.needs_update);
+    assert_eq!(unpriced.instructions.len(), 2);
+    assert!(unpriced.instructions.iter().all(|ix| ix.program_id == compute_budget::id().to_string()));
+
+    let priced = RequestedComputeBudget::of(&transfer(&[ComputeBudgetInstruction::set_compute_unit_price(1_000)]).into());
+    assert!(!recommend_compute_budget(priced, &RECENT_FEES, PriorityLevel::Medium).needs_update);
+    assert!(recommend_compute_budget(priced, &RECENT_FEES, PriorityLevel::High).needs_update);
+
+    // Nothing to recommend when nobody is paying priority fees
+    assert!(!recommend_compute_budget(RequestedComputeBudget::of(&transfer(&[]).into()), &[], PriorityLevel::High).needs_update);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_client_recommends_budget_for_raw_transaction() {
+    let client = SolanaClient::new(&start_mock_rpc().await, "confirmed").unwrap();
+    let transaction = transfer(&[
+        ComputeBudgetInstruction::set_compute_unit_limit(1_000),
+        ComputeBudgetInstruction::set_compute_unit_price(100),
+    ]);
+
+    let recommendation = client.recommend_compute_budget(&encode(&transaction), PriorityLevel::High).await.unwrap();
+
+    assert_eq!(recommendation.compute_unit_price, 10_000);
+    // The transaction's own limit is kept
+    assert_eq!(recommendation.compute_unit_limit, 1_000);
+    assert_eq!(recommendation.priority_fee_lamports, 10);
+    assert!(recommendation.needs_update);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_estimate_includes_priority_fee() {
+    let client = SolanaClient::new(&start_mock_rpc().await, "confirmed").unwrap();
+    let estimate = client.estimate_fee(&encode(&transfer(&[])), PriorityLevel::High).await.unwrap();
+
+    assert_eq!(estimate.fee_lamports, 5_000);
+    assert_eq!(estimate.compute_unit_price, 10_000);
+    assert_eq!(estimate.compute_unit_limit, 200_000);
+    // 10,000 micro-lamports x 200,000 CU
+    assert_eq!(estimate.priority_fee_lamports, 2_000);
+    assert!(estimate.needs_compute_budget_update);
+}
\ No newline at end of file
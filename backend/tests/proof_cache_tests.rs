@@ -0,0 +1,81 @@
+//! Tests for reusing cached proofs of inputs proved before
+//!
+//! Tests that go through the cache need a running Redis instance and are
+//! ignored unless run with `--ignored` and `REDIS_URL` set.
+
+use guardian_aa_backend::zkml::{ProofRequest, ZkmlService};
+use uuid::Uuid;
+
+fn redis_client() -> redis::Client {
+    let url = std::env::var("REDIS_URL").expect("REDIS_URL must be set to run this test");
+    redis::Client::open(url).unwrap()
+}
+
+/// A request for an input no earlier run has cached
+fn unique_request(bypass_cache: bool) -> ProofRequest {
+    ProofRequest {
+        input_data: format!("cache me {}", Uuid::new_v4()).into_bytes(),
+        circuit_type: "sha256".to_string(),
+        bypass_cache,
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs REDIS_URL"]
+async fn test_same_input_is_proved_once() {
+    let client = redis_client();
+    let service = ZkmlService::new().unwrap().with_proof_cache(client, 60);
+    let request = unique_request(false);
+
+    let first = service.generate_proof(&request).await.unwrap();
+    assert_eq!(service.proofs_generated(), 1);
+
+    let second = service.generate_proof(&request).await.unwrap();
+    assert_eq!(service.proofs_generated(), 1, "second request should come from the cache");
+    assert_eq!(second.proof_data, first.proof_data);
+    assert_eq!(second.hash, first.hash);
+    assert!(service.verify_proof(&second, &request.input_data).await.unwrap());
+}
+
+#[tokio::test]
+#[ignore = "needs REDIS_URL"]
+async fn test_circuits_are_cached_separately() {
+    let client = redis_client();
+    let service = ZkmlService::new().unwrap().with_proof_cache(client, 60);
+    let sha256 = unique_request(false);
+    let keccak = ProofRequest { circuit_type: "keccak256".to_string(), ..sha256.clone() };
+
+    service.generate_proof(&sha256).await.unwrap();
+    let proof = service.generate_proof(&keccak).await.unwrap();
+
+    assert_eq!(service.proofs_generated(), 2);
+    assert_eq!(proof.circuit_type, "keccak256");
+}
+
+#[tokio::test]
+#[ignore = "needs REDIS_URL"]
+async fn test_bypass_cache_proves_again() {
+    let client = redis_client();
+    let service = ZkmlService::new().unwrap().with_proof_cache(client, 60);
+    let request = unique_request(false);
+
+    service.generate_proof(&request).await.unwrap();
+    let fresh = service.generate_proof(&ProofRequest { bypass_cache: true, ..request.clone() }).await.unwrap();
+    assert_eq!(service.proofs_generated(), 2);
+
+    // The fresh proof replaced the cached one
+    let cached = service.generate_proof(&request).await.unwrap();
+    assert_eq!(service.proofs_generated(), 2);
+    assert_eq!(cached.proof_data, fresh.proof_data);
+}
+
+#[tokio::test]
+async fn test_without_cache_every_request_is_proved() {
+    let service = ZkmlService::new().unwrap();
+    let request = unique_request(false);
+
+    service.generate_proof(&request).await.unwrap();
+    service.generate_proof(&request).await.unwrap();
+
+    assert_eq!(service.proofs_generated(), 2);
+}
\ No newline at end of file
@@ -0,0 +1,92 @@
+//! Tests for explaining a stored proof's public inputs with the circuit ABI
+//!
+//! The end-to-end test needs a running Postgres instance and is ignored
+//! unless run with `--ignored` and `DATABASE_URL` set.
+
+use guardian_aa_backend::{
+    api::AppState,
+    db::{models::ProofType, queries::ZkmlProofQueries},
+    error::Error,
+    services::{zkml::label_public_inputs, ZkmlProofService},
+};
+use guardian_zkml::abi::sha256_abi;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+mod common;
+
+async fn create_user(state: &AppState) -> Uuid {
+    sqlx::query_scalar("INSERT INTO users (email, password_hash) VALUES ($1, 'hash') RETURNING id")
+        .bind(format!("explain-{}@example.com", Uuid::new_v4()))
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap()
+}
+
+async fn insert_proof(state: &AppState, user_id: Uuid, circuit_type: &str, public_inputs: &[u8]) -> Uuid {
+    ZkmlProofQueries::create(
+        state.db.pool(),
+        None,
+        user_id,
+        ProofType::HashProof,
+        "cHJvb2Y=",
+        &serde_json::Value::String(hex::encode(public_inputs)),
+        "vk",
+        &hex::encode(Sha256::digest(circuit_type.as_bytes())),
+    )
+    .await
+    .unwrap()
+    .id
+}
+
+#[test]
+fn test_hash_bytes_map_to_named_fields() {
+    let hash = Sha256::digest(b"hello world");
+    let labeled = label_public_inputs(&sha256_abi(), &hash).unwrap();
+
+    assert_eq!(labeled.len(), 32);
+    for (i, input) in labeled.iter().enumerate() {
+        assert_eq!(input.name, format!("hash_byte_{}", i));
+        assert_eq!(input.byte_offset, i);
+        assert_eq!(input.value, hash[i]);
+    }
+}
+
+#[test]
+fn test_public_inputs_of_wrong_length_are_not_labeled() {
+    assert!(label_public_inputs(&sha256_abi(), &[0u8; 31]).is_none());
+    assert!(label_public_inputs(&sha256_abi(), &[0u8; 33]).is_none());
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_explain_stored_proof() {
+    let state = common::test_state().await;
+    let user_id = create_user(&state).await;
+    let hash = Sha256::digest(b"hello world");
+    let proof_id = insert_proof(&state, user_id, "sha256", &hash).await;
+
+    let service = ZkmlProofService::new(state.clone());
+    let explanation = service.explain_proof(proof_id, user_id).await.unwrap();
+
+    assert_eq!(explanation.circuit_type, "sha256");
+    assert_eq!(explanation.public_inputs_hex, hex::encode(hash));
+    assert_eq!(explanation.public_inputs[31].name, "hash_byte_31");
+    assert_eq!(explanation.public_inputs[31].value, hash[31]);
+    assert!(!explanation.security_properties.is_empty());
+
+    // Other users can't see the proof
+    let other = service.explain_proof(proof_id, create_user(&state).await).await;
+    assert!(matches!(other, Err(Error::NotFound)));
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_explain_rejects_circuits_without_an_abi() {
+    let state = common::test_state().await;
+    let user_id = create_user(&state).await;
+    let proof_id = insert_proof(&state, user_id, "keccak256", &[0u8; 32]).await;
+
+    let result = ZkmlProofService::new(state).explain_proof(proof_id, user_id).await;
+    assert!(matches!(result, Err(Error::Validation(_))));
+}
\ No newline at end of file
@@ -0,0 +1,155 @@
+//! Tests for the checks on what may be proved
+
+use axum::{extract::{Query, State}, http::StatusCode, Extension, Json};
+use base64::{engine::general_purpose, Engine as _};
+use guardian_aa_backend::{
+    api::{
+        handlers::zkml::{generate_proof, generate_proof_stream, GenerateProofParams, GenerateProofRequest},
+        middleware::auth::UserContext,
+        AppState,
+    },
+    config::{Config, ProofInputPolicy},
+    db::models::Role,
+    error::Error,
+    zkml::{
+        input::{sniff_content_type, PolicyValidator, ProofInput, ProofInputValidator},
+        ZkmlService,
+    },
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+mod common;
+
+/// Only JSON transfer documents may be proved
+fn transfer_policy() -> ProofInputPolicy {
+    ProofInputPolicy {
+        max_bytes: Some(1024),
+        allowed_content_types: vec!["application/json".to_string()],
+        required_json_fields: vec!["wallet".to_string(), "amount".to_string()],
+    }
+}
+
+fn check(policy: ProofInputPolicy, data: &[u8], content_type: Option<&str>) -> Result<(), Error> {
+    PolicyValidator::new(policy).validate(&ProofInput { data, content_type })
+}
+
+/// App state whose connections are only opened on first use; proving
+/// through the stream endpoint needs neither a database nor Redis
+fn lazy_state(zkml_service: ZkmlService) -> Arc<AppState> {
+    let config = Config::default();
+    let db = common::lazy_database(&config);
+    Arc::new(AppState { zkml_service, ..common::app_state(db, config) })
+}
+
+fn proof_request(data: &[u8], content_type: &str) -> GenerateProofRequest {
+    GenerateProofRequest {
+        data: general_purpose::STANDARD.encode(data),
+        circuit_type: None,
+        commitment: None,
+        blinding: None,
+        content_type: Some(content_type.to_string()),
+        bypass_cache: false,
+    }
+}
+
+fn user() -> Extension<UserContext> {
+    Extension(UserContext { user_id: Uuid::new_v4(), email: "prover@example.com".to_string(), role: Role::User })
+}
+
+#[test]
+fn test_default_policy_accepts_anything() {
+    assert!(check(ProofInputPolicy::default(), &[0xff, 0x00, 0x17], None).is_ok());
+    assert!(check(ProofInputPolicy::default(), b"not json", Some("application/json")).is_ok());
+}
+
+#[test]
+fn test_content_is_sniffed_when_no_type_is_declared() {
+    assert_eq!(sniff_content_type(br#"{"wallet": "abc"}"#), "application/json");
+    assert_eq!(sniff_content_type(b"hello"), "text/plain");
+    assert_eq!(sniff_content_type(&[0xff, 0xfe]), "application/octet-stream");
+
+    let document = br#"{"wallet": "abc", "amount": 5}"#;
+    assert!(check(transfer_policy(), document, None).is_ok());
+    assert!(matches!(check(transfer_policy(), b"hello", None), Err(Error::Validation(_))));
+}
+
+#[test]
+fn test_declared_type_is_matched_without_parameters() {
+    let document = br#"{"wallet": "abc", "amount": 5}"#;
+    assert!(check(transfer_policy(), document, Some("Application/JSON; charset=utf-8")).is_ok());
+
+    let Err(Error::Validation(msg)) = check(transfer_policy(), document, Some("text/csv")) else {
+        panic!("text/csv should not be allowed");
+    };
+    assert!(msg.contains("text/csv"));
+}
+
+#[test]
+fn test_json_inputs_must_match_the_schema() {
+    let Err(Error::Validation(msg)) = check(transfer_policy(), br#"{"wallet": "abc"}"#, None) else {
+        panic!("a document without amount should be rejected");
+    };
+    assert!(msg.contains("amount"));
+
+    assert!(matches!(check(transfer_policy(), b"[1, 2]", None), Err(Error::Validation(_))));
+    assert!(matches!(check(transfer_policy(), b"{broken", Some("application/json")), Err(Error::Validation(_))));
+}
+
+#[test]
+fn test_oversized_input_is_rejected() {
+    let policy = ProofInputPolicy { max_bytes: Some(4), ..Default::default() };
+
+    assert!(check(policy.clone(), b"1234", None).is_ok());
+    assert!(matches!(check(policy, b"12345", None), Err(Error::Validation(_))));
+}
+
+#[tokio::test]
+async fn test_disallowed_input_is_rejected_before_proving() {
+    let service = ZkmlService::new().unwrap()
+        .with_input_validator(Arc::new(PolicyValidator::new(transfer_policy())));
+    let state = lazy_state(service);
+
+    let result = generate_proof_stream(
+        State(state.clone()),
+        user(),
+        Json(proof_request(b"wallet,amount\nabc,5", "text/csv")),
+    ).await;
+
+    assert!(matches!(result, Err(Error::Validation(_))));
+    assert!(state.zkml_service.get_status().last_proof_metrics.is_none());
+}
+
+#[tokio::test]
+async fn test_allowed_input_is_proved() {
+    let service = ZkmlService::new().unwrap()
+        .with_input_validator(Arc::new(PolicyValidator::new(transfer_policy())));
+    let state = lazy_state(service);
+
+    let response = generate_proof_stream(
+        State(state.clone()),
+        user(),
+        Json(proof_request(br#"{"wallet": "abc", "amount": 5}"#, "application/json")),
+    ).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(state.zkml_service.get_status().last_proof_metrics.is_some());
+}
+
+#[tokio::test]
+async fn test_oversized_input_is_rejected_before_queueing() {
+    let state = lazy_state(ZkmlService::new().unwrap());
+    let max_input_size = state.zkml_service.get_sha256_circuit_info().max_input_size;
+
+    let result = generate_proof(
+        State(state.clone()),
+        user(),
+        Query(GenerateProofParams { run_async: true }),
+        Json(proof_request(&vec![0u8; max_input_size + 1], "application/octet-stream")),
+    ).await;
+
+    let Err(Error::Validation(msg)) = result else {
+        panic!("expected an oversized input to be rejected");
+    };
+    assert!(msg.contains(&(max_input_size + 1).to_string()));
+}
\ No newline at end of file
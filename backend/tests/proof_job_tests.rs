@@ -0,0 +1,85 @@
+//! Tests for background proof jobs
+
+use guardian_aa_backend::{
+    error::Error,
+    services::{zkml::StoredProof, JobStatus, ProofJob, ProofJobQueue},
+    zkml::ZkmlService,
+};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Poll a job until it finishes
+async fn wait_for_job(queue: &ProofJobQueue, job_id: Uuid, user_id: Uuid) -> ProofJob {
+    for _ in 0..200 {
+        let job = queue.get(job_id, user_id).expect("job should exist");
+        if job.status.is_finished() {
+            return job;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("job {} did not finish", job_id);
+}
+
+#[tokio::test]
+async fn test_submitted_job_completes_with_proof() {
+    let queue = ProofJobQueue::new(1, Duration::from_secs(60));
+    let user_id = Uuid::new_v4();
+
+    let job_id = queue.submit(user_id, async {
+        let proof = ZkmlService::new()?.generate_sha256_proof(b"queued proof").await?;
+        Ok(StoredProof { proof_id: Uuid::new_v4(), proof })
+    });
+
+    let job = queue.get(job_id, user_id).unwrap();
+    assert!(matches!(job.status, JobStatus::Queued | JobStatus::Running | JobStatus::Completed));
+
+    let job = wait_for_job(&queue, job_id, user_id).await;
+    assert_eq!(job.status, JobStatus::Completed);
+    assert!(job.error.is_none());
+
+    let stored = job.proof.expect("completed job should carry its proof");
+    assert_eq!(stored.proof.circuit_type, "sha256");
+    assert!(!stored.proof.proof_data.is_empty());
+}
+
+#[tokio::test]
+async fn test_job_timeout_marks_job_failed() {
+    let queue = ProofJobQueue::new(1, Duration::from_millis(100));
+    let user_id = Uuid::new_v4();
+
+    let job_id = queue.submit(user_id, async {
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        Err(Error::Internal)
+    });
+
+    let job = wait_for_job(&queue, job_id, user_id).await;
+    assert_eq!(job.status, JobStatus::Failed);
+    assert!(job.proof.is_none());
+    assert!(job.error.unwrap().contains("timed out"));
+}
+
+#[tokio::test]
+async fn test_failed_work_reports_error() {
+    let queue = ProofJobQueue::new(1, Duration::from_secs(60));
+    let user_id = Uuid::new_v4();
+
+    let job_id = queue.submit(user_id, async {
+        Err(Error::Validation("input too large".to_string()))
+    });
+
+    let job = wait_for_job(&queue, job_id, user_id).await;
+    assert_eq!(job.status, JobStatus::Failed);
+    assert!(job.error.unwrap().contains("input too large"));
+}
+
+#[tokio::test]
+async fn test_jobs_are_private_to_their_user() {
+    let queue = ProofJobQueue::new(1, Duration::from_secs(60));
+    let user_id = Uuid::new_v4();
+
+    let job_id = queue.submit(user_id, async { Err(Error::Internal) });
+
+    assert!(queue.get(job_id, user_id).is_some());
+    assert!(queue.get(job_id, Uuid::new_v4()).is_none());
+    assert!(queue.get(Uuid::new_v4(), user_id).is_none());
+}
\ No newline at end of file
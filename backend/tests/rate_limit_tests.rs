@@ -0,0 +1,142 @@
+//! Tests for the Redis-backed rate limiting middleware
+//!
+//! Tests that count requests need a running Redis instance and are ignored
+//! unless run with `--ignored` and `REDIS_URL` set.
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header::RETRY_AFTER, StatusCode},
+    middleware,
+    response::Response,
+    routing::post,
+    Router,
+};
+use guardian_aa_backend::{
+    api::middleware::rate_limit::{rate_limit_middleware, RateLimiter, RATE_LIMIT_LIMIT, RATE_LIMIT_REMAINING, RATE_LIMIT_RESET},
+    config::RateLimitRule,
+};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const RULE: RateLimitRule = RateLimitRule { requests: 3, window_secs: 60 };
+
+async fn login_handler() -> &'static str {
+    "ok"
+}
+
+fn app(limiter: RateLimiter) -> Router {
+    Router::new()
+        .route("/login", post(login_handler))
+        .layer(middleware::from_fn_with_state(limiter, rate_limit_middleware))
+}
+
+fn redis_client() -> redis::Client {
+    let url = std::env::var("REDIS_URL").expect("REDIS_URL must be set to run this test");
+    redis::Client::open(url).unwrap()
+}
+
+/// A scope no other test run has counted requests under
+fn unique_scope() -> &'static str {
+    Box::leak(format!("test-{}", Uuid::new_v4()).into_boxed_str())
+}
+
+async fn login_from(app: &Router, ip: &str) -> Response {
+    let request = Request::post("/login")
+        .header("x-forwarded-for", ip)
+        .body(Body::empty())
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap()
+}
+
+fn header(response: &Response, name: impl axum::http::header::AsHeaderName) -> Option<&str> {
+    response.headers().get(name).map(|value| value.to_str().unwrap())
+}
+
+fn seconds(response: &Response, name: impl axum::http::header::AsHeaderName) -> u64 {
+    header(response, name).expect("missing header").parse().unwrap()
+}
+
+#[tokio::test]
+#[ignore = "needs REDIS_URL"]
+async fn test_request_over_limit_is_rejected_with_retry_after() {
+    let redis = redis_client();
+    let app = app(RateLimiter::new(redis, unique_scope(), RULE));
+
+    for expected_remaining in ["2", "1", "0"] {
+        let response = login_from(&app, "203.0.113.7").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(header(&response, RATE_LIMIT_LIMIT), Some("3"));
+        assert_eq!(header(&response, RATE_LIMIT_REMAINING), Some(expected_remaining));
+    }
+
+    let response = login_from(&app, "203.0.113.7").await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(header(&response, RATE_LIMIT_LIMIT), Some("3"));
+    assert_eq!(header(&response, RATE_LIMIT_REMAINING), Some("0"));
+
+    let retry_after = seconds(&response, RETRY_AFTER);
+    assert!((1..=RULE.window_secs).contains(&retry_after));
+    assert_eq!(seconds(&response, RATE_LIMIT_RESET), retry_after);
+}
+
+#[tokio::test]
+#[ignore = "needs REDIS_URL"]
+async fn test_reset_counts_down_to_the_oldest_request_leaving_the_window() {
+    let redis = redis_client();
+    let app = app(RateLimiter::new(redis, unique_scope(), RateLimitRule { requests: 3, window_secs: 2 }));
+
+    let first = login_from(&app, "203.0.113.12").await;
+    assert_eq!(seconds(&first, RATE_LIMIT_RESET), 2);
+
+    tokio::time::sleep(std::time::Duration::from_millis(1200)).await;
+
+    // The window still holds the first request, so the reset follows it
+    // rather than restarting from the latest
+    let second = login_from(&app, "203.0.113.12").await;
+    assert_eq!(header(&second, RATE_LIMIT_REMAINING), Some("1"));
+    assert_eq!(seconds(&second, RATE_LIMIT_RESET), 1);
+
+    tokio::time::sleep(std::time::Duration::from_millis(1000)).await;
+
+    // Once the first ages out its slot is free again
+    let third = login_from(&app, "203.0.113.12").await;
+    assert_eq!(header(&third, RATE_LIMIT_REMAINING), Some("1"));
+}
+
+#[tokio::test]
+#[ignore = "needs REDIS_URL"]
+async fn test_clients_are_counted_separately() {
+    let redis = redis_client();
+    let app = app(RateLimiter::new(redis, unique_scope(), RULE));
+
+    for _ in 0..RULE.requests {
+        login_from(&app, "203.0.113.8").await;
+    }
+    assert_eq!(login_from(&app, "203.0.113.8").await.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    let response = login_from(&app, "198.51.100.9").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header(&response, RATE_LIMIT_REMAINING), Some("2"));
+}
+
+#[tokio::test]
+async fn test_unreachable_redis_lets_requests_through() {
+    let redis = redis::Client::open("redis://127.0.0.1:9").unwrap();
+    let app = app(RateLimiter::new(redis, unique_scope(), RateLimitRule { requests: 0, window_secs: 60 }));
+
+    let response = login_from(&app, "203.0.113.10").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(header(&response, RATE_LIMIT_REMAINING).is_none());
+    assert!(header(&response, RATE_LIMIT_RESET).is_none());
+}
+
+#[tokio::test]
+async fn test_disabled_limiter_does_not_count() {
+    let redis = redis::Client::open("redis://127.0.0.1:9").unwrap();
+    let app = app(RateLimiter::new(redis, unique_scope(), RateLimitRule { requests: 0, window_secs: 60 }).disabled());
+
+    let response = login_from(&app, "203.0.113.11").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(header(&response, RATE_LIMIT_REMAINING).is_none());
+}
\ No newline at end of file
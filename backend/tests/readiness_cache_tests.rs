@@ -0,0 +1,133 @@
+//! Tests for reusing recent dependency checks in the readiness endpoint
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{Request, StatusCode},
+    routing::post,
+    Json, Router,
+};
+use guardian_aa_backend::{
+    api::{create_router, AppState},
+    config::Config,
+    db::Database,
+};
+use sqlx::postgres::PgPoolOptions;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tower::ServiceExt;
+
+mod common;
+
+#[derive(Clone, Default)]
+struct MockRpc {
+    health_checks: Arc<AtomicUsize>,
+    unhealthy: Arc<AtomicBool>,
+}
+
+async fn handle_rpc(State(mock): State<MockRpc>, Json(request): Json<serde_json::Value>) -> Json<serde_json::Value> {
+    if request["method"] == "getHealth" {
+        mock.health_checks.fetch_add(1, Ordering::SeqCst);
+        if mock.unhealthy.load(Ordering::SeqCst) {
+            return Json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "error": { "code": -32005, "message": "Node is behind" }
+            }));
+        }
+    }
+
+    Json(serde_json::json!({ "jsonrpc": "2.0", "id": request["id"], "result": "ok" }))
+}
+
+/// Start a Solana JSON-RPC server that counts `getHealth` calls
+async fn start_mock_rpc() -> (String, MockRpc) {
+    let mock = MockRpc::default();
+    let app = Router::new().route("/", post(handle_rpc)).with_state(mock.clone());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    (url, mock)
+}
+
+/// App state checking the mock RPC. Postgres and Redis point at a closed
+/// port, so their checks fail fast.
+fn state(rpc_url: &str, readiness_cache_secs: u64) -> Arc<AppState> {
+    let mut config = Config::default();
+    config.server.readiness_cache_secs = readiness_cache_secs;
+    config.blockchain.solana_rpc_url = rpc_url.to_string();
+    config.database.url = "postgres://guardian@127.0.0.1:1/guardian".to_string();
+    config.redis.url = "redis://127.0.0.1:1".to_string();
+
+    let pool = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(200))
+        .connect_lazy(&config.database.url)
+        .unwrap();
+    Arc::new(common::app_state(Database::from_pools(pool, None), config))
+}
+
+/// Status code and the Solana RPC check from `/ready`
+async fn ready(app: &Router) -> (StatusCode, serde_json::Value) {
+    let request = Request::builder().uri("/ready").body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let solana = body["checks"].as_array().unwrap().iter()
+        .find(|check| check["name"] == "solana_rpc")
+        .unwrap()
+        .clone();
+    (status, solana)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_rapid_polls_share_one_solana_health_check() {
+    let (url, mock) = start_mock_rpc().await;
+    let app = create_router(state(&url, 60));
+
+    let (status, first) = ready(&app).await;
+    let (_, second) = ready(&app).await;
+
+    // The unreachable database keeps the service unready either way
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(first["status"], "ready");
+    assert_eq!(second, first);
+    assert_eq!(mock.health_checks.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_zero_interval_checks_every_time() {
+    let (url, mock) = start_mock_rpc().await;
+    let app = create_router(state(&url, 0));
+
+    ready(&app).await;
+    ready(&app).await;
+
+    assert_eq!(mock.health_checks.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_failed_check_is_retried_before_the_interval_ends() {
+    let (url, mock) = start_mock_rpc().await;
+    mock.unhealthy.store(true, Ordering::SeqCst);
+    let app = create_router(state(&url, 60));
+
+    let (_, failed) = ready(&app).await;
+    assert_eq!(failed["status"], "not_ready");
+
+    // Still within the short wait for failed checks
+    ready(&app).await;
+    assert_eq!(mock.health_checks.load(Ordering::SeqCst), 1);
+
+    mock.unhealthy.store(false, Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_millis(1_100)).await;
+
+    let (_, recovered) = ready(&app).await;
+    assert_eq!(recovered["status"], "ready");
+    assert_eq!(mock.health_checks.load(Ordering::SeqCst), 2);
+}
\ No newline at end of file
@@ -0,0 +1,93 @@
+//! Tests for rejecting refresh tokens from idle sessions
+//!
+//! These tests need a running Postgres instance and are ignored unless run
+//! with `--ignored` and `DATABASE_URL` set.
+
+use chrono::{Duration, Utc};
+use guardian_aa_backend::{
+    api::{handlers::auth::RefreshTokenRequest, AppState},
+    db::queries::UserSessionQueries,
+    error::Error,
+    services::AuthService,
+};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use uuid::Uuid;
+
+mod common;
+
+const IDLE_TIMEOUT_SECS: i64 = 3600;
+
+async fn test_state() -> Arc<AppState> {
+    let mut config = common::database_config();
+    config.auth.refresh_idle_timeout = IDLE_TIMEOUT_SECS;
+    common::connect(config).await
+}
+
+/// Create a user with a session last used `idle` ago, returning the
+/// session's refresh token and id
+async fn create_session(state: &AppState, idle: Duration) -> (String, Uuid) {
+    let user_id: Uuid = sqlx::query_scalar("INSERT INTO users (email, password_hash) VALUES ($1, 'hash') RETURNING id")
+        .bind(format!("sessions-{}@example.com", Uuid::new_v4()))
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap();
+
+    let refresh_token = Uuid::new_v4().to_string();
+    let token_hash = format!("{:x}", Sha256::digest(refresh_token.as_bytes()));
+    let session = UserSessionQueries::create(
+        state.db.pool(),
+        user_id,
+        &token_hash,
+        Utc::now() + Duration::days(7),
+        None,
+        None,
+    ).await.unwrap();
+
+    sqlx::query("UPDATE user_sessions SET last_used_at = $1 WHERE id = $2")
+        .bind(Utc::now() - idle)
+        .bind(session.id)
+        .execute(state.db.pool())
+        .await
+        .unwrap();
+
+    (refresh_token, session.id)
+}
+
+async fn session_exists(state: &AppState, session_id: Uuid) -> bool {
+    sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM user_sessions WHERE id = $1)")
+        .bind(session_id)
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_recently_used_session_refreshes() {
+    let state = test_state().await;
+    let (refresh_token, session_id) = create_session(&state, Duration::minutes(5)).await;
+
+    let response = AuthService::new(state.clone())
+        .refresh_token(RefreshTokenRequest { refresh_token })
+        .await
+        .unwrap();
+
+    assert!(!response.access_token.is_empty());
+    // Refreshing rotates the session rather than extending it
+    assert!(!session_exists(&state, session_id).await);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_idle_session_is_rejected_and_revoked() {
+    let state = test_state().await;
+    let (refresh_token, session_id) = create_session(&state, Duration::seconds(IDLE_TIMEOUT_SECS + 60)).await;
+
+    let result = AuthService::new(state.clone())
+        .refresh_token(RefreshTokenRequest { refresh_token })
+        .await;
+
+    assert!(matches!(result, Err(Error::AuthenticationFailed)));
+    assert!(!session_exists(&state, session_id).await);
+}
\ No newline at end of file
@@ -0,0 +1,77 @@
+//! Tests for request ids in responses and error bodies
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    routing::get,
+    Extension, Router,
+};
+use guardian_aa_backend::{
+    api::middleware::request_id::{request_id_middleware, RequestId, REQUEST_ID_HEADER},
+    Error,
+};
+use tower::ServiceExt;
+
+fn app() -> Router {
+    Router::new()
+        .route("/failed-login", get(|| async { Err::<(), _>(Error::AuthenticationFailed) }))
+        .route("/invalid", get(|| async { Err::<(), _>(Error::Validation("amount".to_string())) }))
+        .route("/id", get(|Extension(RequestId(id)): Extension<RequestId>| async move { id }))
+        .layer(axum::middleware::from_fn(request_id_middleware))
+}
+
+async fn call(uri: &str, request_id: Option<&str>) -> (StatusCode, Option<String>, Vec<u8>) {
+    let mut request = Request::builder().uri(uri);
+    if let Some(request_id) = request_id {
+        request = request.header(REQUEST_ID_HEADER, request_id);
+    }
+
+    let response = app().oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+    let status = response.status();
+    let header = response
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .map(|value| value.to_str().unwrap().to_string());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, header, body.to_vec())
+}
+
+#[tokio::test]
+async fn test_error_body_has_code_and_request_id() {
+    let (status, header, body) = call("/failed-login", None).await;
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "authentication_failed");
+    let header = header.expect("response has a request id");
+    assert_eq!(body["request_id"], header.as_str());
+}
+
+#[tokio::test]
+async fn test_validation_error_body_has_request_id() {
+    let (_, _, body) = call("/invalid", Some("trace-123")).await;
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(body["code"], "validation_error");
+    assert_eq!(body["request_id"], "trace-123");
+}
+
+#[tokio::test]
+async fn test_caller_request_id_is_echoed() {
+    let (status, header, body) = call("/id", Some("client-abc")).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(header.as_deref(), Some("client-abc"));
+    // Handlers see the same id
+    assert_eq!(body, b"client-abc");
+}
+
+#[tokio::test]
+async fn test_unusable_request_id_is_replaced() {
+    let (_, first, _) = call("/id", Some(&"x".repeat(200))).await;
+    let (_, second, _) = call("/id", None).await;
+
+    let first = first.unwrap();
+    assert_eq!(first.len(), 36);
+    assert_ne!(Some(first), second);
+}
\ No newline at end of file
@@ -0,0 +1,122 @@
+//! Tests for the sampled request logging middleware
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    middleware,
+    routing::get,
+    Router,
+};
+use guardian_aa_backend::{
+    api::middleware::logging::{request_logging_middleware, RequestLogSampler, RequestMetrics},
+    config::LoggingConfig,
+};
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use tower::ServiceExt;
+
+// Writer that keeps everything the subscriber formats
+#[derive(Clone, Default)]
+struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+impl CapturedLogs {
+    fn contents(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn create_test_app(config: &LoggingConfig, metrics: Arc<RequestMetrics>) -> Router {
+    let sampler = RequestLogSampler::new(config, metrics);
+
+    Router::new()
+        .route("/ok", get(|| async { "ok" }))
+        .route("/fail", get(|| async { StatusCode::INTERNAL_SERVER_ERROR }))
+        .layer(middleware::from_fn_with_state(sampler, request_logging_middleware))
+}
+
+async fn send(app: &Router, uri: &str) -> StatusCode {
+    let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+    app.clone().oneshot(request).await.unwrap().status()
+}
+
+#[tokio::test]
+async fn test_zero_sample_rate_still_logs_errors() {
+    let logs = CapturedLogs::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .with_ansi(false)
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let config = LoggingConfig {
+        sample_rate: 0.0,
+        slow_request_threshold_ms: 60_000,
+    };
+    let metrics = Arc::new(RequestMetrics::default());
+    let app = create_test_app(&config, metrics.clone());
+
+    assert_eq!(send(&app, "/ok").await, StatusCode::OK);
+    assert!(!logs.contents().contains("INFO"));
+
+    assert_eq!(send(&app, "/fail").await, StatusCode::INTERNAL_SERVER_ERROR);
+    let output = logs.contents();
+    assert!(output.contains("ERROR"));
+    assert!(output.contains("/fail"));
+
+    // Sampled-out requests are still counted
+    assert_eq!(metrics.total(), 2);
+    assert_eq!(metrics.errors(), 1);
+}
+
+#[tokio::test]
+async fn test_full_sample_rate_logs_successful_requests() {
+    let logs = CapturedLogs::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .with_ansi(false)
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let metrics = Arc::new(RequestMetrics::default());
+    let app = create_test_app(&LoggingConfig::default(), metrics.clone());
+
+    assert_eq!(send(&app, "/ok").await, StatusCode::OK);
+    assert!(logs.contents().contains("Request completed"));
+    assert_eq!(metrics.total(), 1);
+    assert_eq!(metrics.errors(), 0);
+}
+
+#[tokio::test]
+async fn test_slow_requests_are_always_logged() {
+    let logs = CapturedLogs::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .with_ansi(false)
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let config = LoggingConfig {
+        sample_rate: 0.0,
+        slow_request_threshold_ms: 0,
+    };
+    let metrics = Arc::new(RequestMetrics::default());
+    let app = create_test_app(&config, metrics.clone());
+
+    assert_eq!(send(&app, "/ok").await, StatusCode::OK);
+    assert!(logs.contents().contains("Slow request"));
+    assert_eq!(metrics.slow(), 1);
+}
@@ -0,0 +1,204 @@
+//! Tests for restricting routes by the user's role
+//!
+//! The tests against the application's own routes need running Postgres and
+//! Redis instances and are ignored unless run with `--ignored` and both
+//! `DATABASE_URL` and `REDIS_URL` set.
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header::AUTHORIZATION, StatusCode},
+    middleware,
+    routing::post,
+    Router,
+};
+use guardian_aa_backend::{
+    api::{
+        create_router,
+        handlers::auth::{LoginRequest, RegisterRequest},
+        middleware::auth::{auth_middleware, decode_claims, require_role, AuthState},
+        AppState,
+    },
+    config::Config,
+    db::models::Role,
+    services::{AdminService, AuthService},
+};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use std::sync::Arc;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+mod common;
+
+fn config() -> Arc<Config> {
+    let mut config = Config::default();
+    config.auth.jwt_secret = "role-test-secret".to_string();
+    Arc::new(config)
+}
+
+/// An access token whose claims include `role`, or leave it out when `None`
+fn token(config: &Config, role: Option<&str>) -> String {
+    let now = chrono::Utc::now().timestamp();
+    let mut claims = serde_json::json!({
+        "sub": Uuid::new_v4().to_string(),
+        "email": "role@example.com",
+        "exp": now + 3600,
+        "iat": now,
+        "iss": config.auth.jwt_issuer,
+        "aud": config.auth.jwt_audiences[0],
+        "jti": Uuid::new_v4().to_string(),
+        "typ": "access",
+    });
+    if let Some(role) = role {
+        claims["role"] = serde_json::json!(role);
+    }
+
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(config.auth.jwt_secret.as_bytes())).unwrap()
+}
+
+/// An admin-only cleanup route behind authentication, layered as the
+/// application does
+fn app(config: Arc<Config>) -> Router {
+    Router::new()
+        .route("/agent/cleanup", post(|| async { "cleaned" }))
+        .route_layer(middleware::from_fn_with_state(Role::Admin, require_role))
+        .route_layer(middleware::from_fn_with_state(AuthState::new(config), auth_middleware))
+}
+
+async fn cleanup_status(config: &Arc<Config>, token: &str) -> StatusCode {
+    let request = Request::builder()
+        .method("POST")
+        .uri("/agent/cleanup")
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+
+    app(config.clone()).oneshot(request).await.unwrap().status()
+}
+
+#[tokio::test]
+async fn test_admin_token_passes_admin_route() {
+    let config = config();
+    assert_eq!(cleanup_status(&config, &token(&config, Some("admin"))).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_user_token_is_forbidden_on_admin_route() {
+    let config = config();
+    assert_eq!(cleanup_status(&config, &token(&config, Some("user"))).await, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_token_without_role_is_a_regular_user() {
+    let config = config();
+    let legacy = token(&config, None);
+
+    assert_eq!(decode_claims(&legacy, &config.auth).unwrap().role, Role::User);
+    assert_eq!(cleanup_status(&config, &legacy).await, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_missing_token_is_unauthorized_before_role_check() {
+    let request = Request::builder()
+        .method("POST")
+        .uri("/agent/cleanup")
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app(config()).oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[test]
+fn test_admin_outranks_user() {
+    assert!(Role::Admin > Role::User);
+    assert_eq!(Role::from_admin_flag(true), Role::Admin);
+    assert_eq!(Role::from_admin_flag(false), Role::User);
+}
+
+const PASSWORD: &str = "correct horse battery";
+
+/// Connected app state that looks every account up afresh
+async fn connected_state() -> Arc<AppState> {
+    let mut config = common::database_and_redis_config();
+    config.rate_limit.enabled = false;
+    config.auth.account_status_cache_secs = 0;
+    common::connect(config).await
+}
+
+/// Register a user, returning their email, id and access token
+async fn register(state: &Arc<AppState>) -> (String, Uuid, String) {
+    let email = format!("role-{}@example.com", Uuid::new_v4());
+    let registered = AuthService::new(state.clone())
+        .register(RegisterRequest { email: email.clone(), password: PASSWORD.to_string(), username: None })
+        .await
+        .unwrap();
+    let user_id = decode_claims(&registered.access_token, &state.config.auth).unwrap().sub.parse().unwrap();
+
+    (email, user_id, registered.access_token)
+}
+
+async fn maintenance_status(state: &Arc<AppState>, token: &str) -> StatusCode {
+    let request = Request::builder()
+        .uri("/api/v1/admin/maintenance")
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+
+    create_router(state.clone()).oneshot(request).await.unwrap().status()
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_role_comes_from_the_account_not_the_token() {
+    let state = connected_state().await;
+    let (_, user_id, token) = register(&state).await;
+    assert_eq!(decode_claims(&token, &state.config.auth).unwrap().role, Role::User);
+
+    let set_admin = |is_admin: bool| {
+        let state = state.clone();
+        async move {
+            sqlx::query("UPDATE users SET is_admin = $2 WHERE id = $1")
+                .bind(user_id)
+                .bind(is_admin)
+                .execute(state.db.pool())
+                .await
+                .unwrap();
+        }
+    };
+
+    set_admin(true).await;
+    assert_eq!(maintenance_status(&state, &token).await, StatusCode::OK);
+
+    set_admin(false).await;
+    assert_eq!(maintenance_status(&state, &token).await, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_changing_role_revokes_tokens() {
+    let state = connected_state().await;
+    let (email, user_id, token) = register(&state).await;
+
+    AdminService::new(state.clone()).set_role(user_id, Role::Admin).await.unwrap();
+    assert_eq!(maintenance_status(&state, &token).await, StatusCode::UNAUTHORIZED);
+
+    // Revocation covers tokens issued up to the second it happened
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    let login = AuthService::new(state.clone())
+        .login(LoginRequest { email, password: PASSWORD.to_string() })
+        .await
+        .unwrap();
+    assert_eq!(decode_claims(&login.access_token, &state.config.auth).unwrap().role, Role::Admin);
+    assert_eq!(maintenance_status(&state, &login.access_token).await, StatusCode::OK);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_setting_the_current_role_keeps_tokens() {
+    let state = connected_state().await;
+    let (_, user_id, token) = register(&state).await;
+
+    AdminService::new(state.clone()).set_role(user_id, Role::User).await.unwrap();
+    assert_eq!(maintenance_status(&state, &token).await, StatusCode::FORBIDDEN);
+}
\ No newline at end of file
@@ -0,0 +1,186 @@
+//! Tests for the refresh token session lifecycle
+//!
+//! These tests need a running Postgres instance and are ignored unless run
+//! with `--ignored` and `DATABASE_URL` set.
+
+use guardian_aa_backend::{
+    api::{
+        create_router,
+        handlers::auth::{
+            ClientInfo, LoginRequest, LogoutRequest, RefreshTokenRequest, RegisterRequest, RevokeSessionsRequest,
+        },
+        AppState,
+    },
+    db::models::UserSession,
+    error::Error,
+    services::AuthService,
+};
+use axum::{
+    body::Body,
+    http::{header::{AUTHORIZATION, USER_AGENT}, HeaderMap, HeaderValue, Request, StatusCode},
+};
+use sha2::{Digest, Sha256};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+mod common;
+
+fn client_info() -> ClientInfo {
+    let mut headers = HeaderMap::new();
+    headers.insert(USER_AGENT, HeaderValue::from_static("guardian-tests/1.0"));
+    headers.insert("x-forwarded-for", HeaderValue::from_static("203.0.113.7, 10.0.0.1"));
+    ClientInfo::from_headers(&headers)
+}
+
+async fn find_session(state: &AppState, refresh_token: &str) -> Option<UserSession> {
+    sqlx::query_as("SELECT * FROM user_sessions WHERE refresh_token_hash = $1")
+        .bind(format!("{:x}", Sha256::digest(refresh_token.as_bytes())))
+        .fetch_optional(state.db.pool())
+        .await
+        .unwrap()
+}
+
+#[test]
+fn test_client_info_from_headers() {
+    let info = client_info();
+    assert_eq!(info.user_agent.as_deref(), Some("guardian-tests/1.0"));
+    assert_eq!(info.ip_address.unwrap().ip().to_string(), "203.0.113.7");
+
+    let mut headers = HeaderMap::new();
+    headers.insert("x-real-ip", HeaderValue::from_static("198.51.100.2"));
+    assert_eq!(ClientInfo::from_headers(&headers).ip_address.unwrap().ip().to_string(), "198.51.100.2");
+
+    let empty = ClientInfo::from_headers(&HeaderMap::new());
+    assert!(empty.user_agent.is_none());
+    assert!(empty.ip_address.is_none());
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_register_refresh_rotate_logout() {
+    let state = common::test_state().await;
+    let auth = || AuthService::new(state.clone()).with_client_info(client_info());
+
+    // Registering starts a session for the issued refresh token
+    let registered = auth()
+        .register(RegisterRequest {
+            email: format!("lifecycle-{}@example.com", Uuid::new_v4()),
+            password: "correct horse battery".to_string(),
+            username: None,
+        })
+        .await
+        .unwrap();
+    let session = find_session(&state, &registered.refresh_token).await.expect("session was not stored");
+    assert_eq!(session.user_agent.as_deref(), Some("guardian-tests/1.0"));
+    assert_eq!(session.ip_address.unwrap().ip().to_string(), "203.0.113.7");
+
+    // Refreshing rotates the token
+    let refreshed = auth()
+        .refresh_token(RefreshTokenRequest { refresh_token: registered.refresh_token.clone() })
+        .await
+        .unwrap();
+    assert_ne!(refreshed.refresh_token, registered.refresh_token);
+    assert!(find_session(&state, &registered.refresh_token).await.is_none());
+    assert!(find_session(&state, &refreshed.refresh_token).await.is_some());
+
+    // The old token can't be replayed
+    let replayed = auth()
+        .refresh_token(RefreshTokenRequest { refresh_token: registered.refresh_token.clone() })
+        .await;
+    assert!(matches!(replayed, Err(Error::AuthenticationFailed)));
+
+    // Logging out ends the current session
+    auth()
+        .logout(LogoutRequest { refresh_token: refreshed.refresh_token.clone() })
+        .await
+        .unwrap();
+    assert!(find_session(&state, &refreshed.refresh_token).await.is_none());
+
+    let after_logout = auth()
+        .refresh_token(RefreshTokenRequest { refresh_token: refreshed.refresh_token })
+        .await;
+    assert!(matches!(after_logout, Err(Error::AuthenticationFailed)));
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_list_and_revoke_sessions() {
+    let state = common::test_state().await;
+    let auth = || AuthService::new(state.clone()).with_client_info(client_info());
+    let email = format!("sessions-{}@example.com", Uuid::new_v4());
+    let password = "correct horse battery".to_string();
+
+    // Registering and logging in twice gives three sessions
+    let first = auth()
+        .register(RegisterRequest { email: email.clone(), password: password.clone(), username: None })
+        .await
+        .unwrap();
+    let second = auth().login(LoginRequest { email: email.clone(), password: password.clone() }).await.unwrap();
+    let third = auth().login(LoginRequest { email, password }).await.unwrap();
+    let user_id = find_session(&state, &first.refresh_token).await.unwrap().user_id;
+
+    let sessions = auth().list_sessions(user_id).await.unwrap();
+    assert_eq!(sessions.len(), 3);
+    assert_eq!(sessions[0].user_agent.as_deref(), Some("guardian-tests/1.0"));
+    assert_eq!(sessions[0].ip_address.unwrap().to_string(), "203.0.113.7");
+    let listed = serde_json::to_value(&sessions).unwrap().to_string();
+    assert!(!listed.contains("refresh_token_hash"));
+
+    // Another user can't revoke the session, and doesn't learn it exists
+    let second_session = find_session(&state, &second.refresh_token).await.unwrap();
+    let stranger = auth().revoke_session(Uuid::new_v4(), second_session.id).await;
+    assert!(matches!(stranger, Err(Error::NotFound)));
+
+    // A revoked session's refresh token stops working
+    auth().revoke_session(user_id, second_session.id).await.unwrap();
+    assert_eq!(auth().list_sessions(user_id).await.unwrap().len(), 2);
+    let refreshed = auth()
+        .refresh_token(RefreshTokenRequest { refresh_token: second.refresh_token })
+        .await;
+    assert!(matches!(refreshed, Err(Error::AuthenticationFailed)));
+
+    // Revoking the others keeps only the current session
+    let revoked = auth()
+        .revoke_other_sessions(user_id, RevokeSessionsRequest { refresh_token: third.refresh_token.clone() })
+        .await
+        .unwrap();
+    assert_eq!(revoked, 1);
+    let remaining = auth().list_sessions(user_id).await.unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert!(find_session(&state, &first.refresh_token).await.is_none());
+    assert!(auth().refresh_token(RefreshTokenRequest { refresh_token: third.refresh_token }).await.is_ok());
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_refresh_token_is_not_an_access_token() {
+    // The protected routes check the denylist, which needs Redis
+    let mut config = common::database_and_redis_config();
+    config.rate_limit.enabled = false;
+    let state = common::connect(config).await;
+
+    let registered = AuthService::new(state.clone())
+        .register(RegisterRequest {
+            email: format!("lifecycle-{}@example.com", Uuid::new_v4()),
+            password: "correct horse battery".to_string(),
+            username: None,
+        })
+        .await
+        .unwrap();
+
+    let app = create_router(state);
+    let status = |token: String| {
+        let app = app.clone();
+        async move {
+            let request = Request::builder()
+                .uri("/api/v1/auth/sessions")
+                .header(AUTHORIZATION, format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap();
+            app.oneshot(request).await.unwrap().status()
+        }
+    };
+
+    assert_eq!(status(registered.refresh_token).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(status(registered.access_token).await, StatusCode::OK);
+}
\ No newline at end of file
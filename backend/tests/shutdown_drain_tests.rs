@@ -0,0 +1,67 @@
+//! Tests for readiness while the server drains during shutdown, and for
+//! WebSockets being closed cleanly at the end of it
+
+use axum::{body::Body, http::{Request, StatusCode}};
+use futures_util::StreamExt;
+use guardian_aa_backend::{
+    api::{create_router, AppState},
+    config::Config,
+};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::{protocol::frame::coding::CloseCode, Message};
+use tower::ServiceExt;
+
+mod common;
+
+/// App state whose connections are only opened on first use, so no
+/// database or Redis is needed while draining
+fn lazy_state() -> Arc<AppState> {
+    common::lazy_state(Config::default())
+}
+
+async fn get_status(state: &Arc<AppState>, uri: &str) -> StatusCode {
+    let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+    create_router(state.clone()).oneshot(request).await.unwrap().status()
+}
+
+#[tokio::test]
+async fn test_draining_fails_readiness_but_not_health() {
+    let state = lazy_state();
+    state.draining.store(true, Ordering::SeqCst);
+
+    assert_eq!(get_status(&state, "/ready").await, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(get_status(&state, "/health").await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_shutdown_sends_websockets_a_close_frame() {
+    let state = lazy_state();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}/ws", listener.local_addr().unwrap());
+    let app = create_router(state.clone());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(url.as_str()).await.unwrap();
+    let welcome = socket.next().await.unwrap().unwrap();
+    assert!(welcome.is_text());
+    assert_eq!(state.websocket_shutdown.active(), 1);
+
+    let websockets = state.websocket_shutdown.clone();
+    let closing = tokio::spawn(async move { websockets.close_all(Duration::from_secs(5)).await });
+
+    let frame = loop {
+        match socket.next().await {
+            Some(Ok(Message::Close(frame))) => break frame,
+            Some(Ok(_)) => continue,
+            other => panic!("expected a close frame, got {:?}", other),
+        }
+    };
+    assert_eq!(frame.expect("close frame should carry a code").code, CloseCode::Away);
+
+    assert!(closing.await.unwrap());
+    assert_eq!(state.websocket_shutdown.active(), 0);
+}
\ No newline at end of file
@@ -0,0 +1,86 @@
+//! Tests for failing over between Solana RPC endpoints
+
+use axum::{routing::post, Json, Router};
+use guardian_aa_backend::{
+    blockchain::{RetryPolicy, SolanaClient},
+    config::BlockchainConfig,
+};
+use std::time::Duration;
+
+const ADDRESS: &str = "11111111111111111111111111111111";
+
+/// Nothing listens on the discard port, so connections are refused
+const UNREACHABLE_URL: &str = "http://127.0.0.1:9";
+
+/// Start a JSON-RPC server that answers every request with a balance
+async fn start_mock_rpc() -> String {
+    let app = Router::new().route("/", post(|Json(request): Json<serde_json::Value>| async move {
+        Json(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": request["id"],
+            "result": { "context": { "slot": 1 }, "value": 42 }
+        }))
+    }));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    url
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_unreachable_primary_fails_over_to_secondary() {
+    let secondary = start_mock_rpc().await;
+    let client = SolanaClient::with_endpoints(&[UNREACHABLE_URL.to_string(), secondary.clone()], "confirmed")
+        .unwrap()
+        .with_retry_policy(RetryPolicy { max_attempts: 3, base_delay: Duration::from_millis(10) });
+    assert_eq!(client.current_endpoint(), UNREACHABLE_URL);
+
+    let balance = client.get_balance(ADDRESS).await.unwrap();
+    assert_eq!(balance.sol_balance, 42);
+    assert_eq!(client.current_endpoint(), secondary);
+
+    let health = client.endpoint_health();
+    assert!(!health[0].healthy);
+    assert!(health[1].healthy && health[1].current);
+
+    // Later calls go straight to the secondary
+    assert_eq!(client.get_balance(ADDRESS).await.unwrap().sol_balance, 42);
+    assert_eq!(client.current_endpoint(), secondary);
+}
+
+#[test]
+fn test_endpoints_require_at_least_one_url() {
+    assert!(SolanaClient::with_endpoints(&[], "confirmed").is_err());
+}
+
+#[test]
+fn test_rpc_urls_accept_single_url_or_list() {
+    let single: BlockchainConfig = serde_json::from_value(serde_json::json!({
+        "solana_rpc_url": "https://primary.example.com",
+        "guardian_program_id": "11111111111111111111111111111111",
+        "commitment": "confirmed"
+    }))
+    .unwrap();
+    assert_eq!(single.rpc_urls(), vec!["https://primary.example.com"]);
+
+    let joined: BlockchainConfig = serde_json::from_value(serde_json::json!({
+        "solana_rpc_url": "https://primary.example.com",
+        "solana_rpc_urls": "https://backup.example.com, https://primary.example.com",
+        "guardian_program_id": "11111111111111111111111111111111",
+        "commitment": "confirmed"
+    }))
+    .unwrap();
+    assert_eq!(joined.rpc_urls(), vec!["https://primary.example.com", "https://backup.example.com"]);
+
+    let list_only: BlockchainConfig = serde_json::from_value(serde_json::json!({
+        "solana_rpc_urls": ["https://a.example.com", "https://b.example.com"],
+        "guardian_program_id": "11111111111111111111111111111111",
+        "commitment": "confirmed"
+    }))
+    .unwrap();
+    assert_eq!(list_only.rpc_urls(), vec!["https://a.example.com", "https://b.example.com"]);
+}
\ No newline at end of file
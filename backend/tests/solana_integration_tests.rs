@@ -1,6 +1,10 @@
 //! Tests for Solana blockchain integration
 
-use guardian_aa_backend::blockchain::SolanaClient;
+use guardian_aa_backend::{
+    blockchain::{PriorityLevel, SolanaClient},
+    error::Error,
+};
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
 
 #[tokio::test(flavor = "multi_thread")]
 async fn test_solana_client_creation() {
@@ -54,7 +58,7 @@ async fn test_transaction_deserialization_invalid_data() {
     let client = SolanaClient::new("https://api.devnet.solana.com", "confirmed").unwrap();
     
     // Test with invalid transaction data
-    let result = client.estimate_fee("invalid_transaction_data").await;
+    let result = client.estimate_fee("invalid_transaction_data", PriorityLevel::Medium).await;
     assert!(result.is_err());
 }
 
@@ -66,4 +70,29 @@ async fn test_get_current_slot() {
     let slot = client.get_current_slot().await;
     // Don't assert success as it depends on network connectivity
     println!("Current slot result: {:?}", slot);
-} 
\ No newline at end of file
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_airdrop_refused_on_mainnet() {
+    let client = SolanaClient::new("https://api.mainnet-beta.solana.com", "confirmed").unwrap();
+    assert!(client.is_mainnet());
+
+    let result = client.request_airdrop(&Pubkey::new_unique(), LAMPORTS_PER_SOL).await;
+    assert!(matches!(result, Err(Error::BadRequest(_))));
+}
+
+/// Talks to the public devnet faucet, which is rate limited, so it only runs
+/// when asked for
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "needs the public devnet faucet"]
+async fn test_devnet_airdrop_increases_balance() {
+    let client = SolanaClient::new("https://api.devnet.solana.com", "confirmed").unwrap();
+    assert!(!client.is_mainnet());
+    let address = Pubkey::new_unique();
+
+    let before = client.get_balance(&address.to_string()).await.unwrap().sol_balance;
+    client.request_airdrop(&address, LAMPORTS_PER_SOL / 10).await.unwrap();
+    let after = client.get_balance(&address.to_string()).await.unwrap().sol_balance;
+
+    assert_eq!(after, before + LAMPORTS_PER_SOL / 10);
+}
\ No newline at end of file
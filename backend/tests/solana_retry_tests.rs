@@ -0,0 +1,108 @@
+//! Tests for retrying transient Solana RPC failures
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, routing::post, Json, Router};
+use guardian_aa_backend::blockchain::{retry_with_backoff, RetryPolicy, SolanaClient};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+const ADDRESS: &str = "11111111111111111111111111111111";
+
+fn fast_policy(max_attempts: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_attempts,
+        base_delay: Duration::from_millis(10),
+    }
+}
+
+#[derive(Clone)]
+struct MockRpc {
+    requests: Arc<AtomicUsize>,
+    /// Requests answered with 503 before the mock starts succeeding
+    failures: usize,
+}
+
+async fn handle_rpc(State(mock): State<MockRpc>, Json(request): Json<serde_json::Value>) -> impl IntoResponse {
+    let seen = mock.requests.fetch_add(1, Ordering::SeqCst);
+    if seen < mock.failures {
+        return (StatusCode::SERVICE_UNAVAILABLE, Json(serde_json::json!({}))).into_response();
+    }
+
+    Json(serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": request["id"],
+        "result": { "context": { "slot": 1 }, "value": 12_345 }
+    }))
+    .into_response()
+}
+
+/// Start a JSON-RPC server that fails the first `failures` requests
+async fn start_mock_rpc(failures: usize) -> (String, Arc<AtomicUsize>) {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let mock = MockRpc { requests: requests.clone(), failures };
+    let app = Router::new().route("/", post(handle_rpc)).with_state(mock);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    (url, requests)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_balance_succeeds_after_transient_failures() {
+    let (url, requests) = start_mock_rpc(2).await;
+    let client = SolanaClient::new(&url, "confirmed").unwrap().with_retry_policy(fast_policy(3));
+
+    let balance = client.get_balance(ADDRESS).await.unwrap();
+
+    assert_eq!(balance.sol_balance, 12_345);
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_final_error_surfaces_when_attempts_run_out() {
+    let (url, requests) = start_mock_rpc(usize::MAX).await;
+    let client = SolanaClient::new(&url, "confirmed").unwrap().with_retry_policy(fast_policy(3));
+
+    assert!(client.get_balance(ADDRESS).await.is_err());
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_non_retryable_errors_are_not_retried() {
+    let attempts = AtomicUsize::new(0);
+
+    let result: Result<(), String> = retry_with_backoff(
+        &fast_policy(5),
+        || {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err("invalid params".to_string())
+        },
+        |_| false,
+    )
+    .await;
+
+    assert!(result.is_err());
+    assert_eq!(attempts.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_retry_stops_at_first_success() {
+    let attempts = AtomicUsize::new(0);
+
+    let result = retry_with_backoff(
+        &fast_policy(5),
+        || match attempts.fetch_add(1, Ordering::SeqCst) {
+            0 => Err("rate limited".to_string()),
+            n => Ok(n),
+        },
+        |_| true,
+    )
+    .await;
+
+    assert_eq!(result, Ok(1));
+    assert_eq!(attempts.load(Ordering::SeqCst), 2);
+}
\ No newline at end of file
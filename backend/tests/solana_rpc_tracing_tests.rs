@@ -0,0 +1,119 @@
+//! Tests for tracing Solana RPC calls
+
+use axum::{http::StatusCode, response::IntoResponse, routing::post, Json, Router};
+use guardian_aa_backend::blockchain::{RetryPolicy, SolanaClient};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    Subscriber,
+};
+use tracing_subscriber::{layer::Context, prelude::*, registry::LookupSpan, Layer};
+
+const ADDRESS: &str = "11111111111111111111111111111111";
+
+type Fields = HashMap<String, String>;
+
+/// Keeps the fields of every `solana_rpc` span once it closes
+#[derive(Clone, Default)]
+struct RpcSpans(Arc<Mutex<Vec<Fields>>>);
+
+struct FieldVisitor<'a>(&'a mut Fields);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0.insert(field.name().to_string(), format!("{:?}", value));
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for RpcSpans {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if attrs.metadata().name() != "solana_rpc" {
+            return;
+        }
+        let mut fields = Fields::new();
+        attrs.record(&mut FieldVisitor(&mut fields));
+        ctx.span(id).unwrap().extensions_mut().insert(fields);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(fields) = ctx.span(id).unwrap().extensions_mut().get_mut::<Fields>() {
+            values.record(&mut FieldVisitor(fields));
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        if let Some(fields) = ctx.span(&id).unwrap().extensions_mut().remove::<Fields>() {
+            self.0.lock().unwrap().push(fields);
+        }
+    }
+}
+
+async fn handle_rpc(Json(request): Json<serde_json::Value>) -> axum::response::Response {
+    match request["method"].as_str().unwrap_or_default() {
+        "getBalance" => Json(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": request["id"],
+            "result": { "context": { "slot": 1 }, "value": 12_345 }
+        }))
+        .into_response(),
+        _ => (StatusCode::SERVICE_UNAVAILABLE, Json(serde_json::json!({}))).into_response(),
+    }
+}
+
+async fn start_mock_rpc() -> String {
+    let app = Router::new().route("/", post(handle_rpc));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    url
+}
+
+fn client(url: &str) -> SolanaClient {
+    SolanaClient::new(url, "confirmed").unwrap().with_retry_policy(RetryPolicy {
+        max_attempts: 1,
+        base_delay: Duration::from_millis(10),
+    })
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_successful_call_is_traced() {
+    let url = start_mock_rpc().await;
+    let spans = RpcSpans::default();
+    let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(spans.clone()));
+
+    client(&url).get_balance(ADDRESS).await.unwrap();
+
+    let spans = spans.0.lock().unwrap();
+    assert_eq!(spans.len(), 1);
+    assert_eq!(spans[0]["method"], "getBalance");
+    assert_eq!(spans[0]["endpoint"], url);
+    assert_eq!(spans[0]["outcome"], "ok");
+    assert!(spans[0]["latency_ms"].parse::<u64>().is_ok());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_failed_call_is_traced() {
+    let url = start_mock_rpc().await;
+    let spans = RpcSpans::default();
+    let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(spans.clone()));
+
+    assert!(client(&url).get_current_slot().await.is_err());
+
+    let spans = spans.0.lock().unwrap();
+    assert_eq!(spans.len(), 1);
+    assert_eq!(spans[0]["method"], "getSlot");
+    assert_eq!(spans[0]["endpoint"], url);
+    assert_eq!(spans[0]["outcome"], "error");
+    assert!(spans[0]["latency_ms"].parse::<u64>().is_ok());
+}
\ No newline at end of file
@@ -0,0 +1,164 @@
+//! Tests for SPL transfers to recipients without a token account
+
+use axum::{routing::post, Json, Router};
+use base64::{engine::general_purpose, Engine as _};
+use guardian_aa_backend::{
+    api::AppState,
+    blockchain::{token_accounts::{self, ASSOCIATED_TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID}, PriorityLevel, SolanaClient},
+    config::{Config, MissingTokenAccountPolicy},
+    db::models::{CreateTransaction, TransactionType},
+    error::Error,
+    services::TransactionService,
+};
+use solana_sdk::{
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use spl_token::instruction::TokenInstruction;
+use std::sync::Arc;
+use uuid::Uuid;
+
+mod common;
+
+const TOKEN_ACCOUNT_RENT: u64 = 2_039_280;
+
+/// JSON-RPC server on which no account exists
+async fn handle_rpc(Json(request): Json<serde_json::Value>) -> Json<serde_json::Value> {
+    let result = match request["method"].as_str().unwrap_or_default() {
+        "getVersion" => serde_json::json!({ "solana-core": "2.1.0", "feature-set": 0 }),
+        "getAccountInfo" => serde_json::json!({ "context": { "slot": 1 }, "value": null }),
+        "getMultipleAccounts" => {
+            let requested = request["params"][0].as_array().map_or(0, |keys| keys.len());
+            serde_json::json!({ "context": { "slot": 1 }, "value": vec![serde_json::Value::Null; requested] })
+        }
+        "getMinimumBalanceForRentExemption" => serde_json::json!(TOKEN_ACCOUNT_RENT),
+        "getFeeForMessage" => serde_json::json!({ "context": { "slot": 1 }, "value": 5_000 }),
+        "getRecentPrioritizationFees" => serde_json::json!([]),
+        method => panic!("unexpected RPC method {}", method),
+    };
+
+    Json(serde_json::json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+}
+
+async fn start_mock_rpc() -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, Router::new().route("/", post(handle_rpc))).await.unwrap();
+    });
+    url
+}
+
+/// App state talking to the mock RPC; the database is never touched
+fn lazy_state(rpc_url: &str, policy: MissingTokenAccountPolicy) -> Arc<AppState> {
+    let mut config = Config::default();
+    config.blockchain.missing_token_account = policy;
+    config.blockchain.solana_rpc_url = rpc_url.to_string();
+    common::lazy_state(config)
+}
+
+fn token_transfer(source: &Pubkey, destination: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: TOKEN_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*source, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: TokenInstruction::Transfer { amount: 1_000 }.pack(),
+    }
+}
+
+fn encode(payer: &Keypair, instructions: &[Instruction]) -> String {
+    let transaction = Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &[payer], Hash::default());
+    general_purpose::STANDARD.encode(bincode::serialize(&transaction).unwrap())
+}
+
+/// A token send from `sender` to `recipient`'s wallet
+fn token_send(sender: &Keypair, recipient: &Pubkey, mint: &Pubkey) -> CreateTransaction {
+    let source = token_accounts::associated_token_address(&sender.pubkey(), mint);
+    let destination = token_accounts::associated_token_address(recipient, mint);
+
+    CreateTransaction {
+        wallet_id: Uuid::new_v4(),
+        transaction_type: TransactionType::Send,
+        from_address: sender.pubkey().to_string(),
+        to_address: recipient.to_string(),
+        amount: "1".to_string(),
+        token_mint: Some(mint.to_string()),
+        raw_transaction: Some(encode(sender, &[token_transfer(&source, &destination, &sender.pubkey())])),
+    }
+}
+
+#[test]
+fn test_transfer_destinations_skip_accounts_created_first() {
+    let payer = Keypair::new();
+    let (recipient, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let source = token_accounts::associated_token_address(&payer.pubkey(), &mint);
+    let destination = token_accounts::associated_token_address(&recipient, &mint);
+    let transfer = token_transfer(&source, &destination, &payer.pubkey());
+
+    let transaction = Transaction::new_with_payer(&[transfer.clone()], Some(&payer.pubkey()));
+    assert_eq!(token_accounts::transfer_destinations(&transaction.message.instructions, &transaction.message.account_keys), vec![destination]);
+
+    let create = token_accounts::create_associated_token_account(&payer.pubkey(), &recipient, &mint);
+    let transaction = Transaction::new_with_payer(&[create, transfer], Some(&payer.pubkey()));
+    assert!(token_accounts::transfer_destinations(&transaction.message.instructions, &transaction.message.account_keys).is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_missing_transfer_destination_is_found() {
+    let url = start_mock_rpc().await;
+    let client = SolanaClient::new(&url, "confirmed").unwrap();
+    let sender = Keypair::new();
+    let destination = Pubkey::new_unique();
+
+    let encoded = encode(&sender, &[token_transfer(&Pubkey::new_unique(), &destination, &sender.pubkey())]);
+    let missing = client.missing_transfer_destinations(&encoded).await.unwrap();
+
+    assert_eq!(missing, vec![destination]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_missing_token_account_is_rejected_with_its_address() {
+    let url = start_mock_rpc().await;
+    let state = lazy_state(&url, MissingTokenAccountPolicy::Reject);
+    let (sender, recipient, mint) = (Keypair::new(), Pubkey::new_unique(), Pubkey::new_unique());
+
+    let result = TransactionService::new(state)
+        .estimate_fee(&token_send(&sender, &recipient, &mint), PriorityLevel::Medium)
+        .await;
+
+    let expected = token_accounts::associated_token_address(&recipient, &mint).to_string();
+    match result {
+        Err(Error::TokenAccountMissing(account)) => assert_eq!(account, expected),
+        other => panic!("expected a missing token account error, got {:?}", other),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_estimate_includes_token_account_creation() {
+    let url = start_mock_rpc().await;
+    let state = lazy_state(&url, MissingTokenAccountPolicy::CreateBySender);
+    let (sender, recipient, mint) = (Keypair::new(), Pubkey::new_unique(), Pubkey::new_unique());
+
+    let estimate = TransactionService::new(state)
+        .estimate_fee(&token_send(&sender, &recipient, &mint), PriorityLevel::Medium)
+        .await
+        .unwrap();
+
+    let creation = estimate.token_account_creation.expect("token account creation");
+    let address = token_accounts::associated_token_address(&recipient, &mint).to_string();
+    assert_eq!(creation.address, address);
+    assert_eq!(creation.payer, sender.pubkey().to_string());
+    assert_eq!(creation.rent_lamports, TOKEN_ACCOUNT_RENT);
+
+    let instruction = creation.instruction;
+    assert_eq!(instruction.program_id, ASSOCIATED_TOKEN_PROGRAM_ID.to_string());
+    assert_eq!(instruction.accounts[0].pubkey, sender.pubkey().to_string());
+    assert!(instruction.accounts[0].is_signer);
+    assert_eq!(instruction.accounts[1].pubkey, address);
+}
\ No newline at end of file
@@ -0,0 +1,79 @@
+//! Tests for token metadata resolution
+
+use guardian_aa_backend::{
+    api::AppState,
+    blockchain::metadata::{parse_metadata_account, OnChainMetadata},
+    config::Config,
+    services::{token_metadata::bundled_token_metadata, TokenMetadataService},
+};
+use std::sync::Arc;
+
+mod common;
+
+const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+
+/// App state whose RPC and Redis are unreachable, so lookups must fall back
+/// to the bundled token list
+fn offline_state() -> Arc<AppState> {
+    let mut config = Config::default();
+    config.redis.url = "redis://127.0.0.1:9".to_string();
+    config.blockchain.solana_rpc_url = "http://127.0.0.1:9".to_string();
+    common::lazy_state(config)
+}
+
+/// Borsh-encode a metadata account the way Metaplex lays it out
+fn metadata_account(name: &str, symbol: &str, uri: &str) -> Vec<u8> {
+    let mut data = vec![4u8]; // Key::MetadataV1
+    data.extend_from_slice(&[1u8; 32]); // update authority
+    data.extend_from_slice(&[2u8; 32]); // mint
+    for (value, padded_len) in [(name, 32), (symbol, 10), (uri, 200)] {
+        let mut bytes = value.as_bytes().to_vec();
+        bytes.resize(padded_len, 0);
+        data.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        data.extend_from_slice(&bytes);
+    }
+    data
+}
+
+#[test]
+fn test_bundled_list_resolves_usdc() {
+    let metadata = bundled_token_metadata(USDC_MINT).unwrap();
+    assert_eq!(metadata.symbol, "USDC");
+    assert_eq!(metadata.name, "USD Coin");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_resolve_known_mint_without_rpc() {
+    let service = TokenMetadataService::new(offline_state());
+
+    let metadata = service.resolve(USDC_MINT).await.unwrap();
+    assert_eq!(metadata.symbol, "USDC");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_resolve_unknown_mint_is_none() {
+    let service = TokenMetadataService::new(offline_state());
+
+    assert!(service.resolve("11111111111111111111111111111111").await.is_none());
+    assert!(service.resolve("not a mint").await.is_none());
+}
+
+#[test]
+fn test_parse_metadata_account_strips_padding() {
+    let data = metadata_account("USD Coin", "USDC", "https://example.com/usdc.json");
+
+    assert_eq!(
+        parse_metadata_account(&data),
+        Some(OnChainMetadata {
+            name: "USD Coin".to_string(),
+            symbol: "USDC".to_string(),
+            uri: "https://example.com/usdc.json".to_string(),
+        })
+    );
+}
+
+#[test]
+fn test_parse_truncated_metadata_account() {
+    let data = metadata_account("USD Coin", "USDC", "https://example.com/usdc.json");
+    assert!(parse_metadata_account(&data[..80]).is_none());
+}
\ No newline at end of file
@@ -0,0 +1,154 @@
+//! Tests for reloading the supported token registry
+//!
+//! The test of the Redis cache needs a running Redis instance and is
+//! ignored unless run with `--ignored` and `REDIS_URL` set.
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, routing::get, Router};
+use guardian_aa_backend::{
+    api::{handlers::admin::refresh_token_registry, AppState},
+    config::Config,
+    error::Error,
+    services::{TokenMetadata, TokenMetadataService, TokenRegistryRefresher},
+};
+use redis::AsyncCommands;
+use std::sync::Arc;
+use uuid::Uuid;
+
+mod common;
+
+const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+const NEW_MINT: &str = "7GCihgDB8fe6KNjn2MYtkzZcRjQy3t9GHdC8uHYmW2hr";
+
+/// A token list that renames USDC and adds a token the bundled list lacks
+fn token_list() -> serde_json::Value {
+    serde_json::json!({
+        "name": "Test List",
+        "tokens": [
+            { "chainId": 101, "address": USDC_MINT, "symbol": "USDC", "name": "USD Coin (Circle)", "decimals": 6 },
+            {
+                "chainId": 101,
+                "address": NEW_MINT,
+                "symbol": "POPCAT",
+                "name": "Popcat",
+                "decimals": 9,
+                "logoURI": "https://example.com/popcat.png"
+            }
+        ]
+    })
+}
+
+/// Write `contents` to a file no other test uses, returning its path
+fn write_list(contents: &str) -> String {
+    let path = std::env::temp_dir().join(format!("token-list-{}.json", Uuid::new_v4()));
+    std::fs::write(&path, contents).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+/// App state reading its token list from `source`, with RPC unreachable so
+/// metadata can only come from the registry or Redis
+fn state_with_source(source: Option<String>, redis_url: &str) -> Arc<AppState> {
+    let mut config = Config::default();
+    config.redis.url = redis_url.to_string();
+    config.blockchain.solana_rpc_url = "http://127.0.0.1:9".to_string();
+    config.blockchain.token_registry.source = source;
+    common::lazy_state(config)
+}
+
+fn offline_state(source: Option<String>) -> Arc<AppState> {
+    state_with_source(source, "redis://127.0.0.1:9")
+}
+
+async fn start_list_server() -> String {
+    let app = Router::new().route("/tokens.json", get(|| async { axum::Json(token_list()) }));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/tokens.json", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    url
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_refresh_from_file_updates_registry() {
+    let state = offline_state(Some(write_list(&token_list().to_string())));
+    assert!(state.token_registry.get(NEW_MINT).is_none());
+    let bundled = state.token_registry.len();
+
+    let refresh = TokenRegistryRefresher::new(state.clone()).refresh().await.unwrap();
+
+    assert_eq!(refresh.tokens, bundled + 1);
+    assert_eq!(state.token_registry.get(USDC_MINT).unwrap().name, "USD Coin (Circle)");
+    assert_eq!(state.token_registry.get(NEW_MINT), Some(TokenMetadata {
+        symbol: "POPCAT".to_string(),
+        name: "Popcat".to_string(),
+        logo_uri: Some("https://example.com/popcat.png".to_string()),
+    }));
+
+    // Balances resolve the new token without any RPC lookup
+    let metadata = TokenMetadataService::new(state).resolve(NEW_MINT).await.unwrap();
+    assert_eq!(metadata.symbol, "POPCAT");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_refresh_from_url() {
+    let state = offline_state(Some(start_list_server().await));
+
+    TokenRegistryRefresher::new(state.clone()).refresh().await.unwrap();
+
+    assert_eq!(state.token_registry.get(NEW_MINT).unwrap().symbol, "POPCAT");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_unreadable_list_leaves_registry_unchanged() {
+    let state = offline_state(Some(write_list("{ not a token list")));
+
+    let result = TokenRegistryRefresher::new(state.clone()).refresh().await;
+
+    assert!(matches!(result, Err(Error::ExternalService(_))));
+    assert_eq!(state.token_registry.get(USDC_MINT).unwrap().name, "USD Coin");
+}
+
+#[tokio::test]
+async fn test_refresh_without_source_is_rejected() {
+    let state = offline_state(None);
+
+    let result = TokenRegistryRefresher::new(state).refresh().await;
+
+    assert!(matches!(result, Err(Error::BadRequest(_))));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_admin_trigger_refreshes_registry() {
+    let state = offline_state(Some(write_list(&token_list().to_string())));
+
+    let response = refresh_token_registry(State(state.clone())).await.unwrap().into_response();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["tokens"], state.token_registry.len());
+    assert!(state.token_registry.get(NEW_MINT).is_some());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "needs REDIS_URL"]
+async fn test_refresh_overwrites_cached_metadata() {
+    let redis_url = common::required_env("REDIS_URL");
+    let state = state_with_source(Some(write_list(&token_list().to_string())), &redis_url);
+
+    // A stale entry cached before the token was renamed
+    let mut conn = state.redis.get_multiplexed_async_connection().await.unwrap();
+    let stale = serde_json::to_string(&Some(TokenMetadata {
+        symbol: "USDC".to_string(),
+        name: "USD Coin".to_string(),
+        logo_uri: None,
+    })).unwrap();
+    conn.set_ex::<_, _, ()>(format!("token_metadata:{}", USDC_MINT), stale, 60).await.unwrap();
+
+    TokenRegistryRefresher::new(state.clone()).refresh().await.unwrap();
+
+    let metadata = TokenMetadataService::new(state).resolve(USDC_MINT).await.unwrap();
+    assert_eq!(metadata.name, "USD Coin (Circle)");
+}
\ No newline at end of file
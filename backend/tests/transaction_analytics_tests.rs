@@ -0,0 +1,151 @@
+//! Tests for transaction analytics
+//!
+//! These tests need a running Postgres instance and are ignored unless run
+//! with `--ignored` and `DATABASE_URL` set.
+
+use guardian_aa_backend::{
+    api::AppState,
+    error::Error,
+    services::{transaction::TransactionAnalytics, TransactionService},
+};
+use uuid::Uuid;
+
+mod common;
+
+async fn create_user(state: &AppState) -> Uuid {
+    sqlx::query_scalar("INSERT INTO users (email, password_hash) VALUES ($1, 'hash') RETURNING id")
+        .bind(format!("analytics-{}@example.com", Uuid::new_v4()))
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap()
+}
+
+async fn create_wallet(state: &AppState, user_id: Uuid) -> Uuid {
+    sqlx::query_scalar(
+        "INSERT INTO wallets (user_id, name, wallet_type, public_key)
+         VALUES ($1, 'Analytics', 'solana', $2) RETURNING id",
+    )
+        .bind(user_id)
+        .bind(Uuid::new_v4().to_string())
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap()
+}
+
+/// A seeded transaction, created `age_days` ago and confirmed
+/// `confirmation_secs` after that when set
+struct Seed {
+    transaction_type: &'static str,
+    status: &'static str,
+    amount: &'static str,
+    fee: Option<&'static str>,
+    age_days: i32,
+    confirmation_secs: Option<i32>,
+}
+
+async fn seed(state: &AppState, wallet_id: Uuid, seed: Seed) {
+    sqlx::query(
+        "INSERT INTO transactions
+             (wallet_id, transaction_type, status, from_address, to_address, amount, fee, created_at, confirmed_at)
+         VALUES ($1, $2::transaction_type, $3::transaction_status, 'from', 'to', $4, $5,
+                 NOW() - make_interval(days => $6),
+                 NOW() - make_interval(days => $6) + make_interval(secs => $7))",
+    )
+        .bind(wallet_id)
+        .bind(seed.transaction_type)
+        .bind(seed.status)
+        .bind(seed.amount)
+        .bind(seed.fee)
+        .bind(seed.age_days)
+        .bind(seed.confirmation_secs.map(f64::from))
+        .execute(state.db.pool())
+        .await
+        .unwrap();
+}
+
+fn type_count<'a>(analytics: &'a TransactionAnalytics, transaction_type: &str) -> (i64, &'a str) {
+    let by_type = analytics.transaction_types.iter()
+        .find(|by_type| serde_json::to_value(&by_type.transaction_type).unwrap() == transaction_type)
+        .unwrap_or_else(|| panic!("no {} transactions in the analytics", transaction_type));
+    (by_type.count, &by_type.volume)
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_analytics_aggregate_seeded_transactions() {
+    let state = common::test_state().await;
+    let user_id = create_user(&state).await;
+    let wallet_id = create_wallet(&state, user_id).await;
+
+    let seeds = [
+        Seed { transaction_type: "send", status: "confirmed", amount: "1.5", fee: Some("0.000005"), age_days: 1, confirmation_secs: Some(10) },
+        Seed { transaction_type: "send", status: "confirmed", amount: "0.25", fee: Some("0.000005"), age_days: 2, confirmation_secs: Some(20) },
+        Seed { transaction_type: "swap", status: "failed", amount: "3", fee: Some("0.00001"), age_days: 3, confirmation_secs: None },
+        Seed { transaction_type: "stake", status: "pending", amount: "10.1", fee: None, age_days: 0, confirmation_secs: None },
+        // Counted, but its amount can't be summed
+        Seed { transaction_type: "send", status: "confirmed", amount: "n/a", fee: Some("bogus"), age_days: 4, confirmation_secs: Some(30) },
+        // Outside the window
+        Seed { transaction_type: "send", status: "confirmed", amount: "100", fee: Some("1"), age_days: 45, confirmation_secs: Some(5) },
+    ];
+    for seed_data in seeds {
+        seed(&state, wallet_id, seed_data).await;
+    }
+
+    let analytics = TransactionService::new(state)
+        .get_transaction_analytics(user_id, None, 30)
+        .await
+        .unwrap();
+
+    assert_eq!(analytics.total_transactions, 5);
+    assert_eq!(analytics.total_volume, "14.85");
+    assert_eq!(analytics.total_fees, "0.00002");
+    assert_eq!(analytics.success_rate, 75.0);
+    assert_eq!(analytics.average_confirmation_time, 20);
+
+    assert_eq!(analytics.transaction_types.len(), 3);
+    assert_eq!(type_count(&analytics, "Send"), (3, "1.75"));
+    assert_eq!(type_count(&analytics, "Swap"), (1, "3"));
+    assert_eq!(type_count(&analytics, "Stake"), (1, "10.1"));
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_analytics_filter_by_wallet() {
+    let state = common::test_state().await;
+    let user_id = create_user(&state).await;
+    let wallet_id = create_wallet(&state, user_id).await;
+    let other_wallet_id = create_wallet(&state, user_id).await;
+
+    seed(&state, wallet_id, Seed { transaction_type: "send", status: "confirmed", amount: "2", fee: Some("0.1"), age_days: 1, confirmation_secs: Some(4) }).await;
+    seed(&state, other_wallet_id, Seed { transaction_type: "receive", status: "failed", amount: "5", fee: None, age_days: 1, confirmation_secs: None }).await;
+
+    let analytics = TransactionService::new(state.clone())
+        .get_transaction_analytics(user_id, Some(wallet_id), 7)
+        .await
+        .unwrap();
+
+    assert_eq!(analytics.total_transactions, 1);
+    assert_eq!(analytics.total_volume, "2");
+    assert_eq!(analytics.success_rate, 100.0);
+
+    // Another user's wallet yields nothing
+    let stranger = create_user(&state).await;
+    let analytics = TransactionService::new(state)
+        .get_transaction_analytics(stranger, Some(wallet_id), 7)
+        .await
+        .unwrap();
+    assert_eq!(analytics.total_transactions, 0);
+    assert_eq!(analytics.total_volume, "0");
+    assert!(analytics.transaction_types.is_empty());
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_analytics_require_positive_window() {
+    let state = common::test_state().await;
+
+    let result = TransactionService::new(state)
+        .get_transaction_analytics(Uuid::new_v4(), None, 0)
+        .await;
+    assert!(matches!(result, Err(Error::Validation(_))));
+}
\ No newline at end of file
@@ -0,0 +1,170 @@
+//! Tests for submitting several transactions in one request
+//!
+//! These tests need a running Postgres instance and are ignored unless run
+//! with `--ignored` and `DATABASE_URL` set.
+
+use axum::{routing::post, Json, Router};
+use base64::{engine::general_purpose, Engine as _};
+use guardian_aa_backend::{
+    api::AppState,
+    db::{
+        models::{CreateTransaction, TransactionType},
+        queries::TransactionQueries,
+    },
+    error::Error,
+    services::TransactionService,
+};
+use solana_sdk::{
+    hash::Hash,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+mod common;
+
+/// JSON-RPC server that accepts and immediately confirms every transaction
+async fn handle_rpc(Json(request): Json<serde_json::Value>) -> Json<serde_json::Value> {
+    let result = match request["method"].as_str().unwrap_or_default() {
+        "getVersion" => serde_json::json!({ "solana-core": "2.1.0", "feature-set": 0 }),
+        "simulateTransaction" => serde_json::json!({
+            "context": { "slot": 1 },
+            "value": { "err": null, "logs": [], "accounts": null, "unitsConsumed": 150, "returnData": null }
+        }),
+        "sendTransaction" => {
+            let encoded = request["params"][0].as_str().unwrap();
+            let bytes = general_purpose::STANDARD.decode(encoded).unwrap();
+            let transaction: Transaction = bincode::deserialize(&bytes).unwrap();
+            serde_json::json!(transaction.signatures[0].to_string())
+        }
+        "getSignatureStatuses" => serde_json::json!({
+            "context": { "slot": 42 },
+            "value": [{
+                "slot": 42,
+                "confirmations": 0,
+                "err": null,
+                "status": { "Ok": null },
+                "confirmationStatus": "confirmed"
+            }]
+        }),
+        method => panic!("unexpected RPC method {}", method),
+    };
+
+    Json(serde_json::json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+}
+
+async fn test_state() -> Arc<AppState> {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let rpc_url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, Router::new().route("/", post(handle_rpc))).await.unwrap();
+    });
+
+    let mut config = common::database_config();
+    config.blockchain.max_concurrent_submissions = 2;
+    config.blockchain.solana_rpc_url = rpc_url;
+    common::connect(config).await
+}
+
+/// Create a user with a wallet, returning both ids
+async fn create_user_wallet(state: &AppState) -> (Uuid, Uuid) {
+    let user_id: Uuid = sqlx::query_scalar("INSERT INTO users (email, password_hash) VALUES ($1, 'hash') RETURNING id")
+        .bind(format!("batch-{}@example.com", Uuid::new_v4()))
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap();
+    let wallet_id = sqlx::query_scalar(
+        "INSERT INTO wallets (user_id, name, wallet_type, public_key) VALUES ($1, 'test', 'solana', $2) RETURNING id",
+    )
+        .bind(user_id)
+        .bind(Keypair::new().pubkey().to_string())
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap();
+
+    (user_id, wallet_id)
+}
+
+async fn create_transaction(state: &AppState, wallet_id: Uuid, raw_transaction: String) -> Uuid {
+    TransactionQueries::create(state.db.pool(), &CreateTransaction {
+        wallet_id,
+        transaction_type: TransactionType::Send,
+        from_address: Keypair::new().pubkey().to_string(),
+        to_address: Keypair::new().pubkey().to_string(),
+        amount: "0.000001".to_string(),
+        token_mint: None,
+        raw_transaction: Some(raw_transaction),
+    }).await.unwrap().id
+}
+
+fn signed_transaction() -> (String, String) {
+    let payer = Keypair::new();
+    let instruction = system_instruction::transfer(&payer.pubkey(), &Keypair::new().pubkey(), 1_000);
+    let transaction = Transaction::new_signed_with_payer(&[instruction], Some(&payer.pubkey()), &[&payer], Hash::default());
+    let encoded = general_purpose::STANDARD.encode(bincode::serialize(&transaction).unwrap());
+    (encoded, transaction.signatures[0].to_string())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "needs DATABASE_URL"]
+async fn test_batch_reports_each_result_in_order() {
+    let state = test_state().await;
+    let (user_id, wallet_id) = create_user_wallet(&state).await;
+
+    let (first_tx, first_signature) = signed_transaction();
+    let (last_tx, last_signature) = signed_transaction();
+    let first = create_transaction(&state, wallet_id, first_tx).await;
+    let broken = create_transaction(&state, wallet_id, "not a transaction".to_string()).await;
+    let last = create_transaction(&state, wallet_id, last_tx).await;
+
+    let results = TransactionService::new(state.clone())
+        .submit_transactions_batch(user_id, &[first, broken, last], true)
+        .await
+        .unwrap();
+
+    let ids: Vec<Uuid> = results.iter().map(|result| result.transaction_id).collect();
+    assert_eq!(ids, vec![first, broken, last]);
+
+    assert_eq!(results[0].signature.as_deref(), Some(first_signature.as_str()));
+    assert!(results[0].error.is_none());
+    assert!(results[1].signature.is_none());
+    assert!(results[1].error.is_some());
+    assert_eq!(results[2].signature.as_deref(), Some(last_signature.as_str()));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "needs DATABASE_URL"]
+async fn test_batch_rejects_other_users_transactions() {
+    let state = test_state().await;
+    let (user_id, wallet_id) = create_user_wallet(&state).await;
+    let (_, other_wallet_id) = create_user_wallet(&state).await;
+
+    let (own_tx, _) = signed_transaction();
+    let (other_tx, _) = signed_transaction();
+    let own = create_transaction(&state, wallet_id, own_tx).await;
+    let foreign = create_transaction(&state, other_wallet_id, other_tx).await;
+
+    let results = TransactionService::new(state.clone())
+        .submit_transactions_batch(user_id, &[own, foreign], true)
+        .await
+        .unwrap();
+
+    assert!(results[0].signature.is_some());
+    assert!(results[1].signature.is_none());
+    assert!(results[1].error.is_some());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "needs DATABASE_URL"]
+async fn test_batch_rejects_duplicate_ids() {
+    let state = test_state().await;
+    let id = Uuid::new_v4();
+
+    let result = TransactionService::new(state)
+        .submit_transactions_batch(Uuid::new_v4(), &[id, id], true)
+        .await;
+
+    assert!(matches!(result, Err(Error::Validation(_))));
+}
\ No newline at end of file
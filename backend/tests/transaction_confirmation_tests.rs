@@ -0,0 +1,107 @@
+//! Tests for polling submitted transactions until they confirm
+
+use axum::{extract::State, routing::post, Json, Router};
+use base64::{engine::general_purpose, Engine as _};
+use guardian_aa_backend::blockchain::{ConfirmationPolicy, ConfirmationStatus, SolanaClient};
+use solana_sdk::{
+    hash::Hash,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Clone)]
+struct MockRpc {
+    status_polls: Arc<AtomicUsize>,
+    /// Status polls answered with "not found" before the transaction confirms
+    pending_polls: usize,
+}
+
+async fn handle_rpc(State(mock): State<MockRpc>, Json(request): Json<serde_json::Value>) -> Json<serde_json::Value> {
+    let result = match request["method"].as_str().unwrap_or_default() {
+        "getVersion" => serde_json::json!({ "solana-core": "2.1.0", "feature-set": 0 }),
+        "sendTransaction" => {
+            // Echo the transaction's own signature, as a real node does
+            let encoded = request["params"][0].as_str().unwrap();
+            let bytes = general_purpose::STANDARD.decode(encoded).unwrap();
+            let transaction: Transaction = bincode::deserialize(&bytes).unwrap();
+            serde_json::json!(transaction.signatures[0].to_string())
+        }
+        "getSignatureStatuses" => {
+            let polls = mock.status_polls.fetch_add(1, Ordering::SeqCst) + 1;
+            let status = if polls <= mock.pending_polls {
+                serde_json::Value::Null
+            } else {
+                serde_json::json!({
+                    "slot": 42,
+                    "confirmations": 0,
+                    "err": null,
+                    "status": { "Ok": null },
+                    "confirmationStatus": "confirmed"
+                })
+            };
+            serde_json::json!({ "context": { "slot": 42 }, "value": [status] })
+        }
+        method => panic!("unexpected RPC method {}", method),
+    };
+
+    Json(serde_json::json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+}
+
+/// Start a JSON-RPC server whose transaction confirms after `pending_polls`
+/// status checks
+async fn start_mock_rpc(pending_polls: usize) -> (String, Arc<AtomicUsize>) {
+    let status_polls = Arc::new(AtomicUsize::new(0));
+    let mock = MockRpc { status_polls: status_polls.clone(), pending_polls };
+    let app = Router::new().route("/", post(handle_rpc)).with_state(mock);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    (url, status_polls)
+}
+
+fn signed_transaction() -> (Transaction, String) {
+    let payer = Keypair::new();
+    let instruction = system_instruction::transfer(&payer.pubkey(), &Keypair::new().pubkey(), 1_000);
+    let transaction = Transaction::new_signed_with_payer(&[instruction], Some(&payer.pubkey()), &[&payer], Hash::default());
+    let encoded = general_purpose::STANDARD.encode(bincode::serialize(&transaction).unwrap());
+    (transaction, encoded)
+}
+
+fn client(url: &str, timeout: Duration) -> SolanaClient {
+    SolanaClient::new(url, "confirmed").unwrap().with_confirmation_policy(ConfirmationPolicy {
+        timeout,
+        poll_interval: Duration::from_millis(10),
+    })
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_submit_waits_for_confirmation() {
+    let (url, status_polls) = start_mock_rpc(3).await;
+    let (transaction, encoded) = signed_transaction();
+
+    let result = client(&url, Duration::from_secs(5)).submit_transaction(&encoded).await.unwrap();
+
+    assert_eq!(result.signature, transaction.signatures[0].to_string());
+    assert_eq!(result.confirmation_status, ConfirmationStatus::Confirmed);
+    assert_eq!(result.slot, 42);
+    assert_eq!(status_polls.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_submit_times_out_with_signature() {
+    let (url, _) = start_mock_rpc(usize::MAX).await;
+    let (transaction, encoded) = signed_transaction();
+
+    let result = client(&url, Duration::from_millis(50)).submit_transaction(&encoded).await.unwrap();
+
+    assert_eq!(result.signature, transaction.signatures[0].to_string());
+    assert_eq!(result.confirmation_status, ConfirmationStatus::TimedOut);
+}
\ No newline at end of file
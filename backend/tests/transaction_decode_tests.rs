@@ -0,0 +1,139 @@
+//! Tests for explaining raw transactions before they are submitted
+
+use base64::{engine::general_purpose, Engine as _};
+use guardian_aa_backend::{
+    blockchain::{
+        token_accounts::{associated_token_address, create_associated_token_account, TOKEN_PROGRAM_ID},
+        InstructionAction, SolanaClient,
+    },
+    error::Error,
+};
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+use spl_token::instruction::TokenInstruction;
+
+/// The client never connects: decoding transactions without lookup tables
+/// doesn't touch the network
+fn client() -> SolanaClient {
+    SolanaClient::new("http://127.0.0.1:9", "confirmed").unwrap()
+}
+
+fn signed(instructions: &[Instruction], payer: &Keypair) -> Transaction {
+    Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &[payer], Hash::default())
+}
+
+fn base64(transaction: &Transaction) -> String {
+    general_purpose::STANDARD.encode(bincode::serialize(transaction).unwrap())
+}
+
+#[tokio::test]
+async fn test_decodes_sol_transfer() {
+    let payer = Keypair::new();
+    let recipient = Pubkey::new_unique();
+    let transaction = signed(&[
+        ComputeBudgetInstruction::set_compute_unit_price(5_000),
+        system_instruction::transfer(&payer.pubkey(), &recipient, 1_500_000),
+    ], &payer);
+
+    let decoded = client().decode_transaction(&base64(&transaction)).await.unwrap();
+
+    assert!(decoded.fully_signed);
+    assert_eq!(decoded.fee_payer, Some(payer.pubkey().to_string()));
+    assert_eq!(decoded.signatures, vec![transaction.signatures[0].to_string()]);
+    assert!(decoded.unknown_programs.is_empty());
+    assert_eq!(decoded.instructions.len(), 2);
+
+    let budget = &decoded.instructions[0];
+    assert_eq!(budget.program, Some("compute_budget"));
+    assert_eq!(budget.action, Some(InstructionAction::SetComputeUnitPrice { micro_lamports: 5_000 }));
+
+    let transfer = &decoded.instructions[1];
+    assert_eq!(transfer.index, 1);
+    assert_eq!(transfer.program_id, "11111111111111111111111111111111");
+    assert_eq!(transfer.program, Some("system"));
+    assert_eq!(transfer.accounts.len(), 2);
+    assert!(transfer.accounts[0].is_signer && transfer.accounts[0].is_writable);
+    assert!(!transfer.accounts[1].is_signer && transfer.accounts[1].is_writable);
+    assert_eq!(transfer.action, Some(InstructionAction::SolTransfer {
+        from: payer.pubkey().to_string(),
+        to: recipient.to_string(),
+        lamports: 1_500_000,
+    }));
+}
+
+#[tokio::test]
+async fn test_decodes_token_transfer_and_flags_unknown_programs() {
+    let payer = Keypair::new();
+    let recipient = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let source = associated_token_address(&payer.pubkey(), &mint);
+    let destination = associated_token_address(&recipient, &mint);
+    let unknown_program = Pubkey::new_unique();
+
+    let transfer = Instruction {
+        program_id: TOKEN_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(source, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new(destination, false),
+            AccountMeta::new_readonly(payer.pubkey(), true),
+        ],
+        data: TokenInstruction::TransferChecked { amount: 2_500, decimals: 6 }.pack(),
+    };
+    let transaction = signed(&[
+        create_associated_token_account(&payer.pubkey(), &recipient, &mint),
+        transfer,
+        Instruction::new_with_bytes(unknown_program, &[7, 7], vec![AccountMeta::new(payer.pubkey(), true)]),
+    ], &payer);
+
+    let decoded = client().decode_transaction(&hex::encode(bincode::serialize(&transaction).unwrap())).await.unwrap();
+
+    assert_eq!(decoded.instructions[0].action, Some(InstructionAction::CreateAssociatedTokenAccount {
+        payer: payer.pubkey().to_string(),
+        account: destination.to_string(),
+        owner: recipient.to_string(),
+        mint: mint.to_string(),
+    }));
+    assert_eq!(decoded.instructions[1].program, Some("spl_token"));
+    assert_eq!(decoded.instructions[1].action, Some(InstructionAction::TokenTransfer {
+        source: source.to_string(),
+        destination: destination.to_string(),
+        authority: payer.pubkey().to_string(),
+        amount: 2_500,
+        mint: Some(mint.to_string()),
+        decimals: Some(6),
+    }));
+
+    let unknown = &decoded.instructions[2];
+    assert_eq!(unknown.program, None);
+    assert_eq!(unknown.action, None);
+    assert_eq!(unknown.data, general_purpose::STANDARD.encode([7, 7]));
+    assert_eq!(decoded.unknown_programs, vec![unknown_program.to_string()]);
+}
+
+#[tokio::test]
+async fn test_unsigned_transaction_is_reported() {
+    let payer = Pubkey::new_unique();
+    let transaction = Transaction::new_with_payer(
+        &[system_instruction::transfer(&payer, &Pubkey::new_unique(), 1)],
+        Some(&payer),
+    );
+
+    let decoded = client().decode_transaction(&base64(&transaction)).await.unwrap();
+
+    assert!(!decoded.fully_signed);
+}
+
+#[tokio::test]
+async fn test_invalid_data_is_a_bad_request() {
+    let result = client().decode_transaction("not a transaction").await;
+
+    assert!(matches!(result, Err(Error::BadRequest(_))));
+}
\ No newline at end of file
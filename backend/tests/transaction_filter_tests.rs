@@ -0,0 +1,159 @@
+//! Tests for filtering a wallet's transaction list
+//!
+//! The database tests need a running Postgres instance and are ignored
+//! unless run with `--ignored` and `DATABASE_URL` set.
+
+use axum::{extract::Query, http::Uri};
+use chrono::{DateTime, Duration, Utc};
+use guardian_aa_backend::{
+    api::{handlers::transaction::TransactionQuery, pagination::Page, AppState},
+    db::models::{TransactionFilter, TransactionStatus, TransactionType},
+    error::Error,
+    services::TransactionService,
+};
+use uuid::Uuid;
+
+mod common;
+
+/// A user with one wallet: (user id, wallet id)
+async fn create_wallet(state: &AppState) -> (Uuid, Uuid) {
+    let user_id: Uuid = sqlx::query_scalar("INSERT INTO users (email, password_hash) VALUES ($1, 'hash') RETURNING id")
+        .bind(format!("filters-{}@example.com", Uuid::new_v4()))
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap();
+    let wallet_id = sqlx::query_scalar(
+        "INSERT INTO wallets (user_id, name, wallet_type, public_key) VALUES ($1, 'Filters', 'solana', $2) RETURNING id",
+    )
+        .bind(user_id)
+        .bind(Uuid::new_v4().to_string())
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap();
+
+    (user_id, wallet_id)
+}
+
+async fn seed(state: &AppState, wallet_id: Uuid, transaction_type: &str, status: &str, created_at: DateTime<Utc>) {
+    sqlx::query(
+        "INSERT INTO transactions (wallet_id, transaction_type, status, from_address, to_address, amount, created_at)
+         VALUES ($1, $2::transaction_type, $3::transaction_status, 'from', 'to', '1', $4)",
+    )
+        .bind(wallet_id)
+        .bind(transaction_type)
+        .bind(status)
+        .bind(created_at)
+        .execute(state.db.pool())
+        .await
+        .unwrap();
+}
+
+/// Seeds, oldest first: a confirmed send 3 days ago, a pending send 2 days
+/// ago, a confirmed swap yesterday and a confirmed send and failed stake today
+async fn seed_mixed(state: &AppState, wallet_id: Uuid, now: DateTime<Utc>) {
+    seed(state, wallet_id, "send", "confirmed", now - Duration::days(3)).await;
+    seed(state, wallet_id, "send", "pending", now - Duration::days(2)).await;
+    seed(state, wallet_id, "swap", "confirmed", now - Duration::days(1)).await;
+    seed(state, wallet_id, "send", "confirmed", now - Duration::minutes(2)).await;
+    seed(state, wallet_id, "stake", "failed", now - Duration::minutes(1)).await;
+}
+
+#[test]
+fn test_query_string_parses_filters() {
+    let uri: Uri = "/transaction?wallet_id=6f1c1a8e-5d5b-4f4e-9d7a-0c4b3a2f1e0d&status=Confirmed&transaction_type=Swap&from=2024-01-01T00:00:00Z&to=2024-02-01T00:00:00Z"
+        .parse()
+        .unwrap();
+    let Query(query) = Query::<TransactionQuery>::try_from_uri(&uri).unwrap();
+
+    assert!(matches!(query.status, Some(TransactionStatus::Confirmed)));
+    assert!(matches!(query.transaction_type, Some(TransactionType::Swap)));
+    assert_eq!(query.from.unwrap().to_rfc3339(), "2024-01-01T00:00:00+00:00");
+    assert!(query.to.is_some());
+
+    let uri: Uri = "/transaction?wallet_id=6f1c1a8e-5d5b-4f4e-9d7a-0c4b3a2f1e0d".parse().unwrap();
+    let Query(query) = Query::<TransactionQuery>::try_from_uri(&uri).unwrap();
+    assert!(query.status.is_none() && query.transaction_type.is_none() && query.from.is_none());
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_each_filter_narrows_results() {
+    let state = common::test_state().await;
+    let (user_id, wallet_id) = create_wallet(&state).await;
+    let now = Utc::now();
+    seed_mixed(&state, wallet_id, now).await;
+    let service = &TransactionService::new(state.clone());
+    let list = move |filter: TransactionFilter| async move {
+        service.get_wallet_transactions(wallet_id, user_id, filter, Page::new(None, None)).await.unwrap()
+    };
+
+    assert_eq!(list(TransactionFilter::default()).await.total, 5);
+
+    let confirmed = list(TransactionFilter { status: Some(TransactionStatus::Confirmed), ..Default::default() }).await;
+    assert_eq!(confirmed.total, 3);
+    assert!(confirmed.items.iter().all(|t| matches!(t.status, TransactionStatus::Confirmed)));
+
+    let sends = list(TransactionFilter { transaction_type: Some(TransactionType::Send), ..Default::default() }).await;
+    assert_eq!(sends.total, 3);
+    assert!(sends.items.iter().all(|t| matches!(t.transaction_type, TransactionType::Send)));
+
+    // `from` is inclusive and `to` exclusive
+    let range = list(TransactionFilter {
+        from: Some(now - Duration::days(2)),
+        to: Some(now - Duration::days(1)),
+        ..Default::default()
+    }).await;
+    assert_eq!(range.total, 1);
+    assert!(matches!(range.items[0].status, TransactionStatus::Pending));
+
+    let today = list(TransactionFilter { from: Some(now - Duration::hours(1)), ..Default::default() }).await;
+    assert_eq!(today.total, 2);
+
+    // Filters combine
+    let confirmed_sends = list(TransactionFilter {
+        status: Some(TransactionStatus::Confirmed),
+        transaction_type: Some(TransactionType::Send),
+        to: Some(now - Duration::hours(1)),
+        ..Default::default()
+    }).await;
+    assert_eq!(confirmed_sends.total, 1);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_filters_combine_with_pagination() {
+    let state = common::test_state().await;
+    let (user_id, wallet_id) = create_wallet(&state).await;
+    let now = Utc::now();
+    seed_mixed(&state, wallet_id, now).await;
+    let service = TransactionService::new(state.clone());
+    let filter = TransactionFilter { status: Some(TransactionStatus::Confirmed), ..Default::default() };
+
+    let first = service.get_wallet_transactions(wallet_id, user_id, filter.clone(), Page::new(Some(2), None)).await.unwrap();
+    let second = service.get_wallet_transactions(wallet_id, user_id, filter, Page::new(Some(2), Some(2))).await.unwrap();
+
+    // The total counts every match, not just the page
+    assert_eq!(first.total, 3);
+    assert_eq!(second.total, 3);
+    assert_eq!(first.items.len(), 2);
+    assert_eq!(second.items.len(), 1);
+
+    // Newest first, with no overlap between pages
+    assert!(first.items[0].created_at > first.items[1].created_at);
+    assert!(first.items[1].created_at > second.items[0].created_at);
+    assert!(second.items.iter().all(|t| matches!(t.status, TransactionStatus::Confirmed)));
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_empty_range_is_rejected() {
+    let state = common::test_state().await;
+    let (user_id, wallet_id) = create_wallet(&state).await;
+    let now = Utc::now();
+
+    let filter = TransactionFilter { from: Some(now), to: Some(now - Duration::days(1)), ..Default::default() };
+    let result = TransactionService::new(state.clone())
+        .get_wallet_transactions(wallet_id, user_id, filter, Page::new(None, None))
+        .await;
+    assert!(matches!(result, Err(Error::Validation(_))));
+}
\ No newline at end of file
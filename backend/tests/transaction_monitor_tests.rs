@@ -0,0 +1,163 @@
+//! Tests for the background worker that settles pending transactions
+//!
+//! These need a running Postgres instance and are ignored unless run with
+//! `--ignored` and `DATABASE_URL` set.
+
+use axum::{extract::State, routing::post, Json, Router};
+use guardian_aa_backend::{
+    api::AppState,
+    db::{
+        models::{CreateTransaction, TransactionStatus, TransactionType},
+        queries::TransactionQueries,
+    },
+    services::{TransactionMonitor, TransactionService},
+};
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use std::collections::HashSet;
+use std::sync::Arc;
+use uuid::Uuid;
+
+mod common;
+
+/// JSON-RPC server that reports the given signatures finalized at slot 42
+/// and has never seen any other
+async fn handle_rpc(State(finalized): State<Arc<HashSet<String>>>, Json(request): Json<serde_json::Value>) -> Json<serde_json::Value> {
+    let result = match request["method"].as_str().unwrap_or_default() {
+        "getVersion" => serde_json::json!({ "solana-core": "2.1.0", "feature-set": 0 }),
+        "getSignatureStatuses" => {
+            let statuses: Vec<serde_json::Value> = request["params"][0]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|signature| if finalized.contains(signature.as_str().unwrap()) {
+                    serde_json::json!({
+                        "slot": 42,
+                        "confirmations": null,
+                        "err": null,
+                        "status": { "Ok": null },
+                        "confirmationStatus": "finalized"
+                    })
+                } else {
+                    serde_json::Value::Null
+                })
+                .collect();
+            serde_json::json!({ "context": { "slot": 50 }, "value": statuses })
+        }
+        method => panic!("unexpected RPC method {}", method),
+    };
+
+    Json(serde_json::json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+}
+
+async fn start_mock_rpc(finalized: HashSet<String>) -> String {
+    let app = Router::new().route("/", post(handle_rpc)).with_state(Arc::new(finalized));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    url
+}
+
+async fn test_state(rpc_url: &str) -> Arc<AppState> {
+    let mut config = common::database_config();
+    config.blockchain.monitor.deadline_secs = 3600;
+    config.blockchain.solana_rpc_url = rpc_url.to_string();
+    common::connect(config).await
+}
+
+async fn create_wallet(state: &AppState) -> Uuid {
+    let user_id: Uuid = sqlx::query_scalar("INSERT INTO users (email, password_hash) VALUES ($1, 'hash') RETURNING id")
+        .bind(format!("monitor-{}@example.com", Uuid::new_v4()))
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap();
+
+    sqlx::query_scalar(
+        "INSERT INTO wallets (user_id, name, wallet_type, public_key) VALUES ($1, 'test', 'solana', $2) RETURNING id",
+    )
+        .bind(user_id)
+        .bind(Keypair::new().pubkey().to_string())
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap()
+}
+
+/// A transaction that was submitted as `signature` but has not confirmed
+async fn create_submitted(state: &Arc<AppState>, wallet_id: Uuid, signature: &Signature) -> Uuid {
+    let transaction = TransactionQueries::create(state.db.pool(), &CreateTransaction {
+        wallet_id,
+        transaction_type: TransactionType::Send,
+        from_address: Keypair::new().pubkey().to_string(),
+        to_address: Keypair::new().pubkey().to_string(),
+        amount: "0.000001".to_string(),
+        token_mint: None,
+        raw_transaction: None,
+    }).await.unwrap();
+
+    TransactionService::new(state.clone())
+        .update_transaction_status(transaction.id, TransactionStatus::Pending, Some(&signature.to_string()), None, None, None)
+        .await
+        .unwrap();
+
+    transaction.id
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "needs DATABASE_URL"]
+async fn test_monitor_confirms_landed_and_fails_expired_transactions() {
+    let landed = Signature::new_unique();
+    let lost = Signature::new_unique();
+    let rpc_url = start_mock_rpc(HashSet::from([landed.to_string()])).await;
+    let state = test_state(&rpc_url).await;
+
+    let wallet_id = create_wallet(&state).await;
+    let landed_id = create_submitted(&state, wallet_id, &landed).await;
+    let lost_id = create_submitted(&state, wallet_id, &lost).await;
+    sqlx::query("UPDATE transactions SET created_at = NOW() - INTERVAL '2 hours' WHERE id = $1")
+        .bind(lost_id)
+        .execute(state.db.pool())
+        .await
+        .unwrap();
+
+    let mut events = state.transaction_events.subscribe();
+    let sweep = TransactionMonitor::new(state.clone()).sweep().await.unwrap();
+    assert!(sweep.confirmed >= 1);
+    assert!(sweep.failed >= 1);
+
+    let confirmed = TransactionQueries::find_by_id(state.db.pool(), landed_id).await.unwrap().unwrap();
+    assert!(matches!(confirmed.status, TransactionStatus::Confirmed));
+    assert_eq!(confirmed.block_number, Some(42));
+    assert!(confirmed.confirmed_at.is_some());
+
+    let failed = TransactionQueries::find_by_id(state.db.pool(), lost_id).await.unwrap().unwrap();
+    assert!(matches!(failed.status, TransactionStatus::Failed));
+    assert!(failed.error_message.unwrap().contains("3600s"));
+
+    let mut published = Vec::new();
+    while let Ok(update) = events.try_recv() {
+        if update.wallet_id == wallet_id {
+            published.push((update.transaction_id, update.status));
+        }
+    }
+    assert_eq!(published.len(), 2);
+    assert!(published.iter().any(|(id, status)| *id == landed_id && matches!(status, TransactionStatus::Confirmed)));
+    assert!(published.iter().any(|(id, status)| *id == lost_id && matches!(status, TransactionStatus::Failed)));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "needs DATABASE_URL"]
+async fn test_monitor_leaves_recent_unseen_transactions_pending() {
+    let rpc_url = start_mock_rpc(HashSet::new()).await;
+    let state = test_state(&rpc_url).await;
+
+    let wallet_id = create_wallet(&state).await;
+    let transaction_id = create_submitted(&state, wallet_id, &Signature::new_unique()).await;
+
+    TransactionMonitor::new(state.clone()).sweep().await.unwrap();
+
+    let stored = TransactionQueries::find_by_id(state.db.pool(), transaction_id).await.unwrap().unwrap();
+    assert!(matches!(stored.status, TransactionStatus::Pending));
+}
\ No newline at end of file
@@ -0,0 +1,127 @@
+//! Tests for simulating transactions before they are broadcast
+//!
+//! The service-level test needs a running Postgres instance and is ignored
+//! unless run with `--ignored` and `DATABASE_URL` set.
+
+use axum::{extract::State, routing::post, Json, Router};
+use base64::{engine::general_purpose, Engine as _};
+use guardian_aa_backend::{
+    blockchain::SolanaClient,
+    db::{
+        models::{CreateTransaction, TransactionStatus, TransactionType},
+        queries::TransactionQueries,
+    },
+    error::Error,
+    services::TransactionService,
+};
+use solana_sdk::{
+    hash::Hash,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use uuid::Uuid;
+
+mod common;
+
+/// JSON-RPC server whose simulations always fail with a program error
+async fn handle_rpc(State(sends): State<Arc<AtomicUsize>>, Json(request): Json<serde_json::Value>) -> Json<serde_json::Value> {
+    let result = match request["method"].as_str().unwrap_or_default() {
+        "getVersion" => serde_json::json!({ "solana-core": "2.1.0", "feature-set": 0 }),
+        "simulateTransaction" => serde_json::json!({
+            "context": { "slot": 1 },
+            "value": {
+                "err": { "InstructionError": [0, { "Custom": 1 }] },
+                "logs": ["Program 11111111111111111111111111111111 failed: insufficient lamports"],
+                "accounts": null,
+                "unitsConsumed": 150,
+                "returnData": null
+            }
+        }),
+        "sendTransaction" => {
+            sends.fetch_add(1, Ordering::SeqCst);
+            serde_json::Value::Null
+        }
+        method => panic!("unexpected RPC method {}", method),
+    };
+
+    Json(serde_json::json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+}
+
+async fn start_mock_rpc() -> (String, Arc<AtomicUsize>) {
+    let sends = Arc::new(AtomicUsize::new(0));
+    let app = Router::new().route("/", post(handle_rpc)).with_state(sends.clone());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    (url, sends)
+}
+
+fn encoded_transaction() -> String {
+    let payer = Keypair::new();
+    let instruction = system_instruction::transfer(&payer.pubkey(), &Keypair::new().pubkey(), 1_000);
+    let transaction = Transaction::new_signed_with_payer(&[instruction], Some(&payer.pubkey()), &[&payer], Hash::default());
+    general_purpose::STANDARD.encode(bincode::serialize(&transaction).unwrap())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_simulation_reports_program_error() {
+    let (url, _) = start_mock_rpc().await;
+    let client = SolanaClient::new(&url, "confirmed").unwrap();
+
+    let simulation = client.simulate_transaction(&encoded_transaction()).await.unwrap();
+
+    assert!(!simulation.succeeded());
+    assert!(simulation.error.unwrap().contains("custom program error"));
+    assert_eq!(simulation.logs.len(), 1);
+    assert_eq!(simulation.units_consumed, Some(150));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "needs DATABASE_URL"]
+async fn test_failing_simulation_is_never_submitted() {
+    let mut config = common::database_config();
+    let (rpc_url, sends) = start_mock_rpc().await;
+    config.blockchain.solana_rpc_url = rpc_url;
+    let state = common::connect(config).await;
+
+    let user_id: Uuid = sqlx::query_scalar("INSERT INTO users (email, password_hash) VALUES ($1, 'hash') RETURNING id")
+        .bind(format!("simulation-{}@example.com", Uuid::new_v4()))
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap();
+    let wallet_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO wallets (user_id, name, wallet_type, public_key) VALUES ($1, 'test', 'solana', $2) RETURNING id",
+    )
+        .bind(user_id)
+        .bind(Keypair::new().pubkey().to_string())
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap();
+    let transaction = TransactionQueries::create(state.db.pool(), &CreateTransaction {
+        wallet_id,
+        transaction_type: TransactionType::Send,
+        from_address: Keypair::new().pubkey().to_string(),
+        to_address: Keypair::new().pubkey().to_string(),
+        amount: "0.000001".to_string(),
+        token_mint: None,
+        raw_transaction: Some(encoded_transaction()),
+    }).await.unwrap();
+
+    let result = TransactionService::new(state.clone())
+        .submit_transaction(transaction.id, true)
+        .await;
+
+    assert!(matches!(result, Err(Error::TransactionFailed(_))));
+    assert_eq!(sends.load(Ordering::SeqCst), 0);
+
+    let stored = TransactionQueries::find_by_id(state.db.pool(), transaction.id).await.unwrap().unwrap();
+    assert!(matches!(stored.status, TransactionStatus::Pending));
+    assert!(stored.transaction_hash.is_none());
+}
\ No newline at end of file
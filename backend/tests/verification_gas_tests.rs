@@ -0,0 +1,57 @@
+//! Tests for on-chain verification cost estimates
+
+use guardian_aa_backend::{
+    config::VerificationGasConfig,
+    zkml::gas::{GasCostTable, TargetChain},
+};
+
+#[test]
+fn test_estimate_scales_with_proof_size() {
+    let table = GasCostTable::for_chain(TargetChain::Solana);
+
+    let small = table.estimate(1_000, 32);
+    let large = table.estimate(2_000, 32);
+
+    assert!(large > small);
+    assert_eq!(large - small, 1_000 * table.per_proof_byte);
+}
+
+#[test]
+fn test_estimate_includes_base_and_public_inputs() {
+    let table = GasCostTable {
+        base: 1_000,
+        per_proof_byte: 2,
+        per_public_input_byte: 10,
+    };
+
+    assert_eq!(table.estimate(0, 0), 1_000);
+    assert_eq!(table.estimate(100, 32), 1_000 + 200 + 320);
+}
+
+#[test]
+fn test_config_selects_chain_table_or_override() {
+    let mut config = VerificationGasConfig {
+        target_chain: TargetChain::Ethereum,
+        cost_table: None,
+    };
+    assert_eq!(GasCostTable::from(&config), GasCostTable::for_chain(TargetChain::Ethereum));
+
+    let custom = GasCostTable {
+        base: 5,
+        per_proof_byte: 1,
+        per_public_input_byte: 1,
+    };
+    config.cost_table = Some(custom);
+    assert_eq!(GasCostTable::from(&config), custom);
+}
+
+#[test]
+fn test_estimate_saturates_instead_of_overflowing() {
+    let table = GasCostTable {
+        base: u64::MAX,
+        per_proof_byte: u64::MAX,
+        per_public_input_byte: u64::MAX,
+    };
+
+    assert_eq!(table.estimate(usize::MAX, usize::MAX), u64::MAX);
+}
\ No newline at end of file
@@ -0,0 +1,199 @@
+//! Tests for versioned (v0) transactions that load accounts from address
+//! lookup tables
+
+use axum::{extract::State, routing::post, Json, Router};
+use base64::{engine::general_purpose, Engine as _};
+use guardian_aa_backend::{
+    blockchain::{InstructionAction, PriorityLevel, SolanaClient},
+    error::Error,
+};
+use solana_sdk::{
+    address_lookup_table::{
+        self,
+        state::{AddressLookupTable, LookupTableMeta},
+        AddressLookupTableAccount,
+    },
+    hash::Hash,
+    message::{v0, VersionedMessage},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::{Transaction, TransactionVersion, VersionedTransaction},
+};
+use std::borrow::Cow;
+use std::sync::{Arc, Mutex};
+
+/// A v0 transfer of 1,000,000 lamports from `[1; 32]` to `[2; 32]`, with the
+/// recipient loaded from the lookup table at `[3; 32]`. Its one signature is
+/// zeroed, which is enough to price it.
+const V0_TRANSFER: &str = "AQAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAACAAQABAgEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAEBAgACDAIAAABAQg8AAAAAAAEDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwEAAA==";
+
+/// `V0_TRANSFER` with its message prefix claiming version 1
+const V1_TRANSFER: &str = "AQAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAACBAQABAgEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAEBAgACDAIAAABAQg8AAAAAAAEDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwMDAwEAAA==";
+
+const PAYER: Pubkey = Pubkey::new_from_array([1; 32]);
+const RECIPIENT: Pubkey = Pubkey::new_from_array([2; 32]);
+const LOOKUP_TABLE: Pubkey = Pubkey::new_from_array([3; 32]);
+
+/// Accounts each `getRecentPrioritizationFees` call asked about
+type SeenAccounts = Arc<Mutex<Vec<Vec<String>>>>;
+
+fn lookup_table_account(addresses: &[Pubkey]) -> serde_json::Value {
+    let table = AddressLookupTable {
+        meta: LookupTableMeta::default(),
+        addresses: Cow::Borrowed(addresses),
+    };
+    let data = table.serialize_for_tests().unwrap();
+
+    serde_json::json!({
+        "lamports": 1_000_000,
+        "data": [general_purpose::STANDARD.encode(&data), "base64"],
+        "owner": address_lookup_table::program::id().to_string(),
+        "executable": false,
+        "rentEpoch": 0,
+        "space": data.len(),
+    })
+}
+
+async fn handle_rpc(State(seen): State<SeenAccounts>, Json(request): Json<serde_json::Value>) -> Json<serde_json::Value> {
+    let result = match request["method"].as_str().unwrap_or_default() {
+        "getVersion" => serde_json::json!({ "solana-core": "2.1.0", "feature-set": 0 }),
+        "getFeeForMessage" => serde_json::json!({ "context": { "slot": 1 }, "value": 5_000 }),
+        "getRecentPrioritizationFees" => {
+            let accounts = request["params"][0].as_array().cloned().unwrap_or_default();
+            seen.lock().unwrap().push(accounts.iter().filter_map(|a| a.as_str().map(str::to_string)).collect());
+            serde_json::json!([{ "slot": 1, "prioritizationFee": 1_000 }])
+        }
+        "getMultipleAccounts" => {
+            let tables: Vec<serde_json::Value> = request["params"][0].as_array().unwrap().iter()
+                .map(|key| match key.as_str() {
+                    Some(key) if key == LOOKUP_TABLE.to_string() => lookup_table_account(&[RECIPIENT]),
+                    _ => serde_json::Value::Null,
+                })
+                .collect();
+            serde_json::json!({ "context": { "slot": 1 }, "value": tables })
+        }
+        method => panic!("unexpected RPC method {}", method),
+    };
+
+    Json(serde_json::json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+}
+
+async fn start_mock_rpc() -> (String, SeenAccounts) {
+    let seen = SeenAccounts::default();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let app = Router::new().route("/", post(handle_rpc)).with_state(seen.clone());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    (url, seen)
+}
+
+fn encode<T: serde::Serialize>(transaction: &T) -> String {
+    general_purpose::STANDARD.encode(bincode::serialize(transaction).unwrap())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_known_v0_transaction_estimates_fee() {
+    let (url, seen) = start_mock_rpc().await;
+    let client = SolanaClient::new(&url, "confirmed").unwrap();
+
+    let estimate = client.estimate_fee(V0_TRANSFER, PriorityLevel::Medium).await.unwrap();
+
+    assert_eq!(estimate.fee_lamports, 5_000);
+    assert_eq!(estimate.compute_unit_price, 1_000);
+    assert!(estimate.needs_compute_budget_update);
+
+    // The recipient only appears in the lookup table, but it's locked all
+    // the same and its recent fees count
+    let seen = seen.lock().unwrap();
+    assert!(seen[0].contains(&PAYER.to_string()));
+    assert!(seen[0].contains(&RECIPIENT.to_string()));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_legacy_transaction_estimates_fee() {
+    let (url, _) = start_mock_rpc().await;
+    let client = SolanaClient::new(&url, "confirmed").unwrap();
+    let payer = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[system_instruction::transfer(&payer.pubkey(), &RECIPIENT, 1_000_000)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        Hash::default(),
+    );
+
+    let estimate = client.estimate_fee(&encode(&transaction), PriorityLevel::Medium).await.unwrap();
+
+    assert_eq!(estimate.fee_lamports, 5_000);
+    assert_eq!(estimate.compute_unit_price, 1_000);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_known_v0_transaction_decodes_with_lookup_accounts() {
+    let (url, _) = start_mock_rpc().await;
+    let client = SolanaClient::new(&url, "confirmed").unwrap();
+
+    let decoded = client.decode_transaction(V0_TRANSFER).await.unwrap();
+
+    assert_eq!(decoded.version, TransactionVersion::Number(0));
+    assert_eq!(decoded.fee_payer, Some(PAYER.to_string()));
+    assert!(!decoded.fully_signed);
+    assert_eq!(decoded.instructions[0].action, Some(InstructionAction::SolTransfer {
+        from: PAYER.to_string(),
+        to: RECIPIENT.to_string(),
+        lamports: 1_000_000,
+    }));
+    assert!(decoded.instructions[0].accounts[1].is_writable);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_signed_v0_transaction_decodes_as_fully_signed() {
+    let (url, _) = start_mock_rpc().await;
+    let client = SolanaClient::new(&url, "confirmed").unwrap();
+    let payer = Keypair::new();
+    let message = v0::Message::try_compile(
+        &payer.pubkey(),
+        &[system_instruction::transfer(&payer.pubkey(), &RECIPIENT, 1_000_000)],
+        &[AddressLookupTableAccount { key: LOOKUP_TABLE, addresses: vec![RECIPIENT] }],
+        Hash::default(),
+    ).unwrap();
+    let transaction = VersionedTransaction::try_new(VersionedMessage::V0(message), &[&payer]).unwrap();
+
+    let decoded = client.decode_transaction(&encode(&transaction)).await.unwrap();
+
+    assert!(decoded.fully_signed);
+    assert_eq!(decoded.instructions[0].accounts[1].pubkey, RECIPIENT.to_string());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_missing_lookup_table_is_a_bad_request() {
+    let (url, _) = start_mock_rpc().await;
+    let client = SolanaClient::new(&url, "confirmed").unwrap();
+    let payer = Keypair::new();
+    let message = v0::Message::try_compile(
+        &payer.pubkey(),
+        &[system_instruction::transfer(&payer.pubkey(), &RECIPIENT, 1_000_000)],
+        &[AddressLookupTableAccount { key: Pubkey::new_unique(), addresses: vec![RECIPIENT] }],
+        Hash::default(),
+    ).unwrap();
+    let transaction = VersionedTransaction::try_new(VersionedMessage::V0(message), &[&payer]).unwrap();
+
+    let result = client.estimate_fee(&encode(&transaction), PriorityLevel::Medium).await;
+
+    assert!(matches!(result, Err(Error::BadRequest(_))));
+}
+
+#[tokio::test]
+async fn test_newer_version_is_unsupported_rather_than_corrupt() {
+    let client = SolanaClient::new("http://127.0.0.1:9", "confirmed").unwrap();
+
+    let result = client.decode_transaction(V1_TRANSFER).await;
+    assert!(matches!(result, Err(Error::UnsupportedTransactionVersion(1))));
+
+    let truncated = general_purpose::STANDARD.encode(&general_purpose::STANDARD.decode(V0_TRANSFER).unwrap()[..100]);
+    let result = client.decode_transaction(&truncated).await;
+    assert!(matches!(result, Err(Error::BadRequest(_))));
+}
\ No newline at end of file
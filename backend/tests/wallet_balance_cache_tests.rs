@@ -0,0 +1,124 @@
+//! Tests for sharing wallet balance responses through Redis
+//!
+//! The service tests need running Postgres and Redis instances and are
+//! ignored unless run with `--ignored` and both `DATABASE_URL` and
+//! `REDIS_URL` set.
+
+use axum::{extract::{Query, State}, http::Uri, routing::post, Json, Router};
+use guardian_aa_backend::{
+    api::{handlers::wallet::BalanceParams, AppState},
+    config::BalanceCacheConfig,
+    services::WalletService,
+};
+use solana_sdk::signature::{Keypair, Signer};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use uuid::Uuid;
+
+mod common;
+
+/// JSON-RPC server counting balance lookups; every wallet holds 2 SOL
+async fn handle_rpc(State(lookups): State<Arc<AtomicUsize>>, Json(request): Json<serde_json::Value>) -> Json<serde_json::Value> {
+    let result = match request["method"].as_str().unwrap_or_default() {
+        "getVersion" => serde_json::json!({ "solana-core": "2.1.0", "feature-set": 0 }),
+        "getBalance" => {
+            lookups.fetch_add(1, Ordering::SeqCst);
+            serde_json::json!({ "context": { "slot": 1 }, "value": 2_000_000_000u64 })
+        }
+        method => panic!("unexpected RPC method {}", method),
+    };
+
+    Json(serde_json::json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+}
+
+async fn start_mock_rpc() -> (String, Arc<AtomicUsize>) {
+    let lookups = Arc::new(AtomicUsize::new(0));
+    let app = Router::new().route("/", post(handle_rpc)).with_state(lookups.clone());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    (url, lookups)
+}
+
+async fn test_state(rpc_url: &str) -> Arc<AppState> {
+    let mut config = common::database_and_redis_config();
+    // Only the Redis cache is under test, so the in-process one is off
+    config.blockchain.balance_cache = BalanceCacheConfig {
+        positive_ttl_secs: 0,
+        negative_ttl_secs: 0,
+        redis_ttl_secs: 60,
+    };
+    config.blockchain.solana_rpc_url = rpc_url.to_string();
+    common::connect(config).await
+}
+
+/// A user with one Solana wallet: (user id, wallet id)
+async fn create_wallet(state: &AppState) -> (Uuid, Uuid) {
+    let user_id: Uuid = sqlx::query_scalar("INSERT INTO users (email, password_hash) VALUES ($1, 'hash') RETURNING id")
+        .bind(format!("balance-{}@example.com", Uuid::new_v4()))
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap();
+    let wallet_id = sqlx::query_scalar(
+        "INSERT INTO wallets (user_id, name, wallet_type, public_key) VALUES ($1, 'Balance', 'solana', $2) RETURNING id",
+    )
+        .bind(user_id)
+        .bind(Keypair::new().pubkey().to_string())
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap();
+
+    (user_id, wallet_id)
+}
+
+#[test]
+fn test_refresh_is_accepted_for_fresh() {
+    let uri: Uri = "/wallet/balance?refresh=true".parse().unwrap();
+    let Query(params) = Query::<BalanceParams>::try_from_uri(&uri).unwrap();
+    assert!(params.fresh);
+
+    let uri: Uri = "/wallet/balance".parse().unwrap();
+    let Query(params) = Query::<BalanceParams>::try_from_uri(&uri).unwrap();
+    assert!(!params.fresh);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_second_balance_within_ttl_skips_rpc() {
+    let (rpc_url, lookups) = start_mock_rpc().await;
+    let state = test_state(&rpc_url).await;
+    let (user_id, wallet_id) = create_wallet(&state).await;
+    let service = WalletService::new(state.clone());
+
+    let first = service.get_wallet_balance(wallet_id, user_id, false).await.unwrap();
+    let second = service.get_wallet_balance(wallet_id, user_id, false).await.unwrap();
+
+    assert_eq!(lookups.load(Ordering::SeqCst), 1);
+    assert_eq!(second.sol_balance, first.sol_balance);
+    assert_eq!(second.wallet_id, wallet_id);
+    // The timestamp survives the trip through JSON unchanged
+    assert_eq!(second.last_updated, first.last_updated);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_fresh_balance_bypasses_redis() {
+    let (rpc_url, lookups) = start_mock_rpc().await;
+    let state = test_state(&rpc_url).await;
+    let (user_id, wallet_id) = create_wallet(&state).await;
+    let service = WalletService::new(state.clone());
+
+    let first = service.get_wallet_balance(wallet_id, user_id, false).await.unwrap();
+    let fresh = service.get_wallet_balance(wallet_id, user_id, true).await.unwrap();
+    assert_eq!(lookups.load(Ordering::SeqCst), 2);
+    assert!(fresh.last_updated > first.last_updated);
+
+    // The fresh balance replaced the cached one
+    let cached = service.get_wallet_balance(wallet_id, user_id, false).await.unwrap();
+    assert_eq!(lookups.load(Ordering::SeqCst), 2);
+    assert_eq!(cached.last_updated, fresh.last_updated);
+}
\ No newline at end of file
@@ -0,0 +1,121 @@
+//! Tests for looking up the balances of all of a user's wallets at once
+//!
+//! These need a running Postgres instance and are ignored unless run with
+//! `--ignored` and `DATABASE_URL` set.
+
+use axum::{extract::State, routing::post, Json, Router};
+use guardian_aa_backend::{
+    api::AppState,
+    config::BalanceCacheConfig,
+    services::WalletService,
+};
+use solana_sdk::signature::{Keypair, Signer};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use uuid::Uuid;
+
+mod common;
+
+/// JSON-RPC server counting balance lookups; every wallet holds 2 SOL
+async fn handle_rpc(State(lookups): State<Arc<AtomicUsize>>, Json(request): Json<serde_json::Value>) -> Json<serde_json::Value> {
+    let result = match request["method"].as_str().unwrap_or_default() {
+        "getVersion" => serde_json::json!({ "solana-core": "2.1.0", "feature-set": 0 }),
+        "getBalance" => {
+            lookups.fetch_add(1, Ordering::SeqCst);
+            serde_json::json!({ "context": { "slot": 1 }, "value": 2_000_000_000u64 })
+        }
+        method => panic!("unexpected RPC method {}", method),
+    };
+
+    Json(serde_json::json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+}
+
+async fn start_mock_rpc() -> (String, Arc<AtomicUsize>) {
+    let lookups = Arc::new(AtomicUsize::new(0));
+    let app = Router::new().route("/", post(handle_rpc)).with_state(lookups.clone());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    (url, lookups)
+}
+
+async fn test_state(rpc_url: &str) -> Arc<AppState> {
+    let mut config = common::database_config();
+    // Every balance comes from the RPC
+    config.blockchain.balance_cache = BalanceCacheConfig {
+        positive_ttl_secs: 0,
+        negative_ttl_secs: 0,
+        redis_ttl_secs: 0,
+    };
+    config.blockchain.max_concurrent_balance_fetches = 2;
+    config.blockchain.solana_rpc_url = rpc_url.to_string();
+    common::connect(config).await
+}
+
+async fn create_user(state: &AppState) -> Uuid {
+    sqlx::query_scalar("INSERT INTO users (email, password_hash) VALUES ($1, 'hash') RETURNING id")
+        .bind(format!("balances-{}@example.com", Uuid::new_v4()))
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap()
+}
+
+async fn create_wallet(state: &AppState, user_id: Uuid, public_key: &str) -> Uuid {
+    sqlx::query_scalar(
+        "INSERT INTO wallets (user_id, name, wallet_type, public_key) VALUES ($1, 'Balances', 'solana', $2) RETURNING id",
+    )
+        .bind(user_id)
+        .bind(public_key)
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "needs DATABASE_URL"]
+async fn test_invalid_wallet_does_not_fail_the_others() {
+    let (rpc_url, lookups) = start_mock_rpc().await;
+    let state = test_state(&rpc_url).await;
+    let user_id = create_user(&state).await;
+    let first = create_wallet(&state, user_id, &Keypair::new().pubkey().to_string()).await;
+    let broken = create_wallet(&state, user_id, "not-a-solana-address").await;
+    let second = create_wallet(&state, user_id, &Keypair::new().pubkey().to_string()).await;
+
+    let results = WalletService::new(state.clone()).get_all_balances(user_id).await.unwrap();
+
+    assert_eq!(results.len(), 3);
+    for wallet_id in [first, second] {
+        let result = results.iter().find(|r| r.wallet_id == wallet_id).unwrap();
+        let balance = result.balance.as_ref().expect("valid wallet has a balance");
+        assert_eq!(balance.wallet_id, wallet_id);
+        assert!(balance.sol_balance.starts_with('2'));
+        assert!(result.error.is_none());
+    }
+
+    let failed = results.iter().find(|r| r.wallet_id == broken).unwrap();
+    assert!(failed.balance.is_none());
+    assert!(failed.error.is_some());
+    // The invalid address never reached the RPC
+    assert_eq!(lookups.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "needs DATABASE_URL"]
+async fn test_deactivated_wallets_are_left_out() {
+    let (rpc_url, _lookups) = start_mock_rpc().await;
+    let state = test_state(&rpc_url).await;
+    let user_id = create_user(&state).await;
+    let kept = create_wallet(&state, user_id, &Keypair::new().pubkey().to_string()).await;
+    let removed = create_wallet(&state, user_id, &Keypair::new().pubkey().to_string()).await;
+
+    let service = WalletService::new(state.clone());
+    service.deactivate_wallet(removed, user_id).await.unwrap();
+
+    let results = service.get_all_balances(user_id).await.unwrap();
+    let wallet_ids: Vec<Uuid> = results.iter().map(|r| r.wallet_id).collect();
+    assert_eq!(wallet_ids, vec![kept]);
+}
\ No newline at end of file
@@ -0,0 +1,94 @@
+//! Tests for wallet private keys being stored encrypted
+//!
+//! The tests need a running Postgres instance and are ignored unless run
+//! with `--ignored` and `DATABASE_URL` set.
+
+use guardian_aa_backend::{
+    api::AppState,
+    auth::PrivateKey,
+    db::models::{CreateWallet, WalletType},
+    error::Error,
+    services::WalletService,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+mod common;
+
+const PRIVATE_KEY: &str = "4c0883a69102937d6231471b5dbb6204fe512961708279f2e3e8a5d4b8e3e2a1";
+
+async fn test_state(master_secret: &str) -> Arc<AppState> {
+    let mut config = common::database_config();
+    config.key_vault.master_secret = master_secret.to_string();
+    common::connect(config).await
+}
+
+async fn create_user(state: &AppState) -> Uuid {
+    sqlx::query_scalar("INSERT INTO users (email, password_hash) VALUES ($1, 'hash') RETURNING id")
+        .bind(format!("vault-{}@example.com", Uuid::new_v4()))
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap()
+}
+
+fn ethereum_wallet(private_key: Option<&str>) -> CreateWallet {
+    CreateWallet {
+        name: "Vault".to_string(),
+        wallet_type: WalletType::Ethereum,
+        public_key: format!("0x{:040x}", Uuid::new_v4().as_u128()),
+        private_key: private_key.map(PrivateKey::new),
+        derivation_path: None,
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_private_key_is_stored_encrypted() {
+    let state = test_state("test pepper").await;
+    let user_id = create_user(&state).await;
+    let wallet_service = WalletService::new(state.clone());
+
+    let wallet = wallet_service.create_wallet(user_id, ethereum_wallet(Some(PRIVATE_KEY))).await.unwrap();
+
+    let stored: Option<String> = sqlx::query_scalar("SELECT encrypted_private_key FROM wallets WHERE id = $1")
+        .bind(wallet.id)
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap();
+    let stored = stored.unwrap();
+    assert!(!stored.contains(PRIVATE_KEY));
+
+    let signed = wallet_service
+        .with_signing_key(wallet.id, user_id, |key| Ok(key.to_vec()))
+        .await
+        .unwrap();
+    assert_eq!(signed, PRIVATE_KEY.as_bytes());
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_changed_master_secret_fails_cleanly() {
+    let state = test_state("test pepper").await;
+    let user_id = create_user(&state).await;
+    let wallet = WalletService::new(state).create_wallet(user_id, ethereum_wallet(Some(PRIVATE_KEY))).await.unwrap();
+
+    let rotated = test_state("another pepper").await;
+    let result = WalletService::new(rotated)
+        .with_signing_key(wallet.id, user_id, |_| Ok(()))
+        .await;
+    assert!(matches!(result, Err(Error::Internal)));
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_wallet_without_key_cannot_sign() {
+    let state = test_state("test pepper").await;
+    let user_id = create_user(&state).await;
+    let wallet_service = WalletService::new(state);
+
+    let wallet = wallet_service.create_wallet(user_id, ethereum_wallet(None)).await.unwrap();
+    assert!(wallet.encrypted_private_key.is_none());
+
+    let result = wallet_service.with_signing_key(wallet.id, user_id, |_| Ok(())).await;
+    assert!(matches!(result, Err(Error::BadRequest(_))));
+}
\ No newline at end of file
@@ -0,0 +1,185 @@
+//! Tests for signing in with a Solana wallet
+//!
+//! The sign-in tests need running Postgres and Redis instances and are
+//! ignored unless run with `--ignored` and both `DATABASE_URL` and
+//! `REDIS_URL` set.
+
+use guardian_aa_backend::{
+    api::{
+        handlers::auth::{VerifySignatureRequest, WalletChallengeRequest},
+        middleware::auth::decode_claims,
+        AppState,
+    },
+    auth::{WalletChallenge, WalletChallenges},
+    config::Config,
+    db::queries::WalletLoginQueries,
+    error::Error,
+    services::AuthService,
+};
+use chrono::{TimeZone, Utc};
+use solana_sdk::signature::{Keypair, Signer};
+use std::sync::Arc;
+use std::time::Duration;
+
+mod common;
+
+const CHALLENGE_TTL_SECS: i64 = 1;
+
+async fn test_state() -> Arc<AppState> {
+    let mut config = common::database_and_redis_config();
+    config.auth.wallet_challenge_ttl = CHALLENGE_TTL_SECS;
+    common::connect(config).await
+}
+
+/// Ask for a challenge for `wallet` and sign it
+async fn signed_challenge(service: &AuthService, wallet: &Keypair) -> VerifySignatureRequest {
+    let challenge = service
+        .wallet_challenge(WalletChallengeRequest { public_key: wallet.pubkey().to_string() })
+        .await
+        .unwrap();
+
+    VerifySignatureRequest {
+        public_key: challenge.public_key,
+        signature: wallet.sign_message(challenge.message.as_bytes()).to_string(),
+        nonce: challenge.nonce,
+    }
+}
+
+fn signed_in_user(state: &AppState, access_token: &str) -> String {
+    decode_claims(access_token, &state.config.auth).unwrap().sub
+}
+
+fn challenges(domain: &str, uri: &str) -> WalletChallenges {
+    let mut config = Config::default();
+    config.auth.wallet_sign_in_domain = domain.to_string();
+    config.auth.wallet_sign_in_uri = uri.to_string();
+    WalletChallenges::new(redis::Client::open("redis://127.0.0.1:1").unwrap(), &config.auth)
+}
+
+#[test]
+fn test_message_names_the_service_and_validity_window() {
+    let wallet = Keypair::new();
+    let issued_at = Utc.with_ymd_and_hms(2026, 1, 2, 3, 4, 5).unwrap();
+    let expires_at = issued_at + chrono::Duration::minutes(5);
+
+    let message = challenges("guardian.example", "https://guardian.example/login")
+        .message(&wallet.pubkey(), "nonce", issued_at, expires_at);
+
+    assert!(message.starts_with(&format!(
+        "guardian.example wants you to sign in with your Solana account:\n{}\n",
+        wallet.pubkey()
+    )));
+    assert!(message.contains("\nURI: https://guardian.example/login\n"));
+    assert!(message.contains("\nNonce: nonce\n"));
+    assert!(message.contains("\nIssued At: 2026-01-02T03:04:05Z\n"));
+    assert!(message.ends_with("\nExpiration Time: 2026-01-02T03:09:05Z"));
+}
+
+#[test]
+fn test_challenge_is_only_valid_in_its_window() {
+    let wallet = Keypair::new();
+    let issued_at = Utc::now();
+    let expires_at = issued_at + chrono::Duration::minutes(5);
+    let challenge = WalletChallenge {
+        public_key: wallet.pubkey().to_string(),
+        nonce: "nonce".to_string(),
+        message: challenges("guardian.example", "https://guardian.example").message(&wallet.pubkey(), "nonce", issued_at, expires_at),
+        issued_at,
+        expires_at,
+    };
+
+    assert!(challenge.is_valid_at(issued_at));
+    assert!(!challenge.is_valid_at(issued_at - chrono::Duration::seconds(1)));
+    assert!(!challenge.is_valid_at(expires_at));
+
+    let signature = wallet.sign_message(challenge.message.as_bytes());
+    assert!(challenge.is_signed_by(&wallet.pubkey(), &signature));
+    assert!(!challenge.is_signed_by(&Keypair::new().pubkey(), &signature));
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_valid_signature_signs_in() {
+    let state = test_state().await;
+    let service = AuthService::new(state.clone());
+    let wallet = Keypair::new();
+
+    let first = service.verify_signature(signed_challenge(&service, &wallet).await).await.unwrap();
+    let user = WalletLoginQueries::find_user(state.db.pool(), &wallet.pubkey().to_string())
+        .await
+        .unwrap()
+        .expect("first sign-in creates a user for the wallet");
+    assert_eq!(signed_in_user(&state, &first.access_token), user.id.to_string());
+
+    // Signing in again is the same user
+    let second = service.verify_signature(signed_challenge(&service, &wallet).await).await.unwrap();
+    assert_eq!(signed_in_user(&state, &second.access_token), user.id.to_string());
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_signature_from_another_wallet_fails() {
+    let state = test_state().await;
+    let service = AuthService::new(state.clone());
+    let wallet = Keypair::new();
+
+    let mut request = signed_challenge(&service, &wallet).await;
+    request.signature = Keypair::new().sign_message(b"anything").to_string();
+
+    assert!(matches!(service.verify_signature(request).await, Err(Error::AuthenticationFailed)));
+    assert!(WalletLoginQueries::find_user(state.db.pool(), &wallet.pubkey().to_string()).await.unwrap().is_none());
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_expired_nonce_fails() {
+    let state = test_state().await;
+    let service = AuthService::new(state.clone());
+    let wallet = Keypair::new();
+
+    let request = signed_challenge(&service, &wallet).await;
+    tokio::time::sleep(Duration::from_millis(CHALLENGE_TTL_SECS as u64 * 1000 + 200)).await;
+
+    assert!(matches!(service.verify_signature(request).await, Err(Error::AuthenticationFailed)));
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_replayed_nonce_fails() {
+    let state = test_state().await;
+    let service = AuthService::new(state.clone());
+    let wallet = Keypair::new();
+
+    let request = signed_challenge(&service, &wallet).await;
+    let replay = VerifySignatureRequest {
+        public_key: request.public_key.clone(),
+        nonce: request.nonce.clone(),
+        signature: request.signature.clone(),
+    };
+
+    service.verify_signature(request).await.unwrap();
+    assert!(matches!(service.verify_signature(replay).await, Err(Error::AuthenticationFailed)));
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_signature_of_a_message_for_another_site_fails() {
+    let state = test_state().await;
+    let service = AuthService::new(state.clone());
+    let wallet = Keypair::new();
+
+    let challenge = service
+        .wallet_challenge(WalletChallengeRequest { public_key: wallet.pubkey().to_string() })
+        .await
+        .unwrap();
+    // Same nonce and window, but the message another site showed the wallet
+    let phished = challenges("evil.example", "https://evil.example")
+        .message(&wallet.pubkey(), &challenge.nonce, challenge.issued_at, challenge.expires_at);
+    let request = VerifySignatureRequest {
+        public_key: challenge.public_key,
+        signature: wallet.sign_message(phished.as_bytes()).to_string(),
+        nonce: challenge.nonce,
+    };
+
+    assert!(matches!(service.verify_signature(request).await, Err(Error::AuthenticationFailed)));
+}
\ No newline at end of file
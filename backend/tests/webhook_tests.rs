@@ -0,0 +1,404 @@
+//! Tests for queueing, signing and retrying webhook deliveries
+//!
+//! The registration and delivery tests need a running Postgres instance and
+//! are ignored unless run with `--ignored` and `DATABASE_URL` set.
+
+use axum::{extract::State, http::{HeaderMap, StatusCode}, routing::post, Router};
+use chrono::Utc;
+use guardian_aa_backend::{
+    api::{handlers::webhook::CreateWebhookRequest, AppState},
+    config::Config,
+    db::{
+        models::{TransactionStatus, WebhookDelivery, WebhookDeliveryStatus},
+        queries::WebhookDeliveryQueries,
+    },
+    error::Error,
+    services::{
+        webhook::{check_webhook_url, is_public_address, sign_payload},
+        TransactionService, WebhookDispatcher, WebhookEvent, WebhookService,
+    },
+};
+use std::collections::VecDeque;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+mod common;
+
+/// Dispatching claims every due delivery, so the tests here take turns
+static DISPATCH: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+async fn test_state(configure: impl FnOnce(&mut Config)) -> Arc<AppState> {
+    let mut config = common::database_config();
+    config.webhooks.batch_size = 1000;
+    // The receivers here listen on loopback
+    config.webhooks.allow_private_addresses = true;
+    configure(&mut config);
+    common::connect(config).await
+}
+
+/// A webhook receiver answering with the scripted statuses in turn, then
+/// `fallback` once they run out
+#[derive(Default)]
+struct Receiver {
+    scripted: Mutex<VecDeque<u16>>,
+    fallback: u16,
+    received: Mutex<Vec<(HeaderMap, String)>>,
+}
+
+async fn receive(State(receiver): State<Arc<Receiver>>, headers: HeaderMap, body: String) -> StatusCode {
+    receiver.received.lock().unwrap().push((headers, body));
+    let status = receiver.scripted.lock().unwrap().pop_front().unwrap_or(receiver.fallback);
+    StatusCode::from_u16(status).unwrap()
+}
+
+async fn start_receiver(scripted: &[u16], fallback: u16) -> (String, Arc<Receiver>) {
+    let receiver = Arc::new(Receiver {
+        scripted: Mutex::new(scripted.iter().copied().collect()),
+        fallback,
+        received: Mutex::default(),
+    });
+    let app = Router::new().route("/hook", post(receive)).with_state(receiver.clone());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    (url, receiver)
+}
+
+/// A user with one wallet and a pending transaction in it:
+/// (user id, transaction id)
+async fn create_pending_transaction(state: &AppState) -> (Uuid, Uuid) {
+    let user_id: Uuid = sqlx::query_scalar("INSERT INTO users (email, password_hash) VALUES ($1, 'hash') RETURNING id")
+        .bind(format!("webhooks-{}@example.com", Uuid::new_v4()))
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap();
+    let wallet_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO wallets (user_id, name, wallet_type, public_key) VALUES ($1, 'Webhooks', 'solana', $2) RETURNING id",
+    )
+        .bind(user_id)
+        .bind(Uuid::new_v4().to_string())
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap();
+    let transaction_id = sqlx::query_scalar(
+        "INSERT INTO transactions (wallet_id, transaction_type, status, from_address, to_address, amount)
+         VALUES ($1, 'send', 'pending', 'from', 'to', '1') RETURNING id",
+    )
+        .bind(wallet_id)
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap();
+
+    (user_id, transaction_id)
+}
+
+async fn confirm(state: &Arc<AppState>, transaction_id: Uuid) {
+    TransactionService::new(state.clone())
+        .update_transaction_status(transaction_id, TransactionStatus::Confirmed, Some("signature"), Some(42), None, None)
+        .await
+        .unwrap();
+}
+
+async fn deliveries(state: &AppState, webhook_id: Uuid) -> Vec<WebhookDelivery> {
+    WebhookDeliveryQueries::find_by_webhook_id(state.db.pool(), webhook_id).await.unwrap()
+}
+
+fn request(url: &str, event_types: &[&str]) -> CreateWebhookRequest {
+    CreateWebhookRequest {
+        url: url.to_string(),
+        event_types: event_types.iter().map(|e| e.to_string()).collect(),
+    }
+}
+
+#[test]
+fn test_signature_is_hmac_sha256_of_body() {
+    assert_eq!(
+        sign_payload("key", "The quick brown fox jumps over the lazy dog"),
+        "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+    );
+}
+
+#[test]
+fn test_event_names_round_trip() {
+    for event in WebhookEvent::ALL {
+        assert_eq!(WebhookEvent::from_name(event.name()), Some(event));
+    }
+    assert_eq!(WebhookEvent::from_name("transaction.created"), None);
+}
+
+#[test]
+fn test_only_public_addresses_are_public() {
+    for internal in [
+        "127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "0.0.0.0", "100.64.0.1",
+        "255.255.255.255", "224.0.0.1", "::1", "::", "fd00::1", "fe80::1", "::ffff:127.0.0.1", "::ffff:10.0.0.1",
+    ] {
+        assert!(!is_public_address(internal.parse::<IpAddr>().unwrap()), "{} is public", internal);
+    }
+    for public in ["93.184.215.14", "1.1.1.1", "100.128.0.1", "2606:4700:4700::1111", "::ffff:1.1.1.1"] {
+        assert!(is_public_address(public.parse::<IpAddr>().unwrap()), "{} is internal", public);
+    }
+}
+
+fn assert_not_public(addresses: &[&str]) {
+    for address in addresses {
+        assert!(!is_public_address(address.parse::<IpAddr>().unwrap()), "{} is public", address);
+    }
+}
+
+#[test]
+fn test_reserved_ipv4_ranges_are_not_public() {
+    // IETF protocol assignments, benchmarking, reserved and documentation
+    assert_not_public(&["192.0.0.1", "192.0.0.170", "198.18.0.1", "198.19.255.255", "240.0.0.1", "254.1.2.3", "192.0.2.1", "203.0.113.9"]);
+    for public in ["192.0.1.1", "198.17.255.255", "198.20.0.1", "223.255.255.254"] {
+        assert!(is_public_address(public.parse::<IpAddr>().unwrap()), "{} is internal", public);
+    }
+}
+
+#[test]
+fn test_nat64_addresses_are_judged_by_their_ipv4_address() {
+    assert_not_public(&["64:ff9b::7f00:1", "64:ff9b::a00:1", "64:ff9b::a9fe:a9fe", "64:ff9b:1::a00:1"]);
+    assert!(is_public_address("64:ff9b::101:101".parse::<IpAddr>().unwrap()));
+}
+
+#[test]
+fn test_6to4_addresses_are_judged_by_their_ipv4_address() {
+    assert_not_public(&["2002:7f00:1::1", "2002:a00:1::", "2002:c0a8:101:1::1", "2002:a9fe:a9fe::1"]);
+    assert!(is_public_address("2002:101:101::1".parse::<IpAddr>().unwrap()));
+}
+
+#[test]
+fn test_ipv4_compatible_addresses_are_judged_by_their_ipv4_address() {
+    assert_not_public(&["::127.0.0.1", "::10.0.0.1", "::169.254.169.254", "::192.168.1.1"]);
+    assert!(is_public_address("::1.1.1.1".parse::<IpAddr>().unwrap()));
+}
+
+#[tokio::test]
+async fn test_urls_leading_inside_the_network_are_refused() {
+    for url in [
+        "http://127.0.0.1:8080/hook",
+        "http://localhost/hook",
+        "http://10.0.0.5/hook",
+        "http://169.254.169.254/latest/meta-data/",
+        "http://[::1]/hook",
+        "http://[::ffff:192.168.0.1]/hook",
+        "http://0.0.0.0/hook",
+    ] {
+        let url = reqwest::Url::parse(url).unwrap();
+        let result = check_webhook_url(&url, false).await;
+        assert!(matches!(result, Err(Error::Validation(_))), "{} was accepted", url);
+        // Unless that's allowed, for local development
+        check_webhook_url(&url, true).await.unwrap();
+    }
+
+    // An address needs no lookup, so this passes without DNS
+    check_webhook_url(&reqwest::Url::parse("https://93.184.215.14/hook").unwrap(), false).await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_confirmed_transaction_is_delivered_signed_after_a_retry() {
+    let state = test_state(|_| {}).await;
+    let _turn = DISPATCH.lock().await;
+    let (url, receiver) = start_receiver(&[500], 200).await;
+    let (user_id, transaction_id) = create_pending_transaction(&state).await;
+
+    let webhook = WebhookService::new(state.clone())
+        .create_webhook(user_id, request(&url, &["transaction.confirmed"]))
+        .await
+        .unwrap();
+    assert!(webhook.secret.starts_with("whsec_"));
+    let stored: String = sqlx::query_scalar("SELECT encrypted_secret FROM webhooks WHERE id = $1")
+        .bind(webhook.id)
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap();
+    assert!(!stored.contains(&webhook.secret));
+
+    confirm(&state, transaction_id).await;
+
+    let queued = deliveries(&state, webhook.id).await;
+    assert_eq!(queued.len(), 1);
+    assert_eq!(queued[0].event_type, "transaction.confirmed");
+    assert_eq!(queued[0].status, WebhookDeliveryStatus::Pending);
+    let body: serde_json::Value = serde_json::from_str(&queued[0].body).unwrap();
+    assert_eq!(body["event"], "transaction.confirmed");
+    assert_eq!(body["data"]["transaction_id"], transaction_id.to_string());
+
+    // The receiver fails the first attempt, which is put off
+    let dispatcher = WebhookDispatcher::new(state.clone());
+    dispatcher.deliver_due().await.unwrap();
+    let delivery = &deliveries(&state, webhook.id).await[0];
+    assert_eq!(delivery.status, WebhookDeliveryStatus::Pending);
+    assert_eq!(delivery.attempts, 1);
+    assert_eq!(delivery.last_response_status, Some(500));
+    assert!(delivery.next_attempt_at > Utc::now());
+
+    {
+        let received = receiver.received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        let (headers, body) = &received[0];
+        assert_eq!(body, &queued[0].body);
+        assert_eq!(headers["x-guardian-event"], "transaction.confirmed");
+        assert_eq!(headers["x-guardian-delivery"], delivery.id.to_string().as_str());
+        assert_eq!(
+            headers["x-guardian-signature"],
+            format!("sha256={}", sign_payload(&webhook.secret, body)).as_str()
+        );
+    }
+
+    // Not due yet, so nothing is sent
+    dispatcher.deliver_due().await.unwrap();
+    assert_eq!(receiver.received.lock().unwrap().len(), 1);
+
+    sqlx::query("UPDATE webhook_deliveries SET next_attempt_at = NOW() WHERE id = $1")
+        .bind(delivery.id)
+        .execute(state.db.pool())
+        .await
+        .unwrap();
+    dispatcher.deliver_due().await.unwrap();
+
+    let delivery = &deliveries(&state, webhook.id).await[0];
+    assert_eq!(delivery.status, WebhookDeliveryStatus::Delivered);
+    assert_eq!(delivery.attempts, 2);
+    assert_eq!(delivery.last_response_status, Some(200));
+    assert!(delivery.delivered_at.is_some());
+
+    let received = receiver.received.lock().unwrap();
+    assert_eq!(received.len(), 2);
+    assert_eq!(received[1].0["x-guardian-delivery"], received[0].0["x-guardian-delivery"]);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_delivery_is_given_up_after_max_attempts() {
+    let state = test_state(|config| {
+        config.webhooks.max_attempts = 2;
+        // Retries are due straight away
+        config.webhooks.backoff_base_secs = 0;
+    }).await;
+    let _turn = DISPATCH.lock().await;
+    let (url, receiver) = start_receiver(&[], 503).await;
+    let (user_id, transaction_id) = create_pending_transaction(&state).await;
+
+    let webhook = WebhookService::new(state.clone())
+        .create_webhook(user_id, request(&url, &["transaction.confirmed"]))
+        .await
+        .unwrap();
+    confirm(&state, transaction_id).await;
+
+    let dispatcher = WebhookDispatcher::new(state.clone());
+    let first = dispatcher.deliver_due().await.unwrap();
+    assert!(first.retried >= 1);
+    let second = dispatcher.deliver_due().await.unwrap();
+    assert!(second.failed >= 1);
+
+    let delivery = &deliveries(&state, webhook.id).await[0];
+    assert_eq!(delivery.status, WebhookDeliveryStatus::Failed);
+    assert_eq!(delivery.attempts, 2);
+    assert_eq!(delivery.last_response_status, Some(503));
+
+    // Given up on, so it isn't sent again
+    dispatcher.deliver_due().await.unwrap();
+    assert_eq!(receiver.received.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_only_subscribed_events_are_queued() {
+    let state = test_state(|_| {}).await;
+    let (user_id, transaction_id) = create_pending_transaction(&state).await;
+
+    let webhook = WebhookService::new(state.clone())
+        .create_webhook(user_id, request("https://example.com/hook", &["prediction.created"]))
+        .await
+        .unwrap();
+
+    // Failing a transaction sends nothing, and this webhook doesn't want
+    // confirmations either
+    TransactionService::new(state.clone())
+        .update_transaction_status(transaction_id, TransactionStatus::Failed, None, None, None, Some("dropped"))
+        .await
+        .unwrap();
+    confirm(&state, transaction_id).await;
+
+    assert!(deliveries(&state, webhook.id).await.is_empty());
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_invalid_webhooks_are_rejected() {
+    let state = test_state(|config| {
+        config.webhooks.max_per_user = 1;
+        config.webhooks.allow_private_addresses = false;
+    }).await;
+    let (user_id, _) = create_pending_transaction(&state).await;
+    let service = WebhookService::new(state.clone());
+
+    for req in [
+        request("not a url", &["transaction.confirmed"]),
+        request("ftp://example.com/hook", &["transaction.confirmed"]),
+        request("https://example.com/hook", &[]),
+        request("https://example.com/hook", &["transaction.created"]),
+        request("http://169.254.169.254/latest/meta-data/", &["transaction.confirmed"]),
+        request("http://127.0.0.1:5432/", &["transaction.confirmed"]),
+    ] {
+        let result = service.create_webhook(user_id, req).await;
+        assert!(matches!(result, Err(Error::Validation(_))), "{:?}", result.map(|w| w.url));
+    }
+
+    let webhook = service
+        .create_webhook(user_id, request("https://93.184.215.14/hook", &["transaction.confirmed", "transaction.confirmed"]))
+        .await
+        .unwrap();
+    assert_eq!(webhook.event_types, vec!["transaction.confirmed"]);
+
+    let over_limit = service.create_webhook(user_id, request("https://93.184.215.14/other", &["prediction.created"])).await;
+    assert!(matches!(over_limit, Err(Error::Conflict(_))));
+
+    // The secret is never listed
+    let listed = serde_json::to_value(service.list_webhooks(user_id).await.unwrap()).unwrap();
+    assert_eq!(listed[0]["id"], webhook.id.to_string());
+    assert!(listed[0].get("secret").is_none());
+
+    service.delete_webhook(user_id, webhook.id).await.unwrap();
+    assert!(matches!(service.delete_webhook(user_id, webhook.id).await, Err(Error::NotFound)));
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_deliveries_to_internal_addresses_are_refused_when_sent() {
+    let state = test_state(|_| {}).await;
+    let _turn = DISPATCH.lock().await;
+    let (url, receiver) = start_receiver(&[], 200).await;
+    let by_name = url.replace("127.0.0.1", "localhost");
+    let (user_id, transaction_id) = create_pending_transaction(&state).await;
+
+    // Registered while private addresses were allowed
+    let mut webhooks = Vec::new();
+    for url in [&url, &by_name] {
+        webhooks.push(WebhookService::new(state.clone())
+            .create_webhook(user_id, request(url, &["transaction.confirmed"]))
+            .await
+            .unwrap());
+    }
+    confirm(&state, transaction_id).await;
+
+    // ...and sent once they no longer are
+    let mut config = state.config.clone();
+    config.webhooks.allow_private_addresses = false;
+    let strict = Arc::new(AppState { config, ..(*state).clone() });
+    WebhookDispatcher::new(strict).deliver_due().await.unwrap();
+
+    for webhook in &webhooks {
+        let delivery = &deliveries(&state, webhook.id).await[0];
+        assert_eq!(delivery.attempts, 1);
+        assert_eq!(delivery.status, WebhookDeliveryStatus::Pending);
+        assert!(delivery.last_error.is_some());
+    }
+    assert!(receiver.received.lock().unwrap().is_empty());
+}
\ No newline at end of file
@@ -0,0 +1,70 @@
+//! Tests for WebSocket message size limits
+
+use futures_util::{SinkExt, StreamExt};
+use guardian_aa_backend::{
+    api::create_router,
+    config::Config,
+};
+use tokio_tungstenite::tungstenite::{protocol::frame::coding::CloseCode, Message};
+
+mod common;
+
+const MAX_MESSAGE_SIZE: usize = 1024;
+
+/// Serve the app on a random port with a small message limit, returning the
+/// WebSocket URL
+async fn start_server() -> String {
+    let mut config = Config::default();
+    config.websocket.max_message_size = MAX_MESSAGE_SIZE;
+    let state = common::lazy_state(config);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}/ws", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, create_router(state)).await.unwrap();
+    });
+
+    url
+}
+
+#[tokio::test]
+async fn test_oversized_message_closes_socket() {
+    let url = start_server().await;
+    let (mut socket, _) = tokio_tungstenite::connect_async(url.as_str()).await.unwrap();
+
+    // Welcome message
+    let welcome = socket.next().await.unwrap().unwrap();
+    assert!(welcome.is_text());
+
+    socket.send(Message::text("x".repeat(MAX_MESSAGE_SIZE + 1))).await.unwrap();
+
+    let frame = loop {
+        match socket.next().await {
+            Some(Ok(Message::Close(frame))) => break frame,
+            Some(Ok(_)) => continue,
+            other => panic!("expected a close frame, got {:?}", other),
+        }
+    };
+    let frame = frame.expect("close frame should carry a reason");
+    assert_eq!(frame.code, CloseCode::Size);
+    assert!(frame.reason.contains(&MAX_MESSAGE_SIZE.to_string()));
+}
+
+#[tokio::test]
+async fn test_message_within_limit_is_handled() {
+    let url = start_server().await;
+    let (mut socket, _) = tokio_tungstenite::connect_async(url.as_str()).await.unwrap();
+    socket.next().await.unwrap().unwrap();
+
+    socket.send(Message::text(r#"{"type":"ping"}"#)).await.unwrap();
+
+    // Skip heartbeats until the reply arrives
+    loop {
+        let reply = socket.next().await.unwrap().unwrap();
+        let reply: serde_json::Value = serde_json::from_str(reply.to_text().unwrap()).unwrap();
+        if reply["type"] != "heartbeat" {
+            assert_eq!(reply["type"], "pong");
+            break;
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,201 @@
+//! Tests for transaction update subscriptions over WebSocket
+//!
+//! Subscribing needs a wallet in Postgres and authenticating checks the
+//! token denylist in Redis, so those tests are ignored unless run with
+//! `--ignored` and both `DATABASE_URL` and `REDIS_URL` set.
+
+use futures_util::{SinkExt, StreamExt};
+use guardian_aa_backend::{
+    api::{create_router, handlers::auth::RegisterRequest, middleware::auth::decode_claims, AppState},
+    config::Config,
+    db::models::TransactionStatus,
+    services::{AuthService, TransactionUpdate},
+};
+use solana_sdk::signature::{Keypair, Signer};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{
+    tungstenite::{client::IntoClientRequest, Message},
+    MaybeTlsStream, WebSocketStream,
+};
+use uuid::Uuid;
+
+mod common;
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+async fn test_state() -> Arc<AppState> {
+    common::connect(common::database_and_redis_config()).await
+}
+
+/// Serve the app on a random port, returning the WebSocket URL
+async fn start_server(state: Arc<AppState>) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}/ws", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, create_router(state)).await.unwrap();
+    });
+
+    url
+}
+
+/// Connect, optionally with a bearer token, and read past the welcome
+async fn connect(url: &str, access_token: Option<&str>) -> Socket {
+    let mut request = url.into_client_request().unwrap();
+    if let Some(token) = access_token {
+        request.headers_mut().insert("authorization", format!("Bearer {}", token).parse().unwrap());
+    }
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+    socket.next().await.unwrap().unwrap();
+    socket
+}
+
+/// Send `message` and return the first reply that isn't a heartbeat
+async fn request(socket: &mut Socket, message: serde_json::Value) -> serde_json::Value {
+    socket.send(Message::text(message.to_string())).await.unwrap();
+    next_message(socket).await
+}
+
+async fn next_message(socket: &mut Socket) -> serde_json::Value {
+    loop {
+        let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+            .await
+            .expect("timed out waiting for a message")
+            .unwrap()
+            .unwrap();
+        let message: serde_json::Value = serde_json::from_str(message.to_text().unwrap()).unwrap();
+        if message["type"] != "heartbeat" {
+            return message;
+        }
+    }
+}
+
+/// Register a user with one wallet, returning their access token and the
+/// wallet's id
+async fn user_with_wallet(state: &Arc<AppState>) -> (String, Uuid) {
+    let auth = AuthService::new(state.clone())
+        .register(RegisterRequest {
+            email: format!("subscriber-{}@example.com", Uuid::new_v4()),
+            password: "correct horse battery".to_string(),
+            username: None,
+        })
+        .await
+        .unwrap();
+    let user_id: Uuid = decode_claims(&auth.access_token, &state.config.auth).unwrap().sub.parse().unwrap();
+
+    let wallet_id = sqlx::query_scalar(
+        "INSERT INTO wallets (user_id, name, wallet_type, public_key) VALUES ($1, 'test', 'solana', $2) RETURNING id",
+    )
+        .bind(user_id)
+        .bind(Keypair::new().pubkey().to_string())
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap();
+
+    (auth.access_token, wallet_id)
+}
+
+fn update(wallet_id: Uuid, status: TransactionStatus) -> TransactionUpdate {
+    TransactionUpdate {
+        transaction_id: Uuid::new_v4(),
+        wallet_id,
+        status,
+        transaction_hash: Some("5wHu1qwD7q".to_string()),
+        block_number: Some(42),
+        error_message: None,
+        updated_at: chrono::Utc::now(),
+    }
+}
+
+#[tokio::test]
+async fn test_subscribing_requires_authentication() {
+    let url = start_server(common::lazy_state(Config::default())).await;
+    let mut socket = connect(&url, None).await;
+
+    let reply = request(&mut socket, serde_json::json!({
+        "type": "subscribe",
+        "channel": "tx",
+        "wallet_id": Uuid::new_v4(),
+    })).await;
+
+    assert_eq!(reply["type"], "error");
+    assert_eq!(reply["message"], "Authentication required");
+}
+
+#[tokio::test]
+async fn test_unknown_channel_is_rejected() {
+    let url = start_server(common::lazy_state(Config::default())).await;
+    let mut socket = connect(&url, None).await;
+
+    let reply = request(&mut socket, serde_json::json!({ "type": "subscribe", "channel": "prices" })).await;
+
+    assert_eq!(reply["type"], "error");
+    assert_eq!(reply["message"], "Unknown channel: prices");
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_subscriber_receives_updates_for_its_wallet() {
+    let state = test_state().await;
+    let (access_token, wallet_id) = user_with_wallet(&state).await;
+    let url = start_server(state.clone()).await;
+    let mut socket = connect(&url, Some(&access_token)).await;
+
+    let reply = request(&mut socket, serde_json::json!({
+        "type": "subscribe",
+        "channel": "tx",
+        "wallet_id": wallet_id,
+    })).await;
+    assert_eq!(reply["type"], "subscribed");
+    assert_eq!(reply["wallet_id"], wallet_id.to_string());
+
+    // Another wallet's update is not delivered, this wallet's is
+    state.transaction_events.publish(update(Uuid::new_v4(), TransactionStatus::Confirmed));
+    let confirmed = update(wallet_id, TransactionStatus::Confirmed);
+    state.transaction_events.publish(confirmed.clone());
+
+    let message = next_message(&mut socket).await;
+    assert_eq!(message["type"], "transaction_update");
+    assert_eq!(message["channel"], "tx");
+    assert_eq!(message["data"], serde_json::to_value(&confirmed).unwrap());
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_unsubscribed_wallet_receives_nothing() {
+    let state = test_state().await;
+    let (access_token, wallet_id) = user_with_wallet(&state).await;
+    let url = start_server(state.clone()).await;
+    let mut socket = connect(&url, Some(&access_token)).await;
+
+    let subscription = serde_json::json!({ "type": "subscribe", "channel": "tx", "wallet_id": wallet_id });
+    assert_eq!(request(&mut socket, subscription).await["type"], "subscribed");
+    let reply = request(&mut socket, serde_json::json!({ "type": "unsubscribe", "channel": "tx", "wallet_id": wallet_id })).await;
+    assert_eq!(reply["type"], "unsubscribed");
+
+    state.transaction_events.publish(update(wallet_id, TransactionStatus::Failed));
+
+    // The next thing the client hears is its own ping being answered
+    assert_eq!(request(&mut socket, serde_json::json!({ "type": "ping" })).await["type"], "pong");
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and REDIS_URL"]
+async fn test_cannot_subscribe_to_another_users_wallet() {
+    let state = test_state().await;
+    let (access_token, _) = user_with_wallet(&state).await;
+    let (_, other_wallet_id) = user_with_wallet(&state).await;
+    let url = start_server(state.clone()).await;
+    let mut socket = connect(&url, Some(&access_token)).await;
+
+    let reply = request(&mut socket, serde_json::json!({
+        "type": "subscribe",
+        "channel": "tx",
+        "wallet_id": other_wallet_id,
+    })).await;
+
+    assert_eq!(reply["type"], "error");
+    assert_eq!(reply["message"], "Wallet not found");
+}
\ No newline at end of file
@@ -1,6 +1,6 @@
 //! Tests for ZKML integration
 
-use guardian_aa_backend::zkml::{ZkmlService, ZkProof};
+use guardian_aa_backend::zkml::{ProofRequest, ZkmlService, ZkProof};
 use base64::{Engine as _, engine::general_purpose};
 
 #[tokio::test]
@@ -18,6 +18,23 @@ async fn test_zkml_health_check() {
     assert!(health.unwrap());
 }
 
+#[tokio::test]
+async fn test_zkml_health_check_does_not_prove() {
+    let service = ZkmlService::new().unwrap();
+    // The first check may have to set up the proving system
+    assert!(service.health_check().unwrap());
+
+    // A single proof takes far longer than all of these together
+    let clone = service.clone();
+    let started = std::time::Instant::now();
+    for _ in 0..100 {
+        assert!(service.health_check().unwrap());
+        assert!(clone.health_check().unwrap());
+    }
+    assert!(started.elapsed() < std::time::Duration::from_millis(100), "took {:?}", started.elapsed());
+    assert_eq!(service.proofs_generated(), 0);
+}
+
 #[tokio::test]
 async fn test_sha256_proof_generation() {
     let service = ZkmlService::new().unwrap();
@@ -69,7 +86,7 @@ async fn test_prover_status() {
     let status = service.get_status();
     
     assert!(status.available);
-    assert!(status.circuit_size.contains("2^14"));
+    assert!(status.circuit_size.contains("2^17"));
     assert!(status.estimated_setup_time_ms > 0);
     assert!(status.error.is_none());
 }
@@ -91,7 +108,7 @@ async fn test_empty_data_proof() {
 #[tokio::test]
 async fn test_large_data_proof() {
     let service = ZkmlService::new().unwrap();
-    let large_data = vec![0u8; 1024]; // 1KB of data
+    let large_data = vec![0u8; service.max_input_len()]; // As much as the circuit holds
     
     let result = service.generate_sha256_proof(&large_data).await;
     assert!(result.is_ok());
@@ -119,4 +136,338 @@ async fn test_proof_serialization() {
     let deserialized_proof = deserialized.unwrap();
     assert_eq!(proof.hash, deserialized_proof.hash);
     assert_eq!(proof.circuit_type, deserialized_proof.circuit_type);
-} 
\ No newline at end of file
+} 
+#[tokio::test]
+async fn test_proof_carries_transcript_bytes() {
+    let service = ZkmlService::new().unwrap();
+    let proof = service.generate_sha256_proof(b"transcript").await.unwrap();
+    assert!(!proof.proof_data.is_empty());
+}
+
+#[tokio::test]
+async fn test_tampered_proof_fails_verification() {
+    let service = ZkmlService::new().unwrap();
+    let test_data = b"tamper with me";
+
+    let mut proof = service.generate_sha256_proof(test_data).await.unwrap();
+    let mid = proof.proof_data.len() / 2;
+    proof.proof_data[mid] ^= 0x01;
+
+    // Hash still matches the data, but the transcript no longer verifies
+    let verification = service.verify_sha256_proof(&proof, test_data).await.unwrap();
+    assert!(!verification);
+}
+
+#[tokio::test]
+async fn test_streamed_proof_round_trip() {
+    use guardian_aa_backend::api::handlers::zkml::proof_checksum;
+
+    let service = ZkmlService::new().unwrap();
+    let proof = service.generate_sha256_proof(b"stream me").await.unwrap();
+    let checksum = proof_checksum(&proof.proof_data);
+
+    // Stream the proof out in small chunks and reassemble it on the other side
+    let mut received = Vec::new();
+    for chunk in proof.proof_data.chunks(256) {
+        received.extend_from_slice(chunk);
+    }
+
+    assert_eq!(proof_checksum(&received), checksum);
+    assert!(service.verify_proof_bytes(&proof.hash, &received).await.unwrap());
+
+    // A truncated stream must not verify
+    received.truncate(received.len() / 2);
+    assert_ne!(proof_checksum(&received), checksum);
+    assert!(!service.verify_proof_bytes(&proof.hash, &received).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_oversized_input_is_rejected() {
+    let service = ZkmlService::new().unwrap();
+    let info = service.get_sha256_circuit_info();
+    let test_data = vec![0u8; info.max_input_size + 1];
+
+    let result = service.generate_sha256_proof(&test_data).await;
+    match result {
+        Err(guardian_aa_backend::error::Error::Validation(msg)) => {
+            assert!(msg.contains(&test_data.len().to_string()));
+            assert!(msg.contains(&info.max_input_size.to_string()));
+        }
+        other => panic!("expected validation error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_max_size_input_is_proved() {
+    use sha2::{Digest, Sha256};
+
+    let service = ZkmlService::new().unwrap();
+    let test_data = vec![7u8; service.get_sha256_circuit_info().max_input_size];
+
+    let proof = service.generate_sha256_proof(&test_data).await.unwrap();
+    assert_eq!(proof.hash.as_slice(), Sha256::digest(&test_data).as_slice());
+    assert!(service.verify_sha256_proof(&proof, &test_data).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_verify_rejects_short_public_inputs() {
+    let service = ZkmlService::new().unwrap();
+    let test_data = b"schema validation data";
+    let mut proof = service.generate_sha256_proof(test_data).await.unwrap();
+    proof.public_inputs.truncate(16);
+
+    let err = service.verify_sha256_proof(&proof, test_data).await.unwrap_err();
+    match err {
+        guardian_aa_backend::error::Error::Validation(msg) => {
+            assert_eq!(msg, "expected 32 public input bytes, got 16");
+        }
+        other => panic!("expected validation error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_verify_rejects_oversized_public_inputs() {
+    let service = ZkmlService::new().unwrap();
+    let test_data = b"schema validation data";
+    let mut proof = service.generate_sha256_proof(test_data).await.unwrap();
+    proof.public_inputs.extend_from_slice(&[0u8; 8]);
+
+    let err = service.verify_sha256_proof(&proof, test_data).await.unwrap_err();
+    assert!(matches!(
+        err,
+        guardian_aa_backend::error::Error::Validation(ref msg) if msg == "expected 32 public input bytes, got 40"
+    ));
+}
+
+#[test]
+fn test_public_input_schema_for_unknown_circuit() {
+    use guardian_aa_backend::zkml::PublicInputSchema;
+
+    assert_eq!(PublicInputSchema::for_circuit("sha256"), Some(PublicInputSchema { len: 32 }));
+    assert!(PublicInputSchema::for_circuit("poseidon").is_none());
+}
+#[tokio::test]
+async fn test_committed_proof_record_excludes_raw_data() {
+    let service = ZkmlService::new().unwrap();
+    let secret = b"confidential portfolio explanation";
+    let blinding = [11u8; 32];
+    let commitment = guardian_zkml::hash_commitment(&blinding, secret);
+
+    let proof = service
+        .generate_committed_proof(secret, &blinding, &commitment)
+        .await
+        .unwrap();
+    assert_eq!(
+        proof.circuit_type,
+        guardian_aa_backend::zkml::COMMITMENT_CIRCUIT
+    );
+    assert_eq!(&proof.public_inputs[32..], &commitment);
+
+    // Neither the stored fields nor the serialized record contain the data or blinding
+    let contains =
+        |haystack: &[u8], needle: &[u8]| haystack.windows(needle.len()).any(|w| w == needle);
+    assert!(!contains(&proof.proof_data, secret));
+    assert!(!contains(&proof.public_inputs, secret));
+    assert!(!contains(&proof.public_inputs, &blinding));
+
+    let record = serde_json::to_string(&proof).unwrap();
+    assert!(!record.contains(std::str::from_utf8(secret).unwrap()));
+    assert!(!record.contains(&general_purpose::STANDARD.encode(secret)));
+    assert!(!record.contains(&hex::encode(secret)));
+
+    // The stored record alone verifies the commitment -> hash relation
+    let stored: ZkProof = serde_json::from_str(&record).unwrap();
+    assert!(service.verify_committed_proof(&stored).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_committed_proof_rejects_swapped_commitment() {
+    let service = ZkmlService::new().unwrap();
+    let secret = b"committed data";
+    let blinding = [3u8; 32];
+    let commitment = guardian_zkml::hash_commitment(&blinding, secret);
+
+    let mut proof = service
+        .generate_committed_proof(secret, &blinding, &commitment)
+        .await
+        .unwrap();
+    let other = guardian_zkml::hash_commitment(&[4u8; 32], secret);
+    proof.public_inputs[32..].copy_from_slice(&other);
+
+    assert!(!service.verify_committed_proof(&proof).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_committed_proof_requires_matching_commitment() {
+    let service = ZkmlService::new().unwrap();
+    let result = service
+        .generate_committed_proof(b"data", &[1u8; 32], &[0u8; 32])
+        .await;
+    assert!(matches!(
+        result,
+        Err(guardian_aa_backend::error::Error::Validation(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_prover_status_reports_last_proof_metrics() {
+    let service = ZkmlService::new().unwrap();
+    assert!(service.get_status().last_proof_metrics.is_none());
+
+    let test_data = b"status metrics";
+    service.generate_sha256_proof(test_data).await.unwrap();
+
+    let metrics = service.get_status().last_proof_metrics.expect("metrics recorded");
+    assert_eq!(metrics.input_len, test_data.len());
+    assert!(metrics.total_ms >= metrics.proving_ms);
+}
+
+#[tokio::test]
+async fn test_keccak256_proof_round_trip() {
+    let service = ZkmlService::new().unwrap();
+    let request = ProofRequest {
+        input_data: b"ethereum payload".to_vec(),
+        circuit_type: "keccak256".to_string(),
+        bypass_cache: false,
+    };
+
+    let proof = service.generate_proof(&request).await.unwrap();
+    assert_eq!(proof.circuit_type, "keccak256");
+    assert_eq!(proof.hash, guardian_zkml::keccak256(&request.input_data));
+
+    assert!(service.verify_proof(&proof, &request.input_data).await.unwrap());
+    assert!(!service.verify_proof(&proof, b"other payload").await.unwrap());
+}
+
+#[tokio::test]
+async fn test_unknown_circuit_type_is_rejected() {
+    let service = ZkmlService::new().unwrap();
+    let request = ProofRequest {
+        input_data: b"data".to_vec(),
+        circuit_type: "md5".to_string(),
+        bypass_cache: false,
+    };
+
+    let result = service.generate_proof(&request).await;
+    assert!(matches!(result, Err(guardian_aa_backend::error::Error::BadRequest(_))));
+}
+
+#[test]
+fn test_keccak256_circuit_info() {
+    let service = ZkmlService::new().unwrap();
+    let info = service.get_circuit_info(guardian_zkml::CircuitType::Keccak256);
+
+    assert_eq!(info.name, "Keccak256");
+    assert_eq!(info.public_input_size, 32);
+    assert!(info.max_input_size > 0);
+}
+
+#[tokio::test]
+async fn test_sha256_proof_batch() {
+    use sha2::{Digest, Sha256};
+
+    let service = ZkmlService::new().unwrap();
+    let max_input_size = service.get_sha256_circuit_info().max_input_size;
+    let inputs = vec![
+        b"batch one".to_vec(),
+        b"batch two".to_vec(),
+        vec![0u8; max_input_size + 1],
+        b"batch three".to_vec(),
+    ];
+
+    let results = service.generate_sha256_proofs_batch(&inputs).await.unwrap();
+    assert_eq!(results.len(), inputs.len());
+
+    for (index, (result, data)) in results.iter().zip(&inputs).enumerate() {
+        if index == 2 {
+            assert!(matches!(result, Err(guardian_aa_backend::error::Error::Validation(_))));
+            continue;
+        }
+        let proof = result.as_ref().unwrap();
+        assert_eq!(proof.hash.as_slice(), Sha256::digest(data).as_slice());
+        assert!(service.verify_sha256_proof(proof, data).await.unwrap());
+    }
+}
+
+#[tokio::test]
+async fn test_sha256_proof_batch_limits() {
+    let service = ZkmlService::new().unwrap();
+
+    let empty: Vec<Vec<u8>> = Vec::new();
+    assert!(service.generate_sha256_proofs_batch(&empty).await.is_err());
+
+    let too_many = vec![b"x".to_vec(); 1000];
+    let result = service.generate_sha256_proofs_batch(&too_many).await;
+    assert!(matches!(result, Err(guardian_aa_backend::error::Error::Validation(_))));
+}
+
+#[tokio::test]
+async fn test_sha256_proof_labeled_keccak256_fails_verification() {
+    let service = ZkmlService::new().unwrap();
+    let test_data = b"relabeled proof data";
+    let mut proof = service.generate_sha256_proof(test_data).await.unwrap();
+    proof.circuit_type = "keccak256".to_string();
+
+    // Routed to the Keccak256 verifier, which rejects it
+    assert!(!service.verify_proof(&proof, test_data).await.unwrap());
+
+    // The SHA256 verifier won't accept a proof labeled for another circuit
+    let err = service.verify_sha256_proof(&proof, test_data).await.unwrap_err();
+    assert!(matches!(
+        err,
+        guardian_aa_backend::error::Error::Validation(ref msg) if msg == "expected a sha256 proof, got keccak256"
+    ));
+}
+
+#[tokio::test]
+async fn test_external_proof_verifies_against_pinned_key() {
+    let service = ZkmlService::new().unwrap();
+    let test_data = b"external proof data";
+    let proof = service.generate_sha256_proof(test_data).await.unwrap();
+    let vk_hash = service.verification_key_hash().unwrap();
+
+    assert!(service.verify_external_proof(&vk_hash, &proof, Some(test_data)).await.unwrap());
+    assert!(service.verify_external_proof(&vk_hash.to_uppercase(), &proof, Some(test_data)).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_external_proof_with_unknown_key_is_rejected() {
+    let service = ZkmlService::new().unwrap();
+    let test_data = b"external proof data";
+    let proof = service.generate_sha256_proof(test_data).await.unwrap();
+
+    let result = service.verify_external_proof(&"ab".repeat(32), &proof, Some(test_data)).await;
+    assert!(matches!(result, Err(guardian_aa_backend::error::Error::BadRequest(_))));
+}
+
+#[tokio::test]
+async fn test_external_proof_rejected_when_current_key_is_not_pinned() {
+    let service = ZkmlService::new().unwrap().with_pinned_vk_hashes(vec!["cd".repeat(32)]);
+    let test_data = b"external proof data";
+    let proof = service.generate_sha256_proof(test_data).await.unwrap();
+    let vk_hash = service.verification_key_hash().unwrap();
+
+    assert!(service.pinned_vk_hashes().unwrap().is_empty());
+    let result = service.verify_external_proof(&vk_hash, &proof, Some(test_data)).await;
+    assert!(matches!(result, Err(guardian_aa_backend::error::Error::BadRequest(_))));
+}
+
+#[test]
+fn test_external_verify_request_rejects_key_material() {
+    use guardian_aa_backend::api::handlers::zkml::VerifyExternalProofRequest;
+
+    let mut request = serde_json::json!({
+        "vk_hash": "ab".repeat(32),
+        "proof": {
+            "proof_data": [],
+            "public_inputs": [],
+            "hash": [0u8; 32],
+            "circuit_type": "sha256",
+            "created_at": "2024-01-01T00:00:00Z",
+        },
+    });
+    assert!(serde_json::from_value::<VerifyExternalProofRequest>(request.clone()).is_ok());
+
+    request["verifying_key"] = serde_json::json!("AAAA");
+    assert!(serde_json::from_value::<VerifyExternalProofRequest>(request).is_err());
+}
\ No newline at end of file
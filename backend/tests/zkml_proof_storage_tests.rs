@@ -0,0 +1,73 @@
+//! Tests for storing generated proofs and tracking their status
+//!
+//! These tests need a running Postgres instance and are ignored unless run
+//! with `--ignored` and `DATABASE_URL` set.
+
+use guardian_aa_backend::{
+    api::AppState,
+    error::Error,
+    services::ZkmlProofService,
+    zkml::ProofRequest,
+};
+use uuid::Uuid;
+
+mod common;
+
+async fn create_user(state: &AppState) -> Uuid {
+    sqlx::query_scalar("INSERT INTO users (email, password_hash) VALUES ($1, 'hash') RETURNING id")
+        .bind(format!("proofs-{}@example.com", Uuid::new_v4()))
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_stored_proof_transitions_to_verified() {
+    let state = common::test_state().await;
+    let user_id = create_user(&state).await;
+    let proof_service = ZkmlProofService::new(state.clone());
+
+    let request = ProofRequest {
+        input_data: b"audit trail".to_vec(),
+        circuit_type: "sha256".to_string(),
+        bypass_cache: false,
+    };
+    let stored = proof_service.generate_proof(user_id, &request).await.unwrap();
+    assert!(!stored.proof.proof_data.is_empty());
+
+    let status = proof_service.get_proof_status(stored.proof_id, user_id).await.unwrap();
+    assert!(!status.is_verified);
+    assert!(status.verified_at.is_none());
+
+    let is_valid = state.zkml_service.verify_proof(&stored.proof, &request.input_data).await.unwrap();
+    assert!(is_valid);
+    proof_service.mark_verified(stored.proof_id, user_id).await.unwrap();
+
+    let status = proof_service.get_proof_status(stored.proof_id, user_id).await.unwrap();
+    assert!(status.is_verified);
+    assert!(status.verified_at.unwrap() >= status.created_at);
+    assert!(status.verification_gas_cost.unwrap() > 0);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn test_proof_status_is_private_to_its_owner() {
+    let state = common::test_state().await;
+    let owner = create_user(&state).await;
+    let other_user = create_user(&state).await;
+    let proof_service = ZkmlProofService::new(state.clone());
+
+    let request = ProofRequest {
+        input_data: b"private".to_vec(),
+        circuit_type: "sha256".to_string(),
+        bypass_cache: false,
+    };
+    let stored = proof_service.generate_proof(owner, &request).await.unwrap();
+
+    let result = proof_service.get_proof_status(stored.proof_id, other_user).await;
+    assert!(matches!(result, Err(Error::NotFound)));
+
+    let result = proof_service.get_proof_status(Uuid::new_v4(), owner).await;
+    assert!(matches!(result, Err(Error::NotFound)));
+}
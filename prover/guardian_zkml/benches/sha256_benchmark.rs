@@ -5,7 +5,10 @@ use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criteri
 // For this example, I'll assume the library can be accessed via `guardian_zkml`.
 // You might need to adjust this to `crate_name` or however your lib is exposed.
 use guardian_zkml; // Assuming lib.rs functions are part of this crate
-use guardian_zkml::{benchmark_proof_generation, generate_proof_slice};
+use guardian_zkml::{
+    benchmark_proof_generation, generate_proof_slice, generate_proofs_batch, init_proving_system,
+    shutdown_proving_system, ProverConfig,
+};
 use std::time::Duration;
 
 fn benchmark_sha256_proof_generation(c: &mut Criterion) {
@@ -14,7 +17,7 @@ fn benchmark_sha256_proof_generation(c: &mut Criterion) {
         ("empty", vec![]),
         ("small", b"hello world".to_vec()),
         ("medium", b"Lorem ipsum dolor sit amet, consectetur adipiscing elit. Sed do eiusmod tempor incididunt ut labore et dolore magna aliqua.".to_vec()),
-        ("large", vec![0u8; 1024]), // 1KB of zeros
+        ("large", vec![0u8; 256]), // Five padded blocks, within the default circuit
     ];
 
     let mut group = c.benchmark_group("sha256_proof_generation");
@@ -57,18 +60,85 @@ fn benchmark_proof_verification(c: &mut Criterion) {
     let test_data = b"verification test data".to_vec();
 
     // Pre-generate proof for verification benchmark
-    let output = generate_proof_slice(&test_data);
+    let (output, proof) = generate_proof_slice(&test_data);
 
     let mut group = c.benchmark_group("sha256_proof_verification");
     group.sample_size(20);
 
     group.bench_function("verify_proof", |b| {
-        b.iter(|| guardian_zkml::verify_proof_slice(black_box(&test_data), black_box(&output)))
+        b.iter(|| guardian_zkml::verify_proof_slice(black_box(&output), black_box(&proof)))
     });
 
     group.finish();
 }
 
+fn benchmark_batch_proof_generation(c: &mut Criterion) {
+    // 32 small inputs, e.g. a burst of agent explanations
+    let inputs: Vec<Vec<u8>> = (0..32)
+        .map(|i| format!("agent explanation {}", i).into_bytes())
+        .collect();
+    let slices: Vec<&[u8]> = inputs.iter().map(|input| input.as_slice()).collect();
+
+    // Warm up the proving system so setup is not measured
+    let _ = generate_proof_slice(b"warm up");
+
+    let mut group = c.benchmark_group("sha256_batch_proof_generation");
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(60));
+
+    group.bench_function("sequential_32", |b| {
+        b.iter(|| {
+            for input in &slices {
+                let _output = generate_proof_slice(black_box(input));
+            }
+        })
+    });
+
+    group.bench_function("batch_32", |b| {
+        b.iter(|| {
+            for result in generate_proofs_batch(black_box(&slices)) {
+                if let Err(e) = result {
+                    panic!("Batch proof failed: {}", e);
+                }
+            }
+        })
+    });
+
+    group.finish();
+}
+
+fn benchmark_proving_system_startup(c: &mut Criterion) {
+    // The cache only holds the params; keys are derived again on every load
+    let cache_dir =
+        std::env::temp_dir().join(format!("guardian-zkml-bench-{}", std::process::id()));
+    let config = ProverConfig::default();
+
+    let mut group = c.benchmark_group("proving_system_startup");
+    group.sample_size(10);
+    group.measurement_time(Duration::from_secs(60));
+
+    group.bench_function("generate", |b| {
+        b.iter(|| {
+            shutdown_proving_system();
+            init_proving_system(config, None).unwrap();
+        })
+    });
+
+    // Write the cache once, then measure loading it
+    shutdown_proving_system();
+    init_proving_system(config, Some(&cache_dir)).unwrap();
+    group.bench_function("load_cached_params", |b| {
+        b.iter(|| {
+            shutdown_proving_system();
+            init_proving_system(config, Some(&cache_dir)).unwrap();
+        })
+    });
+
+    group.finish();
+    shutdown_proving_system();
+    let _ = std::fs::remove_dir_all(&cache_dir);
+}
+
 fn performance_test(_c: &mut Criterion) {
     println!("\n=== Performance Test Results ===");
 
@@ -79,7 +149,7 @@ fn performance_test(_c: &mut Criterion) {
             "medium",
             b"This is a medium-sized test string for SHA256 proof generation.".to_vec(),
         ),
-        ("large", vec![42u8; 512]),
+        ("large", vec![42u8; 256]),
     ];
 
     for (name, data) in test_cases {
@@ -109,6 +179,8 @@ criterion_group!(
     benches,
     benchmark_sha256_proof_generation,
     benchmark_proof_verification,
-    performance_test
+    benchmark_batch_proof_generation,
+    performance_test,
+    benchmark_proving_system_startup
 );
 criterion_main!(benches);
@@ -0,0 +1,92 @@
+/*
+ * C interface of the guardian_zkml prover.
+ *
+ * Every function may be called from any thread. The proving system is
+ * initialized by the first call that needs it, which can take seconds.
+ *
+ * Memory: the library never takes ownership of caller memory. Input data
+ * only needs to stay alive for the duration of a call. Proof buffers
+ * returned by generate_proof_v2 and generate_proof_batch belong to the
+ * caller and must be released with free_proof, exactly once, passing back
+ * the pointer and length the library returned. Never release them with
+ * free() or another allocator.
+ */
+
+#ifndef GUARDIAN_ZKML_H
+#define GUARDIAN_ZKML_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Status codes; guardian_zkml_strerror describes each */
+#define GUARDIAN_ZKML_OK 0
+#define GUARDIAN_ZKML_VERIFY_FAILED 1 /* proof checked, and not valid */
+#define GUARDIAN_ZKML_NULL_POINTER (-1) /* a required pointer was null */
+#define GUARDIAN_ZKML_NULL_DATA (-2) /* an input's data pointer was null */
+#define GUARDIAN_ZKML_PROOF_FAILED (-3) /* the prover failed */
+
+/* Bytes to prove knowledge of; data must not be null, even when len is 0 */
+typedef struct {
+    const uint8_t *data;
+    size_t len;
+} Input;
+
+/* Length of the proven input and its SHA256 hash */
+typedef struct {
+    size_t len;
+    uint8_t hash[32];
+} Output;
+
+/* Static, NUL-terminated description of a status code. Do not free it. */
+const char *guardian_zkml_strerror(int32_t code);
+
+/* Free the proving system's memory; the next proof initializes it again */
+void guardian_zkml_shutdown(void);
+
+/* Size of Output, so callers can allocate it without mirroring the struct */
+size_t bytes_required(void);
+
+/*
+ * Prove knowledge of input's data. On success writes the hash to *output,
+ * and a newly allocated proof of *proof_len_out bytes to *proof_out, which
+ * the caller owns and must release with free_proof. On failure *proof_out
+ * and *proof_len_out are left untouched.
+ */
+int32_t generate_proof_v2(const Input *input, Output *output, uint8_t **proof_out,
+                          size_t *proof_len_out);
+
+/*
+ * Prove count inputs. inputs, outputs, proofs_out and proof_lens_out each
+ * hold count elements. Every non-null proofs_out entry must be released
+ * with free_proof. Entries that fail get a zeroed output and a null proof,
+ * and the call returns GUARDIAN_ZKML_PROOF_FAILED.
+ */
+int32_t generate_proof_batch(const Input *inputs, Output *outputs, uint8_t **proofs_out,
+                             size_t *proof_lens_out, size_t count);
+
+/* Release a proof from generate_proof_v2 or generate_proof_batch. A null
+ * ptr is ignored. */
+void free_proof(uint8_t *ptr, size_t len);
+
+/* Verify proof_len bytes of proof against output->hash. Returns
+ * GUARDIAN_ZKML_OK or GUARDIAN_ZKML_VERIFY_FAILED. */
+int32_t verify_proof_v2(const Output *output, const uint8_t *proof, size_t proof_len);
+
+/*
+ * Original interface, kept for callers built against it. generate_proof
+ * writes the hash and discards the proof; verify_proof_ffi only checks
+ * that output->hash is the hash of input's data. Neither involves a proof
+ * buffer, so there is nothing to free.
+ */
+int32_t generate_proof(const Input *input, Output *output);
+int32_t verify_proof_ffi(const Input *input, const Output *output);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* GUARDIAN_ZKML_H */
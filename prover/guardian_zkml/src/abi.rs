@@ -0,0 +1,89 @@
+//! ABI describing the SHA256 circuit's inputs
+//!
+//! `generate-abi` writes this to `abi.json` for integrators, and the backend
+//! uses it to label the public inputs of proofs it returns.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AbiInputOutput {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_info: String,
+    pub description: String,
+    #[serde(rename = "byteOffset")]
+    pub byte_offset: usize,
+    pub constraints: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CircuitMetadata {
+    #[serde(rename = "circuitSize")]
+    pub circuit_size: String,
+    #[serde(rename = "constraintCount")]
+    pub constraint_count: String,
+    #[serde(rename = "performanceTarget")]
+    pub performance_target: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Abi {
+    #[serde(rename = "circuitName")]
+    pub circuit_name: String,
+    pub version: String,
+    pub description: String,
+    #[serde(rename = "publicInputs")]
+    pub public_inputs: Vec<AbiInputOutput>,
+    #[serde(rename = "privateInputs")]
+    pub private_inputs: Vec<AbiInputOutput>,
+    pub metadata: CircuitMetadata,
+    #[serde(rename = "securityProperties")]
+    pub security_properties: Vec<String>,
+}
+
+/// ABI of the SHA256 circuit
+pub fn sha256_abi() -> Abi {
+    // Public inputs: 32 bytes of SHA256 hash output
+    let mut public_inputs = Vec::new();
+    for i in 0..32 {
+        public_inputs.push(AbiInputOutput {
+            name: format!("hash_byte_{}", i),
+            type_info: "field_element".to_string(),
+            description: format!("Byte {} of the 32-byte SHA256 hash output (big-endian format). Each byte is represented as a field element in the range [0, 255].", i),
+            byte_offset: i,
+            constraints: Some("0 ≤ value ≤ 255".to_string()),
+        });
+    }
+
+    let private_inputs = vec![AbiInputOutput {
+        name: "preimage_data".to_string(),
+        type_info: "bytes".to_string(),
+        description: "The input data to be hashed. This can be any sequence of bytes. The circuit automatically handles SHA256 padding according to RFC 6234. Maximum supported input size depends on circuit parameters (k=14 supports up to 311 bytes, i.e. 5 padded blocks).".to_string(),
+        byte_offset: 0,
+        constraints: Some("Variable length byte array, automatically padded to 512-bit blocks".to_string()),
+    }];
+
+    let metadata = CircuitMetadata {
+        circuit_size: "2^14 = 16,384 rows".to_string(),
+        constraint_count: "~50,000 constraints".to_string(),
+        performance_target: "< 500ms proof generation on modern hardware".to_string(),
+    };
+
+    let security_properties = vec![
+        "Zero-knowledge: The proof reveals only the SHA256 hash, not the input data".to_string(),
+        "Soundness: Invalid proofs are rejected with negligible probability".to_string(),
+        "Completeness: Valid computations always produce acceptable proofs".to_string(),
+        "SHA256 compliance: Implements the full SHA256 algorithm per RFC 6234".to_string(),
+        "Proper padding: Handles message padding correctly for any input length".to_string(),
+    ];
+
+    Abi {
+        circuit_name: "Guardian-AA SHA256 Circuit".to_string(),
+        version: "1.0.0".to_string(),
+        description: "A Halo2 zero-knowledge circuit that proves the correct computation of a SHA256 hash. The circuit takes arbitrary input data, applies proper SHA256 padding, and computes the hash using the standard SHA256 algorithm. The public outputs are the 32 bytes of the resulting hash, each represented as a field element. This circuit is optimized for performance with a target of sub-500ms proof generation.".to_string(),
+        public_inputs,
+        private_inputs,
+        metadata,
+        security_properties,
+    }
+}
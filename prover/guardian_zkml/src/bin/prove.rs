@@ -0,0 +1,106 @@
+//! Generate and verify SHA256 proofs from the command line, without the
+//! backend.
+//!
+//! `prove <FILE>` proves a file (`-` reads standard input) and prints its
+//! hash in hex and the proof in base64. `verify <HASH> <PROOF>` takes those
+//! two values back and exits with 0 when the proof is valid, 1 when it
+//! isn't and 2 when the arguments can't be read.
+//!
+//! Built with the `cli` feature: `cargo run --features cli --bin prove`.
+
+use base64::{engine::general_purpose, Engine as _};
+use clap::{Parser, Subcommand};
+use guardian_zkml::{
+    generate_proof_with_proof, init_proving_system, verify_proof_with_proof, ProverConfig,
+};
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Exit code for a proof that doesn't verify
+const EXIT_INVALID: u8 = 1;
+/// Exit code for unreadable input or arguments, matching clap's usage errors
+const EXIT_ERROR: u8 = 2;
+
+#[derive(Parser)]
+#[command(name = "prove", about = "Generate and verify Guardian SHA256 proofs")]
+struct Cli {
+    /// Directory to load the proving keys from, or save them to after
+    /// generating them
+    #[arg(long, global = true)]
+    cache_dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Prove the SHA256 of a file and print the hash and proof
+    Prove {
+        /// File to prove, or `-` for standard input
+        input: String,
+    },
+    /// Check a proof against a hash
+    Verify {
+        /// SHA256 of the proved data, in hex
+        hash: String,
+        /// Proof printed by `prove`, in base64
+        proof: String,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    if let Err(e) = init_proving_system(ProverConfig::default(), cli.cache_dir.as_deref()) {
+        eprintln!("error: {}", e);
+        return ExitCode::from(EXIT_ERROR);
+    }
+
+    let result = match cli.command {
+        Command::Prove { input } => prove(&input),
+        Command::Verify { hash, proof } => verify(&hash, &proof),
+    };
+
+    result.unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        ExitCode::from(EXIT_ERROR)
+    })
+}
+
+fn prove(input: &str) -> Result<ExitCode, String> {
+    let data = if input == "-" {
+        let mut data = Vec::new();
+        std::io::stdin()
+            .read_to_end(&mut data)
+            .map_err(|e| format!("failed to read standard input: {}", e))?;
+        data
+    } else {
+        std::fs::read(input).map_err(|e| format!("failed to read {}: {}", input, e))?
+    };
+
+    let (hash, proof) = generate_proof_with_proof(&data)?;
+    println!("hash: {}", hex::encode(hash));
+    println!("proof: {}", general_purpose::STANDARD.encode(proof));
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn verify(hash: &str, proof: &str) -> Result<ExitCode, String> {
+    let hash: [u8; 32] = hex::decode(hash.trim())
+        .map_err(|e| format!("hash is not hex: {}", e))?
+        .try_into()
+        .map_err(|bytes: Vec<u8>| format!("hash is {} bytes, expected 32", bytes.len()))?;
+    let proof = general_purpose::STANDARD
+        .decode(proof.trim())
+        .map_err(|e| format!("proof is not base64: {}", e))?;
+
+    if verify_proof_with_proof(&hash, &proof)? {
+        println!("valid");
+        Ok(ExitCode::SUCCESS)
+    } else {
+        println!("invalid");
+        Ok(ExitCode::from(EXIT_INVALID))
+    }
+}
@@ -0,0 +1,333 @@
+//! Bit-level gates shared by the hash circuits. Each row holds several
+//! side-by-side slots, and each slot applies one operation to up to three
+//! input bits copied in from earlier slots.
+
+use halo2_proofs::{
+    circuit::{AssignedCell, Region, Value},
+    pasta::Fp,
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+// Bit operations laid side by side in each row. Sixteen lanes fit a
+// one-block SHA256 hash in a k=12 circuit.
+pub(crate) const LANES: usize = 16;
+
+// Rows kept free for the blinding factors Halo2 appends to every column
+pub(crate) const RESERVED_ROWS: usize = 16;
+
+/// Rows `ops` bit operations occupy
+pub(crate) fn rows_for_ops(ops: usize) -> usize {
+    ops.div_ceil(LANES) + RESERVED_ROWS
+}
+
+/// Bit operations that fit in `rows` rows
+pub(crate) fn ops_for_rows(rows: usize) -> usize {
+    rows.saturating_sub(RESERVED_ROWS) * LANES
+}
+
+// Bit operations `BitAssigner::frame` spends on each byte position
+pub(crate) const FRAME_OPS_PER_BYTE: usize = 20;
+
+/// Bit operations framing a message over `positions` byte positions takes
+pub(crate) fn frame_ops(positions: usize) -> usize {
+    // The first position has no previous flag to combine with or add to
+    (FRAME_OPS_PER_BYTE * positions).saturating_sub(2)
+}
+
+fn xor(a: Fp, b: Fp) -> Fp {
+    a + b - Fp::from(2) * a * b
+}
+
+fn xor_expr(a: Expression<Fp>, b: Expression<Fp>) -> Expression<Fp> {
+    a.clone() + b.clone() - a * b * Fp::from(2)
+}
+
+// Selectors and columns for one side-by-side slot of a row
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct BitLane {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    c: Column<Advice>,
+    out: Column<Advice>,
+    q_bool: Selector,
+    q_xor: Selector,
+    q_xor3: Selector,
+    q_chi: Selector,
+    q_ch: Selector,
+    q_maj: Selector,
+    q_double_add: Selector,
+    q_add: Selector,
+}
+
+impl BitLane {
+    pub(crate) fn configure(meta: &mut ConstraintSystem<Fp>) -> Self {
+        let lane = Self {
+            a: meta.advice_column(),
+            b: meta.advice_column(),
+            c: meta.advice_column(),
+            out: meta.advice_column(),
+            q_bool: meta.selector(),
+            q_xor: meta.selector(),
+            q_xor3: meta.selector(),
+            q_chi: meta.selector(),
+            q_ch: meta.selector(),
+            q_maj: meta.selector(),
+            q_double_add: meta.selector(),
+            q_add: meta.selector(),
+        };
+        for column in [lane.a, lane.b, lane.c, lane.out] {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("bit operations", |meta| {
+            let q_bool = meta.query_selector(lane.q_bool);
+            let q_xor = meta.query_selector(lane.q_xor);
+            let q_xor3 = meta.query_selector(lane.q_xor3);
+            let q_chi = meta.query_selector(lane.q_chi);
+            let q_ch = meta.query_selector(lane.q_ch);
+            let q_maj = meta.query_selector(lane.q_maj);
+            let q_double_add = meta.query_selector(lane.q_double_add);
+            let q_add = meta.query_selector(lane.q_add);
+
+            let a = meta.query_advice(lane.a, Rotation::cur());
+            let b = meta.query_advice(lane.b, Rotation::cur());
+            let c = meta.query_advice(lane.c, Rotation::cur());
+            let out = meta.query_advice(lane.out, Rotation::cur());
+            let one = Expression::Constant(Fp::from(1));
+
+            // Every input bit is boolean, and these operations on booleans
+            // stay boolean, so only the witnessed message bits need a range
+            // check
+            let ch = a.clone() * b.clone() + (one.clone() - a.clone()) * c.clone();
+            let maj = a.clone() * b.clone() + a.clone() * c.clone() + b.clone() * c.clone()
+                - a.clone() * b.clone() * c.clone() * Fp::from(2);
+            vec![
+                q_bool * a.clone() * (one.clone() - a.clone()),
+                q_xor * (xor_expr(a.clone(), b.clone()) - out.clone()),
+                q_xor3 * (xor_expr(xor_expr(a.clone(), b.clone()), c.clone()) - out.clone()),
+                q_chi * (xor_expr(a.clone(), (one - b.clone()) * c.clone()) - out.clone()),
+                q_ch * (ch - out.clone()),
+                q_maj * (maj - out.clone()),
+                q_double_add * (a.clone() * Fp::from(2) + b.clone() - out.clone()),
+                q_add * (a + b - out),
+            ]
+        });
+
+        lane
+    }
+}
+
+pub(crate) type Bit = AssignedCell<Fp, Fp>;
+
+// Places bit operations into consecutive slots of a single region
+pub(crate) struct BitAssigner<'a, 'r> {
+    region: Region<'r, Fp>,
+    lanes: &'a [BitLane],
+    next_slot: usize,
+}
+
+impl<'a, 'r> BitAssigner<'a, 'r> {
+    pub(crate) fn new(region: Region<'r, Fp>, lanes: &'a [BitLane]) -> Self {
+        Self {
+            region,
+            lanes,
+            next_slot: 0,
+        }
+    }
+}
+
+impl BitAssigner<'_, '_> {
+    fn slot(&mut self) -> (usize, BitLane) {
+        let slot = self.next_slot;
+        self.next_slot += 1;
+        (slot / LANES, self.lanes[slot % LANES])
+    }
+
+    pub(crate) fn constant(&mut self, value: Fp) -> Result<Bit, Error> {
+        let (offset, lane) = self.slot();
+        self.region
+            .assign_advice_from_constant(|| "constant", lane.a, offset, value)
+    }
+
+    pub(crate) fn witness(&mut self, value: Value<Fp>) -> Result<Bit, Error> {
+        let (offset, lane) = self.slot();
+        lane.q_bool.enable(&mut self.region, offset)?;
+        self.region
+            .assign_advice(|| "message bit", lane.a, offset, || value)
+    }
+
+    pub(crate) fn xor(&mut self, a: &Bit, b: &Bit) -> Result<Bit, Error> {
+        let value = a
+            .value()
+            .copied()
+            .zip(b.value().copied())
+            .map(|(a, b)| xor(a, b));
+        self.apply(|lane| lane.q_xor, &[a, b], value)
+    }
+
+    pub(crate) fn xor3(&mut self, a: &Bit, b: &Bit, c: &Bit) -> Result<Bit, Error> {
+        let value = a
+            .value()
+            .copied()
+            .zip(b.value().copied())
+            .zip(c.value().copied())
+            .map(|((a, b), c)| xor(xor(a, b), c));
+        self.apply(|lane| lane.q_xor3, &[a, b, c], value)
+    }
+
+    // a XOR (NOT b AND c)
+    pub(crate) fn chi(&mut self, a: &Bit, b: &Bit, c: &Bit) -> Result<Bit, Error> {
+        let value = a
+            .value()
+            .copied()
+            .zip(b.value().copied())
+            .zip(c.value().copied())
+            .map(|((a, b), c)| xor(a, (Fp::from(1) - b) * c));
+        self.apply(|lane| lane.q_chi, &[a, b, c], value)
+    }
+
+    // b if a is set, else c
+    pub(crate) fn ch(&mut self, a: &Bit, b: &Bit, c: &Bit) -> Result<Bit, Error> {
+        let value = a
+            .value()
+            .copied()
+            .zip(b.value().copied())
+            .zip(c.value().copied())
+            .map(|((a, b), c)| a * b + (Fp::from(1) - a) * c);
+        self.apply(|lane| lane.q_ch, &[a, b, c], value)
+    }
+
+    // Whether at least two of a, b and c are set
+    pub(crate) fn maj(&mut self, a: &Bit, b: &Bit, c: &Bit) -> Result<Bit, Error> {
+        let value = a
+            .value()
+            .copied()
+            .zip(b.value().copied())
+            .zip(c.value().copied())
+            .map(|((a, b), c)| a * b + a * c + b * c - Fp::from(2) * a * b * c);
+        self.apply(|lane| lane.q_maj, &[a, b, c], value)
+    }
+
+    // 2a + b, used to pack bits into bytes and lengths
+    pub(crate) fn double_add(&mut self, a: &Bit, b: &Bit) -> Result<Bit, Error> {
+        let value = a
+            .value()
+            .copied()
+            .zip(b.value().copied())
+            .map(|(a, b)| a * Fp::from(2) + b);
+        self.apply(|lane| lane.q_double_add, &[a, b], value)
+    }
+
+    /// `double_add` with a chosen output, as a dishonest prover would assign
+    #[cfg(test)]
+    pub(crate) fn double_add_claiming(
+        &mut self,
+        a: &Bit,
+        b: &Bit,
+        claimed: Value<Fp>,
+    ) -> Result<Bit, Error> {
+        self.apply(|lane| lane.q_double_add, &[a, b], claimed)
+    }
+
+    // a + b, used to count data bytes
+    pub(crate) fn add(&mut self, a: &Bit, b: &Bit) -> Result<Bit, Error> {
+        let value = a
+            .value()
+            .copied()
+            .zip(b.value().copied())
+            .map(|(a, b)| a + b);
+        self.apply(|lane| lane.q_add, &[a, b], value)
+    }
+
+    /// Pack bits, most significant first, into the number they spell. The
+    /// packing gates only admit sums of boolean bits, so eight bits make a
+    /// range checked byte.
+    pub(crate) fn pack(&mut self, bits: &[Bit]) -> Result<Bit, Error> {
+        let (first, rest) = bits.split_first().ok_or(Error::Synthesis)?;
+        let mut packed = first.clone();
+        for bit in rest {
+            packed = self.double_add(&packed, bit)?;
+        }
+        Ok(packed)
+    }
+
+    pub(crate) fn constrain_equal(&mut self, a: &Bit, b: &Bit) -> Result<(), Error> {
+        self.region.constrain_equal(a.cell(), b.cell())
+    }
+
+    /// Lay `data` out over `positions` byte positions, the rest of them
+    /// padding: the position right after the data has only bit
+    /// `marker_bit` set, and the ones after it are zero. Returns the bits of
+    /// every position, least significant first, and a cell counting the
+    /// data bytes.
+    ///
+    /// A flag per position says whether it holds data. The flags can only
+    /// fall from set to clear, the marker goes where they fall, and the
+    /// count is their sum, so once the count is tied to the data length
+    /// the padding is the one the hash function prescribes. Callers make
+    /// sure `positions` leaves room for the marker.
+    pub(crate) fn frame(
+        &mut self,
+        data: Option<&[u8]>,
+        positions: usize,
+        marker_bit: usize,
+        zero: &Bit,
+        one: &Bit,
+    ) -> Result<(Vec<Bit>, Bit), Error> {
+        let mut bits = Vec::with_capacity(positions * 8);
+        let mut previous: Option<(Bit, Bit)> = None;
+
+        for position in 0..positions {
+            let flag = data.map(|data| Fp::from((position < data.len()) as u64));
+            let byte = data.map(|data| data.get(position).copied().unwrap_or(0));
+
+            let flag = self.witness(to_value(flag))?;
+            let (is_data, marker, count) = match previous {
+                None => {
+                    let marker = self.xor(&flag, one)?;
+                    (flag.clone(), marker, flag)
+                }
+                Some((was_data, count)) => {
+                    // Data stops at the first clear flag: is_data = was_data AND flag
+                    let is_data = self.ch(&was_data, &flag, zero)?;
+                    let marker = self.xor(&was_data, &is_data)?;
+                    let count = self.add(&count, &is_data)?;
+                    (is_data, marker, count)
+                }
+            };
+
+            for i in 0..8 {
+                let bit = self.witness(to_value(
+                    byte.map(|byte| Fp::from(((byte >> i) & 1) as u64)),
+                ))?;
+                let padding = if i == marker_bit { &marker } else { zero };
+                bits.push(self.ch(&is_data, &bit, padding)?);
+            }
+            previous = Some((is_data, count));
+        }
+
+        let (_, count) = previous.ok_or(Error::Synthesis)?;
+        Ok((bits, count))
+    }
+
+    fn apply(
+        &mut self,
+        selector: fn(&BitLane) -> Selector,
+        inputs: &[&Bit],
+        value: Value<Fp>,
+    ) -> Result<Bit, Error> {
+        let (offset, lane) = self.slot();
+        selector(&lane).enable(&mut self.region, offset)?;
+        for (input, column) in inputs.iter().zip([lane.a, lane.b, lane.c]) {
+            input.copy_advice(|| "input", &mut self.region, column, offset)?;
+        }
+        self.region
+            .assign_advice(|| "output", lane.out, offset, || value)
+    }
+}
+
+// A known value, or an unknown one during key generation
+fn to_value(value: Option<Fp>) -> Value<Fp> {
+    value.map(Value::known).unwrap_or_else(Value::unknown)
+}
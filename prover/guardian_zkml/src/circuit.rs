@@ -1,34 +1,545 @@
+use crate::bits::{self, Bit, BitAssigner, BitLane, LANES};
 use halo2_proofs::{
     circuit::{Layouter, SimpleFloorPlanner, Value},
     pasta::Fp,
-    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance},
+    plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
 };
 
-// Simple circuit configuration
+/// 32-bit words in one SHA256 message block
+const BLOCK_WORDS: usize = 16;
+
+/// Bytes in one SHA256 message block
+pub const BLOCK_BYTES: usize = BLOCK_WORDS * 4;
+
+/// Smallest amount of padding SHA256 adds: the 0x80 marker plus the 64-bit length
+pub const MIN_PADDING_BYTES: usize = 9;
+
+/// Bytes of the message length field ending the padding
+const LENGTH_BYTES: usize = 8;
+
+/// Number of 32-bit words in a SHA256 digest
+const DIGEST_WORDS: usize = 8;
+
+const WORD_BITS: usize = 32;
+const ROUNDS: usize = 64;
+
+/// Length of the blinding factor mixed into a hash commitment
+pub const BLINDING_BYTES: usize = 32;
+
+const INITIAL_STATE: [u32; DIGEST_WORDS] = [
+    0x6a09_e667,
+    0xbb67_ae85,
+    0x3c6e_f372,
+    0xa54f_f53a,
+    0x510e_527f,
+    0x9b05_688c,
+    0x1f83_d9ab,
+    0x5be0_cd19,
+];
+
+const ROUND_CONSTANTS: [u32; ROUNDS] = [
+    0x428a_2f98,
+    0x7137_4491,
+    0xb5c0_fbcf,
+    0xe9b5_dba5,
+    0x3956_c25b,
+    0x59f1_11f1,
+    0x923f_82a4,
+    0xab1c_5ed5,
+    0xd807_aa98,
+    0x1283_5b01,
+    0x2431_85be,
+    0x550c_7dc3,
+    0x72be_5d74,
+    0x80de_b1fe,
+    0x9bdc_06a7,
+    0xc19b_f174,
+    0xe49b_69c1,
+    0xefbe_4786,
+    0x0fc1_9dc6,
+    0x240c_a1cc,
+    0x2de9_2c6f,
+    0x4a74_84aa,
+    0x5cb0_a9dc,
+    0x76f9_88da,
+    0x983e_5152,
+    0xa831_c66d,
+    0xb003_27c8,
+    0xbf59_7fc7,
+    0xc6e0_0bf3,
+    0xd5a7_9147,
+    0x06ca_6351,
+    0x1429_2967,
+    0x27b7_0a85,
+    0x2e1b_2138,
+    0x4d2c_6dfc,
+    0x5338_0d13,
+    0x650a_7354,
+    0x766a_0abb,
+    0x81c2_c92e,
+    0x9272_2c85,
+    0xa2bf_e8a1,
+    0xa81a_664b,
+    0xc24b_8b70,
+    0xc76c_51a3,
+    0xd192_e819,
+    0xd699_0624,
+    0xf40e_3585,
+    0x106a_a070,
+    0x19a4_c116,
+    0x1e37_6c08,
+    0x2748_774c,
+    0x34b0_bcb5,
+    0x391c_0cb3,
+    0x4ed8_aa4a,
+    0x5b9c_ca4f,
+    0x682e_6ff3,
+    0x748f_82ee,
+    0x78a5_636f,
+    0x84c8_7814,
+    0x8cc7_0208,
+    0x90be_fffa,
+    0xa450_6ceb,
+    0xbef9_a3f7,
+    0xc671_78f2,
+];
+
+// Operation counts, used to size the circuit without synthesizing it. An
+// addition modulo 2^32 is a ripple-carry adder without the last carry. The
+// length field's three lowest bits are always clear, so only the rest are
+// witnessed and packed.
+const CONSTANT_OPS: usize = 2;
+const ADD_OPS: usize = 2 * WORD_BITS - 1;
+const SCHEDULE_OPS: usize = (ROUNDS - BLOCK_WORDS) * (2 * WORD_BITS + 3 * ADD_OPS);
+const ROUND_OPS: usize = 4 * WORD_BITS + 7 * ADD_OPS;
+const COMPRESSION_OPS: usize = SCHEDULE_OPS + ROUNDS * ROUND_OPS + DIGEST_WORDS * ADD_OPS;
+const LENGTH_OPS: usize = 2 * (LENGTH_BYTES * 8 - 3) - 1;
+const DIGEST_OPS: usize = DIGEST_WORDS * 4 * 7;
+// The commitment's message reuses the preimage's bits after the witnessed
+// blinding, and its length is the preimage's plus the blinding, so it takes
+// a constant and an addition besides its own length field
+const BLINDING_OPS: usize = BLINDING_BYTES * 8;
+const COMMITMENT_LENGTH_OPS: usize = LENGTH_OPS + 2;
+// A bound on each block's operations, framing its bytes included
+const OPS_PER_BLOCK: usize = COMPRESSION_OPS + bits::FRAME_OPS_PER_BYTE * BLOCK_BYTES;
+
+/// Hash commitment to `data`: SHA256(blinding || data)
+pub fn hash_commitment(blinding: &[u8; BLINDING_BYTES], data: &[u8]) -> [u8; 32] {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    hasher.update(blinding);
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+/// Largest number of blocks, preimage and commitment together, that a
+/// circuit with `rows` rows fits
+pub fn max_blocks_for_rows(rows: usize) -> usize {
+    let fixed = CONSTANT_OPS + LENGTH_OPS + COMMITMENT_LENGTH_OPS + 2 * DIGEST_OPS;
+    bits::ops_for_rows(rows).saturating_sub(fixed) / OPS_PER_BLOCK
+}
+
+/// Layout of a circuit, which determines its keys
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CircuitShape {
+    /// Message blocks in the preimage hash
+    pub blocks: usize,
+    /// Message blocks in the commitment hash, or 0 without a commitment
+    pub commitment_blocks: usize,
+}
+
+impl CircuitShape {
+    /// Shape for proving the hash of a `len`-byte input
+    pub fn for_input(len: usize) -> Self {
+        Self {
+            blocks: padded_block_count(len),
+            commitment_blocks: 0,
+        }
+    }
+
+    /// Shape for proving the hash and commitment of a `len`-byte input
+    pub fn for_committed_input(len: usize) -> Self {
+        Self {
+            blocks: padded_block_count(len),
+            commitment_blocks: padded_block_count(BLINDING_BYTES + len),
+        }
+    }
+
+    /// Whether this circuit also proves a commitment
+    pub fn is_committed(&self) -> bool {
+        self.commitment_blocks > 0
+    }
+
+    /// Total SHA256 blocks compressed by the circuit
+    pub fn total_blocks(&self) -> usize {
+        self.blocks + self.commitment_blocks
+    }
+
+    /// Rows a circuit of this shape occupies
+    pub fn rows(&self) -> usize {
+        let preimage = bits::frame_ops(message_positions(self.blocks))
+            + LENGTH_OPS
+            + self.blocks * COMPRESSION_OPS
+            + DIGEST_OPS;
+        let commitment = match self.is_committed() {
+            true => {
+                BLINDING_OPS
+                    + COMMITMENT_LENGTH_OPS
+                    + self.commitment_blocks * COMPRESSION_OPS
+                    + DIGEST_OPS
+            }
+            false => 0,
+        };
+        bits::rows_for_ops(CONSTANT_OPS + preimage + commitment)
+    }
+}
+
+/// Byte positions of a `blocks`-block message ahead of the length field
+fn message_positions(blocks: usize) -> usize {
+    (blocks * BLOCK_BYTES).saturating_sub(LENGTH_BYTES)
+}
+
+/// Number of message blocks a `len`-byte input occupies once padded
+pub fn padded_block_count(len: usize) -> usize {
+    (len + MIN_PADDING_BYTES).div_ceil(BLOCK_BYTES)
+}
+
+// Circuit configuration: the bit operation lanes plus the public digest
+// bytes and input length
 #[derive(Clone, Debug)]
 pub struct Sha256CircuitConfig {
-    advice: [Column<Advice>; 2],
+    lanes: Vec<BitLane>,
     instance: Column<Instance>,
 }
 
-// Main circuit struct - simplified for now
+/// Proves knowledge of a preimage whose SHA256 digest is the public input.
+///
+/// In committed mode the circuit also hashes `blinding || preimage` and
+/// exposes that commitment after the digest, so a proof can be kept without
+/// the preimage. The preimage's length in bytes is the last public input.
+///
+/// The circuit layout depends on the number of message blocks, so a circuit
+/// (and its keys) is specific to inputs with the same [`CircuitShape`].
+///
+/// The hash is computed bit by bit with the gates the Keccak256 circuit
+/// uses, and the digest bytes are packed from the cells the compression
+/// produced, so the public inputs are fully constrained. The padding is
+/// built in the circuit from the public length, so the blocks compressed
+/// are always a padded message of that length. Verifiers only need to
+/// check that the length needs exactly the shape's blocks.
 #[derive(Default, Debug, Clone)]
 pub struct Sha256Circuit {
-    pub data: Vec<u8>,
+    data: Option<Vec<u8>>,
+    blinding: Option<[u8; BLINDING_BYTES]>,
+    shape: CircuitShape,
 }
 
 impl Sha256Circuit {
     pub fn new(data: Vec<u8>) -> Self {
-        Self { data }
+        Self {
+            shape: CircuitShape::for_input(data.len()),
+            data: Some(data),
+            blinding: None,
+        }
+    }
+
+    /// Circuit that also proves the commitment SHA256(blinding || data)
+    pub fn with_commitment(data: Vec<u8>, blinding: [u8; BLINDING_BYTES]) -> Self {
+        Self {
+            shape: CircuitShape::for_committed_input(data.len()),
+            data: Some(data),
+            blinding: Some(blinding),
+        }
+    }
+
+    /// Circuit without a witness, used for key generation
+    pub fn for_shape(shape: CircuitShape) -> Self {
+        Self {
+            data: None,
+            blinding: None,
+            shape,
+        }
+    }
+
+    /// Layout of this circuit
+    pub fn shape(&self) -> CircuitShape {
+        self.shape
     }
 
     // Get the expected hash for testing/verification
     pub fn expected_hash(&self) -> [u8; 32] {
         use sha2::{Digest, Sha256};
         let mut hasher = Sha256::new();
-        hasher.update(&self.data);
+        hasher.update(self.data.as_deref().unwrap_or_default());
         hasher.finalize().into()
     }
+
+    /// Expected commitment, if this circuit has a commitment witness
+    pub fn expected_commitment(&self) -> Option<[u8; 32]> {
+        let blinding = self.blinding.as_ref()?;
+        Some(hash_commitment(blinding, self.data.as_deref()?))
+    }
+
+    /// Assign the padded preimage, returning its bits and a cell holding
+    /// its length
+    fn assign_preimage(
+        &self,
+        assigner: &mut BitAssigner<'_, '_>,
+        constants: &Constants,
+    ) -> Result<(Vec<Bit>, Bit), Error> {
+        let data = self.data.as_deref();
+        assigner.padded_message(data, length_of(data), self.shape.blocks, constants)
+    }
+
+    /// Assign `blinding || preimage` padded for the commitment hash, given
+    /// the padded `preimage` and its `len` as assigned for the preimage hash.
+    ///
+    /// The data bits are the preimage's own cells, so both hashes cover the
+    /// same data. The preimage is already framed with its 0x80 marker and
+    /// zero fill, which shifted past the blinding is the commitment's
+    /// framing too; only the length field differs.
+    fn assign_commitment(
+        &self,
+        assigner: &mut BitAssigner<'_, '_>,
+        constants: &Constants,
+        preimage: &[Bit],
+        len: &Bit,
+    ) -> Result<Vec<Bit>, Error> {
+        let mut message = (0..BLINDING_OPS)
+            .map(|i| {
+                let bit = match &self.blinding {
+                    Some(blinding) => {
+                        Value::known(Fp::from(((blinding[i / 8] >> (i % 8)) & 1) as u64))
+                    }
+                    None => Value::unknown(),
+                };
+                assigner.witness(bit)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // The marker always falls within the commitment's positions, and
+        // anything past the preimage's is zero fill
+        let positions = message_positions(self.shape.commitment_blocks);
+        let data_positions = (positions - BLINDING_BYTES).min(message_positions(self.shape.blocks));
+        message.extend(preimage[..data_positions * 8].iter().cloned());
+        message.resize(positions * 8, constants.zero.clone());
+
+        let commitment_len = length_of(self.data.as_deref()).map(|len| len + BLINDING_BYTES as u64);
+        let (field, packed) = assigner.length_field(commitment_len, &constants.zero)?;
+        let blinding_len = assigner.constant(Fp::from(BLINDING_BYTES as u64))?;
+        let expected_len = assigner.add(len, &blinding_len)?;
+        assigner.constrain_equal(&expected_len, &packed)?;
+        message.extend(field);
+        Ok(message)
+    }
+}
+
+fn length_of(data: Option<&[u8]>) -> Value<u64> {
+    match data {
+        Some(data) => Value::known(data.len() as u64),
+        None => Value::unknown(),
+    }
+}
+
+/// Big-endian 32-bit words of a message given as bits, each byte's least
+/// significant bit first
+fn to_words(message: &[Bit]) -> Vec<Word> {
+    message
+        .chunks_exact(WORD_BITS)
+        .map(|word| {
+            (0..WORD_BITS)
+                .map(|i| word[(3 - i / 8) * 8 + i % 8].clone())
+                .collect()
+        })
+        .collect()
+}
+
+// A 32-bit word as its bits, least significant first
+type Word = Vec<Bit>;
+
+// The cells constant words are built from
+struct Constants {
+    zero: Bit,
+    one: Bit,
+}
+
+impl Constants {
+    fn word(&self, value: u32) -> Word {
+        (0..WORD_BITS)
+            .map(|i| match (value >> i) & 1 {
+                1 => self.one.clone(),
+                _ => self.zero.clone(),
+            })
+            .collect()
+    }
+}
+
+// Rotations and shifts only move bits around, so they cost no constraints
+fn rotr(word: &Word, n: usize) -> Word {
+    (0..WORD_BITS)
+        .map(|i| word[(i + n) % WORD_BITS].clone())
+        .collect()
+}
+
+fn shr(word: &Word, n: usize, zero: &Bit) -> Word {
+    (0..WORD_BITS)
+        .map(|i| word.get(i + n).unwrap_or(zero).clone())
+        .collect()
+}
+
+impl BitAssigner<'_, '_> {
+    /// `data` padded per RFC 6234 section 4.1 over `blocks` blocks, as bits
+    /// with each byte's least significant first, and a cell holding its
+    /// length. The length field is witnessed from `len` and must match the
+    /// number of data bytes.
+    fn padded_message(
+        &mut self,
+        data: Option<&[u8]>,
+        len: Value<u64>,
+        blocks: usize,
+        constants: &Constants,
+    ) -> Result<(Vec<Bit>, Bit), Error> {
+        let (zero, one) = (&constants.zero, &constants.one);
+        let (mut message, count) = self.frame(data, message_positions(blocks), 7, zero, one)?;
+        let (field, len) = self.length_field(len, zero)?;
+        self.constrain_equal(&count, &len)?;
+        message.extend(field);
+        Ok((message, len))
+    }
+
+    /// The big-endian 64-bit field giving a `len`-byte message's length in
+    /// bits, as message bits, and a cell holding `len` packed from them
+    fn length_field(&mut self, len: Value<u64>, zero: &Bit) -> Result<(Vec<Bit>, Bit), Error> {
+        // Counting bits clears the three lowest; the rest are `len`'s bits
+        let len_bits = (0..LENGTH_BYTES * 8 - 3)
+            .map(|i| self.witness(len.map(|len| Fp::from((len >> i) & 1))))
+            .collect::<Result<Vec<_>, _>>()?;
+        let packed = self.pack(&len_bits.iter().rev().cloned().collect::<Vec<_>>())?;
+
+        let field: Vec<Bit> = std::iter::repeat(zero.clone())
+            .take(3)
+            .chain(len_bits)
+            .collect();
+        let bits = field.chunks_exact(8).rev().flatten().cloned().collect();
+        Ok((bits, packed))
+    }
+
+    fn xor3_words(&mut self, a: &Word, b: &Word, c: &Word) -> Result<Word, Error> {
+        (0..WORD_BITS)
+            .map(|i| self.xor3(&a[i], &b[i], &c[i]))
+            .collect()
+    }
+
+    fn ch_words(&mut self, e: &Word, f: &Word, g: &Word) -> Result<Word, Error> {
+        (0..WORD_BITS)
+            .map(|i| self.ch(&e[i], &f[i], &g[i]))
+            .collect()
+    }
+
+    fn maj_words(&mut self, a: &Word, b: &Word, c: &Word) -> Result<Word, Error> {
+        (0..WORD_BITS)
+            .map(|i| self.maj(&a[i], &b[i], &c[i]))
+            .collect()
+    }
+
+    /// `a + b` modulo 2^32
+    fn add(&mut self, a: &Word, b: &Word, zero: &Bit) -> Result<Word, Error> {
+        let mut sum = Vec::with_capacity(WORD_BITS);
+        let mut carry = zero.clone();
+        for i in 0..WORD_BITS {
+            sum.push(self.xor3(&a[i], &b[i], &carry)?);
+            // The top bit's carry leaves the word, so it is never computed
+            if i + 1 < WORD_BITS {
+                carry = self.maj(&a[i], &b[i], &carry)?;
+            }
+        }
+        Ok(sum)
+    }
+
+    /// One SHA256 compression of `block` into `state`
+    fn compress(
+        &mut self,
+        state: &[Word],
+        mut schedule: Vec<Word>,
+        constants: &Constants,
+    ) -> Result<Vec<Word>, Error> {
+        let zero = &constants.zero;
+
+        // Expand the block's 16 words into the 64-word message schedule
+        for t in BLOCK_WORDS..ROUNDS {
+            let w15 = &schedule[t - 15];
+            let s0 = self.xor3_words(&rotr(w15, 7), &rotr(w15, 18), &shr(w15, 3, zero))?;
+            let w2 = &schedule[t - 2];
+            let s1 = self.xor3_words(&rotr(w2, 17), &rotr(w2, 19), &shr(w2, 10, zero))?;
+
+            let word = self.add(&schedule[t - 16], &s0, zero)?;
+            let word = self.add(&word, &schedule[t - 7], zero)?;
+            let word = self.add(&word, &s1, zero)?;
+            schedule.push(word);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h]: [Word; DIGEST_WORDS] =
+            state.to_vec().try_into().map_err(|_| Error::Synthesis)?;
+        for (t, w) in schedule.iter().enumerate() {
+            let s1 = self.xor3_words(&rotr(&e, 6), &rotr(&e, 11), &rotr(&e, 25))?;
+            let ch = self.ch_words(&e, &f, &g)?;
+            let t1 = self.add(&h, &s1, zero)?;
+            let t1 = self.add(&t1, &ch, zero)?;
+            let t1 = self.add(&t1, &constants.word(ROUND_CONSTANTS[t]), zero)?;
+            let t1 = self.add(&t1, w, zero)?;
+
+            let s0 = self.xor3_words(&rotr(&a, 2), &rotr(&a, 13), &rotr(&a, 22))?;
+            let maj = self.maj_words(&a, &b, &c)?;
+            let t2 = self.add(&s0, &maj, zero)?;
+
+            h = g;
+            g = f;
+            f = e;
+            e = self.add(&d, &t1, zero)?;
+            d = c;
+            c = b;
+            b = a;
+            a = self.add(&t1, &t2, zero)?;
+        }
+
+        // Add the compressed words back into the incoming state
+        let compressed = [a, b, c, d, e, f, g, h];
+        compressed
+            .iter()
+            .zip(state)
+            .map(|(word, incoming)| self.add(incoming, word, zero))
+            .collect()
+    }
+
+    /// SHA256 state after compressing the padded `message`
+    fn sha256_state(&mut self, message: &[Bit], constants: &Constants) -> Result<Vec<Word>, Error> {
+        let mut state: Vec<Word> = INITIAL_STATE
+            .iter()
+            .map(|&word| constants.word(word))
+            .collect();
+        for block in to_words(message).chunks_exact(BLOCK_WORDS) {
+            state = self.compress(&state, block.to_vec(), constants)?;
+        }
+        Ok(state)
+    }
+
+    /// Digest bytes of a final `state`, big-endian, each packed from the
+    /// state's bits
+    fn digest_bytes(&mut self, state: &[Word]) -> Result<Vec<Bit>, Error> {
+        let mut digest = Vec::with_capacity(DIGEST_WORDS * 4);
+        for word in state {
+            for byte in (0..4).rev() {
+                digest.push(self.pack(&byte_bits(word, byte))?);
+            }
+        }
+        Ok(digest)
+    }
+}
+
+/// Bits of byte `byte` (0 the least significant) of `word`, most
+/// significant first
+fn byte_bits(word: &Word, byte: usize) -> Vec<Bit> {
+    (0..8).rev().map(|i| word[byte * 8 + i].clone()).collect()
 }
 
 impl Circuit<Fp> for Sha256Circuit {
@@ -36,20 +547,18 @@ impl Circuit<Fp> for Sha256Circuit {
     type FloorPlanner = SimpleFloorPlanner;
 
     fn without_witnesses(&self) -> Self {
-        Self::default()
+        Self::for_shape(self.shape)
     }
 
     fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
-        let advice = [meta.advice_column(), meta.advice_column()];
+        let lanes = (0..LANES).map(|_| BitLane::configure(meta)).collect();
         let instance = meta.instance_column();
+        let constants = meta.fixed_column();
 
-        // Enable equality for advice and instance columns
-        for column in &advice {
-            meta.enable_equality(*column);
-        }
         meta.enable_equality(instance);
+        meta.enable_constant(constants);
 
-        Sha256CircuitConfig { advice, instance }
+        Sha256CircuitConfig { lanes, instance }
     }
 
     fn synthesize(
@@ -57,39 +566,34 @@ impl Circuit<Fp> for Sha256Circuit {
         config: Self::Config,
         mut layouter: impl Layouter<Fp>,
     ) -> Result<(), Error> {
-        // For now, implement a simple hash verification circuit
-        // This proves that we computed the correct SHA256 hash
-        let hash = self.expected_hash();
-
-        layouter.assign_region(
-            || "hash verification",
-            |mut region| {
-                // Assign each byte of the hash to the advice column
-                // and expose it as a public input
-                for (i, &byte) in hash.iter().enumerate() {
-                    let cell = region.assign_advice(
-                        || format!("hash_byte_{}", i),
-                        config.advice[0],
-                        i,
-                        || Value::known(Fp::from(byte as u64)),
-                    )?;
+        let (public_bytes, len) = layouter.assign_region(
+            || "sha256",
+            |region| {
+                let mut assigner = BitAssigner::new(region, &config.lanes);
+                let constants = Constants {
+                    zero: assigner.constant(Fp::from(0))?,
+                    one: assigner.constant(Fp::from(1))?,
+                };
 
-                    // Assign the same value to the instance column
-                    let instance_cell = region.assign_advice(
-                        || format!("instance_byte_{}", i),
-                        config.advice[1],
-                        i,
-                        || Value::known(Fp::from(byte as u64)),
-                    )?;
-
-                    // Constrain the advice cell to equal the instance cell
-                    region.constrain_equal(cell.cell(), instance_cell.cell())?;
+                let (message, len) = self.assign_preimage(&mut assigner, &constants)?;
+                let state = assigner.sha256_state(&message, &constants)?;
+                let mut public_bytes = assigner.digest_bytes(&state)?;
+                if self.shape.is_committed() {
+                    let commitment =
+                        self.assign_commitment(&mut assigner, &constants, &message, &len)?;
+                    let state = assigner.sha256_state(&commitment, &constants)?;
+                    public_bytes.extend(assigner.digest_bytes(&state)?);
                 }
-
-                Ok(())
+                Ok((public_bytes, len))
             },
         )?;
 
+        for (i, byte) in public_bytes.iter().enumerate() {
+            layouter.constrain_instance(byte.cell(), config.instance, i)?;
+        }
+        // The input length follows the public bytes
+        layouter.constrain_instance(len.cell(), config.instance, public_bytes.len())?;
+
         Ok(())
     }
 }
@@ -97,7 +601,209 @@ impl Circuit<Fp> for Sha256Circuit {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use halo2_proofs::{dev::MockProver, pasta::Fp};
+    use halo2_proofs::dev::MockProver;
+
+    /// Public inputs for digests `hashes` of a `len`-byte preimage
+    fn public_inputs(hashes: &[[u8; 32]], len: usize) -> Vec<Fp> {
+        hashes
+            .iter()
+            .flatten()
+            .map(|&byte| Fp::from(byte as u64))
+            .chain(std::iter::once(Fp::from(len as u64)))
+            .collect()
+    }
+
+    fn smallest_k(circuit: &Sha256Circuit) -> u32 {
+        circuit.shape().rows().next_power_of_two().trailing_zeros()
+    }
+
+    /// The SHA256 circuit, except that it assigns `claimed` to the digest
+    /// byte cells in place of the bytes packed from the state, as a
+    /// dishonest prover would
+    struct ClaimedDigest {
+        circuit: Sha256Circuit,
+        claimed: [u8; 32],
+    }
+
+    impl Circuit<Fp> for ClaimedDigest {
+        type Config = Sha256CircuitConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                circuit: self.circuit.without_witnesses(),
+                claimed: self.claimed,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            Sha256Circuit::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let (digest, len) = layouter.assign_region(
+                || "sha256",
+                |region| {
+                    let mut assigner = BitAssigner::new(region, &config.lanes);
+                    let constants = Constants {
+                        zero: assigner.constant(Fp::from(0))?,
+                        one: assigner.constant(Fp::from(1))?,
+                    };
+                    let (message, len) = self.circuit.assign_preimage(&mut assigner, &constants)?;
+                    let state = assigner.sha256_state(&message, &constants)?;
+
+                    // Pack all but the last bit of each byte honestly, then
+                    // claim whatever byte was wanted
+                    let mut digest = Vec::with_capacity(32);
+                    for (i, &claimed) in self.claimed.iter().enumerate() {
+                        let bits = byte_bits(&state[i / 4], 3 - i % 4);
+                        let high = assigner.pack(&bits[..7])?;
+                        digest.push(assigner.double_add_claiming(
+                            &high,
+                            &bits[7],
+                            Value::known(Fp::from(claimed as u64)),
+                        )?);
+                    }
+                    Ok((digest, len))
+                },
+            )?;
+
+            for (i, byte) in digest.iter().enumerate() {
+                layouter.constrain_instance(byte.cell(), config.instance, i)?;
+            }
+            layouter.constrain_instance(len.cell(), config.instance, digest.len())?;
+            Ok(())
+        }
+    }
+
+    /// One block hashing `block` as its data, with a length field stating
+    /// `stated_len` whatever the data's length. A prover skipping the
+    /// padding would fill the block with data this way.
+    struct Unpadded {
+        block: Vec<u8>,
+        stated_len: u64,
+    }
+
+    impl Circuit<Fp> for Unpadded {
+        type Config = Sha256CircuitConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                block: self.block.clone(),
+                stated_len: self.stated_len,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            Sha256Circuit::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let (digest, len) = layouter.assign_region(
+                || "sha256",
+                |region| {
+                    let mut assigner = BitAssigner::new(region, &config.lanes);
+                    let constants = Constants {
+                        zero: assigner.constant(Fp::from(0))?,
+                        one: assigner.constant(Fp::from(1))?,
+                    };
+                    let (message, len) = assigner.padded_message(
+                        Some(&self.block),
+                        Value::known(self.stated_len),
+                        1,
+                        &constants,
+                    )?;
+                    let state = assigner.sha256_state(&message, &constants)?;
+                    Ok((assigner.digest_bytes(&state)?, len))
+                },
+            )?;
+
+            for (i, byte) in digest.iter().enumerate() {
+                layouter.constrain_instance(byte.cell(), config.instance, i)?;
+            }
+            layouter.constrain_instance(len.cell(), config.instance, digest.len())?;
+            Ok(())
+        }
+    }
+
+    /// The committed SHA256 circuit, except that the commitment is assigned
+    /// by a circuit whose witness is `other`, as a prover committing to
+    /// different data than it hashed would
+    struct Rebound {
+        circuit: Sha256Circuit,
+        other: Sha256Circuit,
+    }
+
+    impl Circuit<Fp> for Rebound {
+        type Config = Sha256CircuitConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                circuit: self.circuit.without_witnesses(),
+                other: self.other.without_witnesses(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            Sha256Circuit::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let (public_bytes, len) = layouter.assign_region(
+                || "sha256",
+                |region| {
+                    let mut assigner = BitAssigner::new(region, &config.lanes);
+                    let constants = Constants {
+                        zero: assigner.constant(Fp::from(0))?,
+                        one: assigner.constant(Fp::from(1))?,
+                    };
+                    let (message, len) = self.circuit.assign_preimage(&mut assigner, &constants)?;
+                    let state = assigner.sha256_state(&message, &constants)?;
+                    let mut public_bytes = assigner.digest_bytes(&state)?;
+
+                    let commitment =
+                        self.other
+                            .assign_commitment(&mut assigner, &constants, &message, &len)?;
+                    let state = assigner.sha256_state(&commitment, &constants)?;
+                    public_bytes.extend(assigner.digest_bytes(&state)?);
+                    Ok((public_bytes, len))
+                },
+            )?;
+
+            for (i, byte) in public_bytes.iter().enumerate() {
+                layouter.constrain_instance(byte.cell(), config.instance, i)?;
+            }
+            layouter.constrain_instance(len.cell(), config.instance, public_bytes.len())?;
+            Ok(())
+        }
+    }
+
+    /// SHA256 compression of a single raw `block` from the initial state
+    fn compress_block(block: &[u8]) -> [u8; 32] {
+        use sha2::digest::generic_array::GenericArray;
+        let mut state = INITIAL_STATE;
+        sha2::compress256(&mut state, &[GenericArray::clone_from_slice(block)]);
+
+        let mut digest = [0u8; 32];
+        for (bytes, word) in digest.chunks_exact_mut(4).zip(state) {
+            bytes.copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
 
     #[test]
     fn test_sha256_circuit_small_input() {
@@ -105,14 +811,12 @@ mod tests {
         let circuit = Sha256Circuit::new(data.clone());
         let expected_hash = circuit.expected_hash();
 
-        // Convert hash bytes to field elements for public input
-        let public_input: Vec<Fp> = expected_hash
-            .iter()
-            .map(|&byte| Fp::from(byte as u64))
-            .collect();
-
-        // Test with mock prover (k=8 should be sufficient for this simple circuit)
-        let prover = MockProver::run(8, &circuit, vec![public_input]).unwrap();
+        let prover = MockProver::run(
+            smallest_k(&circuit),
+            &circuit,
+            vec![public_inputs(&[expected_hash], data.len())],
+        )
+        .unwrap();
         prover.assert_satisfied();
     }
 
@@ -122,15 +826,187 @@ mod tests {
         let circuit = Sha256Circuit::new(data);
         let expected_hash = circuit.expected_hash();
 
-        let public_input: Vec<Fp> = expected_hash
-            .iter()
-            .map(|&byte| Fp::from(byte as u64))
-            .collect();
+        let prover = MockProver::run(
+            smallest_k(&circuit),
+            &circuit,
+            vec![public_inputs(&[expected_hash], 0)],
+        )
+        .unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_sha256_circuit_multi_block_input() {
+        let circuit = Sha256Circuit::new(vec![0x61; 100]);
+        assert_eq!(circuit.shape().blocks, 2);
+        let expected_hash = circuit.expected_hash();
 
-        let prover = MockProver::run(8, &circuit, vec![public_input]).unwrap();
+        let prover = MockProver::run(
+            smallest_k(&circuit),
+            &circuit,
+            vec![public_inputs(&[expected_hash], 100)],
+        )
+        .unwrap();
         prover.assert_satisfied();
     }
 
+    #[test]
+    fn test_sha256_circuit_fills_last_block() {
+        // 55 bytes leave exactly the marker and the length field
+        let circuit = Sha256Circuit::new(vec![0x62; 55]);
+        assert_eq!(circuit.shape().blocks, 1);
+        let expected_hash = circuit.expected_hash();
+
+        let prover = MockProver::run(
+            smallest_k(&circuit),
+            &circuit,
+            vec![public_inputs(&[expected_hash], 55)],
+        )
+        .unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_mismatched_preimage_and_hash_fails() {
+        let circuit = Sha256Circuit::new(b"hello".to_vec());
+        let other_hash = Sha256Circuit::new(b"world".to_vec()).expected_hash();
+
+        let prover = MockProver::run(
+            smallest_k(&circuit),
+            &circuit,
+            vec![public_inputs(&[other_hash], 5)],
+        )
+        .unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_public_length_must_be_the_input_length() {
+        let circuit = Sha256Circuit::new(b"hello".to_vec());
+        let hash = circuit.expected_hash();
+
+        for len in [4, 6] {
+            let prover = MockProver::run(
+                smallest_k(&circuit),
+                &circuit,
+                vec![public_inputs(&[hash], len)],
+            )
+            .unwrap();
+            assert!(prover.verify().is_err(), "length {} was accepted", len);
+        }
+    }
+
+    #[test]
+    fn test_padding_is_enforced() {
+        let k = smallest_k(&Sha256Circuit::new(vec![]));
+
+        // Honest padding satisfies the circuit
+        let honest = Unpadded {
+            block: b"hello".to_vec(),
+            stated_len: 5,
+        };
+        let hash = Sha256Circuit::new(b"hello".to_vec()).expected_hash();
+        let prover = MockProver::run(k, &honest, vec![public_inputs(&[hash], 5)]).unwrap();
+        prover.assert_satisfied();
+
+        // A block of arbitrary bytes where the marker and zeros belong, with
+        // a length field claiming five bytes, fails even though the digest
+        // is the one those bits compress to
+        let block = [b"hello".as_slice(), &[0x5a; 51]].concat();
+        let mut raw = block.clone();
+        raw.extend_from_slice(&40u64.to_be_bytes());
+        let forged = Unpadded {
+            block,
+            stated_len: 5,
+        };
+        let prover =
+            MockProver::run(k, &forged, vec![public_inputs(&[compress_block(&raw)], 5)]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_claimed_digest_must_be_the_computed_one() {
+        let circuit = Sha256Circuit::new(b"hello".to_vec());
+        let k = smallest_k(&circuit);
+
+        // Claiming the real digest satisfies the circuit
+        let honest = ClaimedDigest {
+            claimed: circuit.expected_hash(),
+            circuit: circuit.clone(),
+        };
+        let prover =
+            MockProver::run(k, &honest, vec![public_inputs(&[honest.claimed], 5)]).unwrap();
+        prover.assert_satisfied();
+
+        // A digest witness for other data, matching the public input, fails
+        let forged = ClaimedDigest {
+            claimed: Sha256Circuit::new(b"world".to_vec()).expected_hash(),
+            circuit,
+        };
+        let prover =
+            MockProver::run(k, &forged, vec![public_inputs(&[forged.claimed], 5)]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_committed_circuit_exposes_hash_and_commitment() {
+        let blinding = [9u8; BLINDING_BYTES];
+        let circuit = Sha256Circuit::with_commitment(b"private".to_vec(), blinding);
+        let public = public_inputs(
+            &[
+                circuit.expected_hash(),
+                circuit.expected_commitment().unwrap(),
+            ],
+            7,
+        );
+
+        let prover = MockProver::run(smallest_k(&circuit), &circuit, vec![public]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_committed_circuit_rejects_wrong_commitment() {
+        let circuit = Sha256Circuit::with_commitment(b"private".to_vec(), [9u8; BLINDING_BYTES]);
+        let public = public_inputs(
+            &[
+                circuit.expected_hash(),
+                hash_commitment(&[8u8; BLINDING_BYTES], b"private"),
+            ],
+            7,
+        );
+
+        let prover = MockProver::run(smallest_k(&circuit), &circuit, vec![public]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_commitment_covers_the_hashed_data() {
+        let blinding = [9u8; BLINDING_BYTES];
+        let circuit = Sha256Circuit::with_commitment(b"private".to_vec(), blinding);
+        let other = Sha256Circuit::with_commitment(b"swapped".to_vec(), blinding);
+        let public = public_inputs(
+            &[
+                circuit.expected_hash(),
+                other.expected_commitment().unwrap(),
+            ],
+            7,
+        );
+        let k = smallest_k(&circuit);
+
+        let rebound = Rebound { circuit, other };
+        let prover = MockProver::run(k, &rebound, vec![public]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_padded_block_count() {
+        // 56 bytes no longer leave room for the marker and length in one block
+        assert_eq!(padded_block_count(0), 1);
+        assert_eq!(padded_block_count(55), 1);
+        assert_eq!(padded_block_count(56), 2);
+        assert_eq!(message_positions(2), 2 * BLOCK_BYTES - LENGTH_BYTES);
+    }
+
     #[test]
     fn test_hash_computation() {
         let circuit = Sha256Circuit::new(b"test".to_vec());
@@ -0,0 +1,431 @@
+use crate::bits::{self, Bit, BitAssigner, BitLane, LANES};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    pasta::Fp,
+    plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+};
+
+/// Bytes absorbed per Keccak-f[1600] permutation by Keccak256
+pub const RATE_BYTES: usize = 136;
+
+const RATE_BITS: usize = RATE_BYTES * 8;
+const STATE_BITS: usize = 1600;
+const LANE_BITS: usize = 64;
+const DIGEST_BYTES: usize = 32;
+
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000_0000_0000_0001,
+    0x0000_0000_0000_8082,
+    0x8000_0000_0000_808A,
+    0x8000_0000_8000_8000,
+    0x0000_0000_0000_808B,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8009,
+    0x0000_0000_0000_008A,
+    0x0000_0000_0000_0088,
+    0x0000_0000_8000_8009,
+    0x0000_0000_8000_000A,
+    0x0000_0000_8000_808B,
+    0x8000_0000_0000_008B,
+    0x8000_0000_0000_8089,
+    0x8000_0000_0000_8003,
+    0x8000_0000_0000_8002,
+    0x8000_0000_0000_0080,
+    0x0000_0000_0000_800A,
+    0x8000_0000_8000_000A,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8080,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8008,
+];
+
+// Rho rotation offsets, indexed by lane [x][y]
+const ROTATION_OFFSETS: [[usize; 5]; 5] = [
+    [0, 36, 3, 41, 18],
+    [1, 44, 10, 45, 2],
+    [62, 6, 43, 15, 61],
+    [28, 55, 25, 21, 56],
+    [27, 20, 39, 8, 14],
+];
+
+// Operation counts, used to size the circuit without synthesizing it. Every
+// block but the first is absorbed with one XOR per rate bit.
+const CONSTANT_OPS: usize = 2;
+const DIGEST_OPS: usize = DIGEST_BYTES * 7;
+const ROUND_OPS: usize = 2 * 5 * LANE_BITS + 2 * STATE_BITS;
+const PERMUTATION_OPS: usize = ROUND_CONSTANTS.len() * ROUND_OPS + iota_ops();
+const OPS_PER_BLOCK: usize = bits::FRAME_OPS_PER_BYTE * RATE_BYTES + RATE_BITS + PERMUTATION_OPS;
+
+const fn iota_ops() -> usize {
+    let mut ops = 0;
+    let mut i = 0;
+    while i < ROUND_CONSTANTS.len() {
+        ops += ROUND_CONSTANTS[i].count_ones() as usize;
+        i += 1;
+    }
+    ops
+}
+
+/// Keccak256 (the pre-standard padding used by Ethereum) of `data`
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    use tiny_keccak::{Hasher, Keccak};
+    let mut hasher = Keccak::v256();
+    let mut output = [0u8; 32];
+    hasher.update(data);
+    hasher.finalize(&mut output);
+    output
+}
+
+/// Number of permutation blocks a `len`-byte input occupies once padded
+pub fn keccak_block_count(len: usize) -> usize {
+    len / RATE_BYTES + 1
+}
+
+/// Rows a Keccak256 circuit over `blocks` blocks occupies
+pub fn rows_for_blocks(blocks: usize) -> usize {
+    let ops = CONSTANT_OPS
+        + DIGEST_OPS
+        + bits::frame_ops(blocks * RATE_BYTES)
+        + blocks.saturating_sub(1) * RATE_BITS
+        + blocks * PERMUTATION_OPS;
+    bits::rows_for_ops(ops)
+}
+
+/// Largest number of blocks a circuit with `rows` rows fits
+pub fn max_blocks_for_rows(rows: usize) -> usize {
+    // The first block's framing saves two operations and it is not XORed in
+    (bits::ops_for_rows(rows) + RATE_BITS + 2).saturating_sub(CONSTANT_OPS + DIGEST_OPS)
+        / OPS_PER_BLOCK
+}
+
+// Index of bit `z` of lane (x, y) in the flattened state
+fn bit_index(x: usize, y: usize, z: usize) -> usize {
+    (x + 5 * y) * LANE_BITS + z
+}
+
+impl BitAssigner<'_, '_> {
+    /// Keccak-f[1600] over the flattened state
+    fn permute(&mut self, mut state: Vec<Bit>, one: &Bit) -> Result<Vec<Bit>, Error> {
+        for round_constant in ROUND_CONSTANTS {
+            // Theta: fold each column's parity into its neighbours
+            let mut parity = Vec::with_capacity(5 * LANE_BITS);
+            for x in 0..5 {
+                for z in 0..LANE_BITS {
+                    let partial = self.xor3(
+                        &state[bit_index(x, 0, z)],
+                        &state[bit_index(x, 1, z)],
+                        &state[bit_index(x, 2, z)],
+                    )?;
+                    parity.push(self.xor3(
+                        &partial,
+                        &state[bit_index(x, 3, z)],
+                        &state[bit_index(x, 4, z)],
+                    )?);
+                }
+            }
+
+            let mut theta = Vec::with_capacity(STATE_BITS);
+            for y in 0..5 {
+                for x in 0..5 {
+                    for z in 0..LANE_BITS {
+                        theta.push(self.xor3(
+                            &state[bit_index(x, y, z)],
+                            &parity[((x + 4) % 5) * LANE_BITS + z],
+                            &parity[((x + 1) % 5) * LANE_BITS + (z + LANE_BITS - 1) % LANE_BITS],
+                        )?);
+                    }
+                }
+            }
+
+            // Rho and pi only move bits around, so they cost no constraints
+            let mut moved: Vec<Option<Bit>> = vec![None; STATE_BITS];
+            for y in 0..5 {
+                for x in 0..5 {
+                    let offset = ROTATION_OFFSETS[x][y];
+                    for z in 0..LANE_BITS {
+                        moved[bit_index(y, (2 * x + 3 * y) % 5, z)] = Some(
+                            theta[bit_index(x, y, (z + LANE_BITS - offset) % LANE_BITS)].clone(),
+                        );
+                    }
+                }
+            }
+            let moved: Vec<Bit> = moved
+                .into_iter()
+                .map(|bit| bit.expect("rho and pi permute every bit"))
+                .collect();
+
+            // Chi
+            state = Vec::with_capacity(STATE_BITS);
+            for y in 0..5 {
+                for x in 0..5 {
+                    for z in 0..LANE_BITS {
+                        state.push(self.chi(
+                            &moved[bit_index(x, y, z)],
+                            &moved[bit_index((x + 1) % 5, y, z)],
+                            &moved[bit_index((x + 2) % 5, y, z)],
+                        )?);
+                    }
+                }
+            }
+
+            // Iota: flip the bits of lane (0, 0) set in the round constant
+            for z in 0..LANE_BITS {
+                if (round_constant >> z) & 1 == 1 {
+                    state[z] = self.xor(&state[z], one)?;
+                }
+            }
+        }
+
+        Ok(state)
+    }
+}
+
+/// Proves knowledge of a preimage whose Keccak256 digest is the public input,
+/// followed by the preimage's length in bytes.
+///
+/// The digest bytes are packed from the cells the permutation produced, so
+/// the public inputs are fully constrained. The pad10*1 padding (domain
+/// byte 0x01) is built in the circuit from the public length, so verifiers
+/// only need to check that the length needs exactly the circuit's blocks.
+#[derive(Default, Debug, Clone)]
+pub struct KeccakCircuit {
+    data: Option<Vec<u8>>,
+    blocks: usize,
+}
+
+impl KeccakCircuit {
+    pub fn new(data: Vec<u8>) -> Self {
+        Self {
+            blocks: keccak_block_count(data.len()),
+            data: Some(data),
+        }
+    }
+
+    /// Circuit without a witness, used for key generation
+    pub fn for_blocks(blocks: usize) -> Self {
+        Self { data: None, blocks }
+    }
+
+    /// Number of permutation blocks, which determines the circuit layout
+    pub fn blocks(&self) -> usize {
+        self.blocks
+    }
+
+    pub fn expected_hash(&self) -> [u8; 32] {
+        keccak256(self.data.as_deref().unwrap_or_default())
+    }
+}
+
+impl Circuit<Fp> for KeccakCircuit {
+    type Config = KeccakCircuitConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::for_blocks(self.blocks)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        let lanes = (0..LANES).map(|_| BitLane::configure(meta)).collect();
+        let instance = meta.instance_column();
+        let constants = meta.fixed_column();
+
+        meta.enable_equality(instance);
+        meta.enable_constant(constants);
+
+        KeccakCircuitConfig { lanes, instance }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        let (digest, len) = layouter.assign_region(
+            || "keccak256",
+            |region| {
+                let mut assigner = BitAssigner::new(region, &config.lanes);
+                let zero = assigner.constant(Fp::from(0))?;
+                let one = assigner.constant(Fp::from(1))?;
+
+                // The data, then 0x01 and zeros, with the last byte's top
+                // bit set. The data always stops short of the last byte.
+                let positions = self.blocks * RATE_BYTES;
+                let (mut message, len) =
+                    assigner.frame(self.data.as_deref(), positions, 0, &zero, &one)?;
+                *message.last_mut().ok_or(Error::Synthesis)? = one.clone();
+
+                // Absorb each block into the rate portion of the state and
+                // permute; the state starts out all zero
+                let mut state: Option<Vec<Bit>> = None;
+                for block in message.chunks_exact(RATE_BITS) {
+                    let absorbed = match state {
+                        None => block
+                            .iter()
+                            .cloned()
+                            .chain(std::iter::repeat(zero.clone()).take(STATE_BITS - RATE_BITS))
+                            .collect(),
+                        Some(state) => {
+                            let mut absorbed = Vec::with_capacity(STATE_BITS);
+                            for (i, bit) in state.into_iter().enumerate() {
+                                match block.get(i) {
+                                    Some(message_bit) => {
+                                        absorbed.push(assigner.xor(&bit, message_bit)?)
+                                    }
+                                    None => absorbed.push(bit),
+                                }
+                            }
+                            absorbed
+                        }
+                    };
+                    state = Some(assigner.permute(absorbed, &one)?);
+                }
+                let state = state.ok_or(Error::Synthesis)?;
+
+                // Squeeze: the digest is the first 32 bytes of the state,
+                // each byte packed from its little-endian bits
+                let mut digest = Vec::with_capacity(DIGEST_BYTES);
+                for byte_bits in state[..DIGEST_BYTES * 8].chunks_exact(8) {
+                    let mut byte = byte_bits[7].clone();
+                    for bit in byte_bits[..7].iter().rev() {
+                        byte = assigner.double_add(&byte, bit)?;
+                    }
+                    digest.push(byte);
+                }
+                Ok((digest, len))
+            },
+        )?;
+
+        for (i, byte) in digest.iter().enumerate() {
+            layouter.constrain_instance(byte.cell(), config.instance, i)?;
+        }
+        // The input length follows the digest
+        layouter.constrain_instance(len.cell(), config.instance, DIGEST_BYTES)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::dev::MockProver;
+
+    /// Public inputs for the digest `hash` of a `len`-byte preimage
+    fn public_input(hash: &[u8; 32], len: usize) -> Vec<Fp> {
+        hash.iter()
+            .map(|&byte| Fp::from(byte as u64))
+            .chain(std::iter::once(Fp::from(len as u64)))
+            .collect()
+    }
+
+    fn smallest_k(blocks: usize) -> u32 {
+        rows_for_blocks(blocks).next_power_of_two().trailing_zeros()
+    }
+
+    #[test]
+    fn test_keccak_circuit_matches_tiny_keccak() {
+        let circuit = KeccakCircuit::new(b"hello".to_vec());
+        let expected_hash = keccak256(b"hello");
+
+        let prover = MockProver::run(
+            smallest_k(1),
+            &circuit,
+            vec![public_input(&expected_hash, 5)],
+        )
+        .unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_keccak_circuit_empty_input() {
+        let circuit = KeccakCircuit::new(vec![]);
+        let expected_hash = circuit.expected_hash();
+
+        let prover = MockProver::run(
+            smallest_k(1),
+            &circuit,
+            vec![public_input(&expected_hash, 0)],
+        )
+        .unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_keccak_circuit_single_padding_byte() {
+        // 135 bytes leave one padding byte carrying both 0x01 and 0x80
+        let circuit = KeccakCircuit::new(vec![0x62; 135]);
+        assert_eq!(circuit.blocks(), 1);
+        let expected_hash = keccak256(&[0x62; 135]);
+
+        let prover = MockProver::run(
+            smallest_k(1),
+            &circuit,
+            vec![public_input(&expected_hash, 135)],
+        )
+        .unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_keccak_circuit_multi_block_input() {
+        let circuit = KeccakCircuit::new(vec![0x61; 200]);
+        assert_eq!(circuit.blocks(), 2);
+        let expected_hash = keccak256(&[0x61; 200]);
+
+        let prover = MockProver::run(
+            smallest_k(2),
+            &circuit,
+            vec![public_input(&expected_hash, 200)],
+        )
+        .unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn test_keccak_circuit_rejects_wrong_hash() {
+        let circuit = KeccakCircuit::new(b"hello".to_vec());
+        let other_hash = keccak256(b"world");
+
+        let prover =
+            MockProver::run(smallest_k(1), &circuit, vec![public_input(&other_hash, 5)]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_keccak_circuit_rejects_wrong_length() {
+        // Padding follows the public length, so the data must end there
+        let circuit = KeccakCircuit::new(b"hello".to_vec());
+        let hash = keccak256(b"hello");
+
+        for len in [4, 6] {
+            let prover =
+                MockProver::run(smallest_k(1), &circuit, vec![public_input(&hash, len)]).unwrap();
+            assert!(prover.verify().is_err(), "length {} was accepted", len);
+        }
+    }
+
+    #[test]
+    fn test_keccak256_known_vectors() {
+        assert_eq!(
+            hex::encode(keccak256(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert_eq!(
+            hex::encode(keccak256(b"abc")),
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+    }
+
+    #[test]
+    fn test_padding_and_capacity() {
+        // 135 bytes leave room for the padding in one block; 136 do not
+        assert_eq!(keccak_block_count(135), 1);
+        assert_eq!(keccak_block_count(136), 2);
+
+        let max_blocks = max_blocks_for_rows(1 << 17);
+        assert!(max_blocks > 0);
+        assert!(rows_for_blocks(max_blocks) <= 1 << 17);
+        assert!(rows_for_blocks(max_blocks + 1) > 1 << 17);
+    }
+}
@@ -1,14 +1,30 @@
+pub mod abi;
+mod bits;
 mod circuit;
+mod keccak;
 
-use crate::circuit::Sha256Circuit;
+pub use crate::circuit::{hash_commitment, BLINDING_BYTES};
+pub use crate::keccak::keccak256;
+
+use crate::circuit::{CircuitShape, Sha256Circuit, BLOCK_BYTES, MIN_PADDING_BYTES};
+use crate::keccak::{keccak_block_count, KeccakCircuit, RATE_BYTES};
 use halo2_proofs::{
     pasta::{EqAffine, Fp},
-    plonk::{create_proof, keygen_pk, keygen_vk, ProvingKey, VerifyingKey},
+    plonk::{create_proof, keygen_pk, keygen_vk, Circuit, ProvingKey, VerifyingKey},
     poly::commitment::Params,
     transcript::{Blake2bRead, Blake2bWrite, Challenge255},
 };
 use rand::rngs::OsRng;
-use std::time::Instant;
+use rand_core::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::ffi::c_char;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::time::{Duration, Instant};
 
 // FFI structures
 #[repr(C)]
@@ -24,90 +40,1078 @@ pub struct Output {
     pub hash: [u8; 32],
 }
 
+/// Hash function a circuit proves knowledge of a preimage for
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CircuitType {
+    #[default]
+    Sha256,
+    Keccak256,
+}
+
+impl CircuitType {
+    /// Name used for this circuit in requests and stored proofs
+    pub fn name(&self) -> &'static str {
+        match self {
+            CircuitType::Sha256 => "sha256",
+            CircuitType::Keccak256 => "keccak256",
+        }
+    }
+
+    /// Native digest of `data` under this circuit's hash function
+    pub fn digest(&self, data: &[u8]) -> [u8; 32] {
+        match self {
+            CircuitType::Sha256 => Sha256::digest(data).into(),
+            CircuitType::Keccak256 => keccak256(data),
+        }
+    }
+
+    /// Public input identifying this circuit type. It follows the digest in
+    /// every proof's public inputs, so a proof made for one circuit type
+    /// fails verification under any other label.
+    fn domain_tag(&self) -> Fp {
+        match self {
+            CircuitType::Sha256 => Fp::from(1),
+            CircuitType::Keccak256 => Fp::from(2),
+        }
+    }
+}
+
+impl std::str::FromStr for CircuitType {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "sha256" => Ok(CircuitType::Sha256),
+            "keccak256" => Ok(CircuitType::Keccak256),
+            _ => Err(format!("Unsupported circuit type: {}", name)),
+        }
+    }
+}
+
+/// Timing breakdown for a single proof, in milliseconds
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofMetrics {
+    /// Loading the proving system and the keys for the input's circuit shape
+    pub setup_ms: u64,
+    /// Building the circuit witness from the input
+    pub witness_ms: u64,
+    /// Running the prover itself
+    pub proving_ms: u64,
+    /// Wall-clock time for the whole call
+    pub total_ms: u64,
+    /// Length of the proven input in bytes
+    pub input_len: usize,
+}
+
+/// A proof together with the timings recorded while generating it
+#[derive(Clone, Debug)]
+pub struct ProofWithMetrics {
+    pub hash: [u8; 32],
+    pub proof: Vec<u8>,
+    pub metrics: ProofMetrics,
+}
+
 // Configuration for proving system
-const CIRCUIT_K: u32 = 14; // Circuit size parameter (2^14 = 16384 rows)
-                           // TODO: Optimize to k=12 or k=13 for better performance
+const CIRCUIT_K: u32 = 14; // Default circuit size parameter (2^14 = 16384 rows, inputs up to 311 bytes)
+const MIN_CIRCUIT_K: u32 = 12; // Smallest circuit fitting a one-block input
+const DEFAULT_MAX_CIRCUIT_SIZE: usize = 1 << 20;
 
-// Cached proving system state
-static mut PROVING_SYSTEM: Option<ProvingSystem> = None;
-static mut INIT_LOCK: std::sync::Once = std::sync::Once::new();
+/// Configuration for the proving system
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProverConfig {
+    /// Circuit size parameter; the circuit has 2^k rows
+    pub k: u32,
+    /// Largest circuit (in rows) a caller is allowed to request
+    pub max_circuit_size: usize,
+    /// Longest input whose proofs are verified. Proof headers name the
+    /// layout to verify against, so this bounds the layouts a verifier can
+    /// be made to generate keys for; `None` allows any the circuit holds.
+    pub max_verify_input_len: Option<usize>,
+}
 
-struct ProvingSystem {
-    params: Params<EqAffine>,
+impl Default for ProverConfig {
+    fn default() -> Self {
+        Self {
+            k: CIRCUIT_K,
+            max_circuit_size: DEFAULT_MAX_CIRCUIT_SIZE,
+            max_verify_input_len: None,
+        }
+    }
+}
+
+impl ProverConfig {
+    pub fn new(k: u32) -> Self {
+        Self {
+            k,
+            ..Self::default()
+        }
+    }
+
+    /// Number of rows in the circuit (2^k)
+    pub fn circuit_size(&self) -> usize {
+        1usize << self.k
+    }
+
+    /// Largest number of SHA256 message blocks a circuit of this size fits
+    pub fn max_blocks(&self) -> usize {
+        circuit::max_blocks_for_rows(self.circuit_size())
+    }
+
+    /// Largest input, in bytes, that a circuit of this size accepts
+    pub fn max_input_len(&self) -> usize {
+        self.max_blocks()
+            .saturating_mul(BLOCK_BYTES)
+            .saturating_sub(MIN_PADDING_BYTES)
+    }
+
+    /// Largest number of Keccak256 blocks a circuit of this size fits
+    pub fn max_keccak_blocks(&self) -> usize {
+        keccak::max_blocks_for_rows(self.circuit_size())
+    }
+
+    /// Largest input, in bytes, that a `circuit` of this size accepts
+    pub fn max_input_len_for(&self, circuit: CircuitType) -> usize {
+        match circuit {
+            CircuitType::Sha256 => self.max_input_len(),
+            // Keccak padding needs at least one byte of the last block
+            CircuitType::Keccak256 => self
+                .max_keccak_blocks()
+                .saturating_mul(RATE_BYTES)
+                .saturating_sub(1),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.k < MIN_CIRCUIT_K || self.k >= usize::BITS {
+            return Err(format!(
+                "Circuit size parameter k={} is out of range (minimum k={})",
+                self.k, MIN_CIRCUIT_K
+            ));
+        }
+
+        if self.circuit_size() > self.max_circuit_size {
+            return Err(format!(
+                "Circuit size 2^{} = {} rows exceeds the configured maximum of {} rows",
+                self.k,
+                self.circuit_size(),
+                self.max_circuit_size
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+// On-disk cache format for the proving system
+const CACHE_MAGIC: &[u8; 8] = b"GAAZKPS\0";
+const CACHE_VERSION: u32 = 2;
+
+// Most circuit layouts whose keys are kept at once. Keys for a large layout
+// take tens of megabytes, and verification may be asked about any layout.
+// The least recently used layout is evicted first.
+const MAX_CACHED_LAYOUTS: usize = 16;
+
+// Key generation takes seconds of CPU, and a verifier's layout comes from an
+// untrusted proof header, so verification may only generate keys this many
+// times per window. Proving isn't limited; its layouts come from its inputs.
+const MAX_VERIFY_KEYGENS: usize = 4;
+const VERIFY_KEYGEN_WINDOW: Duration = Duration::from_secs(60);
+
+// Proofs are prefixed with the proven input's length and whether they also
+// prove a commitment, as little-endian u32s. The length is a public input,
+// and together with the commitment flag it picks the circuit layout and so
+// the keys.
+const PROOF_HEADER_LEN: usize = 8;
+
+// Cached proving system state. The initialization result is stored so that
+// concurrent first callers block on a single generation and all observe the
+// same outcome. Callers hold their own `Arc` while they work, so
+// `shutdown_proving_system` never pulls the system out from under a proof in
+// progress; its memory is freed once the last of them finishes.
+static PROVING_SYSTEM: RwLock<SharedProvingSystem> = RwLock::new(SharedProvingSystem {
+    system: None,
+    settings: None,
+});
+
+struct SharedProvingSystem {
+    system: Option<Result<Arc<ProvingSystem>, String>>,
+    // Configuration and cache directory of the last initialization, reused
+    // when the system is brought back after a shutdown
+    settings: Option<(ProverConfig, Option<PathBuf>)>,
+}
+
+/// A circuit and its block count, which together determine its keys
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum CircuitLayout {
+    Sha256(CircuitShape),
+    Keccak256(usize),
+}
+
+impl CircuitLayout {
+    /// Layout proving a `len`-byte input with `circuit`, or `None` for a
+    /// commitment under a circuit that doesn't prove one
+    fn for_input(circuit: CircuitType, len: usize, committed: bool) -> Option<Self> {
+        match (circuit, committed) {
+            (CircuitType::Sha256, false) => {
+                Some(CircuitLayout::Sha256(CircuitShape::for_input(len)))
+            }
+            (CircuitType::Sha256, true) => Some(CircuitLayout::Sha256(
+                CircuitShape::for_committed_input(len),
+            )),
+            (CircuitType::Keccak256, false) => {
+                Some(CircuitLayout::Keccak256(keccak_block_count(len)))
+            }
+            (CircuitType::Keccak256, true) => None,
+        }
+    }
+
+    fn circuit_type(&self) -> CircuitType {
+        match self {
+            CircuitLayout::Sha256(_) => CircuitType::Sha256,
+            CircuitLayout::Keccak256(_) => CircuitType::Keccak256,
+        }
+    }
+
+    /// Header of a proof for a `input_len`-byte input with this layout
+    fn header(&self, input_len: usize) -> Vec<u8> {
+        let mut header = Vec::with_capacity(PROOF_HEADER_LEN);
+        header.extend_from_slice(&(input_len as u32).to_le_bytes());
+        header.extend_from_slice(&(self.is_committed() as u32).to_le_bytes());
+        header
+    }
+
+    fn is_committed(&self) -> bool {
+        matches!(self, CircuitLayout::Sha256(shape) if shape.is_committed())
+    }
+
+    /// Message blocks hashed by this layout's circuit
+    fn blocks(&self) -> usize {
+        match *self {
+            CircuitLayout::Sha256(shape) => shape.total_blocks(),
+            CircuitLayout::Keccak256(blocks) => blocks,
+        }
+    }
+
+    /// Whether a proving system with `config` can hold this layout
+    fn fits(&self, config: &ProverConfig) -> bool {
+        match *self {
+            CircuitLayout::Sha256(shape) => {
+                shape.blocks > 0 && shape.total_blocks() <= config.max_blocks()
+            }
+            CircuitLayout::Keccak256(blocks) => blocks > 0 && blocks <= config.max_keccak_blocks(),
+        }
+    }
+
+    /// Whether a proving system with `config` verifies proofs with this
+    /// layout: it must fit, and be no larger than the layout of the longest
+    /// input verified
+    fn is_verifiable(&self, config: &ProverConfig) -> bool {
+        let within_limit = match config.max_verify_input_len {
+            Some(max_len) => {
+                CircuitLayout::for_input(self.circuit_type(), max_len, self.is_committed())
+                    .is_some_and(|largest| self.blocks() <= largest.blocks())
+            }
+            None => true,
+        };
+        within_limit && self.fits(config)
+    }
+}
+
+/// A layout's keys, once generated
+type KeySlot = Mutex<Option<Arc<CircuitKeys>>>;
+
+/// Key slots per circuit layout, with when each was last used
+#[derive(Default)]
+struct KeyCache {
+    slots: HashMap<CircuitLayout, (Arc<KeySlot>, u64)>,
+    // Incremented on every lookup, ordering the slots by last use
+    clock: u64,
+}
+
+impl KeyCache {
+    fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn contains(&self, layout: &CircuitLayout) -> bool {
+        self.slots.contains_key(layout)
+    }
+}
+
+/// Proving and verifying keys for one circuit layout
+struct CircuitKeys {
     pk: ProvingKey<EqAffine>,
     vk: VerifyingKey<EqAffine>,
 }
 
+impl CircuitKeys {
+    fn generate(params: &Params<EqAffine>, layout: CircuitLayout) -> Result<Self, String> {
+        // Key generation runs over a witness-free circuit
+        match layout {
+            CircuitLayout::Sha256(shape) => Self::keygen(params, &Sha256Circuit::for_shape(shape)),
+            CircuitLayout::Keccak256(blocks) => {
+                Self::keygen(params, &KeccakCircuit::for_blocks(blocks))
+            }
+        }
+    }
+
+    fn keygen<C: Circuit<Fp>>(params: &Params<EqAffine>, circuit: &C) -> Result<Self, String> {
+        // Generate verifying key
+        let vk =
+            keygen_vk(params, circuit).map_err(|e| format!("VK generation failed: {:?}", e))?;
+
+        // Generate proving key
+        let pk = keygen_pk(params, vk.clone(), circuit)
+            .map_err(|e| format!("PK generation failed: {:?}", e))?;
+
+        Ok(Self { pk, vk })
+    }
+}
+
+/// Whether the proving system's parameters survive a restart
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum KeyPersistence {
+    /// Loaded from, or saved to, the cache file at `path`
+    Persisted { path: PathBuf },
+    /// Kept in memory only, because the cache directory couldn't be written
+    InMemory { reason: String },
+    /// No cache directory was configured
+    Disabled,
+}
+
+/// Parameters and keys for proving and verifying SHA256 and Keccak256 circuits
+pub struct ProvingSystem {
+    config: ProverConfig,
+    params: Params<EqAffine>,
+    // Keys per circuit layout, generated on first use. Each layout has its
+    // own slot, so generating one layout's keys holds up only the callers
+    // waiting for the same layout.
+    keys: Mutex<KeyCache>,
+    // When verification last generated keys, within the current window
+    verify_keygens: Mutex<VecDeque<Instant>>,
+    fingerprint: [u8; 32],
+    persistence: KeyPersistence,
+}
+
 impl ProvingSystem {
-    fn load_or_generate() -> Result<Self, String> {
-        // For now, always generate new proving system
-        // TODO: Implement proper serialization/deserialization
-        Self::generate_new()
+    /// Generate a proving system for the given configuration
+    pub fn with_config(config: ProverConfig) -> Result<Self, String> {
+        config.validate()?;
+        Self::generate_new(config)
+    }
+
+    /// Configuration this proving system was generated for
+    pub fn config(&self) -> &ProverConfig {
+        &self.config
+    }
+
+    /// Whether this proving system is cached on disk
+    pub fn persistence(&self) -> &KeyPersistence {
+        &self.persistence
+    }
+
+    /// Load the proving system from `cache_dir`, or generate it and cache it
+    /// there. A cache directory that can't be created or written only
+    /// disables persistence; the system is still generated in memory.
+    fn load_or_generate(config: ProverConfig, cache_dir: Option<&Path>) -> Result<Self, String> {
+        config.validate()?;
+
+        let Some(cache_dir) = cache_dir else {
+            return Self::generate_new(config);
+        };
+        let cache_path = cache_file_path(cache_dir, config.k);
+
+        if cache_path.exists() {
+            match Self::read_from(&cache_path, config) {
+                Ok(mut system) => {
+                    tracing::info!(path = ?cache_path, "Loaded proving system from cache");
+                    system.persistence = KeyPersistence::Persisted { path: cache_path };
+                    return Ok(system);
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        path = ?cache_path,
+                        error = %e,
+                        "Ignoring unreadable proving system cache"
+                    );
+                }
+            }
+        }
+
+        // Check the directory before spending minutes on generation
+        let writable = ensure_writable_dir(cache_dir);
+
+        let mut system = Self::generate_new(config)?;
+        system.persistence = match writable.and_then(|_| system.write_to(&cache_path)) {
+            Ok(()) => KeyPersistence::Persisted { path: cache_path },
+            Err(reason) => {
+                tracing::warn!(
+                    path = ?cache_dir,
+                    %reason,
+                    "Proving system cache is unavailable; params are kept in memory and will be regenerated on restart"
+                );
+                KeyPersistence::InMemory { reason }
+            }
+        };
+
+        Ok(system)
     }
 
-    fn generate_new() -> Result<Self, String> {
+    fn generate_new(config: ProverConfig) -> Result<Self, String> {
         let start = Instant::now();
-        println!("Generating new proving system (this may take a few minutes)...");
+        tracing::info!(
+            k = config.k,
+            "Generating new proving system (this may take a few minutes)"
+        );
 
         // Generate params
-        let params = Params::new(CIRCUIT_K);
+        let params = Params::new(config.k);
+        let system = Self::from_params(config, params)?;
 
-        // Create dummy circuit for key generation
-        let circuit = Sha256Circuit::new(vec![]);
+        tracing::info!(elapsed = ?start.elapsed(), "Generated proving system");
 
-        // Generate verifying key
-        let vk =
-            keygen_vk(&params, &circuit).map_err(|e| format!("VK generation failed: {:?}", e))?;
+        Ok(system)
+    }
 
-        // Generate proving key
-        let pk = keygen_pk(&params, vk.clone(), &circuit)
-            .map_err(|e| format!("PK generation failed: {:?}", e))?;
+    fn from_params(config: ProverConfig, params: Params<EqAffine>) -> Result<Self, String> {
+        // Single-block keys are generated eagerly; they cover inputs up to
+        // 55 bytes and anchor the fingerprint
+        let single_block_layout = CircuitLayout::Sha256(CircuitShape::for_input(0));
+        let single_block = CircuitKeys::generate(&params, single_block_layout)?;
 
-        println!("Generated proving system in {:?}", start.elapsed());
+        let mut hasher = Sha256::new();
+        hasher.update(format!("{:?}", single_block.vk.pinned()).as_bytes());
+        let fingerprint = hasher.finalize().into();
 
-        Ok(ProvingSystem { params, pk, vk })
+        let slot = Arc::new(Mutex::new(Some(Arc::new(single_block))));
+        Ok(ProvingSystem {
+            config,
+            params,
+            keys: Mutex::new(KeyCache {
+                slots: HashMap::from([(single_block_layout, (slot, 0))]),
+                clock: 0,
+            }),
+            verify_keygens: Mutex::new(VecDeque::new()),
+            fingerprint,
+            persistence: KeyPersistence::Disabled,
+        })
     }
-}
 
-fn get_proving_system() -> Result<&'static ProvingSystem, String> {
-    unsafe {
-        INIT_LOCK.call_once(|| match ProvingSystem::load_or_generate() {
-            Ok(system) => {
-                PROVING_SYSTEM = Some(system);
+    /// Fingerprint of the single-block verifying key, used to detect a cache
+    /// written for a different circuit layout.
+    fn vk_fingerprint(&self) -> [u8; 32] {
+        self.fingerprint
+    }
+
+    fn keys_for(&self, layout: CircuitLayout) -> Result<Arc<CircuitKeys>, String> {
+        self.keys_or_generate(layout, || Ok(()))
+    }
+
+    /// Keys for checking a proof whose header claims `layout`. Layouts this
+    /// system doesn't verify are refused rather than spending a key
+    /// generation on them, and generating keys for the rest is rate limited.
+    fn verifying_keys_for(&self, layout: CircuitLayout) -> Result<Arc<CircuitKeys>, String> {
+        if !layout.is_verifiable(&self.config) {
+            return Err(format!("No circuit has layout {:?}", layout));
+        }
+        self.keys_or_generate(layout, || self.start_verify_keygen())
+    }
+
+    /// `layout`'s keys, generating them if `may_generate` allows it
+    fn keys_or_generate(
+        &self,
+        layout: CircuitLayout,
+        may_generate: impl FnOnce() -> Result<(), String>,
+    ) -> Result<Arc<CircuitKeys>, String> {
+        let slot = self.key_slot(layout)?;
+        let mut slot = slot
+            .lock()
+            .map_err(|_| "Proving key lock poisoned".to_string())?;
+
+        if let Some(existing) = slot.as_ref() {
+            return Ok(existing.clone());
+        }
+
+        may_generate()?;
+        let generated = Arc::new(CircuitKeys::generate(&self.params, layout)?);
+        *slot = Some(generated.clone());
+        Ok(generated)
+    }
+
+    /// Record a key generation on behalf of verification, or refuse it if
+    /// the window's allowance is used up
+    fn start_verify_keygen(&self) -> Result<(), String> {
+        let mut started = self
+            .verify_keygens
+            .lock()
+            .map_err(|_| "Verifying key rate limit lock poisoned".to_string())?;
+
+        let now = Instant::now();
+        while started
+            .front()
+            .is_some_and(|&at| now.duration_since(at) >= VERIFY_KEYGEN_WINDOW)
+        {
+            started.pop_front();
+        }
+        if started.len() >= MAX_VERIFY_KEYGENS {
+            return Err("Too many verifying key generations; try again later".to_string());
+        }
+
+        started.push_back(now);
+        Ok(())
+    }
+
+    /// The slot holding `layout`'s keys, making room for it if the cache is
+    /// full by evicting the least recently used layout. An evicted layout's
+    /// keys stay alive for whoever is using them.
+    fn key_slot(&self, layout: CircuitLayout) -> Result<Arc<KeySlot>, String> {
+        let mut keys = self
+            .keys
+            .lock()
+            .map_err(|_| "Proving key cache lock poisoned".to_string())?;
+        keys.clock += 1;
+        let now = keys.clock;
+
+        if let Some((slot, last_used)) = keys.slots.get_mut(&layout) {
+            *last_used = now;
+            return Ok(slot.clone());
+        }
+
+        if keys.len() >= MAX_CACHED_LAYOUTS {
+            // The single-block keys anchor the fingerprint and are never evicted
+            let single_block_layout = CircuitLayout::Sha256(CircuitShape::for_input(0));
+            let evicted = keys
+                .slots
+                .iter()
+                .filter(|(cached, _)| **cached != single_block_layout)
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(cached, _)| *cached);
+            if let Some(evicted) = evicted {
+                keys.slots.remove(&evicted);
             }
+        }
+
+        let slot = Arc::new(Mutex::new(None));
+        keys.slots.insert(layout, (slot.clone(), now));
+        Ok(slot)
+    }
+
+    // halo2_proofs 0.3 only supports serializing `Params`: `ProvingKey` and
+    // `VerifyingKey` have no read or write and keep their fields private. So
+    // the cache stores the params together with a verifying key fingerprint,
+    // and a load skips generating the params but still derives the
+    // single-block keys (deterministic given the params) and checks them
+    // against the fingerprint. The `proving_system_startup` benchmark
+    // measures what that saves.
+    fn write_to(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create cache directory: {}", e))?;
+        }
+
+        // Write to a temporary file first so a crash never leaves a truncated cache
+        let tmp_path = path.with_extension("tmp");
+        let file =
+            File::create(&tmp_path).map_err(|e| format!("Failed to create cache file: {}", e))?;
+        let mut writer = BufWriter::new(file);
+
+        writer
+            .write_all(CACHE_MAGIC)
+            .and_then(|_| writer.write_all(&CACHE_VERSION.to_le_bytes()))
+            .and_then(|_| writer.write_all(&self.config.k.to_le_bytes()))
+            .and_then(|_| writer.write_all(&self.vk_fingerprint()))
+            .and_then(|_| self.params.write(&mut writer))
+            .and_then(|_| writer.flush())
+            .map_err(|e| format!("Failed to write cache file: {}", e))?;
+
+        fs::rename(&tmp_path, path).map_err(|e| format!("Failed to finalize cache file: {}", e))
+    }
+
+    fn read_from(path: &Path, config: ProverConfig) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("Failed to open cache file: {}", e))?;
+        let mut reader = BufReader::new(file);
+
+        let mut magic = [0u8; 8];
+        reader
+            .read_exact(&mut magic)
+            .map_err(|e| format!("Failed to read cache header: {}", e))?;
+        if &magic != CACHE_MAGIC {
+            return Err("Invalid cache magic".to_string());
+        }
+
+        let version = read_u32(&mut reader)?;
+        if version != CACHE_VERSION {
+            return Err(format!(
+                "Unsupported cache version {} (expected {})",
+                version, CACHE_VERSION
+            ));
+        }
+
+        let k = read_u32(&mut reader)?;
+        if k != config.k {
+            return Err(format!(
+                "Cache was generated for k={} (expected k={})",
+                k, config.k
+            ));
+        }
+
+        let mut fingerprint = [0u8; 32];
+        reader
+            .read_exact(&mut fingerprint)
+            .map_err(|e| format!("Failed to read cache header: {}", e))?;
+
+        let start = Instant::now();
+        let params = Params::<EqAffine>::read(&mut reader)
+            .map_err(|e| format!("Failed to read params: {}", e))?;
+        let params_read = start.elapsed();
+        let system = Self::from_params(config, params)?;
+        tracing::debug!(
+            params_ms = millis(params_read),
+            keygen_ms = millis(start.elapsed() - params_read),
+            "Read cached params and derived keys"
+        );
+
+        if system.vk_fingerprint() != fingerprint {
+            return Err("Verifying key does not match cached fingerprint".to_string());
+        }
+
+        Ok(system)
+    }
+
+    /// Prove knowledge of `data`, returning its SHA256 hash and the proof bytes
+    pub fn prove(&self, data: &[u8]) -> Result<([u8; 32], Vec<u8>), String> {
+        self.prove_with_metrics(data)
+            .map(|result| (result.hash, result.proof))
+    }
+
+    /// Like [`ProvingSystem::prove`], additionally reporting how long each
+    /// stage took. `setup_ms` covers fetching (or generating) the keys for the
+    /// input's circuit shape.
+    pub fn prove_with_metrics(&self, data: &[u8]) -> Result<ProofWithMetrics, String> {
+        let start = Instant::now();
+        let max_input_len = self.config.max_input_len();
+        if data.len() > max_input_len {
+            return Err(format!(
+                "Input of {} bytes exceeds the {} byte capacity of a k={} circuit",
+                data.len(),
+                max_input_len,
+                self.config.k
+            ));
+        }
+
+        let layout = CircuitLayout::Sha256(CircuitShape::for_input(data.len()));
+        let keys = self.keys_for(layout)?;
+        let setup = start.elapsed();
+
+        let witness_start = Instant::now();
+        let circuit = Sha256Circuit::new(data.to_vec());
+        let hash = circuit.expected_hash();
+        let witness = witness_start.elapsed();
+
+        let proving_start = Instant::now();
+        let proof = self.create_with_keys(&keys, layout, circuit, &hash, data.len(), OsRng)?;
+        let proving = proving_start.elapsed();
+
+        Ok(ProofWithMetrics {
+            hash,
+            proof,
+            metrics: ProofMetrics {
+                setup_ms: millis(setup),
+                witness_ms: millis(witness),
+                proving_ms: millis(proving),
+                total_ms: millis(start.elapsed()),
+                input_len: data.len(),
+            },
+        })
+    }
+
+    /// Like [`ProvingSystem::prove`], but the proof's blinding randomness comes
+    /// from an RNG seeded with `seed`, so the same data and seed always yield
+    /// the same proof bytes.
+    ///
+    /// For tests and proof deduplication only. The blinding factors are what
+    /// keep the witness hidden, and anyone who knows the seed can recompute
+    /// them, so never use this where the input must stay secret.
+    #[cfg(any(test, feature = "seeded-proofs"))]
+    pub fn prove_seeded(&self, data: &[u8], seed: [u8; 32]) -> Result<([u8; 32], Vec<u8>), String> {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let max_input_len = self.config.max_input_len();
+        if data.len() > max_input_len {
+            return Err(format!(
+                "Input of {} bytes exceeds the {} byte capacity of a k={} circuit",
+                data.len(),
+                max_input_len,
+                self.config.k
+            ));
+        }
+
+        let layout = CircuitLayout::Sha256(CircuitShape::for_input(data.len()));
+        let keys = self.keys_for(layout)?;
+        let circuit = Sha256Circuit::new(data.to_vec());
+        let hash = circuit.expected_hash();
+        let rng = StdRng::from_seed(seed);
+        let proof = self.create_with_keys(&keys, layout, circuit, &hash, data.len(), rng)?;
+
+        Ok((hash, proof))
+    }
+
+    /// Prove knowledge of `data` behind the commitment SHA256(blinding || data),
+    /// returning the hash, the commitment and the proof bytes.
+    ///
+    /// The proof binds the commitment to the hash, so it can be stored and
+    /// verified later without the raw data.
+    pub fn prove_committed(
+        &self,
+        data: &[u8],
+        blinding: &[u8; BLINDING_BYTES],
+    ) -> Result<([u8; 32], [u8; 32], Vec<u8>), String> {
+        let shape = CircuitShape::for_committed_input(data.len());
+        if shape.total_blocks() > self.config.max_blocks() {
+            return Err(format!(
+                "Input of {} bytes with a commitment exceeds the capacity of a k={} circuit",
+                data.len(),
+                self.config.k
+            ));
+        }
+
+        let circuit = Sha256Circuit::with_commitment(data.to_vec(), *blinding);
+        let hash = circuit.expected_hash();
+        let commitment = circuit
+            .expected_commitment()
+            .ok_or_else(|| "Committed circuit is missing its witness".to_string())?;
+        let layout = CircuitLayout::Sha256(circuit.shape());
+        let proof = self.create(layout, circuit, &[hash, commitment].concat(), data.len())?;
+
+        Ok((hash, commitment, proof))
+    }
+
+    /// Prove knowledge of `data` with the Keccak256 circuit, returning the
+    /// Keccak256 hash and the proof bytes
+    pub fn prove_keccak(&self, data: &[u8]) -> Result<([u8; 32], Vec<u8>), String> {
+        let max_input_len = self.config.max_input_len_for(CircuitType::Keccak256);
+        // The smallest circuits hold no Keccak256 block at all
+        if !CircuitLayout::Keccak256(keccak_block_count(data.len())).fits(&self.config) {
+            return Err(format!(
+                "Input of {} bytes exceeds the {} byte Keccak256 capacity of a k={} circuit",
+                data.len(),
+                max_input_len,
+                self.config.k
+            ));
+        }
+
+        let circuit = KeccakCircuit::new(data.to_vec());
+        let hash = circuit.expected_hash();
+        let layout = CircuitLayout::Keccak256(circuit.blocks());
+        let proof = self.create(layout, circuit, &hash, data.len())?;
+
+        Ok((hash, proof))
+    }
+
+    /// Prove knowledge of `data` with the circuit for `circuit`
+    pub fn prove_for(
+        &self,
+        circuit: CircuitType,
+        data: &[u8],
+    ) -> Result<([u8; 32], Vec<u8>), String> {
+        match circuit {
+            CircuitType::Sha256 => self.prove(data),
+            CircuitType::Keccak256 => self.prove_keccak(data),
+        }
+    }
+
+    fn create<C: Circuit<Fp>>(
+        &self,
+        layout: CircuitLayout,
+        circuit: C,
+        public_bytes: &[u8],
+        input_len: usize,
+    ) -> Result<Vec<u8>, String> {
+        let keys = self.keys_for(layout)?;
+        self.create_with_keys(&keys, layout, circuit, public_bytes, input_len, OsRng)
+    }
+
+    fn create_with_keys<C: Circuit<Fp>, R: RngCore>(
+        &self,
+        keys: &CircuitKeys,
+        layout: CircuitLayout,
+        circuit: C,
+        public_bytes: &[u8],
+        input_len: usize,
+        rng: R,
+    ) -> Result<Vec<u8>, String> {
+        // Convert public bytes to public inputs
+        let public_inputs = to_public_inputs(layout, public_bytes, input_len);
+        let instances = &[public_inputs.as_slice()];
+
+        // Create proof
+        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(layout.header(input_len));
+
+        create_proof(
+            &self.params,
+            &keys.pk,
+            &[circuit],
+            &[instances],
+            rng,
+            &mut transcript,
+        )
+        .map_err(|e| format!("Proof creation failed: {:?}", e))?;
+
+        Ok(transcript.finalize())
+    }
+
+    /// Verify `proof_bytes` against the public `hash`
+    pub fn verify(&self, hash: &[u8; 32], proof_bytes: &[u8]) -> bool {
+        self.verify_for(CircuitType::Sha256, hash, proof_bytes)
+    }
+
+    /// Verify a proof made by the `circuit` circuit against the public `hash`
+    pub fn verify_for(&self, circuit: CircuitType, hash: &[u8; 32], proof_bytes: &[u8]) -> bool {
+        self.verify_public(circuit, hash, proof_bytes, false)
+    }
+
+    /// Verify a committed proof against the public `hash` and `commitment`
+    pub fn verify_committed(
+        &self,
+        hash: &[u8; 32],
+        commitment: &[u8; 32],
+        proof_bytes: &[u8],
+    ) -> bool {
+        self.verify_public(
+            CircuitType::Sha256,
+            &[*hash, *commitment].concat(),
+            proof_bytes,
+            true,
+        )
+    }
+
+    fn verify_public(
+        &self,
+        circuit: CircuitType,
+        public_bytes: &[u8],
+        proof_bytes: &[u8],
+        committed: bool,
+    ) -> bool {
+        let (layout, input_len, transcript_bytes) = match parse_proof_header(circuit, proof_bytes) {
+            Some(parsed) => parsed,
+            None => return false,
+        };
+        if layout.is_committed() != committed {
+            return false;
+        }
+
+        let keys = match self.verifying_keys_for(layout) {
+            Ok(keys) => keys,
             Err(e) => {
-                eprintln!("Failed to initialize proving system: {}", e);
+                tracing::warn!(?layout, error = %e, "Failed to load verifying keys");
+                return false;
             }
+        };
+
+        // Convert public bytes to public inputs
+        let public_inputs = to_public_inputs(layout, public_bytes, input_len);
+        let instances = &[public_inputs.as_slice()];
+
+        // Verify proof
+        let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(transcript_bytes);
+
+        halo2_proofs::plonk::verify_proof(
+            &self.params,
+            &keys.vk,
+            halo2_proofs::plonk::SingleVerifier::new(&self.params),
+            &[instances],
+            &mut transcript,
+        )
+        .is_ok()
+    }
+}
+
+/// One public input per byte of `bytes`, then the input length and the tag
+/// of the layout's circuit type
+fn to_public_inputs(layout: CircuitLayout, bytes: &[u8], input_len: usize) -> Vec<Fp> {
+    bytes
+        .iter()
+        .map(|&byte| Fp::from(byte as u64))
+        .chain([
+            Fp::from(input_len as u64),
+            layout.circuit_type().domain_tag(),
+        ])
+        .collect()
+}
+
+/// Split a proof for `circuit` into its circuit layout, the proven input's
+/// length and the Halo2 transcript
+fn parse_proof_header(
+    circuit: CircuitType,
+    proof_bytes: &[u8],
+) -> Option<(CircuitLayout, usize, &[u8])> {
+    if proof_bytes.len() < PROOF_HEADER_LEN {
+        return None;
+    }
+    let (header, transcript_bytes) = proof_bytes.split_at(PROOF_HEADER_LEN);
+    let input_len = u32::from_le_bytes(header[0..4].try_into().ok()?) as usize;
+    let committed = match u32::from_le_bytes(header[4..8].try_into().ok()?) {
+        0 => false,
+        1 => true,
+        _ => return None,
+    };
+
+    let layout = CircuitLayout::for_input(circuit, input_len, committed)?;
+    Some((layout, input_len, transcript_bytes))
+}
+
+fn cache_file_path(cache_dir: &Path, k: u32) -> PathBuf {
+    cache_dir.join(format!("guardian_sha256_k{}.bin", k))
+}
+
+/// Create `dir` if needed and check that files can be written in it
+fn ensure_writable_dir(dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create cache directory: {}", e))?;
+
+    let probe = dir.join(".guardian_write_test");
+    File::create(&probe).map_err(|e| format!("Cache directory is not writable: {}", e))?;
+    let _ = fs::remove_file(&probe);
+
+    Ok(())
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, String> {
+    let mut buf = [0u8; 4];
+    reader
+        .read_exact(&mut buf)
+        .map_err(|e| format!("Failed to read cache header: {}", e))?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn get_proving_system() -> Result<Arc<ProvingSystem>, String> {
+    get_or_init_proving_system(None)
+}
+
+/// The shared proving system, initializing it if needed with `settings`, or
+/// else those of the last initialization, or else the defaults
+fn get_or_init_proving_system(
+    settings: Option<(ProverConfig, Option<&Path>)>,
+) -> Result<Arc<ProvingSystem>, String> {
+    if let Some(system) = &PROVING_SYSTEM
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .system
+    {
+        return system.clone();
+    }
+
+    let mut shared = PROVING_SYSTEM
+        .write()
+        .unwrap_or_else(PoisonError::into_inner);
+    // Another caller may have initialized it while this one waited
+    if let Some(system) = &shared.system {
+        return system.clone();
+    }
+
+    let (config, cache_dir) = match settings {
+        Some((config, cache_dir)) => (config, cache_dir.map(Path::to_path_buf)),
+        None => shared
+            .settings
+            .clone()
+            .unwrap_or((ProverConfig::default(), None)),
+    };
+    let system = ProvingSystem::load_or_generate(config, cache_dir.as_deref())
+        .map(Arc::new)
+        .map_err(|e| {
+            tracing::error!(error = %e, "Failed to initialize proving system");
+            format!("Proving system not initialized: {}", e)
         });
 
-        PROVING_SYSTEM
-            .as_ref()
-            .ok_or_else(|| "Proving system not initialized".to_string())
-    }
+    shared.settings = Some((config, cache_dir));
+    shared.system = Some(system.clone());
+    system
+}
+
+/// Initialize the proving system, loading it from `cache_dir` when a valid
+/// cache exists and writing one after generation otherwise.
+///
+/// Only the first call performs initialization; later calls are no-ops
+/// regardless of `config` and `cache_dir` until the system is shut down
+/// with [`shutdown_proving_system`].
+pub fn init_proving_system(config: ProverConfig, cache_dir: Option<&Path>) -> Result<(), String> {
+    get_or_init_proving_system(Some((config, cache_dir))).map(|_| ())
+}
+
+/// Drop the shared proving system, freeing its parameters and keys.
+///
+/// Proofs already running finish with the system they started on. The next
+/// call that needs the system initializes it again with the configuration
+/// and cache directory it last had, so with a cache coming back reads the
+/// params rather than generating them; keys are still derived again. A failed initialization is also
+/// cleared, so the next call retries it. Returns whether an initialized
+/// system was dropped.
+pub fn shutdown_proving_system() -> bool {
+    let system = PROVING_SYSTEM
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .system
+        .take();
+    matches!(system, Some(Ok(_)))
+}
+
+/// Configuration of the shared proving system, initializing it with the
+/// defaults if nothing has been initialized yet
+pub fn prover_config() -> Result<ProverConfig, String> {
+    Ok(*get_proving_system()?.config())
+}
+
+/// Whether the shared proving system is cached on disk, initializing it
+/// with the defaults if nothing has been initialized yet
+pub fn key_persistence() -> Result<KeyPersistence, String> {
+    Ok(get_proving_system()?.persistence().clone())
 }
 
 // Public helper functions
-pub fn generate_proof_slice(data: &[u8]) -> Output {
+
+/// Generate a proof for `data`, returning the public output together with
+/// the proof transcript bytes. On failure the output is zeroed and the
+/// proof is empty.
+pub fn generate_proof_slice(data: &[u8]) -> (Output, Vec<u8>) {
     match generate_proof_internal(data) {
-        Ok((hash, _proof)) => Output {
-            len: data.len(),
-            hash,
-        },
-        Err(e) => {
-            eprintln!("Proof generation failed: {}", e);
+        Ok((hash, proof)) => (
             Output {
-                len: 0,
-                hash: [0u8; 32],
-            }
+                len: data.len(),
+                hash,
+            },
+            proof,
+        ),
+        Err(e) => {
+            tracing::warn!(error = %e, "Proof generation failed");
+            (
+                Output {
+                    len: 0,
+                    hash: [0u8; 32],
+                },
+                Vec::new(),
+            )
+        }
+    }
+}
+
+/// Verify `proof` against the public hash in `output` with the Halo2
+/// verifier.
+pub fn verify_proof_slice(output: &Output, proof: &[u8]) -> bool {
+    match verify_proof_internal(&output.hash, proof) {
+        Ok(valid) => valid,
+        Err(e) => {
+            tracing::warn!(error = %e, "Proof verification failed");
+            false
         }
     }
 }
 
-pub fn verify_proof_slice(data: &[u8], output: &Output) -> bool {
-    // For this interface, we would need to store the proof somewhere
-    // For now, just verify the hash matches
+/// Cheap check that `output.hash` is the SHA256 of `data`.
+///
+/// This does not verify any proof; use [`verify_proof_slice`] for that.
+pub fn hash_matches(data: &[u8], output: &Output) -> bool {
     let expected_hash: [u8; 32] = {
-        use sha2::{Digest, Sha256};
         let mut hasher = Sha256::new();
         hasher.update(data);
         hasher.finalize().into()
@@ -117,122 +1121,422 @@ pub fn verify_proof_slice(data: &[u8], output: &Output) -> bool {
 }
 
 fn generate_proof_internal(data: &[u8]) -> Result<([u8; 32], Vec<u8>), String> {
-    let start = Instant::now();
+    generate_proof_with_metrics(data).map(|result| (result.hash, result.proof))
+}
+
+/// Generate a reproducible proof for `data` from `seed` with the shared
+/// proving system. See [`ProvingSystem::prove_seeded`]: for tests and
+/// deduplication only, never where the input must stay secret.
+#[cfg(any(test, feature = "seeded-proofs"))]
+pub fn generate_proof_seeded(data: &[u8], seed: [u8; 32]) -> Result<([u8; 32], Vec<u8>), String> {
+    get_proving_system()?.prove_seeded(data, seed)
+}
 
+/// Generate a proof for `data` along with a timing breakdown.
+///
+/// `setup_ms` includes initialising the shared proving system on first use,
+/// so the first call in a process reports a much larger setup time.
+pub fn generate_proof_with_metrics(data: &[u8]) -> Result<ProofWithMetrics, String> {
+    let start = Instant::now();
     let system = get_proving_system()?;
-    let circuit = Sha256Circuit::new(data.to_vec());
-    let hash = circuit.expected_hash();
-
-    // Convert hash to public inputs
-    let public_inputs: Vec<Fp> = hash.iter().map(|&byte| Fp::from(byte as u64)).collect();
-    let instances = &[public_inputs.as_slice()];
-
-    // Create proof
-    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
-
-    let proof_start = Instant::now();
-    create_proof(
-        &system.params,
-        &system.pk,
-        &[circuit],
-        &[instances],
-        OsRng,
-        &mut transcript,
-    )
-    .map_err(|e| format!("Proof creation failed: {:?}", e))?;
-
-    let proof_time = proof_start.elapsed();
-    let proof_bytes = transcript.finalize();
-
-    println!("Proof generated in {:?} (target: <500ms)", proof_time);
-
-    if proof_time.as_millis() > 500 {
-        println!(
-            "WARNING: Proof time ({:?}) exceeds 500ms target",
-            proof_time
-        );
+    let system_setup = start.elapsed();
+
+    let mut result = system.prove_with_metrics(data)?;
+    result.metrics.setup_ms += millis(system_setup);
+    result.metrics.total_ms = millis(start.elapsed());
+
+    Ok(result)
+}
+
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Generate proofs for many inputs, reusing the shared proving system.
+///
+/// Results are returned in input order. With the `parallel` feature the
+/// inputs are proven concurrently on the rayon thread pool.
+pub fn generate_proofs_batch(inputs: &[&[u8]]) -> Vec<Result<([u8; 32], Vec<u8>), String>> {
+    let system = match get_proving_system() {
+        Ok(system) => system,
+        Err(e) => return inputs.iter().map(|_| Err(e.clone())).collect(),
+    };
+
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        inputs.par_iter().map(|data| system.prove(data)).collect()
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        inputs.iter().map(|data| system.prove(data)).collect()
     }
+}
 
-    println!("Total time including setup: {:?}", start.elapsed());
+/// Generate a proof for `data` with the circuit for `circuit`, returning the
+/// digest and the proof bytes
+pub fn generate_proof_for(
+    circuit: CircuitType,
+    data: &[u8],
+) -> Result<([u8; 32], Vec<u8>), String> {
+    get_proving_system()?.prove_for(circuit, data)
+}
 
-    Ok((hash, proof_bytes))
+/// Verify a proof from [`generate_proof_for`] against the public `hash`
+pub fn verify_proof_for(
+    circuit: CircuitType,
+    hash: &[u8; 32],
+    proof_bytes: &[u8],
+) -> Result<bool, String> {
+    Ok(get_proving_system()?.verify_for(circuit, hash, proof_bytes))
 }
 
 fn verify_proof_internal(hash: &[u8; 32], proof_bytes: &[u8]) -> Result<bool, String> {
     let system = get_proving_system()?;
+    Ok(system.verify(hash, proof_bytes))
+}
 
-    // Convert hash to public inputs
-    let public_inputs: Vec<Fp> = hash.iter().map(|&byte| Fp::from(byte as u64)).collect();
-    let instances = &[public_inputs.as_slice()];
+// FFI functions
+
+/// Status returned by the FFI functions. It is `repr(i32)`, so C callers
+/// receive it as a plain `int`; [`guardian_zkml_strerror`] describes each
+/// value.
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// The call succeeded, and a verified proof is valid
+    Ok = 0,
+    /// The proof was checked and is not valid for the given hash
+    VerifyFailed = 1,
+    /// A required pointer argument was null
+    NullPointer = -1,
+    /// An input's `data` pointer was null
+    NullData = -2,
+    /// The prover failed: a proof could not be generated, or the proving
+    /// system could not be loaded to verify one
+    ProofFailed = -3,
+}
 
-    // Verify proof
-    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(proof_bytes);
+impl ErrorCode {
+    /// Static, NUL-terminated description of the code
+    fn description(self) -> &'static [u8] {
+        match self {
+            ErrorCode::Ok => b"success\0",
+            ErrorCode::VerifyFailed => b"proof does not verify for the given hash\0",
+            ErrorCode::NullPointer => b"a required pointer argument was null\0",
+            ErrorCode::NullData => b"input data pointer was null\0",
+            ErrorCode::ProofFailed => b"the prover failed to generate or check a proof\0",
+        }
+    }
+}
 
-    let verification_result = halo2_proofs::plonk::verify_proof(
-        &system.params,
-        &system.vk,
-        halo2_proofs::plonk::SingleVerifier::new(&system.params),
-        &[instances],
-        &mut transcript,
-    );
+impl TryFrom<i32> for ErrorCode {
+    type Error = i32;
 
-    match verification_result {
-        Ok(()) => Ok(true),
-        Err(_) => Ok(false),
+    fn try_from(code: i32) -> Result<Self, i32> {
+        match code {
+            0 => Ok(ErrorCode::Ok),
+            1 => Ok(ErrorCode::VerifyFailed),
+            -1 => Ok(ErrorCode::NullPointer),
+            -2 => Ok(ErrorCode::NullData),
+            -3 => Ok(ErrorCode::ProofFailed),
+            other => Err(other),
+        }
     }
 }
 
-// FFI functions
+/// Describe a code returned by one of the FFI functions. The string is
+/// static and must not be freed; unknown codes get a generic description.
+#[no_mangle]
+pub extern "C" fn guardian_zkml_strerror(code: i32) -> *const c_char {
+    let description = match ErrorCode::try_from(code) {
+        Ok(code) => code.description(),
+        Err(_) => b"unknown error code\0",
+    };
+    description.as_ptr() as *const c_char
+}
+
+/// Release the shared proving system's memory once the caller is done
+/// proving for a while. In-flight proofs are unaffected, and the next proof
+/// or verification initializes the system again. Safe to call at any time,
+/// from any thread, and more than once.
 #[no_mangle]
-pub extern "C" fn generate_proof(input_ptr: *const Input, output_ptr: *mut Output) -> i32 {
+pub extern "C" fn guardian_zkml_shutdown() {
+    shutdown_proving_system();
+}
+
+/// Compute the hash of `input` into `output_ptr`, running the prover but
+/// discarding its proof.
+///
+/// This is the original entry point, kept with its original signature for
+/// callers built against it. Use [`generate_proof_v2`] to get the proof.
+///
+/// Returns [`ErrorCode::Ok`], [`ErrorCode::NullPointer`] if either argument
+/// is null, [`ErrorCode::NullData`] if `input_ptr.data` is, or
+/// [`ErrorCode::ProofFailed`].
+#[no_mangle]
+pub extern "C" fn generate_proof(input_ptr: *const Input, output_ptr: *mut Output) -> ErrorCode {
     if input_ptr.is_null() || output_ptr.is_null() {
-        return -1;
+        return ErrorCode::NullPointer;
     }
 
+    // SAFETY: `input_ptr` was checked for null and the caller guarantees it
+    // points to a valid `Input`.
     let input = unsafe { &*input_ptr };
     if input.data.is_null() {
-        return -2;
+        return ErrorCode::NullData;
     }
 
+    // SAFETY: the caller guarantees `input.data` points to `input.len`
+    // readable bytes that stay alive for the duration of this call.
     let data_slice = unsafe { std::slice::from_raw_parts(input.data, input.len) };
 
     match generate_proof_internal(data_slice) {
         Ok((hash, _proof_bytes)) => {
+            // SAFETY: `output_ptr` was checked for null and the caller
+            // guarantees it is valid for writes.
             unsafe {
                 (*output_ptr).len = input.len;
                 (*output_ptr).hash = hash;
             }
-            0
+            ErrorCode::Ok
         }
         Err(e) => {
-            eprintln!("Error generating proof: {}", e);
-            -3
+            tracing::warn!(error = %e, "Error generating proof");
+            ErrorCode::ProofFailed
         }
     }
 }
 
+/// Generate a proof for `input`, writing the hash to `output_ptr` and the
+/// proof transcript to a newly allocated buffer.
+///
+/// On success `*proof_out` points to `*proof_len_out` bytes owned by the
+/// caller, which must be released with [`free_proof`] exactly once. On
+/// failure the proof out-params are left untouched.
+///
+/// Returns [`ErrorCode::Ok`], [`ErrorCode::NullPointer`] if any argument is
+/// null, [`ErrorCode::NullData`] if `input_ptr.data` is, or
+/// [`ErrorCode::ProofFailed`].
 #[no_mangle]
-pub extern "C" fn verify_proof_ffi(input_ptr: *const Input, output_hash_ptr: *const Output) -> i32 {
-    if input_ptr.is_null() || output_hash_ptr.is_null() {
-        return -1;
+pub extern "C" fn generate_proof_v2(
+    input_ptr: *const Input,
+    output_ptr: *mut Output,
+    proof_out: *mut *mut u8,
+    proof_len_out: *mut usize,
+) -> ErrorCode {
+    if input_ptr.is_null() || output_ptr.is_null() || proof_out.is_null() || proof_len_out.is_null()
+    {
+        return ErrorCode::NullPointer;
     }
 
+    // SAFETY: `input_ptr` was checked for null and the caller guarantees it
+    // points to a valid `Input`.
     let input = unsafe { &*input_ptr };
     if input.data.is_null() {
-        return -2;
+        return ErrorCode::NullData;
     }
 
-    let output = unsafe { &*output_hash_ptr };
+    // SAFETY: the caller guarantees `input.data` points to `input.len`
+    // readable bytes that stay alive for the duration of this call.
+    let data_slice = unsafe { std::slice::from_raw_parts(input.data, input.len) };
+
+    match generate_proof_internal(data_slice) {
+        Ok((hash, proof_bytes)) => {
+            let proof = proof_bytes.into_boxed_slice();
+            let proof_len = proof.len();
+            let proof_ptr = Box::into_raw(proof) as *mut u8;
+
+            // SAFETY: all out-pointers were checked for null and the caller
+            // guarantees they are valid for writes. Ownership of `proof_ptr`
+            // passes to the caller until it is returned via `free_proof`.
+            unsafe {
+                (*output_ptr).len = input.len;
+                (*output_ptr).hash = hash;
+                *proof_out = proof_ptr;
+                *proof_len_out = proof_len;
+            }
+            ErrorCode::Ok
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "Error generating proof");
+            ErrorCode::ProofFailed
+        }
+    }
+}
+
+/// Generate proofs for `count` inputs in one call.
+///
+/// `inputs`, `outputs`, `proofs_out` and `proof_lens_out` must each point to
+/// `count` elements. Every non-null entry written to `proofs_out` must be
+/// released with [`free_proof`]. Entries that fail get a zeroed output and
+/// a null proof. Returns [`ErrorCode::Ok`] if every proof succeeded and
+/// [`ErrorCode::ProofFailed`] if any failed; null arguments are reported as
+/// for [`generate_proof_v2`], before anything is written.
+#[no_mangle]
+pub extern "C" fn generate_proof_batch(
+    inputs: *const Input,
+    outputs: *mut Output,
+    proofs_out: *mut *mut u8,
+    proof_lens_out: *mut usize,
+    count: usize,
+) -> ErrorCode {
+    if count == 0 {
+        return ErrorCode::Ok;
+    }
+    if inputs.is_null() || outputs.is_null() || proofs_out.is_null() || proof_lens_out.is_null() {
+        return ErrorCode::NullPointer;
+    }
+
+    // SAFETY: `inputs` was checked for null and the caller guarantees it
+    // points to `count` valid `Input`s.
+    let inputs = unsafe { std::slice::from_raw_parts(inputs, count) };
+    if inputs.iter().any(|input| input.data.is_null()) {
+        return ErrorCode::NullData;
+    }
+
+    let data: Vec<&[u8]> = inputs
+        .iter()
+        .map(|input| {
+            // SAFETY: the caller guarantees each `input.data` points to
+            // `input.len` readable bytes that outlive this call.
+            unsafe { std::slice::from_raw_parts(input.data, input.len) }
+        })
+        .collect();
+
+    let results = generate_proofs_batch(&data);
+
+    // SAFETY: the out-pointers were checked for null and the caller
+    // guarantees each points to `count` writable elements that do not
+    // overlap each other or `inputs`.
+    let (outputs, proofs_out, proof_lens_out) = unsafe {
+        (
+            std::slice::from_raw_parts_mut(outputs, count),
+            std::slice::from_raw_parts_mut(proofs_out, count),
+            std::slice::from_raw_parts_mut(proof_lens_out, count),
+        )
+    };
+
+    let mut status = ErrorCode::Ok;
+    for (i, result) in results.into_iter().enumerate() {
+        match result {
+            Ok((hash, proof_bytes)) => {
+                let proof = proof_bytes.into_boxed_slice();
+                proof_lens_out[i] = proof.len();
+                proofs_out[i] = Box::into_raw(proof) as *mut u8;
+                outputs[i] = Output {
+                    len: inputs[i].len,
+                    hash,
+                };
+            }
+            Err(e) => {
+                tracing::warn!(index = i, error = %e, "Error generating proof in batch");
+                outputs[i] = Output {
+                    len: 0,
+                    hash: [0u8; 32],
+                };
+                proofs_out[i] = std::ptr::null_mut();
+                proof_lens_out[i] = 0;
+                status = ErrorCode::ProofFailed;
+            }
+        }
+    }
+
+    status
+}
+
+/// Release a proof buffer returned by [`generate_proof_v2`] or
+/// [`generate_proof_batch`].
+///
+/// `ptr` and `len` must be exactly the values produced by the generator.
+/// Passing a null pointer is a no-op.
+#[no_mangle]
+pub extern "C" fn free_proof(ptr: *mut u8, len: usize) {
+    if ptr.is_null() {
+        return;
+    }
+
+    // SAFETY: `ptr`/`len` came from `Box::into_raw` on a boxed slice of
+    // exactly `len` bytes in a proof generator, and the caller guarantees
+    // this buffer has not been freed already.
+    unsafe {
+        drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len)));
+    }
+}
+
+/// Check that `output_hash_ptr` holds the hash of `input`. No proof is
+/// involved.
+///
+/// This is the original entry point, kept with its original signature for
+/// callers built against it. Use [`verify_proof_v2`] to verify a proof.
+///
+/// Returns [`ErrorCode::Ok`] if the hash matches, [`ErrorCode::VerifyFailed`]
+/// if it does not, [`ErrorCode::NullPointer`] if either argument is null and
+/// [`ErrorCode::NullData`] if `input_ptr.data` is.
+#[no_mangle]
+pub extern "C" fn verify_proof_ffi(
+    input_ptr: *const Input,
+    output_hash_ptr: *const Output,
+) -> ErrorCode {
+    if input_ptr.is_null() || output_hash_ptr.is_null() {
+        return ErrorCode::NullPointer;
+    }
+
+    // SAFETY: both pointers were checked for null and the caller guarantees
+    // they point to a valid `Input` and `Output`.
+    let (input, output) = unsafe { (&*input_ptr, &*output_hash_ptr) };
+    if input.data.is_null() {
+        return ErrorCode::NullData;
+    }
+
+    // SAFETY: the caller guarantees `input.data` points to `input.len`
+    // readable bytes that stay alive for the duration of this call.
     let data_slice = unsafe { std::slice::from_raw_parts(input.data, input.len) };
 
-    // For this simplified version, verify the hash computation
-    if verify_proof_slice(data_slice, output) {
-        0 // verified
+    if hash_matches(data_slice, output) {
+        ErrorCode::Ok
     } else {
-        1 // verification failed
+        ErrorCode::VerifyFailed
+    }
+}
+
+/// Verify a proof transcript against the hash in `output_hash_ptr`.
+///
+/// Returns [`ErrorCode::Ok`] if the proof verifies and
+/// [`ErrorCode::VerifyFailed`] if it does not. Null arguments give
+/// [`ErrorCode::NullPointer`], and [`ErrorCode::ProofFailed`] means the
+/// proof could not be checked at all.
+#[no_mangle]
+pub extern "C" fn verify_proof_v2(
+    output_hash_ptr: *const Output,
+    proof_ptr: *const u8,
+    proof_len: usize,
+) -> ErrorCode {
+    if output_hash_ptr.is_null() || proof_ptr.is_null() {
+        return ErrorCode::NullPointer;
+    }
+
+    // SAFETY: `output_hash_ptr` was checked for null and the caller
+    // guarantees it points to a valid `Output`.
+    let output = unsafe { &*output_hash_ptr };
+    // SAFETY: the caller guarantees `proof_ptr` points to `proof_len`
+    // readable bytes that stay alive for the duration of this call.
+    let proof_slice = unsafe { std::slice::from_raw_parts(proof_ptr, proof_len) };
+
+    match verify_proof_internal(&output.hash, proof_slice) {
+        Ok(true) => ErrorCode::Ok,
+        Ok(false) => ErrorCode::VerifyFailed,
+        Err(e) => {
+            tracing::warn!(error = %e, "Error verifying proof");
+            ErrorCode::ProofFailed
+        }
     }
 }
 
+/// Size in bytes of the [`Output`] that [`generate_proof_v2`] fills in, so
+/// callers can allocate it without mirroring the struct. This is not the
+/// size of the proof, which is returned separately in `proof_len_out`.
 #[no_mangle]
 pub extern "C" fn bytes_required() -> usize {
     std::mem::size_of::<Output>()
@@ -247,6 +1551,32 @@ pub fn verify_proof_with_proof(hash: &[u8; 32], proof_bytes: &[u8]) -> Result<bo
     verify_proof_internal(hash, proof_bytes)
 }
 
+/// Prove that `commitment` = SHA256(blinding || data) commits to data whose
+/// SHA256 is `hash`, returning `(hash, commitment, proof)`.
+///
+/// The data and blinding are only used while proving; neither is part of
+/// the returned values.
+pub fn generate_committed_proof(
+    data: &[u8],
+    blinding: &[u8; BLINDING_BYTES],
+) -> Result<([u8; 32], [u8; 32], Vec<u8>), String> {
+    get_proving_system()?.prove_committed(data, blinding)
+}
+
+/// Verify a proof from [`generate_committed_proof`]
+pub fn verify_committed_proof(
+    hash: &[u8; 32],
+    commitment: &[u8; 32],
+    proof_bytes: &[u8],
+) -> Result<bool, String> {
+    Ok(get_proving_system()?.verify_committed(hash, commitment, proof_bytes))
+}
+
+/// SHA256 fingerprint identifying the active circuit and parameters
+pub fn verifying_key_fingerprint() -> Result<[u8; 32], String> {
+    Ok(get_proving_system()?.vk_fingerprint())
+}
+
 // Benchmark helpers
 pub fn benchmark_proof_generation(data: &[u8]) -> Result<std::time::Duration, String> {
     let start = Instant::now();
@@ -261,10 +1591,9 @@ mod tests {
     #[test]
     fn test_sha256_hash_computation() {
         let data = b"hello world";
-        let output = generate_proof_slice(data);
+        let (output, _proof) = generate_proof_slice(data);
 
         // Verify hash computation
-        use sha2::{Digest, Sha256};
         let mut hasher = Sha256::new();
         hasher.update(data);
         let expected: [u8; 32] = hasher.finalize().into();
@@ -276,8 +1605,29 @@ mod tests {
     #[test]
     fn test_proof_round_trip() {
         let data = b"test data for proof";
-        let output = generate_proof_slice(data);
-        assert!(verify_proof_slice(data, &output));
+        let (output, proof) = generate_proof_slice(data);
+        assert!(hash_matches(data, &output));
+        assert!(verify_proof_slice(&output, &proof));
+    }
+
+    #[test]
+    fn test_tampered_proof_is_rejected() {
+        let data = b"tamper test";
+        let (output, mut proof) = generate_proof_slice(data);
+        assert!(verify_proof_slice(&output, &proof));
+
+        let mid = proof.len() / 2;
+        proof[mid] ^= 0x01;
+        assert!(!verify_proof_slice(&output, &proof));
+    }
+
+    #[test]
+    fn test_proof_for_wrong_hash_is_rejected() {
+        let (_output, proof) = generate_proof_slice(b"original data");
+        let (other_output, _) = generate_proof_slice(b"other data");
+
+        // A valid proof must not verify against a different public hash
+        assert!(!verify_proof_slice(&other_output, &proof));
     }
 
     #[test]
@@ -291,13 +1641,420 @@ mod tests {
             len: 0,
             hash: [0u8; 32],
         };
+        let mut proof_ptr: *mut u8 = std::ptr::null_mut();
+        let mut proof_len: usize = 0;
 
-        let result = unsafe { generate_proof(&input as *const Input, &mut output as *mut Output) };
-        assert_eq!(result, 0);
+        let result = unsafe {
+            generate_proof_v2(
+                &input as *const Input,
+                &mut output as *mut Output,
+                &mut proof_ptr,
+                &mut proof_len,
+            )
+        };
+        assert_eq!(result, ErrorCode::Ok);
         assert_eq!(output.len, data.len());
+        assert!(!proof_ptr.is_null());
+        assert!(proof_len > 0);
+
+        let verify_result =
+            unsafe { verify_proof_v2(&output as *const Output, proof_ptr, proof_len) };
+        assert_eq!(verify_result, ErrorCode::Ok);
+
+        free_proof(proof_ptr, proof_len);
+    }
+
+    #[test]
+    fn test_original_ffi_signatures_still_work() {
+        let data = b"ffi legacy data";
+        let input = Input {
+            data: data.as_ptr(),
+            len: data.len(),
+        };
+        let mut output = Output {
+            len: 0,
+            hash: [0u8; 32],
+        };
+
+        let result = generate_proof(&input as *const Input, &mut output as *mut Output);
+        assert_eq!(result, ErrorCode::Ok);
+        assert!(hash_matches(data, &output));
+        assert_eq!(
+            verify_proof_ffi(&input as *const Input, &output as *const Output),
+            ErrorCode::Ok
+        );
+
+        output.hash[0] ^= 1;
+        assert_eq!(
+            verify_proof_ffi(&input as *const Input, &output as *const Output),
+            ErrorCode::VerifyFailed
+        );
+    }
+
+    #[test]
+    fn test_ffi_rejects_proof_for_other_hash() {
+        let data = b"ffi mismatch data";
+        let (_hash, proof) = generate_proof_with_proof(data).unwrap();
+        let output = Output {
+            len: data.len(),
+            hash: [7u8; 32],
+        };
 
         let verify_result =
-            unsafe { verify_proof_ffi(&input as *const Input, &output as *const Output) };
-        assert_eq!(verify_result, 0);
+            unsafe { verify_proof_v2(&output as *const Output, proof.as_ptr(), proof.len()) };
+        assert_eq!(verify_result, ErrorCode::VerifyFailed);
+    }
+
+    #[test]
+    fn test_proving_system_cache_round_trip() {
+        let cache_dir =
+            std::env::temp_dir().join(format!("guardian_zkml_cache_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&cache_dir);
+
+        let data = b"cache round trip";
+        let (hash, proof) = {
+            let system =
+                ProvingSystem::load_or_generate(ProverConfig::default(), Some(&cache_dir)).unwrap();
+            assert!(cache_file_path(&cache_dir, CIRCUIT_K).exists());
+            // The missing directory was created
+            assert_eq!(
+                system.persistence(),
+                &KeyPersistence::Persisted {
+                    path: cache_file_path(&cache_dir, CIRCUIT_K)
+                }
+            );
+            system.prove(data).unwrap()
+        };
+
+        let reloaded = ProvingSystem::read_from(
+            &cache_file_path(&cache_dir, CIRCUIT_K),
+            ProverConfig::default(),
+        )
+        .unwrap();
+        assert!(reloaded.verify(&hash, &proof));
+
+        fs::remove_dir_all(&cache_dir).unwrap();
+    }
+
+    #[test]
+    fn test_unwritable_cache_dir_falls_back_to_memory() {
+        // A directory can't be created beneath a regular file, even as root
+        let blocker =
+            std::env::temp_dir().join(format!("guardian_zkml_blocker_{}", std::process::id()));
+        fs::write(&blocker, b"not a directory").unwrap();
+        let cache_dir = blocker.join("srs");
+
+        let system =
+            ProvingSystem::load_or_generate(ProverConfig::default(), Some(&cache_dir)).unwrap();
+
+        assert!(matches!(
+            system.persistence(),
+            KeyPersistence::InMemory { .. }
+        ));
+        let (hash, proof) = system.prove(b"in memory").unwrap();
+        assert!(system.verify(&hash, &proof));
+
+        fs::remove_file(&blocker).unwrap();
+    }
+
+    #[test]
+    fn test_stale_cache_is_rejected() {
+        let cache_dir =
+            std::env::temp_dir().join(format!("guardian_zkml_stale_test_{}", std::process::id()));
+        fs::create_dir_all(&cache_dir).unwrap();
+        let path = cache_file_path(&cache_dir, CIRCUIT_K);
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(CACHE_MAGIC);
+        bytes.extend_from_slice(&CACHE_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(CIRCUIT_K + 1).to_le_bytes());
+        fs::write(&path, bytes).unwrap();
+
+        let err = ProvingSystem::read_from(&path, ProverConfig::default())
+            .err()
+            .unwrap();
+        assert!(err.contains("k="));
+
+        fs::remove_dir_all(&cache_dir).unwrap();
+    }
+
+    #[test]
+    fn test_configurable_circuit_sizes() {
+        let data = b"small input";
+        for k in [12, 14] {
+            let system = ProvingSystem::with_config(ProverConfig::new(k)).unwrap();
+            let (hash, proof) = system.prove(data).unwrap();
+            assert!(system.verify(&hash, &proof), "k={} failed to verify", k);
+        }
+    }
+
+    #[test]
+    fn test_smallest_circuit_refuses_what_it_cannot_hold() {
+        let config = ProverConfig::new(MIN_CIRCUIT_K);
+        assert_eq!(config.max_input_len(), 55);
+        assert_eq!(config.max_input_len_for(CircuitType::Keccak256), 0);
+        assert!(ProverConfig::new(MIN_CIRCUIT_K - 1).validate().is_err());
+
+        let system = ProvingSystem::with_config(config).unwrap();
+        assert!(system.prove_keccak(b"").unwrap_err().contains("exceeds"));
+        assert!(system
+            .prove_committed(b"", &[0u8; BLINDING_BYTES])
+            .unwrap_err()
+            .contains("exceeds"));
+    }
+
+    #[test]
+    fn test_input_exceeding_capacity_is_rejected() {
+        let config = ProverConfig::new(17);
+        let system = ProvingSystem::with_config(config).unwrap();
+        let data = vec![0u8; config.max_input_len() + 1];
+
+        let err = system.prove(&data).unwrap_err();
+        assert!(err.contains("exceeds"));
+    }
+
+    #[test]
+    fn test_circuit_larger_than_maximum_is_rejected() {
+        let config = ProverConfig {
+            k: 18,
+            max_circuit_size: 1 << 17,
+            max_verify_input_len: None,
+        };
+        assert!(ProvingSystem::with_config(config).is_err());
+    }
+
+    #[test]
+    fn test_multi_block_proof_round_trip() {
+        let system = ProvingSystem::with_config(ProverConfig::default()).unwrap();
+        let data = vec![0x42u8; 200];
+
+        let (hash, proof) = system.prove(&data).unwrap();
+        assert_eq!(&proof[..4], &200u32.to_le_bytes());
+        assert_eq!(&proof[4..PROOF_HEADER_LEN], &0u32.to_le_bytes());
+        assert!(system.verify(&hash, &proof));
+    }
+
+    #[test]
+    fn test_proof_with_wrong_length_is_rejected() {
+        let system = ProvingSystem::with_config(ProverConfig::default()).unwrap();
+        let (hash, mut proof) = system.prove(b"short").unwrap();
+
+        // The same circuit shape, but another public length
+        proof[..4].copy_from_slice(&4u32.to_le_bytes());
+        assert!(!system.verify(&hash, &proof));
+
+        // Lengths needing another shape
+        proof[..4].copy_from_slice(&100u32.to_le_bytes());
+        assert!(!system.verify(&hash, &proof));
+
+        proof[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(!system.verify(&hash, &proof));
+    }
+
+    #[test]
+    fn test_unprovable_layout_is_refused_without_keygen() {
+        let system = ProvingSystem::with_config(ProverConfig::default()).unwrap();
+        let (hash, mut proof) = system.prove(b"short").unwrap();
+
+        // No circuit of this size holds an input this long
+        proof[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+        proof[4..PROOF_HEADER_LEN].copy_from_slice(&1u32.to_le_bytes());
+        assert!(!system.verify_committed(&hash, &[0u8; 32], &proof));
+        assert_eq!(system.keys.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_key_cache_is_bounded() {
+        let system = ProvingSystem::with_config(ProverConfig::default()).unwrap();
+        let recent = CircuitLayout::Keccak256(1);
+        for blocks in 1..=MAX_CACHED_LAYOUTS * 2 {
+            system.key_slot(CircuitLayout::Keccak256(blocks)).unwrap();
+            system.key_slot(recent).unwrap();
+        }
+
+        // The least recently used layouts went first
+        let keys = system.keys.lock().unwrap();
+        assert!(keys.len() <= MAX_CACHED_LAYOUTS);
+        assert!(keys.contains(&CircuitLayout::Sha256(CircuitShape::for_input(0))));
+        assert!(keys.contains(&recent));
+        assert!(!keys.contains(&CircuitLayout::Keccak256(2)));
+        assert!(keys.contains(&CircuitLayout::Keccak256(MAX_CACHED_LAYOUTS * 2)));
+    }
+
+    #[test]
+    fn test_verification_is_limited_to_configured_inputs() {
+        let config = ProverConfig {
+            max_verify_input_len: Some(55),
+            ..ProverConfig::default()
+        };
+        let system = ProvingSystem::with_config(config).unwrap();
+        let (hash, mut proof) = system.prove(b"short").unwrap();
+        assert!(system.verify(&hash, &proof));
+
+        // A two-block layout is beyond the longest input verified
+        proof[..4].copy_from_slice(&100u32.to_le_bytes());
+        assert!(!system.verify(&hash, &proof));
+        assert_eq!(system.keys.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_verifying_keygen_is_rate_limited() {
+        let system = ProvingSystem::with_config(ProverConfig::default()).unwrap();
+        for _ in 0..MAX_VERIFY_KEYGENS {
+            system.start_verify_keygen().unwrap();
+        }
+        assert!(system.start_verify_keygen().is_err());
+
+        // Keys already generated are still used
+        let (hash, proof) = system.prove(b"short").unwrap();
+        assert!(system.verify(&hash, &proof));
+
+        // A layout whose keys are gone isn't generated again
+        let data = vec![0x42u8; 100];
+        let (hash, proof) = system.prove(&data).unwrap();
+        let layout = CircuitLayout::Sha256(CircuitShape::for_input(data.len()));
+        system.keys.lock().unwrap().slots.remove(&layout);
+        assert!(!system.verify(&hash, &proof));
+    }
+
+    #[test]
+    fn test_batch_matches_individual_proofs() {
+        let inputs: Vec<&[u8]> = vec![b"first", b"second", b"third"];
+        let results = generate_proofs_batch(&inputs);
+        assert_eq!(results.len(), inputs.len());
+
+        for (data, result) in inputs.iter().zip(results) {
+            let (hash, proof) = result.unwrap();
+            let output = Output {
+                len: data.len(),
+                hash,
+            };
+            assert!(hash_matches(data, &output));
+            assert!(verify_proof_slice(&output, &proof));
+        }
+    }
+
+    #[test]
+    fn test_ffi_batch_interface() {
+        let data: [&[u8]; 2] = [b"batch one", b"batch two"];
+        let inputs: Vec<Input> = data
+            .iter()
+            .map(|d| Input {
+                data: d.as_ptr(),
+                len: d.len(),
+            })
+            .collect();
+        let mut outputs = vec![
+            Output {
+                len: 0,
+                hash: [0u8; 32],
+            };
+            2
+        ];
+        let mut proofs = vec![std::ptr::null_mut(); 2];
+        let mut proof_lens = vec![0usize; 2];
+
+        let result = generate_proof_batch(
+            inputs.as_ptr(),
+            outputs.as_mut_ptr(),
+            proofs.as_mut_ptr(),
+            proof_lens.as_mut_ptr(),
+            inputs.len(),
+        );
+        assert_eq!(result, ErrorCode::Ok);
+
+        for (i, output) in outputs.iter().enumerate() {
+            assert_eq!(output.len, data[i].len());
+            assert!(!proofs[i].is_null());
+            let verify_result = verify_proof_v2(output as *const Output, proofs[i], proof_lens[i]);
+            assert_eq!(verify_result, ErrorCode::Ok);
+            free_proof(proofs[i], proof_lens[i]);
+        }
+    }
+
+    #[test]
+    fn test_committed_proof_round_trip() {
+        let data = b"private explanation";
+        let blinding = [5u8; BLINDING_BYTES];
+
+        let (hash, commitment, proof) = generate_committed_proof(data, &blinding).unwrap();
+        assert_eq!(commitment, hash_commitment(&blinding, data));
+        assert!(verify_committed_proof(&hash, &commitment, &proof).unwrap());
+
+        // The proof is bound to the commitment and must not pass as a plain proof
+        let other_commitment = hash_commitment(&[6u8; BLINDING_BYTES], data);
+        assert!(!verify_committed_proof(&hash, &other_commitment, &proof).unwrap());
+        assert!(!verify_proof_with_proof(&hash, &proof).unwrap());
+    }
+
+    #[test]
+    fn test_proof_metrics_populated() {
+        let data = b"metrics input";
+        let result = generate_proof_with_metrics(data).unwrap();
+        let metrics = result.metrics;
+
+        assert_eq!(metrics.input_len, data.len());
+        assert!(metrics.proving_ms > 0);
+        assert!(metrics.total_ms >= metrics.proving_ms);
+        assert!(metrics.total_ms >= metrics.setup_ms + metrics.witness_ms + metrics.proving_ms);
+        assert!(verify_proof_with_proof(&result.hash, &result.proof).unwrap());
+    }
+
+    #[test]
+    fn test_seeded_proofs_are_reproducible() {
+        let data = b"dedup me";
+
+        let (hash, first) = generate_proof_seeded(data, [7u8; 32]).unwrap();
+        let (_, second) = generate_proof_seeded(data, [7u8; 32]).unwrap();
+        let (_, other_seed) = generate_proof_seeded(data, [8u8; 32]).unwrap();
+
+        assert_eq!(first, second);
+        assert_ne!(first, other_seed);
+        assert!(verify_proof_with_proof(&hash, &first).unwrap());
+        assert!(verify_proof_with_proof(&hash, &other_seed).unwrap());
+
+        // Unseeded proofs still use fresh randomness
+        let (_, unseeded) = generate_proof_internal(data).unwrap();
+        assert_ne!(first, unseeded);
+    }
+
+    #[test]
+    fn test_keccak_proof_round_trip() {
+        let data = b"ethereum transaction";
+        let (hash, proof) = generate_proof_for(CircuitType::Keccak256, data).unwrap();
+
+        // The proven digest is Keccak256, not SHA256
+        let mut expected = [0u8; 32];
+        let mut hasher = tiny_keccak::Keccak::v256();
+        tiny_keccak::Hasher::update(&mut hasher, data);
+        tiny_keccak::Hasher::finalize(hasher, &mut expected);
+        assert_eq!(hash, expected);
+
+        assert!(verify_proof_for(CircuitType::Keccak256, &hash, &proof).unwrap());
+        assert!(!verify_proof_for(CircuitType::Sha256, &hash, &proof).unwrap());
+        assert!(!verify_proof_for(CircuitType::Keccak256, &keccak256(b"other"), &proof).unwrap());
+    }
+
+    #[test]
+    fn test_proof_rejected_under_other_circuit_label() {
+        let data = b"relabeled proof";
+        let (hash, proof) = generate_proof_for(CircuitType::Sha256, data).unwrap();
+
+        // Even with the digest the proof really attests to, a Keccak256
+        // label selects a different domain tag and fails
+        assert!(verify_proof_for(CircuitType::Sha256, &hash, &proof).unwrap());
+        assert!(!verify_proof_for(CircuitType::Keccak256, &hash, &proof).unwrap());
+    }
+
+    #[test]
+    fn test_circuit_type_names() {
+        for circuit in [CircuitType::Sha256, CircuitType::Keccak256] {
+            assert_eq!(circuit.name().parse::<CircuitType>().unwrap(), circuit);
+        }
+        assert!("md5".parse::<CircuitType>().is_err());
+        assert_eq!(
+            CircuitType::Sha256.digest(b"abc"),
+            <[u8; 32]>::from(Sha256::digest(b"abc"))
+        );
     }
 }
@@ -0,0 +1,111 @@
+//! Runs the `prove` binary the way an operator or CI job would. The binary
+//! is only built with the `cli` feature.
+
+#![cfg(feature = "cli")]
+
+use base64::{engine::general_purpose, Engine as _};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+fn prove_bin() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_prove"))
+}
+
+/// A file in the temp directory holding `contents`, unique to this test
+fn temp_file(name: &str, contents: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("guardian-prove-{}-{}", std::process::id(), name));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+/// The `hash` and `proof` lines printed by `prove`
+fn parse_proof(output: &Output) -> (String, String) {
+    assert!(
+        output.status.success(),
+        "prove failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout.clone()).unwrap();
+    let field = |name: &str| {
+        stdout
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .unwrap_or_else(|| panic!("no {} line in {:?}", name, stdout))
+            .to_string()
+    };
+    (field("hash: "), field("proof: "))
+}
+
+fn verify(hash: &str, proof: &str) -> Output {
+    prove_bin().args(["verify", hash, proof]).output().unwrap()
+}
+
+#[test]
+fn test_file_proof_round_trips() {
+    let contents = b"proved from the command line";
+    let path = temp_file("round-trip", contents);
+
+    let output = prove_bin().arg("prove").arg(&path).output().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let (hash, proof) = parse_proof(&output);
+
+    assert_eq!(hash, hex::encode(Sha256::digest(contents)));
+    let verified = verify(&hash, &proof);
+    assert_eq!(verified.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&verified.stdout).trim(), "valid");
+}
+
+#[test]
+fn test_tampered_proof_fails_verification() {
+    let path = temp_file("tampered", b"a proof someone edits");
+    let output = prove_bin().arg("prove").arg(&path).output().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let (hash, proof) = parse_proof(&output);
+
+    let mut bytes = general_purpose::STANDARD.decode(&proof).unwrap();
+    let middle = bytes.len() / 2;
+    bytes[middle] ^= 0x01;
+    let tampered = general_purpose::STANDARD.encode(bytes);
+
+    let verified = verify(&hash, &tampered);
+    assert_eq!(verified.status.code(), Some(1));
+
+    // The untouched proof still holds, only for its own hash
+    let other_hash = hex::encode(Sha256::digest(b"different data"));
+    assert_eq!(verify(&other_hash, &proof).status.code(), Some(1));
+}
+
+#[test]
+fn test_stdin_is_proved() {
+    let mut child = prove_bin()
+        .args(["prove", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"piped in").unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    let (hash, _) = parse_proof(&output);
+    assert_eq!(hash, hex::encode(Sha256::digest(b"piped in")));
+}
+
+#[test]
+fn test_malformed_arguments_are_errors() {
+    // Neither a valid nor an invalid proof: the arguments can't be read
+    let hash = hex::encode([0u8; 32]);
+    assert_eq!(verify("not-hex", "AAAA").status.code(), Some(2));
+    assert_eq!(
+        verify(&hex::encode([0u8; 31]), "AAAA").status.code(),
+        Some(2)
+    );
+    assert_eq!(verify(&hash, "not base64!").status.code(), Some(2));
+
+    let missing = prove_bin()
+        .args(["prove", "/nonexistent/guardian-prove-input"])
+        .output()
+        .unwrap();
+    assert_eq!(missing.status.code(), Some(2));
+}
@@ -0,0 +1,35 @@
+//! Kept in its own test binary so the proving system starts uninitialized
+//! and every thread races on first-touch initialization.
+
+use guardian_zkml::{generate_proof_slice, verify_proof_slice};
+use std::sync::{Arc, Barrier};
+use std::thread;
+
+#[test]
+fn test_concurrent_first_touch_initialization() {
+    const THREADS: usize = 16;
+    let data = b"concurrent proof data";
+    let barrier = Arc::new(Barrier::new(THREADS));
+
+    let handles: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                barrier.wait();
+                generate_proof_slice(data)
+            })
+        })
+        .collect();
+
+    let results: Vec<_> = handles
+        .into_iter()
+        .map(|handle| handle.join().expect("prover thread panicked"))
+        .collect();
+
+    let first_hash = results[0].0.hash;
+    for (output, proof) in &results {
+        assert_eq!(output.len, data.len());
+        assert_eq!(output.hash, first_hash);
+        assert!(verify_proof_slice(output, proof));
+    }
+}
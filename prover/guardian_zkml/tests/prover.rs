@@ -1,20 +1,23 @@
 use guardian_zkml::{
-    bytes_required, generate_proof, generate_proof_slice, verify_proof_ffi, verify_proof_slice,
-    Input, Output,
+    bytes_required, free_proof, generate_proof_batch, generate_proof_slice, generate_proof_v2,
+    guardian_zkml_strerror, hash_matches, verify_proof_slice, verify_proof_v2, ErrorCode, Input,
+    Output,
 };
 use hex;
 use sha2::{Digest, Sha256};
+use std::ffi::CStr;
 
 #[test]
 fn test_proof_round_trip() {
     let data = b"hello world";
-    let out = generate_proof_slice(data);
+    let (out, proof) = generate_proof_slice(data);
     assert_eq!(out.len, data.len());
     let mut hasher = Sha256::new();
     hasher.update(data);
     let expected = hasher.finalize();
     assert_eq!(out.hash.as_slice(), expected.as_slice());
-    assert!(verify_proof_slice(data, &out));
+    assert!(hash_matches(data, &out));
+    assert!(verify_proof_slice(&out, &proof));
 }
 
 #[test]
@@ -28,18 +31,30 @@ fn test_generate_proof_ffi() {
         len: 0,
         hash: [0u8; 32],
     };
-    let ret = unsafe { generate_proof(&input as *const Input, &mut output as *mut Output) };
-    assert_eq!(ret, 0);
+    let mut proof_ptr: *mut u8 = std::ptr::null_mut();
+    let mut proof_len: usize = 0;
+    let ret = unsafe {
+        generate_proof_v2(
+            &input as *const Input,
+            &mut output as *mut Output,
+            &mut proof_ptr,
+            &mut proof_len,
+        )
+    };
+    assert_eq!(ret, ErrorCode::Ok);
+    assert!(!proof_ptr.is_null());
+    assert!(proof_len > 0);
     assert_eq!(output.len, data.len());
     let mut hasher = Sha256::new();
     hasher.update(data);
     let expected = hasher.finalize();
     assert_eq!(output.hash.as_slice(), expected.as_slice());
-    assert!(verify_proof_slice(data, &output));
+    assert!(hash_matches(data, &output));
 
-    // test FFI verification
-    let verify_ret = unsafe { verify_proof_ffi(&input as *const Input, &output as *const Output) };
-    assert_eq!(verify_ret, 0);
+    // test FFI verification against the returned proof bytes
+    let verify_ret = unsafe { verify_proof_v2(&output as *const Output, proof_ptr, proof_len) };
+    assert_eq!(verify_ret, ErrorCode::Ok);
+    free_proof(proof_ptr, proof_len);
 
     // ensure bytes_required matches Output size
     assert_eq!(bytes_required(), std::mem::size_of::<Output>());
@@ -48,14 +63,14 @@ fn test_generate_proof_ffi() {
 #[test]
 fn test_empty_input() {
     let data = b"";
-    let out = generate_proof_slice(data);
+    let (out, proof) = generate_proof_slice(data);
     assert_eq!(out.len, 0);
 
     // Verify against known SHA256 of empty string
     let expected_hash =
         hex::decode("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855").unwrap();
     assert_eq!(out.hash.as_slice(), expected_hash.as_slice());
-    assert!(verify_proof_slice(data, &out));
+    assert!(verify_proof_slice(&out, &proof));
 }
 
 #[test]
@@ -69,7 +84,7 @@ fn test_various_input_sizes() {
     ];
 
     for data in test_cases {
-        let out = generate_proof_slice(&data);
+        let (out, proof) = generate_proof_slice(&data);
         assert_eq!(out.len, data.len());
 
         // Verify hash computation
@@ -78,6 +93,135 @@ fn test_various_input_sizes() {
         let expected = hasher.finalize();
         assert_eq!(out.hash.as_slice(), expected.as_slice());
 
-        assert!(verify_proof_slice(&data, &out));
+        assert!(verify_proof_slice(&out, &proof));
+    }
+}
+
+#[test]
+fn test_tampered_proof_fails_verification() {
+    let data = b"integration tamper test";
+    let (out, mut proof) = generate_proof_slice(data);
+    assert!(verify_proof_slice(&out, &proof));
+
+    proof[0] ^= 0xff;
+    assert!(!verify_proof_slice(&out, &proof));
+}
+
+#[test]
+fn test_hash_match_is_not_proof_verification() {
+    let data = b"hash only";
+    let (out, _proof) = generate_proof_slice(data);
+
+    // The hash still matches, but an empty transcript is not a proof
+    assert!(hash_matches(data, &out));
+    assert!(!verify_proof_slice(&out, &[]));
+}
+
+#[test]
+fn test_ffi_error_paths_return_documented_codes() {
+    let data = b"error codes";
+    let input = Input {
+        data: data.as_ptr(),
+        len: data.len(),
+    };
+    let mut output = Output {
+        len: 0,
+        hash: [0u8; 32],
+    };
+    let mut proof_ptr: *mut u8 = std::ptr::null_mut();
+    let mut proof_len: usize = 0;
+
+    let ret = generate_proof_v2(
+        std::ptr::null(),
+        &mut output,
+        &mut proof_ptr,
+        &mut proof_len,
+    );
+    assert_eq!(ret, ErrorCode::NullPointer);
+
+    let null_data = Input {
+        data: std::ptr::null(),
+        len: 0,
+    };
+    let ret = generate_proof_v2(&null_data, &mut output, &mut proof_ptr, &mut proof_len);
+    assert_eq!(ret, ErrorCode::NullData);
+    let ret = generate_proof_batch(&null_data, &mut output, &mut proof_ptr, &mut proof_len, 1);
+    assert_eq!(ret, ErrorCode::NullData);
+
+    // Far more than any circuit holds
+    let oversized = vec![0u8; 1 << 20];
+    let too_large = Input {
+        data: oversized.as_ptr(),
+        len: oversized.len(),
+    };
+    let ret = generate_proof_v2(&too_large, &mut output, &mut proof_ptr, &mut proof_len);
+    assert_eq!(ret, ErrorCode::ProofFailed);
+    assert!(proof_ptr.is_null());
+
+    let ret = generate_proof_v2(&input, &mut output, &mut proof_ptr, &mut proof_len);
+    assert_eq!(ret, ErrorCode::Ok);
+
+    assert_eq!(
+        verify_proof_v2(&output, std::ptr::null(), 0),
+        ErrorCode::NullPointer
+    );
+    let other = Output {
+        len: output.len,
+        hash: [7u8; 32],
+    };
+    assert_eq!(
+        verify_proof_v2(&other, proof_ptr, proof_len),
+        ErrorCode::VerifyFailed
+    );
+    assert_eq!(
+        verify_proof_v2(&output, proof_ptr, proof_len),
+        ErrorCode::Ok
+    );
+    free_proof(proof_ptr, proof_len);
+
+    // The codes are plain ints across the FFI boundary
+    assert_eq!(ErrorCode::Ok as i32, 0);
+    assert_eq!(ErrorCode::VerifyFailed as i32, 1);
+    assert_eq!(ErrorCode::NullPointer as i32, -1);
+    assert_eq!(ErrorCode::NullData as i32, -2);
+    assert_eq!(ErrorCode::ProofFailed as i32, -3);
+}
+
+#[test]
+fn test_strerror_describes_every_code() {
+    let describe = |code: i32| {
+        // SAFETY: guardian_zkml_strerror returns a static NUL-terminated string
+        unsafe { CStr::from_ptr(guardian_zkml_strerror(code)) }
+            .to_str()
+            .unwrap()
+            .to_string()
+    };
+
+    let descriptions: Vec<String> = [0, 1, -1, -2, -3].into_iter().map(describe).collect();
+    assert_eq!(descriptions[0], "success");
+    for (i, description) in descriptions.iter().enumerate() {
+        assert!(!description.is_empty());
+        assert!(!descriptions[i + 1..].contains(description));
+    }
+    assert_eq!(describe(42), "unknown error code");
+}
+
+#[test]
+fn test_header_declares_every_ffi_function() {
+    let header = include_str!("../include/guardian_zkml.h");
+    let source = include_str!("../src/lib.rs");
+
+    let exported: Vec<&str> = source
+        .split("pub extern \"C\" fn ")
+        .skip(1)
+        .map(|rest| rest.split('(').next().unwrap())
+        .collect();
+    assert!(exported.contains(&"generate_proof_v2"));
+    for name in exported {
+        assert!(
+            header.contains(&format!(" {}(", name)) || header.contains(&format!("*{}(", name)),
+            "{} is missing from the C header",
+            name
+        );
     }
 }
@@ -0,0 +1,69 @@
+//! Kept in its own test binary because shutting the proving system down
+//! affects every test sharing the process.
+
+use guardian_zkml::{
+    generate_proof_slice, guardian_zkml_shutdown, shutdown_proving_system, verify_proof_slice,
+    verifying_key_fingerprint,
+};
+use std::sync::{Arc, Barrier, Mutex, MutexGuard, PoisonError};
+use std::thread;
+
+/// Keeps the tests here from shutting the system down under each other's
+/// assertions
+static SERIAL: Mutex<()> = Mutex::new(());
+
+fn serial() -> MutexGuard<'static, ()> {
+    SERIAL.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[test]
+fn test_shutdown_then_prove_reinitializes() {
+    let _serial = serial();
+    let data = b"proof across a shutdown";
+    let (output, proof) = generate_proof_slice(data);
+    let fingerprint = verifying_key_fingerprint().unwrap();
+
+    assert!(shutdown_proving_system());
+    // Nothing left to drop
+    assert!(!shutdown_proving_system());
+
+    // The next proof brings the system back with the same configuration, so
+    // the keys, and proofs made before the shutdown, still check out
+    let (again, again_proof) = generate_proof_slice(data);
+    assert_eq!(again.hash, output.hash);
+    assert!(verify_proof_slice(&again, &again_proof));
+    assert!(verify_proof_slice(&output, &proof));
+    assert_eq!(verifying_key_fingerprint().unwrap(), fingerprint);
+
+    guardian_zkml_shutdown();
+    guardian_zkml_shutdown();
+    assert!(verify_proof_slice(&output, &proof));
+}
+
+#[test]
+fn test_shutdown_during_proving_is_safe() {
+    let _serial = serial();
+    const PROVERS: usize = 4;
+    let barrier = Arc::new(Barrier::new(PROVERS + 1));
+
+    let provers: Vec<_> = (0..PROVERS)
+        .map(|i| {
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                barrier.wait();
+                let data = format!("prover {}", i).into_bytes();
+                let (output, proof) = generate_proof_slice(&data);
+                (data, output, proof)
+            })
+        })
+        .collect();
+
+    barrier.wait();
+    shutdown_proving_system();
+
+    for prover in provers {
+        let (data, output, proof) = prover.join().expect("prover thread panicked");
+        assert_eq!(output.len, data.len());
+        assert!(verify_proof_slice(&output, &proof));
+    }
+}